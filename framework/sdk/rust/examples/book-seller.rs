@@ -8,10 +8,9 @@
 //! - Automatic fulfillment after payment
 
 use smart402::{
-    Smart402, ContractConfig, PaymentConfig, AEOEngine, LLMOEngine, X402Client,
+    Amount, ConditionConfig, Network, Token, Smart402, ContractConfig, PaymentConfig, PaymentFrequency, AEOEngine, LLMOEngine, X402Client,
 };
 use colored::Colorize;
-use std::collections::HashMap;
 use std::error::Error;
 
 /// Book types available
@@ -36,10 +35,10 @@ impl BookType {
         }
     }
 
-    fn price(&self) -> f64 {
+    fn price(&self) -> Amount {
         match self {
-            BookType::FreeEbook => 0.0,
-            BookType::WorkshopBook => 10.0,
+            BookType::FreeEbook => Amount::ZERO,
+            BookType::WorkshopBook => Amount::from(10),
         }
     }
 
@@ -136,43 +135,31 @@ async fn create_book_contract(book: &BookType) -> Result<smart402::Contract, Box
 
     let is_free = matches!(book, BookType::FreeEbook);
 
-    let config = ContractConfig {
-        contract_type: "digital-product-purchase".to_string(),
-        parties: vec![
-            "bookstore@smart402.io".to_string(),
-            "customer@example.com".to_string(),
-        ],
-        payment: PaymentConfig {
-            amount: book.price(),
-            token: if is_free { "NONE".to_string() } else { "USDC".to_string() },
-            blockchain: "polygon-mumbai".to_string(), // Testnet
-            frequency: "one-time".to_string(),
+    let payment = PaymentConfig::new(
+        book.price(),
+        if is_free { Token::custom("NONE", 0) } else { Token::usdc() },
+        PaymentFrequency::OneTime,
+    )
+    .with_blockchain(Network::PolygonMumbai); // Testnet
+    let payment_received = ConditionConfig::new(
+        "payment_received",
+        if is_free {
+            "No payment required for free book"
+        } else {
+            "Payment of 10 USDC required"
         },
-        conditions: Some(vec![
-            serde_json::json!({
-                "id": "payment_received",
-                "type": "payment",
-                "description": if is_free {
-                    "No payment required for free book"
-                } else {
-                    "Payment of 10 USDC required"
-                },
-                "required": !is_free,
-            }),
-            serde_json::json!({
-                "id": "email_verified",
-                "type": "verification",
-                "description": "Customer email verified",
-                "required": true,
-            }),
-            serde_json::json!({
-                "id": "terms_accepted",
-                "type": "verification",
-                "description": "Terms of service accepted",
-                "required": true,
-            }),
-        ]),
-        metadata: Some(serde_json::json!({
+    )
+    .with_source("payment");
+    let config = ContractConfig::new("digital-product-purchase", payment)
+        .with_parties(["bookstore@smart402.io", "customer@example.com"])
+        .with_condition(if is_free {
+            payment_received.optional()
+        } else {
+            payment_received
+        })
+        .with_condition(verification("email_verified", "Customer email verified"))
+        .with_condition(verification("terms_accepted", "Terms of service accepted"))
+        .with_metadata(serde_json::from_value(serde_json::json!({
             "title": book.name(),
             "description": book.description(),
             "category": "digital-product",
@@ -181,8 +168,7 @@ async fn create_book_contract(book: &BookType) -> Result<smart402::Contract, Box
             "instant_delivery": true,
             "deliverables": book.deliverables(),
             "license": "single-user",
-        })),
-    };
+        }))?);
 
     let contract = Smart402::create(config).await?;
 
@@ -192,6 +178,11 @@ async fn create_book_contract(book: &BookType) -> Result<smart402::Contract, Box
     Ok(contract)
 }
 
+/// Required verification step of a purchase
+fn verification(id: &str, description: &str) -> ConditionConfig {
+    ConditionConfig::new(id, description).with_source("verification")
+}
+
 /// Display contract information
 fn display_contract_info(contract: &smart402::Contract, book: &BookType) -> Result<(), Box<dyn Error>> {
     println!("{}", "📋 Contract Details:".yellow().bold());
@@ -204,7 +195,7 @@ fn display_contract_info(contract: &smart402::Contract, book: &BookType) -> Resu
         contract.ucl.payment.amount,
         contract.ucl.payment.token
     ).yellow());
-    println!("    Network: {}", contract.ucl.payment.blockchain.white());
+    println!("    Network: {}", contract.ucl.payment.blockchain.to_string().white());
     println!();
 
     println!("{}", "  What You'll Receive:".cyan());
@@ -214,8 +205,11 @@ fn display_contract_info(contract: &smart402::Contract, book: &BookType) -> Resu
     println!();
 
     println!("{}", "  Contract Conditions:".cyan());
-    for condition in &contract.ucl.conditions.required {
-        let required_badge = if condition.required {
+    let conditions = &contract.ucl.conditions;
+    let required = conditions.required.iter().map(|condition| (condition, true));
+    let optional = conditions.optional.iter().flatten().map(|condition| (condition, false));
+    for (condition, is_required) in required.chain(optional) {
+        let required_badge = if is_required {
             "[REQUIRED]".red()
         } else {
             "[OPTIONAL]".yellow()
@@ -322,7 +316,7 @@ async fn deploy_to_testnet(mut contract: smart402::Contract) -> Result<DeployedC
     println!("{}", "  Deployment Details:".cyan().bold());
     println!("    Contract Address: {}", result.address.white());
     println!("    Transaction Hash: {}", result.transaction_hash.white());
-    println!("    Network: {}", result.network.white());
+    println!("    Network: {}", result.network.to_string().white());
     if let Some(block) = result.block_number {
         println!("    Block Number: {}", block.to_string().white());
     }
@@ -332,15 +326,14 @@ async fn deploy_to_testnet(mut contract: smart402::Contract) -> Result<DeployedC
     // Display testnet explorer links
     println!("{}", "  📊 View on Block Explorer:".cyan());
     println!("    Contract: {}",
-        format!("https://mumbai.polygonscan.com/address/{}", result.address).blue().underline());
+        result.network.address_url(&result.address).blue().underline());
     println!("    Transaction: {}",
-        format!("https://mumbai.polygonscan.com/tx/{}", result.transaction_hash).blue().underline());
+        result.network.tx_url(&result.transaction_hash).blue().underline());
     println!();
 
     Ok(DeployedContract {
         contract,
         address: result.address,
-        transaction_hash: result.transaction_hash,
         network: result.network,
     })
 }
@@ -455,7 +448,7 @@ async fn process_purchase(
         }
     );
     println!("  Contract: {}", deployed.address.white());
-    println!("  Network: {}", deployed.network.white());
+    println!("  Network: {}", deployed.network.to_string().white());
     println!("  Status: {}", "Completed".green().bold());
     println!("  Items Delivered: {}", book.deliverables().len().to_string().cyan());
     println!();
@@ -467,6 +460,5 @@ async fn process_purchase(
 struct DeployedContract {
     contract: smart402::Contract,
     address: String,
-    transaction_hash: String,
-    network: String,
+    network: Network,
 }
@@ -5,7 +5,7 @@
 //! 2. Deploying to blockchain
 //! 3. Monitoring and auto-execution
 
-use smart402::{Smart402, ContractConfig, PaymentConfig};
+use smart402::{Amount, Network, PaymentFrequency, Token, Smart402, ContractConfig, PaymentConfig};
 use std::error::Error;
 
 #[tokio::main]
@@ -15,21 +15,14 @@ async fn main() -> Result<(), Box<dyn Error>> {
     // Step 1: Create Contract
     println!("1️⃣  Creating SaaS subscription contract...");
 
-    let config = ContractConfig {
-        contract_type: "saas-subscription".to_string(),
-        parties: vec![
-            "vendor@example.com".to_string(),
-            "customer@example.com".to_string(),
-        ],
-        payment: PaymentConfig {
-            amount: 99.0,
-            token: "USDC".to_string(),
-            blockchain: "polygon".to_string(),
-            frequency: "monthly".to_string(),
-        },
-        conditions: None,
-        metadata: None,
-    };
+    let payment = PaymentConfig::new(
+        Amount::from(99),
+        Token::usdc(),
+        PaymentFrequency::Monthly { day: None },
+    )
+    .with_blockchain(Network::Polygon);
+    let config = ContractConfig::new("saas-subscription", payment)
+        .with_parties(["vendor@example.com", "customer@example.com"]);
 
     let mut contract = Smart402::create(config).await?;
     println!("   ✓ Contract created: {}", contract.ucl.contract_id);
@@ -65,7 +65,7 @@ impl AEOEngine {
 
     /// Generate JSON-LD markup
     pub fn generate_jsonld(&self, ucl: &UCLContract) -> Result<String> {
-        let jsonld = serde_json::json!({
+        let mut jsonld = serde_json::json!({
             "@context": "https://schema.org/",
             "@type": "SmartContract",
             "identifier": ucl.contract_id,
@@ -75,6 +75,9 @@ impl AEOEngine {
             "contractType": ucl.metadata.contract_type,
             "category": ucl.metadata.category,
         });
+        if !ucl.metadata.tags.is_empty() {
+            jsonld["keywords"] = ucl.metadata.tags.join(", ").into();
+        }
 
         Ok(serde_json::to_string_pretty(&jsonld)?)
     }
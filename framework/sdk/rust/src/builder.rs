@@ -0,0 +1,288 @@
+//! Builder for [`UCLContract`]
+
+use crate::llmo::LLMOEngine;
+use crate::types::{
+    ConditionDefinition, Conditions, ContractMetadata, ContractSummary, DateInfo,
+    EscalationPolicy, LatePaymentTerms, OracleDefinition, PartyInfo, PaymentTerms,
+    RuleDefinition,
+};
+use crate::{
+    Amount, ContractDuration, Currency, Network, PaymentFrequency, PricingStructure, Renewal,
+    Result, Termination, Token, UCLContract,
+};
+use chrono::{NaiveDate, Utc};
+
+/// Assembles a [`UCLContract`] from the fields that matter, defaulting the
+/// rest
+///
+/// The effective date defaults to today, the standard to UCL-1.0, the token
+/// to USDC on Polygon and the currency to the one the token is pegged to.
+/// [`UCLContractBuilder::build`] validates the result.
+///
+/// ```
+/// use smart402::{Amount, Currency, PaymentFrequency, UCLContract};
+///
+/// # fn main() -> smart402::Result<()> {
+/// let ucl = UCLContract::builder()
+///     .contract_type("saas-subscription")
+///     .title("Pro plan")
+///     .amount(Amount::from(99))
+///     .frequency(PaymentFrequency::Monthly { day: None })
+///     .build()?;
+/// assert_eq!(ucl.payment.currency, Some(Currency::USD));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct UCLContractBuilder {
+    contract_id: Option<String>,
+    contract_type: Option<String>,
+    category: Option<String>,
+    title: Option<String>,
+    description: Option<String>,
+    parties: Vec<PartyInfo>,
+    effective: Option<NaiveDate>,
+    duration: ContractDuration,
+    renewal: Renewal,
+    structure: PricingStructure,
+    amount: Amount,
+    currency: Option<Currency>,
+    token: Option<Token>,
+    network: Option<Network>,
+    frequency: PaymentFrequency,
+    late_payment: Option<LatePaymentTerms>,
+    conditions: Vec<ConditionDefinition>,
+    oracles: Vec<OracleDefinition>,
+    rules: Vec<RuleDefinition>,
+    escalation: Option<EscalationPolicy>,
+    termination: Option<Termination>,
+    tags: Vec<String>,
+}
+
+impl UCLContract {
+    /// Start building a contract
+    pub fn builder() -> UCLContractBuilder {
+        UCLContractBuilder::default()
+    }
+}
+
+impl UCLContractBuilder {
+    /// Contract id (defaults to one generated from the contract type)
+    pub fn contract_id(mut self, contract_id: impl Into<String>) -> Self {
+        self.contract_id = Some(contract_id.into());
+        self
+    }
+
+    /// Contract type, e.g. `"saas-subscription"` (defaults to `"custom"`)
+    pub fn contract_type(mut self, contract_type: impl Into<String>) -> Self {
+        self.contract_type = Some(contract_type.into());
+        self
+    }
+
+    /// Listing category (defaults to `"general"`)
+    pub fn category(mut self, category: impl Into<String>) -> Self {
+        self.category = Some(category.into());
+        self
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Plain-English summary (defaults to one derived from the terms)
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn party(mut self, party: PartyInfo) -> Self {
+        self.parties.push(party);
+        self
+    }
+
+    /// Effective date (defaults to today)
+    pub fn effective(mut self, effective: NaiveDate) -> Self {
+        self.effective = Some(effective);
+        self
+    }
+
+    /// Term (defaults to 12 months)
+    pub fn duration(mut self, duration: ContractDuration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Renewal at the end of the term (defaults to automatic)
+    pub fn renewal(mut self, renewal: Renewal) -> Self {
+        self.renewal = renewal;
+        self
+    }
+
+    /// Usage pricing on top of the amount (defaults to fixed)
+    pub fn structure(mut self, structure: PricingStructure) -> Self {
+        self.structure = structure;
+        self
+    }
+
+    pub fn amount(mut self, amount: Amount) -> Self {
+        self.amount = amount;
+        self
+    }
+
+    /// Fiat currency prices are quoted in (defaults to the token's peg;
+    /// none for unpegged tokens)
+    pub fn currency(mut self, currency: Currency) -> Self {
+        self.currency = Some(currency);
+        self
+    }
+
+    /// Payment token (defaults to USDC)
+    pub fn token(mut self, token: Token) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    /// Settlement network (defaults to Polygon)
+    pub fn network(mut self, network: Network) -> Self {
+        self.network = Some(network);
+        self
+    }
+
+    /// Payment frequency (defaults to one-time)
+    pub fn frequency(mut self, frequency: PaymentFrequency) -> Self {
+        self.frequency = frequency;
+        self
+    }
+
+    pub fn late_payment(mut self, late_payment: LatePaymentTerms) -> Self {
+        self.late_payment = Some(late_payment);
+        self
+    }
+
+    /// Add a required condition
+    pub fn condition(mut self, condition: ConditionDefinition) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+
+    pub fn oracle(mut self, oracle: OracleDefinition) -> Self {
+        self.oracles.push(oracle);
+        self
+    }
+
+    pub fn rule(mut self, rule: RuleDefinition) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    pub fn escalation(mut self, escalation: EscalationPolicy) -> Self {
+        self.escalation = Some(escalation);
+        self
+    }
+
+    pub fn termination(mut self, termination: Termination) -> Self {
+        self.termination = Some(termination);
+        self
+    }
+
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    /// Assemble the contract, failing if it does not pass
+    /// [`LLMOEngine::validate`]
+    pub fn build(self) -> Result<UCLContract> {
+        let contract_type = self.contract_type.unwrap_or_else(|| "custom".to_string());
+        let token = self.token.unwrap_or_default();
+        let currency = self.currency.or_else(|| token.peg());
+        let title = self.title.unwrap_or_else(|| contract_type.clone());
+        let description = self.description.unwrap_or_else(|| {
+            format!("{}: {} {} {}", title, self.amount, token, self.frequency)
+        });
+
+        let ucl = UCLContract {
+            ucl_version: crate::utils::migrate::UCL_VERSION,
+            contract_id: self
+                .contract_id
+                .unwrap_or_else(|| crate::utils::generate_contract_id(&contract_type)),
+            version: "1.0".to_string(),
+            standard: "UCL-1.0".to_string(),
+            summary: ContractSummary {
+                title,
+                plain_english: description,
+                what_it_does: String::new(),
+                who_its_for: String::new(),
+                when_it_executes: String::new(),
+            },
+            metadata: ContractMetadata {
+                contract_type,
+                category: self.category.unwrap_or_else(|| "general".to_string()),
+                parties: self.parties,
+                dates: DateInfo {
+                    effective: self.effective.unwrap_or_else(|| Utc::now().date_naive()),
+                    duration: self.duration,
+                    renewal: self.renewal,
+                },
+                tags: self.tags,
+                custom: Default::default(),
+            },
+            payment: PaymentTerms {
+                structure: self.structure,
+                amount: self.amount,
+                currency,
+                token,
+                blockchain: self.network.unwrap_or(Network::Polygon),
+                frequency: self.frequency,
+                late_payment: self.late_payment,
+            },
+            conditions: Conditions {
+                required: self.conditions,
+                optional: None,
+                groups: vec![],
+            },
+            oracles: self.oracles,
+            rules: self.rules,
+            escalation: self.escalation,
+            termination: self.termination,
+        };
+
+        let validation = LLMOEngine::new().validate(&ucl)?;
+        if !validation.valid {
+            return Err(crate::Error::ValidationError(validation.errors.join("; ")));
+        }
+        Ok(ucl)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_defaults() {
+        let ucl = UCLContract::builder()
+            .title("Data feed")
+            .amount(Amount::new(5, 2))
+            .token(Token::well_known("EURC").unwrap())
+            .build()
+            .unwrap();
+        assert!(ucl.contract_id.starts_with("smart402:custom:"));
+        assert_eq!(ucl.standard, "UCL-1.0");
+        assert_eq!(ucl.payment.currency, Some(Currency::EUR));
+        assert_eq!(ucl.metadata.dates.effective, Utc::now().date_naive());
+        assert_eq!(ucl.summary.plain_english, "Data feed: 0.05 EURC one-time");
+
+        let eth = UCLContract::builder()
+            .token("ETH".parse().unwrap())
+            .build()
+            .unwrap();
+        assert_eq!(eth.payment.currency, None);
+
+        assert!(UCLContract::builder()
+            .amount(Amount::from(-1))
+            .build()
+            .is_err());
+    }
+}
@@ -0,0 +1,308 @@
+//! Actions rules run when they fire
+//!
+//! Actions that change the monitor's own state (`send_payment`, `notify`,
+//! `escalate`, `pause_contract`, `resume_contract`, `hold_payments`,
+//! `switch_oracle` and `apply_late_fee`) are built into the monitor. Every other action is
+//! looked up by name in the monitor's [`ActionRegistry`], which comes with
+//! [`WebhookAction`] and takes the application's own [`Action`]s, e.g. one
+//! provisioning the customer's account in its backend:
+//!
+//! ```no_run
+//! use async_trait::async_trait;
+//! use smart402::{Action, ActionContext, Contract};
+//! use std::sync::Arc;
+//!
+//! struct ProvisionAccount;
+//!
+//! #[async_trait]
+//! impl Action for ProvisionAccount {
+//!     fn name(&self) -> &str {
+//!         "provision_account"
+//!     }
+//!
+//!     async fn run(&self, context: &ActionContext<'_>) -> smart402::Result<Option<String>> {
+//!         let plan = context.params.get("plan").and_then(|plan| plan.as_str());
+//!         // ... call the backend ...
+//!         Ok(Some(format!("provisioned {:?} for {}", plan, context.contract.contract_id)))
+//!     }
+//! }
+//!
+//! # fn register(contract: &mut Contract) -> smart402::Result<()> {
+//! contract.add_action(Arc::new(ProvisionAccount))?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Actions found in neither are published for subscribers to carry out.
+
+use super::webhook::{MonitoringEvent, MonitoringWebhook, WebhookSender};
+use crate::types::RuleDefinition;
+use crate::{ConditionCheckResult, Contract, Result, UCLContract};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Actions the monitor carries out itself; they cannot be replaced
+pub const MONITOR_ACTIONS: &[&str] = &[
+    "send_payment",
+    "execute_payment",
+    "notify",
+    "escalate",
+    "pause_contract",
+    "resume_contract",
+    "hold_payments",
+    "switch_oracle",
+    "apply_late_fee",
+];
+
+/// Something a rule does when it fires
+#[async_trait]
+pub trait Action: Send + Sync {
+    /// Name rules refer to in `action`
+    fn name(&self) -> &str;
+
+    /// Carry out the action, returning a detail for the action log
+    ///
+    /// An error is logged as a failed action and published as
+    /// [`MonitorEvent::ActionFailed`](crate::MonitorEvent::ActionFailed).
+    async fn run(&self, context: &ActionContext<'_>) -> Result<Option<String>>;
+}
+
+/// What an action is run for
+pub struct ActionContext<'a> {
+    pub contract: &'a UCLContract,
+    /// Rule whose trigger fired
+    pub rule: &'a RuleDefinition,
+    /// Parameters the rule gives the action
+    pub params: &'a HashMap<String, Value>,
+    /// Check that fired the rule
+    pub check: &'a ConditionCheckResult,
+}
+
+/// Actions by name
+#[derive(Clone)]
+pub struct ActionRegistry {
+    actions: HashMap<String, Arc<dyn Action>>,
+}
+
+impl Default for ActionRegistry {
+    fn default() -> Self {
+        let mut registry = Self {
+            actions: HashMap::new(),
+        };
+        registry.actions.insert(
+            "webhook".to_string(),
+            Arc::new(WebhookAction::default()) as Arc<dyn Action>,
+        );
+        registry
+    }
+}
+
+impl ActionRegistry {
+    /// Registry with the `webhook` action
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an action, replacing one with the same name
+    ///
+    /// Fails for the names of [`MONITOR_ACTIONS`].
+    pub fn insert(&mut self, action: Arc<dyn Action>) -> Result<()> {
+        let name = action.name();
+        if MONITOR_ACTIONS.contains(&name) {
+            return Err(crate::Error::ConfigError(format!(
+                "{} is built into the monitor and cannot be replaced",
+                name
+            )));
+        }
+        self.actions.insert(name.to_string(), action);
+        Ok(())
+    }
+
+    /// Action with the given name
+    pub fn get(&self, name: &str) -> Option<&Arc<dyn Action>> {
+        self.actions.get(name)
+    }
+}
+
+/// POSTs a signed [`MonitoringWebhook`] to the action's `url`
+///
+/// The webhook carries the firing rule and the action's `payload`
+/// parameter, if any:
+///
+/// ```yaml
+/// actions:
+///   - action: webhook
+///     url: https://backend.example.com/hooks/smart402
+///     payload: { plan: pro }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct WebhookAction {
+    secret: Option<String>,
+}
+
+impl WebhookAction {
+    /// Sign webhooks with `secret` instead of the secret in
+    /// [`WEBHOOK_SECRET_ENV`](super::webhook::WEBHOOK_SECRET_ENV)
+    pub fn new(secret: impl Into<String>) -> Self {
+        Self {
+            secret: Some(secret.into()),
+        }
+    }
+}
+
+#[async_trait]
+impl Action for WebhookAction {
+    fn name(&self) -> &str {
+        "webhook"
+    }
+
+    async fn run(&self, context: &ActionContext<'_>) -> Result<Option<String>> {
+        let url = context
+            .params
+            .get("url")
+            .and_then(|url| url.as_str())
+            .ok_or_else(|| {
+                crate::Error::ConfigError("webhook action needs a `url` param".to_string())
+            })?;
+        let sender = match &self.secret {
+            Some(secret) => WebhookSender::new(url, secret),
+            None => WebhookSender::from_env(url)?,
+        };
+        let webhook = MonitoringWebhook::new(
+            &context.contract.contract_id,
+            MonitoringEvent::RuleWebhook {
+                rule_id: context.rule.rule_id.clone(),
+                payload: context.params.get("payload").cloned().unwrap_or_default(),
+            },
+        );
+        sender.send(&webhook).await?;
+        Ok(Some(format!("webhook {} delivered to {}", webhook.id, url)))
+    }
+}
+
+impl Contract {
+    /// Let the contract's rules run `action`
+    ///
+    /// Affects monitors started afterwards. See [`ActionRegistry::insert`].
+    pub fn add_action(&mut self, action: Arc<dyn Action>) -> Result<()> {
+        self.actions.insert(action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::monitor::{MonitorEvent, Schedule};
+    use crate::core::rules::ActionOutcome;
+    use crate::core::store::{ContractStore, InMemoryContractStore};
+    use crate::core::webhook::verify_monitoring_webhook;
+    use crate::types::{ActionDefinition, RuleConditions};
+    use crate::x402::test_server::{header, serve};
+    use crate::x402::webhook::WEBHOOK_SIGNATURE_HEADER;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    /// Action remembering the params it ran with
+    struct Provision(Mutex<Vec<Value>>);
+
+    #[async_trait]
+    impl Action for Provision {
+        fn name(&self) -> &str {
+            "provision_account"
+        }
+
+        async fn run(&self, context: &ActionContext<'_>) -> Result<Option<String>> {
+            self.0.lock().unwrap().push(context.params["plan"].clone());
+            Ok(Some(format!(
+                "provisioned {}",
+                context.contract.contract_id
+            )))
+        }
+    }
+
+    struct Named(&'static str);
+
+    #[async_trait]
+    impl Action for Named {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        async fn run(&self, _: &ActionContext<'_>) -> Result<Option<String>> {
+            Ok(None)
+        }
+    }
+
+    fn rule(actions: Vec<ActionDefinition>) -> RuleDefinition {
+        RuleDefinition {
+            rule_id: "on_signup".to_string(),
+            name: "Provision on signup".to_string(),
+            trigger: "condition_change".to_string(),
+            conditions: RuleConditions::default(),
+            actions,
+            ..Default::default()
+        }
+    }
+
+    fn action(name: &str, params: Value) -> ActionDefinition {
+        ActionDefinition {
+            action: name.to_string(),
+            params: serde_json::from_value(params).unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rules_run_registered_and_webhook_actions() {
+        let (url, requests) = serve(vec![(200, "{}")]).await;
+        let mut contract = crate::core::fixtures::contract();
+        contract.ucl.rules.push(rule(vec![
+            action("provision_account", serde_json::json!({"plan": "pro"})),
+            action(
+                "webhook",
+                serde_json::json!({"url": url, "payload": {"plan": "pro"}}),
+            ),
+        ]));
+        let provision = Arc::new(Provision(Mutex::default()));
+        contract.add_action(provision.clone()).unwrap();
+        contract
+            .add_action(Arc::new(WebhookAction::new("whsec_test")))
+            .unwrap();
+        assert!(contract.add_action(Arc::new(Named("notify"))).is_err());
+
+        let store = Arc::new(InMemoryContractStore::new());
+        let monitor = contract
+            .resume_monitoring(Schedule::Every(Duration::from_millis(10)), store.clone())
+            .unwrap();
+        let mut events = monitor.subscribe();
+        while !matches!(events.recv().await.unwrap(), MonitorEvent::Checked(_)) {}
+        monitor.stop().await;
+
+        assert_eq!(*provision.0.lock().unwrap(), vec![serde_json::json!("pro")]);
+        let log = store
+            .load_monitor(&contract.ucl.contract_id)
+            .unwrap()
+            .unwrap()
+            .log;
+        assert_eq!(
+            log[0].outcome,
+            ActionOutcome::Executed {
+                detail: Some(format!("provisioned {}", contract.ucl.contract_id))
+            }
+        );
+        assert!(matches!(log[1].outcome, ActionOutcome::Executed { .. }));
+
+        let requests = requests.lock().unwrap();
+        let (head, body) = requests[0].split_once("\r\n\r\n").unwrap();
+        let signature = header(head, WEBHOOK_SIGNATURE_HEADER).unwrap();
+        let webhook = verify_monitoring_webhook(body.as_bytes(), signature, "whsec_test").unwrap();
+        assert_eq!(
+            webhook.event,
+            MonitoringEvent::RuleWebhook {
+                rule_id: "on_signup".to_string(),
+                payload: serde_json::json!({"plan": "pro"}),
+            }
+        );
+    }
+}
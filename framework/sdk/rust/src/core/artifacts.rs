@@ -0,0 +1,420 @@
+//! Contract artifacts in object storage
+//!
+//! [`ArtifactStore`] keeps contract documents and what is generated from
+//! them, Solidity sources, compiled artifacts, JSON-LD and payment
+//! receipts, in S3-compatible storage (`s3` feature) or Google Cloud
+//! Storage (`gcs` feature), so functions without a local disk can share
+//! them. Artifacts are addressed by contract id and the SHA-256 of their
+//! content:
+//!
+//! ```text
+//! <prefix>/<contract_id>/<kind>/<sha256>.<extension>
+//! ```
+//!
+//! A stored artifact never changes, so it can be cached forever, and a
+//! download is checked against its digest. Contract documents are stored
+//! as their [canonical bytes](UCLContract::canonical_bytes), so their
+//! digest is the [content digest](UCLContract::content_digest) that
+//! signatures name.
+
+use crate::{Result, UCLContract};
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use object_store::path::Path;
+use object_store::{ObjectStore, PutPayload};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::sync::Arc;
+
+/// What an artifact is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum ArtifactKind {
+    /// Contract document, as canonical JSON
+    Contract,
+    /// Solidity source generated from the contract
+    Solidity,
+    /// [`crate::llmo::CompiledArtifact`] as JSON
+    Compiled,
+    /// JSON-LD description of the contract
+    JsonLd,
+    /// Payment receipt token
+    Receipt,
+}
+
+impl ArtifactKind {
+    const ALL: [ArtifactKind; 5] = [
+        ArtifactKind::Contract,
+        ArtifactKind::Solidity,
+        ArtifactKind::Compiled,
+        ArtifactKind::JsonLd,
+        ArtifactKind::Receipt,
+    ];
+
+    /// Name of the kind in object keys, e.g. `"json-ld"`
+    pub fn name(self) -> &'static str {
+        match self {
+            ArtifactKind::Contract => "contract",
+            ArtifactKind::Solidity => "solidity",
+            ArtifactKind::Compiled => "compiled",
+            ArtifactKind::JsonLd => "json-ld",
+            ArtifactKind::Receipt => "receipt",
+        }
+    }
+
+    /// File extension of artifacts of the kind
+    pub fn extension(self) -> &'static str {
+        match self {
+            ArtifactKind::Contract | ArtifactKind::Compiled => "json",
+            ArtifactKind::Solidity => "sol",
+            ArtifactKind::JsonLd => "jsonld",
+            ArtifactKind::Receipt => "txt",
+        }
+    }
+}
+
+impl fmt::Display for ArtifactKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Address of a stored artifact
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArtifactRef {
+    pub contract_id: String,
+    pub kind: ArtifactKind,
+    /// SHA-256 of the content, hex-encoded
+    pub digest: String,
+    pub size: u64,
+    /// When the artifact was first stored, if the store reports it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stored_at: Option<DateTime<Utc>>,
+}
+
+/// Why an artifact could not be stored or fetched
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
+pub enum ArtifactError {
+    #[error("Invalid artifact store URL {url}: {reason}")]
+    InvalidUrl { url: String, reason: String },
+
+    #[error("Artifact {location} not found")]
+    NotFound { location: String },
+
+    /// The content does not match its digest or cannot be read
+    #[error("Corrupt artifact {location}: {reason}")]
+    Corrupt { location: String, reason: String },
+
+    #[error("Artifact storage failed: {reason}")]
+    Storage { reason: String },
+}
+
+impl ArtifactError {
+    /// Stable machine-readable code, e.g. `"artifact.not_found"`
+    pub fn code(&self) -> &'static str {
+        match self {
+            ArtifactError::InvalidUrl { .. } => "artifact.invalid_url",
+            ArtifactError::NotFound { .. } => "artifact.not_found",
+            ArtifactError::Corrupt { .. } => "artifact.corrupt",
+            ArtifactError::Storage { .. } => "artifact.storage",
+        }
+    }
+}
+
+impl From<object_store::Error> for ArtifactError {
+    fn from(e: object_store::Error) -> Self {
+        match e {
+            object_store::Error::NotFound { path, .. } => {
+                ArtifactError::NotFound { location: path }
+            }
+            e => ArtifactError::Storage {
+                reason: e.to_string(),
+            },
+        }
+    }
+}
+
+/// Contract artifacts in an object store
+#[derive(Debug, Clone)]
+pub struct ArtifactStore {
+    store: Arc<dyn ObjectStore>,
+    prefix: Path,
+}
+
+impl ArtifactStore {
+    /// Keep artifacts in `store` under `prefix`
+    pub fn new(store: Arc<dyn ObjectStore>, prefix: &str) -> Self {
+        Self {
+            store,
+            prefix: Path::from(prefix),
+        }
+    }
+
+    /// Open the store at `url`
+    ///
+    /// `s3://bucket/prefix` (`s3` feature) and `gs://bucket/prefix` (`gcs`
+    /// feature) read credentials, region and endpoint from the standard
+    /// `AWS_*` and `GOOGLE_*` environment variables, so S3-compatible
+    /// stores work through `AWS_ENDPOINT`. `file:///dir` keeps artifacts on
+    /// local disk and `memory:///` in memory, for tests.
+    pub fn from_url(url: &str) -> Result<Self> {
+        let invalid = |reason: &str| ArtifactError::InvalidUrl {
+            url: url.to_string(),
+            reason: reason.to_string(),
+        };
+        let (scheme, rest) = url
+            .split_once("://")
+            .ok_or_else(|| invalid("expected scheme://location"))?;
+        let (store, prefix): (Arc<dyn ObjectStore>, &str) = match scheme {
+            "memory" => (Arc::new(object_store::memory::InMemory::new()), rest),
+            "file" => {
+                std::fs::create_dir_all(rest)?;
+                let store = object_store::local::LocalFileSystem::new_with_prefix(rest)
+                    .map_err(|e| invalid(&e.to_string()))?;
+                (Arc::new(store), "")
+            }
+            #[cfg(feature = "s3")]
+            "s3" | "s3a" => {
+                let store = object_store::aws::AmazonS3Builder::from_env()
+                    .with_url(url)
+                    .build()
+                    .map_err(|e| invalid(&e.to_string()))?;
+                (Arc::new(store), bucket_prefix(rest))
+            }
+            #[cfg(feature = "gcs")]
+            "gs" => {
+                let store = object_store::gcp::GoogleCloudStorageBuilder::from_env()
+                    .with_url(url)
+                    .build()
+                    .map_err(|e| invalid(&e.to_string()))?;
+                (Arc::new(store), bucket_prefix(rest))
+            }
+            #[cfg(not(feature = "s3"))]
+            "s3" | "s3a" => return Err(invalid("build with the `s3` feature").into()),
+            #[cfg(not(feature = "gcs"))]
+            "gs" => return Err(invalid("build with the `gcs` feature").into()),
+            _ => return Err(invalid("expected s3://, gs://, file:// or memory://").into()),
+        };
+        Ok(Self::new(store, prefix))
+    }
+
+    /// Store `content` as an artifact of `contract_id`
+    ///
+    /// Storing the same content again is a no-op that returns the same
+    /// reference.
+    pub async fn put(
+        &self,
+        contract_id: &str,
+        kind: ArtifactKind,
+        content: impl Into<Vec<u8>>,
+    ) -> Result<ArtifactRef> {
+        let content = content.into();
+        let artifact = ArtifactRef {
+            contract_id: contract_id.to_string(),
+            kind,
+            digest: hex::encode(Sha256::digest(&content)),
+            size: content.len() as u64,
+            stored_at: None,
+        };
+        let location = self.location(&artifact);
+        match self.store.head(&location).await {
+            Ok(meta) => {
+                return Ok(ArtifactRef {
+                    stored_at: Some(meta.last_modified),
+                    ..artifact
+                })
+            }
+            Err(object_store::Error::NotFound { .. }) => {}
+            Err(e) => return Err(ArtifactError::from(e).into()),
+        }
+        self.store
+            .put(&location, PutPayload::from(content))
+            .await
+            .map_err(ArtifactError::from)?;
+        Ok(ArtifactRef {
+            stored_at: Some(Utc::now()),
+            ..artifact
+        })
+    }
+
+    /// Content of an artifact, checked against its digest
+    pub async fn get(&self, artifact: &ArtifactRef) -> Result<Vec<u8>> {
+        let location = self.location(artifact);
+        let content = self
+            .store
+            .get(&location)
+            .await
+            .map_err(ArtifactError::from)?
+            .bytes()
+            .await
+            .map_err(ArtifactError::from)?;
+        let digest = hex::encode(Sha256::digest(&content));
+        if digest != artifact.digest {
+            return Err(ArtifactError::Corrupt {
+                location: location.to_string(),
+                reason: format!("content hashes to {}", digest),
+            }
+            .into());
+        }
+        Ok(content.to_vec())
+    }
+
+    /// Artifacts of `contract_id`, oldest first
+    pub async fn list(&self, contract_id: &str) -> Result<Vec<ArtifactRef>> {
+        let prefix = self.prefix.child(key_part(contract_id));
+        let objects: Vec<_> = self
+            .store
+            .list(Some(&prefix))
+            .try_collect()
+            .await
+            .map_err(ArtifactError::from)?;
+        let mut artifacts: Vec<_> = objects
+            .into_iter()
+            .filter_map(|meta| {
+                let mut parts = meta.location.prefix_match(&prefix)?;
+                let kind = parts.next()?;
+                let kind = ArtifactKind::ALL
+                    .into_iter()
+                    .find(|k| k.name() == kind.as_ref())?;
+                let name = parts.next()?;
+                let (digest, _) = name.as_ref().split_once('.')?;
+                Some(ArtifactRef {
+                    contract_id: contract_id.to_string(),
+                    kind,
+                    digest: digest.to_string(),
+                    size: meta.size,
+                    stored_at: Some(meta.last_modified),
+                })
+            })
+            .collect();
+        artifacts.sort_by_key(|artifact| artifact.stored_at);
+        Ok(artifacts)
+    }
+
+    /// Most recently stored artifact of `kind` for `contract_id`
+    pub async fn latest(
+        &self,
+        contract_id: &str,
+        kind: ArtifactKind,
+    ) -> Result<Option<ArtifactRef>> {
+        let artifacts = self.list(contract_id).await?;
+        Ok(artifacts
+            .into_iter()
+            .rev()
+            .find(|artifact| artifact.kind == kind))
+    }
+
+    /// Store a contract document
+    pub async fn put_contract(&self, ucl: &UCLContract) -> Result<ArtifactRef> {
+        self.put(
+            &ucl.contract_id,
+            ArtifactKind::Contract,
+            ucl.canonical_bytes()?,
+        )
+        .await
+    }
+
+    /// Contract document with id `contract_id` and content digest `digest`,
+    /// upgraded to the current schema
+    pub async fn get_contract(&self, contract_id: &str, digest: &str) -> Result<UCLContract> {
+        let artifact = ArtifactRef {
+            contract_id: contract_id.to_string(),
+            kind: ArtifactKind::Contract,
+            digest: digest.to_string(),
+            size: 0,
+            stored_at: None,
+        };
+        let content = self.get(&artifact).await?;
+        let corrupt = |e: &dyn fmt::Display| ArtifactError::Corrupt {
+            location: self.location(&artifact).to_string(),
+            reason: e.to_string(),
+        };
+        let document = serde_json::from_slice(&content).map_err(|e| corrupt(&e))?;
+        Ok(crate::utils::migrate::migrate(document).map_err(|e| corrupt(&e))?)
+    }
+
+    fn location(&self, artifact: &ArtifactRef) -> Path {
+        self.prefix
+            .child(key_part(&artifact.contract_id))
+            .child(artifact.kind.name())
+            .child(format!("{}.{}", artifact.digest, artifact.kind.extension()))
+    }
+}
+
+/// Prefix of a `bucket/prefix` location
+#[cfg(any(feature = "s3", feature = "gcs"))]
+fn bucket_prefix(location: &str) -> &str {
+    location.split_once('/').map_or("", |(_, prefix)| prefix)
+}
+
+/// `contract_id` as one part of an object key, like the file names of
+/// [`super::store::FileContractStore`]
+fn key_part(contract_id: &str) -> String {
+    contract_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Contract;
+
+    #[tokio::test]
+    async fn test_artifacts_are_content_addressed() {
+        let store = ArtifactStore::from_url("memory:///contracts").unwrap();
+        let ucl = Contract::from_config(Default::default()).unwrap().ucl;
+
+        let document = store.put_contract(&ucl).await.unwrap();
+        assert_eq!(document.digest, ucl.content_digest().unwrap());
+        assert_eq!(
+            store.put_contract(&ucl).await.unwrap().digest,
+            document.digest
+        );
+        let loaded = store
+            .get_contract(&ucl.contract_id, &document.digest)
+            .await
+            .unwrap();
+        assert_eq!(loaded.contract_id, ucl.contract_id);
+
+        let source = "pragma solidity ^0.8.0;";
+        let solidity = store
+            .put(&ucl.contract_id, ArtifactKind::Solidity, source)
+            .await
+            .unwrap();
+        assert_eq!(store.get(&solidity).await.unwrap(), source.as_bytes());
+        let kinds: Vec<_> = store
+            .list(&ucl.contract_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|artifact| artifact.kind)
+            .collect();
+        assert_eq!(kinds, [ArtifactKind::Contract, ArtifactKind::Solidity]);
+        let latest = store
+            .latest(&ucl.contract_id, ArtifactKind::Solidity)
+            .await
+            .unwrap();
+        assert_eq!(latest.unwrap().digest, solidity.digest);
+
+        let missing = ArtifactRef {
+            digest: "00".repeat(32),
+            ..solidity.clone()
+        };
+        let error = store.get(&missing).await.unwrap_err();
+        assert_eq!(error.code(), "artifact.not_found");
+
+        let error = ArtifactStore::from_url("ftp://bucket").unwrap_err();
+        assert_eq!(error.code(), "artifact.invalid_url");
+    }
+}
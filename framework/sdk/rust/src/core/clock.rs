@@ -0,0 +1,154 @@
+//! Time as seen by monitors
+//!
+//! Monitors read the time and wait for their scheduled checks through a
+//! [`Clock`]. Contracts and store-wide monitors run on the [`SystemClock`]
+//! unless given another one, e.g. a [`TestClock`] that tests advance by a
+//! month to see a monthly payment fall due:
+//!
+//! ```no_run
+//! # #[cfg(feature = "testing")]
+//! # async fn run(mut contract: smart402::Contract) {
+//! use smart402::core::clock::TestClock;
+//! use smart402::Schedule;
+//! use std::sync::Arc;
+//!
+//! let clock = Arc::new(TestClock::new("2024-05-01T00:00:00Z".parse().unwrap()));
+//! contract.set_clock(clock.clone());
+//! let monitor = contract.monitor_with_schedule(Schedule::parse("0 0 1 * *").unwrap());
+//! clock.advance(chrono::Duration::days(31));
+//! # }
+//! ```
+
+use crate::Contract;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+
+/// Source of the current time
+#[async_trait]
+pub trait Clock: Send + Sync {
+    /// Current time
+    fn now(&self) -> DateTime<Utc>;
+
+    /// Wait until the clock reads `at` or later
+    async fn sleep_until(&self, at: DateTime<Utc>);
+}
+
+/// The wall clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+#[async_trait]
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+
+    async fn sleep_until(&self, at: DateTime<Utc>) {
+        let wait = (at - Utc::now()).to_std().unwrap_or_default();
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// Clock that stands still until advanced
+///
+/// Monitors waiting on it run the checks that fall due as soon as it is
+/// advanced past them; a check missed by a jump of several runs is caught
+/// up once, like after downtime.
+#[cfg(any(test, feature = "testing"))]
+#[derive(Debug)]
+pub struct TestClock {
+    now: tokio::sync::watch::Sender<DateTime<Utc>>,
+}
+
+#[cfg(any(test, feature = "testing"))]
+impl TestClock {
+    /// Clock reading `start`
+    pub fn new(start: DateTime<Utc>) -> Self {
+        Self {
+            now: tokio::sync::watch::channel(start).0,
+        }
+    }
+
+    /// Move the clock forward by `by`
+    pub fn advance(&self, by: chrono::Duration) {
+        self.now.send_modify(|now| *now += by);
+    }
+
+    /// Set the clock to `to`, which may be earlier than now
+    pub fn set(&self, to: DateTime<Utc>) {
+        self.now.send_replace(to);
+    }
+}
+
+#[cfg(any(test, feature = "testing"))]
+#[async_trait]
+impl Clock for TestClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.borrow()
+    }
+
+    async fn sleep_until(&self, at: DateTime<Utc>) {
+        // The sender lives as long as the clock, so this only returns once due
+        let _ = self.now.subscribe().wait_for(|now| *now >= at).await;
+    }
+}
+
+impl Contract {
+    /// Check conditions and run monitors by `clock` instead of the wall
+    /// clock
+    ///
+    /// Affects monitors started afterwards.
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::monitor::{MonitorEvent, Schedule};
+    use crate::types::{ActionDefinition, RuleDefinition};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_monthly_payment_falls_due_as_test_clock_advances() {
+        let mut contract = crate::core::fixtures::contract();
+        contract.ucl.payment.frequency = crate::PaymentFrequency::Monthly { day: None };
+        contract.ucl.rules.push(RuleDefinition {
+            rule_id: "monthly_payment".to_string(),
+            name: "Execute Monthly Payment".to_string(),
+            trigger: "time_based".to_string(),
+            actions: vec![ActionDefinition {
+                action: "execute_payment".to_string(),
+                params: Default::default(),
+            }],
+            ..Default::default()
+        });
+        let clock = Arc::new(TestClock::new("2024-05-01T00:00:00Z".parse().unwrap()));
+        contract.set_clock(clock.clone());
+
+        let monitor = contract.monitor_with_schedule(Schedule::Every(Duration::from_secs(86_400)));
+        let mut events = monitor.subscribe();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(events.try_recv().is_err(), "checked before the clock moved");
+
+        let mut payments = Vec::new();
+        for _ in 0..32 {
+            clock.advance(chrono::Duration::days(1));
+            loop {
+                match events.recv().await.unwrap() {
+                    MonitorEvent::PaymentExecuted(_) => {
+                        payments.push(clock.now().date_naive().to_string());
+                        break;
+                    }
+                    MonitorEvent::ActionSkipped { .. } => break,
+                    _ => {}
+                }
+            }
+        }
+        monitor.stop().await;
+
+        assert_eq!(payments, vec!["2024-05-02", "2024-06-02"]);
+    }
+}
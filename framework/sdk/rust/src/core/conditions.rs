@@ -0,0 +1,110 @@
+//! Composite condition evaluation
+//!
+//! Rules and condition groups combine condition results: every entry of
+//! `all_of` must hold, at least one of `any_of`, and at least `n` of the
+//! `n_of_m` quorum. Entries are condition or group ids, optionally written
+//! `"<id> == true|false"`. Ids that were not checked never hold.
+
+use crate::types::{ConditionGroup, RuleConditions};
+use crate::ConditionCheckResult;
+use std::collections::HashMap;
+
+impl RuleConditions {
+    /// Whether the combination holds for the given condition results
+    ///
+    /// A combination without any list always holds.
+    pub fn holds(&self, conditions: &HashMap<String, bool>) -> bool {
+        let holds = |entry: &String| entry_holds(entry, conditions);
+        let all_hold = self.all_of.iter().flatten().all(holds);
+        let any_holds = match &self.any_of {
+            Some(any) => any.iter().any(holds),
+            None => true,
+        };
+        let quorum_holds = match &self.n_of_m {
+            Some(quorum) => quorum.of.iter().filter(|entry| holds(entry)).count() >= quorum.n,
+            None => true,
+        };
+        all_hold && any_holds && quorum_holds
+    }
+}
+
+impl ConditionCheckResult {
+    /// Whether a rule's or group's combination of conditions holds
+    pub fn satisfies(&self, conditions: &RuleConditions) -> bool {
+        conditions.holds(&self.conditions)
+    }
+
+    /// Add the result of every group, in order, so later groups and rules
+    /// can refer to earlier ones
+    pub(crate) fn add_groups(&mut self, groups: &[ConditionGroup]) {
+        for group in groups {
+            let holds = self.satisfies(&group.conditions);
+            self.conditions.insert(group.id.clone(), holds);
+        }
+    }
+}
+
+/// Evaluate `"<id>"` or `"<id> == true|false"`
+fn entry_holds(entry: &str, conditions: &HashMap<String, bool>) -> bool {
+    let (id, expected) = match entry.split_once("==") {
+        Some((id, value)) => (id.trim(), value.trim() == "true"),
+        None => (entry.trim(), true),
+    };
+    conditions.get(id) == Some(&expected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Quorum;
+
+    fn ids(ids: &[&str]) -> Option<Vec<String>> {
+        Some(ids.iter().map(|id| id.to_string()).collect())
+    }
+
+    #[test]
+    fn test_groups_and_quorum() {
+        let mut result = ConditionCheckResult {
+            all_met: false,
+            conditions: HashMap::from([
+                ("design_approved".to_string(), true),
+                ("code_merged".to_string(), true),
+                ("docs_published".to_string(), false),
+                ("disputed".to_string(), false),
+            ]),
+            timestamp: chrono::Utc::now(),
+            unavailable: Vec::new(),
+            attestations: Vec::new(),
+        };
+        let two_of_three = RuleConditions {
+            all_of: ids(&["disputed == false"]),
+            any_of: None,
+            n_of_m: Some(Quorum {
+                n: 2,
+                of: ids(&["design_approved", "code_merged", "docs_published"]).unwrap(),
+            }),
+        };
+        assert!(result.satisfies(&two_of_three));
+
+        let mut three_of_three = two_of_three.clone();
+        three_of_three.n_of_m.as_mut().unwrap().n = 3;
+        assert!(!result.satisfies(&three_of_three));
+
+        result.add_groups(&[
+            ConditionGroup {
+                id: "milestones_done".to_string(),
+                conditions: two_of_three,
+            },
+            ConditionGroup {
+                id: "releasable".to_string(),
+                conditions: RuleConditions {
+                    all_of: None,
+                    any_of: ids(&["milestones_done", "never_checked"]),
+                    n_of_m: None,
+                },
+            },
+        ]);
+        assert!(result.conditions["milestones_done"]);
+        assert!(result.conditions["releasable"]);
+    }
+}
@@ -1,113 +1,288 @@
 //! Contract struct
 
-use crate::{ContractConfig, ContractStatus, DeployResult, PaymentResult, Result, UCLContract, ConditionCheckResult};
-use std::collections::HashMap;
+use crate::{ContractConfig, ContractStatus, DeployResult, Deployment, PartyInfo, PaymentRecord, PaymentResult, Result, UCLContract, ConditionCheckResult, CheckExplanation};
+use crate::llmo::CompiledArtifact;
+use crate::utils::envelope::ContractEnvelope;
+use super::events::{ContractEvent, ContractEventKind, EVENT_CAPACITY};
+use super::monitor::{MonitorHandle, Schedule};
+use super::notify::Notifier;
+use super::actions::ActionRegistry;
+use super::clock::{Clock, SystemClock};
+use super::history::{self, ConditionHistory};
+use super::oracle::OracleSet;
+use super::payment::{PaymentExecutor, PlaceholderExecutor};
+use super::temporal::Timeline;
+use super::webhook::WebhookSender;
+use crate::x402::challenge::{PAYEE_ROLES, PAYER_ROLES};
+use crate::x402::metering::UsageMeter;
+use crate::x402::RetryPolicy;
+use std::sync::Arc;
+use tokio::sync::{broadcast, watch};
 
 /// Smart402 Contract instance
 pub struct Contract {
     pub ucl: UCLContract,
     status: ContractStatus,
-    deployed_address: Option<String>,
-    transaction_hash: Option<String>,
+    deployment: Option<Deployment>,
+    artifact: Option<CompiledArtifact>,
+    payments: Vec<PaymentRecord>,
+    usage: Arc<UsageMeter>,
+    pub(super) notifiers: Vec<Arc<dyn Notifier>>,
+    pub(super) oracles: OracleSet,
+    pub(super) actions: ActionRegistry,
+    pub(super) events: broadcast::Sender<ContractEvent>,
+    /// Whether the contract's monitors skip their checks
+    pub(super) monitoring_paused: Arc<watch::Sender<bool>>,
+    /// When notice of termination was given, if it was
+    pub(super) termination_notice: Arc<watch::Sender<Option<chrono::DateTime<chrono::Utc>>>>,
+    pub(super) clock: Arc<dyn Clock>,
+    pub(super) history: Arc<ConditionHistory>,
+    pub(super) payer: Arc<dyn PaymentExecutor>,
+    pub(super) payment_retry: RetryPolicy,
 }
 
+/// Roles of the parties of a [`ContractConfig`], in order; later parties
+/// are plain `"party"`s
+const CONFIG_ROLES: &[&str] = &["vendor", "customer"];
+
 impl Contract {
     /// Create contract from configuration
-    pub fn from_config(_config: ContractConfig) -> Result<Self> {
-        // Placeholder - would generate UCL
+    ///
+    /// The first party is the vendor and the second the customer. Fails if
+    /// the contract does not pass [`crate::LLMOEngine::validate`], e.g. for
+    /// a negative amount. Without a description, the plain-English summary
+    /// gives the title, amount, token and frequency.
+    pub fn from_config(config: ContractConfig) -> Result<Self> {
+        let (required, optional): (Vec<_>, Vec<_>) = config
+            .conditions
+            .unwrap_or_default()
+            .into_iter()
+            .partition(|condition| condition.required);
+        let metadata = config.metadata.unwrap_or_default();
+        metadata.validate()?;
+        let contract_type = match config.contract_type.trim() {
+            "" => "custom".to_string(),
+            contract_type => contract_type.to_string(),
+        };
+        let parties = config
+            .parties
+            .into_iter()
+            .enumerate()
+            .map(|(i, identifier)| PartyInfo {
+                role: CONFIG_ROLES.get(i).unwrap_or(&"party").to_string(),
+                identifier,
+                ..Default::default()
+            })
+            .collect();
+        let title = metadata.title.unwrap_or_else(|| "Contract".to_string());
+        let payment = config.payment;
+        let frequency = match payment.frequency {
+            crate::PaymentFrequency::Monthly { day: None } => crate::PaymentFrequency::Monthly {
+                day: payment.day_of_month,
+            },
+            frequency => frequency,
+        };
+
         let ucl = UCLContract {
-            contract_id: "smart402:contract:abc123".to_string(),
+            ucl_version: crate::utils::migrate::UCL_VERSION,
+            contract_id: crate::utils::generate_contract_id(&contract_type),
             version: "1.0".to_string(),
             standard: "UCL-1.0".to_string(),
             summary: crate::types::ContractSummary {
-                title: "Contract".to_string(),
-                plain_english: "Contract summary".to_string(),
+                plain_english: metadata.description.unwrap_or_else(|| {
+                    format!("{}: {} {} {}", title, payment.amount, payment.token, frequency)
+                }),
+                title,
                 what_it_does: String::new(),
                 who_its_for: String::new(),
                 when_it_executes: String::new(),
             },
             metadata: crate::types::ContractMetadata {
-                contract_type: "custom".to_string(),
-                category: "general".to_string(),
-                parties: vec![],
+                contract_type,
+                category: metadata.category.unwrap_or_else(|| "general".to_string()),
+                parties,
                 dates: crate::types::DateInfo {
-                    effective: "2024-01-01".to_string(),
-                    duration: "12 months".to_string(),
-                    renewal: "auto".to_string(),
+                    effective: chrono::NaiveDate::from_ymd_opt(2024, 1, 1)
+                        .expect("valid date"),
+                    duration: crate::ContractDuration::Months(12),
+                    renewal: crate::Renewal::Auto,
                 },
+                tags: metadata.tags,
+                custom: metadata.custom,
             },
             payment: crate::types::PaymentTerms {
-                structure: "fixed".to_string(),
-                amount: 0.0,
-                currency: "USD".to_string(),
-                token: "USDC".to_string(),
-                blockchain: "polygon".to_string(),
-                frequency: "one-time".to_string(),
+                structure: crate::PricingStructure::Fixed,
+                amount: payment.amount,
+                currency: payment.token.peg(),
+                token: payment.token,
+                blockchain: payment.blockchain.unwrap_or(crate::Network::Polygon),
+                frequency,
+                late_payment: None,
             },
             conditions: crate::types::Conditions {
-                required: vec![],
-                optional: None,
+                required: required.into_iter().map(Into::into).collect(),
+                optional: (!optional.is_empty())
+                    .then(|| optional.into_iter().map(Into::into).collect()),
+                groups: vec![],
             },
             oracles: vec![],
             rules: vec![],
+            escalation: None,
+            termination: None,
         };
+        let validation = crate::llmo::LLMOEngine::new().validate(&ucl)?;
+        if !validation.valid {
+            return Err(crate::Error::ValidationError(validation.errors.join("; ")));
+        }
 
         Ok(Self {
             ucl,
             status: ContractStatus::Draft,
-            deployed_address: None,
-            transaction_hash: None,
+            deployment: None,
+            artifact: None,
+            payments: Vec::new(),
+            usage: Arc::new(UsageMeter::new()),
+            notifiers: Vec::new(),
+            oracles: OracleSet::new(),
+            actions: ActionRegistry::new(),
+            events: broadcast::channel(EVENT_CAPACITY).0,
+            monitoring_paused: Arc::new(watch::channel(false).0),
+            termination_notice: Arc::new(watch::channel(None).0),
+            clock: Arc::new(SystemClock),
+            history: Arc::new(ConditionHistory::new()),
+            payer: Arc::new(PlaceholderExecutor),
+            payment_retry: RetryPolicy::default(),
         })
     }
 
+    /// Attach compiled bytecode and ABI to use for deployment
+    pub fn attach_artifact(&mut self, artifact: CompiledArtifact) {
+        self.artifact = Some(artifact);
+    }
+
+    /// Get the compiled artifact used for deployment
+    pub fn artifact(&self) -> Option<&CompiledArtifact> {
+        self.artifact.as_ref()
+    }
+
     /// Deploy contract to blockchain
-    pub async fn deploy(&mut self, network: &str) -> Result<DeployResult> {
-        self.status = ContractStatus::Deploying;
+    ///
+    /// Takes a [`Network`](crate::Network) or a network name; fails on
+    /// unknown names and on party wallets that are not EVM addresses. The
+    /// payee party's wallet owns the deployed contract.
+    #[tracing::instrument(skip(self, network), fields(network = network.as_ref(), contract_id = %self.ucl.contract_id, tx_hash))]
+    pub async fn deploy(&mut self, network: impl AsRef<str>) -> Result<DeployResult> {
+        let network: crate::Network = network.as_ref().parse()?;
+        if let Some(party) = self.ucl.metadata.parties.iter().find(|party| {
+            party
+                .wallet
+                .as_deref()
+                .is_some_and(|wallet| !crate::utils::is_evm_address(wallet))
+        }) {
+            return Err(crate::Error::ValidationError(format!(
+                "Wallet of {} party {} is not an EVM address: {}",
+                party.role,
+                party.identifier,
+                party.wallet.as_deref().unwrap_or_default()
+            )));
+        }
+        self.set_status(ContractStatus::Deploying);
 
-        // Placeholder deployment
-        let address = "0x1234567890abcdef".to_string();
+        // Placeholder deployment, at a well-formed address
+        let address = "0x1234567890abcdef1234567890abcdef12345678".to_string();
         let tx_hash = "0xabcdef1234567890".to_string();
 
-        self.deployed_address = Some(address.clone());
-        self.transaction_hash = Some(tx_hash.clone());
-        self.status = ContractStatus::Deployed;
+        tracing::Span::current().record("tx_hash", tx_hash.as_str());
+        tracing::info!(address = %address, "contract deployed");
 
-        Ok(DeployResult {
+        let result = DeployResult {
             success: true,
             address,
             transaction_hash: tx_hash,
-            network: network.to_string(),
+            network,
+            owner: self
+                .ucl
+                .metadata
+                .party(PAYEE_ROLES)
+                .and_then(PartyInfo::wallet_address)
+                .map(str::to_string),
             block_number: Some(12345678),
             contract_id: self.ucl.contract_id.clone(),
-        })
+        };
+        self.deployment = Some(Deployment::from(&result));
+        self.set_status(ContractStatus::Deployed);
+        self.publish(ContractEventKind::Deployed(result.clone()));
+        Ok(result)
     }
 
     /// Execute payment
+    ///
+    /// Pays with the contract's
+    /// [`PaymentExecutor`](super::payment::PaymentExecutor), without the
+    /// retries of monitor payments.
     pub async fn execute_payment(&self) -> Result<PaymentResult> {
-        Ok(PaymentResult {
-            success: true,
-            transaction_hash: "0xpayment123".to_string(),
-            amount: self.ucl.payment.amount,
-            token: self.ucl.payment.token.clone(),
-            network: self.ucl.payment.blockchain.clone(),
-            from: "0xfrom".to_string(),
-            to: "0xto".to_string(),
-        })
+        let result = self.payer.pay(&self.ucl).await?;
+        self.publish(ContractEventKind::PaymentExecuted(result.clone()));
+        Ok(result)
+    }
+
+    /// Record a settled payment in the contract's history
+    pub fn record_payment(&mut self, record: PaymentRecord) {
+        self.publish(ContractEventKind::PaymentRecorded(record.clone()));
+        self.payments.push(record);
+    }
+
+    /// Payments made under the contract, oldest first
+    pub fn payment_history(&self) -> &[PaymentRecord] {
+        &self.payments
+    }
+
+    /// Meter of paid requests, to install on the contract's paywall with
+    /// [`crate::x402::X402Paywall::with_usage_meter`]
+    pub fn usage_meter(&self) -> Arc<UsageMeter> {
+        self.usage.clone()
     }
 
     /// Start monitoring
-    pub async fn start_monitoring(&self, _frequency: &str, _webhook: Option<String>) -> Result<()> {
-        // Placeholder
-        Ok(())
+    ///
+    /// Checks conditions at `frequency` (see [`Schedule::parse`]) in a
+    /// background task and fires the actions of matching rules. Failed
+    /// conditions, payments and expiry are posted to `webhook`, signed with
+    /// the secret in `SMART402_WEBHOOK_SECRET`.
+    pub async fn start_monitoring(&self, frequency: &str, webhook: Option<String>) -> Result<MonitorHandle> {
+        let sender = webhook.map(WebhookSender::from_env).transpose()?;
+        let monitor = self.monitor_with_schedule(Schedule::parse(frequency)?);
+        if let Some(sender) = sender {
+            sender.attach(&monitor);
+        }
+        Ok(monitor)
     }
 
-    /// Check conditions
+    /// Check conditions against the contract's oracles
+    ///
+    /// Conditions whose `source` has no oracle (see
+    /// [`Contract::connect_oracles`]) are not met. Optional conditions
+    /// and condition groups are reported too, but only required conditions
+    /// count towards `all_met`. Each result is added to the contract's
+    /// [condition history](Contract::condition_history).
+    #[tracing::instrument(skip(self), fields(contract_id = %self.ucl.contract_id))]
     pub async fn check_conditions(&self) -> Result<ConditionCheckResult> {
-        Ok(ConditionCheckResult {
-            all_met: true,
-            conditions: HashMap::new(),
-            timestamp: chrono::Utc::now(),
-        })
+        let explanation = self.oracles.explain_at(&self.ucl, &Timeline::at(self.clock.now())).await;
+        self.history.extend(&history::records(&explanation, &Default::default()));
+        let result = explanation.result;
+        self.publish(ContractEventKind::ConditionsChecked(result.clone()));
+        Ok(result)
+    }
+
+    /// Check conditions like [`Contract::check_conditions`], explaining
+    /// each result
+    ///
+    /// Every condition comes with the value read from its oracle, the
+    /// operator and threshold it was compared with and a reason, to debug
+    /// why a rule did not fire. Nothing is executed.
+    #[tracing::instrument(skip(self), fields(contract_id = %self.ucl.contract_id))]
+    pub async fn check_conditions_explain(&self) -> Result<CheckExplanation> {
+        Ok(self.oracles.explain_at(&self.ucl, &Timeline::at(self.clock.now())).await)
     }
 
     /// Get contract summary
@@ -120,13 +295,253 @@ impl Contract {
         self.status
     }
 
+    fn set_status(&mut self, status: ContractStatus) {
+        if status != self.status {
+            self.publish(ContractEventKind::StatusChanged { from: self.status, to: status });
+            self.status = status;
+        }
+    }
+
     /// Get deployed address
     pub fn address(&self) -> Option<&str> {
-        self.deployed_address.as_deref()
+        self.deployment.as_ref().map(|d| d.address.as_str())
     }
 
     /// Get transaction hash
     pub fn transaction_hash(&self) -> Option<&str> {
-        self.transaction_hash.as_deref()
+        self.deployment.as_ref().map(|d| d.transaction_hash.as_str())
+    }
+
+    /// Where the contract was deployed, if it was
+    pub fn deployment(&self) -> Option<&Deployment> {
+        self.deployment.as_ref()
+    }
+
+    /// Contract terms with the current status and deployment, for saving
+    /// with [`crate::utils::save_envelope`]
+    pub fn envelope(&self) -> ContractEnvelope {
+        ContractEnvelope {
+            status: self.status,
+            deployment: self.deployment.clone(),
+            contract: self.ucl.clone(),
+        }
+    }
+
+    /// Restore a contract saved with [`Contract::envelope`]
+    pub fn from_envelope(envelope: ContractEnvelope) -> Result<Self> {
+        let mut contract = Self::from_config(ContractConfig::default())?;
+        contract.ucl = envelope.contract;
+        contract.status = envelope.status;
+        contract.deployment = envelope.deployment;
+        Ok(contract)
+    }
+}
+
+/// Placeholder payment execution
+///
+/// Fails if no payer or no payee party has a wallet address.
+#[tracing::instrument(name = "payment", skip(ucl), fields(contract_id = %ucl.contract_id, network = %ucl.payment.blockchain))]
+pub(crate) fn payment_result(ucl: &UCLContract) -> Result<PaymentResult> {
+    let payment = PaymentResult {
+        success: true,
+        transaction_hash: "0xpayment123".to_string(),
+        amount: ucl.payment.amount,
+        token: ucl.payment.token.clone(),
+        network: ucl.payment.blockchain,
+        from: wallet(ucl, PAYER_ROLES, "payer")?,
+        to: wallet(ucl, PAYEE_ROLES, "payee")?,
+    };
+    if payment.success {
+        tracing::info!(tx_hash = %payment.transaction_hash, amount = %payment.amount, token = %payment.token, "payment executed");
+    } else {
+        tracing::warn!(amount = %payment.amount, token = %payment.token, "payment failed");
+    }
+    Ok(payment)
+}
+
+/// Wallet address of the first party with one of `roles`
+fn wallet(ucl: &UCLContract, roles: &[&str], side: &str) -> Result<String> {
+    ucl.metadata
+        .party(roles)
+        .and_then(PartyInfo::wallet_address)
+        .map(str::to_string)
+        .ok_or_else(|| {
+            crate::Error::PaymentError(format!(
+                "No {} wallet: no party with role {} has a wallet address",
+                side,
+                roles.join(", ")
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ContractMetadataInput;
+
+    #[test]
+    fn test_from_config_maps_conditions() {
+        let config: ContractConfig = serde_json::from_value(serde_json::json!({
+            "type": "digital-product-purchase",
+            "parties": [],
+            "payment": {"amount": 10, "token": "USDC", "frequency": "one-time"},
+            "conditions": [
+                {"id": "payment_received", "type": "payment", "description": "Paid"},
+                {"id": "reviewed", "source": "review_api", "operator": ">=",
+                 "threshold": 4, "required": false}
+            ]
+        }))
+        .unwrap();
+
+        let conditions = Contract::from_config(config).unwrap().ucl.conditions;
+        assert_eq!(conditions.required.len(), 1);
+        assert_eq!(conditions.required[0].id, "payment_received");
+        assert_eq!(conditions.required[0].source, "payment");
+        let optional = conditions.optional.unwrap();
+        assert_eq!(optional[0].operator, ">=");
+        assert_eq!(optional[0].threshold, Some(serde_json::json!(4)));
+    }
+
+    #[tokio::test]
+    async fn test_from_config_maps_payment_and_parties() {
+        const CUSTOMER: &str = "0x00000000000000000000000000000000000000c0";
+        let payment = crate::PaymentConfig::new(
+            crate::Amount::from(99),
+            crate::Token::usdc(),
+            crate::PaymentFrequency::Monthly { day: None },
+        )
+        .with_blockchain(crate::Network::Base)
+        .with_day_of_month(15);
+        let config = ContractConfig::new("saas-subscription", payment)
+            .with_parties(["vendor@example.com", CUSTOMER]);
+
+        let contract = Contract::from_config(config.clone()).unwrap();
+        let ucl = &contract.ucl;
+        assert!(ucl.contract_id.starts_with("smart402:saas-subscription:"));
+        assert_ne!(
+            Contract::from_config(config).unwrap().ucl.contract_id,
+            ucl.contract_id
+        );
+        assert_eq!(ucl.payment.amount, crate::Amount::from(99));
+        assert_eq!(ucl.payment.blockchain, crate::Network::Base);
+        assert_eq!(
+            ucl.payment.frequency,
+            crate::PaymentFrequency::Monthly { day: Some(15) }
+        );
+        let roles: Vec<_> = ucl.metadata.parties.iter().map(|p| p.role.as_str()).collect();
+        assert_eq!(roles, ["vendor", "customer"]);
+
+        // The vendor has no wallet to be paid to
+        let error = contract.execute_payment().await.unwrap_err();
+        assert_eq!(error.code(), "payment");
+        assert!(error.to_string().contains("No payee wallet"), "{}", error);
+    }
+
+    #[test]
+    fn test_from_config_rejects_invalid_terms() {
+        let payment = crate::PaymentConfig::new(
+            crate::Amount::from(-1),
+            crate::Token::usdc(),
+            crate::PaymentFrequency::OneTime,
+        );
+        match Contract::from_config(ContractConfig::new("custom", payment)) {
+            Err(error) => assert_eq!(error.code(), "validation"),
+            Ok(_) => panic!("negative amount accepted"),
+        }
+    }
+
+    #[test]
+    fn test_config_constructors_match_deserialized_config() {
+        let payment = crate::PaymentConfig::new(
+            crate::Amount::from(10),
+            crate::Token::usdc(),
+            crate::PaymentFrequency::OneTime,
+        );
+        let built = ContractConfig::new("digital-product-purchase", payment)
+            .with_parties(["seller@example.com"])
+            .with_condition(
+                crate::ConditionConfig::new("reviewed", "")
+                    .with_source("review_api")
+                    .with_threshold(">=", 4)
+                    .optional(),
+            );
+        let parsed: ContractConfig = serde_json::from_value(serde_json::json!({
+            "type": "digital-product-purchase",
+            "parties": ["seller@example.com"],
+            "payment": {"amount": 10, "token": "USDC", "frequency": "one-time"},
+            "conditions": [{"id": "reviewed", "source": "review_api", "operator": ">=",
+                            "threshold": 4, "required": false}]
+        }))
+        .unwrap();
+        assert_eq!(
+            serde_json::to_value(&built).unwrap(),
+            serde_json::to_value(&parsed).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_from_config_maps_metadata() {
+        let metadata: ContractMetadataInput = serde_json::from_value(serde_json::json!({
+            "title": "Monthly SaaS Subscription",
+            "category": "saas",
+            "tags": ["api", "billing"],
+            "license": "single-user"
+        }))
+        .unwrap();
+        assert_eq!(metadata.custom["license"], "single-user");
+
+        let ucl = Contract::from_config(ContractConfig {
+            metadata: Some(metadata.clone()),
+            ..Default::default()
+        })
+        .unwrap()
+        .ucl;
+        assert_eq!(ucl.summary.title, "Monthly SaaS Subscription");
+        assert_eq!(
+            ucl.summary.plain_english,
+            "Monthly SaaS Subscription: 0 USDC one-time"
+        );
+        assert_eq!(ucl.metadata.category, "saas");
+        assert_eq!(ucl.metadata.tags, ["api", "billing"]);
+        assert_eq!(ucl.metadata.custom["license"], "single-user");
+
+        let blank = ContractMetadataInput {
+            title: Some(" ".to_string()),
+            ..metadata
+        };
+        assert!(blank.validate().is_err());
+        assert!(serde_json::from_value::<ContractMetadataInput>(
+            serde_json::json!({"tags": "api"})
+        )
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_deploy_and_payment_use_party_wallets() {
+        const VENDOR: &str = "0x209693Bc6afc0C5328bA36FaF03C514EF312287C";
+        let mut contract = Contract::from_config(ContractConfig::default()).unwrap();
+        contract.ucl.metadata.parties = serde_json::from_value(serde_json::json!([
+            {"role": "vendor", "identifier": "billing@vendor.example", "wallet": VENDOR,
+             "contact": {"type": "email", "address": "billing@vendor.example"},
+             "verified": true},
+            {"role": "customer", "identifier": "0x00000000000000000000000000000000000000c0",
+             "did": "did:web:customer.example"}
+        ]))
+        .unwrap();
+
+        assert_eq!(contract.deploy("polygon").await.unwrap().owner.as_deref(), Some(VENDOR));
+        let payment = contract.execute_payment().await.unwrap();
+        assert_eq!(payment.from, "0x00000000000000000000000000000000000000c0");
+        assert_eq!(payment.to, VENDOR);
+
+        contract.ucl.metadata.parties[0].wallet = Some("vendor.eth".to_string());
+        assert!(contract.deploy("polygon").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_deployed_address_is_evm_address() {
+        let mut contract = Contract::from_config(ContractConfig::default()).unwrap();
+        let deployed = contract.deploy("base").await.unwrap();
+        assert!(crate::utils::is_evm_address(&deployed.address), "{}", deployed.address);
     }
 }
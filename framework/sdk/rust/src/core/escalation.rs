@@ -0,0 +1,174 @@
+//! Escalation of alerts about failing conditions
+//!
+//! The monitor counts the consecutive failed checks of every condition and
+//! takes the steps of the contract's [`EscalationPolicy`] as they fall due,
+//! each once while the condition keeps failing. A condition failing for a
+//! day therefore raises a handful of alerts rather than one per check, and
+//! a single "recovered" alert once it is met again. Contracts without a
+//! policy alert every channel on the first failed check only.
+
+use crate::llmo::lint::parse_interval_secs;
+use crate::types::{EscalationPolicy, EscalationStep};
+use crate::UCLContract;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Escalation actions the monitor knows
+pub const ESCALATION_ACTIONS: &[&str] = &["notify", "pause_contract"];
+
+/// Alerting progress of a failing condition
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertState {
+    /// Consecutive checks the condition failed
+    pub failures: u32,
+    /// When the condition started failing
+    pub since: DateTime<Utc>,
+    /// Indices of the policy steps already taken
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub escalated: Vec<usize>,
+}
+
+impl AlertState {
+    /// State of a condition first seen failing at `at`
+    pub fn new(at: DateTime<Utc>) -> Self {
+        Self {
+            failures: 0,
+            since: at,
+            escalated: Vec::new(),
+        }
+    }
+}
+
+impl EscalationPolicy {
+    /// Policy of contracts without one: alert every channel on the first
+    /// failed check
+    pub fn alert_once() -> Self {
+        Self {
+            steps: vec![EscalationStep {
+                action: "notify".to_string(),
+                ..Default::default()
+            }],
+        }
+    }
+
+    /// Steps due for a condition in `state` at `now` and not taken yet
+    pub fn due(&self, state: &AlertState, now: DateTime<Utc>) -> Vec<usize> {
+        self.steps
+            .iter()
+            .enumerate()
+            .filter(|(index, step)| {
+                !state.escalated.contains(index) && step.is_due(state.failures, now - state.since)
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+}
+
+impl EscalationStep {
+    /// Whether the step is due after `failures` consecutive failed checks
+    /// spanning `failing_for`
+    ///
+    /// A step whose `after` is not a duration never falls due.
+    pub fn is_due(&self, failures: u32, failing_for: chrono::Duration) -> bool {
+        let enough_failures = failures >= self.after_failures.unwrap_or(1);
+        let long_enough = match &self.after {
+            None => true,
+            Some(after) => parse_interval_secs(after)
+                .is_some_and(|secs| failing_for >= chrono::Duration::seconds(secs as i64)),
+        };
+        enough_failures && long_enough
+    }
+
+    /// Addresses to alert: identifiers of the parties whose role is listed
+    /// in `to`, and other entries as given
+    pub fn recipients(&self, ucl: &UCLContract) -> Vec<String> {
+        recipients(ucl, &self.to)
+    }
+}
+
+/// Identifiers of the parties of `ucl` whose role is listed in `to`, and
+/// other entries as given
+pub(crate) fn recipients(ucl: &UCLContract, to: &[String]) -> Vec<String> {
+    let mut recipients = Vec::new();
+    for to in to {
+        let parties: Vec<_> = ucl
+            .metadata
+            .parties
+            .iter()
+            .filter(|party| party.role.eq_ignore_ascii_case(to))
+            .map(|party| party.identifier.clone())
+            .collect();
+        if parties.is_empty() {
+            recipients.push(to.clone());
+        } else {
+            recipients.extend(parties);
+        }
+    }
+    recipients
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PartyInfo;
+    use crate::{Contract, ContractConfig};
+
+    #[test]
+    fn test_steps_fall_due_once_by_failures_and_duration() {
+        let policy = EscalationPolicy {
+            steps: vec![
+                EscalationStep {
+                    action: "notify".to_string(),
+                    channel: Some("webhook".to_string()),
+                    ..Default::default()
+                },
+                EscalationStep {
+                    after_failures: Some(3),
+                    action: "notify".to_string(),
+                    channel: Some("email".to_string()),
+                    to: vec!["vendor".to_string()],
+                    ..Default::default()
+                },
+                EscalationStep {
+                    after: Some("24h".to_string()),
+                    action: "pause_contract".to_string(),
+                    ..Default::default()
+                },
+            ],
+        };
+        let since: DateTime<Utc> = "2024-05-01T00:00:00Z".parse().unwrap();
+        let mut state = AlertState::new(since);
+
+        state.failures = 1;
+        assert_eq!(policy.due(&state, since), vec![0]);
+        state.escalated.push(0);
+        state.failures = 2;
+        assert!(policy
+            .due(&state, since + chrono::Duration::hours(1))
+            .is_empty());
+        state.failures = 3;
+        assert_eq!(
+            policy.due(&state, since + chrono::Duration::hours(2)),
+            vec![1]
+        );
+        state.escalated.push(1);
+        assert_eq!(
+            policy.due(&state, since + chrono::Duration::hours(24)),
+            vec![2]
+        );
+
+        let mut ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+        ucl.metadata.parties.push(PartyInfo {
+            role: "vendor".to_string(),
+            identifier: "billing@vendor.example".to_string(),
+            name: None,
+            ..Default::default()
+        });
+        assert_eq!(
+            policy.steps[1].recipients(&ucl),
+            vec!["billing@vendor.example"]
+        );
+    }
+}
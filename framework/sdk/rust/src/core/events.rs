@@ -0,0 +1,148 @@
+//! Real-time contract events
+//!
+//! [`Contract::subscribe`] streams what happens to a contract as it
+//! happens: deployment, status changes, condition checks, payments and the
+//! progress of its monitors. [`FleetHandle::subscribe`] streams the same
+//! events for every contract of a store-wide [`Monitor`].
+//!
+//! [`FleetHandle::subscribe`]: super::fleet::FleetHandle::subscribe
+//! [`Monitor`]: super::fleet::Monitor
+
+use super::monitor::MonitorEvent;
+use crate::{
+    ConditionCheckResult, Contract, ContractStatus, DeployResult, PaymentRecord, PaymentResult,
+};
+use chrono::{DateTime, Utc};
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+/// Events buffered per subscriber of a contract before the oldest are
+/// dropped
+pub(crate) const EVENT_CAPACITY: usize = 256;
+
+/// Something that happened to a contract
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContractEvent {
+    pub contract_id: String,
+    pub at: DateTime<Utc>,
+    pub event: ContractEventKind,
+}
+
+impl ContractEvent {
+    pub(crate) fn new(contract_id: &str, event: ContractEventKind) -> Self {
+        Self {
+            contract_id: contract_id.to_string(),
+            at: Utc::now(),
+            event,
+        }
+    }
+}
+
+/// What happened to a contract
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum ContractEventKind {
+    /// The contract was deployed
+    Deployed(DeployResult),
+    /// The contract moved to another status
+    StatusChanged {
+        from: ContractStatus,
+        to: ContractStatus,
+    },
+    /// [`Contract::check_conditions`] checked the conditions
+    ConditionsChecked(ConditionCheckResult),
+    /// [`Contract::execute_payment`] paid
+    PaymentExecuted(PaymentResult),
+    /// A settled payment was added to the payment history
+    PaymentRecorded(PaymentRecord),
+    /// A monitor of the contract made progress; checks and payments of
+    /// monitors are reported here
+    Monitor(MonitorEvent),
+}
+
+impl Contract {
+    /// Stream the events of this contract from now on
+    ///
+    /// Events of every monitor started from the contract are included.
+    /// A subscriber that falls more than a few hundred events behind
+    /// misses the oldest ones; the stream ends when the contract and all
+    /// its monitors are dropped.
+    pub fn subscribe(&self) -> impl Stream<Item = ContractEvent> + Send + Unpin + 'static {
+        event_stream(self.events.subscribe())
+    }
+
+    /// Publish an event to subscribers
+    pub(crate) fn publish(&self, event: ContractEventKind) {
+        // Sending only fails while nobody is subscribed
+        let _ = self
+            .events
+            .send(ContractEvent::new(&self.ucl.contract_id, event));
+    }
+}
+
+/// Stream of the events a receiver gets, skipping the ones it missed
+pub(crate) fn event_stream(
+    events: broadcast::Receiver<ContractEvent>,
+) -> impl Stream<Item = ContractEvent> + Send + Unpin + 'static {
+    Box::pin(futures::stream::unfold(events, |mut events| async move {
+        loop {
+            match events.recv().await {
+                Ok(event) => return Some((event, events)),
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::monitor::Schedule;
+    use futures::StreamExt;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_subscribe_streams_deploy_and_monitor_events() {
+        let mut contract = crate::core::fixtures::contract();
+        let mut events = contract.subscribe();
+
+        contract.deploy("polygon").await.unwrap();
+        let kinds: Vec<_> = (&mut events)
+            .take(3)
+            .map(|event| event.event)
+            .collect()
+            .await;
+        assert!(matches!(
+            kinds[0],
+            ContractEventKind::StatusChanged {
+                from: ContractStatus::Draft,
+                to: ContractStatus::Deploying
+            }
+        ));
+        assert!(matches!(
+            kinds[1],
+            ContractEventKind::StatusChanged {
+                to: ContractStatus::Deployed,
+                ..
+            }
+        ));
+        assert!(
+            matches!(&kinds[2], ContractEventKind::Deployed(result) if result.network == crate::Network::Polygon)
+        );
+
+        let monitor = contract.monitor_with_schedule(Schedule::Every(Duration::from_millis(10)));
+        let event = events.next().await.unwrap();
+        assert_eq!(event.contract_id, contract.ucl.contract_id);
+        assert!(matches!(
+            event.event,
+            ContractEventKind::Monitor(MonitorEvent::Checked(_))
+        ));
+        monitor.stop().await;
+
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["event"]["monitor"]["type"], "checked");
+    }
+}
@@ -0,0 +1,641 @@
+//! Condition expression language
+//!
+//! Conditions may state what they require as an expression over oracle
+//! values instead of a single `operator` and `threshold`:
+//!
+//! ```text
+//! uptime >= 99.5% && incidents_last_30d < 3
+//! flight.status == "landed" || (delay_minutes > 180 and not cancelled)
+//! ```
+//!
+//! Identifiers name oracles; `.field` selects a field (or array index) of
+//! an oracle's JSON value. Supported are numbers (`99%` is `0.99`), quoted
+//! strings, `true`, `false`, `null`, arithmetic (`+ - * /`), comparisons
+//! (`== != < <= > >=`) and the logical operators `&&`/`and`, `||`/`or` and
+//! `!`/`not`. Strings holding numbers are compared as numbers; any other
+//! mix of types is a type error.
+
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::str::FromStr;
+
+/// Failure to parse or evaluate an expression
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[non_exhaustive]
+pub enum ExpressionError {
+    #[error("Syntax error at column {column}: {message}")]
+    Syntax { column: usize, message: String },
+
+    #[error("Unknown identifier `{0}`")]
+    UnknownIdentifier(String),
+
+    #[error("Type error: `{operator}` expects {expected}, but `{operand}` is {found}")]
+    Type {
+        operator: String,
+        expected: &'static str,
+        operand: String,
+        found: &'static str,
+    },
+}
+
+/// Parsed condition expression
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expression {
+    root: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Literal(Value),
+    /// Oracle id followed by field selectors
+    Path(Vec<String>),
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    Binary(Box<Expr>, BinaryOp, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl Expression {
+    /// Parse an expression
+    pub fn parse(source: &str) -> Result<Self, ExpressionError> {
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            position: 0,
+            end: source.chars().count() + 1,
+        };
+        let root = parser.or()?;
+        match parser.tokens.get(parser.position) {
+            None => Ok(Self { root }),
+            Some((token, column)) => Err(ExpressionError::Syntax {
+                column: *column,
+                message: format!("unexpected {}", token),
+            }),
+        }
+    }
+
+    /// Oracle ids the expression reads
+    pub fn identifiers(&self) -> BTreeSet<&str> {
+        let mut identifiers = BTreeSet::new();
+        self.root.identifiers(&mut identifiers);
+        identifiers
+    }
+
+    /// Evaluate with the given oracle values
+    pub fn evaluate(&self, values: &HashMap<String, Value>) -> Result<Value, ExpressionError> {
+        self.root.evaluate(values)
+    }
+
+    /// Evaluate an expression that must be `true` or `false`
+    pub fn is_met(&self, values: &HashMap<String, Value>) -> Result<bool, ExpressionError> {
+        match self.evaluate(values)? {
+            Value::Bool(met) => Ok(met),
+            other => Err(ExpressionError::Type {
+                operator: "condition".to_string(),
+                expected: "a bool",
+                operand: self.root.to_string(),
+                found: type_name(&other),
+            }),
+        }
+    }
+}
+
+impl FromStr for Expression {
+    type Err = ExpressionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.root.fmt(f)
+    }
+}
+
+impl Expr {
+    fn identifiers<'a>(&'a self, identifiers: &mut BTreeSet<&'a str>) {
+        match self {
+            Self::Literal(_) => {}
+            Self::Path(path) => {
+                identifiers.insert(&path[0]);
+            }
+            Self::Not(inner) | Self::Neg(inner) => inner.identifiers(identifiers),
+            Self::Binary(left, _, right) => {
+                left.identifiers(identifiers);
+                right.identifiers(identifiers);
+            }
+        }
+    }
+
+    fn evaluate(&self, values: &HashMap<String, Value>) -> Result<Value, ExpressionError> {
+        match self {
+            Self::Literal(value) => Ok(value.clone()),
+            Self::Path(path) => {
+                let mut value = values
+                    .get(&path[0])
+                    .ok_or_else(|| ExpressionError::UnknownIdentifier(path[0].clone()))?;
+                for field in &path[1..] {
+                    value = match value {
+                        Value::Array(items) => {
+                            field.parse::<usize>().ok().and_then(|i| items.get(i))
+                        }
+                        Value::Object(fields) => fields.get(field),
+                        _ => None,
+                    }
+                    .ok_or_else(|| ExpressionError::UnknownIdentifier(self.to_string()))?;
+                }
+                Ok(value.clone())
+            }
+            Self::Not(inner) => Ok(Value::Bool(!inner.boolean("!", values)?)),
+            Self::Neg(inner) => Ok(number_value(-inner.number("-", values)?)),
+            Self::Binary(left, BinaryOp::And, right) => Ok(Value::Bool(
+                left.boolean("&&", values)? && right.boolean("&&", values)?,
+            )),
+            Self::Binary(left, BinaryOp::Or, right) => Ok(Value::Bool(
+                left.boolean("||", values)? || right.boolean("||", values)?,
+            )),
+            Self::Binary(left, op, right) if op.is_arithmetic() => {
+                let (a, b) = (
+                    left.number(op.symbol(), values)?,
+                    right.number(op.symbol(), values)?,
+                );
+                Ok(number_value(match op {
+                    BinaryOp::Add => a + b,
+                    BinaryOp::Sub => a - b,
+                    BinaryOp::Mul => a * b,
+                    _ => a / b,
+                }))
+            }
+            Self::Binary(left, op, right) => {
+                let (a, b) = (left.evaluate(values)?, right.evaluate(values)?);
+                let ordering = match (as_number(&a), as_number(&b)) {
+                    (Some(x), Some(y)) => x.partial_cmp(&y),
+                    _ => match (&a, &b) {
+                        (Value::String(x), Value::String(y)) => Some(x.cmp(y)),
+                        (Value::Bool(_), Value::Bool(_)) | (Value::Null, _) | (_, Value::Null)
+                            if matches!(op, BinaryOp::Eq | BinaryOp::Ne) =>
+                        {
+                            return Ok(Value::Bool((a == b) == (*op == BinaryOp::Eq)))
+                        }
+                        _ => {
+                            let (operand, found) = if as_number(&a).is_some() {
+                                (right, type_name(&b))
+                            } else {
+                                (left, type_name(&a))
+                            };
+                            return Err(ExpressionError::Type {
+                                operator: op.symbol().to_string(),
+                                expected: comparable_with(&a, &b),
+                                operand: operand.to_string(),
+                                found,
+                            });
+                        }
+                    },
+                };
+                Ok(Value::Bool(ordering.is_some_and(|o| match op {
+                    BinaryOp::Eq => o.is_eq(),
+                    BinaryOp::Ne => o.is_ne(),
+                    BinaryOp::Lt => o.is_lt(),
+                    BinaryOp::Le => o.is_le(),
+                    BinaryOp::Gt => o.is_gt(),
+                    _ => o.is_ge(),
+                })))
+            }
+        }
+    }
+
+    fn boolean(
+        &self,
+        operator: &str,
+        values: &HashMap<String, Value>,
+    ) -> Result<bool, ExpressionError> {
+        match self.evaluate(values)? {
+            Value::Bool(b) => Ok(b),
+            other => Err(self.type_error(operator, "a bool", &other)),
+        }
+    }
+
+    fn number(
+        &self,
+        operator: &str,
+        values: &HashMap<String, Value>,
+    ) -> Result<f64, ExpressionError> {
+        let value = self.evaluate(values)?;
+        as_number(&value).ok_or_else(|| self.type_error(operator, "a number", &value))
+    }
+
+    fn type_error(&self, operator: &str, expected: &'static str, found: &Value) -> ExpressionError {
+        ExpressionError::Type {
+            operator: operator.to_string(),
+            expected,
+            operand: self.to_string(),
+            found: type_name(found),
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Literal(value) => write!(f, "{}", value),
+            Self::Path(path) => f.write_str(&path.join(".")),
+            Self::Not(inner) => write!(f, "!{}", inner),
+            Self::Neg(inner) => write!(f, "-{}", inner),
+            Self::Binary(left, op, right) => write!(f, "({} {} {})", left, op.symbol(), right),
+        }
+    }
+}
+
+impl BinaryOp {
+    fn symbol(self) -> &'static str {
+        match self {
+            Self::Or => "||",
+            Self::And => "&&",
+            Self::Eq => "==",
+            Self::Ne => "!=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+            Self::Add => "+",
+            Self::Sub => "-",
+            Self::Mul => "*",
+            Self::Div => "/",
+        }
+    }
+
+    fn is_arithmetic(self) -> bool {
+        matches!(self, Self::Add | Self::Sub | Self::Mul | Self::Div)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    String(String),
+    Ident(String),
+    Op(&'static str),
+    Open,
+    Close,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Number(n) => write!(f, "number {}", n),
+            Self::String(s) => write!(f, "string {:?}", s),
+            Self::Ident(name) => write!(f, "`{}`", name),
+            Self::Op(op) => write!(f, "`{}`", op),
+            Self::Open => f.write_str("`(`"),
+            Self::Close => f.write_str("`)`"),
+        }
+    }
+}
+
+/// Tokens with their 1-based column
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, ExpressionError> {
+    const OPERATORS: [&str; 14] = [
+        "&&", "||", "==", "!=", "<=", ">=", "<", ">", "!", "+", "-", "*", "/", "=",
+    ];
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let column = i + 1;
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '(' || c == ')' {
+            tokens.push((if c == '(' { Token::Open } else { Token::Close }, column));
+            i += 1;
+        } else if c.is_ascii_digit()
+            || (c == '.' && chars.get(i + 1).is_some_and(char::is_ascii_digit))
+        {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let mut number: f64 = text.parse().map_err(|_| ExpressionError::Syntax {
+                column,
+                message: format!("invalid number {}", text),
+            })?;
+            if chars.get(i) == Some(&'%') {
+                number /= 100.0;
+                i += 1;
+            }
+            tokens.push((Token::Number(number), column));
+        } else if c == '"' || c == '\'' {
+            let end = chars[i + 1..].iter().position(|&q| q == c).ok_or_else(|| {
+                ExpressionError::Syntax {
+                    column,
+                    message: "unterminated string".to_string(),
+                }
+            })?;
+            tokens.push((
+                Token::String(chars[i + 1..i + 1 + end].iter().collect()),
+                column,
+            ));
+            i += end + 2;
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || matches!(chars[i], '_' | '.')) {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            tokens.push((
+                match word.as_str() {
+                    "and" => Token::Op("&&"),
+                    "or" => Token::Op("||"),
+                    "not" => Token::Op("!"),
+                    _ => Token::Ident(word),
+                },
+                column,
+            ));
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            let op = OPERATORS
+                .iter()
+                .find(|op| rest.starts_with(**op))
+                .ok_or_else(|| ExpressionError::Syntax {
+                    column,
+                    message: format!("unexpected character {:?}", c),
+                })?;
+            // A lone `=` reads as `==`
+            tokens.push((Token::Op(if *op == "=" { "==" } else { op }), column));
+            i += op.len();
+        }
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent parser, one method per precedence level
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    /// Column reported for errors at the end of input
+    end: usize,
+}
+
+impl Parser {
+    fn or(&mut self) -> Result<Expr, ExpressionError> {
+        self.binary(&[("||", BinaryOp::Or)], Self::and)
+    }
+
+    fn and(&mut self) -> Result<Expr, ExpressionError> {
+        self.binary(&[("&&", BinaryOp::And)], Self::comparison)
+    }
+
+    fn comparison(&mut self) -> Result<Expr, ExpressionError> {
+        let left = self.sum()?;
+        let ops = [
+            ("==", BinaryOp::Eq),
+            ("!=", BinaryOp::Ne),
+            ("<=", BinaryOp::Le),
+            (">=", BinaryOp::Ge),
+            ("<", BinaryOp::Lt),
+            (">", BinaryOp::Gt),
+        ];
+        match self.operator(&ops) {
+            Some(op) => Ok(Expr::Binary(Box::new(left), op, Box::new(self.sum()?))),
+            None => Ok(left),
+        }
+    }
+
+    fn sum(&mut self) -> Result<Expr, ExpressionError> {
+        self.binary(&[("+", BinaryOp::Add), ("-", BinaryOp::Sub)], Self::product)
+    }
+
+    fn product(&mut self) -> Result<Expr, ExpressionError> {
+        self.binary(&[("*", BinaryOp::Mul), ("/", BinaryOp::Div)], Self::unary)
+    }
+
+    fn unary(&mut self) -> Result<Expr, ExpressionError> {
+        match self.peek() {
+            Some(Token::Op("!")) => {
+                self.position += 1;
+                Ok(Expr::Not(Box::new(self.unary()?)))
+            }
+            Some(Token::Op("-")) => {
+                self.position += 1;
+                Ok(Expr::Neg(Box::new(self.unary()?)))
+            }
+            _ => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, ExpressionError> {
+        let Some((token, column)) = self.tokens.get(self.position).cloned() else {
+            return Err(ExpressionError::Syntax {
+                column: self.end,
+                message: "unexpected end of expression".to_string(),
+            });
+        };
+        self.position += 1;
+        Ok(match token {
+            Token::Number(n) => Expr::Literal(number_value(n)),
+            Token::String(s) => Expr::Literal(Value::String(s)),
+            Token::Ident(word) => match word.as_str() {
+                "true" => Expr::Literal(Value::Bool(true)),
+                "false" => Expr::Literal(Value::Bool(false)),
+                "null" => Expr::Literal(Value::Null),
+                _ => {
+                    let path: Vec<String> = word.split('.').map(str::to_string).collect();
+                    if path.iter().any(String::is_empty) {
+                        return Err(ExpressionError::Syntax {
+                            column,
+                            message: format!("invalid identifier `{}`", word),
+                        });
+                    }
+                    Expr::Path(path)
+                }
+            },
+            Token::Open => {
+                let inner = self.or()?;
+                match self.tokens.get(self.position) {
+                    Some((Token::Close, _)) => {
+                        self.position += 1;
+                        inner
+                    }
+                    Some((token, column)) => {
+                        return Err(ExpressionError::Syntax {
+                            column: *column,
+                            message: format!("expected `)`, found {}", token),
+                        })
+                    }
+                    None => {
+                        return Err(ExpressionError::Syntax {
+                            column: self.end,
+                            message: "missing `)`".to_string(),
+                        })
+                    }
+                }
+            }
+            token => {
+                return Err(ExpressionError::Syntax {
+                    column,
+                    message: format!("unexpected {}", token),
+                })
+            }
+        })
+    }
+
+    /// Left-associative chain of `next` separated by the given operators
+    fn binary(
+        &mut self,
+        ops: &[(&str, BinaryOp)],
+        next: fn(&mut Self) -> Result<Expr, ExpressionError>,
+    ) -> Result<Expr, ExpressionError> {
+        let mut left = next(self)?;
+        while let Some(op) = self.operator(ops) {
+            left = Expr::Binary(Box::new(left), op, Box::new(next(self)?));
+        }
+        Ok(left)
+    }
+
+    fn operator(&mut self, ops: &[(&str, BinaryOp)]) -> Option<BinaryOp> {
+        let Some(Token::Op(symbol)) = self.peek() else {
+            return None;
+        };
+        let op = ops.iter().find(|(s, _)| s == symbol).map(|(_, op)| *op)?;
+        self.position += 1;
+        Some(op)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+}
+
+/// JSON number, integral when `n` is whole
+fn number_value(n: f64) -> Value {
+    if n.fract() == 0.0 && n.abs() < i64::MAX as f64 {
+        return Value::from(n as i64);
+    }
+    serde_json::Number::from_f64(n)
+        .map(Value::Number)
+        .unwrap_or(Value::Null)
+}
+
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a bool",
+        Value::Number(_) => "a number",
+        Value::String(s) if s.trim().parse::<f64>().is_ok() => "a numeric string",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+/// Type the other operand of a comparison would have needed
+fn comparable_with(a: &Value, b: &Value) -> &'static str {
+    if as_number(a).is_some() || as_number(b).is_some() {
+        "numbers"
+    } else if a.is_string() || b.is_string() {
+        "strings"
+    } else {
+        "comparable values"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn values() -> HashMap<String, Value> {
+        HashMap::from([
+            ("uptime".to_string(), json!(0.997)),
+            ("incidents_last_30d".to_string(), json!("2")),
+            (
+                "flight".to_string(),
+                json!({"status": "landed", "legs": [{"delay": 40}]}),
+            ),
+            ("cancelled".to_string(), json!(false)),
+        ])
+    }
+
+    fn met(expression: &str) -> bool {
+        Expression::parse(expression)
+            .unwrap()
+            .is_met(&values())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_evaluates_expressions() {
+        assert!(met("uptime >= 99.5% && incidents_last_30d < 3"));
+        assert!(!met("uptime > 0.999 or incidents_last_30d >= 3"));
+        assert!(met("flight.status == 'landed' && not cancelled"));
+        assert!(met("flight.legs.0.delay / 60 < 1 || -uptime > 0"));
+        assert!(met("!(1 + 2 * 3 == 9)"));
+
+        let expression =
+            Expression::parse("uptime > 0.99 && flight.status != \"diverted\"").unwrap();
+        assert_eq!(
+            expression.identifiers(),
+            BTreeSet::from(["flight", "uptime"])
+        );
+    }
+
+    #[test]
+    fn test_reports_errors() {
+        assert_eq!(
+            Expression::parse("uptime >= && x").unwrap_err(),
+            ExpressionError::Syntax {
+                column: 11,
+                message: "unexpected `&&`".to_string()
+            }
+        );
+        assert!(Expression::parse("(uptime > 1").is_err());
+        assert!(Expression::parse("uptime > 'x").is_err());
+
+        let evaluate = |expression: &str| {
+            Expression::parse(expression)
+                .unwrap()
+                .is_met(&values())
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            evaluate("uptime && cancelled"),
+            "Type error: `&&` expects a bool, but `uptime` is a number"
+        );
+        assert_eq!(
+            evaluate("flight.status > 3"),
+            "Type error: `>` expects numbers, but `flight.status` is a string"
+        );
+        assert_eq!(
+            evaluate("uptime + 1"),
+            "Type error: `condition` expects a bool, but `(uptime + 1)` is a number"
+        );
+        assert_eq!(evaluate("latency < 200"), "Unknown identifier `latency`");
+    }
+}
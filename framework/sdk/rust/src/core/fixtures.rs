@@ -0,0 +1,16 @@
+//! Contracts shared by the core tests
+
+use crate::{Contract, ContractConfig};
+
+/// Wallet of the vendor, who is paid
+pub(crate) const VENDOR_WALLET: &str = "0x209693Bc6afc0C5328bA36FaF03C514EF312287C";
+
+/// Wallet of the customer, who pays
+pub(crate) const CUSTOMER_WALLET: &str = "0x00000000000000000000000000000000000000c0";
+
+/// Default contract between the vendor and customer wallets, so payments
+/// have both sides
+pub(crate) fn contract() -> Contract {
+    Contract::from_config(ContractConfig::default().with_parties([VENDOR_WALLET, CUSTOMER_WALLET]))
+        .unwrap()
+}
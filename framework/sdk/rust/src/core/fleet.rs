@@ -0,0 +1,438 @@
+//! Monitoring every contract of a store in one runtime
+//!
+//! [`Contract::resume_monitoring`](crate::Contract::resume_monitoring)
+//! runs one task per contract. A [`Monitor`] instead schedules all
+//! contracts of a [`ContractStore`] from a single task and runs their
+//! checks on a fixed pool of workers, with one [`OracleSet`] shared by all
+//! of them. Contracts added to or removed from the store are picked up on
+//! the next refresh.
+
+use super::actions::ActionRegistry;
+use super::clock::{Clock, SystemClock};
+use super::events::{event_stream, ContractEvent};
+use super::monitor::{EventSink, MonitorState, Schedule, Watch};
+use super::notify::{forward_shared_alerts, Notifier};
+use super::oracle::OracleSet;
+use super::payment::{PaymentExecutor, PlaceholderExecutor};
+use super::store::ContractStore;
+use crate::x402::RetryPolicy;
+use crate::Result;
+use chrono::{DateTime, Utc};
+use futures::Stream;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc, Mutex, Notify};
+use tokio::task::JoinHandle;
+
+/// Workers used unless [`Monitor::with_workers`] says otherwise
+pub const DEFAULT_WORKERS: usize = 8;
+
+/// How often the store is re-read for added and removed contracts
+const DEFAULT_REFRESH: Duration = Duration::from_secs(60);
+
+/// Events buffered per subscriber before the oldest are dropped
+const EVENT_CAPACITY: usize = 1024;
+
+/// Monitor for all contracts of a store
+///
+/// ```no_run
+/// # async fn run() -> smart402::Result<()> {
+/// use smart402::{FileContractStore, Monitor, Schedule};
+/// use std::sync::Arc;
+///
+/// let store = Arc::new(FileContractStore::open(".smart402")?);
+/// let monitor = Monitor::new(store, Schedule::parse("5m")?)
+///     .with_workers(16)
+///     .start()?;
+/// # monitor.stop().await;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Monitor {
+    store: Arc<dyn ContractStore>,
+    schedule: Schedule,
+    oracles: OracleSet,
+    actions: ActionRegistry,
+    notifiers: Vec<Arc<dyn Notifier>>,
+    workers: usize,
+    refresh: Duration,
+    clock: Arc<dyn Clock>,
+    payer: Arc<dyn PaymentExecutor>,
+    payment_retry: RetryPolicy,
+}
+
+impl Monitor {
+    /// Monitor the contracts of `store` on `schedule`
+    ///
+    /// Progress of each contract is saved to the store after every check,
+    /// so a restarted monitor resumes where the previous one stopped.
+    pub fn new(store: Arc<dyn ContractStore>, schedule: Schedule) -> Self {
+        Self {
+            store,
+            schedule,
+            oracles: OracleSet::new(),
+            actions: ActionRegistry::new(),
+            notifiers: Vec::new(),
+            workers: DEFAULT_WORKERS,
+            refresh: DEFAULT_REFRESH,
+            clock: Arc::new(SystemClock),
+            payer: Arc::new(PlaceholderExecutor),
+            payment_retry: RetryPolicy::default(),
+        }
+    }
+
+    /// Run at most `workers` checks at a time (at least one)
+    pub fn with_workers(mut self, workers: usize) -> Self {
+        self.workers = workers.max(1);
+        self
+    }
+
+    /// Start from these oracles; oracles the contracts define are added
+    /// to them, each id connected once
+    pub fn with_oracles(mut self, oracles: OracleSet) -> Self {
+        self.oracles = oracles;
+        self
+    }
+
+    /// Let the rules of every contract run these actions
+    pub fn with_actions(mut self, actions: ActionRegistry) -> Self {
+        self.actions = actions;
+        self
+    }
+
+    /// Alert `notifier` about the events of every contract
+    pub fn with_notifier(mut self, notifier: Arc<dyn Notifier>) -> Self {
+        self.notifiers.push(notifier);
+        self
+    }
+
+    /// Re-read the store for added and removed contracts every `refresh`
+    pub fn with_refresh(mut self, refresh: Duration) -> Self {
+        self.refresh = refresh;
+        self
+    }
+
+    /// Read the time from `clock` instead of the wall clock
+    ///
+    /// The store is re-read as the clock passes each refresh.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Make the contracts' payments with `executor`
+    pub fn with_payment_executor(mut self, executor: Arc<dyn PaymentExecutor>) -> Self {
+        self.payer = executor;
+        self
+    }
+
+    /// Retry failed payments on `retry`; a worker waits for the retries
+    pub fn with_payment_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.payment_retry = retry;
+        self
+    }
+
+    /// Load the store's contracts and start monitoring them
+    pub fn start(self) -> Result<FleetHandle> {
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+        for notifier in &self.notifiers {
+            forward_shared_alerts(events.subscribe(), notifier.clone());
+        }
+        let mut scheduler = Scheduler {
+            store: self.store,
+            schedule: self.schedule,
+            oracles: self.oracles,
+            actions: self.actions,
+            refresh: self.refresh,
+            clock: self.clock,
+            payer: self.payer,
+            payment_retry: self.payment_retry,
+            events: events.clone(),
+            idle: HashMap::new(),
+            running: HashSet::new(),
+            stored: HashSet::new(),
+            retired: HashSet::new(),
+            pending: HashMap::new(),
+        };
+        scheduler.load()?;
+
+        let stop = Arc::new(Notify::new());
+        let (control, controls) = mpsc::unbounded_channel();
+        Ok(FleetHandle {
+            stop: stop.clone(),
+            control,
+            events,
+            task: tokio::spawn(scheduler.run(self.workers, stop, controls)),
+        })
+    }
+}
+
+/// Handle to a running [`Monitor`]
+///
+/// Dropping the handle leaves the monitor running; call
+/// [`FleetHandle::stop`] to end it.
+pub struct FleetHandle {
+    stop: Arc<Notify>,
+    control: mpsc::UnboundedSender<(String, bool)>,
+    events: broadcast::Sender<ContractEvent>,
+    task: JoinHandle<()>,
+}
+
+impl FleetHandle {
+    /// Stream the events of all contracts from now on
+    ///
+    /// A subscriber that falls behind misses the oldest events.
+    pub fn subscribe(&self) -> impl Stream<Item = ContractEvent> + Send + Unpin + 'static {
+        event_stream(self.events.subscribe())
+    }
+
+    /// Skip the scheduled checks of a contract until
+    /// [`FleetHandle::resume`], as
+    /// [`Contract::pause_monitoring`](crate::Contract::pause_monitoring)
+    /// does
+    ///
+    /// The pause is saved to the store, so it survives restarts.
+    pub fn pause(&self, contract_id: &str) {
+        let _ = self.control.send((contract_id.to_string(), true));
+    }
+
+    /// Resume checks of a contract paused by [`FleetHandle::pause`]
+    pub fn resume(&self, contract_id: &str) {
+        let _ = self.control.send((contract_id.to_string(), false));
+    }
+
+    /// Whether the monitor is still running
+    pub fn is_running(&self) -> bool {
+        !self.task.is_finished()
+    }
+
+    /// Stop the monitor, waiting for in-progress checks to finish
+    pub async fn stop(self) {
+        self.stop.notify_one();
+        let _ = self.task.await;
+    }
+}
+
+/// Decides which contract is checked when; owns every idle watch
+struct Scheduler {
+    store: Arc<dyn ContractStore>,
+    schedule: Schedule,
+    oracles: OracleSet,
+    actions: ActionRegistry,
+    refresh: Duration,
+    clock: Arc<dyn Clock>,
+    payer: Arc<dyn PaymentExecutor>,
+    payment_retry: RetryPolicy,
+    events: broadcast::Sender<ContractEvent>,
+    /// Watches waiting for their next check, with its time
+    idle: HashMap<String, (DateTime<Utc>, Watch)>,
+    /// Contracts whose check is queued or in progress
+    running: HashSet<String>,
+    /// Contracts in the store as of the last refresh
+    stored: HashSet<String>,
+    /// Contracts that expired or ran out of scheduled checks
+    retired: HashSet<String>,
+    /// Pauses and resumes of running watches, applied once they are back
+    pending: HashMap<String, bool>,
+}
+
+impl Scheduler {
+    async fn run(
+        mut self,
+        workers: usize,
+        stop: Arc<Notify>,
+        mut controls: mpsc::UnboundedReceiver<(String, bool)>,
+    ) {
+        let (jobs, queue) = mpsc::unbounded_channel::<Watch>();
+        let (done, mut finished) = mpsc::unbounded_channel::<(Watch, bool)>();
+        let queue = Arc::new(Mutex::new(queue));
+        for _ in 0..workers {
+            let queue = queue.clone();
+            let done = done.clone();
+            tokio::spawn(async move {
+                loop {
+                    // Hold the lock only while waiting, not while checking
+                    let Some(mut watch) = queue.lock().await.recv().await else {
+                        return;
+                    };
+                    let scheduled = watch.run_due().await;
+                    if done.send((watch, scheduled)).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+        drop(done);
+
+        let clock = self.clock.clone();
+        let mut next_refresh = clock.now() + self.refresh_interval();
+        loop {
+            let next_check = self.idle.values().map(|(next, _)| *next).min();
+            let wake = next_check.map_or(next_refresh, |next| next.min(next_refresh));
+            tokio::select! {
+                _ = clock.sleep_until(wake) => {}
+                Some((watch, scheduled)) = finished.recv() => {
+                    self.finish(watch, scheduled);
+                    continue;
+                }
+                Some((id, paused)) = controls.recv() => {
+                    self.set_paused(id, paused);
+                    continue;
+                }
+                _ = stop.notified() => break,
+            }
+
+            let now = clock.now();
+            if next_refresh <= now {
+                if let Err(e) = self.load() {
+                    tracing::warn!(error = %e, "reading contracts from store failed");
+                }
+                next_refresh = now + self.refresh_interval();
+            }
+            let due: Vec<String> = self
+                .idle
+                .iter()
+                .filter(|(_, (next, _))| *next <= now)
+                .map(|(id, _)| id.clone())
+                .collect();
+            for id in due {
+                if let Some((_, watch)) = self.idle.remove(&id) {
+                    self.running.insert(id);
+                    let _ = jobs.send(watch);
+                }
+            }
+        }
+
+        // Let queued and in-progress checks finish
+        drop(jobs);
+        while let Some((watch, scheduled)) = finished.recv().await {
+            self.finish(watch, scheduled);
+        }
+    }
+
+    /// Start watching the store's contracts not watched yet and stop
+    /// watching the ones removed from it
+    fn load(&mut self) -> Result<()> {
+        let contracts = self.store.load_contracts()?;
+        self.stored = contracts
+            .iter()
+            .map(|ucl| ucl.contract_id.clone())
+            .collect();
+        let stored = &self.stored;
+        self.idle.retain(|id, _| stored.contains(id));
+        self.retired.retain(|id| stored.contains(id));
+
+        for ucl in contracts {
+            let id = &ucl.contract_id;
+            if self.idle.contains_key(id) || self.running.contains(id) || self.retired.contains(id)
+            {
+                continue;
+            }
+            if let Err(e) = self.oracles.connect(&ucl) {
+                tracing::warn!(contract_id = %id, error = %e, "connecting oracles failed");
+            }
+            let state = MonitorState::resume(self.store.as_ref(), id, &self.schedule)?;
+            let watch = Watch::new(
+                ucl.clone(),
+                self.oracles.clone(),
+                self.actions.clone(),
+                self.schedule.clone(),
+                state,
+                Some(self.store.clone()),
+                EventSink {
+                    monitor: None,
+                    contract: self.events.clone(),
+                },
+            )
+            .with_clock(self.clock.clone())
+            .with_payments(self.payer.clone(), self.payment_retry.clone());
+            self.schedule_next(watch);
+        }
+        Ok(())
+    }
+
+    /// Pause or resume a contract's watch, once it is back if running
+    fn set_paused(&mut self, id: String, paused: bool) {
+        if let Some((_, watch)) = self.idle.get_mut(&id) {
+            watch.set_paused(paused);
+        } else if self.running.contains(&id) {
+            self.pending.insert(id, paused);
+        } else {
+            tracing::debug!(contract_id = %id, "not monitoring contract to pause or resume");
+        }
+    }
+
+    /// Take back a watch from a worker
+    fn finish(&mut self, mut watch: Watch, scheduled: bool) {
+        let id = watch.contract_id().to_string();
+        self.running.remove(&id);
+        if let Some(paused) = self.pending.remove(&id) {
+            watch.set_paused(paused);
+        }
+        if !self.stored.contains(&id) {
+            // Removed from the store during the check
+        } else if scheduled {
+            self.schedule_next(watch);
+        } else {
+            self.retired.insert(id);
+        }
+    }
+
+    fn schedule_next(&mut self, mut watch: Watch) {
+        let id = watch.contract_id().to_string();
+        match watch.next_check() {
+            Some(next) => {
+                self.idle.insert(id, (next, watch));
+            }
+            None => {
+                self.retired.insert(id);
+            }
+        }
+    }
+
+    fn refresh_interval(&self) -> chrono::Duration {
+        chrono::Duration::from_std(self.refresh).unwrap_or_else(|_| chrono::Duration::weeks(52))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::events::ContractEventKind;
+    use crate::core::monitor::MonitorEvent;
+    use crate::core::store::InMemoryContractStore;
+    use crate::{Contract, ContractConfig};
+    use futures::StreamExt;
+
+    #[tokio::test]
+    async fn test_monitor_checks_every_contract_of_store() {
+        let store = Arc::new(InMemoryContractStore::new());
+        for n in 0..20 {
+            let mut contract = Contract::from_config(ContractConfig::default()).unwrap();
+            contract.ucl.contract_id = format!("smart402:contract:{}", n);
+            store.save_contract(&contract.ucl).unwrap();
+        }
+
+        let monitor = Monitor::new(store.clone(), Schedule::Every(Duration::from_millis(10)))
+            .with_workers(3)
+            .start()
+            .unwrap();
+        let mut events = monitor.subscribe();
+        let mut checked = HashSet::new();
+        while checked.len() < 20 {
+            let event = events.next().await.unwrap();
+            if matches!(
+                event.event,
+                ContractEventKind::Monitor(MonitorEvent::Checked(_))
+            ) {
+                checked.insert(event.contract_id);
+            }
+        }
+        monitor.stop().await;
+
+        let state = store.load_monitor("smart402:contract:7").unwrap().unwrap();
+        assert!(state.last_run.is_some());
+        assert!(state.last_check.unwrap().all_met);
+    }
+}
@@ -0,0 +1,178 @@
+//! History of condition checks
+//!
+//! Every check of a contract's conditions, by
+//! [`Contract::check_conditions`] or one of its monitors, is recorded per
+//! condition with the value read and the oracle it came from. Monitors
+//! saving to a [`ContractStore`] append the records to the store as well,
+//! so reports can tell when and how often a condition failed across
+//! restarts.
+
+use super::store::ContractStore;
+use crate::{CheckExplanation, ConditionRecord, Contract, Result};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::ops::RangeBounds;
+use std::sync::Mutex;
+
+/// Condition records of a contract, oldest first
+#[derive(Debug, Default)]
+pub(crate) struct ConditionHistory {
+    records: Mutex<Vec<ConditionRecord>>,
+}
+
+impl ConditionHistory {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn extend(&self, records: &[ConditionRecord]) {
+        self.records.lock().unwrap().extend_from_slice(records);
+    }
+
+    /// Load the history saved in `store`, unless records were kept already
+    pub(crate) fn load(&self, store: &dyn ContractStore, contract_id: &str) -> Result<()> {
+        let mut records = self.records.lock().unwrap();
+        if records.is_empty() {
+            *records = store.load_condition_history(contract_id)?;
+        }
+        Ok(())
+    }
+
+    fn query(
+        &self,
+        condition_id: &str,
+        range: impl RangeBounds<DateTime<Utc>>,
+    ) -> Vec<ConditionRecord> {
+        self.records
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|record| record.condition_id == condition_id && range.contains(&record.checked_at))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Records of the conditions in `explanation`
+///
+/// `fallbacks` maps oracles to the fallbacks serving their reads.
+pub(crate) fn records(
+    explanation: &CheckExplanation,
+    fallbacks: &HashMap<String, String>,
+) -> Vec<ConditionRecord> {
+    explanation
+        .conditions
+        .iter()
+        .map(|condition| ConditionRecord {
+            condition_id: condition.condition_id.clone(),
+            checked_at: explanation.result.timestamp,
+            met: condition.met,
+            served_by: condition
+                .source
+                .as_ref()
+                .and_then(|source| fallbacks.get(source))
+                .cloned(),
+            source: condition.source.clone(),
+            value: condition.value.clone(),
+            inputs: condition.inputs.clone(),
+        })
+        .collect()
+}
+
+impl Contract {
+    /// Checks of condition `condition_id` within `range`, oldest first
+    ///
+    /// Covers the checks of [`Contract::check_conditions`] and of the
+    /// contract's monitors. A monitor started with
+    /// [`Contract::resume_monitoring`] first loads the history saved in its
+    /// store if the contract has none yet, e.g. after a restart:
+    ///
+    /// ```no_run
+    /// # fn run(contract: smart402::Contract) {
+    /// use chrono::{Duration, Utc};
+    ///
+    /// let month = Utc::now() - Duration::days(30)..;
+    /// let breaches = contract
+    ///     .condition_history("uptime_met", month)
+    ///     .into_iter()
+    ///     .filter(|record| !record.met)
+    ///     .count();
+    /// # }
+    /// ```
+    pub fn condition_history(
+        &self,
+        condition_id: &str,
+        range: impl RangeBounds<DateTime<Utc>>,
+    ) -> Vec<ConditionRecord> {
+        self.history.query(condition_id, range)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::monitor::{MonitorEvent, Schedule};
+    use crate::core::store::InMemoryContractStore;
+    use crate::types::ConditionDefinition;
+    use crate::ContractConfig;
+    use std::sync::Arc;
+
+    struct Fixed(serde_json::Value);
+
+    #[async_trait::async_trait]
+    impl crate::Oracle for Fixed {
+        fn id(&self) -> &str {
+            "uptime_oracle"
+        }
+
+        async fn read(&self) -> Result<serde_json::Value> {
+            Ok(self.0.clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_condition_history_survives_restart() {
+        let mut contract = Contract::from_config(ContractConfig::default()).unwrap();
+        contract.ucl.conditions.required.push(ConditionDefinition {
+            id: "uptime_met".to_string(),
+            description: "Uptime above 99%".to_string(),
+            source: "uptime_oracle".to_string(),
+            operator: ">=".to_string(),
+            threshold: Some(serde_json::json!(99.0)),
+            expression: None,
+            temporal: None,
+        });
+        contract.add_oracle(Arc::new(Fixed(serde_json::json!(98.5))));
+        let store = Arc::new(InMemoryContractStore::new());
+
+        let started = Utc::now();
+        contract.check_conditions().await.unwrap();
+        let monitor = contract
+            .resume_monitoring(Schedule::parse("10ms").unwrap(), store.clone())
+            .unwrap();
+        let mut events = monitor.subscribe();
+        while !matches!(events.recv().await.unwrap(), MonitorEvent::Checked(_)) {}
+        monitor.stop().await;
+
+        let history = contract.condition_history("uptime_met", started..);
+        assert!(history.len() >= 2, "{:?}", history);
+        let breach = &history[0];
+        assert!(!breach.met);
+        assert_eq!(breach.source.as_deref(), Some("uptime_oracle"));
+        assert_eq!(breach.value, Some(serde_json::json!(98.5)));
+        assert!(contract.condition_history("uptime_met", ..started).is_empty());
+        assert!(contract.condition_history("other", ..).is_empty());
+
+        // Only the monitor's checks were saved
+        let mut restarted = Contract::from_config(ContractConfig::default()).unwrap();
+        restarted.ucl = contract.ucl.clone();
+        let monitor = restarted
+            .resume_monitoring(Schedule::parse("1h").unwrap(), store.clone())
+            .unwrap();
+        monitor.stop().await;
+        assert_eq!(
+            restarted.condition_history("uptime_met", ..),
+            history[1..].to_vec()
+        );
+    }
+}
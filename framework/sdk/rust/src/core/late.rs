@@ -0,0 +1,187 @@
+//! Late payment rules
+//!
+//! [`Contract::add_late_payment_rules`] turns the contract's
+//! [`LatePaymentTerms`] into a condition that holds once a payment is
+//! overdue past its grace period, and a rule that charges the late fee on
+//! the next payment and notifies every party when it does.
+
+use crate::types::{
+    ActionDefinition, ConditionDefinition, LatePaymentTerms, RuleConditions, RuleDefinition,
+    TemporalCondition,
+};
+use crate::{Amount, Contract, Result, UCLContract};
+use std::collections::HashMap;
+
+/// Id of the condition added by [`Contract::add_late_payment_rules`]
+pub const LATE_PAYMENT_CONDITION: &str = "payment_late";
+
+/// Id of the rule added by [`Contract::add_late_payment_rules`]
+pub const LATE_PAYMENT_RULE: &str = "late_payment";
+
+impl LatePaymentTerms {
+    /// Fee charged on a late payment of `amount`: the flat fee plus the
+    /// interest
+    pub fn fee_on(&self, amount: Amount) -> Amount {
+        self.fee.unwrap_or_default() + amount.percent(self.interest.unwrap_or_default())
+    }
+
+    /// Condition holding once the next payment is overdue past the grace
+    /// period
+    pub fn condition(&self) -> ConditionDefinition {
+        let description = match &self.grace {
+            Some(grace) => format!("Payment is more than {} overdue", grace),
+            None => "Payment is overdue".to_string(),
+        };
+        ConditionDefinition {
+            id: LATE_PAYMENT_CONDITION.to_string(),
+            description,
+            source: "time".to_string(),
+            operator: "passed".to_string(),
+            threshold: None,
+            expression: None,
+            temporal: Some(TemporalCondition::PaymentOverdue {
+                grace: self.grace.clone(),
+            }),
+        }
+    }
+
+    /// Rule charging the late fee once per late payment and notifying the
+    /// parties of `ucl`
+    pub fn rule(&self, ucl: &UCLContract) -> RuleDefinition {
+        let fee = self.fee_on(ucl.payment.amount);
+        let mut roles: Vec<String> = Vec::new();
+        for party in &ucl.metadata.parties {
+            if !roles.contains(&party.role) {
+                roles.push(party.role.clone());
+            }
+        }
+        let mut notify = HashMap::from([(
+            "message".to_string(),
+            serde_json::json!(format!(
+                "Payment is late; a late fee of {} {} is added to the next payment",
+                fee, ucl.payment.token
+            )),
+        )]);
+        if !roles.is_empty() {
+            notify.insert("to".to_string(), serde_json::json!(roles));
+        }
+        RuleDefinition {
+            rule_id: LATE_PAYMENT_RULE.to_string(),
+            name: "Charge Late Fee".to_string(),
+            trigger: "condition_met".to_string(),
+            conditions: RuleConditions {
+                all_of: Some(vec![LATE_PAYMENT_CONDITION.to_string()]),
+                ..Default::default()
+            },
+            actions: vec![
+                ActionDefinition {
+                    action: "apply_late_fee".to_string(),
+                    params: HashMap::from([("amount".to_string(), serde_json::json!(fee))]),
+                },
+                ActionDefinition {
+                    action: "notify".to_string(),
+                    params: notify,
+                },
+            ],
+            ..Default::default()
+        }
+    }
+}
+
+impl Contract {
+    /// Add the condition and rule charging the contract's late payment
+    /// terms
+    ///
+    /// The condition is optional, so a late payment does not count against
+    /// `all_met`. Rules added before are replaced, e.g. after the terms
+    /// changed. Fails when the payment terms have no `late_payment`.
+    pub fn add_late_payment_rules(&mut self) -> Result<()> {
+        let terms = self.ucl.payment.late_payment.clone().ok_or_else(|| {
+            crate::Error::ConfigError("Payment terms have no late_payment".to_string())
+        })?;
+        let optional = self.ucl.conditions.optional.get_or_insert_with(Vec::new);
+        optional.retain(|condition| condition.id != LATE_PAYMENT_CONDITION);
+        optional.push(terms.condition());
+        let rule = terms.rule(&self.ucl);
+        self.ucl.rules.retain(|rule| rule.rule_id != LATE_PAYMENT_RULE);
+        self.ucl.rules.push(rule);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::clock::{Clock, TestClock};
+    use crate::core::fixtures::{CUSTOMER_WALLET, VENDOR_WALLET};
+    use crate::core::monitor::{MonitorEvent, Schedule};
+    use crate::types::PartyInfo;
+    use crate::ContractConfig;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_late_payment_adds_fee_to_next_payment() {
+        let mut contract = Contract::from_config(ContractConfig::default()).unwrap();
+        assert!(contract.add_late_payment_rules().is_err());
+        contract.ucl.payment.amount = Amount::from(100);
+        contract.ucl.payment.frequency = crate::PaymentFrequency::Monthly { day: None };
+        contract.ucl.payment.late_payment = Some(LatePaymentTerms {
+            grace: Some("5 days".to_string()),
+            fee: Some(Amount::from(10)),
+            interest: Some(Amount::new(15, 1)),
+        });
+        for (role, wallet) in [("payer", CUSTOMER_WALLET), ("payee", VENDOR_WALLET)] {
+            contract.ucl.metadata.parties.push(PartyInfo {
+                role: role.to_string(),
+                identifier: format!("{}@example.com", role),
+                name: None,
+                wallet: Some(wallet.to_string()),
+                ..Default::default()
+            });
+        }
+        contract.add_late_payment_rules().unwrap();
+        contract.add_late_payment_rules().unwrap();
+        assert_eq!(contract.ucl.rules.len(), 1);
+        // The first payment, due on the effective date, is only made late
+        contract.ucl.rules.push(RuleDefinition {
+            rule_id: "monthly_payment".to_string(),
+            name: "Execute Monthly Payment".to_string(),
+            trigger: "time_based".to_string(),
+            conditions: RuleConditions {
+                all_of: Some(vec![LATE_PAYMENT_CONDITION.to_string()]),
+                ..Default::default()
+            },
+            actions: vec![ActionDefinition {
+                action: "execute_payment".to_string(),
+                params: HashMap::new(),
+            }],
+            ..Default::default()
+        });
+        let clock = Arc::new(TestClock::new("2024-01-01T00:00:00Z".parse().unwrap()));
+        contract.set_clock(clock.clone());
+
+        let monitor = contract.monitor_with_schedule(Schedule::Every(Duration::from_secs(86_400)));
+        let mut events = monitor.subscribe();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let mut recipients = Vec::new();
+        let payment = 'days: loop {
+            clock.advance(chrono::Duration::days(1));
+            loop {
+                match events.recv().await.unwrap() {
+                    MonitorEvent::Notified { recipients: to, .. } => recipients = to,
+                    MonitorEvent::PaymentExecuted(payment) => break 'days payment,
+                    MonitorEvent::Checked(check) if !check.conditions[LATE_PAYMENT_CONDITION] => {
+                        break
+                    }
+                    _ => {}
+                }
+            }
+        };
+        monitor.stop().await;
+
+        assert_eq!(clock.now().to_rfc3339(), "2024-01-06T00:00:00+00:00");
+        assert_eq!(recipients, vec!["payer@example.com", "payee@example.com"]);
+        assert_eq!(payment.amount, Amount::new(1115, 1));
+    }
+}
@@ -1,2 +1,30 @@
 pub mod smart402;
+pub mod actions;
+#[cfg(feature = "artifacts")]
+pub mod artifacts;
+pub mod clock;
+pub mod conditions;
 pub mod contract;
+pub mod escalation;
+pub mod events;
+pub mod expression;
+pub mod fleet;
+pub mod history;
+pub mod late;
+pub mod monitor;
+pub mod notify;
+pub mod oracle;
+pub mod payment;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+#[cfg(feature = "websocket")]
+pub mod push;
+pub mod rules;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod store;
+pub mod temporal;
+pub mod webhook;
+
+#[cfg(test)]
+pub(crate) mod fixtures;
@@ -0,0 +1,2076 @@
+//! Background contract monitoring
+//!
+//! [`Contract::start_monitoring`] spawns a tokio task that checks the
+//! contract's conditions on a [`Schedule`] and fires the actions of every
+//! rule whose conditions hold. Progress is published as [`MonitorEvent`]s
+//! to subscribers of the returned [`MonitorHandle`].
+//!
+//! [`Contract::resume_monitoring`] additionally keeps a [`MonitorState`]
+//! in a [`ContractStore`], so a restarted monitor picks up its schedule,
+//! missed runs and payment obligations where the previous one stopped.
+
+use super::actions::{ActionContext, ActionRegistry};
+use super::clock::{Clock, SystemClock};
+use super::escalation::AlertState;
+use super::events::{ContractEvent, ContractEventKind};
+use super::history::{self, ConditionHistory};
+use super::notify::{forward_alerts, Severity};
+use super::oracle::OracleSet;
+use super::payment::{PaymentAttempt, PaymentExecutor, PlaceholderExecutor};
+use super::rules::{ActionOutcome, ActionRecord, PaymentHold, Trigger, ACTION_LOG_LIMIT};
+use super::store::ContractStore;
+use super::temporal::Timeline;
+use crate::types::{ActionDefinition, EscalationPolicy, RuleDefinition};
+use crate::x402::RetryPolicy;
+use crate::{
+    Amount, CheckExplanation, ConditionCheckResult, Contract, OutstandingPayments, PaymentResult,
+    Result, TerminationReason, UCLContract,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, watch, Notify};
+use tokio::task::JoinHandle;
+
+/// Events buffered per subscriber before the oldest are dropped
+const EVENT_CAPACITY: usize = 64;
+
+/// When the monitor checks a contract
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum Schedule {
+    /// At a fixed interval, first one interval after start
+    Every(Duration),
+    /// At the times matched by a cron expression (UTC)
+    Cron(Box<cron::Schedule>),
+}
+
+impl Schedule {
+    /// Parse a frequency
+    ///
+    /// Accepts the named frequencies `quick` (every minute), `medium`
+    /// (every 15 minutes) and `slow` (hourly), intervals such as `"30s"`,
+    /// `"5m"` or `"daily"`, and cron expressions of five (minute first) or
+    /// six (second first) fields. Cron day-of-week numbers run from
+    /// 1 (Sunday) to 7; names like `MON-FRI` avoid the ambiguity.
+    pub fn parse(frequency: &str) -> Result<Self> {
+        let frequency = frequency.trim();
+        if let Some(Ok(millis)) = frequency.strip_suffix("ms").map(str::parse) {
+            return Ok(Schedule::Every(Duration::from_millis(millis)));
+        }
+        let named = match frequency.to_lowercase().as_str() {
+            "quick" => Some(60),
+            "medium" => Some(15 * 60),
+            "slow" => Some(3_600),
+            _ => crate::llmo::lint::parse_interval_secs(frequency),
+        };
+        match named {
+            Some(0) => Err(crate::Error::ConfigError(format!(
+                "Monitoring frequency {:?} has no interval",
+                frequency
+            ))),
+            Some(secs) => Ok(Schedule::Every(Duration::from_secs(secs))),
+            None => {
+                let expression = if frequency.split_whitespace().count() == 5 {
+                    format!("0 {}", frequency)
+                } else {
+                    frequency.to_string()
+                };
+                cron::Schedule::from_str(&expression)
+                    .map(|schedule| Schedule::Cron(Box::new(schedule)))
+                    .map_err(|e| {
+                        crate::Error::ConfigError(format!(
+                            "Invalid monitoring frequency {:?}: {}",
+                            frequency, e
+                        ))
+                    })
+            }
+        }
+    }
+
+    /// First run strictly after `after`, if any
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Schedule::Every(interval) => Some(after + chrono::Duration::from_std(*interval).ok()?),
+            Schedule::Cron(schedule) => schedule.after(&after).next(),
+        }
+    }
+}
+
+impl std::fmt::Display for Schedule {
+    /// The schedule in a form [`Schedule::parse`] accepts
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Schedule::Every(interval) if interval.subsec_nanos() == 0 => {
+                write!(f, "{}s", interval.as_secs())
+            }
+            Schedule::Every(interval) => write!(f, "{}ms", interval.as_millis()),
+            Schedule::Cron(schedule) => write!(f, "{}", schedule),
+        }
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+/// Progress of a running monitor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum MonitorEvent {
+    /// Conditions were checked
+    Checked(ConditionCheckResult),
+    /// A failing condition reached an escalation step that alerts
+    ConditionFailed {
+        condition_id: String,
+        checked_at: DateTime<Utc>,
+        /// Consecutive checks the condition failed
+        failures: u32,
+        failing_since: DateTime<Utc>,
+        /// Notifier channel the alert is for; every channel when unset
+        channel: Option<String>,
+        recipients: Vec<String>,
+    },
+    /// A condition that was alerted about is met again
+    ConditionResolved {
+        condition_id: String,
+        failing_since: DateTime<Utc>,
+        resolved_at: DateTime<Utc>,
+    },
+    /// A rule's conditions held and one of its actions ran
+    ActionFired {
+        rule_id: String,
+        action: ActionDefinition,
+    },
+    /// An `execute_payment` action paid
+    PaymentExecuted(PaymentResult),
+    /// A payment attempt failed and is retried at `retry_at`
+    PaymentRetrying {
+        rule_id: String,
+        attempt: u32,
+        error: String,
+        retry_at: DateTime<Utc>,
+    },
+    /// A payment still failed after its last attempt
+    PaymentEscalated {
+        rule_id: String,
+        attempts: u32,
+        error: String,
+    },
+    /// An action was skipped, e.g. because its obligation is already met
+    ActionSkipped {
+        rule_id: String,
+        action: String,
+        reason: String,
+    },
+    /// An action ran and failed
+    ActionFailed {
+        rule_id: String,
+        action: String,
+        error: String,
+    },
+    /// A `notify` or `escalate` action raised an alert
+    Notified {
+        rule_id: String,
+        message: String,
+        severity: Severity,
+        /// Addresses to alert instead of the notifier's own, from the
+        /// action's `to`
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        recipients: Vec<String>,
+    },
+    /// An `apply_late_fee` action added `fee` to the next payment
+    LateFeeApplied {
+        rule_id: String,
+        fee: Amount,
+        /// Late fees the next payment carries, this one included
+        total: Amount,
+    },
+    /// A `pause_contract` action paused payments
+    Paused {
+        rule_id: String,
+        paused_at: DateTime<Utc>,
+    },
+    /// A `resume_contract` action resumed payments
+    Resumed { rule_id: String },
+    /// A `hold_payments` action held payments
+    PaymentsHeld {
+        rule_id: String,
+        held_at: DateTime<Utc>,
+    },
+    /// Held payments were released
+    PaymentsReleased { rule_id: String },
+    /// A `switch_oracle` action replaced an oracle with its fallback
+    OracleSwitched {
+        rule_id: String,
+        from: String,
+        to: String,
+    },
+    /// Monitoring was paused; scheduled checks are skipped until it resumes
+    MonitoringPaused { paused_at: DateTime<Utc> },
+    /// Monitoring resumed after a pause
+    MonitoringResumed {
+        paused_at: DateTime<Utc>,
+        resumed_at: DateTime<Utc>,
+    },
+    /// The contract term ended; the monitor stops
+    Expired { expired_at: DateTime<Utc> },
+    /// Notice of termination was given; the contract terminates at
+    /// `effective_at`
+    NoticeGiven {
+        given_at: DateTime<Utc>,
+        effective_at: DateTime<Utc>,
+    },
+    /// The contract was terminated before its term was up; the monitor
+    /// stops
+    Terminated {
+        terminated_at: DateTime<Utc>,
+        reason: TerminationReason,
+        outstanding: OutstandingPayments,
+        /// Refund owed on the last payment, for prorated payments
+        #[serde(default, skip_serializing_if = "Amount::is_zero")]
+        refund: Amount,
+    },
+    /// The monitor state could not be saved
+    PersistFailed { error: String },
+}
+
+/// Progress of a monitor, persisted between runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonitorState {
+    pub contract_id: String,
+    /// Schedule the monitor runs on, as accepted by [`Schedule::parse`]
+    pub schedule: String,
+    #[serde(default)]
+    pub last_run: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub last_check: Option<ConditionCheckResult>,
+    /// Next scheduled check; a time in the past is a run missed while the
+    /// monitor was down and is caught up immediately
+    #[serde(default)]
+    pub next_run: Option<DateTime<Utc>>,
+    /// When the next recurring payment falls due; `execute_payment`
+    /// actions before then are skipped
+    #[serde(default)]
+    pub next_payment_due: Option<DateTime<Utc>>,
+    /// Set once the contract term has ended; an expired monitor never runs
+    #[serde(default)]
+    pub expired_at: Option<DateTime<Utc>>,
+    /// Set once notice of termination is given, see
+    /// [`Contract::give_notice`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notice_given_at: Option<DateTime<Utc>>,
+    /// Set once the contract was terminated; a terminated monitor never
+    /// runs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub terminated_at: Option<DateTime<Utc>>,
+    /// When each currently unmet condition was first seen unmet, for breach
+    /// grace periods
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub breached_since: HashMap<String, DateTime<Utc>>,
+    /// Escalation progress of each currently failing condition
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub alerts: HashMap<String, AlertState>,
+    /// Consecutive failed reads of each oracle that is currently failing
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub oracle_failures: HashMap<String, u32>,
+    /// Consecutive payments that did not go through
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub payment_failures: u32,
+    /// Set while a `hold_payments` action holds payments
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub held: Option<PaymentHold>,
+    /// Fallbacks installed by `switch_oracle` actions, by replaced oracle
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub oracle_fallbacks: HashMap<String, String>,
+    /// Set while a `pause_contract` action holds payments
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paused_at: Option<DateTime<Utc>>,
+    /// Late fees added to the next payment by `apply_late_fee` actions
+    #[serde(default, skip_serializing_if = "Amount::is_zero")]
+    pub late_fees: Amount,
+    /// Set while monitoring is paused, e.g. for a maintenance window
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monitoring_paused_at: Option<DateTime<Utc>>,
+    /// Actions run by the monitor, oldest first, up to
+    /// [`ACTION_LOG_LIMIT`] entries
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub log: Vec<ActionRecord>,
+    /// Attempts at the monitor's payments, retries included, oldest first,
+    /// up to [`ACTION_LOG_LIMIT`] entries
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub payment_attempts: Vec<PaymentAttempt>,
+}
+
+impl MonitorState {
+    fn new(contract_id: &str, schedule: &Schedule) -> Self {
+        Self {
+            contract_id: contract_id.to_string(),
+            schedule: schedule.to_string(),
+            last_run: None,
+            last_check: None,
+            next_run: None,
+            next_payment_due: None,
+            expired_at: None,
+            notice_given_at: None,
+            terminated_at: None,
+            breached_since: HashMap::new(),
+            alerts: HashMap::new(),
+            oracle_failures: HashMap::new(),
+            payment_failures: 0,
+            held: None,
+            oracle_fallbacks: HashMap::new(),
+            paused_at: None,
+            late_fees: Amount::ZERO,
+            monitoring_paused_at: None,
+            log: Vec::new(),
+            payment_attempts: Vec::new(),
+        }
+    }
+
+    /// State saved in `store`, or a fresh one; a changed schedule drops the
+    /// pending run
+    pub(crate) fn resume(
+        store: &dyn ContractStore,
+        contract_id: &str,
+        schedule: &Schedule,
+    ) -> Result<Self> {
+        let mut state = store
+            .load_monitor(contract_id)?
+            .unwrap_or_else(|| Self::new(contract_id, schedule));
+        if state.schedule != schedule.to_string() {
+            state.schedule = schedule.to_string();
+            state.next_run = None;
+        }
+        Ok(state)
+    }
+}
+
+/// Handle to a running monitor
+///
+/// Dropping the handle leaves the monitor running; call
+/// [`MonitorHandle::stop`] to end it.
+pub struct MonitorHandle {
+    contract_id: String,
+    stop: Arc<Notify>,
+    paused: Arc<watch::Sender<bool>>,
+    events: broadcast::Sender<MonitorEvent>,
+    task: JoinHandle<()>,
+}
+
+impl MonitorHandle {
+    /// Id of the monitored contract
+    pub fn contract_id(&self) -> &str {
+        &self.contract_id
+    }
+
+    /// Receive events published from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<MonitorEvent> {
+        self.events.subscribe()
+    }
+
+    /// Whether the monitor is still running
+    pub fn is_running(&self) -> bool {
+        !self.task.is_finished()
+    }
+
+    /// Skip scheduled checks until [`MonitorHandle::resume`], keeping the
+    /// schedule and the state of failing conditions
+    ///
+    /// Pauses every monitor of the contract, like
+    /// [`Contract::pause_monitoring`].
+    pub fn pause(&self) {
+        self.paused.send_replace(true);
+    }
+
+    /// Resume checks paused by [`MonitorHandle::pause`]
+    pub fn resume(&self) {
+        self.paused.send_replace(false);
+    }
+
+    /// Whether checks are paused
+    pub fn is_paused(&self) -> bool {
+        *self.paused.borrow()
+    }
+
+    /// Stop the monitor, waiting for an in-progress check to finish
+    pub async fn stop(self) {
+        self.stop.notify_one();
+        let _ = self.task.await;
+    }
+}
+
+impl Contract {
+    /// Monitor the contract on a [`Schedule`] instead of a frequency string
+    pub fn monitor_with_schedule(&self, schedule: Schedule) -> MonitorHandle {
+        let state = MonitorState::new(&self.ucl.contract_id, &schedule);
+        self.spawn_monitor(schedule, state, None)
+    }
+
+    /// Monitor the contract, saving progress to `store` after every check
+    ///
+    /// Resumes from the state saved by a previous monitor of this contract.
+    /// Its pending run is kept unless `schedule` differs from the saved one;
+    /// the last check and payment obligations are kept either way. Condition
+    /// results are appended to the store's condition history and monitor
+    /// events to its event log.
+    pub fn resume_monitoring(
+        &self,
+        schedule: Schedule,
+        store: Arc<dyn ContractStore>,
+    ) -> Result<MonitorHandle> {
+        let state = MonitorState::resume(store.as_ref(), &self.ucl.contract_id, &schedule)?;
+        self.history.load(store.as_ref(), &self.ucl.contract_id)?;
+        Ok(self.spawn_monitor(schedule, state, Some(store)))
+    }
+
+    /// Pause the contract's monitors, e.g. for a maintenance window
+    ///
+    /// Monitors keep their schedule but skip its checks, so no condition
+    /// is found failing, alerted about or acted on while paused. Conditions
+    /// failing before the pause do not count the time paused towards their
+    /// grace periods and escalation delays. Monitors started later start
+    /// paused, and a pause saved to a store survives restarts.
+    pub fn pause_monitoring(&self) {
+        self.monitoring_paused.send_replace(true);
+    }
+
+    /// Resume monitors paused by [`Contract::pause_monitoring`]
+    ///
+    /// Scheduled checks resume from the next run of the schedule.
+    /// (`resume_monitoring` starts a monitor from saved progress instead.)
+    pub fn unpause_monitoring(&self) {
+        self.monitoring_paused.send_replace(false);
+    }
+
+    /// Whether the contract's monitors are paused
+    pub fn is_monitoring_paused(&self) -> bool {
+        *self.monitoring_paused.borrow()
+    }
+
+    /// Give notice of termination, returning when it takes effect
+    ///
+    /// The contract's monitors terminate the contract once the notice
+    /// period of its [`crate::Termination`] clause has run out, and a
+    /// notice saved to a store survives restarts. Giving notice again keeps
+    /// the first one. Fails if the contract cannot be terminated on notice.
+    pub fn give_notice(&self) -> Result<DateTime<Utc>> {
+        let termination = self.ucl.termination.as_ref();
+        let given_at = self.termination_notice.borrow().unwrap_or_else(|| self.clock.now());
+        let effective_at = termination
+            .and_then(|termination| termination.effective_after(given_at))
+            .ok_or_else(|| {
+                crate::Error::ValidationError(format!(
+                    "Contract {} has no notice period and cannot be terminated on notice",
+                    self.ucl.contract_id
+                ))
+            })?;
+        self.termination_notice.send_if_modified(|notice| {
+            let first = notice.is_none();
+            notice.get_or_insert(given_at);
+            first
+        });
+        Ok(effective_at)
+    }
+
+    fn spawn_monitor(
+        &self,
+        schedule: Schedule,
+        state: MonitorState,
+        store: Option<Arc<dyn ContractStore>>,
+    ) -> MonitorHandle {
+        let stop = Arc::new(Notify::new());
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+        if state.monitoring_paused_at.is_some() {
+            // Paused before a restart; stay paused until unpaused
+            self.monitoring_paused.send_replace(true);
+        }
+        let paused = self.monitoring_paused.subscribe();
+        if let Some(given_at) = state.notice_given_at {
+            // Notice given before a restart still runs
+            self.termination_notice.send_if_modified(|notice| {
+                let first = notice.is_none();
+                notice.get_or_insert(given_at);
+                first
+            });
+        }
+        let notice = self.termination_notice.subscribe();
+        let watch = Watch::new(
+            self.ucl.clone(),
+            self.oracles.clone(),
+            self.actions.clone(),
+            schedule,
+            state,
+            store,
+            EventSink {
+                monitor: Some(events.clone()),
+                contract: self.events.clone(),
+            },
+        )
+        .with_clock(self.clock.clone())
+        .with_history(self.history.clone())
+        .with_payments(self.payer.clone(), self.payment_retry.clone());
+        // Subscribe before the first check can run
+        for notifier in &self.notifiers {
+            forward_alerts(
+                self.ucl.contract_id.clone(),
+                events.subscribe(),
+                notifier.clone(),
+            );
+        }
+        MonitorHandle {
+            contract_id: self.ucl.contract_id.clone(),
+            stop: stop.clone(),
+            paused: self.monitoring_paused.clone(),
+            events,
+            task: tokio::spawn(watch.run(stop, paused, notice)),
+        }
+    }
+}
+
+/// Where a watch publishes its events
+#[derive(Clone)]
+pub(crate) struct EventSink {
+    /// Subscribers of the watch's [`MonitorHandle`], if it has one
+    pub monitor: Option<broadcast::Sender<MonitorEvent>>,
+    /// Subscribers of the contract, or of the shared monitor running the
+    /// watch
+    pub contract: broadcast::Sender<ContractEvent>,
+}
+
+/// Schedule and state of one monitored contract
+pub(crate) struct Watch {
+    ucl: UCLContract,
+    oracles: OracleSet,
+    actions: ActionRegistry,
+    schedule: Schedule,
+    state: MonitorState,
+    store: Option<Arc<dyn ContractStore>>,
+    events: EventSink,
+    clock: Arc<dyn Clock>,
+    /// Condition history of the contract, if it has one in memory
+    history: Option<Arc<ConditionHistory>>,
+    payer: Arc<dyn PaymentExecutor>,
+    payment_retry: RetryPolicy,
+}
+
+impl Watch {
+    pub(crate) fn new(
+        ucl: UCLContract,
+        mut oracles: OracleSet,
+        actions: ActionRegistry,
+        schedule: Schedule,
+        state: MonitorState,
+        store: Option<Arc<dyn ContractStore>>,
+        events: EventSink,
+    ) -> Self {
+        for (from, to) in &state.oracle_fallbacks {
+            // A fallback no longer registered leaves the original in place
+            let _ = oracles.reroute(from, to);
+        }
+        Self {
+            ucl,
+            oracles,
+            actions,
+            schedule,
+            state,
+            store,
+            events,
+            clock: Arc::new(SystemClock),
+            history: None,
+            payer: Arc::new(PlaceholderExecutor),
+            payment_retry: RetryPolicy::default(),
+        }
+    }
+
+    /// Read the time from `clock` instead of the wall clock
+    pub(crate) fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Add condition results to `history` as well as to the store
+    pub(crate) fn with_history(mut self, history: Arc<ConditionHistory>) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    /// Make payments with `payer`, retrying failed ones on `retry`
+    pub(crate) fn with_payments(mut self, payer: Arc<dyn PaymentExecutor>, retry: RetryPolicy) -> Self {
+        self.payer = payer;
+        self.payment_retry = retry;
+        self
+    }
+
+    /// Id of the watched contract
+    pub(crate) fn contract_id(&self) -> &str {
+        &self.ucl.contract_id
+    }
+
+    async fn run(
+        mut self,
+        stop: Arc<Notify>,
+        mut paused: watch::Receiver<bool>,
+        mut notice: watch::Receiver<Option<DateTime<Utc>>>,
+    ) {
+        self.set_paused(*paused.borrow_and_update());
+        if let Some(given_at) = *notice.borrow_and_update() {
+            self.give_notice(given_at);
+        }
+        let clock = self.clock.clone();
+        while let Some(next) = self.next_check() {
+            tokio::select! {
+                _ = clock.sleep_until(next) => {}
+                Ok(()) = paused.changed() => {
+                    let pause = *paused.borrow_and_update();
+                    self.set_paused(pause);
+                    continue;
+                }
+                Ok(()) = notice.changed() => {
+                    if let Some(given_at) = *notice.borrow_and_update() {
+                        self.give_notice(given_at);
+                    }
+                    continue;
+                }
+                _ = stop.notified() => return,
+            }
+            if !self.run_due().await {
+                return;
+            }
+        }
+    }
+
+    /// When the next check is due, scheduling it if needed
+    ///
+    /// `None` once the contract has expired or terminated, or the schedule
+    /// has no more runs. A pending termination is due when it takes effect.
+    pub(crate) fn next_check(&mut self) -> Option<DateTime<Utc>> {
+        if self.state.expired_at.is_some() || self.state.terminated_at.is_some() {
+            return None;
+        }
+        let scheduled = match self.state.next_run {
+            Some(next) => Some(next),
+            None => self.schedule.next_after(self.clock.now()),
+        };
+        let next = scheduled.into_iter().chain(self.terminates_at()).min()?;
+        self.state.next_run = Some(next);
+        self.persist();
+        Some(next)
+    }
+
+    /// Run the due check, returning whether another one is scheduled
+    pub(crate) async fn run_due(&mut self) -> bool {
+        let now = self.clock.now();
+        if let Some(expired_at) = self.ucl.expires_at().filter(|end| *end <= now) {
+            self.state.expired_at = Some(expired_at);
+            self.state.next_run = None;
+            self.emit(MonitorEvent::Expired { expired_at });
+            self.persist();
+            return false;
+        }
+        if let (Some(given_at), Some(end)) = (self.state.notice_given_at, self.terminates_at()) {
+            if end <= now {
+                self.terminate(end, TerminationReason::Notice { given_at });
+                return false;
+            }
+        }
+
+        // A paused watch keeps its schedule but skips the checks
+        if self.state.monitoring_paused_at.is_none() {
+            self.check().await;
+            self.state.last_run = Some(self.clock.now());
+        }
+        if self.state.terminated_at.is_some() {
+            return false;
+        }
+        self.state.next_run = self.schedule.next_after(self.clock.now());
+        if self.state.next_run.is_none() {
+            self.persist();
+            return false;
+        }
+        true
+    }
+
+    /// When notice given takes effect, if it was given
+    fn terminates_at(&self) -> Option<DateTime<Utc>> {
+        self.ucl
+            .termination
+            .as_ref()?
+            .effective_after(self.state.notice_given_at?)
+    }
+
+    /// Record notice of termination given at `given_at`, unless notice was
+    /// already given or the contract cannot be terminated on notice
+    pub(crate) fn give_notice(&mut self, given_at: DateTime<Utc>) {
+        if self.state.notice_given_at.is_some() || self.state.terminated_at.is_some() {
+            return;
+        }
+        self.state.notice_given_at = Some(given_at);
+        let Some(effective_at) = self.terminates_at() else {
+            self.state.notice_given_at = None;
+            return;
+        };
+        self.emit(MonitorEvent::NoticeGiven {
+            given_at,
+            effective_at,
+        });
+        self.persist();
+    }
+
+    /// Condition named for cause that failed in `result`, if any
+    ///
+    /// Conditions whose oracle could not be read do not count.
+    fn cause(&self, result: &ConditionCheckResult) -> Option<String> {
+        let termination = self.ucl.termination.as_ref()?;
+        termination
+            .for_cause
+            .iter()
+            .find(|id| {
+                let unreadable = self
+                    .ucl
+                    .conditions
+                    .required
+                    .iter()
+                    .chain(self.ucl.conditions.optional.iter().flatten())
+                    .any(|condition| {
+                        condition.id == **id && result.unavailable.contains(&condition.source)
+                    });
+                result.conditions.get(*id) == Some(&false) && !unreadable
+            })
+            .cloned()
+    }
+
+    /// End the contract at `at`, settling the last payment as its
+    /// termination clause says
+    fn terminate(&mut self, at: DateTime<Utc>, reason: TerminationReason) {
+        let termination = self.ucl.termination.clone().unwrap_or_default();
+        let last_payment = self
+            .state
+            .payment_attempts
+            .iter()
+            .rev()
+            .find(|attempt| attempt.transaction_hash.is_some());
+        let refund = match (last_payment, self.state.next_payment_due) {
+            (Some(paid), Some(until)) => termination
+                .refund(paid.amount, paid.at, until, at)
+                .round_dp(paid.token.decimals()),
+            _ => Amount::ZERO,
+        };
+        tracing::info!(contract_id = %self.ucl.contract_id, reason = %reason, "contract terminated");
+        self.state.terminated_at = Some(at);
+        self.state.next_run = None;
+        self.emit(MonitorEvent::Terminated {
+            terminated_at: at,
+            reason,
+            outstanding: termination.outstanding,
+            refund,
+        });
+        self.persist();
+    }
+
+    /// Pause or resume checks, keeping the schedule
+    ///
+    /// On resume, conditions failing since before the pause are treated as
+    /// failing since that much later, so the pause does not count towards
+    /// their grace periods and escalation delays.
+    pub(crate) fn set_paused(&mut self, paused: bool) {
+        let now = self.clock.now();
+        match (paused, self.state.monitoring_paused_at) {
+            (true, None) => {
+                self.state.monitoring_paused_at = Some(now);
+                self.emit(MonitorEvent::MonitoringPaused { paused_at: now });
+            }
+            (false, Some(paused_at)) => {
+                let pause = now - paused_at;
+                for since in self.state.breached_since.values_mut() {
+                    *since += pause;
+                }
+                for alert in self.state.alerts.values_mut() {
+                    alert.since += pause;
+                }
+                self.state.monitoring_paused_at = None;
+                self.emit(MonitorEvent::MonitoringResumed {
+                    paused_at,
+                    resumed_at: now,
+                });
+            }
+            _ => return,
+        }
+        self.persist();
+    }
+
+    /// Check conditions once and fire the rules that apply
+    #[tracing::instrument(name = "monitor_check", skip_all, fields(contract_id = %self.ucl.contract_id))]
+    async fn check(&mut self) {
+        let timeline = Timeline {
+            now: self.clock.now(),
+            next_payment_due: self.state.next_payment_due,
+            breached_since: Some(&self.state.breached_since),
+        };
+        let explanation = self.oracles.explain_at(&self.ucl, &timeline).await;
+        self.record_conditions(&explanation);
+        let result = explanation.result;
+        for (id, met) in &result.conditions {
+            if *met {
+                self.state.breached_since.remove(id);
+            } else {
+                self.state
+                    .breached_since
+                    .entry(id.clone())
+                    .or_insert(result.timestamp);
+            }
+        }
+        let oracle_failures = self.state.oracle_failures.clone();
+        self.state
+            .oracle_failures
+            .retain(|id, _| result.unavailable.contains(id));
+        for id in &result.unavailable {
+            *self.state.oracle_failures.entry(id.clone()).or_default() += 1;
+        }
+        let payment_failures = self.state.payment_failures;
+        let previous = self.state.last_check.replace(result.clone());
+        self.emit(MonitorEvent::Checked(result.clone()));
+        if let Some(condition_id) = self.cause(&result) {
+            self.terminate(result.timestamp, TerminationReason::ForCause { condition_id });
+            return;
+        }
+        self.escalate(&result);
+        self.release_hold();
+
+        let rules = self.ucl.rules.clone();
+        for rule in &rules {
+            let holds = result.satisfies(&rule.conditions);
+            let held = previous
+                .as_ref()
+                .is_some_and(|previous| previous.satisfies(&rule.conditions));
+            match rule.trigger.parse::<Trigger>() {
+                Ok(trigger) if !trigger.fires(held, holds) => continue,
+                Ok(_) => {
+                    for action in &rule.actions {
+                        let outcome = self.execute(rule, action, &result).await;
+                        self.log(rule, &action.action, outcome);
+                    }
+                }
+                Err(e) if holds => {
+                    for action in &rule.actions {
+                        let outcome = ActionOutcome::Skipped {
+                            reason: e.to_string(),
+                        };
+                        self.log(rule, &action.action, outcome);
+                    }
+                }
+                Err(_) => {}
+            }
+        }
+
+        // Failure triggers see this check's failures, payments included
+        for rule in &rules {
+            let Ok(trigger) = rule.trigger.parse::<Trigger>() else {
+                continue;
+            };
+            let (before, after) = match trigger {
+                Trigger::OracleFailure => {
+                    let source = rule.source.as_deref();
+                    (
+                        consecutive_failures(&oracle_failures, source),
+                        consecutive_failures(&self.state.oracle_failures, source),
+                    )
+                }
+                Trigger::PaymentFailure => (payment_failures, self.state.payment_failures),
+                _ => continue,
+            };
+            let threshold = rule.after_failures.unwrap_or(1);
+            if trigger.fires_on_failures(before, after, threshold)
+                && result.satisfies(&rule.conditions)
+            {
+                for action in &rule.actions {
+                    let outcome = self.execute(rule, action, &result).await;
+                    self.log(rule, &action.action, outcome);
+                }
+            }
+        }
+    }
+
+    /// Release a hold placed by an `oracle_failure` rule once its oracles
+    /// respond again
+    fn release_hold(&mut self) {
+        let Some(hold) = &self.state.held else {
+            return;
+        };
+        let recovered = self.ucl.rules.iter().any(|rule| {
+            rule.rule_id == hold.rule_id
+                && matches!(rule.trigger.parse(), Ok(Trigger::OracleFailure))
+                && consecutive_failures(&self.state.oracle_failures, rule.source.as_deref()) == 0
+        });
+        if recovered {
+            let rule_id = hold.rule_id.clone();
+            self.state.held = None;
+            self.emit(MonitorEvent::PaymentsReleased { rule_id });
+        }
+    }
+
+    /// Take the escalation steps that fell due and clear recovered alerts
+    fn escalate(&mut self, result: &ConditionCheckResult) {
+        let policy = self
+            .ucl
+            .escalation
+            .clone()
+            .unwrap_or_else(EscalationPolicy::alert_once);
+        let mut conditions: Vec<_> = result.conditions.iter().collect();
+        conditions.sort();
+        for (id, met) in conditions {
+            if *met {
+                if let Some(alert) = self.state.alerts.remove(id) {
+                    if !alert.escalated.is_empty() {
+                        self.emit(MonitorEvent::ConditionResolved {
+                            condition_id: id.clone(),
+                            failing_since: alert.since,
+                            resolved_at: result.timestamp,
+                        });
+                    }
+                }
+                continue;
+            }
+
+            let alert = self
+                .state
+                .alerts
+                .entry(id.clone())
+                .or_insert_with(|| AlertState::new(result.timestamp));
+            alert.failures += 1;
+            let due = policy.due(alert, result.timestamp);
+            alert.escalated.extend(&due);
+            let (failures, since) = (alert.failures, alert.since);
+
+            for step in due.into_iter().map(|index| &policy.steps[index]) {
+                match step.action.as_str() {
+                    "notify" => self.emit(MonitorEvent::ConditionFailed {
+                        condition_id: id.clone(),
+                        checked_at: result.timestamp,
+                        failures,
+                        failing_since: since,
+                        channel: step.channel.clone(),
+                        recipients: step.recipients(&self.ucl),
+                    }),
+                    "pause_contract" if self.state.paused_at.is_none() => {
+                        let rule_id = format!("escalation:{}", id);
+                        self.state.paused_at = Some(result.timestamp);
+                        self.emit(MonitorEvent::Paused {
+                            rule_id: rule_id.clone(),
+                            paused_at: result.timestamp,
+                        });
+                        self.record(ActionRecord {
+                            rule_id,
+                            action: step.action.clone(),
+                            at: result.timestamp,
+                            outcome: ActionOutcome::Executed {
+                                detail: Some(format!(
+                                    "{} failed {} consecutive checks",
+                                    id, failures
+                                )),
+                            },
+                            evidence: Vec::new(),
+                        });
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Run one action of a rule whose trigger fired
+    async fn execute(
+        &mut self,
+        rule: &RuleDefinition,
+        action: &ActionDefinition,
+        result: &ConditionCheckResult,
+    ) -> ActionOutcome {
+        let fired = MonitorEvent::ActionFired {
+            rule_id: rule.rule_id.clone(),
+            action: action.clone(),
+        };
+        let message = || {
+            action
+                .params
+                .get("message")
+                .and_then(|message| message.as_str())
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("Rule {} fired", rule.name))
+        };
+        let now = self.clock.now();
+        match action.action.as_str() {
+            "send_payment" | "execute_payment" => {
+                let waived = self.ucl.termination.as_ref().is_some_and(|termination| {
+                    termination.outstanding == OutstandingPayments::Waived
+                });
+                if let Some(given_at) = self.state.notice_given_at.filter(|_| waived) {
+                    return ActionOutcome::Skipped {
+                        reason: format!(
+                            "termination notice was given {}; outstanding payments are waived",
+                            given_at.to_rfc3339()
+                        ),
+                    };
+                }
+                if let Some(paused_at) = self.state.paused_at {
+                    return ActionOutcome::Skipped {
+                        reason: format!("contract is paused since {}", paused_at.to_rfc3339()),
+                    };
+                }
+                if let Some(hold) = &self.state.held {
+                    return ActionOutcome::Skipped {
+                        reason: format!(
+                            "payments are held by rule {} since {}",
+                            hold.rule_id,
+                            hold.since.to_rfc3339()
+                        ),
+                    };
+                }
+                if let Some(due) = self.state.next_payment_due.filter(|due| *due > now) {
+                    return ActionOutcome::Skipped {
+                        reason: format!("next payment is due {}", due.to_rfc3339()),
+                    };
+                }
+                self.emit(fired);
+                let payment = match self.pay(rule).await {
+                    Ok(payment) => payment,
+                    Err(error) => {
+                        self.state.payment_failures += 1;
+                        return ActionOutcome::Failed { error };
+                    }
+                };
+                self.state.payment_failures = 0;
+                self.state.next_payment_due = self.ucl.payment.frequency.next_occurrence(now);
+                ActionOutcome::Executed {
+                    detail: Some(format!("transaction {}", payment.transaction_hash)),
+                }
+            }
+            "notify" | "escalate" => {
+                let severity = action
+                    .params
+                    .get("severity")
+                    .and_then(|severity| serde_json::from_value(severity.clone()).ok())
+                    .unwrap_or(if action.action == "escalate" {
+                        Severity::Critical
+                    } else {
+                        Severity::Warning
+                    });
+                let to: Vec<String> = action
+                    .params
+                    .get("to")
+                    .and_then(|to| serde_json::from_value(to.clone()).ok())
+                    .unwrap_or_default();
+                self.emit(fired);
+                self.emit(MonitorEvent::Notified {
+                    rule_id: rule.rule_id.clone(),
+                    message: message(),
+                    severity,
+                    recipients: super::escalation::recipients(&self.ucl, &to),
+                });
+                ActionOutcome::Executed { detail: None }
+            }
+            "apply_late_fee" => {
+                // Without an amount, the fee of the contract's late payment terms
+                let fee = match action.params.get("amount") {
+                    Some(fee) => serde_json::from_value::<Amount>(fee.clone()).ok(),
+                    None => self
+                        .ucl
+                        .payment
+                        .late_payment
+                        .as_ref()
+                        .map(|terms| terms.fee_on(self.ucl.payment.amount)),
+                };
+                let Some(fee) = fee else {
+                    return ActionOutcome::Failed {
+                        error: "apply_late_fee needs a decimal `amount` or late payment terms"
+                            .to_string(),
+                    };
+                };
+                self.emit(fired);
+                self.state.late_fees += fee;
+                self.emit(MonitorEvent::LateFeeApplied {
+                    rule_id: rule.rule_id.clone(),
+                    fee,
+                    total: self.state.late_fees,
+                });
+                ActionOutcome::Executed {
+                    detail: Some(format!(
+                        "{} {} added to the next payment",
+                        fee, self.ucl.payment.token
+                    )),
+                }
+            }
+            "pause_contract" => {
+                if let Some(paused_at) = self.state.paused_at {
+                    return ActionOutcome::Skipped {
+                        reason: format!("contract is paused since {}", paused_at.to_rfc3339()),
+                    };
+                }
+                self.emit(fired);
+                self.state.paused_at = Some(now);
+                self.emit(MonitorEvent::Paused {
+                    rule_id: rule.rule_id.clone(),
+                    paused_at: now,
+                });
+                ActionOutcome::Executed { detail: None }
+            }
+            "resume_contract" => {
+                if self.state.paused_at.is_none() && self.state.held.is_none() {
+                    return ActionOutcome::Skipped {
+                        reason: "contract is not paused".to_string(),
+                    };
+                }
+                self.emit(fired);
+                self.state.paused_at = None;
+                self.state.held = None;
+                self.emit(MonitorEvent::Resumed {
+                    rule_id: rule.rule_id.clone(),
+                });
+                ActionOutcome::Executed { detail: None }
+            }
+            "hold_payments" => {
+                if let Some(hold) = &self.state.held {
+                    return ActionOutcome::Skipped {
+                        reason: format!(
+                            "payments are held by rule {} since {}",
+                            hold.rule_id,
+                            hold.since.to_rfc3339()
+                        ),
+                    };
+                }
+                self.emit(fired);
+                self.state.held = Some(PaymentHold {
+                    rule_id: rule.rule_id.clone(),
+                    since: now,
+                });
+                self.emit(MonitorEvent::PaymentsHeld {
+                    rule_id: rule.rule_id.clone(),
+                    held_at: now,
+                });
+                ActionOutcome::Executed { detail: None }
+            }
+            "switch_oracle" => {
+                let param = |name: &str| {
+                    action
+                        .params
+                        .get(name)
+                        .and_then(|value| value.as_str())
+                        .map(str::to_string)
+                };
+                let (Some(from), Some(to)) =
+                    (param("from").or_else(|| rule.source.clone()), param("to"))
+                else {
+                    return ActionOutcome::Failed {
+                        error:
+                            "switch_oracle needs a `to` oracle and a `from` oracle or rule source"
+                                .to_string(),
+                    };
+                };
+                if self.state.oracle_fallbacks.get(&from) == Some(&to) {
+                    return ActionOutcome::Skipped {
+                        reason: format!("{} already reads from {}", from, to),
+                    };
+                }
+                if let Err(e) = self.oracles.reroute(&from, &to) {
+                    return ActionOutcome::Failed {
+                        error: e.to_string(),
+                    };
+                }
+                self.emit(fired);
+                self.state.oracle_failures.remove(&from);
+                self.state.oracle_fallbacks.insert(from.clone(), to.clone());
+                self.emit(MonitorEvent::OracleSwitched {
+                    rule_id: rule.rule_id.clone(),
+                    from: from.clone(),
+                    to: to.clone(),
+                });
+                ActionOutcome::Executed {
+                    detail: Some(format!("{} now reads from {}", from, to)),
+                }
+            }
+            name => {
+                self.emit(fired);
+                // Without a registered action, carried out by subscribers
+                let Some(registered) = self.actions.get(name).cloned() else {
+                    return ActionOutcome::Executed { detail: None };
+                };
+                let context = ActionContext {
+                    contract: &self.ucl,
+                    rule,
+                    params: &action.params,
+                    check: result,
+                };
+                match registered.run(&context).await {
+                    Ok(detail) => ActionOutcome::Executed { detail },
+                    Err(e) => ActionOutcome::Failed {
+                        error: e.to_string(),
+                    },
+                }
+            }
+        }
+    }
+
+    /// Make the contract's payment, retrying failed attempts
+    ///
+    /// Fails with the last attempt's error once the retries are used up, or
+    /// at once for errors that are not [retryable](crate::Error::is_retryable),
+    /// escalating the payment.
+    async fn pay(&mut self, rule: &RuleDefinition) -> std::result::Result<PaymentResult, String> {
+        // Late fees ride on the payment
+        let mut ucl = self.ucl.clone();
+        ucl.payment.amount += self.state.late_fees;
+        let mut attempt = 1;
+        loop {
+            let mut permanent = false;
+            let mut retry_after = None;
+            let error = match self.payer.pay(&ucl).await {
+                Ok(payment) if payment.success => {
+                    self.record_attempt(rule, attempt, Ok(&payment.transaction_hash));
+                    self.state.late_fees = Amount::ZERO;
+                    self.emit(MonitorEvent::PaymentExecuted(payment.clone()));
+                    return Ok(payment);
+                }
+                Ok(payment) => {
+                    let error = format!(
+                        "payment of {} {} did not go through",
+                        payment.amount, payment.token
+                    );
+                    if attempt >= self.payment_retry.max_attempts {
+                        self.emit(MonitorEvent::PaymentExecuted(payment));
+                    }
+                    error
+                }
+                Err(e) => {
+                    permanent = !e.is_retryable();
+                    retry_after = e.retry_after();
+                    e.to_string()
+                }
+            };
+            self.record_attempt(rule, attempt, Err(&error));
+            if permanent || attempt >= self.payment_retry.max_attempts {
+                tracing::warn!(rule_id = %rule.rule_id, attempts = attempt, error = %error, "payment escalated");
+                self.emit(MonitorEvent::PaymentEscalated {
+                    rule_id: rule.rule_id.clone(),
+                    attempts: attempt,
+                    error: error.clone(),
+                });
+                return Err(match attempt {
+                    1 => error,
+                    attempts => format!("{} after {} attempts", error, attempts),
+                });
+            }
+            let backoff = self.payment_retry.backoff(attempt).max(retry_after.unwrap_or_default());
+            let retry_at =
+                self.clock.now() + chrono::Duration::from_std(backoff).unwrap_or_default();
+            tracing::info!(rule_id = %rule.rule_id, attempt, error = %error, "payment failed, retrying");
+            self.emit(MonitorEvent::PaymentRetrying {
+                rule_id: rule.rule_id.clone(),
+                attempt,
+                error,
+                retry_at,
+            });
+            self.clock.sleep_until(retry_at).await;
+            attempt += 1;
+        }
+    }
+
+    /// Append a payment attempt to the state, dropping the oldest past the
+    /// limit
+    fn record_attempt(
+        &mut self,
+        rule: &RuleDefinition,
+        attempt: u32,
+        outcome: std::result::Result<&str, &str>,
+    ) {
+        self.state.payment_attempts.push(PaymentAttempt {
+            rule_id: rule.rule_id.clone(),
+            attempt,
+            at: self.clock.now(),
+            amount: self.ucl.payment.amount + self.state.late_fees,
+            token: self.ucl.payment.token.clone(),
+            transaction_hash: outcome.ok().map(str::to_string),
+            error: outcome.err().map(str::to_string),
+        });
+        let excess = self
+            .state
+            .payment_attempts
+            .len()
+            .saturating_sub(ACTION_LOG_LIMIT);
+        self.state.payment_attempts.drain(..excess);
+    }
+
+    /// Append an action's outcome to the log, publishing skips and failures
+    fn log(&mut self, rule: &RuleDefinition, action: &str, outcome: ActionOutcome) {
+        let rule_id = rule.rule_id.as_str();
+        match &outcome {
+            ActionOutcome::Executed { detail } => {
+                tracing::info!(
+                    rule_id,
+                    action,
+                    detail = detail.as_deref(),
+                    "action executed"
+                )
+            }
+            ActionOutcome::Skipped { reason } => {
+                tracing::info!(rule_id, action, reason = %reason, "action skipped");
+                self.emit(MonitorEvent::ActionSkipped {
+                    rule_id: rule.rule_id.clone(),
+                    action: action.to_string(),
+                    reason: reason.clone(),
+                })
+            }
+            ActionOutcome::Failed { error } => {
+                tracing::warn!(rule_id, action, error = %error, "action failed");
+                self.emit(MonitorEvent::ActionFailed {
+                    rule_id: rule.rule_id.clone(),
+                    action: action.to_string(),
+                    error: error.clone(),
+                })
+            }
+        }
+        // Payments keep the attested oracle values that justified them
+        let evidence = match (&outcome, action) {
+            (ActionOutcome::Executed { .. }, "send_payment" | "execute_payment") => self
+                .state
+                .last_check
+                .as_ref()
+                .map(|check| check.attestations.clone())
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        };
+        self.record(ActionRecord {
+            rule_id: rule.rule_id.clone(),
+            action: action.to_string(),
+            at: self.clock.now(),
+            outcome,
+            evidence,
+        });
+    }
+
+    /// Append to the action log, dropping the oldest records past the limit
+    fn record(&mut self, record: ActionRecord) {
+        self.state.log.push(record);
+        let excess = self.state.log.len().saturating_sub(ACTION_LOG_LIMIT);
+        self.state.log.drain(..excess);
+    }
+
+    /// Add the condition results of a check to the history
+    fn record_conditions(&self, explanation: &CheckExplanation) {
+        let records = history::records(explanation, &self.state.oracle_fallbacks);
+        if let Some(history) = &self.history {
+            history.extend(&records);
+        }
+        if let Some(store) = &self.store {
+            if let Err(e) = store.append_condition_history(&self.ucl.contract_id, &records) {
+                tracing::warn!(contract_id = %self.ucl.contract_id, error = %e, "saving condition history failed");
+                self.emit(MonitorEvent::PersistFailed {
+                    error: e.to_string(),
+                });
+            }
+        }
+    }
+
+    fn persist(&self) {
+        if let Some(store) = &self.store {
+            if let Err(e) = store.save_monitor(&self.state) {
+                tracing::warn!(contract_id = %self.ucl.contract_id, error = %e, "saving monitor state failed");
+                self.emit(MonitorEvent::PersistFailed {
+                    error: e.to_string(),
+                });
+            }
+        }
+    }
+
+    fn emit(&self, event: MonitorEvent) {
+        // Sending only fails while nobody is subscribed
+        if let Some(events) = &self.events.monitor {
+            let _ = events.send(event.clone());
+        }
+        let event = ContractEvent::new(&self.ucl.contract_id, ContractEventKind::Monitor(event));
+        if let Some(store) = &self.store {
+            // Not reported as PersistFailed, which would be logged in turn
+            if let Err(e) = store.append_event(&event) {
+                tracing::warn!(contract_id = %self.ucl.contract_id, error = %e, "saving event failed");
+            }
+        }
+        let _ = self.events.contract.send(event);
+    }
+}
+
+/// Consecutive failures of oracle `source`, or of the worst oracle
+fn consecutive_failures(failures: &HashMap<String, u32>, source: Option<&str>) -> u32 {
+    match source {
+        Some(id) => failures.get(id).copied().unwrap_or(0),
+        None => failures.values().copied().max().unwrap_or(0),
+    }
+}
+
+fn is_zero(n: &u32) -> bool {
+    *n == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::store::InMemoryContractStore;
+    use crate::types::{
+        ConditionDefinition, EscalationStep, PartyInfo, RuleConditions, TemporalCondition,
+    };
+    use crate::ContractConfig;
+
+    #[test]
+    fn test_parse_schedules() {
+        assert!(matches!(
+            Schedule::parse("medium").unwrap(),
+            Schedule::Every(d) if d == Duration::from_secs(900)
+        ));
+        assert!(matches!(
+            Schedule::parse("hourly").unwrap(),
+            Schedule::Every(d) if d == Duration::from_secs(3_600)
+        ));
+
+        let monthly = Schedule::parse("0 0 1 * *").unwrap();
+        let after: DateTime<Utc> = "2024-05-15T13:45:00Z".parse().unwrap();
+        assert_eq!(
+            monthly.next_after(after).unwrap().to_rfc3339(),
+            "2024-06-01T00:00:00+00:00"
+        );
+
+        assert!(Schedule::parse("whenever").is_err());
+        assert!(Schedule::parse("realtime").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_monitor_fires_rules_until_stopped() {
+        let mut contract = crate::core::fixtures::contract();
+        contract.ucl.conditions.required.push(ConditionDefinition {
+            id: "uptime_met".to_string(),
+            description: "Uptime above 99%".to_string(),
+            source: "uptime_oracle".to_string(),
+            operator: ">=".to_string(),
+            threshold: None,
+            expression: None,
+            temporal: None,
+        });
+        contract.ucl.rules.push(RuleDefinition {
+            rule_id: "monthly_payment".to_string(),
+            name: "Execute Monthly Payment".to_string(),
+            trigger: "time_based".to_string(),
+            conditions: RuleConditions {
+                all_of: Some(vec!["uptime_met == true".to_string()]),
+                any_of: None,
+                n_of_m: None,
+            },
+            actions: vec![ActionDefinition {
+                action: "execute_payment".to_string(),
+                params: HashMap::new(),
+            }],
+            ..Default::default()
+        });
+
+        contract.add_oracle(Arc::new(Fixed("uptime_oracle", Some(serde_json::json!(true)))));
+
+        let monitor = contract.monitor_with_schedule(Schedule::Every(Duration::from_millis(10)));
+        let mut events = monitor.subscribe();
+        assert!(matches!(events.recv().await.unwrap(), MonitorEvent::Checked(r) if r.all_met));
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            MonitorEvent::ActionFired { rule_id, .. } if rule_id == "monthly_payment"
+        ));
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            MonitorEvent::PaymentExecuted(_)
+        ));
+
+        assert!(monitor.is_running());
+        monitor.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_missed_deadline_fires_late_fee_rule() {
+        let mut contract = Contract::from_config(ContractConfig::default()).unwrap();
+        contract.ucl.conditions.optional = Some(vec![ConditionDefinition {
+            id: "delivery_late".to_string(),
+            description: "Delivery is more than 5 days late".to_string(),
+            source: String::new(),
+            operator: String::new(),
+            threshold: None,
+            expression: None,
+            temporal: Some(TemporalCondition::Deadline {
+                at: "30 days".to_string(),
+                grace: Some("5 days".to_string()),
+            }),
+        }]);
+        contract.ucl.payment.late_payment = Some(crate::types::LatePaymentTerms {
+            fee: Some(Amount::from(25)),
+            ..Default::default()
+        });
+        contract.ucl.rules.push(RuleDefinition {
+            rule_id: "late_fee".to_string(),
+            name: "Apply Late Fee".to_string(),
+            trigger: "time_based".to_string(),
+            conditions: RuleConditions {
+                all_of: Some(vec!["delivery_late".to_string()]),
+                any_of: None,
+                n_of_m: None,
+            },
+            actions: vec![ActionDefinition {
+                action: "apply_late_fee".to_string(),
+                params: HashMap::new(),
+            }],
+            ..Default::default()
+        });
+
+        let monitor = contract.monitor_with_schedule(Schedule::Every(Duration::from_millis(10)));
+        let mut events = monitor.subscribe();
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            MonitorEvent::Checked(r) if r.all_met && r.conditions["delivery_late"]
+        ));
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            MonitorEvent::ActionFired { rule_id, action }
+                if rule_id == "late_fee" && action.action == "apply_late_fee"
+        ));
+        monitor.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_rule_actions_are_executed_and_logged() {
+        let mut contract = Contract::from_config(ContractConfig::default()).unwrap();
+        let rule =
+            |rule_id: &str, trigger: &str, actions: &[(&str, Option<&str>)]| RuleDefinition {
+                rule_id: rule_id.to_string(),
+                name: rule_id.to_string(),
+                trigger: trigger.to_string(),
+                conditions: RuleConditions {
+                    all_of: None,
+                    any_of: None,
+                    n_of_m: None,
+                },
+                actions: actions
+                    .iter()
+                    .map(|(action, message)| ActionDefinition {
+                        action: action.to_string(),
+                        params: message
+                            .map(|m| HashMap::from([("message".to_string(), m.into())]))
+                            .unwrap_or_default(),
+                    })
+                    .collect(),
+                ..Default::default()
+            };
+        contract.ucl.rules.push(rule(
+            "breach",
+            "condition_change",
+            &[("escalate", Some("SLA breached")), ("pause_contract", None)],
+        ));
+        contract
+            .ucl
+            .rules
+            .push(rule("payment", "time_based", &[("send_payment", None)]));
+        let store = Arc::new(InMemoryContractStore::new());
+
+        let monitor = contract
+            .resume_monitoring(Schedule::parse("10ms").unwrap(), store.clone())
+            .unwrap();
+        let mut events = monitor.subscribe();
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            MonitorEvent::Checked(_)
+        ));
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            MonitorEvent::ActionFired { .. }
+        ));
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            MonitorEvent::Notified { message, severity: Severity::Critical, .. } if message == "SLA breached"
+        ));
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            MonitorEvent::ActionFired { .. }
+        ));
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            MonitorEvent::Paused { .. }
+        ));
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            MonitorEvent::ActionSkipped { rule_id, .. } if rule_id == "payment"
+        ));
+
+        // The breach rule fires once; the paused payment keeps being skipped
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            MonitorEvent::Checked(_)
+        ));
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            MonitorEvent::ActionSkipped { rule_id, .. } if rule_id == "payment"
+        ));
+        monitor.stop().await;
+
+        let state = store
+            .load_monitor(&contract.ucl.contract_id)
+            .unwrap()
+            .unwrap();
+        assert!(state.paused_at.is_some());
+        let actions: Vec<_> = state.log.iter().map(|r| r.action.as_str()).collect();
+        assert_eq!(
+            &actions[..4],
+            ["escalate", "pause_contract", "send_payment", "send_payment"]
+        );
+        assert!(matches!(
+            state.log[2].outcome,
+            ActionOutcome::Skipped { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_resumed_monitor_keeps_payment_obligations() {
+        let mut contract = crate::core::fixtures::contract();
+        contract.ucl.payment.frequency = crate::PaymentFrequency::Monthly { day: None };
+        contract.ucl.rules.push(RuleDefinition {
+            rule_id: "monthly_payment".to_string(),
+            name: "Execute Monthly Payment".to_string(),
+            trigger: "time_based".to_string(),
+            conditions: RuleConditions {
+                all_of: None,
+                any_of: None,
+                n_of_m: None,
+            },
+            actions: vec![ActionDefinition {
+                action: "execute_payment".to_string(),
+                params: HashMap::new(),
+            }],
+            ..Default::default()
+        });
+        let store = Arc::new(InMemoryContractStore::new());
+        let schedule = || Schedule::parse("10ms").unwrap();
+
+        let monitor = contract
+            .resume_monitoring(schedule(), store.clone())
+            .unwrap();
+        let mut events = monitor.subscribe();
+        while !matches!(
+            events.recv().await.unwrap(),
+            MonitorEvent::PaymentExecuted(_)
+        ) {}
+        monitor.stop().await;
+
+        let saved = store
+            .load_monitor(&contract.ucl.contract_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(saved.schedule, "10ms");
+        assert!(saved.last_check.is_some());
+        assert!(saved.next_payment_due.unwrap() > Utc::now() + chrono::Duration::days(29));
+
+        let monitor = contract.resume_monitoring(schedule(), store).unwrap();
+        let mut events = monitor.subscribe();
+        loop {
+            match events.recv().await.unwrap() {
+                MonitorEvent::ActionSkipped { rule_id, .. } => {
+                    assert_eq!(rule_id, "monthly_payment");
+                    break;
+                }
+                MonitorEvent::PaymentExecuted(_) => panic!("paid twice in one period"),
+                _ => {}
+            }
+        }
+        monitor.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_payment_keeps_attested_oracle_values() {
+        let mut contract = crate::core::fixtures::contract();
+        contract.ucl.conditions.required.push(ConditionDefinition {
+            id: "uptime_met".to_string(),
+            description: "Uptime above 99%".to_string(),
+            source: "uptime_oracle".to_string(),
+            operator: ">=".to_string(),
+            threshold: Some(serde_json::json!(99.0)),
+            expression: None,
+            temporal: None,
+        });
+        contract.ucl.rules.push(RuleDefinition {
+            rule_id: "monthly_payment".to_string(),
+            name: "Execute Monthly Payment".to_string(),
+            trigger: "condition_met".to_string(),
+            actions: vec![ActionDefinition {
+                action: "execute_payment".to_string(),
+                params: HashMap::new(),
+            }],
+            ..Default::default()
+        });
+        contract.add_oracle(Arc::new(Fixed(
+            "uptime_oracle",
+            Some(serde_json::json!(99.95)),
+        )));
+        let signer = crate::x402::Secp256k1Signer::from_private_key(
+            "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318",
+        )
+        .unwrap();
+        contract.set_oracle_attestor(Arc::new(signer.clone()));
+        let store = Arc::new(InMemoryContractStore::new());
+
+        let monitor = contract
+            .resume_monitoring(Schedule::parse("10ms").unwrap(), store.clone())
+            .unwrap();
+        let mut events = monitor.subscribe();
+        while !matches!(
+            events.recv().await.unwrap(),
+            MonitorEvent::PaymentExecuted(_)
+        ) {}
+        monitor.stop().await;
+
+        let saved = store
+            .load_monitor(&contract.ucl.contract_id)
+            .unwrap()
+            .unwrap();
+        let payment = saved
+            .log
+            .iter()
+            .find(|record| record.action == "execute_payment")
+            .unwrap();
+        assert_eq!(payment.evidence, saved.last_check.unwrap().attestations);
+        let [attestation] = payment.evidence.as_slice() else {
+            panic!("expected one attestation, got {:?}", payment.evidence);
+        };
+        assert_eq!(attestation.oracle_id, "uptime_oracle");
+        assert_eq!(attestation.value, serde_json::json!(99.95));
+        assert_eq!(attestation.signer, signer.address());
+        attestation.verify().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_failing_condition_escalates_once_per_step() {
+        let mut contract = Contract::from_config(ContractConfig::default()).unwrap();
+        contract.ucl.conditions.required.push(ConditionDefinition {
+            id: "uptime_met".to_string(),
+            description: "Uptime above 99%".to_string(),
+            source: String::new(),
+            operator: String::new(),
+            threshold: None,
+            expression: Some("0.95 > 0.99".to_string()),
+            temporal: None,
+        });
+        contract.ucl.metadata.parties.push(PartyInfo {
+            role: "vendor".to_string(),
+            identifier: "billing@vendor.example".to_string(),
+            name: None,
+            ..Default::default()
+        });
+        let notify = |after_failures: Option<u32>, channel: &str, to: &[&str]| EscalationStep {
+            after_failures,
+            action: "notify".to_string(),
+            channel: Some(channel.to_string()),
+            to: to.iter().map(|to| to.to_string()).collect(),
+            ..Default::default()
+        };
+        contract.ucl.escalation = Some(EscalationPolicy {
+            steps: vec![
+                notify(None, "webhook", &[]),
+                notify(Some(3), "email", &["vendor"]),
+                EscalationStep {
+                    after_failures: Some(4),
+                    action: "pause_contract".to_string(),
+                    ..Default::default()
+                },
+            ],
+        });
+
+        let store = Arc::new(InMemoryContractStore::new());
+        let monitor = contract
+            .resume_monitoring(Schedule::Every(Duration::from_millis(10)), store.clone())
+            .unwrap();
+        let mut events = monitor.subscribe();
+        let mut alerts = Vec::new();
+        while alerts.len() < 3 {
+            match events.recv().await.unwrap() {
+                MonitorEvent::ConditionFailed {
+                    failures,
+                    channel,
+                    recipients,
+                    ..
+                } => alerts.push((failures, channel.unwrap(), recipients)),
+                MonitorEvent::Paused { rule_id, .. } => alerts.push((4, rule_id, Vec::new())),
+                _ => {}
+            }
+        }
+        // Later checks add no alerts
+        let mut checks = 0;
+        while checks < 2 {
+            match events.recv().await.unwrap() {
+                MonitorEvent::Checked(_) => checks += 1,
+                MonitorEvent::ConditionFailed { .. } | MonitorEvent::Paused { .. } => {
+                    panic!("escalation step taken twice")
+                }
+                _ => {}
+            }
+        }
+        monitor.stop().await;
+
+        assert_eq!(
+            alerts,
+            vec![
+                (1, "webhook".to_string(), vec![]),
+                (
+                    3,
+                    "email".to_string(),
+                    vec!["billing@vendor.example".to_string()]
+                ),
+                (4, "escalation:uptime_met".to_string(), vec![]),
+            ]
+        );
+        let state = store
+            .load_monitor(&contract.ucl.contract_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(state.alerts["uptime_met"].escalated, vec![0, 1, 2]);
+        assert!(state.paused_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_paused_monitor_skips_checks_and_keeps_breach_timing() {
+        let mut contract = Contract::from_config(ContractConfig::default()).unwrap();
+        contract.ucl.conditions.required.push(ConditionDefinition {
+            id: "uptime_met".to_string(),
+            description: "Uptime above 99%".to_string(),
+            source: String::new(),
+            operator: String::new(),
+            threshold: None,
+            expression: Some("0.95 > 0.99".to_string()),
+            temporal: None,
+        });
+        let store = Arc::new(InMemoryContractStore::new());
+        let monitor = contract
+            .resume_monitoring(Schedule::Every(Duration::from_millis(10)), store.clone())
+            .unwrap();
+        let mut events = monitor.subscribe();
+        let failing_since = loop {
+            if let MonitorEvent::Checked(result) = events.recv().await.unwrap() {
+                break result.timestamp;
+            }
+        };
+
+        contract.pause_monitoring();
+        assert!(monitor.is_paused());
+        while !matches!(
+            events.recv().await.unwrap(),
+            MonitorEvent::MonitoringPaused { .. }
+        ) {}
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        while let Ok(event) = events.try_recv() {
+            assert!(
+                !matches!(event, MonitorEvent::Checked(_)),
+                "checked while paused"
+            );
+        }
+        let state = store.load_monitor(monitor.contract_id()).unwrap().unwrap();
+        assert!(state.monitoring_paused_at.is_some());
+
+        monitor.resume();
+        let (paused_at, resumed_at) = loop {
+            if let MonitorEvent::MonitoringResumed {
+                paused_at,
+                resumed_at,
+            } = events.recv().await.unwrap()
+            {
+                break (paused_at, resumed_at);
+            }
+        };
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            MonitorEvent::Checked(_)
+        ));
+        monitor.stop().await;
+
+        let state = store
+            .load_monitor(&contract.ucl.contract_id)
+            .unwrap()
+            .unwrap();
+        assert!(state.monitoring_paused_at.is_none());
+        assert_eq!(
+            state.alerts["uptime_met"].since,
+            failing_since + (resumed_at - paused_at)
+        );
+    }
+
+    /// Oracle reading a fixed value, or failing without one
+    struct Fixed(&'static str, Option<serde_json::Value>);
+
+    #[async_trait::async_trait]
+    impl crate::Oracle for Fixed {
+        fn id(&self) -> &str {
+            self.0
+        }
+
+        async fn read(&self) -> Result<serde_json::Value> {
+            self.1
+                .clone()
+                .ok_or_else(|| crate::Error::NetworkError("oracle down".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_oracle_failure_holds_payments_and_switches_to_fallback() {
+        let mut contract = Contract::from_config(ContractConfig::default()).unwrap();
+        contract.add_oracle(Arc::new(Fixed("uptime_oracle", None)));
+        contract.add_oracle(Arc::new(Fixed(
+            "uptime_backup",
+            Some(serde_json::json!(0.999)),
+        )));
+        contract.ucl.conditions.required.push(ConditionDefinition {
+            id: "uptime_met".to_string(),
+            description: "Uptime above 99%".to_string(),
+            source: "uptime_oracle".to_string(),
+            operator: ">=".to_string(),
+            threshold: Some(serde_json::json!(0.99)),
+            expression: None,
+            temporal: None,
+        });
+        let action = |action: &str, params: &[(&str, &str)]| ActionDefinition {
+            action: action.to_string(),
+            params: params
+                .iter()
+                .map(|(name, value)| (name.to_string(), (*value).into()))
+                .collect(),
+        };
+        contract.ucl.rules.push(RuleDefinition {
+            rule_id: "oracle_down".to_string(),
+            name: "Fall back when the uptime oracle is down".to_string(),
+            trigger: "oracle_failure".to_string(),
+            actions: vec![
+                action("switch_oracle", &[("to", "uptime_backup")]),
+                action("hold_payments", &[]),
+            ],
+            after_failures: Some(2),
+            source: Some("uptime_oracle".to_string()),
+            ..Default::default()
+        });
+
+        let store = Arc::new(InMemoryContractStore::new());
+        let monitor = contract
+            .resume_monitoring(Schedule::Every(Duration::from_millis(10)), store.clone())
+            .unwrap();
+        let mut events = monitor.subscribe();
+        let mut seen = Vec::new();
+        while seen.len() < 3 {
+            match events.recv().await.unwrap() {
+                MonitorEvent::OracleSwitched { from, to, .. } => {
+                    seen.push(format!("switched {} to {}", from, to))
+                }
+                MonitorEvent::PaymentsHeld { rule_id, .. } => {
+                    seen.push(format!("held by {}", rule_id))
+                }
+                MonitorEvent::PaymentsReleased { rule_id } => {
+                    seen.push(format!("released by {}", rule_id))
+                }
+                MonitorEvent::Checked(result) if seen.is_empty() => {
+                    assert_eq!(result.unavailable, vec!["uptime_oracle"]);
+                }
+                _ => {}
+            }
+        }
+        monitor.stop().await;
+
+        assert_eq!(
+            seen,
+            vec![
+                "switched uptime_oracle to uptime_backup",
+                "held by oracle_down",
+                "released by oracle_down",
+            ]
+        );
+        let state = store
+            .load_monitor(&contract.ucl.contract_id)
+            .unwrap()
+            .unwrap();
+        assert!(state.held.is_none());
+        assert!(state.oracle_failures.is_empty());
+        assert_eq!(state.oracle_fallbacks["uptime_oracle"], "uptime_backup");
+        assert!(state.last_check.unwrap().conditions["uptime_met"]);
+    }
+
+    #[tokio::test]
+    async fn test_monitor_stops_when_contract_expires() {
+        let mut contract = Contract::from_config(ContractConfig::default()).unwrap();
+        contract.ucl.metadata.dates.renewal = crate::Renewal::None;
+        assert_eq!(
+            contract.ucl.expires_at().unwrap().to_rfc3339(),
+            "2025-01-01T00:00:00+00:00"
+        );
+
+        let monitor = contract.monitor_with_schedule(Schedule::Every(Duration::from_millis(10)));
+        let mut events = monitor.subscribe();
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            MonitorEvent::Expired { .. }
+        ));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!monitor.is_running());
+    }
+
+    #[tokio::test]
+    async fn test_monitor_terminates_for_cause_and_on_notice() {
+        let mut contract = Contract::from_config(ContractConfig::default()).unwrap();
+        assert!(contract.give_notice().is_err());
+        contract.add_oracle(Arc::new(Fixed("uptime_oracle", Some(serde_json::json!(0.5)))));
+        contract.ucl.conditions.required.push(ConditionDefinition {
+            id: "uptime_met".to_string(),
+            description: "Uptime above 99%".to_string(),
+            source: "uptime_oracle".to_string(),
+            operator: ">=".to_string(),
+            threshold: Some(serde_json::json!(0.99)),
+            expression: None,
+            temporal: None,
+        });
+        contract.ucl.termination = Some(crate::Termination {
+            notice: Some(crate::ContractDuration::Days(0)),
+            for_cause: vec!["uptime_met".to_string()],
+            outstanding: OutstandingPayments::Waived,
+        });
+
+        let monitor = contract.monitor_with_schedule(Schedule::Every(Duration::from_millis(10)));
+        let mut events = monitor.subscribe();
+        loop {
+            if let MonitorEvent::Terminated { reason, refund, .. } = events.recv().await.unwrap() {
+                assert_eq!(
+                    reason,
+                    TerminationReason::ForCause {
+                        condition_id: "uptime_met".to_string()
+                    }
+                );
+                assert!(refund.is_zero());
+                break;
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!monitor.is_running());
+
+        contract.ucl.termination.as_mut().unwrap().for_cause.clear();
+        let monitor = contract.monitor_with_schedule(Schedule::Every(Duration::from_secs(3600)));
+        let mut events = monitor.subscribe();
+        let effective_at = contract.give_notice().unwrap();
+        assert_eq!(contract.give_notice().unwrap(), effective_at);
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            MonitorEvent::NoticeGiven { effective_at: at, .. } if at == effective_at
+        ));
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            MonitorEvent::Terminated {
+                reason: TerminationReason::Notice { .. },
+                ..
+            }
+        ));
+    }
+}
@@ -0,0 +1,82 @@
+//! Signed oracle values for auditing automatic payments
+
+use crate::types::OracleAttestation;
+use crate::utils::canonical::canonical_json;
+use crate::x402::signing::{verify_signature, X402Signer};
+use crate::Result;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+impl OracleAttestation {
+    /// Sign `value` as read from oracle `oracle_id` for `contract_id`
+    pub fn sign(
+        signer: &dyn X402Signer,
+        contract_id: &str,
+        oracle_id: &str,
+        served_by: Option<&str>,
+        value: Value,
+        observed_at: DateTime<Utc>,
+    ) -> Result<Self> {
+        let mut attestation = Self {
+            contract_id: contract_id.to_string(),
+            oracle_id: oracle_id.to_string(),
+            served_by: served_by.map(str::to_string),
+            value,
+            observed_at,
+            scheme: signer.scheme(),
+            signer: signer.identity(),
+            signature: String::new(),
+        };
+        attestation.signature = signer.sign(&attestation.payload())?;
+        Ok(attestation)
+    }
+
+    /// Text the signature covers
+    pub fn payload(&self) -> String {
+        format!(
+            "smart402 oracle attestation\ncontract: {}\noracle: {}\nserved by: {}\nvalue: {}\nobserved at: {}",
+            self.contract_id,
+            self.oracle_id,
+            self.served_by.as_deref().unwrap_or(&self.oracle_id),
+            canonical_json(&self.value),
+            self.observed_at.to_rfc3339(),
+        )
+    }
+
+    /// Check that `signer` signed this value
+    pub fn verify(&self) -> Result<()> {
+        verify_signature(self.scheme, &self.payload(), &self.signature, &self.signer)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::x402::Secp256k1Signer;
+
+    const TEST_KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    #[test]
+    fn test_attestation_survives_storage_and_detects_tampering() {
+        let signer = Secp256k1Signer::from_private_key(TEST_KEY).unwrap();
+        let attestation = OracleAttestation::sign(
+            &signer,
+            "smart402:contract:abc123",
+            "uptime_oracle",
+            None,
+            serde_json::json!({"uptime": 99.95, "incidents": 0}),
+            "2024-05-01T00:00:00Z".parse().unwrap(),
+        )
+        .unwrap();
+        assert_eq!(attestation.signer, signer.address());
+
+        let stored: OracleAttestation =
+            serde_json::from_str(&serde_json::to_string(&attestation).unwrap()).unwrap();
+        stored.verify().unwrap();
+
+        let mut forged = stored.clone();
+        forged.value = serde_json::json!({"uptime": 100.0, "incidents": 0});
+        assert!(forged.verify().is_err());
+    }
+}
@@ -0,0 +1,210 @@
+//! Responses shared by oracles reading the same endpoint
+//!
+//! Contracts watching the same service each define their own oracle, so a
+//! fleet of contracts would query one status page once per contract. A
+//! [`ResponseCache`] keeps the last response of every endpoint for the
+//! reading oracle's refresh rate and backs off from endpoints that keep
+//! failing, so third-party APIs are not hammered while they are down.
+
+use crate::Result;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// How long a failing endpoint is left alone
+///
+/// Each consecutive failure doubles the wait before the next request, up
+/// to `max_backoff`. After `open_after` failures in a row the circuit
+/// opens: reads fail without a request for `open_for`, then a single
+/// request probes whether the endpoint is back.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackoffPolicy {
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: f64,
+    /// Consecutive failures that open the circuit; 0 never opens it
+    pub open_after: u32,
+    pub open_for: Duration,
+}
+
+impl Default for BackoffPolicy {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_secs(5),
+            max_backoff: Duration::from_secs(5 * 60),
+            multiplier: 2.0,
+            open_after: 5,
+            open_for: Duration::from_secs(15 * 60),
+        }
+    }
+}
+
+impl BackoffPolicy {
+    /// Wait after `failures` consecutive failures (at least one)
+    pub fn delay(&self, failures: u32) -> Duration {
+        if self.open_after > 0 && failures >= self.open_after {
+            return self.open_for;
+        }
+        let exponent = failures.saturating_sub(1) as i32;
+        self.initial_backoff
+            .mul_f64(self.multiplier.powi(exponent))
+            .min(self.max_backoff)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Entry {
+    response: Option<(Instant, Value)>,
+    failures: u32,
+    retry_at: Option<Instant>,
+    error: String,
+}
+
+/// Last responses of endpoints, with the backoff state of failing ones
+#[derive(Debug, Default)]
+pub struct ResponseCache {
+    backoff: BackoffPolicy,
+    entries: Mutex<HashMap<String, Arc<tokio::sync::Mutex<Entry>>>>,
+}
+
+impl ResponseCache {
+    /// Empty cache backing off with `backoff`
+    pub fn new(backoff: BackoffPolicy) -> Self {
+        Self {
+            backoff,
+            entries: Mutex::default(),
+        }
+    }
+
+    /// Cache shared by every oracle that is not given its own
+    pub fn global() -> Arc<Self> {
+        static GLOBAL: OnceLock<Arc<ResponseCache>> = OnceLock::new();
+        GLOBAL.get_or_init(Arc::default).clone()
+    }
+
+    /// Response for `key` no older than `ttl`, else the result of `fetch`
+    ///
+    /// Concurrent reads of one key wait for a single request. While the
+    /// key is backing off, reads fail with the last error instead.
+    pub async fn get_or_fetch<F, Fut>(&self, key: &str, ttl: Duration, fetch: F) -> Result<Value>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Value>>,
+    {
+        let entry = self
+            .entries
+            .lock()
+            .unwrap()
+            .entry(key.to_string())
+            .or_default()
+            .clone();
+        let mut entry = entry.lock().await;
+        if let Some((fetched_at, value)) = &entry.response {
+            if fetched_at.elapsed() < ttl {
+                return Ok(value.clone());
+            }
+        }
+        if let Some(wait) = entry
+            .retry_at
+            .and_then(|at| at.checked_duration_since(Instant::now()))
+        {
+            return Err(crate::Error::Unavailable {
+                reason: format!(
+                    "Backing off for {}s after {} consecutive failures: {}",
+                    wait.as_secs().max(1),
+                    entry.failures,
+                    entry.error
+                ),
+                retry_after: Some(wait),
+            });
+        }
+
+        match fetch().await {
+            Ok(value) => {
+                *entry = Entry {
+                    response: Some((Instant::now(), value.clone())),
+                    ..Entry::default()
+                };
+                Ok(value)
+            }
+            Err(e) => {
+                entry.failures += 1;
+                entry.retry_at = Some(Instant::now() + self.backoff.delay(entry.failures));
+                entry.error = e.to_string();
+                if entry.failures == self.backoff.open_after {
+                    tracing::warn!(
+                        failures = entry.failures,
+                        open_for = ?self.backoff.open_for,
+                        error = %e,
+                        "oracle endpoint circuit opened"
+                    );
+                }
+                Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::oracle::{HttpOracle, Oracle};
+    use crate::x402::test_server::serve;
+
+    #[tokio::test]
+    async fn test_oracles_share_responses_of_an_endpoint() {
+        let (endpoint, requests) = serve(vec![(200, r#"{"uptime":99.9,"incidents":2}"#)]).await;
+        let cache = Arc::new(ResponseCache::default());
+        let uptime = HttpOracle::new("uptime", &endpoint)
+            .with_path("$.uptime")
+            .unwrap()
+            .with_refresh_rate(Duration::from_secs(60))
+            .with_cache(cache.clone());
+        let incidents = HttpOracle::new("incidents", &endpoint)
+            .with_path("$.incidents")
+            .unwrap()
+            .with_refresh_rate(Duration::from_secs(60))
+            .with_cache(cache);
+
+        assert_eq!(uptime.read().await.unwrap(), serde_json::json!(99.9));
+        assert_eq!(incidents.read().await.unwrap(), serde_json::json!(2));
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_failing_endpoint_backs_off_then_opens_circuit() {
+        let (endpoint, requests) =
+            serve(vec![(503, "{}"), (503, "{}"), (200, r#"{"up":true}"#)]).await;
+        let backoff = BackoffPolicy {
+            initial_backoff: Duration::from_millis(20),
+            max_backoff: Duration::from_secs(1),
+            multiplier: 2.0,
+            open_after: 2,
+            open_for: Duration::from_millis(100),
+        };
+        assert_eq!(backoff.delay(1), Duration::from_millis(20));
+        assert_eq!(backoff.delay(2), Duration::from_millis(100));
+        let oracle =
+            HttpOracle::new("status", endpoint).with_cache(Arc::new(ResponseCache::new(backoff)));
+
+        assert!(oracle.read().await.is_err());
+        let error = oracle.read().await.unwrap_err();
+        assert!(error.to_string().contains("after 1 consecutive failures"));
+        assert_eq!(requests.lock().unwrap().len(), 1);
+
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert!(oracle.read().await.is_err());
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        // Open: still not retried after the backoff would have passed
+        assert!(oracle.read().await.is_err());
+        assert_eq!(requests.lock().unwrap().len(), 2);
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(
+            oracle.read().await.unwrap(),
+            serde_json::json!({"up": true})
+        );
+    }
+}
@@ -0,0 +1,154 @@
+//! Chainlink price feed oracle
+
+use super::{rpc_url, Oracle, OracleError};
+use crate::types::OracleDefinition;
+use crate::{Network, Result};
+use async_trait::async_trait;
+use ethers::providers::{Http, Middleware, Provider};
+use ethers::types::{Address, Bytes, TransactionRequest, I256, U256};
+use serde_json::Value;
+use std::time::Duration;
+use tokio::sync::OnceCell;
+
+/// Selector of `decimals()`
+const DECIMALS: [u8; 4] = [0x31, 0x3c, 0xe5, 0x67];
+/// Selector of `latestRoundData()`
+const LATEST_ROUND_DATA: [u8; 4] = [0xfe, 0xaf, 0x96, 0x8c];
+
+/// Oracle reading the latest answer of a Chainlink aggregator
+///
+/// The value is the answer scaled by the feed's decimals, e.g. `0.52` for
+/// a MATIC/USD answer of `52000000` with 8 decimals.
+pub struct ChainlinkOracle {
+    id: String,
+    feed: Address,
+    provider: Provider<Http>,
+    max_age: Option<Duration>,
+    decimals: OnceCell<u8>,
+}
+
+impl ChainlinkOracle {
+    /// Read the aggregator at `feed` through the JSON-RPC endpoint `rpc_url`
+    pub fn new(id: impl Into<String>, rpc_url: &str, feed: &str) -> Result<Self> {
+        let id = id.into();
+        let feed = feed.parse().map_err(|_| {
+            OracleError::misconfigured(&id, format!("invalid feed address {}", feed))
+        })?;
+        let provider = Provider::<Http>::try_from(rpc_url)
+            .map_err(|e| OracleError::misconfigured(&id, format!("invalid RPC URL: {}", e)))?;
+        Ok(Self {
+            id,
+            feed,
+            provider,
+            max_age: None,
+            decimals: OnceCell::new(),
+        })
+    }
+
+    /// Oracle for a `chainlink` definition, whose `address` is the feed
+    ///
+    /// Reads from the definition's `blockchain`, or `network` if it has
+    /// none.
+    pub fn from_definition(definition: &OracleDefinition, network: Network) -> Result<Self> {
+        let feed = definition
+            .address
+            .as_deref()
+            .ok_or_else(|| OracleError::misconfigured(&definition.id, "no feed address"))?;
+        let network = definition.blockchain.unwrap_or(network);
+        Self::new(&definition.id, &rpc_url(network)?, feed)
+    }
+
+    /// Reject answers not updated within `max_age`
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    async fn call(&self, selector: [u8; 4]) -> Result<Bytes> {
+        let tx = TransactionRequest::new()
+            .to(self.feed)
+            .data(Bytes::from(selector.to_vec()));
+        self.provider
+            .call(&tx.into(), None)
+            .await
+            .map_err(|e| OracleError::unreachable(&self.id, e).into())
+    }
+
+    fn word(&self, output: &[u8], index: usize) -> Result<[u8; 32]> {
+        output
+            .get(index * 32..(index + 1) * 32)
+            .and_then(|word| word.try_into().ok())
+            .ok_or_else(|| OracleError::invalid_response(&self.id, "short feed response").into())
+    }
+}
+
+#[async_trait]
+impl Oracle for ChainlinkOracle {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    async fn read(&self) -> Result<Value> {
+        let decimals = *self
+            .decimals
+            .get_or_try_init(|| async {
+                let output = self.call(DECIMALS).await?;
+                Ok::<_, crate::Error>(self.word(&output, 0)?[31])
+            })
+            .await?;
+
+        // (roundId, answer, startedAt, updatedAt, answeredInRound)
+        let output = self.call(LATEST_ROUND_DATA).await?;
+        let answer = I256::from_raw(U256::from_big_endian(&self.word(&output, 1)?));
+        let updated_at = U256::from_big_endian(&self.word(&output, 3)?).low_u64();
+        if let Some(max_age) = self.max_age {
+            let age = crate::x402::nonce::unix_now().saturating_sub(updated_at);
+            if age > max_age.as_secs() {
+                return Err(OracleError::invalid_response(
+                    &self.id,
+                    format!("feed answer is {}s old", age),
+                )
+                .into());
+            }
+        }
+
+        let value =
+            answer.to_string().parse::<f64>().unwrap_or(f64::NAN) / 10f64.powi(decimals.into());
+        serde_json::Number::from_f64(value)
+            .map(Value::Number)
+            .ok_or_else(|| OracleError::invalid_response(&self.id, "invalid feed answer").into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::x402::test_server::serve;
+
+    #[tokio::test]
+    async fn test_reads_scaled_answer() {
+        // decimals() = 8, then an answer of 52000000 updated at 1700000000
+        let (rpc, requests) = serve(vec![
+            (
+                200,
+                r#"{"jsonrpc":"2.0","id":1,"result":"0x0000000000000000000000000000000000000000000000000000000000000008"}"#,
+            ),
+            (
+                200,
+                r#"{"jsonrpc":"2.0","id":2,"result":"0x000000000000000000000000000000000000000000000000000000000000000100000000000000000000000000000000000000000000000000000000031975000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000006553f1000000000000000000000000000000000000000000000000000000000000000001"}"#,
+            ),
+        ])
+        .await;
+        let oracle = ChainlinkOracle::new(
+            "matic_usd",
+            &rpc,
+            "0xAB594600376Ec9fD91F8e885dADF0CE036862dE0",
+        )
+        .unwrap();
+
+        assert_eq!(oracle.read().await.unwrap(), serde_json::json!(0.52));
+        let requests = requests.lock().unwrap();
+        assert!(requests[0].contains("0x313ce567"));
+        assert!(requests[1].contains("0xfeaf968c"));
+    }
+}
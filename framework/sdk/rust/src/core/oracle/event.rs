@@ -0,0 +1,213 @@
+//! On-chain event oracle
+
+use super::{rpc_url, Oracle, OracleError};
+use crate::types::OracleDefinition;
+use crate::{Network, Result};
+use async_trait::async_trait;
+use ethers::providers::{Http, Middleware, Provider, StreamExt, Ws};
+use ethers::types::{Address, Filter, Log};
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+/// Delay before reconnecting a dropped log subscription
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+enum Source {
+    /// `eth_getLogs` over HTTP on every read
+    Poll {
+        provider: Box<Provider<Http>>,
+        /// First block not yet scanned; unset until the first read
+        next_block: Mutex<Option<u64>>,
+    },
+    /// `eth_subscribe` over a WebSocket, in a background task
+    Subscription(JoinHandle<()>),
+}
+
+/// Oracle counting the logs of one event emitted by a contract
+///
+/// The value is the number of matching events seen since the oracle
+/// started, so a condition without a threshold holds once the first one
+/// arrives and `>=` thresholds wait for several. Over HTTP the logs are
+/// polled whenever the oracle is read; over WebSocket they are pushed as
+/// they are mined, and events emitted while reconnecting are missed.
+pub struct EventOracle {
+    id: String,
+    filter: Filter,
+    source: Source,
+    logs: Arc<Mutex<Vec<Log>>>,
+}
+
+impl EventOracle {
+    /// Oracle for `event` (a signature such as
+    /// `"DeliveryConfirmed(uint256,address)"`) emitted by `address`
+    ///
+    /// `rpc_url` selects the transport: `ws://` and `wss://` URLs subscribe
+    /// to new logs, which must happen within a Tokio runtime; other URLs
+    /// are polled.
+    pub fn new(id: impl Into<String>, rpc_url: &str, address: &str, event: &str) -> Result<Self> {
+        let id = id.into();
+        let address: Address = address
+            .parse()
+            .map_err(|_| OracleError::misconfigured(&id, format!("invalid address {}", address)))?;
+        let filter = Filter::new().address(address).event(event);
+        let logs = Arc::new(Mutex::new(Vec::new()));
+
+        let source = if rpc_url.starts_with("ws://") || rpc_url.starts_with("wss://") {
+            Source::Subscription(tokio::spawn(subscribe(
+                rpc_url.to_string(),
+                filter.clone(),
+                logs.clone(),
+            )))
+        } else {
+            Source::Poll {
+                provider: Box::new(Provider::<Http>::try_from(rpc_url).map_err(|e| {
+                    OracleError::misconfigured(&id, format!("invalid RPC URL: {}", e))
+                })?),
+                next_block: Mutex::new(None),
+            }
+        };
+        Ok(Self {
+            id,
+            filter,
+            source,
+            logs,
+        })
+    }
+
+    /// Oracle for a definition with an `address` and an `event` signature
+    ///
+    /// Reads from the definition's `blockchain`, or `network` if it has
+    /// none; an `endpoint` overrides the network's RPC URL.
+    pub fn from_definition(definition: &OracleDefinition, network: Network) -> Result<Self> {
+        let missing = |field: &str| OracleError::misconfigured(&definition.id, format!("no {}", field));
+        let address = definition
+            .address
+            .as_deref()
+            .ok_or_else(|| missing("address"))?;
+        let event = definition
+            .event
+            .as_deref()
+            .ok_or_else(|| missing("event"))?;
+        let url = match &definition.endpoint {
+            Some(endpoint) => endpoint.clone(),
+            None => rpc_url(definition.blockchain.unwrap_or(network))?,
+        };
+        Self::new(&definition.id, &url, address, event)
+    }
+
+    /// Start polling at `block` instead of at the latest block
+    ///
+    /// Only affects polled oracles before their first read.
+    pub fn with_from_block(self, block: u64) -> Self {
+        if let Source::Poll { next_block, .. } = &self.source {
+            if let Ok(mut next_block) = next_block.try_lock() {
+                *next_block = Some(block);
+            }
+        }
+        self
+    }
+
+    /// Matching logs seen so far, oldest first
+    pub async fn events(&self) -> Vec<Log> {
+        self.logs.lock().await.clone()
+    }
+
+    async fn poll(&self, provider: &Provider<Http>, next_block: &Mutex<Option<u64>>) -> Result<()> {
+        let rpc_error =
+            |e: ethers::providers::ProviderError| OracleError::unreachable(&self.id, e);
+        // Holding the cursor serializes concurrent reads
+        let mut next_block = next_block.lock().await;
+        let latest = provider
+            .get_block_number()
+            .await
+            .map_err(rpc_error)?
+            .as_u64();
+        let from = next_block.unwrap_or(latest);
+        if from <= latest {
+            let filter = self.filter.clone().from_block(from).to_block(latest);
+            let logs = provider.get_logs(&filter).await.map_err(rpc_error)?;
+            self.logs.lock().await.extend(logs);
+        }
+        *next_block = Some(latest + 1);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Oracle for EventOracle {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    async fn read(&self) -> Result<Value> {
+        if let Source::Poll {
+            provider,
+            next_block,
+        } = &self.source
+        {
+            self.poll(provider, next_block).await?;
+        }
+        Ok(self.logs.lock().await.len().into())
+    }
+}
+
+impl Drop for EventOracle {
+    fn drop(&mut self) {
+        if let Source::Subscription(task) = &self.source {
+            task.abort();
+        }
+    }
+}
+
+/// Push subscribed logs into `logs`, reconnecting when the socket drops
+async fn subscribe(url: String, filter: Filter, logs: Arc<Mutex<Vec<Log>>>) {
+    loop {
+        if let Ok(provider) = Provider::<Ws>::connect(&url).await {
+            if let Ok(mut stream) = provider.subscribe_logs(&filter).await {
+                while let Some(log) = stream.next().await {
+                    logs.lock().await.push(log);
+                }
+            }
+        }
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::x402::test_server::serve;
+
+    const PARTNER: &str = "0x00000000000000000000000000000000000000aa";
+
+    #[tokio::test]
+    async fn test_polls_new_events() {
+        let log = r#"{"jsonrpc":"2.0","id":2,"result":[{"address":"0x00000000000000000000000000000000000000aa","topics":["0x7d1e3b4f9b1a9a3f0e5f2d8c6b4a29180706050403020100f0e0d0c0b0a09080"],"data":"0x","blockNumber":"0x10","transactionHash":"0x00000000000000000000000000000000000000000000000000000000000000ab","logIndex":"0x0"}]}"#;
+        let (rpc, requests) = serve(vec![
+            (200, r#"{"jsonrpc":"2.0","id":1,"result":"0x10"}"#),
+            (200, log),
+            (200, r#"{"jsonrpc":"2.0","id":3,"result":"0x12"}"#),
+            (200, r#"{"jsonrpc":"2.0","id":4,"result":[]}"#),
+        ])
+        .await;
+        let oracle = EventOracle::new(
+            "delivery",
+            &rpc,
+            PARTNER,
+            "DeliveryConfirmed(uint256,address)",
+        )
+        .unwrap()
+        .with_from_block(0x0f);
+
+        assert_eq!(oracle.read().await.unwrap(), serde_json::json!(1));
+        assert_eq!(oracle.read().await.unwrap(), serde_json::json!(1));
+        assert_eq!(oracle.events().await.len(), 1);
+
+        let requests = requests.lock().unwrap();
+        assert!(requests[1].contains(r#""fromBlock":"0xf""#));
+        assert!(requests[3].contains(r#""fromBlock":"0x11""#));
+    }
+}
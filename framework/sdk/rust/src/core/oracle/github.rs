@@ -0,0 +1,285 @@
+//! GitHub oracle for milestone contracts
+
+use super::{expand_env, Oracle, OracleError};
+use crate::types::OracleDefinition;
+use crate::Result;
+use async_trait::async_trait;
+use serde_json::Value;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Default GitHub REST API base URL
+pub const GITHUB_API_URL: &str = "https://api.github.com";
+
+/// Milestone a [`GitHubOracle`] waits for
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum GitHubCheck {
+    /// Issue `number` is closed
+    IssueClosed(u64),
+    /// Pull request `number` is merged
+    PullRequestMerged(u64),
+    /// A release is published: the one tagged `tag`, or any release
+    ReleasePublished(Option<String>),
+}
+
+impl GitHubCheck {
+    /// Repository (`owner/name`) and check for a GitHub web URL
+    ///
+    /// Understands issue, pull request, `releases/tag/<tag>` and `releases`
+    /// URLs.
+    pub fn from_url(url: &str) -> Result<(String, Self)> {
+        let invalid = || crate::Error::ConfigError(format!("Unsupported GitHub URL {}", url));
+        let path = url
+            .trim_start_matches("https://")
+            .trim_start_matches("http://")
+            .strip_prefix("github.com/")
+            .ok_or_else(invalid)?;
+        let parts: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+        let number = |n: &str| n.parse::<u64>().map_err(|_| invalid());
+        let check = match parts.as_slice() {
+            [_, _, "issues", n] => Self::IssueClosed(number(n)?),
+            [_, _, "pull", n] => Self::PullRequestMerged(number(n)?),
+            [_, _, "releases", "tag", tag] => Self::ReleasePublished(Some(tag.to_string())),
+            [_, _, "releases"] => Self::ReleasePublished(None),
+            _ => return Err(invalid()),
+        };
+        Ok((format!("{}/{}", parts[0], parts[1]), check))
+    }
+}
+
+/// Oracle reading `true` once work has landed on GitHub
+///
+/// Reads are cached for the refresh rate to stay within the API rate
+/// limits, which are far lower for unauthenticated requests.
+pub struct GitHubOracle {
+    id: String,
+    repo: String,
+    check: GitHubCheck,
+    api_url: String,
+    token: Option<String>,
+    refresh: Duration,
+    http: reqwest::Client,
+    cached: Mutex<Option<(Instant, bool)>>,
+}
+
+impl GitHubOracle {
+    /// Oracle for `check` on the `owner/name` repository
+    pub fn new(id: impl Into<String>, repo: impl Into<String>, check: GitHubCheck) -> Self {
+        Self {
+            id: id.into(),
+            repo: repo.into(),
+            check,
+            api_url: GITHUB_API_URL.to_string(),
+            token: None,
+            refresh: Duration::ZERO,
+            http: reqwest::Client::new(),
+            cached: Mutex::new(None),
+        }
+    }
+
+    /// Oracle for a `github` definition whose endpoint is the web URL of
+    /// the issue, pull request or release
+    ///
+    /// The token is taken from an `Authorization` header of the definition,
+    /// else from `GITHUB_TOKEN`.
+    pub fn from_definition(definition: &OracleDefinition) -> Result<Self> {
+        let endpoint = definition
+            .endpoint
+            .as_ref()
+            .ok_or_else(|| OracleError::misconfigured(&definition.id, "no endpoint"))?;
+        let refresh =
+            crate::llmo::lint::parse_interval_secs(&definition.refresh_rate).ok_or_else(|| {
+                OracleError::misconfigured(
+                    &definition.id,
+                    format!("invalid refresh rate {:?}", definition.refresh_rate),
+                )
+            })?;
+        let (repo, check) = GitHubCheck::from_url(endpoint)
+            .map_err(|e| OracleError::misconfigured(&definition.id, e.to_string()))?;
+
+        let mut oracle =
+            Self::new(&definition.id, repo, check).with_refresh_rate(Duration::from_secs(refresh));
+        let authorization = definition
+            .headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("authorization"));
+        if let Some((_, value)) = authorization {
+            let value = expand_env(value)?;
+            let token = value
+                .strip_prefix("Bearer ")
+                .or_else(|| value.strip_prefix("token "))
+                .unwrap_or(&value);
+            oracle = oracle.with_token(token);
+        } else if let Ok(token) = std::env::var("GITHUB_TOKEN") {
+            oracle = oracle.with_token(token);
+        }
+        Ok(oracle)
+    }
+
+    /// Authenticate with a personal access or app token
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Use another API base URL, e.g. for GitHub Enterprise Server
+    pub fn with_api_url(mut self, api_url: impl Into<String>) -> Self {
+        self.api_url = api_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Reuse a read value for `refresh` before querying again
+    pub fn with_refresh_rate(mut self, refresh: Duration) -> Self {
+        self.refresh = refresh;
+        self
+    }
+
+    /// Fetch an API resource, `None` if it does not exist
+    async fn get(&self, path: &str) -> Result<Option<Value>> {
+        let mut request = self
+            .http
+            .get(format!("{}/repos/{}/{}", self.api_url, self.repo, path))
+            .header("Accept", "application/vnd.github+json")
+            .header("X-GitHub-Api-Version", "2022-11-28")
+            .header(
+                "User-Agent",
+                concat!("smart402/", env!("CARGO_PKG_VERSION")),
+            );
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| OracleError::unreachable(&self.id, e))?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(OracleError::unreachable(
+                &self.id,
+                format!("GitHub API returned {} for {}: {}", status, self.repo, body),
+            )
+            .into());
+        }
+        let body = response
+            .json()
+            .await
+            .map_err(|e| OracleError::invalid_response(&self.id, e.to_string()))?;
+        Ok(Some(body))
+    }
+
+    async fn fetch(&self) -> Result<bool> {
+        Ok(match &self.check {
+            GitHubCheck::IssueClosed(number) => {
+                self.get(&format!("issues/{}", number))
+                    .await?
+                    .ok_or_else(|| self.missing(&format!("issue #{}", number)))?
+                    .get("state")
+                    .and_then(Value::as_str)
+                    == Some("closed")
+            }
+            GitHubCheck::PullRequestMerged(number) => self
+                .get(&format!("pulls/{}", number))
+                .await?
+                .ok_or_else(|| self.missing(&format!("pull request #{}", number)))?
+                .get("merged")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+            // Drafts are only visible to collaborators and are not published
+            GitHubCheck::ReleasePublished(tag) => {
+                let path = match tag {
+                    Some(tag) => format!("releases/tags/{}", tag),
+                    None => "releases/latest".to_string(),
+                };
+                self.get(&path).await?.is_some_and(|release| {
+                    !release
+                        .get("draft")
+                        .and_then(Value::as_bool)
+                        .unwrap_or(false)
+                })
+            }
+        })
+    }
+
+    fn missing(&self, what: &str) -> OracleError {
+        OracleError::NotFound {
+            oracle: self.id.clone(),
+            resource: format!("{} in {}", what, self.repo),
+        }
+    }
+}
+
+#[async_trait]
+impl Oracle for GitHubOracle {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    async fn read(&self) -> Result<Value> {
+        if let Some((read_at, landed)) = *self.cached.lock().unwrap() {
+            if read_at.elapsed() < self.refresh {
+                return Ok(Value::Bool(landed));
+            }
+        }
+        let landed = self.fetch().await?;
+        *self.cached.lock().unwrap() = Some((Instant::now(), landed));
+        Ok(Value::Bool(landed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::x402::test_server::{header, serve};
+
+    #[test]
+    fn test_check_from_url() {
+        assert_eq!(
+            GitHubCheck::from_url("https://github.com/acme/app/pull/42").unwrap(),
+            ("acme/app".to_string(), GitHubCheck::PullRequestMerged(42))
+        );
+        assert_eq!(
+            GitHubCheck::from_url("https://github.com/acme/app/releases/tag/v1.0").unwrap(),
+            (
+                "acme/app".to_string(),
+                GitHubCheck::ReleasePublished(Some("v1.0".to_string()))
+            )
+        );
+        assert!(GitHubCheck::from_url("https://gitlab.com/acme/app/issues/1").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reads_merged_pull_request() {
+        let (url, requests) = serve(vec![
+            (200, r#"{"number":42,"state":"open","merged":false}"#),
+            (200, r#"{"number":42,"state":"closed","merged":true}"#),
+        ])
+        .await;
+        let oracle = GitHubOracle::new("pr_merged", "acme/app", GitHubCheck::PullRequestMerged(42))
+            .with_api_url(url)
+            .with_token("ghp_test");
+
+        assert_eq!(oracle.read().await.unwrap(), Value::Bool(false));
+        assert_eq!(oracle.read().await.unwrap(), Value::Bool(true));
+
+        let request = requests.lock().unwrap()[0].clone();
+        assert!(request.starts_with("GET /repos/acme/app/pulls/42 "));
+        assert_eq!(header(&request, "authorization"), Some("Bearer ghp_test"));
+    }
+
+    #[tokio::test]
+    async fn test_missing_release_is_not_published() {
+        let (url, _) = serve(vec![(404, r#"{"message":"Not Found"}"#)]).await;
+        let oracle = GitHubOracle::new(
+            "release",
+            "acme/app",
+            GitHubCheck::ReleasePublished(Some("v2.0".to_string())),
+        )
+        .with_api_url(url);
+        assert_eq!(oracle.read().await.unwrap(), Value::Bool(false));
+    }
+}
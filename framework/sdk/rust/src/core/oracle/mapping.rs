@@ -0,0 +1,346 @@
+//! Response mappings reducing API responses to comparable values
+//!
+//! A mapping is a JSONPath followed by `|`-separated filters, applied left
+//! to right:
+//!
+//! ```text
+//! $.flights[?@.number == 'BA117'].status | first | == "landed"
+//! $.incidents[*] | count
+//! $.readings[*].celsius | avg | * 1.8 | + 32
+//! $.shipment.stage | map({"picked_up": 1, "in_transit": 2, "delivered": 3})
+//! ```
+//!
+//! A path matching exactly one node yields that node; otherwise it yields
+//! the array of matched nodes. Aggregating filters (`count`, `sum`, `avg`,
+//! `min`, `max`, `first`, `last`) treat any other value as a one-element
+//! list.
+
+use crate::Result;
+use serde_json::{json, Value};
+use serde_json_path::JsonPath;
+use std::fmt;
+use std::str::FromStr;
+
+/// Parsed response mapping
+#[derive(Debug, Clone)]
+pub struct ResponseMapping {
+    source: String,
+    path: JsonPath,
+    filters: Vec<Filter>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Filter {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+    First,
+    Last,
+    Number,
+    Lower,
+    Not,
+    Contains(Value),
+    Default(Value),
+    Map(serde_json::Map<String, Value>),
+    Compare(&'static str, Value),
+    Arithmetic(char, f64),
+}
+
+impl ResponseMapping {
+    /// Parse a mapping such as `$.data.items[*] | count`
+    pub fn parse(mapping: &str) -> Result<Self> {
+        let invalid = |reason: String| {
+            crate::Error::ConfigError(format!("Invalid mapping {:?}: {}", mapping, reason))
+        };
+        let mut stages = split_stages(mapping).into_iter();
+        let path = stages.next().unwrap_or_default();
+        let path = JsonPath::parse(path.trim()).map_err(|e| invalid(e.to_string()))?;
+        let filters = stages
+            .map(|stage| Filter::parse(stage.trim()).map_err(&invalid))
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            source: mapping.trim().to_string(),
+            path,
+            filters,
+        })
+    }
+
+    /// Map a response body to a value
+    pub fn apply(&self, body: &Value) -> Result<Value> {
+        let nodes = self.path.query(body);
+        let mut value = match nodes.len() {
+            0 if self.filters.is_empty() => {
+                return Err(crate::Error::NotFoundError(format!(
+                    "Response has no value at {}",
+                    self.path
+                )))
+            }
+            1 => nodes.exactly_one().cloned().unwrap_or_default(),
+            _ => Value::Array(nodes.into_iter().cloned().collect()),
+        };
+        for filter in &self.filters {
+            value = filter.apply(value).map_err(|reason| {
+                crate::Error::ValidationError(format!("Mapping {:?}: {}", self.source, reason))
+            })?;
+        }
+        Ok(value)
+    }
+}
+
+impl FromStr for ResponseMapping {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for ResponseMapping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl Filter {
+    fn parse(stage: &str) -> std::result::Result<Self, String> {
+        for operator in ["==", "!=", ">=", "<=", ">", "<"] {
+            if let Some(operand) = stage.strip_prefix(operator) {
+                return Ok(Self::Compare(operator, literal(operand)));
+            }
+        }
+        for operator in ['*', '/', '+', '-'] {
+            if let Some(operand) = stage.strip_prefix(operator) {
+                let operand = operand.trim().parse().map_err(|_| {
+                    format!("{} needs a number, got {:?}", operator, operand.trim())
+                })?;
+                return Ok(Self::Arithmetic(operator, operand));
+            }
+        }
+        if let Some((name, argument)) = stage
+            .strip_suffix(')')
+            .and_then(|call| call.split_once('('))
+        {
+            return match name.trim() {
+                "contains" => Ok(Self::Contains(literal(argument))),
+                "default" => Ok(Self::Default(literal(argument))),
+                "map" => match literal(argument) {
+                    Value::Object(table) => Ok(Self::Map(table)),
+                    _ => Err("map needs a JSON object".to_string()),
+                },
+                other => Err(format!("unknown filter {}()", other)),
+            };
+        }
+        Ok(match stage {
+            "count" | "length" => Self::Count,
+            "sum" => Self::Sum,
+            "avg" | "mean" => Self::Avg,
+            "min" => Self::Min,
+            "max" => Self::Max,
+            "first" => Self::First,
+            "last" => Self::Last,
+            "number" => Self::Number,
+            "lower" => Self::Lower,
+            "not" => Self::Not,
+            "" => return Err("empty filter".to_string()),
+            other => return Err(format!("unknown filter {}", other)),
+        })
+    }
+
+    fn apply(&self, value: Value) -> std::result::Result<Value, String> {
+        Ok(match self {
+            Self::Count => json!(match &value {
+                Value::Null => 0,
+                Value::Array(items) => items.len(),
+                Value::Object(fields) => fields.len(),
+                Value::String(s) => s.chars().count(),
+                _ => 1,
+            }),
+            Self::Sum => json!(numbers(value)?.iter().sum::<f64>()),
+            Self::Avg => {
+                let numbers = numbers(value)?;
+                match numbers.len() {
+                    0 => Value::Null,
+                    n => json!(numbers.iter().sum::<f64>() / n as f64),
+                }
+            }
+            Self::Min => numbers(value)?
+                .into_iter()
+                .reduce(f64::min)
+                .map_or(Value::Null, |n| json!(n)),
+            Self::Max => numbers(value)?
+                .into_iter()
+                .reduce(f64::max)
+                .map_or(Value::Null, |n| json!(n)),
+            Self::First => items(value).into_iter().next().unwrap_or_default(),
+            Self::Last => items(value).pop().unwrap_or_default(),
+            Self::Number => json!(number(&value)?),
+            Self::Lower => match value {
+                Value::String(s) => Value::String(s.to_lowercase()),
+                other => return Err(format!("lower needs a string, got {}", other)),
+            },
+            Self::Not => Value::Bool(!truthy(&value)),
+            Self::Contains(needle) => Value::Bool(match &value {
+                Value::Array(items) => items.contains(needle),
+                Value::String(s) => needle.as_str().is_some_and(|needle| s.contains(needle)),
+                other => return Err(format!("contains needs an array or string, got {}", other)),
+            }),
+            Self::Default(fallback) => match value {
+                Value::Null => fallback.clone(),
+                Value::Array(items) if items.is_empty() => fallback.clone(),
+                other => other,
+            },
+            Self::Map(table) => {
+                let key = match &value {
+                    Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                };
+                table.get(&key).cloned().unwrap_or_default()
+            }
+            Self::Compare(operator, operand) => {
+                let ordering = match (number(&value), number(operand)) {
+                    (Ok(a), Ok(b)) => a.partial_cmp(&b),
+                    _ if &value == operand => Some(std::cmp::Ordering::Equal),
+                    _ => None,
+                };
+                Value::Bool(match (*operator, ordering) {
+                    ("!=", ordering) => ordering != Some(std::cmp::Ordering::Equal),
+                    (_, None) => false,
+                    ("==", Some(o)) => o.is_eq(),
+                    (">=", Some(o)) => o.is_ge(),
+                    ("<=", Some(o)) => o.is_le(),
+                    (">", Some(o)) => o.is_gt(),
+                    (_, Some(o)) => o.is_lt(),
+                })
+            }
+            Self::Arithmetic(operator, operand) => {
+                let n = number(&value)?;
+                json!(match operator {
+                    '*' => n * operand,
+                    '/' => n / operand,
+                    '+' => n + operand,
+                    _ => n - operand,
+                })
+            }
+        })
+    }
+}
+
+/// Split a mapping on top-level `|`, leaving those inside brackets,
+/// parentheses and quotes (such as `||` in JSONPath filters) alone
+fn split_stages(mapping: &str) -> Vec<&str> {
+    let mut stages = Vec::new();
+    let mut depth = 0i32;
+    let mut quote = None;
+    let mut start = 0;
+    for (i, c) in mapping.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '[' | '(' | '{') => depth += 1,
+            (None, ']' | ')' | '}') => depth -= 1,
+            (None, '|') if depth == 0 => {
+                stages.push(&mapping[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    stages.push(&mapping[start..]);
+    stages
+}
+
+/// JSON literal, or a bare word taken as a string
+fn literal(operand: &str) -> Value {
+    let operand = operand.trim();
+    serde_json::from_str(operand).unwrap_or_else(|_| Value::String(operand.to_string()))
+}
+
+fn items(value: Value) -> Vec<Value> {
+    match value {
+        Value::Array(items) => items,
+        Value::Null => Vec::new(),
+        other => vec![other],
+    }
+}
+
+fn numbers(value: Value) -> std::result::Result<Vec<f64>, String> {
+    items(value).iter().map(number).collect()
+}
+
+fn number(value: &Value) -> std::result::Result<f64, String> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        Value::Bool(b) => Some(if *b { 1.0 } else { 0.0 }),
+        _ => None,
+    }
+    .ok_or_else(|| format!("expected a number, got {}", value))
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(_) => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(mapping: &str, body: Value) -> Value {
+        ResponseMapping::parse(mapping)
+            .unwrap()
+            .apply(&body)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_mappings() {
+        let flights = json!({"flights": [
+            {"number": "BA117", "status": "Landed", "delay": 12},
+            {"number": "BA119", "status": "Delayed", "delay": 48},
+        ]});
+        assert_eq!(
+            map(
+                "$.flights[?@.number == 'BA117' || @.number == 'X'].status | lower | == landed",
+                flights.clone()
+            ),
+            json!(true)
+        );
+        assert_eq!(
+            map("$.flights[*].delay | avg", flights.clone()),
+            json!(30.0)
+        );
+        assert_eq!(map("$.flights[*] | count", flights.clone()), json!(2));
+        assert_eq!(map("$.cancelled[*] | count", flights), json!(0));
+        assert_eq!(
+            map("$.celsius | * 1.8 | + 32", json!({"celsius": "20"})),
+            json!(68.0)
+        );
+        assert_eq!(
+            map(
+                r#"$.stage | map({"in_transit": 2, "delivered": 3}) | default(0)"#,
+                json!({"stage": "returned"})
+            ),
+            json!(0)
+        );
+    }
+
+    #[test]
+    fn test_mapping_errors() {
+        assert!(ResponseMapping::parse("$.a | frobnicate").is_err());
+        assert!(ResponseMapping::parse("a.b").is_err());
+
+        let mapping = ResponseMapping::parse("$.status | * 2").unwrap();
+        assert!(mapping.apply(&json!({"status": "ok"})).is_err());
+        assert!(mapping.apply(&json!({})).is_err());
+    }
+}
@@ -0,0 +1,1095 @@
+//! Oracles feeding condition evaluation
+//!
+//! Each required condition names the oracle it is measured by in its
+//! `source`. [`Contract::check_conditions`] reads the oracles registered on
+//! the contract and compares their values against the condition's
+//! `operator` and `threshold`. Conditions with an [`Expression`] are
+//! evaluated over the values of every oracle the expression names. A
+//! condition whose oracle is not registered is never met, so a misspelled
+//! source cannot release a payment; its oracle is listed as unavailable.
+
+pub mod attestation;
+pub mod cache;
+pub mod chainlink;
+pub mod event;
+pub mod github;
+pub mod mapping;
+pub mod uptime;
+
+pub use cache::{BackoffPolicy, ResponseCache};
+pub use chainlink::ChainlinkOracle;
+pub use event::EventOracle;
+pub use github::{GitHubCheck, GitHubOracle};
+pub use mapping::ResponseMapping;
+pub use uptime::UptimeOracle;
+
+use crate::core::temporal::{temporal_due, temporal_met, Timeline};
+use crate::types::{
+    ApiKeyLocation, CheckExplanation, ConditionDefinition, ConditionExplanation, OracleAttestation,
+    OracleAuth, OracleDefinition, TemporalCondition,
+};
+use crate::x402::X402Signer;
+use crate::{
+    ConditionCheckResult, Contract, Expression, ExpressionError, Network, Result, UCLContract,
+};
+use async_trait::async_trait;
+use base64::Engine as _;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use serde_json_path::JsonPath;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Why an oracle could not be set up or read
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
+pub enum OracleError {
+    #[error("Oracle {oracle} is misconfigured: {reason}")]
+    Misconfigured { oracle: String, reason: String },
+
+    #[error("Oracle {oracle} is unreachable: {reason}")]
+    Unreachable { oracle: String, reason: String },
+
+    #[error("Oracle {oracle} returned an invalid response: {reason}")]
+    InvalidResponse { oracle: String, reason: String },
+
+    #[error("Oracle {oracle} response has no value at {path}")]
+    MissingValue { oracle: String, path: String },
+
+    #[error("Oracle {oracle} found no {resource}")]
+    NotFound { oracle: String, resource: String },
+
+    #[error("No oracle {oracle}")]
+    Unknown { oracle: String },
+}
+
+impl OracleError {
+    /// Stable machine-readable code, e.g. `"oracle.unreachable"`
+    pub fn code(&self) -> &'static str {
+        match self {
+            OracleError::Misconfigured { .. } => "oracle.misconfigured",
+            OracleError::Unreachable { .. } => "oracle.unreachable",
+            OracleError::InvalidResponse { .. } => "oracle.invalid_response",
+            OracleError::MissingValue { .. } => "oracle.missing_value",
+            OracleError::NotFound { .. } => "oracle.not_found",
+            OracleError::Unknown { .. } => "oracle.unknown",
+        }
+    }
+
+    /// Whether a later read may succeed
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            OracleError::Unreachable { .. } | OracleError::InvalidResponse { .. }
+        )
+    }
+
+    /// Id of the oracle at fault
+    pub fn oracle(&self) -> &str {
+        match self {
+            OracleError::Misconfigured { oracle, .. }
+            | OracleError::Unreachable { oracle, .. }
+            | OracleError::InvalidResponse { oracle, .. }
+            | OracleError::MissingValue { oracle, .. }
+            | OracleError::NotFound { oracle, .. }
+            | OracleError::Unknown { oracle } => oracle,
+        }
+    }
+
+    pub(crate) fn misconfigured(oracle: &str, reason: impl Into<String>) -> Self {
+        OracleError::Misconfigured {
+            oracle: oracle.to_string(),
+            reason: reason.into(),
+        }
+    }
+
+    pub(crate) fn unreachable(oracle: &str, reason: impl ToString) -> Self {
+        OracleError::Unreachable {
+            oracle: oracle.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+
+    pub(crate) fn invalid_response(oracle: &str, reason: impl Into<String>) -> Self {
+        OracleError::InvalidResponse {
+            oracle: oracle.to_string(),
+            reason: reason.into(),
+        }
+    }
+}
+
+/// Source of the data a condition is checked against
+#[async_trait]
+pub trait Oracle: Send + Sync {
+    /// Id conditions refer to in their `source`
+    fn id(&self) -> &str;
+
+    /// Current value
+    async fn read(&self) -> Result<Value>;
+}
+
+/// Oracle reading a value from a JSON HTTP endpoint
+///
+/// Any third-party API can be wired in from the contract's oracle
+/// definition: method, query, body and credentials shape the request and a
+/// [`ResponseMapping`] reduces the response to a comparable value.
+/// Responses are kept in a [`ResponseCache`] for the refresh rate and
+/// shared with every oracle sending the same request, so monitors checking
+/// more often than the oracle refreshes do not hammer the endpoint, and
+/// failing endpoints are backed off from.
+pub struct HttpOracle {
+    id: String,
+    endpoint: String,
+    method: reqwest::Method,
+    path: Option<JsonPath>,
+    mapping: Option<ResponseMapping>,
+    headers: Vec<(String, String)>,
+    query: Vec<(String, String)>,
+    body: Option<Value>,
+    refresh: Duration,
+    http: reqwest::Client,
+    cache: Arc<ResponseCache>,
+}
+
+impl HttpOracle {
+    /// Read the whole JSON body of `endpoint` on every check
+    pub fn new(id: impl Into<String>, endpoint: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            endpoint: endpoint.into(),
+            method: reqwest::Method::GET,
+            path: None,
+            mapping: None,
+            headers: Vec::new(),
+            query: Vec::new(),
+            body: None,
+            refresh: Duration::ZERO,
+            http: reqwest::Client::new(),
+            cache: ResponseCache::global(),
+        }
+    }
+
+    /// Oracle described by a contract's oracle definition
+    pub fn from_definition(definition: &OracleDefinition) -> Result<Self> {
+        let endpoint = definition
+            .endpoint
+            .as_ref()
+            .ok_or_else(|| OracleError::misconfigured(&definition.id, "no endpoint"))?;
+        let refresh =
+            crate::llmo::lint::parse_interval_secs(&definition.refresh_rate).ok_or_else(|| {
+                OracleError::misconfigured(
+                    &definition.id,
+                    format!("invalid refresh rate {:?}", definition.refresh_rate),
+                )
+            })?;
+
+        let mut oracle =
+            Self::new(&definition.id, endpoint).with_refresh_rate(Duration::from_secs(refresh));
+        if let Some(method) = &definition.method {
+            oracle.method = method.to_uppercase().parse().map_err(|_| {
+                OracleError::misconfigured(&definition.id, format!("invalid method {}", method))
+            })?;
+        }
+        match (&definition.path, &definition.mapping) {
+            (Some(_), Some(_)) => {
+                return Err(OracleError::misconfigured(
+                    &definition.id,
+                    "both a path and a mapping",
+                )
+                .into())
+            }
+            (Some(path), None) => oracle = oracle.with_path(path)?,
+            (None, Some(mapping)) => oracle = oracle.with_mapping(mapping.parse()?),
+            (None, None) => {}
+        }
+        for (name, value) in &definition.headers {
+            oracle = oracle.with_header(name, expand_env(value)?);
+        }
+        for (name, value) in &definition.query {
+            oracle = oracle.with_query(name, expand_env(value)?);
+        }
+        if let Some(body) = &definition.body {
+            oracle = oracle.with_body(body.clone());
+        }
+        match &definition.auth {
+            None => {}
+            Some(OracleAuth::Bearer { token }) => {
+                oracle =
+                    oracle.with_header("Authorization", format!("Bearer {}", expand_env(token)?));
+            }
+            Some(OracleAuth::Basic { username, password }) => {
+                let credentials = format!(
+                    "{}:{}",
+                    expand_env(username)?,
+                    password
+                        .as_deref()
+                        .map(expand_env)
+                        .transpose()?
+                        .unwrap_or_default()
+                );
+                oracle = oracle.with_header(
+                    "Authorization",
+                    format!(
+                        "Basic {}",
+                        base64::engine::general_purpose::STANDARD.encode(credentials)
+                    ),
+                );
+            }
+            Some(OracleAuth::ApiKey {
+                name,
+                value,
+                location,
+            }) => {
+                let value = expand_env(value)?;
+                oracle = match location {
+                    ApiKeyLocation::Header => oracle.with_header(name, value),
+                    ApiKeyLocation::Query => oracle.with_query(name, value),
+                };
+            }
+        }
+        Ok(oracle)
+    }
+
+    /// Reduce responses with `mapping`, e.g. `$.incidents[*] | count`
+    pub fn with_mapping(mut self, mapping: ResponseMapping) -> Self {
+        self.mapping = Some(mapping);
+        self
+    }
+
+    /// Add a query parameter to every request
+    pub fn with_query(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.query.push((name.into(), value.into()));
+        self
+    }
+
+    /// POST `body` as JSON instead of sending a GET
+    pub fn with_body(mut self, body: Value) -> Self {
+        if self.method == reqwest::Method::GET {
+            self.method = reqwest::Method::POST;
+        }
+        self.body = Some(body);
+        self
+    }
+
+    /// Select the value with a JSONPath such as `$.data.uptime`
+    pub fn with_path(mut self, path: &str) -> Result<Self> {
+        self.path = Some(JsonPath::parse(path).map_err(|e| {
+            OracleError::misconfigured(&self.id, format!("invalid path: {}", e))
+        })?);
+        Ok(self)
+    }
+
+    /// Send a header with every request, e.g. `Authorization`
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Reuse a read value for `refresh` before querying again
+    pub fn with_refresh_rate(mut self, refresh: Duration) -> Self {
+        self.refresh = refresh;
+        self
+    }
+
+    /// Keep responses in `cache` instead of the [global](ResponseCache::global) one
+    pub fn with_cache(mut self, cache: Arc<ResponseCache>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Identifies the request, so only identical requests share responses
+    fn cache_key(&self) -> String {
+        let mut key = format!("{} {}", self.method, self.endpoint);
+        for (name, value) in &self.query {
+            key.push_str(&format!(" ?{}={}", name, value));
+        }
+        let mut headers: Vec<_> = self.headers.iter().collect();
+        headers.sort();
+        for (name, value) in headers {
+            key.push_str(&format!(" {}: {}", name.to_lowercase(), value));
+        }
+        if let Some(body) = &self.body {
+            key.push_str(&format!(" {}", body));
+        }
+        key
+    }
+
+    async fn fetch(&self) -> Result<Value> {
+        let mut request = self.http.request(self.method.clone(), &self.endpoint);
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        if !self.query.is_empty() {
+            request = request.query(&self.query);
+        }
+        if let Some(body) = &self.body {
+            request = request.json(body);
+        }
+        let response = request
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| OracleError::unreachable(&self.id, e))?;
+        Ok(response
+            .json()
+            .await
+            .map_err(|e| OracleError::invalid_response(&self.id, e.to_string()))?)
+    }
+
+    /// Reduce a response to the oracle's value
+    fn extract(&self, body: Value) -> Result<Value> {
+        if let Some(mapping) = &self.mapping {
+            return mapping.apply(&body);
+        }
+        match &self.path {
+            None => Ok(body),
+            Some(path) => path.query(&body).first().cloned().ok_or_else(|| {
+                OracleError::MissingValue {
+                    oracle: self.id.clone(),
+                    path: path.to_string(),
+                }
+                .into()
+            }),
+        }
+    }
+}
+
+#[async_trait]
+impl Oracle for HttpOracle {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    async fn read(&self) -> Result<Value> {
+        let body = self
+            .cache
+            .get_or_fetch(&self.cache_key(), self.refresh, || self.fetch())
+            .await?;
+        self.extract(body)
+    }
+}
+
+/// Oracles of a contract, by id
+#[derive(Clone, Default)]
+pub struct OracleSet {
+    oracles: HashMap<String, Arc<dyn Oracle>>,
+    attestor: Option<Arc<dyn X402Signer>>,
+}
+
+impl OracleSet {
+    /// Create empty set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Oracles for the definitions of the supported types
+    ///
+    /// `chainlink` definitions read price feeds and definitions with an
+    /// `event` watch contract logs, both on `network` unless they name their
+    /// own `blockchain`. `uptime`, `healthcheck` and `status_page`
+    /// definitions start probing their endpoint and `github` definitions
+    /// watch the issue, pull request or release their endpoint links to;
+    /// any other definition with an endpoint is read over HTTP. The rest are
+    /// skipped.
+    pub fn from_definitions(definitions: &[OracleDefinition], network: Network) -> Result<Self> {
+        let mut set = Self::new();
+        for definition in definitions {
+            if definition.oracle_type == "chainlink" {
+                set.insert(Arc::new(ChainlinkOracle::from_definition(
+                    definition, network,
+                )?));
+            } else if definition.event.is_some() {
+                set.insert(Arc::new(EventOracle::from_definition(definition, network)?));
+            } else if matches!(
+                definition.oracle_type.as_str(),
+                "uptime" | "healthcheck" | "status_page"
+            ) {
+                set.insert(Arc::new(UptimeOracle::from_definition(definition)?));
+            } else if definition.oracle_type == "github" {
+                set.insert(Arc::new(GitHubOracle::from_definition(definition)?));
+            } else if definition.endpoint.is_some() {
+                set.insert(Arc::new(HttpOracle::from_definition(definition)?));
+            }
+        }
+        Ok(set)
+    }
+
+    /// Add an oracle, replacing one with the same id
+    pub fn insert(&mut self, oracle: Arc<dyn Oracle>) {
+        self.oracles.insert(oracle.id().to_string(), oracle);
+    }
+
+    /// Oracle with the given id
+    pub fn get(&self, id: &str) -> Option<&Arc<dyn Oracle>> {
+        self.oracles.get(id)
+    }
+
+    /// Add the oracles a contract defines that the set lacks
+    ///
+    /// Oracles are shared by id: a definition whose id is already in the
+    /// set reuses that oracle rather than replacing it.
+    pub fn connect(&mut self, ucl: &UCLContract) -> Result<()> {
+        let missing: Vec<_> = ucl
+            .oracles
+            .iter()
+            .filter(|definition| !self.oracles.contains_key(&definition.id))
+            .cloned()
+            .collect();
+        let connected = Self::from_definitions(&missing, ucl.payment.blockchain)?;
+        self.oracles.extend(connected.oracles);
+        Ok(())
+    }
+
+    /// Sign every value read during a check with `signer`, the oracle
+    /// adapter's key, and keep the signatures with the check's result
+    pub fn set_attestor(&mut self, signer: Arc<dyn X402Signer>) {
+        self.attestor = Some(signer);
+    }
+
+    /// Serve reads of oracle `from` with oracle `to`, e.g. a fallback for
+    /// an oracle that stopped responding
+    pub fn reroute(&mut self, from: &str, to: &str) -> Result<()> {
+        let fallback = self.oracles.get(to).cloned().ok_or_else(|| OracleError::Unknown {
+            oracle: to.to_string(),
+        })?;
+        self.oracles.insert(from.to_string(), fallback);
+        Ok(())
+    }
+
+    /// Check the conditions of a contract
+    ///
+    /// `all_met` covers the required conditions; optional conditions and
+    /// condition groups are added to the per-condition results. A condition
+    /// whose oracle is not registered or cannot be read, or whose
+    /// expression is invalid or fails to evaluate, is not met; oracles that
+    /// are missing or could not be read are listed in `unavailable`.
+    pub async fn evaluate(&self, ucl: &UCLContract) -> ConditionCheckResult {
+        self.evaluate_at(ucl, &Timeline::now()).await
+    }
+
+    /// Check the conditions of a contract, explaining each result
+    ///
+    /// Reads the oracles like [`OracleSet::evaluate`] and reports, per
+    /// condition, the values read and why the condition holds or not.
+    pub async fn explain(&self, ucl: &UCLContract) -> CheckExplanation {
+        self.explain_at(ucl, &Timeline::now()).await
+    }
+
+    /// Check the conditions of a contract with the monitor's history
+    pub(crate) async fn evaluate_at(
+        &self,
+        ucl: &UCLContract,
+        timeline: &Timeline<'_>,
+    ) -> ConditionCheckResult {
+        self.explain_at(ucl, timeline).await.result
+    }
+
+    /// Check and explain the conditions of a contract with the monitor's
+    /// history
+    ///
+    /// Time-based conditions are measured last, so breach grace periods see
+    /// the current results of the conditions they watch.
+    #[tracing::instrument(name = "check_conditions", skip_all, fields(contract_id = %ucl.contract_id, all_met))]
+    pub(crate) async fn explain_at(&self, ucl: &UCLContract, timeline: &Timeline<'_>) -> CheckExplanation {
+        let required = ucl.conditions.required.iter().map(|c| (c, true));
+        let optional = ucl.conditions.optional.iter().flatten().map(|c| (c, false));
+        let all: Vec<_> = required.chain(optional).collect();
+
+        let mut conditions = HashMap::new();
+        let mut explanations = HashMap::new();
+        let mut reads = Reads::default();
+        for (condition, required) in all.iter().filter(|(c, _)| c.temporal.is_none()) {
+            let explanation = self.measure(condition, *required, &mut reads).await;
+            conditions.insert(condition.id.clone(), explanation.met);
+            explanations.insert(condition.id.clone(), explanation);
+        }
+        let mut unavailable = reads.unavailable;
+        unavailable.sort();
+        let attestations = match &self.attestor {
+            Some(signer) => reads
+                .observed
+                .into_iter()
+                .filter_map(|read| {
+                    OracleAttestation::sign(
+                        signer.as_ref(),
+                        &ucl.contract_id,
+                        &read.oracle_id,
+                        read.served_by.as_deref(),
+                        read.value,
+                        read.at,
+                    )
+                    .map_err(|e| {
+                        tracing::warn!(oracle_id = %read.oracle_id, error = %e, "signing oracle value failed")
+                    })
+                    .ok()
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+        for (condition, required) in &all {
+            if let Some(temporal) = &condition.temporal {
+                let met = temporal_met(ucl, temporal, &conditions, timeline);
+                let due = temporal_due(ucl, temporal, &conditions, timeline);
+                let reason = match (due, temporal) {
+                    (Some(due), _) if met => format!("passed at {}", due.to_rfc3339()),
+                    (Some(due), _) => format!("passes at {}", due.to_rfc3339()),
+                    (None, TemporalCondition::Breached { condition, .. }) => {
+                        format!("{} is not failing", condition)
+                    }
+                    (None, _) => "its date or grace period cannot be read".to_string(),
+                };
+                conditions.insert(condition.id.clone(), met);
+                explanations.insert(
+                    condition.id.clone(),
+                    ConditionExplanation {
+                        met,
+                        reason,
+                        ..ConditionExplanation::new(condition, *required)
+                    },
+                );
+            }
+        }
+        let all_met = all
+            .iter()
+            .filter(|(_, required)| *required)
+            .all(|(c, _)| conditions.get(&c.id) == Some(&true));
+        tracing::Span::current().record("all_met", all_met);
+        tracing::debug!(conditions = conditions.len(), "conditions checked");
+
+        let mut result = ConditionCheckResult {
+            all_met,
+            conditions,
+            timestamp: timeline.now,
+            unavailable,
+            attestations,
+        };
+        result.add_groups(&ucl.conditions.groups);
+        CheckExplanation {
+            result,
+            conditions: all
+                .iter()
+                .filter_map(|(condition, _)| explanations.remove(&condition.id))
+                .collect(),
+        }
+    }
+
+    /// Measure a single condition, noting the oracle reads in `reads`
+    async fn measure(
+        &self,
+        condition: &ConditionDefinition,
+        required: bool,
+        reads: &mut Reads,
+    ) -> ConditionExplanation {
+        let mut explanation = ConditionExplanation::new(condition, required);
+        match (&condition.expression, self.oracles.get(&condition.source)) {
+            (Some(expression), _) => match Expression::parse(expression) {
+                Ok(parsed) => {
+                    let outcome = self.evaluate_noting(&parsed, reads).await;
+                    explanation.inputs = parsed
+                        .identifiers()
+                        .into_iter()
+                        .filter_map(|id| Some((id.to_string(), reads.value(id)?.clone())))
+                        .collect();
+                    match outcome {
+                        Ok(met) => {
+                            explanation.met = met;
+                            explanation.reason = holds(expression, met);
+                        }
+                        Err(e) => explanation.reason = e.to_string(),
+                    }
+                }
+                Err(e) => explanation.reason = format!("invalid expression: {}", e),
+            },
+            (None, None) => {
+                reads.fail(&condition.source);
+                explanation.reason = format!("no oracle {} is registered", condition.source);
+            }
+            (None, Some(oracle)) => match read(oracle.as_ref()).await {
+                Ok(value) => {
+                    explanation.met = condition_met(condition, &value);
+                    explanation.reason = match &condition.threshold {
+                        Some(threshold) => holds(
+                            &format!("{} {} {}", value, condition.operator, threshold),
+                            explanation.met,
+                        ),
+                        None => holds(&format!("{} is true", value), explanation.met),
+                    };
+                    explanation.value = Some(value.clone());
+                    reads.observe(&condition.source, oracle.as_ref(), value);
+                }
+                Err(e) => {
+                    reads.fail(&condition.source);
+                    explanation.reason =
+                        format!("oracle {} could not be read: {}", condition.source, e);
+                }
+            },
+        }
+        tracing::debug!(condition_id = %condition.id, met = explanation.met, "condition checked");
+        explanation
+    }
+
+    /// Evaluate an expression over the values of the oracles it names
+    ///
+    /// Errors tell which identifier names no registered oracle, which
+    /// oracle could not be read or which operand has the wrong type.
+    pub async fn evaluate_expression(&self, expression: &Expression) -> Result<bool> {
+        self.evaluate_noting(expression, &mut Reads::default())
+            .await
+    }
+
+    async fn evaluate_noting(&self, expression: &Expression, reads: &mut Reads) -> Result<bool> {
+        let identifiers = expression.identifiers();
+        let unknown: Vec<_> = identifiers
+            .iter()
+            .filter(|id| !self.oracles.contains_key(**id))
+            .collect();
+        if let Some(first) = unknown.first() {
+            for id in &unknown {
+                reads.fail(id);
+            }
+            return Err(ExpressionError::UnknownIdentifier(first.to_string()).into());
+        }
+        let mut values = HashMap::new();
+        for id in &identifiers {
+            let oracle = &self.oracles[*id];
+            match read(oracle.as_ref()).await {
+                Ok(value) => {
+                    reads.observe(id, oracle.as_ref(), value.clone());
+                    values.insert(id.to_string(), value)
+                }
+                Err(e) => {
+                    reads.fail(id);
+                    return Err(e);
+                }
+            };
+        }
+        Ok(expression.is_met(&values)?)
+    }
+}
+
+/// Oracle reads of one check
+#[derive(Default)]
+struct Reads {
+    /// Oracles that could not be read
+    unavailable: Vec<String>,
+    /// First value read from each oracle
+    observed: Vec<Observed>,
+}
+
+struct Observed {
+    oracle_id: String,
+    served_by: Option<String>,
+    value: Value,
+    at: DateTime<Utc>,
+}
+
+impl Reads {
+    fn fail(&mut self, id: &str) {
+        if !self.unavailable.iter().any(|noted| noted == id) {
+            self.unavailable.push(id.to_string());
+        }
+    }
+
+    fn value(&self, id: &str) -> Option<&Value> {
+        self.observed
+            .iter()
+            .find(|read| read.oracle_id == id)
+            .map(|read| &read.value)
+    }
+
+    fn observe(&mut self, id: &str, oracle: &dyn Oracle, value: Value) {
+        if self.observed.iter().any(|read| read.oracle_id == id) {
+            return;
+        }
+        self.observed.push(Observed {
+            oracle_id: id.to_string(),
+            served_by: (oracle.id() != id).then(|| oracle.id().to_string()),
+            value,
+            at: Utc::now(),
+        });
+    }
+}
+
+fn holds(requirement: &str, met: bool) -> String {
+    if met {
+        format!("{} holds", requirement)
+    } else {
+        format!("{} does not hold", requirement)
+    }
+}
+
+impl ConditionExplanation {
+    /// Explanation of a condition not measured yet
+    fn new(condition: &ConditionDefinition, required: bool) -> Self {
+        let compared = condition.expression.is_none() && condition.temporal.is_none();
+        Self {
+            condition_id: condition.id.clone(),
+            required,
+            met: false,
+            source: compared.then(|| condition.source.clone()),
+            value: None,
+            operator: compared.then(|| condition.operator.clone()),
+            threshold: condition.threshold.clone().filter(|_| compared),
+            expression: condition.expression.clone(),
+            inputs: Default::default(),
+            reason: String::new(),
+        }
+    }
+}
+
+/// Read an oracle within a span naming it, logging failures
+#[tracing::instrument(name = "oracle_read", skip_all, fields(oracle_id = oracle.id()))]
+async fn read(oracle: &dyn Oracle) -> Result<Value> {
+    let value = oracle.read().await;
+    match &value {
+        Ok(value) => tracing::debug!(%value, "oracle read"),
+        Err(e) => tracing::warn!(error = %e, "oracle read failed"),
+    }
+    value
+}
+
+impl Contract {
+    /// Measure conditions with `oracle`, replacing one with the same id
+    pub fn add_oracle(&mut self, oracle: Arc<dyn Oracle>) {
+        self.oracles.insert(oracle);
+    }
+
+    /// Sign the oracle values of every condition check with `signer`
+    ///
+    /// See [`OracleSet::set_attestor`].
+    pub fn set_oracle_attestor(&mut self, signer: Arc<dyn X402Signer>) {
+        self.oracles.set_attestor(signer);
+    }
+
+    /// Register oracles for the contract's oracle definitions
+    ///
+    /// Like [`OracleSet::connect`], oracles already registered, e.g. with
+    /// [`Contract::add_oracle`], are kept over the definitions' oracles.
+    pub fn connect_oracles(&mut self) -> Result<()> {
+        self.oracles.connect(&self.ucl)
+    }
+}
+
+/// Whether an oracle value satisfies a condition
+///
+/// Without a threshold the value itself must be `true` (or a non-zero
+/// number). Numbers, including numeric strings, are compared numerically.
+pub(crate) fn condition_met(condition: &ConditionDefinition, value: &Value) -> bool {
+    let Some(threshold) = &condition.threshold else {
+        return value
+            .as_bool()
+            .or_else(|| number(value).map(|n| n != 0.0))
+            .unwrap_or(false);
+    };
+    let ordering = match (number(value), number(threshold)) {
+        (Some(a), Some(b)) => a.partial_cmp(&b),
+        _ if value == threshold => Some(std::cmp::Ordering::Equal),
+        _ => None,
+    };
+    let Some(ordering) = ordering else {
+        return matches!(condition.operator.as_str(), "!=" | "ne" | "not_equals");
+    };
+    match condition.operator.as_str() {
+        ">" | "gt" | "greater_than" => ordering.is_gt(),
+        ">=" | "gte" | "greater_than_or_equal" | "at_least" => ordering.is_ge(),
+        "<" | "lt" | "less_than" => ordering.is_lt(),
+        "<=" | "lte" | "less_than_or_equal" | "at_most" => ordering.is_le(),
+        "==" | "=" | "eq" | "equals" => ordering.is_eq(),
+        "!=" | "ne" | "not_equals" => ordering.is_ne(),
+        _ => false,
+    }
+}
+
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// JSON-RPC endpoint of a network
+///
+/// Read from `SMART402_RPC_URL_<NETWORK>` (e.g. `SMART402_RPC_URL_POLYGON`),
+/// falling back to a public endpoint for well-known networks.
+pub fn rpc_url(network: Network) -> Result<String> {
+    let var = format!(
+        "SMART402_RPC_URL_{}",
+        network.name().to_uppercase().replace('-', "_")
+    );
+    if let Ok(url) = std::env::var(&var) {
+        return Ok(url);
+    }
+    let public = match network {
+        Network::Ethereum => "https://cloudflare-eth.com",
+        Network::Polygon => "https://polygon-rpc.com",
+        Network::Base => "https://mainnet.base.org",
+        Network::Arbitrum => "https://arb1.arbitrum.io/rpc",
+        Network::Optimism => "https://mainnet.optimism.io",
+        _ => {
+            return Err(crate::Error::ConfigError(format!(
+                "No RPC endpoint for network {}; set {}",
+                network, var
+            )))
+        }
+    };
+    Ok(public.to_string())
+}
+
+/// Replace `${VAR}` with the environment variable `VAR`
+fn expand_env(value: &str) -> Result<String> {
+    let mut out = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| crate::Error::ConfigError(format!("Unterminated ${{ in {:?}", value)))?;
+        let name = &rest[start + 2..start + end];
+        let resolved = std::env::var(name).map_err(|_| {
+            crate::Error::ConfigError(format!("Environment variable {} is not set", name))
+        })?;
+        out.push_str(&rest[..start]);
+        out.push_str(&resolved);
+        rest = &rest[start + end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::x402::test_server::{header, serve};
+    use crate::ContractConfig;
+
+    fn condition(id: &str, operator: &str, threshold: f64) -> ConditionDefinition {
+        ConditionDefinition {
+            id: id.to_string(),
+            description: String::new(),
+            source: "uptime_oracle".to_string(),
+            operator: operator.to_string(),
+            threshold: Some(threshold.into()),
+            expression: None,
+            temporal: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_http_oracle_feeds_conditions() {
+        std::env::set_var("SMART402_TEST_ORACLE_TOKEN", "secret");
+        let (endpoint, requests) = serve(vec![(200, r#"{"data":{"uptime":"99.95"}}"#)]).await;
+
+        let mut contract = Contract::from_config(ContractConfig::default()).unwrap();
+        contract.ucl.oracles.push(OracleDefinition {
+            id: "uptime_oracle".to_string(),
+            oracle_type: "custom_api".to_string(),
+            endpoint: Some(endpoint),
+            path: Some("$.data.uptime".to_string()),
+            headers: HashMap::from([(
+                "Authorization".to_string(),
+                "Bearer ${SMART402_TEST_ORACLE_TOKEN}".to_string(),
+            )]),
+            refresh_rate: "1 hour".to_string(),
+            required: true,
+            ..Default::default()
+        });
+        contract
+            .ucl
+            .conditions
+            .required
+            .push(condition("uptime_met", ">=", 99.9));
+        contract
+            .ucl
+            .conditions
+            .required
+            .push(condition("perfect_uptime", "==", 100.0));
+        contract.connect_oracles().unwrap();
+
+        let result = contract.check_conditions().await.unwrap();
+        assert!(result.conditions["uptime_met"]);
+        assert!(!result.conditions["perfect_uptime"]);
+        assert!(!result.all_met);
+
+        // Served from the cache: the test server only answers once
+        assert!(contract.check_conditions().await.unwrap().conditions["uptime_met"]);
+        assert_eq!(
+            header(&requests.lock().unwrap()[0], "Authorization"),
+            Some("Bearer secret")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_api_oracle_from_definition() {
+        let (endpoint, requests) = serve(vec![(
+            200,
+            r#"{"flights":[{"number":"BA117","status":"landed"}]}"#,
+        )])
+        .await;
+        let definition: OracleDefinition = serde_json::from_value(serde_json::json!({
+            "id": "flight_status",
+            "type": "api",
+            "endpoint": endpoint,
+            "method": "post",
+            "query": {"date": "2024-01-01"},
+            "body": {"flights": ["BA117"]},
+            "auth": {"type": "api_key", "name": "key", "value": "k123", "in": "query"},
+            "mapping": "$.flights[?@.number == 'BA117'].status | == landed",
+            "refresh_rate": "5m",
+            "required": true
+        }))
+        .unwrap();
+        let oracle = HttpOracle::from_definition(&definition).unwrap();
+        assert_eq!(oracle.read().await.unwrap(), serde_json::json!(true));
+
+        let request = requests.lock().unwrap()[0].clone();
+        assert!(request.starts_with("POST /?date=2024-01-01&key=k123 "));
+        assert!(request.ends_with(r#"{"flights":["BA117"]}"#));
+
+        let mut both = definition.clone();
+        both.path = Some("$.flights".to_string());
+        let Err(crate::Error::Oracle(error)) = HttpOracle::from_definition(&both) else {
+            panic!("expected an oracle error");
+        };
+        assert_eq!(error.code(), "oracle.misconfigured");
+        assert_eq!(error.oracle(), definition.id);
+    }
+
+    struct Fixed(&'static str, Value);
+
+    #[async_trait]
+    impl Oracle for Fixed {
+        fn id(&self) -> &str {
+            self.0
+        }
+
+        async fn read(&self) -> Result<Value> {
+            Ok(self.1.clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_expression_conditions() {
+        let mut contract = Contract::from_config(ContractConfig::default()).unwrap();
+        contract.add_oracle(Arc::new(Fixed("uptime", serde_json::json!(0.995))));
+        contract.add_oracle(Arc::new(Fixed("incidents", serde_json::json!(4))));
+        for (id, expression) in [
+            ("sla_met", "uptime >= 99% && incidents < 3"),
+            ("uptime_met", "uptime >= 99% || incidents < 3"),
+            ("mistyped", "uptime && incidents"),
+            ("unmeasured", "latency_ms < 200"),
+        ] {
+            let mut condition = condition(id, "", 0.0);
+            condition.expression = Some(expression.to_string());
+            contract.ucl.conditions.required.push(condition);
+        }
+
+        let result = contract.check_conditions().await.unwrap();
+        assert!(!result.conditions["sla_met"]);
+        assert!(result.conditions["uptime_met"]);
+        assert!(!result.conditions["mistyped"]);
+        assert!(!result.conditions["unmeasured"]);
+        assert_eq!(result.unavailable, ["latency_ms"]);
+
+        // Optional conditions and groups are reported without affecting all_met
+        let mut few_incidents = condition("few_incidents", "", 0.0);
+        few_incidents.expression = Some("incidents < 5".to_string());
+        contract.ucl.conditions.optional = Some(vec![few_incidents]);
+        contract
+            .ucl
+            .conditions
+            .groups
+            .push(crate::types::ConditionGroup {
+                id: "mostly_healthy".to_string(),
+                conditions: crate::types::RuleConditions {
+                    all_of: None,
+                    any_of: None,
+                    n_of_m: Some(crate::types::Quorum {
+                        n: 2,
+                        of: vec![
+                            "sla_met".to_string(),
+                            "uptime_met".to_string(),
+                            "few_incidents".to_string(),
+                        ],
+                    }),
+                },
+            });
+        let result = contract.check_conditions().await.unwrap();
+        assert!(!result.all_met);
+        assert!(result.conditions["few_incidents"]);
+        assert!(result.conditions["mostly_healthy"]);
+
+        let error = contract
+            .oracles
+            .evaluate_expression(&"uptime && incidents".parse().unwrap())
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("`uptime` is a number"));
+        let error = contract
+            .oracles
+            .evaluate_expression(&"uptime >= 99% || latency_ms < 200".parse().unwrap())
+            .await
+            .unwrap_err();
+        assert_eq!(error.to_string(), "Unknown identifier `latency_ms`");
+    }
+
+    #[tokio::test]
+    async fn test_explain_conditions() {
+        let mut contract = Contract::from_config(ContractConfig::default()).unwrap();
+        contract.add_oracle(Arc::new(Fixed("uptime_oracle", serde_json::json!(99.5))));
+        contract.add_oracle(Arc::new(Fixed("incidents", serde_json::json!(1))));
+        contract
+            .ucl
+            .conditions
+            .required
+            .push(condition("uptime_met", ">=", 99.9));
+        let mut few_incidents = condition("few_incidents", "", 0.0);
+        few_incidents.expression = Some("incidents < 3".to_string());
+        contract.ucl.conditions.required.push(few_incidents);
+        let mut unmeasured = condition("latency_met", "<", 200.0);
+        unmeasured.source = "latency_oracle".to_string();
+        contract.ucl.conditions.optional = Some(vec![unmeasured]);
+        // Connecting keeps the oracle registered above
+        contract.ucl.oracles.push(OracleDefinition {
+            id: "uptime_oracle".to_string(),
+            oracle_type: "custom_api".to_string(),
+            endpoint: Some("http://127.0.0.1:9".to_string()),
+            ..Default::default()
+        });
+        contract.connect_oracles().unwrap();
+
+        let explanation = contract.check_conditions_explain().await.unwrap();
+        assert!(!explanation.result.all_met);
+        let [uptime, incidents, latency] = explanation.conditions.as_slice() else {
+            panic!("expected three conditions, got {:?}", explanation.conditions);
+        };
+        assert_eq!(uptime.condition_id, "uptime_met");
+        assert!(!uptime.met);
+        assert_eq!(uptime.value, Some(serde_json::json!(99.5)));
+        assert_eq!(uptime.operator.as_deref(), Some(">="));
+        assert_eq!(uptime.threshold, Some(serde_json::json!(99.9)));
+        assert_eq!(uptime.reason, "99.5 >= 99.9 does not hold");
+
+        assert!(incidents.met && incidents.required);
+        assert_eq!(incidents.source, None);
+        assert_eq!(incidents.inputs["incidents"], serde_json::json!(1));
+        assert_eq!(incidents.reason, "incidents < 3 holds");
+
+        assert!(!latency.met && !latency.required);
+        assert_eq!(latency.reason, "no oracle latency_oracle is registered");
+        assert_eq!(explanation.result.unavailable, ["latency_oracle"]);
+    }
+
+    #[test]
+    fn test_condition_operators() {
+        let value = serde_json::json!(0.995);
+        assert!(condition_met(&condition("c", "greater_than", 0.99), &value));
+        assert!(!condition_met(&condition("c", "<", 0.99), &value));
+        assert!(condition_met(&condition("c", "!=", 1.0), &value));
+
+        let mut flag = condition("c", "==", 0.0);
+        flag.threshold = None;
+        assert!(condition_met(&flag, &serde_json::json!(true)));
+        assert!(!condition_met(&flag, &serde_json::json!(false)));
+    }
+}
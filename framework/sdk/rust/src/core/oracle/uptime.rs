@@ -0,0 +1,260 @@
+//! Uptime oracle for SLA conditions
+
+use super::{Oracle, OracleError};
+use crate::types::OracleDefinition;
+use crate::Result;
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+/// Default rolling window uptime is computed over
+pub const DEFAULT_UPTIME_WINDOW: Duration = Duration::from_secs(30 * 86_400);
+
+/// How a probe decides whether the service is up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ProbeKind {
+    /// Any 2xx response from a health endpoint
+    Healthcheck,
+    /// A Statuspage-style `status.json` reporting `status.indicator`
+    /// `"none"`; degraded or outage indicators count as down
+    StatusPage,
+}
+
+#[derive(Debug, Default)]
+struct Samples {
+    /// Probe time and result, oldest first
+    probes: VecDeque<(Instant, bool)>,
+}
+
+/// Oracle reporting the share of successful probes of a service over a
+/// rolling window, as a fraction between 0 and 1
+///
+/// Probes run in a background task every interval once
+/// [`UptimeOracle::start`] is called; the task stops when the oracle is
+/// dropped.
+pub struct UptimeOracle {
+    id: String,
+    url: String,
+    kind: ProbeKind,
+    interval: Duration,
+    window: Duration,
+    http: reqwest::Client,
+    samples: Arc<Mutex<Samples>>,
+    task: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl UptimeOracle {
+    /// Probe the health endpoint `url` every minute, over the default window
+    pub fn new(id: impl Into<String>, url: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            url: url.into(),
+            kind: ProbeKind::Healthcheck,
+            interval: Duration::from_secs(60),
+            window: DEFAULT_UPTIME_WINDOW,
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap_or_default(),
+            samples: Arc::default(),
+            task: Mutex::new(None),
+        }
+    }
+
+    /// Oracle for an `uptime`, `healthcheck` or `status_page` definition
+    ///
+    /// The endpoint is probed at the definition's refresh rate. Probing
+    /// starts immediately, so this must be called within a Tokio runtime.
+    pub fn from_definition(definition: &OracleDefinition) -> Result<Self> {
+        let endpoint = definition
+            .endpoint
+            .as_ref()
+            .ok_or_else(|| OracleError::misconfigured(&definition.id, "no endpoint"))?;
+        let interval = crate::llmo::lint::parse_interval_secs(&definition.refresh_rate)
+            .filter(|secs| *secs > 0)
+            .ok_or_else(|| {
+                OracleError::misconfigured(
+                    &definition.id,
+                    format!("invalid refresh rate {:?}", definition.refresh_rate),
+                )
+            })?;
+        let kind = if definition.oracle_type == "status_page" {
+            ProbeKind::StatusPage
+        } else {
+            ProbeKind::Healthcheck
+        };
+        let oracle = Self::new(&definition.id, endpoint)
+            .with_kind(kind)
+            .with_interval(Duration::from_secs(interval));
+        oracle.start();
+        Ok(oracle)
+    }
+
+    /// How probes judge the service
+    pub fn with_kind(mut self, kind: ProbeKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Time between background probes
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Rolling window uptime is computed over
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Start probing in the background, first after one interval
+    pub fn start(&self) {
+        let mut task = self.task.lock().unwrap();
+        if task.is_some() {
+            return;
+        }
+        let probe = Prober {
+            url: self.url.clone(),
+            kind: self.kind,
+            http: self.http.clone(),
+            samples: self.samples.clone(),
+        };
+        let interval = self.interval;
+        *task = Some(tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                probe.probe().await;
+            }
+        }));
+    }
+
+    /// Probe once now, returning whether the service is up
+    pub async fn probe(&self) -> bool {
+        Prober {
+            url: self.url.clone(),
+            kind: self.kind,
+            http: self.http.clone(),
+            samples: self.samples.clone(),
+        }
+        .probe()
+        .await
+    }
+
+    /// Uptime over the window, if any probe ran within it
+    pub fn uptime(&self) -> Option<f64> {
+        let mut samples = self.samples.lock().unwrap();
+        while samples
+            .probes
+            .front()
+            .is_some_and(|(at, _)| at.elapsed() > self.window)
+        {
+            samples.probes.pop_front();
+        }
+        let total = samples.probes.len();
+        let up = samples.probes.iter().filter(|(_, up)| *up).count();
+        (total > 0).then(|| up as f64 / total as f64)
+    }
+}
+
+#[async_trait]
+impl Oracle for UptimeOracle {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    async fn read(&self) -> Result<Value> {
+        if self.uptime().is_none() {
+            self.probe().await;
+        }
+        let uptime = self.uptime().unwrap_or(0.0);
+        Ok(serde_json::Number::from_f64(uptime)
+            .map(Value::Number)
+            .unwrap_or(Value::Null))
+    }
+}
+
+impl Drop for UptimeOracle {
+    fn drop(&mut self) {
+        if let Some(task) = self.task.get_mut().unwrap().take() {
+            task.abort();
+        }
+    }
+}
+
+/// Probing state shared with the background task
+struct Prober {
+    url: String,
+    kind: ProbeKind,
+    http: reqwest::Client,
+    samples: Arc<Mutex<Samples>>,
+}
+
+impl Prober {
+    async fn probe(&self) -> bool {
+        let up = match self.http.get(&self.url).send().await {
+            Err(_) => false,
+            Ok(response) if !response.status().is_success() => false,
+            Ok(response) => match self.kind {
+                ProbeKind::Healthcheck => true,
+                ProbeKind::StatusPage => response
+                    .json::<Value>()
+                    .await
+                    .ok()
+                    .and_then(|body| {
+                        body.pointer("/status/indicator")
+                            .and_then(Value::as_str)
+                            .map(|indicator| indicator == "none")
+                    })
+                    .unwrap_or(false),
+            },
+        };
+        self.samples
+            .lock()
+            .unwrap()
+            .probes
+            .push_back((Instant::now(), up));
+        up
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::x402::test_server::serve;
+
+    #[tokio::test]
+    async fn test_rolling_uptime() {
+        let (url, _) = serve(vec![(200, "{}"), (503, "{}"), (200, "{}"), (200, "{}")]).await;
+        let oracle = UptimeOracle::new("uptime_oracle", url);
+        for _ in 0..4 {
+            oracle.probe().await;
+        }
+        assert_eq!(oracle.read().await.unwrap(), serde_json::json!(0.75));
+
+        let oracle = oracle.with_window(Duration::ZERO);
+        assert_eq!(oracle.uptime(), None);
+    }
+
+    #[tokio::test]
+    async fn test_status_page_indicator() {
+        let (url, _) = serve(vec![
+            (
+                200,
+                r#"{"status":{"indicator":"none","description":"All Systems Operational"}}"#,
+            ),
+            (
+                200,
+                r#"{"status":{"indicator":"major","description":"Partial System Outage"}}"#,
+            ),
+        ])
+        .await;
+        let oracle = UptimeOracle::new("status", url).with_kind(ProbeKind::StatusPage);
+        assert!(oracle.probe().await);
+        assert!(!oracle.probe().await);
+    }
+}
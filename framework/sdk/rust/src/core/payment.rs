@@ -0,0 +1,210 @@
+//! Executing payments
+//!
+//! Payments are made by the contract's [`PaymentExecutor`]. When one made
+//! by a monitor fails, e.g. on an RPC error or for lack of gas, the monitor
+//! retries it on the contract's [`RetryPolicy`], recording every attempt in
+//! [`MonitorState::payment_attempts`](super::monitor::MonitorState::payment_attempts).
+//! A payment still failing after the last attempt is escalated with
+//! [`MonitorEvent::PaymentEscalated`](super::monitor::MonitorEvent::PaymentEscalated).
+
+use super::contract::payment_result;
+use crate::x402::RetryPolicy;
+use crate::{Amount, Contract, PaymentResult, Result, UCLContract};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Makes the payments of a contract
+#[async_trait]
+pub trait PaymentExecutor: Send + Sync {
+    /// Pay the contract's payment terms
+    ///
+    /// An error, or a result that is not `success`, is a failed attempt the
+    /// monitor may retry, so a payment must not go through twice when
+    /// retried.
+    async fn pay(&self, ucl: &UCLContract) -> Result<PaymentResult>;
+}
+
+/// Placeholder executor reporting every payment as made
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct PlaceholderExecutor;
+
+#[async_trait]
+impl PaymentExecutor for PlaceholderExecutor {
+    async fn pay(&self, ucl: &UCLContract) -> Result<PaymentResult> {
+        payment_result(ucl)
+    }
+}
+
+/// One try at a payment made by a monitor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PaymentAttempt {
+    pub rule_id: String,
+    /// 1 for the first try, counting up through the retries
+    pub attempt: u32,
+    pub at: DateTime<Utc>,
+    pub amount: Amount,
+    pub token: crate::Token,
+    /// Transaction of a successful attempt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transaction_hash: Option<String>,
+    /// Why a failed attempt failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Contract {
+    /// Make payments with `executor`
+    ///
+    /// Affects [`Contract::execute_payment`] and monitors started
+    /// afterwards.
+    pub fn set_payment_executor(&mut self, executor: Arc<dyn PaymentExecutor>) {
+        self.payer = executor;
+    }
+
+    /// Retry failed monitor payments on `retry`
+    ///
+    /// Payments are tried up to `retry.max_attempts` times, three by
+    /// default; the monitor's next check waits for the retries. Affects
+    /// monitors started afterwards.
+    pub fn set_payment_retry_policy(&mut self, retry: RetryPolicy) {
+        self.payment_retry = retry;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::monitor::{MonitorEvent, MonitorState, Schedule};
+    use crate::core::store::{ContractStore, InMemoryContractStore};
+    use crate::types::{ActionDefinition, RuleDefinition};
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+
+    /// Fails the first `.0` payments with an RPC error
+    struct Flaky(u32, AtomicU32);
+
+    #[async_trait]
+    impl PaymentExecutor for Flaky {
+        async fn pay(&self, ucl: &UCLContract) -> Result<PaymentResult> {
+            if self.1.fetch_add(1, Ordering::SeqCst) < self.0 {
+                return Err(crate::Error::NetworkError("rpc unavailable".to_string()));
+            }
+            payment_result(ucl)
+        }
+    }
+
+    /// Rejects every payment for good
+    struct Rejecting;
+
+    #[async_trait]
+    impl PaymentExecutor for Rejecting {
+        async fn pay(&self, _ucl: &UCLContract) -> Result<PaymentResult> {
+            Err(crate::Error::PaymentError("insufficient allowance".to_string()))
+        }
+    }
+
+    fn paying_contract(failures: u32) -> Contract {
+        let mut contract = crate::core::fixtures::contract();
+        contract.ucl.rules.push(RuleDefinition {
+            rule_id: "monthly_payment".to_string(),
+            name: "Execute Monthly Payment".to_string(),
+            trigger: "time_based".to_string(),
+            actions: vec![ActionDefinition {
+                action: "execute_payment".to_string(),
+                params: Default::default(),
+            }],
+            ..Default::default()
+        });
+        contract.set_payment_executor(Arc::new(Flaky(failures, AtomicU32::new(0))));
+        contract.set_payment_retry_policy(RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(1),
+            jitter: false,
+            ..RetryPolicy::default()
+        });
+        contract
+    }
+
+    async fn first_payment(contract: &Contract) -> (Vec<MonitorEvent>, MonitorState) {
+        let store = Arc::new(InMemoryContractStore::new());
+        let monitor = contract
+            .resume_monitoring(Schedule::parse("10ms").unwrap(), store.clone())
+            .unwrap();
+        let mut events = monitor.subscribe();
+        let mut seen = Vec::new();
+        loop {
+            let event = events.recv().await.unwrap();
+            let done = matches!(
+                event,
+                MonitorEvent::PaymentExecuted(_) | MonitorEvent::PaymentEscalated { .. }
+            );
+            seen.push(event);
+            if done {
+                break;
+            }
+        }
+        monitor.stop().await;
+        let state = store
+            .load_monitor(&contract.ucl.contract_id)
+            .unwrap()
+            .unwrap();
+        (seen, state)
+    }
+
+    #[tokio::test]
+    async fn test_failed_payment_is_retried_until_it_goes_through() {
+        let contract = paying_contract(2);
+        let (events, state) = first_payment(&contract).await;
+
+        let retries = events
+            .iter()
+            .filter(|event| matches!(event, MonitorEvent::PaymentRetrying { .. }))
+            .count();
+        assert_eq!(retries, 2);
+        let attempts: Vec<_> = state
+            .payment_attempts
+            .iter()
+            .map(|attempt| (attempt.attempt, attempt.error.is_none()))
+            .collect();
+        assert_eq!(attempts, vec![(1, false), (2, false), (3, true)]);
+        assert_eq!(state.payment_failures, 0);
+    }
+
+    #[tokio::test]
+    async fn test_payment_escalates_after_last_attempt() {
+        let contract = paying_contract(u32::MAX);
+        let (events, state) = first_payment(&contract).await;
+
+        let Some(MonitorEvent::PaymentEscalated {
+            rule_id,
+            attempts,
+            error,
+        }) = events.last()
+        else {
+            panic!("expected an escalation, got {:?}", events);
+        };
+        assert_eq!(rule_id, "monthly_payment");
+        assert_eq!(*attempts, 3);
+        assert!(error.contains("rpc unavailable"), "{}", error);
+        assert_eq!(state.payment_attempts.len(), 3);
+        assert_eq!(state.payment_failures, 1);
+        assert!(state.next_payment_due.is_none());
+    }
+    #[tokio::test]
+    async fn test_permanent_failure_escalates_without_retrying() {
+        let mut contract = paying_contract(0);
+        contract.set_payment_executor(Arc::new(Rejecting));
+        let (events, state) = first_payment(&contract).await;
+
+        assert!(!events
+            .iter()
+            .any(|event| matches!(event, MonitorEvent::PaymentRetrying { .. })));
+        let Some(MonitorEvent::PaymentEscalated { attempts, .. }) = events.last() else {
+            panic!("expected an escalation, got {:?}", events);
+        };
+        assert_eq!(*attempts, 1);
+        assert_eq!(state.payment_attempts.len(), 1);
+    }
+}
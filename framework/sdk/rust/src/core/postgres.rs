@@ -0,0 +1,504 @@
+//! Postgres contract store
+//!
+//! [`PostgresContractStore`] keeps contracts, monitor state, condition
+//! history and event logs in a Postgres database shared by every instance
+//! of a deployment. Tables are created and upgraded on connect; contracts
+//! are stored as JSONB next to indexed columns, so queries match those of
+//! the SQLite store without reading every contract.
+//!
+//! Each contract row has a version, bumped by every save. Instances that
+//! edit the same contracts read it with [`PostgresContractStore::get_versioned`]
+//! and write with [`PostgresContractStore::update_contract`], which fails
+//! with [`StoreError::Conflict`] instead of overwriting a concurrent edit.
+
+use super::events::ContractEvent;
+use super::monitor::MonitorState;
+use super::store::{ContractQuery, ContractStore, StoreError};
+use crate::{ConditionRecord, Result, UCLContract};
+use sqlx::postgres::{PgPool, PgPoolOptions};
+use sqlx::{Executor, Row};
+use std::future::Future;
+
+/// Schema migrations, in order; `smart402_migrations` records the ones
+/// applied
+const MIGRATIONS: &[&str] = &["
+    CREATE TABLE smart402_contracts (
+        contract_id TEXT PRIMARY KEY,
+        contract_type TEXT NOT NULL,
+        category TEXT NOT NULL,
+        network TEXT NOT NULL,
+        document JSONB NOT NULL,
+        version BIGINT NOT NULL DEFAULT 1
+    );
+    CREATE INDEX smart402_contracts_type ON smart402_contracts (contract_type);
+    CREATE INDEX smart402_contracts_category ON smart402_contracts (category);
+    CREATE INDEX smart402_contracts_network ON smart402_contracts (network);
+    CREATE INDEX smart402_contracts_tags
+        ON smart402_contracts USING GIN ((document -> 'metadata' -> 'tags'));
+    CREATE TABLE smart402_monitors (
+        contract_id TEXT PRIMARY KEY,
+        state JSONB NOT NULL
+    );
+    CREATE TABLE smart402_condition_history (
+        seq BIGSERIAL PRIMARY KEY,
+        contract_id TEXT NOT NULL,
+        record JSONB NOT NULL
+    );
+    CREATE INDEX smart402_condition_history_contract
+        ON smart402_condition_history (contract_id, seq);
+    CREATE TABLE smart402_events (
+        seq BIGSERIAL PRIMARY KEY,
+        contract_id TEXT NOT NULL,
+        at TIMESTAMPTZ NOT NULL,
+        event JSONB NOT NULL
+    );
+    CREATE INDEX smart402_events_contract ON smart402_events (contract_id, seq);
+"];
+
+/// Advisory lock held while migrating, so instances starting together
+/// migrate once
+const MIGRATION_LOCK: i64 = 0x5334_3032;
+
+/// Store keeping everything in a shared Postgres database
+///
+/// Queries run on a runtime of the store's own, so the store can be used
+/// from synchronous code and from any async runtime alike.
+#[derive(Debug)]
+pub struct PostgresContractStore {
+    /// Database URL without its password, for errors
+    database: String,
+    pool: PgPool,
+    runtime: Option<tokio::runtime::Runtime>,
+}
+
+impl PostgresContractStore {
+    /// Connect to the database at `url`, creating or upgrading its tables
+    pub fn connect(url: &str) -> Result<Self> {
+        let database = super::store::redact(url);
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("smart402-postgres")
+            .enable_all()
+            .build()
+            .map_err(|e| StoreError::Database {
+                database: database.clone(),
+                reason: e.to_string(),
+            })?;
+        let options = PgPoolOptions::new().max_connections(8);
+        let url = url.to_string();
+        let pool = block_on(&runtime, async move { options.connect(&url).await })
+            .map_err(database_error(&database))?;
+        let store = Self {
+            database,
+            pool,
+            runtime: Some(runtime),
+        };
+        store.run(migrate(store.pool.clone()))?;
+        Ok(store)
+    }
+
+    /// Contract with id `contract_id` and the version of its row
+    pub fn get_versioned(&self, contract_id: &str) -> Result<Option<(UCLContract, u64)>> {
+        let pool = self.pool.clone();
+        let contract_id = contract_id.to_string();
+        let row = self.run(async move {
+            sqlx::query(
+                "SELECT document::text, version FROM smart402_contracts WHERE contract_id = $1",
+            )
+            .bind(contract_id)
+            .fetch_optional(&pool)
+            .await?
+            .map(|row| Ok((row.try_get::<String, _>(0)?, row.try_get::<i64, _>(1)?)))
+            .transpose()
+        })?;
+        row.map(|(document, version)| Ok((self.contract(&document)?, version as u64)))
+            .transpose()
+    }
+
+    /// Save `ucl` if its row is still at `version`, returning the new
+    /// version
+    ///
+    /// Fails with [`StoreError::Conflict`] when another instance saved or
+    /// removed the contract since `version` was read.
+    pub fn update_contract(&self, ucl: &UCLContract, version: u64) -> Result<u64> {
+        let pool = self.pool.clone();
+        let columns = Columns::of(ucl)?;
+        let updated = self.run(async move {
+            sqlx::query(
+                "UPDATE smart402_contracts
+                 SET contract_type = $2, category = $3, network = $4, document = $5::jsonb,
+                     version = version + 1
+                 WHERE contract_id = $1 AND version = $6
+                 RETURNING version",
+            )
+            .bind(columns.contract_id)
+            .bind(columns.contract_type)
+            .bind(columns.category)
+            .bind(columns.network)
+            .bind(columns.document)
+            .bind(version as i64)
+            .fetch_optional(&pool)
+            .await?
+            .map(|row| row.try_get::<i64, _>(0))
+            .transpose()
+        })?;
+        match updated {
+            Some(version) => Ok(version as u64),
+            None => Err(StoreError::Conflict {
+                contract_id: ucl.contract_id.clone(),
+                expected: version,
+            }
+            .into()),
+        }
+    }
+
+    /// Run a query on the store's runtime and wait for it
+    fn run<T: Send + 'static>(
+        &self,
+        query: impl Future<Output = sqlx::Result<T>> + Send + 'static,
+    ) -> Result<T> {
+        let runtime = self.runtime.as_ref().expect("runtime lives until drop");
+        Ok(block_on(runtime, query).map_err(database_error(&self.database))?)
+    }
+
+    /// Text of the JSON column of the rows `sql` selects, with `binds` as
+    /// its parameters
+    fn select(&self, sql: &'static str, binds: Vec<Option<String>>) -> Result<Vec<String>> {
+        let pool = self.pool.clone();
+        self.run(async move {
+            let mut query = sqlx::query_scalar(sql);
+            for bind in binds {
+                query = query.bind(bind);
+            }
+            query.fetch_all(&pool).await
+        })
+    }
+
+    fn execute(&self, sql: &'static str, binds: Vec<String>) -> Result<()> {
+        let pool = self.pool.clone();
+        self.run(async move {
+            let mut query = sqlx::query(sql);
+            for bind in binds {
+                query = query.bind(bind);
+            }
+            query.execute(&pool).await.map(drop)
+        })
+    }
+
+    fn decode<T: serde::de::DeserializeOwned>(&self, document: &str) -> Result<T> {
+        serde_json::from_str(document).map_err(|e| self.corrupt(e))
+    }
+
+    /// Contract of a `document` column, upgraded to the current schema
+    fn contract(&self, document: &str) -> Result<UCLContract> {
+        crate::utils::migrate::migrate(self.decode(document)?).map_err(|e| self.corrupt(e))
+    }
+
+    fn contracts(&self, sql: &'static str, binds: Vec<Option<String>>) -> Result<Vec<UCLContract>> {
+        self.select(sql, binds)?
+            .iter()
+            .map(|document| self.contract(document))
+            .collect()
+    }
+
+    fn corrupt(&self, e: impl std::fmt::Display) -> crate::Error {
+        StoreError::Database {
+            database: self.database.clone(),
+            reason: format!("corrupt row: {}", e),
+        }
+        .into()
+    }
+}
+
+impl Drop for PostgresContractStore {
+    fn drop(&mut self) {
+        // Dropping a runtime blocks, which panics inside another runtime
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+impl ContractStore for PostgresContractStore {
+    fn load_monitor(&self, contract_id: &str) -> Result<Option<MonitorState>> {
+        let mut states = self.select(
+            "SELECT state::text FROM smart402_monitors WHERE contract_id = $1",
+            vec![Some(contract_id.to_string())],
+        )?;
+        states.pop().map(|state| self.decode(&state)).transpose()
+    }
+
+    fn save_monitor(&self, state: &MonitorState) -> Result<()> {
+        self.execute(
+            "INSERT INTO smart402_monitors (contract_id, state) VALUES ($1, $2::jsonb)
+             ON CONFLICT (contract_id) DO UPDATE SET state = excluded.state",
+            vec![state.contract_id.clone(), serde_json::to_string(state)?],
+        )
+    }
+
+    fn load_contracts(&self) -> Result<Vec<UCLContract>> {
+        self.contracts("SELECT document::text FROM smart402_contracts", Vec::new())
+    }
+
+    fn get_contract(&self, contract_id: &str) -> Result<Option<UCLContract>> {
+        Ok(self.get_versioned(contract_id)?.map(|(ucl, _)| ucl))
+    }
+
+    fn query_contracts(&self, query: &ContractQuery) -> Result<Vec<UCLContract>> {
+        // Unset fields are NULL and match every row
+        self.contracts(
+            "SELECT document::text FROM smart402_contracts
+             WHERE ($1::text IS NULL OR contract_type = $1)
+               AND ($2::text IS NULL OR category = $2)
+               AND ($3::text IS NULL OR network = $3)
+               AND ($4::text IS NULL OR document -> 'metadata' -> 'tags' ? $4)
+               AND ($5::text IS NULL OR EXISTS (
+                   SELECT 1 FROM jsonb_array_elements(document -> 'metadata' -> 'parties') party
+                   WHERE party ->> 'identifier' = $5
+                      OR party ->> 'did' = $5
+                      OR lower(party ->> 'wallet') = lower($5)))",
+            vec![
+                query.contract_type.clone(),
+                query.category.clone(),
+                query.network.map(|network| network.name().to_string()),
+                query.tag.clone(),
+                query.party.clone(),
+            ],
+        )
+    }
+
+    fn save_contract(&self, ucl: &UCLContract) -> Result<()> {
+        let columns = Columns::of(ucl)?;
+        self.execute(
+            "INSERT INTO smart402_contracts
+             (contract_id, contract_type, category, network, document)
+             VALUES ($1, $2, $3, $4, $5::jsonb)
+             ON CONFLICT (contract_id) DO UPDATE SET
+                 contract_type = excluded.contract_type,
+                 category = excluded.category,
+                 network = excluded.network,
+                 document = excluded.document,
+                 version = smart402_contracts.version + 1",
+            vec![
+                columns.contract_id,
+                columns.contract_type,
+                columns.category,
+                columns.network,
+                columns.document,
+            ],
+        )
+    }
+
+    fn remove_contract(&self, contract_id: &str) -> Result<()> {
+        self.execute(
+            "DELETE FROM smart402_contracts WHERE contract_id = $1",
+            vec![contract_id.to_string()],
+        )
+    }
+
+    fn append_condition_history(
+        &self,
+        contract_id: &str,
+        records: &[ConditionRecord],
+    ) -> Result<()> {
+        let records = records
+            .iter()
+            .map(serde_json::to_string)
+            .collect::<serde_json::Result<Vec<_>>>()?;
+        let pool = self.pool.clone();
+        let contract_id = contract_id.to_string();
+        // One statement per check, so a crash loses at most that check
+        self.run(async move {
+            sqlx::query(
+                "INSERT INTO smart402_condition_history (contract_id, record)
+                 SELECT $1, record::jsonb FROM unnest($2::text[]) WITH ORDINALITY AS r (record, n)
+                 ORDER BY n",
+            )
+            .bind(contract_id)
+            .bind(records)
+            .execute(&pool)
+            .await
+            .map(drop)
+        })
+    }
+
+    fn load_condition_history(&self, contract_id: &str) -> Result<Vec<ConditionRecord>> {
+        self.select(
+            "SELECT record::text FROM smart402_condition_history
+             WHERE contract_id = $1 ORDER BY seq",
+            vec![Some(contract_id.to_string())],
+        )?
+        .iter()
+        .map(|record| self.decode(record))
+        .collect()
+    }
+
+    fn append_event(&self, event: &ContractEvent) -> Result<()> {
+        self.execute(
+            "INSERT INTO smart402_events (contract_id, at, event)
+             VALUES ($1, $2::timestamptz, $3::jsonb)",
+            vec![
+                event.contract_id.clone(),
+                event.at.to_rfc3339(),
+                serde_json::to_string(event)?,
+            ],
+        )
+    }
+
+    fn load_events(&self, contract_id: &str) -> Result<Vec<ContractEvent>> {
+        self.select(
+            "SELECT event::text FROM smart402_events WHERE contract_id = $1 ORDER BY seq",
+            vec![Some(contract_id.to_string())],
+        )?
+        .iter()
+        .map(|event| self.decode(event))
+        .collect()
+    }
+}
+
+/// Apply the migrations not applied yet
+async fn migrate(pool: PgPool) -> sqlx::Result<()> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("SELECT pg_advisory_xact_lock($1)")
+        .bind(MIGRATION_LOCK)
+        .execute(&mut *tx)
+        .await?;
+    // Statements without parameters run unprepared, several at a time
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS smart402_migrations (
+             version INTEGER PRIMARY KEY,
+             applied_at TIMESTAMPTZ NOT NULL DEFAULT now()
+         )",
+    )
+    .await?;
+    let applied: i32 =
+        sqlx::query_scalar("SELECT coalesce(max(version), 0) FROM smart402_migrations")
+            .fetch_one(&mut *tx)
+            .await?;
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(applied as usize) {
+        tx.execute(*migration).await?;
+        sqlx::query("INSERT INTO smart402_migrations (version) VALUES ($1)")
+            .bind(i as i32 + 1)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await
+}
+
+/// Indexed columns and document of a contract row
+struct Columns {
+    contract_id: String,
+    contract_type: String,
+    category: String,
+    network: String,
+    document: String,
+}
+
+impl Columns {
+    fn of(ucl: &UCLContract) -> Result<Self> {
+        Ok(Self {
+            contract_id: ucl.contract_id.clone(),
+            contract_type: ucl.metadata.contract_type.clone(),
+            category: ucl.metadata.category.clone(),
+            network: ucl.payment.blockchain.name().to_string(),
+            document: serde_json::to_string(ucl)?,
+        })
+    }
+}
+
+/// Run `future` on `runtime` and wait for it, from inside another runtime
+/// or outside any
+fn block_on<T: Send + 'static>(
+    runtime: &tokio::runtime::Runtime,
+    future: impl Future<Output = T> + Send + 'static,
+) -> T {
+    let (tx, rx) = std::sync::mpsc::sync_channel(1);
+    runtime.spawn(async move {
+        let _ = tx.send(future.await);
+    });
+    rx.recv().expect("store runtime outlives its queries")
+}
+
+fn database_error(database: &str) -> impl FnOnce(sqlx::Error) -> StoreError + '_ {
+    move |e| StoreError::Database {
+        database: database.to_string(),
+        reason: e.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Contract, Network};
+
+    /// Database the test runs against, e.g.
+    /// `postgres://postgres@localhost/smart402_test`; skipped when unset
+    const DATABASE_URL_ENV: &str = "SMART402_TEST_DATABASE_URL";
+
+    #[test]
+    #[ignore = "needs SMART402_TEST_DATABASE_URL"]
+    fn test_postgres_store_locks_contract_updates() {
+        let url = std::env::var(DATABASE_URL_ENV)
+            .unwrap_or_else(|_| panic!("{} must name a Postgres database", DATABASE_URL_ENV));
+        let store = PostgresContractStore::connect(&url).unwrap();
+        // Instances connecting later find the schema up to date
+        let other = PostgresContractStore::connect(&url).unwrap();
+
+        let mut ucl = Contract::from_config(Default::default()).unwrap().ucl;
+        ucl.contract_id = format!("smart402:contract:{:016x}", rand::random::<u64>());
+        ucl.metadata.tags = vec![ucl.contract_id.clone()];
+        store.save_contract(&ucl).unwrap();
+        let (mut mine, version) = store.get_versioned(&ucl.contract_id).unwrap().unwrap();
+        let (mut theirs, _) = other.get_versioned(&ucl.contract_id).unwrap().unwrap();
+
+        theirs.payment.blockchain = Network::Base;
+        assert_eq!(
+            other.update_contract(&theirs, version).unwrap(),
+            version + 1
+        );
+        mine.metadata.category = "stale".to_string();
+        let error = store.update_contract(&mine, version).unwrap_err();
+        assert_eq!(error.code(), "store.conflict");
+
+        let query = ContractQuery::new().with_tag(&ucl.contract_id);
+        let found = store.query_contracts(&query).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].payment.blockchain, Network::Base);
+        assert!(store
+            .query_contracts(&query.clone().with_network(Network::Polygon))
+            .unwrap()
+            .is_empty());
+
+        let event = ContractEvent::new(
+            &ucl.contract_id,
+            crate::ContractEventKind::StatusChanged {
+                from: crate::ContractStatus::Draft,
+                to: crate::ContractStatus::Deployed,
+            },
+        );
+        store.append_event(&event).unwrap();
+        assert_eq!(other.load_events(&ucl.contract_id).unwrap()[0].at, event.at);
+        let records: Vec<crate::ConditionRecord> = (0..3)
+            .map(|i| crate::ConditionRecord {
+                condition_id: format!("condition_{}", i),
+                checked_at: chrono::Utc::now(),
+                met: true,
+                source: None,
+                served_by: None,
+                value: None,
+                inputs: Default::default(),
+            })
+            .collect();
+        store
+            .append_condition_history(&ucl.contract_id, &records)
+            .unwrap();
+        assert_eq!(
+            other.load_condition_history(&ucl.contract_id).unwrap(),
+            records
+        );
+
+        store.remove_contract(&ucl.contract_id).unwrap();
+        let error = other.update_contract(&theirs, version + 1).unwrap_err();
+        assert_eq!(error.code(), "store.conflict");
+    }
+}
@@ -0,0 +1,340 @@
+//! WebSocket push of contract events to dashboards
+//!
+//! An [`EventServer`] relays the events of any number of contracts and
+//! store-wide monitors to WebSocket clients at `/events`:
+//!
+//! - clients authenticate with a token, sent as `Authorization: Bearer
+//!   <token>` or, for browsers, as the `token` query parameter
+//! - every `contract` query parameter adds a contract to follow; without
+//!   any, a client follows every contract its token may read
+//! - `{"subscribe": ["<contract id>"]}` and `{"unsubscribe": [...]}`
+//!   change the followed contracts of an open connection and are answered
+//!   with `{"topics": [...]}`, or `{"error": "..."}`
+//!
+//! Each event is pushed as one JSON text message shaped like
+//! [`ContractEvent`]. A client too slow to keep up gets `{"missed": <n>}`
+//! in place of the events it missed.
+//!
+//! ```no_run
+//! # async fn run(contract: smart402::Contract) -> smart402::Result<()> {
+//! use smart402::EventServer;
+//!
+//! let server = EventServer::new()
+//!     .with_token("dashboard-secret")
+//!     .with_contract_token("partner-secret", [contract.ucl.contract_id.clone()]);
+//! server.forward(contract.subscribe());
+//! server.serve("127.0.0.1:9402".parse().unwrap()).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use super::events::ContractEvent;
+use crate::Result;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use futures::{Stream, StreamExt};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+/// Events buffered per connection before the oldest are dropped
+const EVENT_CAPACITY: usize = 1024;
+
+/// Contracts a token may read
+#[derive(Debug, Clone)]
+enum Access {
+    All,
+    Contracts(HashSet<String>),
+}
+
+impl Access {
+    fn allows(&self, contract_id: &str) -> bool {
+        match self {
+            Access::All => true,
+            Access::Contracts(ids) => ids.contains(contract_id),
+        }
+    }
+}
+
+/// WebSocket server pushing contract events
+///
+/// Clones share the same clients and sources. Connections without a
+/// known token are refused, so a server without tokens accepts no one.
+#[derive(Clone)]
+pub struct EventServer {
+    /// Access of each token, keyed by the token's SHA-256 so lookups do not
+    /// leak how much of a guessed token matched
+    tokens: Arc<HashMap<[u8; 32], Access>>,
+    events: broadcast::Sender<ContractEvent>,
+}
+
+impl Default for EventServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventServer {
+    /// Server without sources or tokens
+    pub fn new() -> Self {
+        Self {
+            tokens: Arc::default(),
+            events: broadcast::channel(EVENT_CAPACITY).0,
+        }
+    }
+
+    /// Let clients presenting `token` read the events of every contract
+    pub fn with_token(mut self, token: impl AsRef<str>) -> Self {
+        Arc::make_mut(&mut self.tokens).insert(digest(token.as_ref()), Access::All);
+        self
+    }
+
+    /// Let clients presenting `token` read the events of `contracts` only
+    pub fn with_contract_token<I>(mut self, token: impl AsRef<str>, contracts: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        let ids = contracts.into_iter().map(Into::into).collect();
+        Arc::make_mut(&mut self.tokens).insert(digest(token.as_ref()), Access::Contracts(ids));
+        self
+    }
+
+    /// Push the events of `events` to clients, e.g. from
+    /// [`Contract::subscribe`](crate::Contract::subscribe) or
+    /// [`FleetHandle::subscribe`](crate::FleetHandle::subscribe)
+    ///
+    /// Sources can be added while the server runs; each is forwarded until
+    /// it ends.
+    pub fn forward<S>(&self, events: S)
+    where
+        S: Stream<Item = ContractEvent> + Send + 'static,
+    {
+        let sender = self.events.clone();
+        tokio::spawn(async move {
+            let mut events = Box::pin(events);
+            while let Some(event) = events.next().await {
+                // Sending only fails while no client is connected
+                let _ = sender.send(event);
+            }
+        });
+    }
+
+    /// Router serving the WebSocket endpoint at `/events`
+    pub fn router(self) -> Router {
+        Router::new()
+            .route("/events", get(connect))
+            .with_state(self)
+    }
+
+    /// Listen on `addr` until the process exits
+    pub async fn serve(self, addr: SocketAddr) -> Result<()> {
+        axum::Server::try_bind(&addr)
+            .map_err(|e| crate::Error::ConfigError(format!("Cannot listen on {}: {}", addr, e)))?
+            .serve(self.router().into_make_service())
+            .await
+            .map_err(|e| crate::Error::NetworkError(e.to_string()))
+    }
+
+    fn access(&self, token: &str) -> Option<Access> {
+        self.tokens.get(&digest(token)).cloned()
+    }
+}
+
+fn digest(token: &str) -> [u8; 32] {
+    Sha256::digest(token.as_bytes()).into()
+}
+
+async fn connect(
+    State(server): State<EventServer>,
+    Query(params): Query<Vec<(String, String)>>,
+    headers: HeaderMap,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let token = bearer.or_else(|| {
+        params
+            .iter()
+            .find(|(name, _)| name == "token")
+            .map(|(_, value)| value.as_str())
+    });
+    let Some(access) = token.and_then(|token| server.access(token)) else {
+        return (StatusCode::UNAUTHORIZED, "Missing or unknown token").into_response();
+    };
+
+    let topics: BTreeSet<String> = params
+        .into_iter()
+        .filter(|(name, _)| name == "contract")
+        .map(|(_, id)| id)
+        .collect();
+    if let Some(id) = topics.iter().find(|id| !access.allows(id)) {
+        return (
+            StatusCode::FORBIDDEN,
+            format!("Token may not read contract {}", id),
+        )
+            .into_response();
+    }
+
+    // Subscribe before answering so no event after the handshake is lost
+    let events = server.events.subscribe();
+    upgrade.on_upgrade(move |socket| push(socket, access, topics, events))
+}
+
+/// Change to the contracts a connection follows
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Control {
+    Subscribe(Vec<String>),
+    Unsubscribe(Vec<String>),
+}
+
+async fn push(
+    mut socket: WebSocket,
+    access: Access,
+    mut topics: BTreeSet<String>,
+    mut events: broadcast::Receiver<ContractEvent>,
+) {
+    loop {
+        let reply = tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => {
+                    let id = &event.contract_id;
+                    if !access.allows(id) || !(topics.is_empty() || topics.contains(id)) {
+                        continue;
+                    }
+                    serde_json::to_value(&event)
+                        .unwrap_or_else(|e| serde_json::json!({ "error": e.to_string() }))
+                }
+                Err(RecvError::Lagged(missed)) => serde_json::json!({ "missed": missed }),
+                Err(RecvError::Closed) => return,
+            },
+            message = socket.recv() => match message {
+                Some(Ok(Message::Text(text))) => control(&access, &mut topics, &text),
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                // Pings are answered by the socket itself
+                Some(Ok(_)) => continue,
+            },
+        };
+        if socket.send(Message::Text(reply.to_string())).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Apply a control message, returning the reply
+fn control(access: &Access, topics: &mut BTreeSet<String>, text: &str) -> serde_json::Value {
+    match serde_json::from_str(text) {
+        Ok(Control::Subscribe(ids)) => {
+            if let Some(id) = ids.iter().find(|id| !access.allows(id)) {
+                return serde_json::json!({
+                    "error": format!("Token may not read contract {}", id)
+                });
+            }
+            topics.extend(ids);
+        }
+        Ok(Control::Unsubscribe(ids)) => {
+            for id in &ids {
+                topics.remove(id);
+            }
+        }
+        Err(e) => return serde_json::json!({ "error": format!("Invalid message: {}", e) }),
+    }
+    serde_json::json!({ "topics": topics })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::events::ContractEventKind;
+    use crate::ContractStatus;
+    use futures::SinkExt;
+    use tokio_tungstenite::tungstenite::Message as ClientMessage;
+
+    fn status_changed(contract_id: &str) -> ContractEvent {
+        ContractEvent::new(
+            contract_id,
+            ContractEventKind::StatusChanged {
+                from: ContractStatus::Deployed,
+                to: ContractStatus::Active,
+            },
+        )
+    }
+
+    async fn next_json<S>(client: &mut S) -> serde_json::Value
+    where
+        S: Stream<Item = tokio_tungstenite::tungstenite::Result<ClientMessage>> + Unpin,
+    {
+        let message = client.next().await.unwrap().unwrap();
+        serde_json::from_str(message.to_text().unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_clients_get_events_of_followed_contracts_only() {
+        let (source, events) = futures::channel::mpsc::unbounded();
+        let server = EventServer::new()
+            .with_token("admin")
+            .with_contract_token("partner", ["contract:a"]);
+        server.forward(events);
+        let http = axum::Server::bind(&"127.0.0.1:0".parse().unwrap())
+            .serve(server.router().into_make_service());
+        let url = format!("ws://{}/events", http.local_addr());
+        tokio::spawn(http);
+
+        assert!(tokio_tungstenite::connect_async(url.clone()).await.is_err());
+        assert!(tokio_tungstenite::connect_async(format!(
+            "{}?token=partner&contract=contract:b",
+            url
+        ))
+        .await
+        .is_err());
+
+        let (mut partner, _) = tokio_tungstenite::connect_async(format!("{}?token=partner", url))
+            .await
+            .unwrap();
+        let (mut admin, _) =
+            tokio_tungstenite::connect_async(format!("{}?token=admin&contract=contract:a", url))
+                .await
+                .unwrap();
+
+        source.unbounded_send(status_changed("contract:b")).unwrap();
+        source.unbounded_send(status_changed("contract:a")).unwrap();
+        assert_eq!(next_json(&mut partner).await["contract_id"], "contract:a");
+        let event = next_json(&mut admin).await;
+        assert_eq!(event["contract_id"], "contract:a");
+        assert_eq!(event["event"]["status_changed"]["to"], "active");
+
+        partner
+            .send(ClientMessage::Text(
+                r#"{"subscribe":["contract:b"]}"#.into(),
+            ))
+            .await
+            .unwrap();
+        assert!(next_json(&mut partner).await["error"]
+            .as_str()
+            .unwrap()
+            .contains("contract:b"));
+
+        admin
+            .send(ClientMessage::Text(
+                r#"{"subscribe":["contract:b"]}"#.into(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(
+            next_json(&mut admin).await["topics"],
+            serde_json::json!(["contract:a", "contract:b"])
+        );
+        source.unbounded_send(status_changed("contract:b")).unwrap();
+        assert_eq!(next_json(&mut admin).await["contract_id"], "contract:b");
+    }
+}
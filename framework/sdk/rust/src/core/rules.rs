@@ -0,0 +1,175 @@
+//! Rule triggers and the action log
+//!
+//! The monitor runs a rule's actions when its trigger fires and its
+//! conditions hold. Built-in actions are `send_payment` (or
+//! `execute_payment`), `notify`, `escalate`, `pause_contract`,
+//! `resume_contract`, `hold_payments`, `switch_oracle` and
+//! `apply_late_fee`; other actions are run from the monitor's
+//! [`ActionRegistry`](super::actions::ActionRegistry) or, if not
+//! registered, published for subscribers to carry out. Every
+//! action run is appended to the monitor's action log.
+//!
+//! The `oracle_failure` and `payment_failure` triggers handle things going
+//! wrong: they fire once when an oracle has failed to respond, or payments
+//! have failed, `after_failures` times in a row. A rule pausing the
+//! contract on `oracle_failure` acts as a dead-man switch.
+
+use crate::types::OracleAttestation;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Most recent action records kept in the monitor state
+pub const ACTION_LOG_LIMIT: usize = 500;
+
+/// When a rule's actions run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Trigger {
+    /// On every check where the rule's conditions hold
+    Scheduled,
+    /// Once each time the rule's conditions start to hold
+    ConditionChange,
+    /// Never automatically
+    Manual,
+    /// When oracle reads have failed the rule's `after_failures` times in
+    /// a row
+    OracleFailure,
+    /// When payments have failed the rule's `after_failures` times in a
+    /// row
+    PaymentFailure,
+}
+
+impl Trigger {
+    /// Whether the trigger fires, given whether the rule's conditions held
+    /// at the previous check and hold now
+    ///
+    /// Failure triggers never fire on condition results alone; see
+    /// [`Trigger::fires_on_failures`].
+    pub fn fires(self, held: bool, holds: bool) -> bool {
+        match self {
+            Trigger::Scheduled => holds,
+            Trigger::ConditionChange => holds && !held,
+            Trigger::Manual | Trigger::OracleFailure | Trigger::PaymentFailure => false,
+        }
+    }
+
+    /// Whether a failure trigger fires, given the consecutive failures
+    /// before and after the latest attempt and the rule's threshold
+    pub fn fires_on_failures(self, before: u32, after: u32, threshold: u32) -> bool {
+        match self {
+            Trigger::OracleFailure | Trigger::PaymentFailure => {
+                before < threshold.max(1) && after >= threshold.max(1)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the trigger is driven by failure counts
+    pub fn is_failure(self) -> bool {
+        matches!(self, Trigger::OracleFailure | Trigger::PaymentFailure)
+    }
+}
+
+impl FromStr for Trigger {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> crate::Result<Self> {
+        match s.trim().to_lowercase().replace('-', "_").as_str() {
+            "time_based" | "scheduled" | "schedule" | "periodic" => Ok(Trigger::Scheduled),
+            "condition_change" | "condition_based" | "condition_met" | "event_based"
+            | "on_change" => Ok(Trigger::ConditionChange),
+            "manual" => Ok(Trigger::Manual),
+            "oracle_failure" | "oracle_down" | "oracle_unavailable" => Ok(Trigger::OracleFailure),
+            "payment_failure" | "payment_failed" => Ok(Trigger::PaymentFailure),
+            other => Err(crate::Error::ConfigError(format!(
+                "Unknown rule trigger {}",
+                other
+            ))),
+        }
+    }
+}
+
+/// Payments held by a `hold_payments` action
+///
+/// A hold placed by an `oracle_failure` rule is released once the oracles
+/// it watches respond again; any hold is released by `resume_contract`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PaymentHold {
+    pub rule_id: String,
+    pub since: DateTime<Utc>,
+}
+
+/// One action run, as kept in the monitor's action log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActionRecord {
+    pub rule_id: String,
+    pub action: String,
+    pub at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub outcome: ActionOutcome,
+    /// Attested oracle values of the check that led to a payment
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub evidence: Vec<OracleAttestation>,
+}
+
+/// Result of running an action
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum ActionOutcome {
+    Executed {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        detail: Option<String>,
+    },
+    Skipped {
+        reason: String,
+    },
+    Failed {
+        error: String,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_triggers() {
+        let scheduled: Trigger = "time_based".parse().unwrap();
+        assert!(scheduled.fires(true, true));
+        assert!(!scheduled.fires(true, false));
+
+        let change: Trigger = "condition-change".parse().unwrap();
+        assert!(change.fires(false, true));
+        assert!(!change.fires(true, true));
+
+        assert!(!Trigger::Manual.fires(false, true));
+        assert!("sometimes".parse::<Trigger>().is_err());
+
+        let oracle_down: Trigger = "oracle-failure".parse().unwrap();
+        assert!(!oracle_down.fires(false, true));
+        assert!(oracle_down.fires_on_failures(2, 3, 3));
+        assert!(!oracle_down.fires_on_failures(3, 4, 3));
+        assert!(Trigger::PaymentFailure.fires_on_failures(0, 1, 0));
+    }
+
+    #[test]
+    fn test_action_record_json() {
+        let record = ActionRecord {
+            rule_id: "late_fee".to_string(),
+            action: "send_payment".to_string(),
+            at: "2024-05-01T00:00:00Z".parse().unwrap(),
+            outcome: ActionOutcome::Skipped {
+                reason: "contract is paused".to_string(),
+            },
+            evidence: Vec::new(),
+        };
+        let json = serde_json::to_value(&record).unwrap();
+        assert_eq!(json["status"], "skipped");
+        assert_eq!(
+            serde_json::from_value::<ActionRecord>(json).unwrap(),
+            record
+        );
+    }
+}
@@ -1,6 +1,13 @@
 //! Smart402 Main Struct
 
-use crate::{Contract, ContractConfig, Result};
+use crate::core::store::{ContractQuery, ContractStore};
+use crate::llmo::TemplateError;
+use crate::utils::envelope::ContractEnvelope;
+use crate::{
+    Amount, Contract, ContractConfig, Network, PaymentConfig, PaymentFrequency, Result, Token,
+    UCLContract,
+};
+use std::sync::Arc;
 
 /// Main Smart402 SDK struct
 ///
@@ -11,28 +18,40 @@ use crate::{Contract, ContractConfig, Result};
 ///
 /// #[tokio::main]
 /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
-///     let sdk = Smart402::new("polygon".to_string(), None)?;
+///     let sdk = Smart402::new("polygon", None)?;
 ///     let contract = sdk.create_contract(ContractConfig::default()).await?;
 ///     Ok(())
 /// }
 /// ```
 pub struct Smart402 {
-    network: String,
+    network: Network,
+    /// Key deployments will sign with; not read yet
+    #[allow(dead_code)]
     private_key: Option<String>,
+    store: Option<Arc<dyn ContractStore>>,
 }
 
 impl Smart402 {
     /// Create new Smart402 SDK instance
-    pub fn new(network: String, private_key: Option<String>) -> Result<Self> {
+    ///
+    /// Takes a [`Network`] or a network name; fails on unknown names.
+    pub fn new(network: impl AsRef<str>, private_key: Option<String>) -> Result<Self> {
         Ok(Self {
-            network,
+            network: network.as_ref().parse()?,
             private_key,
+            store: None,
         })
     }
 
+    /// Load and list contracts from `store`
+    pub fn with_store(mut self, store: Arc<dyn ContractStore>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
     /// Create a new contract
     pub async fn create(config: ContractConfig) -> Result<Contract> {
-        let sdk = Self::new("polygon".to_string(), None)?;
+        let sdk = Self::new(Network::Polygon, None)?;
         sdk.create_contract(config).await
     }
 
@@ -41,13 +60,13 @@ impl Smart402 {
         template_name: String,
         variables: std::collections::HashMap<String, serde_json::Value>,
     ) -> Result<Contract> {
-        let sdk = Self::new("polygon".to_string(), None)?;
+        let sdk = Self::new(Network::Polygon, None)?;
         sdk.create_from_template(template_name, variables).await
     }
 
     /// Load existing contract
     pub async fn load(contract_id: String) -> Result<Contract> {
-        let sdk = Self::new("polygon".to_string(), None)?;
+        let sdk = Self::new(Network::Polygon, None)?;
         sdk.load_contract(contract_id).await
     }
 
@@ -58,21 +77,87 @@ impl Smart402 {
     }
 
     /// Create from template
+    ///
+    /// The template sets the contract type and how often it pays;
+    /// `variables` give the `amount`, the `token` (USDC by default) and the
+    /// parties, `vendor_wallet` or `vendor_email` then `customer_wallet` or
+    /// `customer_email`. Fails with [`TemplateError::Unknown`] for names not
+    /// in [`Smart402::get_templates`].
     pub async fn create_from_template(
         &self,
-        _template_name: String,
-        _variables: std::collections::HashMap<String, serde_json::Value>,
+        template_name: String,
+        variables: std::collections::HashMap<String, serde_json::Value>,
     ) -> Result<Contract> {
-        // Placeholder
-        Contract::from_config(ContractConfig::default())
+        if !Self::get_templates().contains(&template_name) {
+            return Err(TemplateError::Unknown {
+                template: template_name,
+            }
+            .into());
+        }
+        fn variable<T: serde::de::DeserializeOwned>(
+            variables: &std::collections::HashMap<String, serde_json::Value>,
+            name: &str,
+        ) -> Result<Option<T>> {
+            variables
+                .get(name)
+                .map(|value| {
+                    serde_json::from_value(value.clone()).map_err(|e| {
+                        crate::Error::ValidationError(format!(
+                            "Invalid template variable {}: {}",
+                            name, e
+                        ))
+                    })
+                })
+                .transpose()
+        }
+        let amount = variable(&variables, "amount")?.unwrap_or(Amount::ZERO);
+        let token = variable(&variables, "token")?.unwrap_or_else(Token::usdc);
+        let frequency = match template_name.as_str() {
+            "saas-subscription" | "affiliate-commission" | "vendor-sla" => {
+                PaymentFrequency::Monthly { day: None }
+            }
+            _ => PaymentFrequency::OneTime,
+        };
+        let mut parties = Vec::new();
+        for role in ["vendor", "customer"] {
+            let wallet = variable::<String>(&variables, &format!("{}_wallet", role))?;
+            let email = variable::<String>(&variables, &format!("{}_email", role))?;
+            parties.extend(wallet.or(email));
+        }
+        let payment = PaymentConfig::new(amount, token, frequency).with_blockchain(self.network);
+        Contract::from_config(ContractConfig::new(template_name, payment).with_parties(parties))
     }
 
     /// Load contract
-    pub async fn load_contract(&self, _contract_id: String) -> Result<Contract> {
+    ///
+    /// A path to a contract file restores the contract with the status and
+    /// deployment saved in it; other ids are looked up in the store, if one
+    /// is set.
+    pub async fn load_contract(&self, contract_id: String) -> Result<Contract> {
+        let path = std::path::Path::new(&contract_id);
+        if path.is_file() {
+            return Contract::from_envelope(crate::utils::load_envelope(path)?);
+        }
+        if let Some(store) = &self.store {
+            if let Some(ucl) = store.get_contract(&contract_id)? {
+                return Contract::from_envelope(ContractEnvelope::draft(ucl));
+            }
+        }
         // Placeholder
         Contract::from_config(ContractConfig::default())
     }
 
+    /// Contracts of the store matching `query`, sorted by id; none without
+    /// a store
+    pub fn list_contracts(&self, query: &ContractQuery) -> Result<Vec<UCLContract>> {
+        let Some(store) = &self.store else {
+            return Ok(Vec::new());
+        };
+        let mut contracts = store.query_contracts(query)?;
+        contracts.sort_by(|a, b| a.contract_id.cmp(&b.contract_id));
+        Ok(contracts)
+    }
+
     /// Get available templates
     pub fn get_templates() -> Vec<String> {
         vec![
@@ -84,3 +169,49 @@ impl Smart402 {
         ]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::fixtures::{CUSTOMER_WALLET, VENDOR_WALLET};
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_template_variables_set_payment_and_parties() {
+        let sdk = Smart402::new("base", None).unwrap();
+        let variables = HashMap::from([
+            ("amount".to_string(), json!("49.5")),
+            ("token".to_string(), json!("USDT")),
+            ("vendor_email".to_string(), json!("vendor@example.com")),
+            ("vendor_wallet".to_string(), json!(VENDOR_WALLET)),
+            ("customer_wallet".to_string(), json!(CUSTOMER_WALLET)),
+        ]);
+        let contract = sdk
+            .create_from_template("vendor-sla".to_string(), variables)
+            .await
+            .unwrap();
+
+        let ucl = &contract.ucl;
+        assert_eq!(ucl.metadata.contract_type, "vendor-sla");
+        assert_eq!(ucl.payment.amount, Amount::new(495, 1));
+        assert!(ucl.payment.token.is("USDT"));
+        assert_eq!(ucl.payment.blockchain, Network::Base);
+        assert_eq!(ucl.payment.frequency, PaymentFrequency::Monthly { day: None });
+        let parties: Vec<_> = ucl.metadata.parties.iter().map(|p| p.identifier.as_str()).collect();
+        assert_eq!(parties, [VENDOR_WALLET, CUSTOMER_WALLET]);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_template_variable() {
+        let sdk = Smart402::new("polygon", None).unwrap();
+        let variables = HashMap::from([("amount".to_string(), json!("lots"))]);
+        match sdk
+            .create_from_template("supply-chain".to_string(), variables)
+            .await
+        {
+            Err(error) => assert!(error.to_string().contains("amount"), "{}", error),
+            Ok(_) => panic!("invalid amount accepted"),
+        }
+    }
+}
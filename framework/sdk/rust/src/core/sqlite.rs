@@ -0,0 +1,369 @@
+//! SQLite contract store
+//!
+//! [`SqliteContractStore`] keeps contracts, monitor state, condition
+//! history and event logs in a single database file. Contracts are stored
+//! as JSON next to indexed columns for their type, category and network,
+//! so [`ContractStore::query_contracts`] does not read every contract, and
+//! the database runs in WAL mode so several monitor processes can share it.
+
+use super::events::ContractEvent;
+use super::monitor::MonitorState;
+use super::store::{corrupt, ContractQuery, ContractStore, StoreError};
+use crate::{ConditionRecord, Result, UCLContract};
+use rusqlite::types::ToSql;
+use rusqlite::{params, Connection};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Schema migrations, in order; the database's `user_version` counts the
+/// ones applied
+const MIGRATIONS: &[&str] = &["
+    CREATE TABLE contracts (
+        contract_id TEXT PRIMARY KEY,
+        contract_type TEXT NOT NULL,
+        category TEXT NOT NULL,
+        network TEXT NOT NULL,
+        document TEXT NOT NULL
+    );
+    CREATE INDEX contracts_type ON contracts (contract_type);
+    CREATE INDEX contracts_category ON contracts (category);
+    CREATE INDEX contracts_network ON contracts (network);
+    CREATE TABLE monitors (
+        contract_id TEXT PRIMARY KEY,
+        state TEXT NOT NULL
+    );
+    CREATE TABLE condition_history (
+        seq INTEGER PRIMARY KEY AUTOINCREMENT,
+        contract_id TEXT NOT NULL,
+        record TEXT NOT NULL
+    );
+    CREATE INDEX condition_history_contract ON condition_history (contract_id, seq);
+    CREATE TABLE events (
+        seq INTEGER PRIMARY KEY AUTOINCREMENT,
+        contract_id TEXT NOT NULL,
+        at TEXT NOT NULL,
+        event TEXT NOT NULL
+    );
+    CREATE INDEX events_contract ON events (contract_id, seq);
+"];
+
+/// Store keeping everything in one SQLite database
+#[derive(Debug)]
+pub struct SqliteContractStore {
+    path: PathBuf,
+    db: Mutex<Connection>,
+}
+
+impl SqliteContractStore {
+    /// Open the database at `path`, creating it and its tables if needed
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).map_err(super::store::io_error(dir))?;
+        }
+        let db = Connection::open(&path).map_err(database(&path))?;
+        let store = Self {
+            path,
+            db: Mutex::new(db),
+        };
+        store.migrate()?;
+        Ok(store)
+    }
+
+    fn migrate(&self) -> Result<()> {
+        let mut db = self.db.lock().unwrap();
+        db.busy_timeout(Duration::from_secs(5))
+            .and_then(|()| db.pragma_update(None, "journal_mode", "WAL"))
+            .map_err(database(&self.path))?;
+        let tx = db.transaction().map_err(database(&self.path))?;
+        let applied: usize = tx
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .map_err(database(&self.path))?;
+        for migration in MIGRATIONS.iter().skip(applied) {
+            tx.execute_batch(migration).map_err(database(&self.path))?;
+        }
+        tx.pragma_update(None, "user_version", MIGRATIONS.len())
+            .and_then(|()| tx.commit())
+            .map_err(database(&self.path))?;
+        Ok(())
+    }
+
+    /// Decoded values of the JSON column of the rows `sql` selects
+    fn select<T: serde::de::DeserializeOwned>(
+        &self,
+        sql: &str,
+        params: &[&dyn ToSql],
+    ) -> Result<Vec<T>> {
+        let db = self.db.lock().unwrap();
+        let documents = db
+            .prepare_cached(sql)
+            .and_then(|mut statement| {
+                statement
+                    .query_map(params, |row| row.get::<_, String>(0))?
+                    .collect::<rusqlite::Result<Vec<_>>>()
+            })
+            .map_err(database(&self.path))?;
+        documents
+            .iter()
+            .map(|document| Ok(serde_json::from_str(document).map_err(corrupt(&self.path))?))
+            .collect()
+    }
+
+    fn execute(&self, sql: &str, params: &[&dyn ToSql]) -> Result<()> {
+        self.db
+            .lock()
+            .unwrap()
+            .prepare_cached(sql)
+            .and_then(|mut statement| statement.execute(params))
+            .map_err(database(&self.path))?;
+        Ok(())
+    }
+
+    /// Contracts of the `document` column, upgraded to the current schema
+    fn contracts(&self, sql: &str, params: &[&dyn ToSql]) -> Result<Vec<UCLContract>> {
+        self.select::<serde_json::Value>(sql, params)?
+            .into_iter()
+            .map(|document| {
+                Ok(crate::utils::migrate::migrate(document).map_err(corrupt(&self.path))?)
+            })
+            .collect()
+    }
+}
+
+impl ContractStore for SqliteContractStore {
+    fn load_monitor(&self, contract_id: &str) -> Result<Option<MonitorState>> {
+        let mut states = self.select(
+            "SELECT state FROM monitors WHERE contract_id = ?1",
+            &[&contract_id],
+        )?;
+        Ok(states.pop())
+    }
+
+    fn save_monitor(&self, state: &MonitorState) -> Result<()> {
+        self.execute(
+            "INSERT OR REPLACE INTO monitors (contract_id, state) VALUES (?1, ?2)",
+            params![state.contract_id, serde_json::to_string(state)?],
+        )
+    }
+
+    fn load_contracts(&self) -> Result<Vec<UCLContract>> {
+        self.contracts("SELECT document FROM contracts", &[])
+    }
+
+    fn get_contract(&self, contract_id: &str) -> Result<Option<UCLContract>> {
+        let mut contracts = self.contracts(
+            "SELECT document FROM contracts WHERE contract_id = ?1",
+            &[&contract_id],
+        )?;
+        Ok(contracts.pop())
+    }
+
+    fn query_contracts(&self, query: &ContractQuery) -> Result<Vec<UCLContract>> {
+        // Unset fields are NULL and match every row
+        self.contracts(
+            "SELECT document FROM contracts
+             WHERE (?1 IS NULL OR contract_type = ?1)
+               AND (?2 IS NULL OR category = ?2)
+               AND (?3 IS NULL OR network = ?3)
+               AND (?4 IS NULL OR EXISTS (
+                   SELECT 1 FROM json_each(document, '$.metadata.tags') WHERE value = ?4))
+               AND (?5 IS NULL OR EXISTS (
+                   SELECT 1 FROM json_each(document, '$.metadata.parties')
+                   WHERE json_extract(value, '$.identifier') = ?5
+                      OR json_extract(value, '$.did') = ?5
+                      OR lower(json_extract(value, '$.wallet')) = lower(?5)))",
+            params![
+                query.contract_type,
+                query.category,
+                query.network.map(|network| network.name()),
+                query.tag,
+                query.party,
+            ],
+        )
+    }
+
+    fn save_contract(&self, ucl: &UCLContract) -> Result<()> {
+        let metadata = &ucl.metadata;
+        self.execute(
+            "INSERT OR REPLACE INTO contracts
+             (contract_id, contract_type, category, network, document)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                ucl.contract_id,
+                metadata.contract_type,
+                metadata.category,
+                ucl.payment.blockchain.name(),
+                serde_json::to_string(ucl)?,
+            ],
+        )
+    }
+
+    fn remove_contract(&self, contract_id: &str) -> Result<()> {
+        self.execute(
+            "DELETE FROM contracts WHERE contract_id = ?1",
+            &[&contract_id],
+        )
+    }
+
+    fn append_condition_history(
+        &self,
+        contract_id: &str,
+        records: &[ConditionRecord],
+    ) -> Result<()> {
+        let records = records
+            .iter()
+            .map(serde_json::to_string)
+            .collect::<serde_json::Result<Vec<_>>>()?;
+        let mut db = self.db.lock().unwrap();
+        // One transaction per check, so a crash loses at most that check
+        let tx = db.transaction().map_err(database(&self.path))?;
+        for record in &records {
+            tx.prepare_cached(
+                "INSERT INTO condition_history (contract_id, record) VALUES (?1, ?2)",
+            )
+            .and_then(|mut statement| statement.execute(params![contract_id, record]))
+            .map_err(database(&self.path))?;
+        }
+        tx.commit().map_err(database(&self.path))?;
+        Ok(())
+    }
+
+    fn load_condition_history(&self, contract_id: &str) -> Result<Vec<ConditionRecord>> {
+        self.select(
+            "SELECT record FROM condition_history WHERE contract_id = ?1 ORDER BY seq",
+            &[&contract_id],
+        )
+    }
+
+    fn append_event(&self, event: &ContractEvent) -> Result<()> {
+        self.execute(
+            "INSERT INTO events (contract_id, at, event) VALUES (?1, ?2, ?3)",
+            params![
+                event.contract_id,
+                event.at.to_rfc3339(),
+                serde_json::to_string(event)?,
+            ],
+        )
+    }
+
+    fn load_events(&self, contract_id: &str) -> Result<Vec<ContractEvent>> {
+        self.select(
+            "SELECT event FROM events WHERE contract_id = ?1 ORDER BY seq",
+            &[&contract_id],
+        )
+    }
+}
+
+fn database(path: &Path) -> impl FnOnce(rusqlite::Error) -> StoreError + '_ {
+    move |e| StoreError::Database {
+        database: path.display().to_string(),
+        reason: e.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::events::ContractEventKind;
+    use crate::types::PartyInfo;
+    use crate::{Contract, ContractStatus, Network};
+
+    #[test]
+    fn test_sqlite_store_queries_contracts() {
+        let dir =
+            std::env::temp_dir().join(format!("smart402-sqlite-{:016x}", rand::random::<u64>()));
+        let path = dir.join("contracts.db");
+        let store = SqliteContractStore::open(&path).unwrap();
+
+        let mut saas = Contract::from_config(Default::default()).unwrap().ucl;
+        saas.contract_id = "smart402:contract:saas".to_string();
+        saas.metadata.contract_type = "saas-subscription".to_string();
+        saas.metadata.tags = vec!["enterprise".to_string()];
+        let payer: PartyInfo = serde_json::from_value(serde_json::json!({
+            "role": "payer",
+            "identifier": "billing@acme.example",
+            "wallet": "0xAbC0000000000000000000000000000000000001",
+        }))
+        .unwrap();
+        saas.metadata.parties = vec![payer];
+        let mut freelance = saas.clone();
+        freelance.contract_id = "smart402:contract:freelance".to_string();
+        freelance.metadata.contract_type = "freelancer-milestone".to_string();
+        freelance.metadata.tags.clear();
+        freelance.payment.blockchain = Network::Base;
+        for ucl in [&saas, &freelance] {
+            store.save_contract(ucl).unwrap();
+        }
+
+        let ids = |query: ContractQuery| {
+            let mut ids: Vec<_> = store
+                .query_contracts(&query)
+                .unwrap()
+                .into_iter()
+                .map(|ucl| ucl.contract_id)
+                .collect();
+            ids.sort();
+            // The SQL query agrees with ContractQuery::matches
+            let mut expected: Vec<_> = [&saas, &freelance]
+                .into_iter()
+                .filter(|ucl| query.matches(ucl))
+                .map(|ucl| ucl.contract_id.clone())
+                .collect();
+            expected.sort();
+            assert_eq!(ids, expected, "{:?}", query);
+            ids
+        };
+        assert_eq!(ids(ContractQuery::new()).len(), 2);
+        assert_eq!(
+            ids(ContractQuery::new().with_type("saas-subscription")),
+            [saas.contract_id.as_str()]
+        );
+        assert_eq!(
+            ids(ContractQuery::new().with_tag("enterprise")),
+            [saas.contract_id.as_str()]
+        );
+        assert_eq!(
+            ids(ContractQuery::new().with_network(Network::Base)),
+            [freelance.contract_id.as_str()]
+        );
+        let party = "0xabc0000000000000000000000000000000000001";
+        assert_eq!(ids(ContractQuery::new().with_party(party)).len(), 2);
+        assert!(ids(ContractQuery::new()
+            .with_party("nobody")
+            .with_tag("enterprise"))
+        .is_empty());
+
+        store.remove_contract(&freelance.contract_id).unwrap();
+        let event = ContractEvent::new(
+            &saas.contract_id,
+            ContractEventKind::StatusChanged {
+                from: ContractStatus::Draft,
+                to: ContractStatus::Deployed,
+            },
+        );
+        store.append_event(&event).unwrap();
+
+        // Everything survives reopening the database
+        drop(store);
+        let store = SqliteContractStore::open(&path).unwrap();
+        assert!(store
+            .get_contract(&freelance.contract_id)
+            .unwrap()
+            .is_none());
+        let loaded = store.get_contract(&saas.contract_id).unwrap().unwrap();
+        assert_eq!(loaded.metadata.tags, saas.metadata.tags);
+        let events = store.load_events(&saas.contract_id).unwrap();
+        assert!(matches!(
+            events[..],
+            [ContractEvent {
+                event: ContractEventKind::StatusChanged {
+                    to: ContractStatus::Deployed,
+                    ..
+                },
+                ..
+            }]
+        ));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
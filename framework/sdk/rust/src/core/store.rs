@@ -0,0 +1,595 @@
+//! Persistence of contract runtime state
+//!
+//! [`FileContractStore`] keeps loose JSON files and suits a handful of
+//! contracts; with the `sqlite` feature, `SqliteContractStore` keeps
+//! everything in one database and answers queries with indexes, and with
+//! the `postgres` feature, `PostgresContractStore` shares one database
+//! between the instances of a deployment. [`open`] picks between them by
+//! path or URL.
+
+use super::events::ContractEvent;
+use super::monitor::MonitorState;
+use crate::{ConditionRecord, Network, Result, UCLContract};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Storage for the state of running contracts
+pub trait ContractStore: Send + Sync {
+    /// Saved monitor state of a contract, if any
+    fn load_monitor(&self, contract_id: &str) -> Result<Option<MonitorState>>;
+
+    /// Save the monitor state of a contract, replacing the previous one
+    fn save_monitor(&self, state: &MonitorState) -> Result<()>;
+
+    /// Contracts kept in the store, in no particular order
+    fn load_contracts(&self) -> Result<Vec<UCLContract>>;
+
+    /// Contract with id `contract_id`, if the store keeps it
+    fn get_contract(&self, contract_id: &str) -> Result<Option<UCLContract>> {
+        Ok(self
+            .load_contracts()?
+            .into_iter()
+            .find(|ucl| ucl.contract_id == contract_id))
+    }
+
+    /// Contracts matching `query`, in no particular order
+    fn query_contracts(&self, query: &ContractQuery) -> Result<Vec<UCLContract>> {
+        let mut contracts = self.load_contracts()?;
+        contracts.retain(|ucl| query.matches(ucl));
+        Ok(contracts)
+    }
+
+    /// Save a contract, replacing the one with the same id
+    fn save_contract(&self, ucl: &UCLContract) -> Result<()>;
+
+    /// Remove a contract; its monitor state is kept
+    fn remove_contract(&self, contract_id: &str) -> Result<()>;
+
+    /// Append condition check records to a contract's history
+    fn append_condition_history(
+        &self,
+        contract_id: &str,
+        records: &[ConditionRecord],
+    ) -> Result<()>;
+
+    /// Condition history of a contract, oldest first
+    fn load_condition_history(&self, contract_id: &str) -> Result<Vec<ConditionRecord>>;
+
+    /// Append an event to the event log of its contract
+    fn append_event(&self, event: &ContractEvent) -> Result<()>;
+
+    /// Event log of a contract, oldest first
+    fn load_events(&self, contract_id: &str) -> Result<Vec<ContractEvent>>;
+}
+
+/// Which contracts [`ContractStore::query_contracts`] returns; fields left
+/// unset match every contract
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContractQuery {
+    /// Contract type, e.g. `"saas-subscription"`
+    pub contract_type: Option<String>,
+    pub category: Option<String>,
+    /// Identifier, wallet or DID of one of the parties
+    pub party: Option<String>,
+    pub tag: Option<String>,
+    /// Network the contract is paid on
+    pub network: Option<Network>,
+}
+
+impl ContractQuery {
+    /// Query matching every contract
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Match contracts of type `contract_type`
+    pub fn with_type(mut self, contract_type: impl Into<String>) -> Self {
+        self.contract_type = Some(contract_type.into());
+        self
+    }
+
+    /// Match contracts in `category`
+    pub fn with_category(mut self, category: impl Into<String>) -> Self {
+        self.category = Some(category.into());
+        self
+    }
+
+    /// Match contracts one of whose parties is `party`
+    pub fn with_party(mut self, party: impl Into<String>) -> Self {
+        self.party = Some(party.into());
+        self
+    }
+
+    /// Match contracts tagged `tag`
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.tag = Some(tag.into());
+        self
+    }
+
+    /// Match contracts paid on `network`
+    pub fn with_network(mut self, network: Network) -> Self {
+        self.network = Some(network);
+        self
+    }
+
+    /// Whether `ucl` matches the query
+    ///
+    /// Wallets compare case-insensitively, everything else exactly.
+    pub fn matches(&self, ucl: &UCLContract) -> bool {
+        let metadata = &ucl.metadata;
+        let is = |expected: &Option<String>, value: &str| {
+            expected.as_deref().is_none_or(|expected| expected == value)
+        };
+        is(&self.contract_type, &metadata.contract_type)
+            && is(&self.category, &metadata.category)
+            && self
+                .tag
+                .as_ref()
+                .is_none_or(|tag| metadata.tags.contains(tag))
+            && self
+                .network
+                .is_none_or(|network| network == ucl.payment.blockchain)
+            && self.party.as_deref().is_none_or(|party| {
+                metadata.parties.iter().any(|p| {
+                    p.identifier == party
+                        || p.did.as_deref() == Some(party)
+                        || p.wallet
+                            .as_deref()
+                            .is_some_and(|wallet| wallet.eq_ignore_ascii_case(party))
+                })
+            })
+    }
+}
+
+/// Open the store at `path`: a Postgres database for `postgres://` URLs, a
+/// SQLite database for `.db`, `.sqlite` and `.sqlite3` files and a
+/// [`FileContractStore`] directory otherwise
+pub fn open(path: impl AsRef<Path>) -> Result<Arc<dyn ContractStore>> {
+    let path = path.as_ref();
+    let location = path.to_string_lossy();
+    if location.starts_with("postgres://") || location.starts_with("postgresql://") {
+        #[cfg(feature = "postgres")]
+        return Ok(Arc::new(super::postgres::PostgresContractStore::connect(
+            &location,
+        )?));
+        #[cfg(not(feature = "postgres"))]
+        return Err(missing_feature("a Postgres database", "postgres"));
+    }
+    let is_sqlite = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| matches!(ext, "db" | "sqlite" | "sqlite3"));
+    if !is_sqlite {
+        return Ok(Arc::new(FileContractStore::open(path)?));
+    }
+    #[cfg(feature = "sqlite")]
+    {
+        Ok(Arc::new(super::sqlite::SqliteContractStore::open(path)?))
+    }
+    #[cfg(not(feature = "sqlite"))]
+    Err(missing_feature("a SQLite database", "sqlite"))
+}
+
+/// Store location fit to print: `location` without the password, if it is
+/// a URL with one
+pub fn redact(location: &str) -> String {
+    let Some((scheme, rest)) = location.split_once("://") else {
+        return location.to_string();
+    };
+    // The password may hold '@'s of its own; the host follows the last one
+    match rest.rsplit_once('@') {
+        Some((user, host)) => {
+            let user = user.split_once(':').map_or(user, |(user, _)| user);
+            format!("{}://{}@{}", scheme, user, host)
+        }
+        None => location.to_string(),
+    }
+}
+
+#[cfg(not(all(feature = "sqlite", feature = "postgres")))]
+fn missing_feature(store: &str, feature: &str) -> crate::Error {
+    crate::Error::ValidationError(format!(
+        "The store is {}; build with the `{}` feature to open it",
+        store, feature
+    ))
+}
+
+/// Process-local store, for tests and short-lived monitors
+#[derive(Debug, Default)]
+pub struct InMemoryContractStore {
+    monitors: Mutex<HashMap<String, MonitorState>>,
+    contracts: Mutex<HashMap<String, UCLContract>>,
+    conditions: Mutex<HashMap<String, Vec<ConditionRecord>>>,
+    events: Mutex<HashMap<String, Vec<ContractEvent>>>,
+}
+
+impl InMemoryContractStore {
+    /// Create empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ContractStore for InMemoryContractStore {
+    fn load_monitor(&self, contract_id: &str) -> Result<Option<MonitorState>> {
+        Ok(self.monitors.lock().unwrap().get(contract_id).cloned())
+    }
+
+    fn save_monitor(&self, state: &MonitorState) -> Result<()> {
+        self.monitors
+            .lock()
+            .unwrap()
+            .insert(state.contract_id.clone(), state.clone());
+        Ok(())
+    }
+
+    fn load_contracts(&self) -> Result<Vec<UCLContract>> {
+        Ok(self.contracts.lock().unwrap().values().cloned().collect())
+    }
+
+    fn save_contract(&self, ucl: &UCLContract) -> Result<()> {
+        self.contracts
+            .lock()
+            .unwrap()
+            .insert(ucl.contract_id.clone(), ucl.clone());
+        Ok(())
+    }
+
+    fn remove_contract(&self, contract_id: &str) -> Result<()> {
+        self.contracts.lock().unwrap().remove(contract_id);
+        Ok(())
+    }
+
+    fn append_condition_history(
+        &self,
+        contract_id: &str,
+        records: &[ConditionRecord],
+    ) -> Result<()> {
+        self.conditions
+            .lock()
+            .unwrap()
+            .entry(contract_id.to_string())
+            .or_default()
+            .extend_from_slice(records);
+        Ok(())
+    }
+
+    fn load_condition_history(&self, contract_id: &str) -> Result<Vec<ConditionRecord>> {
+        Ok(self
+            .conditions
+            .lock()
+            .unwrap()
+            .get(contract_id)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    fn append_event(&self, event: &ContractEvent) -> Result<()> {
+        self.events
+            .lock()
+            .unwrap()
+            .entry(event.contract_id.clone())
+            .or_default()
+            .push(event.clone());
+        Ok(())
+    }
+
+    fn load_events(&self, contract_id: &str) -> Result<Vec<ContractEvent>> {
+        Ok(self
+            .events
+            .lock()
+            .unwrap()
+            .get(contract_id)
+            .cloned()
+            .unwrap_or_default())
+    }
+}
+
+/// Store keeping one JSON file per contract in a directory
+#[derive(Debug, Clone)]
+pub struct FileContractStore {
+    dir: PathBuf,
+}
+
+impl FileContractStore {
+    /// Open the store in `dir`, creating the directory if needed
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir).map_err(io_error(&dir))?;
+        Ok(Self { dir })
+    }
+
+    fn monitor_path(&self, contract_id: &str) -> PathBuf {
+        self.path(contract_id, "monitor")
+    }
+
+    fn contract_path(&self, contract_id: &str) -> PathBuf {
+        self.path(contract_id, "contract")
+    }
+
+    /// Condition history, one JSON record per line
+    fn conditions_path(&self, contract_id: &str) -> PathBuf {
+        self.path(contract_id, "conditions").with_extension("jsonl")
+    }
+
+    /// Event log, one JSON event per line
+    fn events_path(&self, contract_id: &str) -> PathBuf {
+        self.path(contract_id, "events").with_extension("jsonl")
+    }
+
+    fn path(&self, contract_id: &str, kind: &str) -> PathBuf {
+        let name: String = contract_id
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.dir.join(format!("{}.{}.json", name, kind))
+    }
+}
+
+impl ContractStore for FileContractStore {
+    fn load_monitor(&self, contract_id: &str) -> Result<Option<MonitorState>> {
+        let path = self.monitor_path(contract_id);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let state = serde_json::from_str(&read(&path)?).map_err(corrupt(&path))?;
+        Ok(Some(state))
+    }
+
+    fn save_monitor(&self, state: &MonitorState) -> Result<()> {
+        write_json(&self.monitor_path(&state.contract_id), state)
+    }
+
+    fn load_contracts(&self) -> Result<Vec<UCLContract>> {
+        let mut contracts = Vec::new();
+        for entry in std::fs::read_dir(&self.dir).map_err(io_error(&self.dir))? {
+            let path = entry.map_err(io_error(&self.dir))?.path();
+            let is_contract = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.ends_with(".contract.json"));
+            if is_contract {
+                let document = serde_json::from_str(&read(&path)?).map_err(corrupt(&path))?;
+                contracts.push(crate::utils::migrate::migrate(document).map_err(corrupt(&path))?);
+            }
+        }
+        Ok(contracts)
+    }
+
+    fn save_contract(&self, ucl: &UCLContract) -> Result<()> {
+        write_json(&self.contract_path(&ucl.contract_id), ucl)
+    }
+
+    fn remove_contract(&self, contract_id: &str) -> Result<()> {
+        let path = self.contract_path(contract_id);
+        match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(io_error(&path)(e).into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn append_condition_history(
+        &self,
+        contract_id: &str,
+        records: &[ConditionRecord],
+    ) -> Result<()> {
+        // One write per check, so a crash loses at most that check
+        append_lines(&self.conditions_path(contract_id), records)
+    }
+
+    fn load_condition_history(&self, contract_id: &str) -> Result<Vec<ConditionRecord>> {
+        read_lines(&self.conditions_path(contract_id))
+    }
+
+    fn append_event(&self, event: &ContractEvent) -> Result<()> {
+        append_lines(
+            &self.events_path(&event.contract_id),
+            std::slice::from_ref(event),
+        )
+    }
+
+    fn load_events(&self, contract_id: &str) -> Result<Vec<ContractEvent>> {
+        read_lines(&self.events_path(contract_id))
+    }
+}
+
+/// Why a store could not read or write its files or database
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
+pub enum StoreError {
+    #[error("Cannot access {}: {reason}", .path.display())]
+    Io { path: PathBuf, reason: String },
+
+    #[error("Corrupt store file {}: {reason}", .path.display())]
+    Corrupt { path: PathBuf, reason: String },
+
+    /// The database of a SQLite or Postgres store failed a query
+    #[error("Store database {database} failed: {reason}")]
+    Database { database: String, reason: String },
+
+    /// The contract changed since the version an update was based on
+    #[error("Contract {contract_id} is no longer at version {expected}; reload it and retry")]
+    Conflict { contract_id: String, expected: u64 },
+}
+
+impl StoreError {
+    /// Stable machine-readable code, e.g. `"store.corrupt"`
+    pub fn code(&self) -> &'static str {
+        match self {
+            StoreError::Io { .. } => "store.io",
+            StoreError::Corrupt { .. } => "store.corrupt",
+            StoreError::Database { .. } => "store.database",
+            StoreError::Conflict { .. } => "store.conflict",
+        }
+    }
+}
+
+pub(super) fn io_error(path: &Path) -> impl FnOnce(std::io::Error) -> StoreError + '_ {
+    move |e| StoreError::Io {
+        path: path.to_path_buf(),
+        reason: e.to_string(),
+    }
+}
+
+pub(super) fn corrupt<E: std::fmt::Display>(path: &Path) -> impl FnOnce(E) -> StoreError + '_ {
+    move |e| StoreError::Corrupt {
+        path: path.to_path_buf(),
+        reason: e.to_string(),
+    }
+}
+
+fn read(path: &Path) -> Result<String> {
+    Ok(std::fs::read_to_string(path).map_err(io_error(path))?)
+}
+
+/// Append `values` to a JSON Lines file in a single write
+fn append_lines<T: serde::Serialize>(path: &Path, values: &[T]) -> Result<()> {
+    let mut lines = String::new();
+    for value in values {
+        lines.push_str(&serde_json::to_string(value)?);
+        lines.push('\n');
+    }
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(lines.as_bytes()))
+        .map_err(io_error(path))?;
+    Ok(())
+}
+
+/// Values of a JSON Lines file, none if it does not exist
+fn read_lines<T: serde::de::DeserializeOwned>(path: &Path) -> Result<Vec<T>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    read(path)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| Ok(serde_json::from_str(line).map_err(corrupt(path))?))
+        .collect()
+}
+
+/// Replace the file at `path` without leaving it half-written
+fn write_json(path: &Path, value: &impl serde::Serialize) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, serde_json::to_string_pretty(value)?)
+        .and_then(|()| std::fs::rename(&tmp, path))
+        .map_err(io_error(path))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_store_survives_reopen() {
+        let dir =
+            std::env::temp_dir().join(format!("smart402-store-{:016x}", rand::random::<u64>()));
+        let state = MonitorState {
+            contract_id: "smart402:contract:abc123".to_string(),
+            schedule: "900s".to_string(),
+            last_run: None,
+            last_check: None,
+            next_run: Some(chrono::Utc::now()),
+            next_payment_due: None,
+            expired_at: None,
+            notice_given_at: None,
+            terminated_at: None,
+            breached_since: Default::default(),
+            alerts: Default::default(),
+            oracle_failures: Default::default(),
+            payment_failures: 0,
+            held: None,
+            oracle_fallbacks: Default::default(),
+            paused_at: None,
+            late_fees: crate::Amount::ZERO,
+            monitoring_paused_at: None,
+            log: Vec::new(),
+            payment_attempts: Vec::new(),
+        };
+        FileContractStore::open(&dir)
+            .unwrap()
+            .save_monitor(&state)
+            .unwrap();
+
+        let reopened = FileContractStore::open(&dir).unwrap();
+        let loaded = reopened.load_monitor(&state.contract_id).unwrap().unwrap();
+        assert_eq!(loaded.next_run, state.next_run);
+        assert!(reopened
+            .load_monitor("smart402:contract:other")
+            .unwrap()
+            .is_none());
+
+        let mut contract = crate::Contract::from_config(Default::default()).unwrap();
+        contract.ucl.contract_id = state.contract_id.clone();
+        reopened.save_contract(&contract.ucl).unwrap();
+        let contracts = FileContractStore::open(&dir)
+            .unwrap()
+            .load_contracts()
+            .unwrap();
+        assert_eq!(contracts.len(), 1);
+        assert_eq!(contracts[0].contract_id, state.contract_id);
+        reopened.remove_contract(&state.contract_id).unwrap();
+        assert!(reopened.load_contracts().unwrap().is_empty());
+
+        let record = crate::ConditionRecord {
+            condition_id: "uptime_met".to_string(),
+            checked_at: chrono::Utc::now(),
+            met: false,
+            source: Some("uptime_oracle".to_string()),
+            served_by: None,
+            value: Some(serde_json::json!(98.5)),
+            inputs: Default::default(),
+        };
+        for _ in 0..2 {
+            reopened
+                .append_condition_history(&state.contract_id, std::slice::from_ref(&record))
+                .unwrap();
+        }
+        let history = FileContractStore::open(&dir)
+            .unwrap()
+            .load_condition_history(&state.contract_id)
+            .unwrap();
+        assert_eq!(history, vec![record.clone(), record]);
+
+        let event = ContractEvent::new(
+            &state.contract_id,
+            crate::ContractEventKind::StatusChanged {
+                from: crate::ContractStatus::Draft,
+                to: crate::ContractStatus::Deployed,
+            },
+        );
+        reopened.append_event(&event).unwrap();
+        let events = FileContractStore::open(&dir)
+            .unwrap()
+            .load_events(&state.contract_id)
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].at, event.at);
+        assert_eq!(
+            redact("postgres://app:secret@db:5432/smart402"),
+            "postgres://app@db:5432/smart402"
+        );
+        assert_eq!(
+            redact("postgres://app:p@ss@word@db/smart402"),
+            "postgres://app@db/smart402"
+        );
+
+        std::fs::write(reopened.monitor_path(&state.contract_id), "{").unwrap();
+        let Err(crate::Error::Store(error)) = reopened.load_monitor(&state.contract_id) else {
+            panic!("expected a store error");
+        };
+        assert_eq!(error.code(), "store.corrupt");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
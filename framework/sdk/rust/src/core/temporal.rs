@@ -0,0 +1,208 @@
+//! Time-based conditions
+//!
+//! Conditions with a [`TemporalCondition`] are measured against the
+//! contract's dates instead of an oracle. They hold once the moment they
+//! describe has passed, so rules such as a late fee can require
+//! `"delivery_deadline"` together with `"delivered == false"`.
+
+use crate::types::TemporalCondition;
+use crate::UCLContract;
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use std::collections::HashMap;
+
+/// What is known about the contract's history when conditions are checked
+pub(crate) struct Timeline<'a> {
+    pub now: DateTime<Utc>,
+    /// When the next payment falls due, if one was already made
+    pub next_payment_due: Option<DateTime<Utc>>,
+    /// When each currently unmet condition was first seen unmet
+    pub breached_since: Option<&'a HashMap<String, DateTime<Utc>>>,
+}
+
+impl Timeline<'_> {
+    /// Timeline of a one-off check, without monitor history
+    pub fn now() -> Self {
+        Self::at(Utc::now())
+    }
+
+    /// Timeline of a one-off check at `now`
+    pub fn at(now: DateTime<Utc>) -> Self {
+        Self {
+            now,
+            next_payment_due: None,
+            breached_since: None,
+        }
+    }
+}
+
+/// Whether a temporal condition holds, given the other condition results
+///
+/// Conditions with dates or spans that cannot be parsed never hold.
+pub(crate) fn temporal_met(
+    ucl: &UCLContract,
+    temporal: &TemporalCondition,
+    conditions: &HashMap<String, bool>,
+    timeline: &Timeline,
+) -> bool {
+    temporal_due(ucl, temporal, conditions, timeline).is_some_and(|due| due <= timeline.now)
+}
+
+/// Moment a temporal condition starts to hold, if it is underway
+///
+/// `None` for a breach of a condition that is not failing, and for dates
+/// or spans that cannot be parsed.
+pub(crate) fn temporal_due(
+    ucl: &UCLContract,
+    temporal: &TemporalCondition,
+    conditions: &HashMap<String, bool>,
+    timeline: &Timeline,
+) -> Option<DateTime<Utc>> {
+    let (since, grace) = match temporal {
+        TemporalCondition::Deadline { at, grace } => (deadline(ucl, at), grace),
+        // Before the first payment is made it is due on the effective date
+        TemporalCondition::PaymentOverdue { grace } => (
+            Some(timeline.next_payment_due.unwrap_or_else(|| ucl.effective_at())),
+            grace,
+        ),
+        TemporalCondition::Breached { condition, grace } => {
+            if conditions.get(condition) != Some(&false) {
+                return None;
+            }
+            let since = timeline
+                .breached_since
+                .and_then(|breaches| breaches.get(condition).copied())
+                .unwrap_or(timeline.now);
+            (Some(since), grace)
+        }
+    };
+    match (since?, grace) {
+        (since, None) => Some(since),
+        (since, Some(grace)) => span_after(since, grace),
+    }
+}
+
+/// Moment a deadline passes
+///
+/// `at` is a date (the deadline is the end of that day), an RFC 3339 time
+/// or a span after the effective date such as `"30 days"`.
+pub(crate) fn deadline(ucl: &UCLContract, at: &str) -> Option<DateTime<Utc>> {
+    if let Some(day) = start_of_day(at) {
+        return Some(day + chrono::Duration::days(1));
+    }
+    if let Ok(time) = DateTime::parse_from_rfc3339(at.trim()) {
+        return Some(time.with_timezone(&Utc));
+    }
+    span_after(ucl.effective_at(), at)
+}
+
+/// `start` plus a span of days, weeks, months or years such as
+/// `"12 months"`, or an interval such as `"36h"`
+pub(crate) fn span_after(start: DateTime<Utc>, span: &str) -> Option<DateTime<Utc>> {
+    if let Some((count, unit)) = span.trim().split_once(char::is_whitespace) {
+        let count: u32 = count.parse().ok()?;
+        match unit.trim().trim_end_matches('s').to_lowercase().as_str() {
+            "week" => return Some(start + chrono::Duration::weeks(count.into())),
+            "month" => return start.checked_add_months(chrono::Months::new(count)),
+            "year" => return start.checked_add_months(chrono::Months::new(count * 12)),
+            _ => {}
+        }
+    }
+    let secs = crate::llmo::lint::parse_interval_secs(span)?;
+    Some(start + chrono::Duration::seconds(secs.try_into().ok()?))
+}
+
+fn start_of_day(date: &str) -> Option<DateTime<Utc>> {
+    Some(
+        NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+            .ok()?
+            .and_time(NaiveTime::MIN)
+            .and_utc(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Contract, ContractConfig};
+
+    fn at(time: &str) -> DateTime<Utc> {
+        time.parse().unwrap()
+    }
+
+    #[test]
+    fn test_deadlines_and_grace_periods() {
+        let ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+        let timeline = |now: &str| Timeline {
+            now: at(now),
+            next_payment_due: None,
+            breached_since: None,
+        };
+        let met = |temporal: &TemporalCondition, now: &str| {
+            temporal_met(&ucl, temporal, &HashMap::new(), &timeline(now))
+        };
+
+        let delivery = TemporalCondition::Deadline {
+            at: "30 days".to_string(),
+            grace: Some("2 days".to_string()),
+        };
+        assert!(!met(&delivery, "2024-02-01T23:59:59Z"));
+        assert!(met(&delivery, "2024-02-02T00:00:00Z"));
+
+        let end_of_quarter = TemporalCondition::Deadline {
+            at: "2024-03-31".to_string(),
+            grace: None,
+        };
+        assert!(!met(&end_of_quarter, "2024-03-31T12:00:00Z"));
+        assert!(met(&end_of_quarter, "2024-04-01T00:00:00Z"));
+
+        let overdue = TemporalCondition::PaymentOverdue {
+            grace: Some("1 week".to_string()),
+        };
+        assert!(met(&overdue, "2024-01-08T00:00:00Z"));
+        let paid = Timeline {
+            next_payment_due: Some(at("2024-02-01T00:00:00Z")),
+            ..timeline("2024-02-05T00:00:00Z")
+        };
+        assert!(!temporal_met(&ucl, &overdue, &HashMap::new(), &paid));
+    }
+
+    #[test]
+    fn test_breach_grace_period() {
+        let ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+        let breach = TemporalCondition::Breached {
+            condition: "uptime_met".to_string(),
+            grace: Some("24h".to_string()),
+        };
+        let breaches = HashMap::from([("uptime_met".to_string(), at("2024-05-01T00:00:00Z"))]);
+        let timeline = |now: &str| Timeline {
+            now: at(now),
+            next_payment_due: None,
+            breached_since: Some(&breaches),
+        };
+        let unmet = HashMap::from([("uptime_met".to_string(), false)]);
+        let met = HashMap::from([("uptime_met".to_string(), true)]);
+
+        assert!(!temporal_met(
+            &ucl,
+            &breach,
+            &unmet,
+            &timeline("2024-05-01T12:00:00Z")
+        ));
+        assert!(temporal_met(
+            &ucl,
+            &breach,
+            &unmet,
+            &timeline("2024-05-02T00:00:00Z")
+        ));
+        assert!(!temporal_met(
+            &ucl,
+            &breach,
+            &met,
+            &timeline("2024-05-02T00:00:00Z")
+        ));
+    }
+}
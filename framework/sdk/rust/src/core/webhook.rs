@@ -0,0 +1,459 @@
+//! Webhook notifications for monitoring events
+//!
+//! Deliveries are JSON [`MonitoringWebhook`] bodies POSTed to the
+//! configured URL, signed like facilitator webhooks: the
+//! `X402-Webhook-Signature` header carries `t=<unix seconds>,v1=<hex
+//! HMAC-SHA256>` over `"<t>.<raw body>"` under the shared secret.
+//! Receivers check it with [`verify_monitoring_webhook`].
+
+use super::monitor::{MonitorEvent, MonitorHandle};
+use super::notify::{Alert, Notifier, Severity};
+use crate::x402::nonce::unix_now;
+use crate::x402::retry::{is_transient, RetryPolicy};
+use crate::x402::webhook::{check_signature, sign_webhook, WEBHOOK_SIGNATURE_HEADER};
+use crate::{Amount, Network, PaymentResult, Result, TerminationReason, Token};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+
+/// Environment variable holding the secret webhooks are signed with
+pub const WEBHOOK_SECRET_ENV: &str = "SMART402_WEBHOOK_SECRET";
+
+/// Monitoring event delivered to a webhook
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data")]
+#[non_exhaustive]
+pub enum MonitoringEvent {
+    /// A condition kept failing until an escalation step alerted on it
+    #[serde(rename = "condition.failed")]
+    ConditionFailed {
+        condition_id: String,
+        checked_at: DateTime<Utc>,
+        /// Consecutive checks the condition failed
+        failures: u32,
+        failing_since: DateTime<Utc>,
+        /// Notifier channel the alert is for; every channel when unset
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        channel: Option<String>,
+        /// Addresses to alert instead of the notifier's own
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        recipients: Vec<String>,
+    },
+    /// A condition alerted about is met again
+    #[serde(rename = "condition.resolved")]
+    ConditionResolved {
+        condition_id: String,
+        failing_since: DateTime<Utc>,
+        resolved_at: DateTime<Utc>,
+    },
+    #[serde(rename = "payment.executed")]
+    PaymentExecuted {
+        transaction_hash: String,
+        amount: Amount,
+        token: Token,
+        network: Network,
+    },
+    /// An `execute_payment` action did not go through
+    #[serde(rename = "payment.failed")]
+    PaymentFailed {
+        amount: Amount,
+        token: Token,
+        network: Network,
+    },
+    /// A payment still failed after its retries
+    #[serde(rename = "payment.escalated")]
+    PaymentEscalated {
+        rule_id: String,
+        attempts: u32,
+        error: String,
+    },
+    /// The contract term ended
+    #[serde(rename = "contract.expired")]
+    ContractExpired { expired_at: DateTime<Utc> },
+    /// Notice of termination was given
+    #[serde(rename = "contract.notice_given")]
+    NoticeGiven {
+        given_at: DateTime<Utc>,
+        effective_at: DateTime<Utc>,
+    },
+    /// The contract was terminated before its term was up
+    #[serde(rename = "contract.terminated")]
+    ContractTerminated {
+        terminated_at: DateTime<Utc>,
+        reason: TerminationReason,
+        #[serde(default, skip_serializing_if = "Amount::is_zero")]
+        refund: Amount,
+    },
+    /// A rule's `notify` or `escalate` action fired
+    #[serde(rename = "rule.notification")]
+    RuleNotification {
+        rule_id: String,
+        message: String,
+        severity: Severity,
+        /// Addresses to alert instead of the notifier's own
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        recipients: Vec<String>,
+    },
+    /// A rule's action ran and failed
+    #[serde(rename = "action.failed")]
+    ActionFailed {
+        rule_id: String,
+        action: String,
+        error: String,
+    },
+    /// A rule paused payments under the contract
+    #[serde(rename = "contract.paused")]
+    ContractPaused {
+        rule_id: String,
+        paused_at: DateTime<Utc>,
+    },
+    /// A rule resumed payments under the contract
+    #[serde(rename = "contract.resumed")]
+    ContractResumed { rule_id: String },
+    /// A rule held payments, e.g. while its oracles are down
+    #[serde(rename = "payments.held")]
+    PaymentsHeld {
+        rule_id: String,
+        held_at: DateTime<Utc>,
+    },
+    /// Payments held by a rule were released
+    #[serde(rename = "payments.released")]
+    PaymentsReleased { rule_id: String },
+    /// A rule replaced an oracle with its fallback
+    #[serde(rename = "oracle.switched")]
+    OracleSwitched {
+        rule_id: String,
+        from: String,
+        to: String,
+    },
+    /// A rule's `webhook` action fired
+    #[serde(rename = "rule.webhook")]
+    RuleWebhook {
+        rule_id: String,
+        /// The action's `payload` parameter
+        #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+        payload: serde_json::Value,
+    },
+    /// Monitoring was paused, e.g. for a maintenance window
+    #[serde(rename = "monitoring.paused")]
+    MonitoringPaused { paused_at: DateTime<Utc> },
+    /// Monitoring resumed after a pause
+    #[serde(rename = "monitoring.resumed")]
+    MonitoringResumed {
+        paused_at: DateTime<Utc>,
+        resumed_at: DateTime<Utc>,
+    },
+}
+
+impl MonitoringEvent {
+    /// Events to notify about a monitor event; most have none
+    pub fn from_monitor_event(event: &MonitorEvent) -> Vec<Self> {
+        match event {
+            MonitorEvent::ConditionFailed {
+                condition_id,
+                checked_at,
+                failures,
+                failing_since,
+                channel,
+                recipients,
+            } => vec![MonitoringEvent::ConditionFailed {
+                condition_id: condition_id.clone(),
+                checked_at: *checked_at,
+                failures: *failures,
+                failing_since: *failing_since,
+                channel: channel.clone(),
+                recipients: recipients.clone(),
+            }],
+            MonitorEvent::ConditionResolved {
+                condition_id,
+                failing_since,
+                resolved_at,
+            } => vec![MonitoringEvent::ConditionResolved {
+                condition_id: condition_id.clone(),
+                failing_since: *failing_since,
+                resolved_at: *resolved_at,
+            }],
+            MonitorEvent::PaymentExecuted(payment) => vec![payment.into()],
+            MonitorEvent::PaymentEscalated {
+                rule_id,
+                attempts,
+                error,
+            } => vec![MonitoringEvent::PaymentEscalated {
+                rule_id: rule_id.clone(),
+                attempts: *attempts,
+                error: error.clone(),
+            }],
+            MonitorEvent::Expired { expired_at } => vec![MonitoringEvent::ContractExpired {
+                expired_at: *expired_at,
+            }],
+            MonitorEvent::NoticeGiven {
+                given_at,
+                effective_at,
+            } => vec![MonitoringEvent::NoticeGiven {
+                given_at: *given_at,
+                effective_at: *effective_at,
+            }],
+            MonitorEvent::Terminated {
+                terminated_at,
+                reason,
+                refund,
+                ..
+            } => vec![MonitoringEvent::ContractTerminated {
+                terminated_at: *terminated_at,
+                reason: reason.clone(),
+                refund: *refund,
+            }],
+            MonitorEvent::Notified {
+                rule_id,
+                message,
+                severity,
+                recipients,
+            } => vec![MonitoringEvent::RuleNotification {
+                rule_id: rule_id.clone(),
+                message: message.clone(),
+                severity: *severity,
+                recipients: recipients.clone(),
+            }],
+            MonitorEvent::ActionFailed {
+                rule_id,
+                action,
+                error,
+            } => vec![MonitoringEvent::ActionFailed {
+                rule_id: rule_id.clone(),
+                action: action.clone(),
+                error: error.clone(),
+            }],
+            MonitorEvent::Paused { rule_id, paused_at } => vec![MonitoringEvent::ContractPaused {
+                rule_id: rule_id.clone(),
+                paused_at: *paused_at,
+            }],
+            MonitorEvent::Resumed { rule_id } => vec![MonitoringEvent::ContractResumed {
+                rule_id: rule_id.clone(),
+            }],
+            MonitorEvent::PaymentsHeld { rule_id, held_at } => {
+                vec![MonitoringEvent::PaymentsHeld {
+                    rule_id: rule_id.clone(),
+                    held_at: *held_at,
+                }]
+            }
+            MonitorEvent::PaymentsReleased { rule_id } => vec![MonitoringEvent::PaymentsReleased {
+                rule_id: rule_id.clone(),
+            }],
+            MonitorEvent::OracleSwitched { rule_id, from, to } => {
+                vec![MonitoringEvent::OracleSwitched {
+                    rule_id: rule_id.clone(),
+                    from: from.clone(),
+                    to: to.clone(),
+                }]
+            }
+            MonitorEvent::MonitoringPaused { paused_at } => {
+                vec![MonitoringEvent::MonitoringPaused {
+                    paused_at: *paused_at,
+                }]
+            }
+            MonitorEvent::MonitoringResumed {
+                paused_at,
+                resumed_at,
+            } => vec![MonitoringEvent::MonitoringResumed {
+                paused_at: *paused_at,
+                resumed_at: *resumed_at,
+            }],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Notifier channel the event is meant for, if only one
+    pub fn channel(&self) -> Option<&str> {
+        match self {
+            MonitoringEvent::ConditionFailed { channel, .. } => channel.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Addresses to alert instead of the notifier's own, if any
+    pub fn recipients(&self) -> &[String] {
+        match self {
+            MonitoringEvent::ConditionFailed { recipients, .. }
+            | MonitoringEvent::RuleNotification { recipients, .. } => recipients,
+            _ => &[],
+        }
+    }
+}
+
+impl From<&PaymentResult> for MonitoringEvent {
+    fn from(payment: &PaymentResult) -> Self {
+        if payment.success {
+            MonitoringEvent::PaymentExecuted {
+                transaction_hash: payment.transaction_hash.clone(),
+                amount: payment.amount,
+                token: payment.token.clone(),
+                network: payment.network,
+            }
+        } else {
+            MonitoringEvent::PaymentFailed {
+                amount: payment.amount,
+                token: payment.token.clone(),
+                network: payment.network,
+            }
+        }
+    }
+}
+
+/// Body of a monitoring webhook
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MonitoringWebhook {
+    /// Unique per event and kept across delivery retries, so receivers
+    /// can drop duplicates
+    pub id: String,
+    pub contract_id: String,
+    pub created_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: MonitoringEvent,
+}
+
+impl MonitoringWebhook {
+    /// Wrap an event of a contract
+    pub fn new(contract_id: &str, event: MonitoringEvent) -> Self {
+        Self {
+            id: format!("evt_{:016x}", rand::random::<u64>()),
+            contract_id: contract_id.to_string(),
+            created_at: Utc::now(),
+            event,
+        }
+    }
+}
+
+/// Verify a monitoring webhook signature and parse its body
+pub fn verify_monitoring_webhook(
+    payload: &[u8],
+    signature: &str,
+    secret: &str,
+) -> Result<MonitoringWebhook> {
+    check_signature(payload, signature, secret, unix_now())?;
+    Ok(serde_json::from_slice(payload)?)
+}
+
+/// Delivers signed monitoring webhooks to one URL
+#[derive(Debug, Clone)]
+pub struct WebhookSender {
+    url: String,
+    secret: String,
+    http: reqwest::Client,
+    retry: RetryPolicy,
+}
+
+impl WebhookSender {
+    /// Deliver to `url`, signing with `secret`
+    pub fn new(url: impl Into<String>, secret: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            secret: secret.into(),
+            http: reqwest::Client::new(),
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    /// Deliver to `url`, signing with the secret in [`WEBHOOK_SECRET_ENV`]
+    pub fn from_env(url: impl Into<String>) -> Result<Self> {
+        let secret = std::env::var(WEBHOOK_SECRET_ENV).map_err(|_| {
+            crate::Error::ConfigError(format!(
+                "Set {} to sign monitoring webhooks",
+                WEBHOOK_SECRET_ENV
+            ))
+        })?;
+        Ok(Self::new(url, secret))
+    }
+
+    /// Retry policy for failed deliveries
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Deliver one webhook, retrying connection errors, 429 and 5xx
+    pub async fn send(&self, webhook: &MonitoringWebhook) -> Result<()> {
+        let body = serde_json::to_vec(webhook)?;
+        let mut attempt = 1;
+        loop {
+            let result = self
+                .http
+                .post(&self.url)
+                .header("content-type", "application/json")
+                .header(WEBHOOK_SIGNATURE_HEADER, sign_webhook(&body, &self.secret))
+                .body(body.clone())
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            match result {
+                Ok(_) => return Ok(()),
+                Err(e) if attempt < self.retry.max_attempts && is_transient(&e) => {
+                    tokio::time::sleep(self.retry.backoff(attempt)).await;
+                    attempt += 1;
+                }
+                Err(e) => {
+                    return Err(crate::Error::NetworkError(format!(
+                        "Webhook delivery to {} failed after {} attempt(s): {}",
+                        self.url, attempt, e
+                    )))
+                }
+            }
+        }
+    }
+
+    /// Deliver the notable events of a monitor until it stops
+    ///
+    /// Events that still fail after the retries are dropped.
+    pub fn attach(self, monitor: &MonitorHandle) -> JoinHandle<()> {
+        monitor.notify(Arc::new(self))
+    }
+}
+
+#[async_trait]
+impl Notifier for WebhookSender {
+    async fn notify(&self, alert: &Alert) -> Result<()> {
+        self.send(&MonitoringWebhook::new(
+            &alert.contract_id,
+            alert.event.clone(),
+        ))
+        .await
+    }
+
+    fn channel(&self) -> &str {
+        "webhook"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::x402::test_server::{header, serve};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_delivers_signed_webhook_with_retries() {
+        let (url, requests) = serve(vec![(503, "{}"), (200, "{}")]).await;
+        let sender = WebhookSender::new(url, "whsec_test").with_retry_policy(RetryPolicy {
+            initial_backoff: Duration::from_millis(1),
+            ..RetryPolicy::default()
+        });
+        let webhook = MonitoringWebhook::new(
+            "smart402:contract:abc123",
+            MonitoringEvent::ContractExpired {
+                expired_at: Utc::now(),
+            },
+        );
+        sender.send(&webhook).await.unwrap();
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        let (head, body) = requests[1].split_once("\r\n\r\n").unwrap();
+        let signature = header(head, WEBHOOK_SIGNATURE_HEADER).unwrap();
+        let delivered =
+            verify_monitoring_webhook(body.as_bytes(), signature, "whsec_test").unwrap();
+        assert_eq!(delivered, webhook);
+        assert!(body.contains(r#""type":"contract.expired""#));
+        assert!(verify_monitoring_webhook(body.as_bytes(), signature, "whsec_other").is_err());
+    }
+}
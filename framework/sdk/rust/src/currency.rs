@@ -0,0 +1,128 @@
+//! ISO 4217 currencies
+//!
+//! Contract prices are quoted in a fiat [`Currency`] and paid in a
+//! [`Token`]. Stablecoins are pegged to a currency, see [`Token::peg`], and a
+//! contract paid in one must be priced in the currency it is pegged to.
+
+use crate::token::Token;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// ISO 4217 currency, e.g. `USD`
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Currency([u8; 3]);
+
+/// Active ISO 4217 codes
+const ISO_4217: &str = "AED AFN ALL AMD ANG AOA ARS AUD AWG AZN BAM BBD BDT BGN BHD BIF BMD BND \
+    BOB BRL BSD BTN BWP BYN BZD CAD CDF CHF CLF CLP CNY COP CRC CUP CVE CZK DJF DKK DOP DZD EGP \
+    ERN ETB EUR FJD FKP GBP GEL GHS GIP GMD GNF GTQ GYD HKD HNL HTG HUF IDR ILS INR IQD IRR ISK \
+    JMD JOD JPY KES KGS KHR KMF KPW KRW KWD KYD KZT LAK LBP LKR LRD LSL LYD MAD MDL MGA MKD MMK \
+    MNT MOP MRU MUR MVR MWK MXN MYR MZN NAD NGN NIO NOK NPR NZD OMR PAB PEN PGK PHP PKR PLN PYG \
+    QAR RON RSD RUB RWF SAR SBD SCR SDG SEK SGD SHP SLE SOS SRD SSP STN SVC SYP SZL THB TJS TMT \
+    TND TOP TRY TTD TWD TZS UAH UGX USD UYU UZS VES VND VUV WST XAF XCD XOF XPF YER ZAR ZMW ZWL";
+
+/// Stablecoins by the currency they are pegged to
+const PEGS: &[(&str, Currency)] = &[
+    ("USDC", Currency::USD),
+    ("USDT", Currency::USD),
+    ("DAI", Currency::USD),
+    ("BUSD", Currency::USD),
+    ("PYUSD", Currency::USD),
+    ("EURC", Currency::EUR),
+    ("EURT", Currency::EUR),
+];
+
+impl Currency {
+    pub const USD: Currency = Currency(*b"USD");
+    pub const EUR: Currency = Currency(*b"EUR");
+    pub const GBP: Currency = Currency(*b"GBP");
+
+    /// ISO 4217 code, e.g. `"USD"`
+    pub fn code(&self) -> &str {
+        std::str::from_utf8(&self.0).expect("currency codes are ASCII")
+    }
+
+    /// Decimal places of the currency's minor unit, e.g. 2 for cents
+    pub fn minor_units(&self) -> u32 {
+        match self.code() {
+            "BIF" | "CLP" | "DJF" | "GNF" | "ISK" | "JPY" | "KMF" | "KRW" | "PYG" | "RWF"
+            | "UGX" | "VND" | "VUV" | "XAF" | "XOF" | "XPF" => 0,
+            "BHD" | "IQD" | "JOD" | "KWD" | "LYD" | "OMR" | "TND" => 3,
+            "CLF" => 4,
+            _ => 2,
+        }
+    }
+}
+
+impl Token {
+    /// Currency the token is pegged to; `None` for tokens that float
+    pub fn peg(&self) -> Option<Currency> {
+        PEGS.iter()
+            .find(|(symbol, _)| self.is(symbol))
+            .map(|(_, currency)| *currency)
+    }
+}
+
+impl fmt::Debug for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Currency({})", self.code())
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.code())
+    }
+}
+
+impl FromStr for Currency {
+    type Err = crate::Error;
+
+    /// Parse an ISO 4217 code, ignoring case
+    fn from_str(s: &str) -> crate::Result<Self> {
+        let code = s.trim().to_uppercase();
+        match <[u8; 3]>::try_from(code.as_bytes()) {
+            Ok(bytes) if ISO_4217.split_whitespace().any(|known| known == code) => {
+                Ok(Currency(bytes))
+            }
+            _ => Err(crate::Error::ValidationError(format!(
+                "{:?} is not an ISO 4217 currency code",
+                s
+            ))),
+        }
+    }
+}
+
+impl Serialize for Currency {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.code())
+    }
+}
+
+impl<'de> Deserialize<'de> for Currency {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_iso_codes_and_pegs() {
+        let yen: Currency = "jpy".parse().unwrap();
+        assert_eq!(yen.to_string(), "JPY");
+        assert_eq!(yen.minor_units(), 0);
+        assert_eq!(Currency::USD.minor_units(), 2);
+        assert!("USDC".parse::<Currency>().is_err());
+        assert!("ETH".parse::<Currency>().is_err());
+
+        assert_eq!(Token::usdc().peg(), Some(Currency::USD));
+        assert_eq!(Token::well_known("EURC").unwrap().peg(), Some(Currency::EUR));
+        assert_eq!(Token::well_known("ETH").unwrap().peg(), None);
+    }
+}
@@ -1,10 +1,12 @@
 //! Error types for Smart402 SDK
 
+use std::time::Duration;
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum Error {
     #[error("Contract validation failed: {0}")]
     ValidationError(String),
@@ -21,6 +23,54 @@ pub enum Error {
     #[error("Contract not found: {0}")]
     NotFoundError(String),
 
+    #[error("Compilation failed: {0}")]
+    CompilationError(String),
+
+    #[error(transparent)]
+    Verification(#[from] crate::x402::VerificationError),
+
+    #[error(transparent)]
+    PolicyViolation(#[from] crate::x402::PolicyViolation),
+
+    #[error(transparent)]
+    LlmOutput(#[from] crate::llmo::LlmOutputError),
+
+    #[error(transparent)]
+    Expression(#[from] crate::core::expression::ExpressionError),
+
+    #[error(transparent)]
+    Parse(#[from] crate::utils::parse::ParseError),
+
+    #[error(transparent)]
+    Schema(#[from] crate::utils::schema::SchemaError),
+
+    #[error(transparent)]
+    Signature(#[from] crate::x402::SignatureError),
+
+    #[error(transparent)]
+    Oracle(#[from] crate::core::oracle::OracleError),
+
+    #[error(transparent)]
+    Template(#[from] crate::llmo::TemplateError),
+
+    #[error(transparent)]
+    Store(#[from] crate::core::store::StoreError),
+
+    #[error(transparent)]
+    Encryption(#[from] crate::utils::encryption::EncryptionError),
+
+    #[cfg(feature = "artifacts")]
+    #[error(transparent)]
+    Artifact(#[from] crate::core::artifacts::ArtifactError),
+
+    /// A service is overloaded or down, e.g. a facilitator answering 429
+    #[error("Service unavailable: {reason}")]
+    Unavailable {
+        reason: String,
+        /// How long the service asked callers to wait
+        retry_after: Option<Duration>,
+    },
+
     #[error("Invalid configuration: {0}")]
     ConfigError(String),
 
@@ -30,6 +80,9 @@ pub enum Error {
     #[error("YAML error: {0}")]
     YamlError(#[from] serde_yaml::Error),
 
+    #[error("TOML error: {0}")]
+    TomlError(String),
+
     #[error("HTTP error: {0}")]
     HttpError(#[from] reqwest::Error),
 
@@ -39,3 +92,172 @@ pub enum Error {
     #[error(transparent)]
     Other(#[from] anyhow::Error),
 }
+
+impl From<toml::de::Error> for Error {
+    fn from(e: toml::de::Error) -> Self {
+        Error::TomlError(e.message().to_string())
+    }
+}
+
+impl From<toml::ser::Error> for Error {
+    fn from(e: toml::ser::Error) -> Self {
+        Error::TomlError(e.to_string())
+    }
+}
+
+impl Error {
+    /// Stable machine-readable code to branch on, e.g. `"oracle.unreachable"`
+    ///
+    /// Errors of the typed variants report their own code; the rest report
+    /// their kind.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::ValidationError(_) => "validation",
+            Error::NetworkError(_) => "network",
+            Error::DeploymentError(_) => "deployment",
+            Error::PaymentError(_) => "payment",
+            Error::NotFoundError(_) => "not_found",
+            Error::CompilationError(_) => "compilation",
+            Error::Verification(_) => "verification",
+            Error::PolicyViolation(_) => "policy",
+            Error::LlmOutput(_) => "llm_output",
+            Error::Expression(_) => "expression",
+            Error::Parse(_) => "parse",
+            Error::Schema(_) => "schema",
+            Error::Signature(e) => e.code(),
+            Error::Oracle(e) => e.code(),
+            Error::Template(e) => e.code(),
+            Error::Store(e) => e.code(),
+            Error::Encryption(e) => e.code(),
+            #[cfg(feature = "artifacts")]
+            Error::Artifact(e) => e.code(),
+            Error::Unavailable { .. } => "unavailable",
+            Error::ConfigError(_) => "config",
+            Error::SerializationError(_) => "serialization",
+            Error::YamlError(_) => "yaml",
+            Error::TomlError(_) => "toml",
+            Error::HttpError(_) => "http",
+            Error::IoError(_) => "io",
+            Error::Other(_) => "other",
+        }
+    }
+
+    /// Whether the operation may succeed if retried as is
+    ///
+    /// True for transient failures: unreachable or overloaded services,
+    /// timeouts and oracles that cannot be read right now. False for
+    /// failures retrying cannot fix, e.g. an invalid contract, a bad
+    /// signature or a rejected payment, which should be dead-lettered.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::NetworkError(_) | Error::Unavailable { .. } => true,
+            Error::HttpError(e) => crate::x402::retry::is_transient(e),
+            Error::IoError(e) => matches!(
+                e.kind(),
+                std::io::ErrorKind::Interrupted
+                    | std::io::ErrorKind::TimedOut
+                    | std::io::ErrorKind::WouldBlock
+                    | std::io::ErrorKind::ConnectionRefused
+                    | std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+            ),
+            Error::Oracle(e) => e.is_retryable(),
+            Error::Verification(
+                crate::x402::VerificationError::NonceStoreUnavailable(_)
+                | crate::x402::VerificationError::FacilitatorUnavailable(_),
+            ) => true,
+            _ => false,
+        }
+    }
+
+    /// How long to wait before retrying, when the failing service said so
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Error::Unavailable { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retryability() {
+        let rate_limited = Error::Unavailable {
+            reason: "429 Too Many Requests".to_string(),
+            retry_after: Some(Duration::from_secs(30)),
+        };
+        assert!(rate_limited.is_retryable());
+        assert_eq!(rate_limited.retry_after(), Some(Duration::from_secs(30)));
+        assert_eq!(rate_limited.code(), "unavailable");
+
+        let invalid = Error::ValidationError("negative amount".to_string());
+        assert!(!invalid.is_retryable());
+        assert_eq!(invalid.retry_after(), None);
+        assert!(Error::from(std::io::Error::from(std::io::ErrorKind::TimedOut)).is_retryable());
+        assert!(!Error::from(std::io::Error::from(std::io::ErrorKind::NotFound)).is_retryable());
+    }
+
+    #[test]
+    fn test_codes_are_stable() {
+        let oracle = crate::core::oracle::OracleError::Misconfigured {
+            oracle: "uptime".to_string(),
+            reason: "no url".to_string(),
+        };
+        let parse = crate::utils::parse::ParseError {
+            path: "contract.yaml".into(),
+            format: "yaml",
+            line: None,
+            column: None,
+            message: "bad".to_string(),
+        };
+        let http = reqwest::Client::new().get("not a url").build().unwrap_err();
+        let errors = [
+            (Error::ValidationError(String::new()), "validation"),
+            (Error::NetworkError(String::new()), "network"),
+            (Error::DeploymentError(String::new()), "deployment"),
+            (Error::PaymentError(String::new()), "payment"),
+            (Error::NotFoundError(String::new()), "not_found"),
+            (Error::CompilationError(String::new()), "compilation"),
+            (crate::x402::VerificationError::MissingHeader("X-402".to_string()).into(), "verification"),
+            (crate::x402::PolicyViolation::RateLimited { limit: 1 }.into(), "policy"),
+            (crate::llmo::LlmOutputError::InvalidJson(String::new()).into(), "llm_output"),
+            (crate::core::expression::ExpressionError::UnknownIdentifier("x".to_string()).into(), "expression"),
+            (parse.into(), "parse"),
+            (crate::utils::schema::SchemaError { violations: Vec::new() }.into(), "schema"),
+            (
+                crate::x402::SignatureError::UnsupportedScheme { scheme: "rsa".to_string() }.into(),
+                "signature.unsupported_scheme",
+            ),
+            (oracle.into(), "oracle.misconfigured"),
+            (crate::llmo::TemplateError::Unknown { template: "x".to_string() }.into(), "template.unknown"),
+            (
+                crate::core::store::StoreError::Io { path: "state".into(), reason: String::new() }.into(),
+                "store.io",
+            ),
+            (crate::utils::encryption::EncryptionError::KeyRequired.into(), "encryption.key_required"),
+            (Error::Unavailable { reason: String::new(), retry_after: None }, "unavailable"),
+            (Error::ConfigError(String::new()), "config"),
+            (serde_json::from_str::<u32>("x").unwrap_err().into(), "serialization"),
+            (serde_yaml::from_str::<u32>("x").unwrap_err().into(), "yaml"),
+            (Error::TomlError(String::new()), "toml"),
+            (http.into(), "http"),
+            (std::io::Error::from(std::io::ErrorKind::NotFound).into(), "io"),
+            (anyhow::anyhow!("other").into(), "other"),
+        ];
+        for (error, code) in errors {
+            assert_eq!(error.code(), code, "{error:?}");
+        }
+
+        #[cfg(feature = "artifacts")]
+        {
+            let artifact = crate::core::artifacts::ArtifactError::InvalidUrl {
+                url: "ftp://x".to_string(),
+                reason: String::new(),
+            };
+            assert_eq!(Error::from(artifact).code(), "artifact.invalid_url");
+        }
+    }
+}
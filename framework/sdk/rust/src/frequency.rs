@@ -0,0 +1,185 @@
+//! Payment frequencies
+//!
+//! Frequencies parse from the strings contract files have always used, e.g.
+//! `"monthly"`, `"one-time"` or `"per-request"`, as well as
+//! `"monthly on day 15"` and cron expressions such as `"0 9 1 * *"`. They
+//! display and serialize back to the same strings.
+
+use chrono::{DateTime, Datelike, Months, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// How often a contract is paid
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum PaymentFrequency {
+    /// A single payment
+    #[default]
+    OneTime,
+    Daily,
+    Weekly,
+    /// Every month, on day `day` if set (the last day of shorter months)
+    Monthly { day: Option<u8> },
+    Quarterly,
+    Yearly,
+    /// A payment per API request, metered by [`crate::x402::metering`]
+    PerRequest,
+    /// At the times matched by a cron expression (UTC)
+    Cron(String),
+}
+
+impl PaymentFrequency {
+    /// Whether payments repeat on a schedule
+    pub fn is_recurring(&self) -> bool {
+        !matches!(self, PaymentFrequency::OneTime | PaymentFrequency::PerRequest)
+    }
+
+    /// Next payment strictly after `after`; `None` for one-time and
+    /// per-request payments
+    pub fn next_occurrence(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            PaymentFrequency::OneTime | PaymentFrequency::PerRequest => None,
+            PaymentFrequency::Daily => Some(after + chrono::Duration::days(1)),
+            PaymentFrequency::Weekly => Some(after + chrono::Duration::weeks(1)),
+            PaymentFrequency::Monthly { day: None } => after.checked_add_months(Months::new(1)),
+            PaymentFrequency::Monthly { day: Some(day) } => (0..=1)
+                .filter_map(|months| on_day(after, months, *day))
+                .find(|next| *next > after),
+            PaymentFrequency::Quarterly => after.checked_add_months(Months::new(3)),
+            PaymentFrequency::Yearly => after.checked_add_months(Months::new(12)),
+            PaymentFrequency::Cron(expression) => crate::core::monitor::Schedule::parse(expression)
+                .ok()?
+                .next_after(after),
+        }
+    }
+}
+
+/// `after`'s time of day on day `day` of the month `months` after `after`'s,
+/// clamped to the month's last day
+fn on_day(after: DateTime<Utc>, months: u32, day: u8) -> Option<DateTime<Utc>> {
+    let first = after
+        .with_day(1)?
+        .checked_add_months(Months::new(months))?;
+    let last = (28..=31)
+        .rev()
+        .find(|day| first.with_day(*day).is_some())?;
+    first.with_day(u32::from(day).min(last))
+}
+
+impl fmt::Display for PaymentFrequency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaymentFrequency::OneTime => f.pad("one-time"),
+            PaymentFrequency::Daily => f.pad("daily"),
+            PaymentFrequency::Weekly => f.pad("weekly"),
+            PaymentFrequency::Monthly { day: None } => f.pad("monthly"),
+            PaymentFrequency::Monthly { day: Some(day) } => {
+                f.pad(&format!("monthly on day {}", day))
+            }
+            PaymentFrequency::Quarterly => f.pad("quarterly"),
+            PaymentFrequency::Yearly => f.pad("yearly"),
+            PaymentFrequency::PerRequest => f.pad("per-request"),
+            PaymentFrequency::Cron(expression) => f.pad(expression),
+        }
+    }
+}
+
+impl FromStr for PaymentFrequency {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> crate::Result<Self> {
+        let frequency = s.trim();
+        let name = frequency.to_lowercase().replace('_', "-");
+        let parsed = match name.as_str() {
+            "" | "one-time" | "onetime" | "once" => PaymentFrequency::OneTime,
+            "daily" => PaymentFrequency::Daily,
+            "weekly" => PaymentFrequency::Weekly,
+            "monthly" => PaymentFrequency::Monthly { day: None },
+            "quarterly" => PaymentFrequency::Quarterly,
+            "yearly" | "annual" | "annually" => PaymentFrequency::Yearly,
+            "per-request" | "per request" => PaymentFrequency::PerRequest,
+            _ if frequency.split_whitespace().count() >= 5 => {
+                crate::core::monitor::Schedule::parse(frequency)?;
+                PaymentFrequency::Cron(frequency.to_string())
+            }
+            _ => {
+                let day = name
+                    .strip_prefix("monthly on day ")
+                    .and_then(|day| day.trim().parse().ok())
+                    .filter(|day| (1..=31).contains(day))
+                    .ok_or_else(|| {
+                        crate::Error::ConfigError(format!(
+                            "Unknown payment frequency {:?}; expected one-time, daily, weekly, \
+                             monthly, monthly on day N, quarterly, yearly, per-request or a cron \
+                             expression",
+                            s
+                        ))
+                    })?;
+                PaymentFrequency::Monthly { day: Some(day) }
+            }
+        };
+        Ok(parsed)
+    }
+}
+
+impl Serialize for PaymentFrequency {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for PaymentFrequency {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_frequency_parses_and_displays() {
+        for name in ["one-time", "daily", "weekly", "monthly", "quarterly", "yearly", "per-request"] {
+            let frequency: PaymentFrequency = name.parse().unwrap();
+            assert_eq!(frequency.to_string(), name);
+        }
+        assert_eq!("once".parse::<PaymentFrequency>().unwrap(), PaymentFrequency::OneTime);
+        assert_eq!("Annually".parse::<PaymentFrequency>().unwrap(), PaymentFrequency::Yearly);
+        assert_eq!(
+            "monthly on day 15".parse::<PaymentFrequency>().unwrap(),
+            PaymentFrequency::Monthly { day: Some(15) }
+        );
+        assert_eq!(
+            serde_json::to_string(&PaymentFrequency::Cron("0 9 1 * *".to_string())).unwrap(),
+            "\"0 9 1 * *\""
+        );
+        assert!("fortnightly".parse::<PaymentFrequency>().is_err());
+        assert!("monthly on day 32".parse::<PaymentFrequency>().is_err());
+        assert!("0 99 * * *".parse::<PaymentFrequency>().is_err());
+    }
+
+    #[test]
+    fn test_next_occurrence() {
+        let at = |y, m, d| Utc.with_ymd_and_hms(y, m, d, 12, 0, 0).unwrap();
+        let jan31 = at(2026, 1, 31);
+
+        assert_eq!(PaymentFrequency::OneTime.next_occurrence(jan31), None);
+        assert_eq!(PaymentFrequency::Weekly.next_occurrence(jan31), Some(at(2026, 2, 7)));
+        assert_eq!(
+            PaymentFrequency::Monthly { day: None }.next_occurrence(jan31),
+            Some(at(2026, 2, 28))
+        );
+        let on_30th = PaymentFrequency::Monthly { day: Some(30) };
+        assert_eq!(on_30th.next_occurrence(at(2026, 1, 15)), Some(at(2026, 1, 30)));
+        assert_eq!(on_30th.next_occurrence(jan31), Some(at(2026, 2, 28)));
+        assert_eq!(
+            PaymentFrequency::Cron("0 9 1 * *".to_string()).next_occurrence(jan31),
+            Some(Utc.with_ymd_and_hms(2026, 2, 1, 9, 0, 0).unwrap())
+        );
+    }
+}
@@ -0,0 +1,291 @@
+//! Invoices for contract payments
+//!
+//! Renders a traditional invoice from a contract's payment terms and the
+//! payments recorded in its history, for accounting systems that need one
+//! alongside on-chain settlement. Invoice numbers are sequential per
+//! vendor.
+
+pub mod pdf;
+
+use crate::x402::challenge::{PAYEE_ROLES, PAYER_ROLES};
+use crate::{Amount, Contract, PartyInfo, PaymentRecord, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Sequential invoice numbers per vendor
+///
+/// Serializable so counters can be persisted between runs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InvoiceNumbering {
+    prefix: String,
+    counters: HashMap<String, u64>,
+}
+
+impl InvoiceNumbering {
+    /// Number invoices `<prefix>-<vendor>-<sequence>`
+    pub fn new(prefix: &str) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+            counters: HashMap::new(),
+        }
+    }
+
+    /// Allocate the next number for a vendor
+    pub fn next(&mut self, vendor: &str) -> String {
+        let counter = self.counters.entry(vendor.to_lowercase()).or_default();
+        *counter += 1;
+        let tag: String = vendor
+            .chars()
+            .filter(char::is_ascii_alphanumeric)
+            .take(8)
+            .collect::<String>()
+            .to_uppercase();
+        format!("{}-{}-{:06}", self.prefix, tag, counter)
+    }
+
+    /// Numbers issued so far for a vendor
+    pub fn issued(&self, vendor: &str) -> u64 {
+        self.counters
+            .get(&vendor.to_lowercase())
+            .copied()
+            .unwrap_or(0)
+    }
+}
+
+/// Billed item
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InvoiceLine {
+    pub description: String,
+    pub quantity: u32,
+    pub unit_price: Amount,
+    pub amount: Amount,
+}
+
+/// Invoice for a contract
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Invoice {
+    pub number: String,
+    pub contract_id: String,
+    pub issued_at: DateTime<Utc>,
+    pub vendor: PartyInfo,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub customer: Option<PartyInfo>,
+    /// Currency of all amounts (the contract currency)
+    pub currency: String,
+    pub lines: Vec<InvoiceLine>,
+    pub total: Amount,
+    /// Payments applied to the invoice
+    pub payments: Vec<PaymentRecord>,
+    pub amount_paid: Amount,
+    pub balance_due: Amount,
+}
+
+impl Invoice {
+    /// Invoice a contract's terms, applying the payments in its history
+    ///
+    /// Bills one period per recorded payment, or a single period when
+    /// nothing was paid yet. Token payments count at par with the contract
+    /// currency, as contract prices are quoted in it. Fails if the contract
+    /// has no payee party to invoice from.
+    pub fn from_contract(contract: &Contract, numbering: &mut InvoiceNumbering) -> Result<Self> {
+        let ucl = &contract.ucl;
+        let vendor = ucl.metadata.party(PAYEE_ROLES).ok_or_else(|| {
+            crate::Error::ValidationError(format!(
+                "Contract {} has no payee party to invoice from",
+                ucl.contract_id
+            ))
+        })?;
+        let payments = contract.payment_history().to_vec();
+        let periods = payments.len().max(1) as u32;
+        let unit_price = ucl.payment.amount;
+        let total = (unit_price * periods).round_dp(2);
+        let amount_paid = payments.iter().map(|p| p.amount).sum::<Amount>().round_dp(2);
+
+        Ok(Self {
+            number: numbering.next(&vendor.identifier),
+            contract_id: ucl.contract_id.clone(),
+            issued_at: Utc::now(),
+            customer: ucl.metadata.party(PAYER_ROLES).cloned(),
+            vendor: vendor.clone(),
+            currency: ucl
+                .payment
+                .price_currency()
+                .map_or_else(|| ucl.payment.token.to_string(), |currency| currency.to_string()),
+            lines: vec![InvoiceLine {
+                description: format!("{} ({})", ucl.summary.title, ucl.payment.frequency),
+                quantity: periods,
+                unit_price,
+                amount: total,
+            }],
+            total,
+            payments,
+            amount_paid,
+            balance_due: (total - amount_paid).max(Amount::ZERO),
+        })
+    }
+
+    /// Render as pretty-printed JSON
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Render as a standalone HTML page
+    pub fn to_html(&self) -> String {
+        let party = |p: &PartyInfo| {
+            escape(&match &p.name {
+                Some(name) => format!("{} ({})", name, p.identifier),
+                None => p.identifier.clone(),
+            })
+        };
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Invoice {number}</title></head>\n\
+             <body>\n<h1>Invoice {number}</h1>\n<p>Issued {issued}<br>Contract {contract}</p>\n\
+             <p><strong>From:</strong> {vendor}</p>\n",
+            number = escape(&self.number),
+            issued = self.issued_at.format("%Y-%m-%d"),
+            contract = escape(&self.contract_id),
+            vendor = party(&self.vendor),
+        );
+        if let Some(customer) = &self.customer {
+            html.push_str(&format!(
+                "<p><strong>To:</strong> {}</p>\n",
+                party(customer)
+            ));
+        }
+
+        html.push_str(
+            "<table>\n<tr><th>Description</th><th>Qty</th><th>Unit price</th><th>Amount</th></tr>\n",
+        );
+        for line in &self.lines {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{:.2}</td><td>{:.2}</td></tr>\n",
+                escape(&line.description),
+                line.quantity,
+                line.unit_price,
+                line.amount
+            ));
+        }
+        html.push_str("</table>\n");
+
+        if !self.payments.is_empty() {
+            html.push_str("<h2>Payments</h2>\n<ul>\n");
+            for payment in &self.payments {
+                html.push_str(&format!(
+                    "<li>{} {:.2} {} &mdash; {}</li>\n",
+                    payment.timestamp.format("%Y-%m-%d"),
+                    payment.amount,
+                    escape(&payment.currency),
+                    escape(&payment.reference)
+                ));
+            }
+            html.push_str("</ul>\n");
+        }
+
+        html.push_str(&format!(
+            "<p>Total: {total:.2} {cur}<br>Paid: {paid:.2} {cur}<br>\
+             <strong>Balance due: {due:.2} {cur}</strong></p>\n</body></html>\n",
+            total = self.total,
+            paid = self.amount_paid,
+            due = self.balance_due,
+            cur = escape(&self.currency),
+        ));
+        html
+    }
+
+    /// Render as a single-page PDF
+    pub fn to_pdf(&self) -> Vec<u8> {
+        let mut lines = vec![
+            format!("Invoice {}", self.number),
+            format!("Issued {}", self.issued_at.format("%Y-%m-%d")),
+            format!("Contract {}", self.contract_id),
+            String::new(),
+            format!("From: {}", self.vendor.identifier),
+        ];
+        if let Some(customer) = &self.customer {
+            lines.push(format!("To: {}", customer.identifier));
+        }
+        lines.push(String::new());
+        for line in &self.lines {
+            lines.push(format!(
+                "{}  x{}  @ {:.2}  = {:.2} {}",
+                line.description, line.quantity, line.unit_price, line.amount, self.currency
+            ));
+        }
+        lines.push(String::new());
+        for payment in &self.payments {
+            lines.push(format!(
+                "Paid {} {:.2} {} ({})",
+                payment.timestamp.format("%Y-%m-%d"),
+                payment.amount,
+                payment.currency,
+                payment.reference
+            ));
+        }
+        lines.push(format!("Total: {:.2} {}", self.total, self.currency));
+        lines.push(format!("Paid: {:.2} {}", self.amount_paid, self.currency));
+        lines.push(format!(
+            "Balance due: {:.2} {}",
+            self.balance_due, self.currency
+        ));
+        pdf::render_text(&lines)
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ContractConfig, PaymentMethod};
+
+    #[test]
+    fn test_invoice_from_contract() {
+        let mut contract = Contract::from_config(ContractConfig::default()).unwrap();
+        contract.ucl.payment.amount = Amount::from(99);
+        contract.ucl.metadata.parties = vec![
+            PartyInfo {
+                role: "vendor".to_string(),
+                identifier: "acme@example.com".to_string(),
+                name: Some("Acme <Inc>".to_string()),
+                ..Default::default()
+            },
+            PartyInfo {
+                role: "customer".to_string(),
+                identifier: "bob@example.com".to_string(),
+                name: None,
+                ..Default::default()
+            },
+        ];
+        contract.record_payment(PaymentRecord {
+            reference: "0xabc".to_string(),
+            method: PaymentMethod::Crypto {
+                network: crate::Network::Polygon,
+            },
+            amount: Amount::from(99),
+            currency: "USDC".to_string(),
+            payer: None,
+            timestamp: Utc::now(),
+            refund_of: None,
+        });
+
+        let mut numbering = InvoiceNumbering::new("INV");
+        let invoice = Invoice::from_contract(&contract, &mut numbering).unwrap();
+        assert_eq!(invoice.number, "INV-ACMEEXAM-000001");
+        assert_eq!(invoice.balance_due, Amount::ZERO);
+        assert_eq!(
+            invoice.customer.as_ref().unwrap().identifier,
+            "bob@example.com"
+        );
+        assert!(invoice.to_html().contains("Acme &lt;Inc&gt;"));
+        assert!(invoice.to_pdf().starts_with(b"%PDF-1.4"));
+
+        let next = Invoice::from_contract(&contract, &mut numbering).unwrap();
+        assert_eq!(next.number, "INV-ACMEEXAM-000002");
+    }
+}
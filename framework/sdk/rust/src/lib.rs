@@ -5,21 +5,19 @@
 //! ## Quick Start
 //!
 //! ```no_run
-//! use smart402::{Smart402, ContractConfig, PaymentConfig};
+//! use smart402::{Amount, PaymentFrequency, Smart402, ContractConfig, PaymentConfig, Token};
 //!
 //! #[tokio::main]
 //! async fn main() -> Result<(), Box<dyn std::error::Error>> {
-//!     let contract = Smart402::create(ContractConfig {
-//!         contract_type: "saas-subscription".to_string(),
-//!         parties: vec!["vendor@example.com".to_string(), "customer@example.com".to_string()],
-//!         payment: PaymentConfig {
-//!             amount: 99.0,
-//!             frequency: "monthly".to_string(),
-//!             token: "USDC".to_string(),
-//!             ..Default::default()
-//!         },
-//!         ..Default::default()
-//!     }).await?;
+//!     let payment = PaymentConfig::new(
+//!         Amount::from(99),
+//!         Token::usdc(),
+//!         PaymentFrequency::Monthly { day: None },
+//!     );
+//!     let mut contract = Smart402::create(
+//!         ContractConfig::new("saas-subscription", payment)
+//!             .with_parties(["vendor@example.com", "customer@example.com"]),
+//!     ).await?;
 //!
 //!     contract.deploy("polygon").await?;
 //!     contract.start_monitoring("hourly", None).await?;
@@ -31,19 +29,63 @@
 
 pub mod core;
 pub mod aeo;
+pub mod builder;
+pub mod invoice;
 pub mod llmo;
+pub mod currency;
+pub mod money;
+pub mod network;
+pub mod token;
 pub mod x402;
+pub mod telemetry;
 pub mod utils;
 pub mod error;
+pub mod frequency;
+pub mod pricing;
+pub mod term;
 pub mod types;
 
 // Re-exports for convenience
 pub use core::smart402::Smart402;
 pub use core::contract::Contract;
+pub use core::clock::{Clock, SystemClock};
+pub use core::actions::{Action, ActionContext, ActionRegistry, WebhookAction};
+pub use core::expression::{Expression, ExpressionError};
+pub use core::fleet::{FleetHandle, Monitor};
+pub use core::events::{ContractEvent, ContractEventKind};
+pub use core::monitor::{MonitorEvent, MonitorHandle, MonitorState, Schedule};
+pub use core::notify::{Alert, DiscordNotifier, Notifier, Severity, SlackNotifier};
+#[cfg(feature = "email")]
+pub use core::notify::EmailNotifier;
+#[cfg(feature = "websocket")]
+pub use core::push::EventServer;
+pub use core::payment::{PaymentAttempt, PaymentExecutor};
+pub use core::oracle::{
+    BackoffPolicy, ChainlinkOracle, EventOracle, GitHubCheck, GitHubOracle, HttpOracle, Oracle,
+    OracleError, OracleSet, ResponseCache, ResponseMapping, UptimeOracle,
+};
+pub use core::store::{
+    ContractQuery, ContractStore, FileContractStore, InMemoryContractStore, StoreError,
+};
+#[cfg(feature = "artifacts")]
+pub use core::artifacts::{ArtifactError, ArtifactKind, ArtifactRef, ArtifactStore};
+#[cfg(feature = "postgres")]
+pub use core::postgres::PostgresContractStore;
+#[cfg(feature = "sqlite")]
+pub use core::sqlite::SqliteContractStore;
+pub use core::webhook::{MonitoringEvent, MonitoringWebhook, WebhookSender};
 pub use aeo::{AEOEngine, engine::AEOScore};
 pub use llmo::{LLMOEngine, engine::ValidationResult};
 pub use x402::{X402Client, client::{X402Headers, PaymentResponse}};
 pub use types::*;
+pub use money::Amount;
+pub use currency::Currency;
+pub use network::Network;
+pub use token::Token;
+pub use frequency::PaymentFrequency;
+pub use pricing::{PriceTier, PricingStructure};
+pub use term::{ContractDuration, OutstandingPayments, Renewal, Termination, TerminationReason};
+pub use builder::UCLContractBuilder;
 pub use error::{Error, Result};
 
 /// SDK version
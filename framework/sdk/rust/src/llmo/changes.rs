@@ -0,0 +1,271 @@
+//! Plain-English change summaries between contract versions
+
+use super::LLMOEngine;
+use crate::types::ConditionDefinition;
+use crate::{Currency, PaymentFrequency, UCLContract};
+
+impl LLMOEngine {
+    /// Summarize what changed between two versions of a contract
+    ///
+    /// Produces a single paragraph suitable for customer notification
+    /// emails, e.g. "Price increased from $99 to $119/month; uptime
+    /// threshold lowered to 99.5%."
+    pub fn summarize_changes(&self, old: &UCLContract, new: &UCLContract) -> String {
+        let mut changes = Vec::new();
+
+        if old.summary.title != new.summary.title {
+            changes.push(format!(
+                "title changed from \"{}\" to \"{}\"",
+                old.summary.title, new.summary.title
+            ));
+        }
+
+        let old_price = format_price(old);
+        let new_price = format_price(new);
+        if old.payment.amount != new.payment.amount {
+            let direction = if new.payment.amount > old.payment.amount {
+                "increased"
+            } else {
+                "decreased"
+            };
+            changes.push(format!(
+                "price {} from {} to {}",
+                direction, old_price, new_price
+            ));
+        } else if old.payment.frequency != new.payment.frequency {
+            changes.push(format!(
+                "billing frequency changed from {} to {}",
+                old.payment.frequency, new.payment.frequency
+            ));
+        }
+
+        if old.payment.token != new.payment.token {
+            changes.push(format!(
+                "payment token changed from {} to {}",
+                old.payment.token, new.payment.token
+            ));
+        }
+
+        if old.payment.blockchain != new.payment.blockchain {
+            changes.push(format!(
+                "settlement network changed from {} to {}",
+                old.payment.blockchain, new.payment.blockchain
+            ));
+        }
+
+        if old.payment.structure != new.payment.structure {
+            changes.push(format!(
+                "pricing structure changed from {} to {}",
+                old.payment.structure, new.payment.structure
+            ));
+        }
+
+        let old_dates = &old.metadata.dates;
+        let new_dates = &new.metadata.dates;
+        if old_dates.effective != new_dates.effective {
+            changes.push(format!("effective date moved to {}", new_dates.effective));
+        }
+        if old_dates.duration != new_dates.duration {
+            changes.push(format!(
+                "term changed from {} to {}",
+                old_dates.duration, new_dates.duration
+            ));
+        }
+        if old_dates.renewal != new_dates.renewal {
+            changes.push(format!("renewal changed to {}", new_dates.renewal));
+        }
+
+        summarize_conditions(
+            &old.conditions.required,
+            &new.conditions.required,
+            &mut changes,
+        );
+
+        for party in &new.metadata.parties {
+            if !old
+                .metadata
+                .parties
+                .iter()
+                .any(|p| p.identifier == party.identifier)
+            {
+                changes.push(format!("{} added as {}", party.identifier, party.role));
+            }
+        }
+        for party in &old.metadata.parties {
+            if !new
+                .metadata
+                .parties
+                .iter()
+                .any(|p| p.identifier == party.identifier)
+            {
+                changes.push(format!("{} removed as {}", party.identifier, party.role));
+            }
+        }
+
+        if changes.is_empty() {
+            return "No material changes.".to_string();
+        }
+
+        let mut paragraph = changes.join("; ");
+        if let Some(first) = paragraph.get(..1) {
+            let upper = first.to_uppercase();
+            paragraph.replace_range(..1, &upper);
+        }
+        paragraph.push('.');
+        paragraph
+    }
+}
+
+fn summarize_conditions(
+    old: &[ConditionDefinition],
+    new: &[ConditionDefinition],
+    changes: &mut Vec<String>,
+) {
+    for condition in new {
+        match old.iter().find(|c| c.id == condition.id) {
+            None => changes.push(format!("new condition added: {}", condition.description)),
+            Some(previous) => {
+                if previous.threshold != condition.threshold {
+                    if let Some(threshold) = &condition.threshold {
+                        let direction = match (
+                            previous.threshold.as_ref().and_then(|v| v.as_f64()),
+                            threshold.as_f64(),
+                        ) {
+                            (Some(a), Some(b)) if b > a => "raised",
+                            (Some(a), Some(b)) if b < a => "lowered",
+                            _ => "changed",
+                        };
+                        changes.push(format!(
+                            "{} threshold {} to {}",
+                            condition_label(condition),
+                            direction,
+                            format_threshold(condition, threshold)
+                        ));
+                    } else {
+                        changes.push(format!("{} threshold removed", condition_label(condition)));
+                    }
+                } else if previous.operator != condition.operator {
+                    changes.push(format!(
+                        "{} operator changed from {} to {}",
+                        condition_label(condition),
+                        previous.operator,
+                        condition.operator
+                    ));
+                }
+            }
+        }
+    }
+
+    for condition in old {
+        if !new.iter().any(|c| c.id == condition.id) {
+            changes.push(format!("condition removed: {}", condition.description));
+        }
+    }
+}
+
+/// Human label for a condition, derived from its id ("uptime_check" → "uptime check")
+fn condition_label(condition: &ConditionDefinition) -> String {
+    let label = condition.id.replace(['_', '-'], " ");
+    label
+        .strip_suffix(" check")
+        .map(str::to_string)
+        .unwrap_or(label)
+}
+
+fn format_threshold(condition: &ConditionDefinition, value: &serde_json::Value) -> String {
+    match value.as_f64() {
+        Some(n) if n > 0.0 && n <= 1.0 && is_ratio(condition) => {
+            format!("{}%", format_number(n * 100.0))
+        }
+        Some(n) => format_number(n),
+        None => match value {
+            serde_json::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        },
+    }
+}
+
+fn is_ratio(condition: &ConditionDefinition) -> bool {
+    let haystack = format!("{} {}", condition.id, condition.source).to_lowercase();
+    ["uptime", "rate", "ratio", "percent"]
+        .iter()
+        .any(|key| haystack.contains(key))
+}
+
+fn format_price(ucl: &UCLContract) -> String {
+    let amount = ucl.payment.amount;
+    let price = match ucl.payment.currency.as_ref().map(Currency::code) {
+        Some("USD") => format!("${}", amount),
+        Some("EUR") => format!("€{}", amount),
+        Some("GBP") => format!("£{}", amount),
+        Some(currency) => format!("{} {}", amount, currency),
+        None => format!("{} {}", amount, ucl.payment.token),
+    };
+
+    match ucl.payment.frequency {
+        PaymentFrequency::Daily => format!("{}/day", price),
+        PaymentFrequency::Weekly => format!("{}/week", price),
+        PaymentFrequency::Monthly { .. } => format!("{}/month", price),
+        PaymentFrequency::Quarterly => format!("{}/quarter", price),
+        PaymentFrequency::Yearly => format!("{}/year", price),
+        PaymentFrequency::PerRequest => format!("{}/request", price),
+        PaymentFrequency::OneTime | PaymentFrequency::Cron(_) => price,
+    }
+}
+
+/// Format a number without a trailing ".0" and without float noise
+fn format_number(n: f64) -> String {
+    let rounded = (n * 1_000_000.0).round() / 1_000_000.0;
+    if rounded.fract() == 0.0 {
+        format!("{}", rounded as i64)
+    } else {
+        format!("{}", rounded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Amount, Contract, ContractConfig};
+
+    fn base() -> UCLContract {
+        let mut ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+        ucl.payment.amount = Amount::from(99);
+        ucl.payment.frequency = crate::PaymentFrequency::Monthly { day: None };
+        ucl.conditions.required.push(ConditionDefinition {
+            id: "uptime_check".to_string(),
+            description: "Service uptime above 99.9%".to_string(),
+            source: "uptime_oracle".to_string(),
+            operator: "greater_than".to_string(),
+            threshold: Some(serde_json::json!(0.999)),
+            expression: None,
+            temporal: None,
+        });
+        ucl
+    }
+
+    #[test]
+    fn test_summarize_price_and_threshold() {
+        let old = base();
+        let mut new = base();
+        new.payment.amount = Amount::from(119);
+        new.conditions.required[0].threshold = Some(serde_json::json!(0.995));
+
+        let summary = LLMOEngine::new().summarize_changes(&old, &new);
+        assert_eq!(
+            summary,
+            "Price increased from $99/month to $119/month; uptime threshold lowered to 99.5%."
+        );
+    }
+
+    #[test]
+    fn test_summarize_no_changes() {
+        let ucl = base();
+        assert_eq!(
+            LLMOEngine::new().summarize_changes(&ucl, &ucl),
+            "No material changes."
+        );
+    }
+}
@@ -0,0 +1,253 @@
+//! Jurisdiction-aware compliance checks
+
+use super::engine::ValidationResult;
+use super::LLMOEngine;
+use crate::{Amount, PaymentFrequency, Result, UCLContract};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Tokens associated with sanctioned entities (OFAC SDN list)
+const SANCTIONED_TOKENS: &[&str] = &["TORN", "GARA"];
+
+/// Jurisdiction a contract is checked against
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum Jurisdiction {
+    /// European Union
+    EU,
+    /// United Kingdom
+    UK,
+    /// United States, optionally narrowed to a state code (e.g. "CA")
+    US(Option<String>),
+    /// Any other jurisdiction; only global rules apply
+    Other(String),
+}
+
+impl FromStr for Jurisdiction {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let code = s.trim().to_uppercase();
+        match code.as_str() {
+            "" => Err(crate::Error::ConfigError(
+                "Jurisdiction cannot be empty".to_string(),
+            )),
+            "EU" => Ok(Jurisdiction::EU),
+            "UK" | "GB" => Ok(Jurisdiction::UK),
+            "US" => Ok(Jurisdiction::US(None)),
+            _ => match code.strip_prefix("US-") {
+                Some(state) if state.len() == 2 => Ok(Jurisdiction::US(Some(state.to_string()))),
+                Some(_) => Err(crate::Error::ConfigError(format!(
+                    "Invalid US state code: {}",
+                    s
+                ))),
+                None => Ok(Jurisdiction::Other(code)),
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for Jurisdiction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Jurisdiction::EU => write!(f, "EU"),
+            Jurisdiction::UK => write!(f, "UK"),
+            Jurisdiction::US(None) => write!(f, "US"),
+            Jurisdiction::US(Some(state)) => write!(f, "US-{}", state),
+            Jurisdiction::Other(code) => write!(f, "{}", code),
+        }
+    }
+}
+
+/// Severity of a compliance finding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+/// A single compliance issue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComplianceFinding {
+    /// Stable rule identifier (e.g. "eu.withdrawal_rights")
+    pub rule: String,
+    pub severity: Severity,
+    /// Contract field the finding relates to
+    pub field: String,
+    pub message: String,
+}
+
+/// Validation result together with jurisdiction-specific findings
+#[derive(Debug, Clone)]
+pub struct ComplianceReport {
+    pub jurisdiction: Jurisdiction,
+    pub validation: ValidationResult,
+    pub findings: Vec<ComplianceFinding>,
+}
+
+impl ComplianceReport {
+    /// Whether the contract is valid and has no error-level findings
+    pub fn is_compliant(&self) -> bool {
+        self.validation.valid && !self.findings.iter().any(|f| f.severity == Severity::Error)
+    }
+}
+
+impl LLMOEngine {
+    /// Flag jurisdiction-specific compliance issues in a contract
+    pub fn check_compliance(
+        &self,
+        ucl: &UCLContract,
+        jurisdiction: &Jurisdiction,
+    ) -> Vec<ComplianceFinding> {
+        let text = contract_text(ucl);
+        let mentions = |terms: &[&str]| terms.iter().any(|t| text.contains(t));
+        let recurring = ucl.payment.frequency != PaymentFrequency::OneTime;
+        let mut findings = Vec::new();
+
+        if SANCTIONED_TOKENS
+            .iter()
+            .any(|symbol| ucl.payment.token.is(symbol)) {
+            findings.push(ComplianceFinding {
+                rule: "global.sanctioned_token".to_string(),
+                severity: Severity::Error,
+                field: "payment.token".to_string(),
+                message: format!(
+                    "Token {} is associated with a sanctioned entity",
+                    ucl.payment.token
+                ),
+            });
+        }
+
+        match jurisdiction {
+            Jurisdiction::EU | Jurisdiction::UK => {
+                if recurring && !mentions(&["withdrawal", "cooling-off", "cooling_off", "cancel"]) {
+                    findings.push(ComplianceFinding {
+                        rule: "eu.withdrawal_rights".to_string(),
+                        severity: Severity::Error,
+                        field: "conditions".to_string(),
+                        message: "Consumer contracts must grant a 14-day right of withdrawal"
+                            .to_string(),
+                    });
+                }
+                if ucl.payment.amount > Amount::ZERO && !mentions(&["vat", "tax"]) {
+                    findings.push(ComplianceFinding {
+                        rule: "eu.vat_handling".to_string(),
+                        severity: Severity::Warning,
+                        field: "payment".to_string(),
+                        message: "Payment terms do not state whether the amount includes VAT"
+                            .to_string(),
+                    });
+                }
+                if ucl.payment.currency.is_none() {
+                    findings.push(ComplianceFinding {
+                        rule: "eu.price_transparency".to_string(),
+                        severity: Severity::Warning,
+                        field: "payment.currency".to_string(),
+                        message: "Total price must be shown in a fiat currency".to_string(),
+                    });
+                }
+            }
+            Jurisdiction::US(state) => {
+                let auto_renews = ucl.metadata.dates.renewal == crate::Renewal::Auto;
+                if state.as_deref() == Some("CA") && auto_renews && !mentions(&["cancel"]) {
+                    findings.push(ComplianceFinding {
+                        rule: "us-ca.auto_renewal_disclosure".to_string(),
+                        severity: Severity::Error,
+                        field: "metadata.dates.renewal".to_string(),
+                        message:
+                            "California auto-renewal law requires a clear cancellation disclosure"
+                                .to_string(),
+                    });
+                }
+                if ucl.payment.amount > Amount::ZERO && !mentions(&["tax"]) {
+                    findings.push(ComplianceFinding {
+                        rule: "us.sales_tax".to_string(),
+                        severity: Severity::Info,
+                        field: "payment".to_string(),
+                        message: "Sales tax treatment is not stated".to_string(),
+                    });
+                }
+            }
+            Jurisdiction::Other(_) => {}
+        }
+
+        findings
+    }
+
+    /// Validate a contract and check it against a jurisdiction in one pass
+    pub fn validate_for_jurisdiction(
+        &self,
+        ucl: &UCLContract,
+        jurisdiction: &Jurisdiction,
+    ) -> Result<ComplianceReport> {
+        Ok(ComplianceReport {
+            jurisdiction: jurisdiction.clone(),
+            validation: self.validate(ucl)?,
+            findings: self.check_compliance(ucl, jurisdiction),
+        })
+    }
+}
+
+/// Lowercased free text of the contract, used for clause detection
+fn contract_text(ucl: &UCLContract) -> String {
+    let mut parts = vec![
+        ucl.summary.title.as_str(),
+        ucl.summary.plain_english.as_str(),
+        ucl.summary.what_it_does.as_str(),
+        ucl.summary.who_its_for.as_str(),
+        ucl.summary.when_it_executes.as_str(),
+    ];
+    for condition in ucl
+        .conditions
+        .required
+        .iter()
+        .chain(ucl.conditions.optional.iter().flatten())
+    {
+        parts.push(&condition.id);
+        parts.push(&condition.description);
+    }
+    for rule in &ucl.rules {
+        parts.push(&rule.name);
+        parts.push(&rule.trigger);
+    }
+    parts.join(" ").to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Contract, ContractConfig};
+
+    #[test]
+    fn test_parse_jurisdiction() {
+        assert_eq!("eu".parse::<Jurisdiction>().unwrap(), Jurisdiction::EU);
+        assert_eq!(
+            "US-CA".parse::<Jurisdiction>().unwrap(),
+            Jurisdiction::US(Some("CA".to_string()))
+        );
+        assert!("US-CAL".parse::<Jurisdiction>().is_err());
+    }
+
+    #[test]
+    fn test_eu_subscription_requires_withdrawal_rights() {
+        let engine = LLMOEngine::new();
+        let mut ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+        ucl.payment.amount = Amount::from(99);
+        ucl.payment.frequency = crate::PaymentFrequency::Monthly { day: None };
+
+        let report = engine
+            .validate_for_jurisdiction(&ucl, &Jurisdiction::EU)
+            .unwrap();
+        assert!(!report.is_compliant());
+        assert!(report
+            .findings
+            .iter()
+            .any(|f| f.rule == "eu.withdrawal_rights"));
+        assert!(report.findings.iter().any(|f| f.rule == "eu.vat_handling"));
+    }
+}
@@ -1,9 +1,15 @@
 //! LLMO Engine for LLM understanding
 
-use crate::{Result, UCLContract};
+use super::prompts::PromptRegistry;
+use crate::{Amount, Result, UCLContract};
+
+/// Start of the validation error for a currency the token is not pegged to
+pub(crate) const CURRENCY_MISMATCH: &str = "currency does not match the token";
 
 /// LLMO Engine
-pub struct LLMOEngine {}
+pub struct LLMOEngine {
+    prompts: PromptRegistry,
+}
 
 impl Default for LLMOEngine {
     fn default() -> Self {
@@ -14,7 +20,35 @@ impl Default for LLMOEngine {
 impl LLMOEngine {
     /// Create new LLMO engine
     pub fn new() -> Self {
-        Self {}
+        Self {
+            prompts: PromptRegistry::default(),
+        }
+    }
+
+    /// Use a custom prompt registry for LLM-backed features
+    pub fn with_prompts(mut self, prompts: PromptRegistry) -> Self {
+        self.prompts = prompts;
+        self
+    }
+
+    /// Prompt registry used by LLM-backed features
+    pub fn prompts(&self) -> &PromptRegistry {
+        &self.prompts
+    }
+
+    /// Mutable access to the prompt registry
+    pub fn prompts_mut(&mut self) -> &mut PromptRegistry {
+        &mut self.prompts
+    }
+
+    /// Build the exact prompt that would be sent to the LLM for a feature
+    pub fn build_prompt(
+        &self,
+        feature: &str,
+        ucl: &UCLContract,
+        extra: &[(&str, String)],
+    ) -> Result<String> {
+        self.prompts.render_for_contract(feature, ucl, extra)
     }
 
     /// Validate UCL contract
@@ -40,12 +74,51 @@ impl LLMOEngine {
         }
 
         // Check payment terms
-        if ucl.payment.amount < 0.0 {
+        if ucl.payment.amount.is_sign_negative() {
             errors.push("payment amount cannot be negative".to_string());
         }
 
-        if ucl.payment.currency.is_empty() {
-            warnings.push("currency should be specified".to_string());
+        match (ucl.payment.currency, ucl.payment.token.peg()) {
+            (None, _) => warnings.push("currency should be specified".to_string()),
+            (Some(currency), Some(peg)) if currency != peg => errors.push(format!(
+                "{}: priced in {}, but {} is pegged to {}",
+                CURRENCY_MISMATCH, currency, ucl.payment.token, peg
+            )),
+            _ => {}
+        }
+
+        let structure = &ucl.payment.structure;
+        errors.extend(
+            structure
+                .errors()
+                .into_iter()
+                .map(|error| format!("payment structure: {}", error)),
+        );
+        if let Some(source) = structure.source() {
+            if !ucl.oracles.iter().any(|oracle| oracle.id == source) {
+                warnings.push(format!(
+                    "usage source {:?} is not one of the contract's oracles",
+                    source
+                ));
+            }
+        }
+
+        if let Some(termination) = &ucl.termination {
+            let conditions: Vec<_> = ucl
+                .conditions
+                .required
+                .iter()
+                .chain(ucl.conditions.optional.iter().flatten())
+                .map(|condition| condition.id.as_str())
+                .collect();
+            for id in &termination.for_cause {
+                if !conditions.contains(&id.as_str()) {
+                    errors.push(format!(
+                        "termination for cause names unknown condition {:?}",
+                        id
+                    ));
+                }
+            }
         }
 
         Ok(ValidationResult {
@@ -69,10 +142,20 @@ impl LLMOEngine {
         explanation.push_str(&format!("- **Duration**: {}\n\n", ucl.metadata.dates.duration));
 
         explanation.push_str("## Payment Terms\n\n");
-        explanation.push_str(&format!(
-            "- **Amount**: {} {}\n",
-            ucl.payment.amount, ucl.payment.currency
-        ));
+        let currency = ucl
+            .payment
+            .price_currency()
+            .map_or_else(|| ucl.payment.token.to_string(), |currency| currency.to_string());
+        explanation.push_str(&format!("- **Amount**: {} {}\n", ucl.payment.amount, currency));
+        let structure = &ucl.payment.structure;
+        if !structure.is_fixed() {
+            explanation.push_str(&format!(
+                "- **Usage pricing**: {} (prices in {}), metered by {}\n",
+                structure,
+                currency,
+                structure.source().unwrap_or("the paywall's request meter")
+            ));
+        }
         explanation.push_str(&format!("- **Token**: {}\n", ucl.payment.token));
         explanation.push_str(&format!("- **Network**: {}\n", ucl.payment.blockchain));
         explanation.push_str(&format!("- **Frequency**: {}\n\n", ucl.payment.frequency));
@@ -85,26 +168,77 @@ impl LLMOEngine {
             explanation.push('\n');
         }
 
+        if let Some(termination) = &ucl.termination {
+            explanation.push_str("## Termination\n\n");
+            match termination.notice {
+                Some(notice) => explanation.push_str(&format!(
+                    "- **Notice**: either party may terminate with {} notice\n",
+                    notice
+                )),
+                None => explanation.push_str("- **Notice**: cannot be terminated on notice\n"),
+            }
+            for id in &termination.for_cause {
+                let condition = ucl
+                    .conditions
+                    .required
+                    .iter()
+                    .chain(ucl.conditions.optional.iter().flatten())
+                    .find(|condition| condition.id == *id);
+                explanation.push_str(&format!(
+                    "- **For cause**: terminates at once if this fails: {}\n",
+                    condition.map_or(id.as_str(), |condition| condition.description.as_str())
+                ));
+            }
+            explanation.push_str(&format!(
+                "- **Outstanding payments**: {}\n\n",
+                match termination.outstanding {
+                    crate::OutstandingPayments::Prorated => {
+                        "paid until termination, with the unused part of the last period refunded"
+                    }
+                    crate::OutstandingPayments::Waived => {
+                        "no payments are made once notice is given or a cause arises"
+                    }
+                    _ => "payments due before termination are made in full",
+                }
+            ));
+        }
+
         Ok(explanation)
     }
 
     /// Compile UCL to target language
     pub fn compile(&self, ucl: &UCLContract, target: &str) -> Result<String> {
-        match target {
-            "solidity" => self.compile_solidity(ucl),
+        self.compile_with_options(ucl, target, &CompileOptions::default())
+    }
+
+    /// Compile UCL to target language with explicit codegen options
+    pub fn compile_with_options(
+        &self,
+        ucl: &UCLContract,
+        target: &str,
+        options: &CompileOptions,
+    ) -> Result<String> {
+        let code = match target {
+            "solidity" => self.compile_solidity(ucl, options),
             "javascript" => self.compile_javascript(ucl),
             "rust" => self.compile_rust(ucl),
             _ => Err(crate::Error::CompilationError(format!(
                 "Unsupported target: {}",
                 target
             ))),
+        }?;
+
+        if options.deterministic {
+            Ok(format!("{}{}", deterministic_header(ucl)?, code))
+        } else {
+            Ok(code)
         }
     }
 
-    fn compile_solidity(&self, ucl: &UCLContract) -> Result<String> {
+    fn compile_solidity(&self, ucl: &UCLContract, options: &CompileOptions) -> Result<String> {
         let code = format!(
             r#"// SPDX-License-Identifier: MIT
-pragma solidity ^0.8.0;
+pragma solidity {};
 
 /**
  * {}
@@ -117,7 +251,7 @@ contract Smart402Contract {{
 
     constructor(address _token) {{
         owner = msg.sender;
-        paymentAmount = {} * 10**18;
+        paymentAmount = {};
         paymentToken = _token;
     }}
 
@@ -125,15 +259,124 @@ contract Smart402Contract {{
         require(msg.value >= paymentAmount, "Insufficient payment");
         // Payment logic here
     }}
-}}
+{}}}
 "#,
+            if options.deterministic {
+                PINNED_SOLC
+            } else {
+                "^0.8.0"
+            },
             ucl.summary.title,
             ucl.summary.plain_english,
-            ucl.payment.amount
+            Self::solidity_amount(ucl)?,
+            Self::solidity_usage(ucl)?
         );
         Ok(code)
     }
 
+    /// Solidity literal for the payment amount in the token's base units
+    pub(crate) fn solidity_amount(ucl: &UCLContract) -> Result<String> {
+        base_units(ucl.payment.amount, ucl.payment.token.decimals())
+    }
+
+    /// Price bands of usage pricing as base-unit literals: (from, to, price),
+    /// `to` being `None` for no limit
+    fn usage_bands(ucl: &UCLContract) -> Result<Vec<(u64, Option<u64>, String)>> {
+        let decimals = ucl.payment.token.decimals();
+        ucl.payment
+            .structure
+            .bands()
+            .into_iter()
+            .map(|band| Ok((band.from, band.to, base_units(band.price, decimals)?)))
+            .collect()
+    }
+
+    /// Solidity `amountFor` for usage pricing; empty for fixed pricing
+    fn solidity_usage(ucl: &UCLContract) -> Result<String> {
+        if ucl.payment.structure.is_fixed() {
+            return Ok(String::new());
+        }
+        let bands: String = Self::usage_bands(ucl)?
+            .into_iter()
+            .map(|(from, to, price)| {
+                let to = to.map_or("type(uint256).max".to_string(), |to| to.to_string());
+                format!("        total += _band(units, {}, {}, {});\n", from, to, price)
+            })
+            .collect();
+        Ok(format!(
+            r#"
+    /// Amount due for `units` units ({}) used in the period
+    function amountFor(uint256 units) public view returns (uint256 total) {{
+        total = paymentAmount;
+{}    }}
+
+    function _band(uint256 units, uint256 from, uint256 to, uint256 price) private pure returns (uint256) {{
+        if (units <= from) return 0;
+        return ((units < to ? units : to) - from) * price;
+    }}
+"#,
+            ucl.payment.structure.unit().unwrap_or("unit"),
+            bands
+        ))
+    }
+
+    /// JavaScript `amountFor` for usage pricing; empty for fixed pricing
+    fn javascript_usage(ucl: &UCLContract) -> Result<String> {
+        if ucl.payment.structure.is_fixed() {
+            return Ok(String::new());
+        }
+        let bands: Vec<_> = Self::usage_bands(ucl)?
+            .into_iter()
+            .map(|(from, to, price)| {
+                let to = to.map_or("null".to_string(), |to| format!("{}n", to));
+                format!("band({}n, {}, {}n)", from, to, price)
+            })
+            .collect();
+        Ok(format!(
+            r#"
+  // Amount due for `units` units ({}) used in the period
+  amountFor(units) {{
+    const used = BigInt(units);
+    const band = (from, to, price) =>
+      used <= from ? 0n : ((to === null || used < to ? used : to) - from) * price;
+    return this.paymentAmount + {};
+  }}
+"#,
+            ucl.payment.structure.unit().unwrap_or("unit"),
+            bands.join(" + ")
+        ))
+    }
+
+    /// Rust `amount_for` for usage pricing; empty for fixed pricing
+    fn rust_usage(ucl: &UCLContract) -> Result<String> {
+        if ucl.payment.structure.is_fixed() {
+            return Ok(String::new());
+        }
+        let bands: Vec<_> = Self::usage_bands(ucl)?
+            .into_iter()
+            .map(|(from, to, price)| {
+                let to = to.map_or("u128::MAX".to_string(), |to| to.to_string());
+                format!("band({}, {}, {})", from, to, price)
+            })
+            .collect();
+        Ok(format!(
+            r#"
+    /// Amount due for `units` units ({}) used in the period, in base units
+    pub fn amount_for(&self, units: u128) -> u128 {{
+        let band = |from: u128, to: u128, price: u128| (units.clamp(from, to) - from) * price;
+        self.payment_amount + {}
+    }}
+"#,
+            ucl.payment.structure.unit().unwrap_or("unit"),
+            bands.join(" + ")
+        ))
+    }
+
+    /// Address of the payment token on the contract's network
+    fn token_address(ucl: &UCLContract) -> Option<&str> {
+        ucl.payment.token.address(ucl.payment.blockchain)
+    }
+
     fn compile_javascript(&self, ucl: &UCLContract) -> Result<String> {
         let code = format!(
             r#"/**
@@ -142,8 +385,10 @@ contract Smart402Contract {{
  */
 class Smart402Contract {{
   constructor() {{
-    this.paymentAmount = {};
+    this.paymentAmount = {}n;
     this.paymentToken = '{}';
+    this.paymentDecimals = {};
+    this.paymentTokenAddress = {};
     this.network = '{}';
   }}
 
@@ -155,15 +400,18 @@ class Smart402Contract {{
       token: this.paymentToken
     }};
   }}
-}}
+{}}}
 
 module.exports = Smart402Contract;
 "#,
             ucl.summary.title,
             ucl.summary.plain_english,
-            ucl.payment.amount,
+            Self::solidity_amount(ucl)?,
             ucl.payment.token,
-            ucl.payment.blockchain
+            ucl.payment.token.decimals(),
+            Self::token_address(ucl).map_or("null".to_string(), |a| format!("'{}'", a)),
+            ucl.payment.blockchain,
+            Self::javascript_usage(ucl)?
         );
         Ok(code)
     }
@@ -173,8 +421,12 @@ module.exports = Smart402Contract;
             r#"/// {}
 /// {}
 pub struct Smart402Contract {{
-    pub payment_amount: f64,
+    /// Payment amount in the token's base units
+    pub payment_amount: u128,
     pub payment_token: String,
+    pub payment_decimals: u32,
+    /// Token contract address; `None` for native gas tokens
+    pub payment_token_address: Option<String>,
     pub network: String,
 }}
 
@@ -183,6 +435,8 @@ impl Smart402Contract {{
         Self {{
             payment_amount: {},
             payment_token: "{}".to_string(),
+            payment_decimals: {},
+            payment_token_address: {},
             network: "{}".to_string(),
         }}
     }}
@@ -195,18 +449,69 @@ impl Smart402Contract {{
             token: self.payment_token.clone(),
         }})
     }}
-}}
+{}}}
 "#,
             ucl.summary.title,
             ucl.summary.plain_english,
-            ucl.payment.amount,
+            Self::solidity_amount(ucl)?,
             ucl.payment.token,
-            ucl.payment.blockchain
+            ucl.payment.token.decimals(),
+            Self::token_address(ucl).map_or("None".to_string(), |a| format!("Some({:?}.to_string())", a)),
+            ucl.payment.blockchain,
+            Self::rust_usage(ucl)?
         );
         Ok(code)
     }
 }
 
+/// Solidity compiler version pinned in deterministic mode
+const PINNED_SOLC: &str = "0.8.20";
+
+/// Code generation options
+#[derive(Debug, Clone, Default)]
+pub struct CompileOptions {
+    /// Produce byte-identical output for identical contracts: pinned compiler
+    /// version and a stable generator header
+    pub deterministic: bool,
+}
+
+impl CompileOptions {
+    /// Options for deterministic output suitable for diffing in CI
+    pub fn deterministic() -> Self {
+        Self {
+            deterministic: true,
+        }
+    }
+}
+
+/// Header identifying the generator version and the exact contract content
+fn deterministic_header(ucl: &UCLContract) -> Result<String> {
+    let digest = ucl.content_digest()?;
+    Ok(format!(
+        "// Generated by smart402 {} (deterministic mode). Do not edit.\n\
+         // Source: {} sha256:{}\n",
+        crate::VERSION,
+        ucl.contract_id,
+        digest
+    ))
+}
+
+/// Integer base units for an amount of a token with `decimals` decimals
+///
+/// Fractions finer than the token's decimals are rounded off. Fails for
+/// negative amounts and amounts too large for 128 bits.
+pub(crate) fn base_units(amount: Amount, decimals: u32) -> Result<String> {
+    amount
+        .to_base_units(decimals)
+        .map(|units| units.to_string())
+        .ok_or_else(|| {
+            crate::Error::ValidationError(format!(
+                "{} has no amount in base units with {} decimals",
+                amount, decimals
+            ))
+        })
+}
+
 /// Validation result
 #[derive(Debug, Clone)]
 pub struct ValidationResult {
@@ -214,3 +519,106 @@ pub struct ValidationResult {
     pub errors: Vec<String>,
     pub warnings: Vec<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Contract, ContractConfig};
+
+    #[test]
+    fn test_deterministic_compile() {
+        let engine = LLMOEngine::new();
+        let mut ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+        ucl.payment.amount = Amount::new(1, 1);
+
+        let options = CompileOptions::deterministic();
+        let first = engine.compile_with_options(&ucl, "solidity", &options).unwrap();
+        let second = engine.compile_with_options(&ucl, "solidity", &options).unwrap();
+        assert_eq!(first, second);
+        assert!(first.starts_with("// Generated by smart402"));
+        assert!(first.contains("pragma solidity 0.8.20;"));
+        assert!(first.contains("paymentAmount = 100000;"));
+    }
+
+    #[test]
+    fn test_solidity_amount_uses_token_decimals() {
+        let mut ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+        ucl.payment.amount = Amount::new(995, 1);
+
+        ucl.payment.token = "USDC".parse().unwrap();
+        assert_eq!(LLMOEngine::solidity_amount(&ucl).unwrap(), "99500000");
+
+        ucl.payment.token = "DAI".parse().unwrap();
+        assert_eq!(LLMOEngine::solidity_amount(&ucl).unwrap(), "99500000000000000000");
+
+        ucl.payment.token = "ETH".parse().unwrap();
+        ucl.payment.amount = Amount::new(5, 2);
+        assert_eq!(LLMOEngine::solidity_amount(&ucl).unwrap(), "50000000000000000");
+
+        ucl.payment.token = crate::Token::custom("ACME", 2);
+        assert_eq!(LLMOEngine::solidity_amount(&ucl).unwrap(), "5");
+    }
+
+    #[test]
+    fn test_base_units() {
+        assert_eq!(base_units(Amount::from(99), 6).unwrap(), "99000000");
+        assert_eq!(base_units(Amount::new(1, 1), 18).unwrap(), "100000000000000000");
+        assert_eq!(base_units(Amount::new(15, 7), 6).unwrap(), "2");
+        assert_eq!(base_units(Amount::ZERO, 18).unwrap(), "0");
+        assert!(base_units(Amount::from(-99), 6).is_err());
+    }
+
+    #[test]
+    fn test_usage_pricing_validation_and_codegen() {
+        let engine = LLMOEngine::new();
+        let mut ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+        ucl.payment.structure = crate::PricingStructure::Tiered {
+            unit: "request".to_string(),
+            tiers: vec![
+                crate::PriceTier {
+                    up_to: Some(1000),
+                    unit_price: Amount::new(1, 2),
+                },
+                crate::PriceTier {
+                    up_to: None,
+                    unit_price: Amount::new(5, 3),
+                },
+            ],
+            source: Some("usage-api".to_string()),
+        };
+        let validation = engine.validate(&ucl).unwrap();
+        assert!(validation.valid, "{:?}", validation.errors);
+        assert!(validation.warnings.iter().any(|w| w.contains("\"usage-api\"")));
+        let explanation = engine.explain(&ucl).unwrap();
+        assert!(
+            explanation.contains(
+                "- **Usage pricing**: tiered: 0.01 per request for 1-1000, 0.005 per request \
+                 for 1001+ (prices in USD), metered by usage-api"
+            ),
+            "{}",
+            explanation
+        );
+
+        let solidity = engine.compile(&ucl, "solidity").unwrap();
+        assert!(solidity.contains("total += _band(units, 0, 1000, 10000);"), "{}", solidity);
+        assert!(solidity.contains("total += _band(units, 1000, type(uint256).max, 5000);"));
+        let rust = engine.compile(&ucl, "rust").unwrap();
+        assert!(rust.contains(
+            "self.payment_amount + band(0, 1000, 10000) + band(1000, u128::MAX, 5000)"
+        ));
+        assert!(engine.compile(&ucl, "javascript").unwrap().contains("band(1000n, null, 5000n)"));
+
+        if let crate::PricingStructure::Tiered { tiers, .. } = &mut ucl.payment.structure {
+            tiers.swap(0, 1);
+        }
+        let validation = engine.validate(&ucl).unwrap();
+        assert!(!validation.valid);
+        assert!(validation.errors[0].starts_with("payment structure: "));
+    }
+}
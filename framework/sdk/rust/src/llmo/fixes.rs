@@ -0,0 +1,219 @@
+//! Auto-fix suggestions for validation failures
+
+use super::engine::{ValidationResult, CURRENCY_MISMATCH};
+use super::LLMOEngine;
+use crate::{Result, UCLContract};
+use serde::{Deserialize, Serialize};
+
+/// A concrete patch proposed for a validation failure
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FixSuggestion {
+    /// JSON pointer of the field to change (e.g. "/summary/title")
+    pub field: String,
+    /// Validation message this fix addresses
+    pub issue: String,
+    /// Proposed value
+    pub value: serde_json::Value,
+    /// Human-readable explanation of the fix
+    pub description: String,
+    /// Whether the fix can be applied without human review
+    pub safe: bool,
+}
+
+impl LLMOEngine {
+    /// Propose concrete patches for the errors and warnings in a validation result
+    pub fn suggest_fixes(
+        &self,
+        ucl: &UCLContract,
+        validation: &ValidationResult,
+    ) -> Vec<FixSuggestion> {
+        validation
+            .errors
+            .iter()
+            .chain(validation.warnings.iter())
+            .filter_map(|issue| suggest_fix(ucl, issue))
+            .collect()
+    }
+
+    /// Apply the safe subset of `fixes`, returning the corrected contract for review
+    pub fn apply_safe_fixes(
+        &self,
+        ucl: &UCLContract,
+        fixes: &[FixSuggestion],
+    ) -> Result<UCLContract> {
+        let safe: Vec<FixSuggestion> = fixes.iter().filter(|f| f.safe).cloned().collect();
+        self.apply_fixes(ucl, &safe)
+    }
+
+    /// Apply all `fixes` to a copy of the contract
+    pub fn apply_fixes(&self, ucl: &UCLContract, fixes: &[FixSuggestion]) -> Result<UCLContract> {
+        let mut doc = serde_json::to_value(ucl)?;
+        for fix in fixes {
+            if let Some(target) = doc.pointer_mut(&fix.field) {
+                *target = fix.value.clone();
+                continue;
+            }
+            // Optional fields that are unset are left out of the document
+            let parent = fix
+                .field
+                .rsplit_once('/')
+                .and_then(|(parent, key)| Some((doc.pointer_mut(parent)?.as_object_mut()?, key)));
+            let (parent, key) = parent.ok_or_else(|| {
+                crate::Error::ValidationError(format!("Unknown field in fix: {}", fix.field))
+            })?;
+            parent.insert(key.to_string(), fix.value.clone());
+        }
+        Ok(serde_json::from_value(doc)?)
+    }
+}
+
+fn suggest_fix(ucl: &UCLContract, issue: &str) -> Option<FixSuggestion> {
+    let fix =
+        |field: &str, value: serde_json::Value, description: String, safe: bool| FixSuggestion {
+            field: field.to_string(),
+            issue: issue.to_string(),
+            value,
+            description,
+            safe,
+        };
+
+    match issue {
+        "contract_id is required" => {
+            let id = crate::utils::generate_contract_id(&ucl.metadata.contract_type);
+            Some(fix(
+                "/contract_id",
+                id.clone().into(),
+                format!("Generate contract id {}", id),
+                true,
+            ))
+        }
+        "version is required" => Some(fix(
+            "/version",
+            "1.0".into(),
+            "Default version to 1.0".to_string(),
+            true,
+        )),
+        "title should be provided" => {
+            let title = default_title(ucl);
+            Some(fix(
+                "/summary/title",
+                title.clone().into(),
+                format!("Fill title from metadata: \"{}\"", title),
+                true,
+            ))
+        }
+        "plain_english summary should be provided" => {
+            let summary = default_summary(ucl);
+            Some(fix(
+                "/summary/plain_english",
+                summary.clone().into(),
+                format!("Generate summary from payment terms: \"{}\"", summary),
+                true,
+            ))
+        }
+        "currency should be specified" => {
+            let currency = ucl.payment.token.peg()?;
+            Some(fix(
+                "/payment/currency",
+                currency.code().into(),
+                format!("Default currency from token {}", ucl.payment.token),
+                true,
+            ))
+        }
+        _ if issue.starts_with(CURRENCY_MISMATCH) => {
+            let currency = ucl.payment.token.peg()?;
+            Some(fix(
+                "/payment/currency",
+                currency.code().into(),
+                format!(
+                    "Price in {}, the currency {} is pegged to (verify the price)",
+                    currency, ucl.payment.token
+                ),
+                false,
+            ))
+        }
+        "payment amount cannot be negative" => Some(fix(
+            "/payment/amount",
+            serde_json::json!(ucl.payment.amount.abs()),
+            "Use the absolute payment amount (verify the sign was a typo)".to_string(),
+            false,
+        )),
+        _ => None,
+    }
+}
+
+fn default_title(ucl: &UCLContract) -> String {
+    let words: Vec<String> = ucl
+        .metadata
+        .contract_type
+        .split(['-', '_', ' '])
+        .filter(|w| !w.is_empty())
+        .map(|w| {
+            let mut chars = w.chars();
+            match chars.next() {
+                Some(c) => c.to_uppercase().collect::<String>() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect();
+
+    if words.is_empty() {
+        "Smart402 Contract".to_string()
+    } else {
+        format!("{} Contract", words.join(" "))
+    }
+}
+
+fn default_summary(ucl: &UCLContract) -> String {
+    format!(
+        "Pays {} {} ({}) on {}.",
+        ucl.payment.amount, ucl.payment.token, ucl.payment.frequency, ucl.payment.blockchain
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Amount, Contract, ContractConfig, Currency};
+
+    #[test]
+    fn test_suggest_and_apply_safe_fixes() {
+        let engine = LLMOEngine::new();
+        let mut ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+        ucl.metadata.contract_type = "saas-subscription".to_string();
+        ucl.summary.title.clear();
+        ucl.payment.currency = None;
+        ucl.payment.amount = Amount::from(-5);
+
+        let validation = engine.validate(&ucl).unwrap();
+        let fixes = engine.suggest_fixes(&ucl, &validation);
+        assert_eq!(fixes.len(), 3);
+
+        let fixed = engine.apply_safe_fixes(&ucl, &fixes).unwrap();
+        assert_eq!(fixed.summary.title, "Saas Subscription Contract");
+        assert_eq!(fixed.payment.currency, Some(Currency::USD));
+        assert_eq!(fixed.payment.amount, Amount::from(-5));
+    }
+
+    #[test]
+    fn test_currency_must_match_token_peg() {
+        let engine = LLMOEngine::new();
+        let mut ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+        ucl.payment.currency = Some(Currency::EUR);
+
+        let validation = engine.validate(&ucl).unwrap();
+        assert!(!validation.valid);
+        assert_eq!(
+            validation.errors,
+            ["currency does not match the token: priced in EUR, but USDC is pegged to USD"]
+        );
+        let fixes = engine.suggest_fixes(&ucl, &validation);
+        assert!(!fixes[0].safe);
+        let fixed = engine.apply_fixes(&ucl, &fixes).unwrap();
+        assert!(engine.validate(&fixed).unwrap().valid);
+    }
+}
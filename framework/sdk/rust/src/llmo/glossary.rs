@@ -0,0 +1,166 @@
+//! Glossary generation for contract terms
+
+use super::LLMOEngine;
+use crate::{Network, Result, UCLContract};
+use serde::{Deserialize, Serialize};
+
+/// A term referenced by a contract and its plain-English definition
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GlossaryEntry {
+    pub term: String,
+    pub definition: String,
+}
+
+impl LLMOEngine {
+    /// Define the domain terms a contract references
+    ///
+    /// Covers the payment token, settlement network, oracles and condition
+    /// sources, so explanations can be understood by non-technical signatories.
+    pub fn glossary(&self, ucl: &UCLContract) -> Vec<GlossaryEntry> {
+        let mut entries: Vec<GlossaryEntry> = Vec::new();
+        let mut push = |term: &str, definition: String| {
+            if !term.is_empty() && !entries.iter().any(|e| e.term.eq_ignore_ascii_case(term)) {
+                entries.push(GlossaryEntry {
+                    term: term.to_string(),
+                    definition,
+                });
+            }
+        };
+
+        push(
+            &ucl.standard,
+            "Universal Contract Language: the structured, machine-readable format this agreement is written in.".to_string(),
+        );
+
+        let token = ucl.payment.token.symbol();
+        if let Some(definition) = token_definition(token) {
+            push(token, definition.to_string());
+        } else {
+            push(
+                token,
+                format!(
+                    "The digital token used to settle payments under this contract on {}.",
+                    ucl.payment.blockchain
+                ),
+            );
+        }
+
+        push(
+            ucl.payment.blockchain.name(),
+            network_definition(ucl.payment.blockchain).to_string(),
+        );
+
+        for oracle in &ucl.oracles {
+            let mut definition = format!(
+                "{} data source that reports facts the contract depends on, refreshed {}.",
+                oracle_kind(&oracle.oracle_type),
+                oracle.refresh_rate
+            );
+            if oracle.required {
+                definition.push_str(" The contract cannot execute without it.");
+            }
+            push(&oracle.id, definition);
+        }
+
+        for condition in ucl
+            .conditions
+            .required
+            .iter()
+            .chain(ucl.conditions.optional.iter().flatten())
+        {
+            if ucl.oracles.iter().any(|o| o.id == condition.source) {
+                continue;
+            }
+            push(
+                &condition.source,
+                format!(
+                    "The source checked to decide whether \"{}\" holds.",
+                    condition.description
+                ),
+            );
+        }
+
+        push(
+            "X402",
+            "The HTTP payment protocol used to request and prove payments between the parties' systems.".to_string(),
+        );
+
+        entries
+    }
+
+    /// Render glossary entries as a markdown section
+    pub fn render_glossary(&self, entries: &[GlossaryEntry]) -> String {
+        let mut section = String::from("## Glossary\n\n");
+        for entry in entries {
+            section.push_str(&format!("- **{}**: {}\n", entry.term, entry.definition));
+        }
+        section.push('\n');
+        section
+    }
+
+    /// Generate explanation of contract followed by a glossary of its terms
+    pub fn explain_with_glossary(&self, ucl: &UCLContract) -> Result<String> {
+        let mut explanation = self.explain(ucl)?;
+        explanation.push_str(&self.render_glossary(&self.glossary(ucl)));
+        Ok(explanation)
+    }
+}
+
+fn token_definition(token: &str) -> Option<&'static str> {
+    Some(match token.to_uppercase().as_str() {
+        "USDC" => "USD Coin, a stablecoin issued by Circle that is redeemable 1:1 for US dollars.",
+        "USDT" => "Tether, a stablecoin designed to track the US dollar 1:1.",
+        "DAI" => "A decentralized stablecoin from MakerDAO that targets a value of 1 US dollar.",
+        "EURC" => "Euro Coin, a stablecoin issued by Circle that is redeemable 1:1 for euros.",
+        "ETH" => "Ether, the native currency of the Ethereum network; its price fluctuates.",
+        "MATIC" | "POL" => "The native token of the Polygon network; its price fluctuates.",
+        _ => return None,
+    })
+}
+
+fn network_definition(network: Network) -> &'static str {
+    if network.is_testnet() {
+        return "A test blockchain; payments here have no real value.";
+    }
+    match network {
+        Network::Polygon => {
+            "A low-fee blockchain compatible with Ethereum, where payments are recorded."
+        }
+        Network::Ethereum => "The Ethereum blockchain, where payments are recorded publicly.",
+        Network::Base => "A low-fee Ethereum layer-2 blockchain operated by Coinbase.",
+        Network::Avalanche => "A low-fee blockchain compatible with Ethereum.",
+        _ => "A low-fee Ethereum layer-2 blockchain.",
+    }
+}
+
+fn oracle_kind(oracle_type: &str) -> &'static str {
+    match oracle_type {
+        "chainlink" => "A Chainlink price-feed",
+        "uptime" | "healthcheck" => "A service-availability",
+        "github" => "A GitHub activity",
+        "event" | "onchain" => "An on-chain event",
+        _ => "An external",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Contract, ContractConfig};
+
+    #[test]
+    fn test_glossary_covers_token_and_network() {
+        let engine = LLMOEngine::new();
+        let ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+        let glossary = engine.glossary(&ucl);
+
+        assert!(glossary.iter().any(|e| e.term == "USDC"));
+        assert!(glossary.iter().any(|e| e.term == "polygon"));
+        assert!(engine
+            .explain_with_glossary(&ucl)
+            .unwrap()
+            .contains("## Glossary"));
+    }
+}
@@ -0,0 +1,167 @@
+//! Schema guard for LLM-produced contracts
+
+use super::LLMOEngine;
+use crate::UCLContract;
+use serde_json::Value;
+
+/// Structured failure of LLM output that could not be repaired
+#[derive(Debug, Clone, thiserror::Error)]
+#[non_exhaustive]
+pub enum LlmOutputError {
+    #[error("LLM output is not valid JSON: {0}")]
+    InvalidJson(String),
+
+    #[error("LLM output does not match the UCL schema: {0}")]
+    SchemaMismatch(String),
+
+    #[error("LLM output failed validation: {}", .0.join("; "))]
+    Invalid(Vec<String>),
+}
+
+/// A contract accepted by the schema guard, with the repairs that were made
+#[derive(Debug, Clone)]
+pub struct GuardedOutput {
+    pub ucl: UCLContract,
+    /// Human-readable description of every repair applied
+    pub repairs: Vec<String>,
+}
+
+impl LLMOEngine {
+    /// Re-validate raw LLM output against the UCL schema
+    ///
+    /// Strips markdown code fences, drops fields that are not part of the
+    /// schema, applies safe fixes for validation failures and rejects
+    /// anything that still is not a valid contract.
+    pub fn guard_output(&self, raw: &str) -> Result<GuardedOutput, LlmOutputError> {
+        let text = strip_code_fence(raw);
+        let value: Value =
+            serde_json::from_str(text).map_err(|e| LlmOutputError::InvalidJson(e.to_string()))?;
+        self.guard_value(value)
+    }
+
+    /// Re-validate an already-parsed JSON document against the UCL schema
+    pub fn guard_value(&self, value: Value) -> Result<GuardedOutput, LlmOutputError> {
+        let ucl: UCLContract = serde_json::from_value(value.clone())
+            .map_err(|e| LlmOutputError::SchemaMismatch(e.to_string()))?;
+
+        // Anything serde ignored on the way in was hallucinated
+        let round_trip = serde_json::to_value(&ucl)
+            .map_err(|e| LlmOutputError::SchemaMismatch(e.to_string()))?;
+        let mut repairs = Vec::new();
+        collect_unknown_fields(&value, &round_trip, "", &mut repairs);
+
+        let mut ucl = ucl;
+        let validation = self
+            .validate(&ucl)
+            .map_err(|e| LlmOutputError::SchemaMismatch(e.to_string()))?;
+        if !validation.errors.is_empty() || !validation.warnings.is_empty() {
+            let fixes = self.suggest_fixes(&ucl, &validation);
+            let safe: Vec<_> = fixes.into_iter().filter(|f| f.safe).collect();
+            if !safe.is_empty() {
+                ucl = self
+                    .apply_fixes(&ucl, &safe)
+                    .map_err(|e| LlmOutputError::SchemaMismatch(e.to_string()))?;
+                repairs.extend(safe.into_iter().map(|f| f.description));
+            }
+        }
+
+        let validation = self
+            .validate(&ucl)
+            .map_err(|e| LlmOutputError::SchemaMismatch(e.to_string()))?;
+        if !validation.valid {
+            return Err(LlmOutputError::Invalid(validation.errors));
+        }
+
+        Ok(GuardedOutput { ucl, repairs })
+    }
+
+    /// Parse an LLM response into a contract, rejecting unrepairable output
+    pub fn parse_llm_response(&self, raw: &str) -> crate::Result<UCLContract> {
+        Ok(self.guard_output(raw)?.ucl)
+    }
+}
+
+fn strip_code_fence(raw: &str) -> &str {
+    let trimmed = raw.trim();
+    match trimmed.strip_prefix("```") {
+        Some(rest) => {
+            let body = rest.split_once('\n').map(|(_, body)| body).unwrap_or(rest);
+            body.trim_end().strip_suffix("```").unwrap_or(body).trim()
+        }
+        None => trimmed,
+    }
+}
+
+/// Record keys present in `original` but dropped by deserialization
+fn collect_unknown_fields(original: &Value, kept: &Value, path: &str, repairs: &mut Vec<String>) {
+    match (original, kept) {
+        (Value::Object(original), Value::Object(kept)) => {
+            for (key, value) in original {
+                let child = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                match kept.get(key) {
+                    Some(kept_value) => collect_unknown_fields(value, kept_value, &child, repairs),
+                    None if value.is_null() => {}
+                    None => repairs.push(format!("Removed unknown field {}", child)),
+                }
+            }
+        }
+        (Value::Array(original), Value::Array(kept)) => {
+            for (i, (value, kept_value)) in original.iter().zip(kept).enumerate() {
+                collect_unknown_fields(value, kept_value, &format!("{}[{}]", path, i), repairs);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Amount, Contract, ContractConfig};
+
+    #[test]
+    fn test_guard_repairs_hallucinated_fields() {
+        let engine = LLMOEngine::new();
+        let ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+        let mut value = serde_json::to_value(&ucl).unwrap();
+        value["payment"]["discount_code"] = "FREE".into();
+        value["summary"]["title"] = "".into();
+        let raw = format!("```json\n{}\n```", value);
+
+        let guarded = engine.guard_output(&raw).unwrap();
+        assert!(guarded
+            .repairs
+            .iter()
+            .any(|r| r == "Removed unknown field payment.discount_code"));
+        assert!(!guarded.ucl.summary.title.is_empty());
+    }
+
+    #[test]
+    fn test_guard_rejects_invalid_output() {
+        let engine = LLMOEngine::new();
+        assert!(matches!(
+            engine.guard_output("not json"),
+            Err(LlmOutputError::InvalidJson(_))
+        ));
+        assert!(matches!(
+            engine.guard_output("{\"contract_id\": \"x\"}"),
+            Err(LlmOutputError::SchemaMismatch(_))
+        ));
+
+        let mut ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+        ucl.payment.amount = Amount::from(-1);
+        let raw = serde_json::to_string(&ucl).unwrap();
+        assert!(matches!(
+            engine.guard_output(&raw),
+            Err(LlmOutputError::Invalid(_))
+        ));
+    }
+}
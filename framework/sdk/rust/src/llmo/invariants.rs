@@ -0,0 +1,214 @@
+//! Invariant and property generation for formal checking
+
+use super::LLMOEngine;
+use crate::{PaymentFrequency, Result, UCLContract};
+use serde::{Deserialize, Serialize};
+
+/// Output format for generated invariants
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum InvariantFormat {
+    /// Foundry invariant test suite (`forge test`)
+    Foundry,
+    /// SMT-LIB v2 script; every assertion block is expected to be `unsat`
+    Smtlib,
+}
+
+/// A machine-checkable property of a generated contract
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Invariant {
+    pub id: String,
+    pub description: String,
+    /// Solidity boolean expression evaluated in the invariant test
+    pub solidity: String,
+    /// SMT-LIB assertions whose negation must be unsatisfiable
+    pub smt: String,
+}
+
+impl LLMOEngine {
+    /// Generate properties the compiled contract must always satisfy
+    pub fn generate_invariants(&self, ucl: &UCLContract) -> Result<Vec<Invariant>> {
+        let amount = Self::solidity_amount(ucl)?;
+        let mut invariants = vec![
+            Invariant {
+                id: "payment_amount_constant".to_string(),
+                description: format!(
+                    "Payment amount always equals the contracted {} {}",
+                    ucl.payment.amount, ucl.payment.token
+                ),
+                solidity: format!("target.paymentAmount() == {}", amount),
+                smt: "(declare-const amount Int)\n(declare-const stored Int)\n\
+                      (assert (= stored amount))\n(assert (not (= stored amount)))"
+                    .to_string(),
+            },
+            Invariant {
+                id: "owner_immutable".to_string(),
+                description: "Contract owner never changes after deployment".to_string(),
+                solidity: "target.owner() == deployer".to_string(),
+                smt: "(declare-const owner0 Int)\n(declare-const owner Int)\n\
+                      (assert (= owner owner0))\n(assert (not (= owner owner0)))"
+                    .to_string(),
+            },
+            Invariant {
+                id: "balance_matches_payments".to_string(),
+                description: "Contract balance equals the sum of executed payments".to_string(),
+                solidity: "address(target).balance == handler.ghostTotalPaid()".to_string(),
+                smt: "(declare-const balance Int)\n(declare-const paid Int)\n\
+                      (assert (= balance paid))\n(assert (not (= balance paid)))"
+                    .to_string(),
+            },
+        ];
+
+        match period_seconds(&ucl.payment.frequency) {
+            Some(period) => invariants.push(Invariant {
+                id: "paid_within_elapsed_periods".to_string(),
+                description: format!(
+                    "Total paid never exceeds amount x periods elapsed ({} billing)",
+                    ucl.payment.frequency
+                ),
+                solidity: format!(
+                    "handler.ghostTotalPaid() <= target.paymentAmount() * \
+                     ((block.timestamp - handler.start()) / {} + 1)",
+                    period
+                ),
+                smt: format!(
+                    "(declare-const amount Int)\n(declare-const paid Int)\n(declare-const elapsed Int)\n\
+                     (assert (>= amount 0))\n(assert (>= elapsed 0))\n\
+                     (assert (<= paid (* amount (+ (div elapsed {}) 1))))\n\
+                     (assert (not (<= paid (* amount (+ (div elapsed {}) 1)))))",
+                    period, period
+                ),
+            }),
+            None => invariants.push(Invariant {
+                id: "single_payment".to_string(),
+                description: "A one-time contract is paid at most once".to_string(),
+                solidity: "handler.ghostPayments() <= 1".to_string(),
+                smt: "(declare-const payments Int)\n\
+                      (assert (<= payments 1))\n(assert (not (<= payments 1)))"
+                    .to_string(),
+            }),
+        }
+
+        Ok(invariants)
+    }
+
+    /// Render the contract's invariants in the requested format
+    pub fn render_invariants(&self, ucl: &UCLContract, format: InvariantFormat) -> Result<String> {
+        let invariants = self.generate_invariants(ucl)?;
+        Ok(match format {
+            InvariantFormat::Foundry => render_foundry(&invariants),
+            InvariantFormat::Smtlib => render_smtlib(ucl, &invariants),
+        })
+    }
+}
+
+/// Billing period in seconds for recurring frequencies
+fn period_seconds(frequency: &PaymentFrequency) -> Option<u64> {
+    match frequency {
+        PaymentFrequency::Daily => Some(86_400),
+        PaymentFrequency::Weekly => Some(7 * 86_400),
+        PaymentFrequency::Monthly { .. } => Some(30 * 86_400),
+        PaymentFrequency::Quarterly => Some(91 * 86_400),
+        PaymentFrequency::Yearly => Some(365 * 86_400),
+        _ => None,
+    }
+}
+
+fn render_foundry(invariants: &[Invariant]) -> String {
+    let mut tests = String::new();
+    for invariant in invariants {
+        tests.push_str(&format!(
+            "\n    /// {}\n    function invariant_{}() public view {{\n        assertTrue({});\n    }}\n",
+            invariant.description, invariant.id, invariant.solidity
+        ));
+    }
+
+    format!(
+        r#"// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.0;
+
+import "forge-std/Test.sol";
+import "../src/Smart402Contract.sol";
+
+contract Smart402Handler is Test {{
+    Smart402Contract public target;
+    uint256 public start;
+    uint256 public ghostTotalPaid;
+    uint256 public ghostPayments;
+
+    constructor(Smart402Contract _target) {{
+        target = _target;
+        start = block.timestamp;
+    }}
+
+    function executePayment(uint256 warp) public {{
+        vm.warp(block.timestamp + bound(warp, 0, 400 days));
+        uint256 amount = target.paymentAmount();
+        vm.deal(address(this), amount);
+        target.executePayment{{value: amount}}();
+        ghostTotalPaid += amount;
+        ghostPayments += 1;
+    }}
+}}
+
+contract Smart402InvariantTest is Test {{
+    Smart402Contract internal target;
+    Smart402Handler internal handler;
+    address internal deployer;
+
+    function setUp() public {{
+        deployer = address(this);
+        target = new Smart402Contract(address(0));
+        handler = new Smart402Handler(target);
+        targetContract(address(handler));
+    }}
+{}}}
+"#,
+        tests
+    )
+}
+
+fn render_smtlib(ucl: &UCLContract, invariants: &[Invariant]) -> String {
+    let mut script = format!(
+        "; Invariants for {}\n; Each block must report unsat\n(set-logic ALL)\n",
+        ucl.contract_id
+    );
+    for invariant in invariants {
+        script.push_str(&format!(
+            "\n; {}: {}\n(push 1)\n{}\n(check-sat)\n(pop 1)\n",
+            invariant.id, invariant.description, invariant.smt
+        ));
+    }
+    script
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Contract, ContractConfig};
+
+    #[test]
+    fn test_recurring_contract_invariants() {
+        let engine = LLMOEngine::new();
+        let mut ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+        ucl.payment.frequency = crate::PaymentFrequency::Monthly { day: None };
+
+        let invariants = engine.generate_invariants(&ucl).unwrap();
+        assert!(invariants
+            .iter()
+            .any(|i| i.id == "paid_within_elapsed_periods"));
+
+        let foundry = engine
+            .render_invariants(&ucl, InvariantFormat::Foundry)
+            .unwrap();
+        assert!(foundry.contains("function invariant_paid_within_elapsed_periods()"));
+
+        let smt = engine
+            .render_invariants(&ucl, InvariantFormat::Smtlib)
+            .unwrap();
+        assert_eq!(smt.matches("(check-sat)").count(), invariants.len());
+    }
+}
@@ -0,0 +1,461 @@
+//! UCL linter with configurable rule set
+
+use super::LLMOEngine;
+use crate::core::escalation::ESCALATION_ACTIONS;
+use crate::UCLContract;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Lint rule identifiers
+pub const RULE_SUMMARY_LENGTH: &str = "summary-length";
+pub const RULE_TITLE_LENGTH: &str = "title-length";
+pub const RULE_CONDITION_NAMING: &str = "condition-naming";
+pub const RULE_CONDITION_DESCRIPTION: &str = "condition-description";
+pub const RULE_ORACLE_REFRESH_RATE: &str = "oracle-refresh-rate";
+pub const RULE_UNKNOWN_CONDITION_SOURCE: &str = "unknown-condition-source";
+pub const RULE_CONDITION_EXPRESSION: &str = "condition-expression";
+pub const RULE_EMPTY_RULE_ACTIONS: &str = "empty-rule-actions";
+pub const RULE_ESCALATION_STEP: &str = "escalation-step";
+
+/// All lint rules known to the linter
+pub const ALL_RULES: &[&str] = &[
+    RULE_SUMMARY_LENGTH,
+    RULE_TITLE_LENGTH,
+    RULE_CONDITION_NAMING,
+    RULE_CONDITION_DESCRIPTION,
+    RULE_ORACLE_REFRESH_RATE,
+    RULE_UNKNOWN_CONDITION_SOURCE,
+    RULE_CONDITION_EXPRESSION,
+    RULE_EMPTY_RULE_ACTIONS,
+    RULE_ESCALATION_STEP,
+];
+
+/// Linter configuration
+///
+/// Rules in `deny` are reported as errors, rules in `allow` are skipped and
+/// every other rule is reported as a warning.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct LintConfig {
+    #[serde(default)]
+    pub allow: HashSet<String>,
+    #[serde(default)]
+    pub deny: HashSet<String>,
+    #[serde(default = "default_min_summary_len")]
+    pub min_summary_len: usize,
+    #[serde(default = "default_max_summary_len")]
+    pub max_summary_len: usize,
+    #[serde(default = "default_max_title_len")]
+    pub max_title_len: usize,
+    /// Shortest oracle refresh interval considered reasonable, in seconds
+    #[serde(default = "default_min_refresh_secs")]
+    pub min_refresh_secs: u64,
+}
+
+fn default_min_summary_len() -> usize {
+    50
+}
+
+fn default_max_summary_len() -> usize {
+    500
+}
+
+fn default_max_title_len() -> usize {
+    80
+}
+
+fn default_min_refresh_secs() -> u64 {
+    60
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            allow: HashSet::new(),
+            deny: HashSet::new(),
+            min_summary_len: default_min_summary_len(),
+            max_summary_len: default_max_summary_len(),
+            max_title_len: default_max_title_len(),
+            min_refresh_secs: default_min_refresh_secs(),
+        }
+    }
+}
+
+impl LintConfig {
+    /// Skip a rule
+    pub fn allow(mut self, rule: &str) -> Self {
+        self.deny.remove(rule);
+        self.allow.insert(rule.to_string());
+        self
+    }
+
+    /// Report a rule as an error
+    pub fn deny(mut self, rule: &str) -> Self {
+        self.allow.remove(rule);
+        self.deny.insert(rule.to_string());
+        self
+    }
+
+    fn level(&self, rule: &str) -> Option<LintLevel> {
+        if self.allow.contains(rule) {
+            None
+        } else if self.deny.contains(rule) {
+            Some(LintLevel::Error)
+        } else {
+            Some(LintLevel::Warning)
+        }
+    }
+}
+
+/// Severity of a lint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum LintLevel {
+    Warning,
+    Error,
+}
+
+/// A style issue found by the linter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Lint {
+    pub rule: String,
+    pub level: LintLevel,
+    /// Dotted path of the offending field
+    pub field: String,
+    pub message: String,
+    pub hint: String,
+}
+
+impl LLMOEngine {
+    /// Check a contract against opinionated style rules
+    ///
+    /// Unlike `validate`, lints never make a contract invalid on their own;
+    /// callers decide what to do with denied rules.
+    pub fn lint(&self, ucl: &UCLContract, config: &LintConfig) -> Vec<Lint> {
+        let mut lints = Vec::new();
+        let mut report = |rule: &str, field: String, message: String, hint: String| {
+            if let Some(level) = config.level(rule) {
+                lints.push(Lint {
+                    rule: rule.to_string(),
+                    level,
+                    field,
+                    message,
+                    hint,
+                });
+            }
+        };
+
+        let summary_len = ucl.summary.plain_english.chars().count();
+        if summary_len < config.min_summary_len {
+            report(
+                RULE_SUMMARY_LENGTH,
+                "summary.plain_english".to_string(),
+                format!("Summary is {} characters long", summary_len),
+                format!(
+                    "Describe who pays whom, how much and when in at least {} characters",
+                    config.min_summary_len
+                ),
+            );
+        } else if summary_len > config.max_summary_len {
+            report(
+                RULE_SUMMARY_LENGTH,
+                "summary.plain_english".to_string(),
+                format!("Summary is {} characters long", summary_len),
+                format!(
+                    "Keep the summary under {} characters; move details into conditions",
+                    config.max_summary_len
+                ),
+            );
+        }
+
+        if ucl.summary.title.chars().count() > config.max_title_len {
+            report(
+                RULE_TITLE_LENGTH,
+                "summary.title".to_string(),
+                "Title is too long".to_string(),
+                format!("Shorten the title to {} characters", config.max_title_len),
+            );
+        }
+
+        let conditions = ucl
+            .conditions
+            .required
+            .iter()
+            .map(|c| ("required", c))
+            .chain(
+                ucl.conditions
+                    .optional
+                    .iter()
+                    .flatten()
+                    .map(|c| ("optional", c)),
+            );
+        let mut indices = (0usize, 0usize);
+        for (group, condition) in conditions {
+            let index = if group == "required" {
+                &mut indices.0
+            } else {
+                &mut indices.1
+            };
+            let field = format!("conditions.{}[{}]", group, *index);
+            *index += 1;
+
+            if !is_snake_case(&condition.id) {
+                report(
+                    RULE_CONDITION_NAMING,
+                    format!("{}.id", field),
+                    format!("Condition id \"{}\" is not snake_case", condition.id),
+                    format!("Rename to \"{}\"", to_snake_case(&condition.id)),
+                );
+            }
+
+            if condition.description.trim().is_empty() {
+                report(
+                    RULE_CONDITION_DESCRIPTION,
+                    format!("{}.description", field),
+                    format!("Condition \"{}\" has no description", condition.id),
+                    "Describe the condition in plain English".to_string(),
+                );
+            }
+
+            if let Some(expression) = &condition.expression {
+                match crate::Expression::parse(expression) {
+                    Err(e) => report(
+                        RULE_CONDITION_EXPRESSION,
+                        format!("{}.expression", field),
+                        format!(
+                            "Condition \"{}\" has an invalid expression: {}",
+                            condition.id, e
+                        ),
+                        "Fix the expression syntax".to_string(),
+                    ),
+                    Ok(expression) => {
+                        for id in expression.identifiers() {
+                            if !ucl.oracles.is_empty() && !ucl.oracles.iter().any(|o| o.id == id) {
+                                report(
+                                    RULE_CONDITION_EXPRESSION,
+                                    format!("{}.expression", field),
+                                    format!(
+                                        "Condition \"{}\" reads from undeclared oracle \"{}\"",
+                                        condition.id, id
+                                    ),
+                                    "Declare the oracle in the oracles section".to_string(),
+                                );
+                            }
+                        }
+                    }
+                }
+            } else if !ucl.oracles.is_empty()
+                && !ucl.oracles.iter().any(|o| o.id == condition.source)
+            {
+                report(
+                    RULE_UNKNOWN_CONDITION_SOURCE,
+                    format!("{}.source", field),
+                    format!(
+                        "Condition \"{}\" reads from undeclared source \"{}\"",
+                        condition.id, condition.source
+                    ),
+                    "Declare the source in the oracles section".to_string(),
+                );
+            }
+        }
+
+        for (i, oracle) in ucl.oracles.iter().enumerate() {
+            match parse_interval_secs(&oracle.refresh_rate) {
+                Some(secs) if secs < config.min_refresh_secs => report(
+                    RULE_ORACLE_REFRESH_RATE,
+                    format!("oracles[{}].refresh_rate", i),
+                    format!("Oracle \"{}\" refreshes every {}s", oracle.id, secs),
+                    format!(
+                        "Use a refresh rate of at least {}s to avoid rate limits",
+                        config.min_refresh_secs
+                    ),
+                ),
+                Some(_) => {}
+                None => report(
+                    RULE_ORACLE_REFRESH_RATE,
+                    format!("oracles[{}].refresh_rate", i),
+                    format!(
+                        "Oracle \"{}\" has unrecognized refresh rate \"{}\"",
+                        oracle.id, oracle.refresh_rate
+                    ),
+                    "Use a duration such as \"30s\", \"5m\", \"1h\" or \"hourly\"".to_string(),
+                ),
+            }
+        }
+
+        for (i, rule) in ucl.rules.iter().enumerate() {
+            if rule.actions.is_empty() {
+                report(
+                    RULE_EMPTY_RULE_ACTIONS,
+                    format!("rules[{}].actions", i),
+                    format!("Rule \"{}\" has no actions", rule.rule_id),
+                    "Add an action or remove the rule".to_string(),
+                );
+            }
+        }
+
+        let steps = ucl.escalation.iter().flat_map(|policy| &policy.steps);
+        for (i, step) in steps.enumerate() {
+            let field = format!("escalation.steps[{}]", i);
+            if !ESCALATION_ACTIONS.contains(&step.action.as_str()) {
+                report(
+                    RULE_ESCALATION_STEP,
+                    format!("{}.action", field),
+                    format!("Escalation step has unknown action \"{}\"", step.action),
+                    format!("Use one of: {}", ESCALATION_ACTIONS.join(", ")),
+                );
+            }
+            if let Some(after) = step
+                .after
+                .as_ref()
+                .filter(|after| parse_interval_secs(after).is_none())
+            {
+                report(
+                    RULE_ESCALATION_STEP,
+                    format!("{}.after", field),
+                    format!(
+                        "Escalation step waits for unrecognized duration \"{}\"",
+                        after
+                    ),
+                    "Use a duration such as \"30m\" or \"24h\"; the step never falls due"
+                        .to_string(),
+                );
+            }
+        }
+
+        lints
+    }
+}
+
+fn is_snake_case(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        && !id.starts_with('_')
+        && !id.ends_with('_')
+}
+
+fn to_snake_case(id: &str) -> String {
+    let mut out = String::new();
+    for (i, c) in id.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i > 0 && !out.ends_with('_') {
+                out.push('_');
+            }
+            out.push(c.to_ascii_lowercase());
+        } else if c.is_ascii_alphanumeric() {
+            out.push(c);
+        } else if !out.ends_with('_') {
+            out.push('_');
+        }
+    }
+    out.trim_matches('_').to_string()
+}
+
+/// Parse refresh rates like "30s", "5m", "1h", "1d" or "hourly"
+pub(crate) fn parse_interval_secs(rate: &str) -> Option<u64> {
+    let rate = rate.trim().to_lowercase();
+    match rate.as_str() {
+        "realtime" | "real-time" => return Some(0),
+        "minutely" => return Some(60),
+        "hourly" => return Some(3_600),
+        "daily" => return Some(86_400),
+        "weekly" => return Some(7 * 86_400),
+        _ => {}
+    }
+
+    let split = rate.find(|c: char| !c.is_ascii_digit())?;
+    let (value, unit) = rate.split_at(split);
+    let value: u64 = value.parse().ok()?;
+    let multiplier = match unit.trim() {
+        "s" | "sec" | "secs" | "second" | "seconds" => 1,
+        "m" | "min" | "mins" | "minute" | "minutes" => 60,
+        "h" | "hr" | "hrs" | "hour" | "hours" => 3_600,
+        "d" | "day" | "days" => 86_400,
+        _ => return None,
+    };
+    Some(value * multiplier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ConditionDefinition, OracleDefinition};
+    use crate::{Contract, ContractConfig};
+
+    #[test]
+    fn test_lint_rules_and_config() {
+        let engine = LLMOEngine::new();
+        let mut ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+        ucl.oracles.push(OracleDefinition {
+            id: "uptime_oracle".to_string(),
+            oracle_type: "uptime".to_string(),
+            refresh_rate: "10s".to_string(),
+            required: true,
+            ..Default::default()
+        });
+        ucl.conditions.required.push(ConditionDefinition {
+            id: "UptimeCheck".to_string(),
+            description: "Uptime above 99%".to_string(),
+            source: "uptime_oracle".to_string(),
+            operator: "greater_than".to_string(),
+            threshold: Some(serde_json::json!(0.99)),
+            expression: None,
+            temporal: None,
+        });
+        ucl.conditions.required.push(ConditionDefinition {
+            id: "few_incidents".to_string(),
+            description: "Few incidents".to_string(),
+            source: String::new(),
+            operator: String::new(),
+            threshold: None,
+            expression: Some("uptime_oracle > 0.99 && incidents < 3".to_string()),
+            temporal: None,
+        });
+
+        ucl.escalation = Some(crate::types::EscalationPolicy {
+            steps: vec![crate::types::EscalationStep {
+                after: Some("a while".to_string()),
+                action: "pause_contract".to_string(),
+                ..Default::default()
+            }],
+        });
+
+        let lints = engine.lint(&ucl, &LintConfig::default());
+        assert!(lints
+            .iter()
+            .any(|l| l.rule == RULE_ESCALATION_STEP && l.field == "escalation.steps[0].after"));
+        let expression: Vec<_> = lints
+            .iter()
+            .filter(|l| l.field == "conditions.required[1].expression")
+            .collect();
+        assert_eq!(expression.len(), 1);
+        assert!(expression[0].message.contains("\"incidents\""));
+        let naming = lints
+            .iter()
+            .find(|l| l.rule == RULE_CONDITION_NAMING)
+            .unwrap();
+        assert_eq!(naming.hint, "Rename to \"uptime_check\"");
+        assert!(lints.iter().any(|l| l.rule == RULE_ORACLE_REFRESH_RATE));
+
+        let config = LintConfig::default()
+            .allow(RULE_SUMMARY_LENGTH)
+            .deny(RULE_CONDITION_NAMING);
+        let lints = engine.lint(&ucl, &config);
+        assert!(!lints.iter().any(|l| l.rule == RULE_SUMMARY_LENGTH));
+        assert!(lints
+            .iter()
+            .any(|l| l.rule == RULE_CONDITION_NAMING && l.level == LintLevel::Error));
+    }
+
+    #[test]
+    fn test_parse_interval() {
+        assert_eq!(parse_interval_secs("5m"), Some(300));
+        assert_eq!(parse_interval_secs("hourly"), Some(3_600));
+        assert_eq!(parse_interval_secs("soon"), None);
+    }
+}
@@ -1,5 +1,26 @@
 //! LLMO (Large Language Model Optimization) module
 
 pub mod engine;
+pub mod compliance;
+pub mod fixes;
+pub mod glossary;
+pub mod guard;
+pub mod invariants;
+pub mod lint;
+pub mod project;
+pub mod prompts;
+pub mod qa;
+pub mod solc;
+mod changes;
 
-pub use engine::LLMOEngine;
+pub use engine::{CompileOptions, LLMOEngine};
+pub use compliance::{ComplianceFinding, ComplianceReport, Jurisdiction};
+pub use fixes::FixSuggestion;
+pub use glossary::GlossaryEntry;
+pub use guard::{GuardedOutput, LlmOutputError};
+pub use invariants::{Invariant, InvariantFormat};
+pub use lint::{Lint, LintConfig, LintLevel};
+pub use project::ProjectManifest;
+pub use prompts::{PromptRegistry, TemplateError};
+pub use qa::{Answer, Citation};
+pub use solc::CompiledArtifact;
@@ -0,0 +1,203 @@
+//! Project scaffolds for compiled contracts
+
+use super::LLMOEngine;
+use crate::{Result, UCLContract};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Files written by `compile_project`
+#[derive(Debug, Clone)]
+pub struct ProjectManifest {
+    pub root: PathBuf,
+    pub target: String,
+    pub files: Vec<PathBuf>,
+}
+
+impl LLMOEngine {
+    /// Compile UCL into a full buildable project under `out_dir`
+    ///
+    /// - `solidity`: Foundry layout with a forge test for the payment path
+    /// - `rust`: Cargo library crate with an integration test
+    /// - `javascript`: npm package with a `node --test` suite
+    pub fn compile_project(
+        &self,
+        ucl: &UCLContract,
+        target: &str,
+        out_dir: &Path,
+    ) -> Result<ProjectManifest> {
+        let source = self.compile(ucl, target)?;
+        let files: Vec<(&str, String)> = match target {
+            "solidity" => vec![
+                ("foundry.toml", FOUNDRY_TOML.to_string()),
+                ("src/Smart402Contract.sol", source),
+                ("test/Smart402Contract.t.sol", FOUNDRY_TEST.to_string()),
+                (
+                    "README.md",
+                    readme(ucl, "forge install foundry-rs/forge-std\nforge test"),
+                ),
+            ],
+            "rust" => vec![
+                ("Cargo.toml", CARGO_TOML.to_string()),
+                ("src/lib.rs", format!("{}\n{}", RUST_PRELUDE, source)),
+                ("tests/payment.rs", RUST_TEST.to_string()),
+                ("README.md", readme(ucl, "cargo test")),
+            ],
+            "javascript" => vec![
+                ("package.json", PACKAGE_JSON.to_string()),
+                ("src/index.js", source),
+                ("test/payment.test.js", JS_TEST.to_string()),
+                ("README.md", readme(ucl, "npm test")),
+            ],
+            _ => unreachable!("compile() rejects unsupported targets"),
+        };
+
+        let mut written = Vec::with_capacity(files.len());
+        for (relative, content) in files {
+            let path = out_dir.join(relative);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, content)?;
+            written.push(path);
+        }
+
+        Ok(ProjectManifest {
+            root: out_dir.to_path_buf(),
+            target: target.to_string(),
+            files: written,
+        })
+    }
+}
+
+fn readme(ucl: &UCLContract, commands: &str) -> String {
+    format!(
+        "# {}\n\n{}\n\nGenerated by Smart402 from contract `{}`.\n\n## Build and test\n\n```sh\n{}\n```\n",
+        ucl.summary.title, ucl.summary.plain_english, ucl.contract_id, commands
+    )
+}
+
+const FOUNDRY_TOML: &str = r#"[profile.default]
+src = "src"
+out = "out"
+libs = ["lib"]
+solc_version = "0.8.20"
+"#;
+
+const FOUNDRY_TEST: &str = r#"// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.0;
+
+import "forge-std/Test.sol";
+import "../src/Smart402Contract.sol";
+
+contract Smart402ContractTest is Test {
+    Smart402Contract internal target;
+
+    function setUp() public {
+        target = new Smart402Contract(address(0));
+    }
+
+    function testExecutePaymentWithFullAmount() public {
+        uint256 amount = target.paymentAmount();
+        vm.deal(address(this), amount);
+        target.executePayment{value: amount}();
+        assertEq(address(target).balance, amount);
+    }
+
+    function testExecutePaymentRejectsUnderpayment() public {
+        uint256 amount = target.paymentAmount();
+        if (amount == 0) return;
+        vm.deal(address(this), amount);
+        vm.expectRevert(bytes("Insufficient payment"));
+        target.executePayment{value: amount - 1}();
+    }
+}
+"#;
+
+const CARGO_TOML: &str = r#"[package]
+name = "smart402-contract"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+
+[dev-dependencies]
+tokio = { version = "1", features = ["macros", "rt"] }
+"#;
+
+const RUST_PRELUDE: &str = r#"/// Result of a payment execution
+#[derive(Debug, Clone)]
+pub struct PaymentResult {
+    pub success: bool,
+    pub amount: u128,
+    pub token: String,
+}
+
+pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+impl Default for Smart402Contract {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+"#;
+
+const RUST_TEST: &str = r#"use smart402_contract::Smart402Contract;
+
+#[tokio::test]
+async fn executes_payment_for_contract_amount() {
+    let contract = Smart402Contract::new();
+    let result = contract.execute_payment().await.unwrap();
+
+    assert!(result.success);
+    assert_eq!(result.amount, contract.payment_amount);
+    assert_eq!(result.token, contract.payment_token);
+}
+"#;
+
+const PACKAGE_JSON: &str = r#"{
+  "name": "smart402-contract",
+  "version": "0.1.0",
+  "private": true,
+  "main": "src/index.js",
+  "scripts": {
+    "test": "node --test test/"
+  }
+}
+"#;
+
+const JS_TEST: &str = r#"const test = require('node:test');
+const assert = require('node:assert');
+const Smart402Contract = require('../src/index.js');
+
+test('executes payment for contract amount', async () => {
+  const contract = new Smart402Contract();
+  const result = await contract.executePayment();
+
+  assert.strictEqual(result.success, true);
+  assert.strictEqual(result.amount, contract.paymentAmount);
+  assert.strictEqual(result.token, contract.paymentToken);
+});
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Contract, ContractConfig};
+
+    #[test]
+    fn test_compile_rust_project() {
+        let engine = LLMOEngine::new();
+        let ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+        let dir = std::env::temp_dir().join(format!("smart402-project-{}", std::process::id()));
+
+        let manifest = engine.compile_project(&ucl, "rust", &dir).unwrap();
+        assert!(dir.join("Cargo.toml").exists());
+        assert!(dir.join("tests/payment.rs").exists());
+        assert_eq!(manifest.files.len(), 4);
+
+        assert!(engine.compile_project(&ucl, "cobol", &dir).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
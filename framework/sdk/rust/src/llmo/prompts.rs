@@ -0,0 +1,211 @@
+//! Prompt templates for LLM-backed LLMO features
+
+use crate::{Result, UCLContract};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+const PARSE_PROMPT: &str = "You convert natural-language agreements into UCL contracts.
+Respond with a single JSON object matching the UCL-1.0 schema and nothing else.
+
+Agreement:
+{{input}}
+";
+
+const EXPLAIN_PROMPT: &str = "Explain the following contract to a non-technical signatory.
+Only use facts stated in the contract.
+
+Contract:
+{{contract}}
+";
+
+const RISK_PROMPT: &str = "Review the following contract and list risks for each party
+(payment, oracle availability, termination). Only use facts stated in the contract.
+
+Contract:
+{{contract}}
+";
+
+const ASK_PROMPT: &str = "Answer the question using only the contract below and cite the
+fields you used. If the contract does not contain the answer, say so.
+
+Contract:
+{{contract}}
+
+Question: {{question}}
+";
+
+/// Why a template could not be rendered
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
+pub enum TemplateError {
+    #[error("No template named {template}")]
+    Unknown { template: String },
+
+    #[error("Unclosed placeholder in template {template}")]
+    UnclosedPlaceholder { template: String },
+
+    #[error("Missing value for placeholder {{{{{variable}}}}} in template {template}")]
+    MissingVariable { template: String, variable: String },
+}
+
+impl TemplateError {
+    /// Stable machine-readable code, e.g. `"template.missing_variable"`
+    pub fn code(&self) -> &'static str {
+        match self {
+            TemplateError::Unknown { .. } => "template.unknown",
+            TemplateError::UnclosedPlaceholder { .. } => "template.unclosed_placeholder",
+            TemplateError::MissingVariable { .. } => "template.missing_variable",
+        }
+    }
+}
+
+/// Registry of prompt templates keyed by feature name
+///
+/// Templates use `{{name}}` placeholders. Built-in prompts can be
+/// overridden programmatically or from a directory of `<feature>.txt` files,
+/// so the exact text sent to the LLM stays under the caller's control.
+#[derive(Debug, Clone)]
+pub struct PromptRegistry {
+    templates: HashMap<String, String>,
+}
+
+impl Default for PromptRegistry {
+    fn default() -> Self {
+        let mut templates = HashMap::new();
+        templates.insert("parse".to_string(), PARSE_PROMPT.to_string());
+        templates.insert("explain".to_string(), EXPLAIN_PROMPT.to_string());
+        templates.insert("risk".to_string(), RISK_PROMPT.to_string());
+        templates.insert("ask".to_string(), ASK_PROMPT.to_string());
+
+        Self { templates }
+    }
+}
+
+impl PromptRegistry {
+    /// Create registry with built-in prompts
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create registry with no prompts at all
+    pub fn empty() -> Self {
+        Self {
+            templates: HashMap::new(),
+        }
+    }
+
+    /// Load overrides from `<feature>.txt` / `<feature>.prompt` files in a directory
+    pub fn load_dir(mut self, dir: &Path) -> Result<Self> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let is_prompt = matches!(
+                path.extension().and_then(|e| e.to_str()),
+                Some("txt") | Some("prompt")
+            );
+            if !is_prompt {
+                continue;
+            }
+            if let Some(name) = path.file_stem().and_then(|s| s.to_str()) {
+                let template = fs::read_to_string(&path)?;
+                self.templates.insert(name.to_string(), template);
+            }
+        }
+        Ok(self)
+    }
+
+    /// Set the template for a feature
+    pub fn set(&mut self, feature: &str, template: impl Into<String>) -> &mut Self {
+        self.templates.insert(feature.to_string(), template.into());
+        self
+    }
+
+    /// Get the raw template for a feature
+    pub fn get(&self, feature: &str) -> Option<&str> {
+        self.templates.get(feature).map(String::as_str)
+    }
+
+    /// Render a feature's template, substituting `{{name}}` placeholders
+    ///
+    /// Fails if the feature has no template or a placeholder has no value.
+    pub fn render(&self, feature: &str, vars: &HashMap<&str, String>) -> Result<String> {
+        let template = self.get(feature).ok_or_else(|| TemplateError::Unknown {
+            template: feature.to_string(),
+        })?;
+
+        let mut rendered = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find("{{") {
+            let end = rest[start..].find("}}").ok_or_else(|| {
+                TemplateError::UnclosedPlaceholder {
+                    template: feature.to_string(),
+                }
+            })? + start;
+            let name = rest[start + 2..end].trim();
+            let value = vars.get(name).ok_or_else(|| TemplateError::MissingVariable {
+                template: feature.to_string(),
+                variable: name.to_string(),
+            })?;
+            rendered.push_str(&rest[..start]);
+            rendered.push_str(value);
+            rest = &rest[end + 2..];
+        }
+        rendered.push_str(rest);
+
+        Ok(rendered)
+    }
+
+    /// Render a feature's template for a contract
+    ///
+    /// Provides `contract` (pretty JSON), `title` and `contract_id`, plus any
+    /// `extra` values.
+    pub fn render_for_contract(
+        &self,
+        feature: &str,
+        ucl: &UCLContract,
+        extra: &[(&str, String)],
+    ) -> Result<String> {
+        let mut vars: HashMap<&str, String> = HashMap::new();
+        vars.insert("contract", serde_json::to_string_pretty(ucl)?);
+        vars.insert("title", ucl.summary.title.clone());
+        vars.insert("contract_id", ucl.contract_id.clone());
+        for (name, value) in extra {
+            vars.insert(name, value.clone());
+        }
+        self.render(feature, &vars)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_override_and_render() {
+        let mut registry = PromptRegistry::new();
+        registry.set("explain", "Explain {{ title }} briefly.");
+
+        let mut vars = HashMap::new();
+        vars.insert("title", "SLA".to_string());
+        assert_eq!(
+            registry.render("explain", &vars).unwrap(),
+            "Explain SLA briefly."
+        );
+        let Err(crate::Error::Template(missing)) = registry.render("risk", &vars) else {
+            panic!("expected a template error");
+        };
+        assert_eq!(
+            missing,
+            TemplateError::MissingVariable {
+                template: "risk".to_string(),
+                variable: "contract".to_string(),
+            }
+        );
+        assert_eq!(
+            missing.to_string(),
+            "Missing value for placeholder {{contract}} in template risk"
+        );
+        let unknown = registry.render("unknown", &vars).unwrap_err();
+        assert_eq!(unknown.code(), "template.unknown");
+    }
+}
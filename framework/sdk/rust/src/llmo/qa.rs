@@ -0,0 +1,275 @@
+//! Question answering grounded in contract content
+
+use super::LLMOEngine;
+use crate::{PaymentFrequency, UCLContract};
+use chrono::{Datelike, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A contract field an answer relies on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Citation {
+    /// Dotted path of the field (e.g. "payment.amount")
+    pub field: String,
+    /// Field value as it appears in the contract
+    pub value: String,
+}
+
+/// Answer to a question about a contract
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Answer {
+    pub text: String,
+    pub citations: Vec<Citation>,
+    /// False when the contract does not contain the information asked for
+    pub grounded: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Topic {
+    NextCharge,
+    Price,
+    Term,
+    Conditions,
+    Parties,
+    Settlement,
+}
+
+impl LLMOEngine {
+    /// Answer a question using only the content of the contract
+    ///
+    /// Every statement in the answer is backed by a citation to the UCL field
+    /// it came from; questions the contract cannot answer return an
+    /// ungrounded answer rather than a guess.
+    pub fn ask(&self, ucl: &UCLContract, question: &str) -> Answer {
+        let topics = classify(question);
+        if topics.is_empty() {
+            return Answer {
+                text: "This contract does not contain information to answer that question."
+                    .to_string(),
+                citations: Vec::new(),
+                grounded: false,
+            };
+        }
+
+        let mut sentences = Vec::new();
+        let mut citations = Vec::new();
+        let mut cite = |field: &str, value: &str| {
+            let citation = Citation {
+                field: field.to_string(),
+                value: value.to_string(),
+            };
+            if !citations.contains(&citation) {
+                citations.push(citation);
+            }
+        };
+
+        for topic in topics {
+            match topic {
+                Topic::NextCharge => {
+                    let payment = &ucl.payment;
+                    cite("payment.frequency", &payment.frequency.to_string());
+                    cite("metadata.dates.effective", &ucl.metadata.dates.effective.to_string());
+                    match next_charge(ucl.metadata.dates.effective, &payment.frequency) {
+                        Some(date) => sentences.push(format!(
+                            "You are charged {} {} {}; the next charge is due on {}.",
+                            payment.amount, payment.token, payment.frequency, date
+                        )),
+                        None => sentences.push(format!(
+                            "You are charged {} {} ({}), starting {}.",
+                            payment.amount,
+                            payment.token,
+                            payment.frequency,
+                            ucl.metadata.dates.effective
+                        )),
+                    }
+                    cite("payment.amount", &payment.amount.to_string());
+                    cite("payment.token", payment.token.symbol());
+                }
+                Topic::Price => {
+                    let payment = &ucl.payment;
+                    let fiat = payment
+                        .currency
+                        .map(|currency| format!(" ({} {})", payment.amount, currency))
+                        .unwrap_or_default();
+                    sentences.push(format!(
+                        "The contract amount is {} {}{}, billed {}.",
+                        payment.amount, payment.token, fiat, payment.frequency
+                    ));
+                    cite("payment.amount", &payment.amount.to_string());
+                    cite("payment.token", payment.token.symbol());
+                    if let Some(currency) = payment.currency {
+                        cite("payment.currency", currency.code());
+                    }
+                    cite("payment.frequency", &payment.frequency.to_string());
+                }
+                Topic::Term => {
+                    let dates = &ucl.metadata.dates;
+                    sentences.push(format!(
+                        "The contract starts on {}, runs for {} and renewal is {}.",
+                        dates.effective, dates.duration, dates.renewal
+                    ));
+                    cite("metadata.dates.effective", &dates.effective.to_string());
+                    cite("metadata.dates.duration", &dates.duration.to_string());
+                    cite("metadata.dates.renewal", &dates.renewal.to_string());
+                }
+                Topic::Conditions => {
+                    if ucl.conditions.required.is_empty() {
+                        sentences.push("The contract has no payment conditions.".to_string());
+                        cite("conditions.required", "[]");
+                    } else {
+                        let list: Vec<&str> = ucl
+                            .conditions
+                            .required
+                            .iter()
+                            .map(|c| c.description.as_str())
+                            .collect();
+                        sentences.push(format!(
+                            "Payment executes when all of these hold: {}.",
+                            list.join("; ")
+                        ));
+                        for (i, condition) in ucl.conditions.required.iter().enumerate() {
+                            cite(
+                                &format!("conditions.required[{}].description", i),
+                                &condition.description,
+                            );
+                        }
+                    }
+                }
+                Topic::Parties => {
+                    if ucl.metadata.parties.is_empty() {
+                        sentences.push("The contract does not list its parties.".to_string());
+                        cite("metadata.parties", "[]");
+                    } else {
+                        let list: Vec<String> = ucl
+                            .metadata
+                            .parties
+                            .iter()
+                            .map(|p| format!("{} ({})", p.identifier, p.role))
+                            .collect();
+                        sentences.push(format!("The parties are {}.", list.join(" and ")));
+                        for (i, party) in ucl.metadata.parties.iter().enumerate() {
+                            cite(
+                                &format!("metadata.parties[{}].identifier", i),
+                                &party.identifier,
+                            );
+                        }
+                    }
+                }
+                Topic::Settlement => {
+                    sentences.push(format!(
+                        "Payments settle in {} on the {} network.",
+                        ucl.payment.token, ucl.payment.blockchain
+                    ));
+                    cite("payment.token", ucl.payment.token.symbol());
+                    cite("payment.blockchain", ucl.payment.blockchain.name());
+                }
+            }
+        }
+
+        Answer {
+            text: sentences.join(" "),
+            citations,
+            grounded: true,
+        }
+    }
+}
+
+fn classify(question: &str) -> Vec<Topic> {
+    let q = question.to_lowercase();
+    let has = |words: &[&str]| words.iter().any(|w| q.contains(w));
+    let mut topics = Vec::new();
+
+    if has(&["charge", "bill", "next payment", "due"]) && has(&["when", "next", "date"]) {
+        topics.push(Topic::NextCharge);
+    } else if has(&["how much", "price", "cost", "amount", "fee", "pay"]) {
+        topics.push(Topic::Price);
+    }
+    if has(&[
+        "cancel", "renew", "end", "expire", "duration", "how long", "start",
+    ]) {
+        topics.push(Topic::Term);
+    }
+    if has(&["condition", "requirement", "uptime", "trigger", "release"]) {
+        topics.push(Topic::Conditions);
+    }
+    if has(&["who", "parties", "party", "vendor", "customer"]) {
+        topics.push(Topic::Parties);
+    }
+    if has(&[
+        "network",
+        "blockchain",
+        "chain",
+        "token",
+        "crypto",
+        "wallet",
+    ]) {
+        topics.push(Topic::Settlement);
+    }
+
+    topics
+}
+
+/// Next charge date after today for date-based recurring frequencies
+fn next_charge(start: NaiveDate, frequency: &PaymentFrequency) -> Option<NaiveDate> {
+    let today = Utc::now().date_naive();
+    if start > today {
+        return Some(start);
+    }
+
+    match frequency {
+        PaymentFrequency::Daily => today.succ_opt(),
+        PaymentFrequency::Weekly => {
+            let elapsed = (today - start).num_days();
+            Some(start + chrono::Duration::days((elapsed / 7 + 1) * 7))
+        }
+        PaymentFrequency::Monthly { day: None } => {
+            let months =
+                (today.year() - start.year()) * 12 + today.month() as i32 - start.month() as i32;
+            (months..months + 2)
+                .filter_map(|m| start.checked_add_months(chrono::Months::new(m as u32)))
+                .find(|d| *d > today)
+        }
+        PaymentFrequency::Yearly => (today.year() - start.year()
+            ..=today.year() - start.year() + 1)
+            .filter_map(|y| start.checked_add_months(chrono::Months::new(y as u32 * 12)))
+            .find(|d| *d > today),
+        PaymentFrequency::Monthly { day: Some(_) } | PaymentFrequency::Cron(_) => frequency
+            .next_occurrence(today.and_hms_opt(23, 59, 59)?.and_utc())
+            .map(|next| next.date_naive()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Contract, ContractConfig};
+
+    #[test]
+    fn test_ask_next_charge_cites_fields() {
+        let engine = LLMOEngine::new();
+        let mut ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+        ucl.payment.frequency = crate::PaymentFrequency::Monthly { day: None };
+
+        let answer = engine.ask(&ucl, "When will I be charged next?");
+        assert!(answer.grounded);
+        assert!(answer.text.contains("next charge is due on"));
+        assert!(answer
+            .citations
+            .iter()
+            .any(|c| c.field == "payment.frequency"));
+    }
+
+    #[test]
+    fn test_ask_unanswerable() {
+        let engine = LLMOEngine::new();
+        let ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+
+        let answer = engine.ask(&ucl, "What's the weather like?");
+        assert!(!answer.grounded);
+        assert!(answer.citations.is_empty());
+    }
+}
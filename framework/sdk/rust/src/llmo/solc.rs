@@ -0,0 +1,119 @@
+//! solc integration for compiled bytecode and ABI
+
+use super::LLMOEngine;
+use crate::{Result, UCLContract};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Name of the contract emitted by the Solidity generator
+const CONTRACT_NAME: &str = "Smart402Contract";
+
+/// Deployable output of the Solidity compiler
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompiledArtifact {
+    pub contract_name: String,
+    pub abi: serde_json::Value,
+    /// Hex-encoded creation bytecode, `0x`-prefixed
+    pub bytecode: String,
+    pub compiler_version: String,
+}
+
+impl LLMOEngine {
+    /// Compile UCL to Solidity and run it through `solc`
+    ///
+    /// Uses the `solc` binary on `PATH`, or the one named by the `SOLC`
+    /// environment variable. The returned artifact can be attached to a
+    /// contract with `Contract::attach_artifact` before deploying.
+    pub fn compile_artifact(&self, ucl: &UCLContract) -> Result<CompiledArtifact> {
+        let source = self.compile(ucl, "solidity")?;
+        let solc = std::env::var("SOLC").unwrap_or_else(|_| "solc".to_string());
+
+        let mut child = Command::new(&solc)
+            .args(["--combined-json", "abi,bin", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| {
+                crate::Error::CompilationError(format!(
+                    "Could not run {} ({}); install solc or set SOLC",
+                    solc, e
+                ))
+            })?;
+
+        child
+            .stdin
+            .take()
+            .expect("stdin is piped")
+            .write_all(source.as_bytes())?;
+        let output = child.wait_with_output()?;
+
+        if !output.status.success() {
+            return Err(crate::Error::CompilationError(format!(
+                "solc failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        parse_combined_json(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+/// Extract the generated contract from `solc --combined-json abi,bin` output
+fn parse_combined_json(output: &str) -> Result<CompiledArtifact> {
+    let json: serde_json::Value = serde_json::from_str(output)?;
+    let contracts = json["contracts"].as_object().ok_or_else(|| {
+        crate::Error::CompilationError("solc output has no contracts".to_string())
+    })?;
+
+    let (_, contract) = contracts
+        .iter()
+        .find(|(key, _)| key.rsplit(':').next() == Some(CONTRACT_NAME))
+        .ok_or_else(|| {
+            crate::Error::CompilationError(format!("{} missing from solc output", CONTRACT_NAME))
+        })?;
+
+    let bin = contract["bin"].as_str().unwrap_or_default();
+    if bin.is_empty() {
+        return Err(crate::Error::CompilationError(
+            "solc produced empty bytecode".to_string(),
+        ));
+    }
+
+    // Older solc versions emit the ABI as a JSON string
+    let abi = match &contract["abi"] {
+        serde_json::Value::String(s) => serde_json::from_str(s)?,
+        other => other.clone(),
+    };
+
+    Ok(CompiledArtifact {
+        contract_name: CONTRACT_NAME.to_string(),
+        abi,
+        bytecode: format!("0x{}", bin.trim_start_matches("0x")),
+        compiler_version: json["version"].as_str().unwrap_or_default().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_combined_json() {
+        let output = r#"{
+            "contracts": {
+                "<stdin>:Smart402Contract": {
+                    "abi": [{"type": "function", "name": "executePayment"}],
+                    "bin": "6080604052"
+                }
+            },
+            "version": "0.8.20+commit.a1b79de6"
+        }"#;
+
+        let artifact = parse_combined_json(output).unwrap();
+        assert_eq!(artifact.bytecode, "0x6080604052");
+        assert_eq!(artifact.abi[0]["name"], "executePayment");
+        assert_eq!(artifact.compiler_version, "0.8.20+commit.a1b79de6");
+    }
+}
@@ -0,0 +1,286 @@
+//! Money amounts
+//!
+//! Payment amounts are exact decimals rather than floats, so 0.1 USDC is
+//! exactly 100000 base units and sums of payments never drift.
+//!
+//! An [`Amount`] serializes as a number when the number reads back as the
+//! same amount, e.g. `99.5`, and as a string otherwise, e.g.
+//! `"0.123456789012345678"`. Both forms deserialize, so contract files
+//! written with float amounts keep loading.
+
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+/// Exact decimal amount of a token or currency
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Amount(Decimal);
+
+impl Amount {
+    pub const ZERO: Amount = Amount(Decimal::ZERO);
+
+    /// `units` scaled down by `scale` decimal places, e.g. `Amount::new(995, 1)`
+    /// is 99.5
+    pub fn new(units: i64, scale: u32) -> Self {
+        Self(Decimal::new(units, scale))
+    }
+
+    /// Amount written the way `value` prints, e.g. 0.1 for `0.1_f64`;
+    /// `None` for NaN, infinities and values out of range
+    pub fn from_f64(value: f64) -> Option<Self> {
+        if !value.is_finite() {
+            return None;
+        }
+        Decimal::from_str(&value.to_string()).ok().map(Self)
+    }
+
+    /// Nearest float, for metrics and other approximate uses
+    pub fn to_f64(self) -> f64 {
+        self.0.to_f64().unwrap_or(0.0)
+    }
+
+    /// Amount of `units` base units of a token with `decimals` decimals
+    pub fn from_base_units(units: u128, decimals: u32) -> Option<Self> {
+        let units = i128::try_from(units).ok()?;
+        Decimal::try_from_i128_with_scale(units, decimals)
+            .ok()
+            .map(|amount| Self(amount.normalize()))
+    }
+
+    /// Integer base units of a token with `decimals` decimals, rounding off
+    /// finer fractions; `None` for negative amounts or on overflow
+    pub fn to_base_units(self, decimals: u32) -> Option<u128> {
+        if self.is_sign_negative() {
+            return None;
+        }
+        let rounded = self.round_dp(decimals).0;
+        let units = u128::try_from(rounded.mantissa()).ok()?;
+        10u128
+            .checked_pow(decimals - rounded.scale())
+            .and_then(|factor| units.checked_mul(factor))
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    pub fn is_sign_negative(self) -> bool {
+        self.0.is_sign_negative() && !self.0.is_zero()
+    }
+
+    pub fn abs(self) -> Self {
+        Self(self.0.abs())
+    }
+
+    /// Round to `dp` decimal places, halves away from zero
+    pub fn round_dp(self, dp: u32) -> Self {
+        Self(
+            self.0
+                .round_dp_with_strategy(dp, RoundingStrategy::MidpointAwayFromZero),
+        )
+    }
+
+    /// `percent` percent of the amount, e.g. 1.5 for 1.5%
+    pub fn percent(self, percent: Amount) -> Self {
+        Self(self.0 * percent.0 / Decimal::ONE_HUNDRED)
+    }
+
+    /// Whether the amount reads back unchanged from its nearest float
+    fn exact_f64(self) -> Option<f64> {
+        let value = self.0.to_f64()?;
+        (Self::from_f64(value) == Some(self)).then_some(value)
+    }
+}
+
+impl fmt::Display for Amount {
+    /// Shortest form without trailing zeros; a precision rounds to that many
+    /// decimal places, e.g. `format!("{:.2}", amount)` for cents
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match f.precision() {
+            Some(dp) => fmt::Display::fmt(&self.round_dp(dp as u32).0, f),
+            None => fmt::Display::fmt(&self.0.normalize(), f),
+        }
+    }
+}
+
+impl FromStr for Amount {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> crate::Result<Self> {
+        let s = s.trim();
+        Decimal::from_str(s)
+            .or_else(|_| Decimal::from_scientific(s))
+            .map(Self)
+            .map_err(|_| crate::Error::ValidationError(format!("Invalid amount: {}", s)))
+    }
+}
+
+impl From<Decimal> for Amount {
+    fn from(amount: Decimal) -> Self {
+        Self(amount)
+    }
+}
+
+impl From<Amount> for Decimal {
+    fn from(amount: Amount) -> Self {
+        amount.0
+    }
+}
+
+macro_rules! from_integer {
+    ($($int:ty),*) => {
+        $(impl From<$int> for Amount {
+            fn from(amount: $int) -> Self {
+                Self(Decimal::from(amount))
+            }
+        })*
+    };
+}
+
+from_integer!(i32, i64, u32, u64);
+
+impl Add for Amount {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self(self.0 + other.0)
+    }
+}
+
+impl AddAssign for Amount {
+    fn add_assign(&mut self, other: Self) {
+        self.0 += other.0;
+    }
+}
+
+impl Sub for Amount {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self(self.0 - other.0)
+    }
+}
+
+impl SubAssign for Amount {
+    fn sub_assign(&mut self, other: Self) {
+        self.0 -= other.0;
+    }
+}
+
+impl Neg for Amount {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self(-self.0)
+    }
+}
+
+impl Mul<u32> for Amount {
+    type Output = Self;
+
+    fn mul(self, quantity: u32) -> Self {
+        Self(self.0 * Decimal::from(quantity))
+    }
+}
+
+impl Mul<u64> for Amount {
+    type Output = Self;
+
+    fn mul(self, quantity: u64) -> Self {
+        Self(self.0 * Decimal::from(quantity))
+    }
+}
+
+impl Sum for Amount {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::ZERO, Add::add)
+    }
+}
+
+impl<'a> Sum<&'a Amount> for Amount {
+    fn sum<I: Iterator<Item = &'a Amount>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
+
+impl Serialize for Amount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.exact_f64() {
+            Some(value) => serializer.serialize_f64(value),
+            None => serializer.collect_str(self),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Amount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(AmountVisitor)
+    }
+}
+
+struct AmountVisitor;
+
+impl Visitor<'_> for AmountVisitor {
+    type Value = Amount;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a decimal amount as a number or string")
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Amount, E> {
+        Ok(Amount::from(value))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Amount, E> {
+        Ok(Amount::from(value))
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Amount, E> {
+        Amount::from_f64(value)
+            .ok_or_else(|| E::custom(format!("amount out of range: {}", value)))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Amount, E> {
+        value.parse().map_err(E::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_amount_serde_round_trips_exactly() {
+        let amount: Amount = serde_json::from_str("0.1").unwrap();
+        assert_eq!(amount + amount + amount, "0.3".parse().unwrap());
+        assert_eq!(serde_json::to_string(&amount).unwrap(), "0.1");
+        assert_eq!(serde_yaml::from_str::<Amount>("99").unwrap(), Amount::from(99));
+
+        let precise: Amount = serde_json::from_str("\"0.123456789012345678\"").unwrap();
+        assert_eq!(
+            serde_json::to_string(&precise).unwrap(),
+            "\"0.123456789012345678\""
+        );
+        assert_eq!(precise.to_base_units(18), Some(123456789012345678));
+        assert!(serde_json::from_str::<Amount>("\"ten\"").is_err());
+    }
+
+    #[test]
+    fn test_amount_base_units() {
+        assert_eq!(Amount::new(995, 1).to_base_units(6), Some(99_500_000));
+        assert_eq!(Amount::new(1, 1).to_base_units(18), Some(100_000_000_000_000_000));
+        assert_eq!(Amount::new(15, 7).to_base_units(6), Some(2));
+        assert_eq!(Amount::new(-1, 0).to_base_units(6), None);
+        assert_eq!(
+            Amount::from_base_units(49_990_000, 6),
+            Some(Amount::new(4999, 2))
+        );
+        assert_eq!(format!("{:.2}", Amount::new(5, 3)), "0.01");
+        assert_eq!(Amount::new(9950, 2).to_string(), "99.5");
+    }
+}
@@ -0,0 +1,244 @@
+//! Blockchain networks
+//!
+//! Networks are parsed from their names, common aliases such as `"matic"`
+//! or `"mumbai"`, chain ids such as `"137"` and CAIP-2 ids such as
+//! `"eip155:137"`. A name that is none of these, e.g. a typo, fails to
+//! parse rather than failing later at payment time. Networks serialize as
+//! their canonical name.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// EVM network a contract is deployed to or paid on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum Network {
+    Ethereum,
+    Sepolia,
+    Polygon,
+    PolygonAmoy,
+    PolygonMumbai,
+    Base,
+    BaseSepolia,
+    Arbitrum,
+    ArbitrumSepolia,
+    Optimism,
+    OptimismSepolia,
+    Avalanche,
+    AvalancheFuji,
+}
+
+impl Network {
+    /// Every supported network
+    pub const ALL: [Network; 13] = [
+        Network::Ethereum,
+        Network::Sepolia,
+        Network::Polygon,
+        Network::PolygonAmoy,
+        Network::PolygonMumbai,
+        Network::Base,
+        Network::BaseSepolia,
+        Network::Arbitrum,
+        Network::ArbitrumSepolia,
+        Network::Optimism,
+        Network::OptimismSepolia,
+        Network::Avalanche,
+        Network::AvalancheFuji,
+    ];
+
+    /// Canonical name, as used by x402, e.g. `"polygon-amoy"`
+    pub fn name(self) -> &'static str {
+        match self {
+            Network::Ethereum => "ethereum",
+            Network::Sepolia => "sepolia",
+            Network::Polygon => "polygon",
+            Network::PolygonAmoy => "polygon-amoy",
+            Network::PolygonMumbai => "polygon-mumbai",
+            Network::Base => "base",
+            Network::BaseSepolia => "base-sepolia",
+            Network::Arbitrum => "arbitrum",
+            Network::ArbitrumSepolia => "arbitrum-sepolia",
+            Network::Optimism => "optimism",
+            Network::OptimismSepolia => "optimism-sepolia",
+            Network::Avalanche => "avalanche",
+            Network::AvalancheFuji => "avalanche-fuji",
+        }
+    }
+
+    /// EIP-155 chain id
+    pub fn chain_id(self) -> u64 {
+        match self {
+            Network::Ethereum => 1,
+            Network::Sepolia => 11155111,
+            Network::Polygon => 137,
+            Network::PolygonAmoy => 80002,
+            Network::PolygonMumbai => 80001,
+            Network::Base => 8453,
+            Network::BaseSepolia => 84532,
+            Network::Arbitrum => 42161,
+            Network::ArbitrumSepolia => 421614,
+            Network::Optimism => 10,
+            Network::OptimismSepolia => 11155420,
+            Network::Avalanche => 43114,
+            Network::AvalancheFuji => 43113,
+        }
+    }
+
+    /// Network with chain id `chain_id`
+    pub fn from_chain_id(chain_id: u64) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|network| network.chain_id() == chain_id)
+    }
+
+    /// Whether tokens on the network have no real value
+    pub fn is_testnet(self) -> bool {
+        matches!(
+            self,
+            Network::Sepolia
+                | Network::PolygonAmoy
+                | Network::PolygonMumbai
+                | Network::BaseSepolia
+                | Network::ArbitrumSepolia
+                | Network::OptimismSepolia
+                | Network::AvalancheFuji
+        )
+    }
+
+    /// Block explorer home page, without a trailing slash
+    pub fn explorer_url(self) -> &'static str {
+        match self {
+            Network::Ethereum => "https://etherscan.io",
+            Network::Sepolia => "https://sepolia.etherscan.io",
+            Network::Polygon => "https://polygonscan.com",
+            Network::PolygonAmoy => "https://amoy.polygonscan.com",
+            Network::PolygonMumbai => "https://mumbai.polygonscan.com",
+            Network::Base => "https://basescan.org",
+            Network::BaseSepolia => "https://sepolia.basescan.org",
+            Network::Arbitrum => "https://arbiscan.io",
+            Network::ArbitrumSepolia => "https://sepolia.arbiscan.io",
+            Network::Optimism => "https://optimistic.etherscan.io",
+            Network::OptimismSepolia => "https://sepolia-optimism.etherscan.io",
+            Network::Avalanche => "https://snowtrace.io",
+            Network::AvalancheFuji => "https://testnet.snowtrace.io",
+        }
+    }
+
+    /// Explorer page of transaction `hash`
+    pub fn tx_url(self, hash: &str) -> String {
+        format!("{}/tx/{}", self.explorer_url(), hash)
+    }
+
+    /// Explorer page of account or contract `address`
+    pub fn address_url(self, address: &str) -> String {
+        format!("{}/address/{}", self.explorer_url(), address)
+    }
+}
+
+/// Whether `a` and `b` name the same network, e.g. `"matic"` and
+/// `"polygon"`; unknown names must match exactly, ignoring case
+pub(crate) fn same_network(a: &str, b: &str) -> bool {
+    match (a.parse::<Network>(), b.parse::<Network>()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a.eq_ignore_ascii_case(b),
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+impl AsRef<str> for Network {
+    fn as_ref(&self) -> &str {
+        self.name()
+    }
+}
+
+impl FromStr for Network {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> crate::Result<Self> {
+        let name = s.trim().to_lowercase().replace(['_', ' '], "-");
+        let chain_id = name.strip_prefix("eip155:").unwrap_or(&name).parse().ok();
+        if let Some(network) = chain_id.and_then(Self::from_chain_id) {
+            return Ok(network);
+        }
+        let network = match name.as_str() {
+            "mainnet" | "eth" | "ethereum-mainnet" | "homestead" => Network::Ethereum,
+            "ethereum-sepolia" => Network::Sepolia,
+            "matic" | "polygon-pos" | "polygon-mainnet" => Network::Polygon,
+            "amoy" => Network::PolygonAmoy,
+            "mumbai" | "matic-mumbai" => Network::PolygonMumbai,
+            "base-mainnet" => Network::Base,
+            "arbitrum-one" | "arb" => Network::Arbitrum,
+            "op" | "op-mainnet" | "optimism-mainnet" => Network::Optimism,
+            "op-sepolia" => Network::OptimismSepolia,
+            "avax" | "avalanche-c" | "avalanche-mainnet" => Network::Avalanche,
+            "fuji" => Network::AvalancheFuji,
+            name => Self::ALL
+                .into_iter()
+                .find(|network| network.name() == name)
+                .ok_or_else(|| {
+                    let known: Vec<_> = Self::ALL.iter().map(|network| network.name()).collect();
+                    crate::Error::ConfigError(format!(
+                        "Unknown network {:?}; expected one of {}",
+                        s,
+                        known.join(", ")
+                    ))
+                })?,
+        };
+        Ok(network)
+    }
+}
+
+impl Serialize for Network {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for Network {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_network_parses_names_aliases_and_chain_ids() {
+        for network in Network::ALL {
+            assert_eq!(network.name().parse::<Network>().unwrap(), network);
+            assert_eq!(network.chain_id().to_string().parse::<Network>().unwrap(), network);
+        }
+        assert_eq!("Polygon_Mumbai".parse::<Network>().unwrap(), Network::PolygonMumbai);
+        assert_eq!("matic".parse::<Network>().unwrap(), Network::Polygon);
+        assert_eq!("eip155:8453".parse::<Network>().unwrap(), Network::Base);
+
+        let typo = "polgon".parse::<Network>().unwrap_err().to_string();
+        assert!(typo.contains("\"polgon\""), "{}", typo);
+        assert!("eip155:999999".parse::<Network>().is_err());
+    }
+
+    #[test]
+    fn test_network_serde_and_explorer() {
+        let network: Network = serde_json::from_str("\"mainnet\"").unwrap();
+        assert_eq!(network, Network::Ethereum);
+        assert_eq!(serde_json::to_string(&Network::BaseSepolia).unwrap(), "\"base-sepolia\"");
+        assert!(serde_yaml::from_str::<Network>("solana").is_err());
+
+        assert!(Network::PolygonAmoy.is_testnet());
+        assert!(!Network::Polygon.is_testnet());
+        assert_eq!(
+            Network::Polygon.tx_url("0xabc"),
+            "https://polygonscan.com/tx/0xabc"
+        );
+    }
+}
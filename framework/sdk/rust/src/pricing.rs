@@ -0,0 +1,340 @@
+//! Pricing structures
+//!
+//! A contract's `payment.amount` is always the flat fee due each period. A
+//! [`PricingStructure`] adds what is charged for metered usage on top of
+//! it: a price per unit, graduated tiers, or overage beyond an included
+//! allowance. Contract files have always written flat fees as
+//! `structure: "fixed"`, which still loads and is still what fixed pricing
+//! serializes to; the other structures are tagged maps, e.g.
+//! `{type: usage, unit: request, unit_price: 0.002}`.
+
+use crate::types::PaymentTerms;
+use crate::Amount;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+/// How usage is charged on top of the flat fee
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum PricingStructure {
+    /// The flat fee only
+    #[default]
+    Fixed,
+    /// `unit_price` for every unit used
+    Usage {
+        unit: String,
+        #[serde(alias = "unitPrice")]
+        unit_price: Amount,
+        /// Oracle reporting usage; `None` for the requests metered by
+        /// [`crate::x402::metering`]
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        source: Option<String>,
+    },
+    /// Graduated tiers: each unit is charged at the price of the tier it
+    /// falls in
+    Tiered {
+        unit: String,
+        tiers: Vec<PriceTier>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        source: Option<String>,
+    },
+    /// `included` units covered by the flat fee, then `overage_price` for
+    /// every unit beyond
+    BasePlusOverage {
+        unit: String,
+        included: u64,
+        #[serde(alias = "overagePrice")]
+        overage_price: Amount,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        source: Option<String>,
+    },
+}
+
+/// Tier of a [`PricingStructure::Tiered`] price
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceTier {
+    /// Last unit of the tier; `None` for the final, unbounded tier
+    #[serde(default, skip_serializing_if = "Option::is_none", alias = "upTo")]
+    pub up_to: Option<u64>,
+    #[serde(alias = "unitPrice")]
+    pub unit_price: Amount,
+}
+
+/// Units `from` (exclusive) to `to` (inclusive, `None` for no limit)
+/// charged at `price` each
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct PriceBand {
+    pub from: u64,
+    pub to: Option<u64>,
+    pub price: Amount,
+}
+
+impl PricingStructure {
+    /// Name of the structure, e.g. `"base_plus_overage"`
+    pub fn name(&self) -> &'static str {
+        match self {
+            PricingStructure::Fixed => "fixed",
+            PricingStructure::Usage { .. } => "usage",
+            PricingStructure::Tiered { .. } => "tiered",
+            PricingStructure::BasePlusOverage { .. } => "base_plus_overage",
+        }
+    }
+
+    pub fn is_fixed(&self) -> bool {
+        matches!(self, PricingStructure::Fixed)
+    }
+
+    /// Unit usage is counted in; `None` for fixed pricing
+    pub fn unit(&self) -> Option<&str> {
+        match self {
+            PricingStructure::Fixed => None,
+            PricingStructure::Usage { unit, .. }
+            | PricingStructure::Tiered { unit, .. }
+            | PricingStructure::BasePlusOverage { unit, .. } => Some(unit),
+        }
+    }
+
+    /// Oracle reporting usage, if not the metered requests
+    pub fn source(&self) -> Option<&str> {
+        match self {
+            PricingStructure::Fixed => None,
+            PricingStructure::Usage { source, .. }
+            | PricingStructure::Tiered { source, .. }
+            | PricingStructure::BasePlusOverage { source, .. } => source.as_deref(),
+        }
+    }
+
+    /// Bands of units and their price, in order
+    pub(crate) fn bands(&self) -> Vec<PriceBand> {
+        match self {
+            PricingStructure::Fixed => vec![],
+            PricingStructure::Usage { unit_price, .. } => vec![PriceBand {
+                from: 0,
+                to: None,
+                price: *unit_price,
+            }],
+            PricingStructure::Tiered { tiers, .. } => {
+                let mut from = 0;
+                tiers
+                    .iter()
+                    .map(|tier| {
+                        let band = PriceBand {
+                            from,
+                            to: tier.up_to,
+                            price: tier.unit_price,
+                        };
+                        from = tier.up_to.unwrap_or(u64::MAX);
+                        band
+                    })
+                    .collect()
+            }
+            PricingStructure::BasePlusOverage {
+                included,
+                overage_price,
+                ..
+            } => vec![PriceBand {
+                from: *included,
+                to: None,
+                price: *overage_price,
+            }],
+        }
+    }
+
+    /// Usage charge for `units` units, excluding the flat fee
+    pub fn usage_charge(&self, units: u64) -> Amount {
+        self.bands()
+            .iter()
+            .map(|band| {
+                let upper = band.to.map_or(units, |to| units.min(to));
+                band.price * upper.saturating_sub(band.from)
+            })
+            .sum()
+    }
+
+    /// Problems that make the structure unusable
+    pub(crate) fn errors(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.unit().is_some_and(|unit| unit.trim().is_empty()) {
+            errors.push(format!("{} pricing needs a unit", self.name()));
+        }
+        if self.bands().iter().any(|band| band.price.is_sign_negative()) {
+            errors.push("unit prices cannot be negative".to_string());
+        }
+        if let PricingStructure::Tiered { tiers, .. } = self {
+            if tiers.is_empty() {
+                errors.push("tiered pricing needs at least one tier".to_string());
+            }
+            let bounds: Vec<_> = tiers.iter().map(|tier| tier.up_to).collect();
+            if bounds.iter().rev().skip(1).any(Option::is_none) {
+                errors.push("only the last price tier may be unbounded".to_string());
+            } else if bounds.windows(2).any(|pair| pair[0] >= pair[1] && pair[1].is_some()) {
+                errors.push("price tier limits must increase".to_string());
+            }
+            if bounds.last().is_some_and(Option::is_some) {
+                errors.push("the last price tier must be unbounded".to_string());
+            }
+            if bounds.first() == Some(&Some(0)) {
+                errors.push("the first price tier must include at least one unit".to_string());
+            }
+        }
+        errors
+    }
+}
+
+impl fmt::Display for PricingStructure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PricingStructure::Fixed => f.pad("fixed"),
+            PricingStructure::Usage {
+                unit, unit_price, ..
+            } => f.pad(&format!("{} per {}", unit_price, unit)),
+            PricingStructure::Tiered { unit, tiers, .. } => {
+                let mut from = 0;
+                let tiers: Vec<_> = tiers
+                    .iter()
+                    .map(|tier| {
+                        let range = match tier.up_to {
+                            Some(to) => format!("{}-{}", from + 1, to),
+                            None => format!("{}+", from + 1),
+                        };
+                        from = tier.up_to.unwrap_or(from);
+                        format!("{} per {} for {}", tier.unit_price, unit, range)
+                    })
+                    .collect();
+                f.pad(&format!("tiered: {}", tiers.join(", ")))
+            }
+            PricingStructure::BasePlusOverage {
+                unit,
+                included,
+                overage_price,
+                ..
+            } => f.pad(&format!(
+                "{} {} included, then {} per {}",
+                included, unit, overage_price, unit
+            )),
+        }
+    }
+}
+
+impl PaymentTerms {
+    /// Amount due for a period in which `units` units were used: the flat
+    /// fee plus the usage charge
+    pub fn amount_for(&self, units: u64) -> Amount {
+        self.amount + self.structure.usage_charge(units)
+    }
+}
+
+/// Strings contract files use for fixed pricing
+const FIXED_NAMES: &[&str] = &["fixed", "flat", "fixed_amount", "fixed-amount"];
+
+/// Serde for [`PaymentTerms::structure`]: `"fixed"` for fixed pricing, a
+/// tagged map otherwise
+pub(crate) mod structure {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        structure: &PricingStructure,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match structure {
+            PricingStructure::Fixed => serializer.serialize_str("fixed"),
+            other => other.serialize(serializer),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<PricingStructure, D::Error> {
+        match serde_json::Value::deserialize(deserializer)? {
+            serde_json::Value::String(name)
+                if FIXED_NAMES.contains(&name.trim().to_lowercase().as_str()) =>
+            {
+                Ok(PricingStructure::Fixed)
+            }
+            serde_json::Value::String(name) => Err(serde::de::Error::custom(format!(
+                "unknown pricing structure {:?}; expected \"fixed\" or a map with a type of \
+                 usage, tiered or base_plus_overage",
+                name
+            ))),
+            value => serde_json::from_value(value).map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_structure_serde_keeps_fixed_string() {
+        let ucl = crate::UCLContract::builder().build().unwrap();
+        let document = serde_json::to_value(&ucl).unwrap();
+        assert_eq!(document["payment"]["structure"], "fixed");
+
+        let mut tiered = document.clone();
+        tiered["payment"]["structure"] = serde_json::json!({
+            "type": "tiered",
+            "unit": "request",
+            "tiers": [{"upTo": 1000, "unitPrice": 0.01}, {"unit_price": 0.005}],
+        });
+        let parsed = crate::UCLContract::parse_validated(tiered).unwrap();
+        let PricingStructure::Tiered { tiers, source, .. } = &parsed.payment.structure else {
+            panic!("expected tiered pricing, got {:?}", parsed.payment.structure);
+        };
+        assert_eq!(tiers[0].up_to, Some(1000));
+        assert_eq!(*source, None);
+        let reserialized = serde_json::to_value(&parsed).unwrap();
+        assert_eq!(reserialized["payment"]["structure"]["type"], "tiered");
+        assert_eq!(reserialized["payment"]["structure"]["tiers"][0]["up_to"], 1000);
+
+        let mut unknown = document;
+        unknown["payment"]["structure"] = "per-seat".into();
+        let error = crate::UCLContract::parse_validated(unknown).unwrap_err().to_string();
+        assert!(error.contains("payment.structure"), "{}", error);
+    }
+
+    #[test]
+    fn test_amount_for_usage() {
+        let tiered = PricingStructure::Tiered {
+            unit: "request".to_string(),
+            tiers: vec![
+                PriceTier {
+                    up_to: Some(1000),
+                    unit_price: Amount::new(1, 2),
+                },
+                PriceTier {
+                    up_to: None,
+                    unit_price: Amount::new(5, 3),
+                },
+            ],
+            source: None,
+        };
+        assert_eq!(tiered.usage_charge(0), Amount::ZERO);
+        assert_eq!(tiered.usage_charge(1000), Amount::from(10));
+        assert_eq!(tiered.usage_charge(1200), Amount::from(11));
+
+        let overage = PricingStructure::BasePlusOverage {
+            unit: "GB".to_string(),
+            included: 100,
+            overage_price: Amount::new(25, 2),
+            source: Some("storage".to_string()),
+        };
+        assert_eq!(overage.usage_charge(80), Amount::ZERO);
+        assert_eq!(overage.usage_charge(140), Amount::from(10));
+        assert_eq!(
+            overage.to_string(),
+            "100 GB included, then 0.25 per GB"
+        );
+
+        let mut terms = crate::UCLContract::builder()
+            .amount(Amount::from(49))
+            .structure(overage)
+            .build()
+            .unwrap()
+            .payment;
+        assert_eq!(terms.amount_for(140), Amount::from(59));
+        terms.structure = PricingStructure::Fixed;
+        assert_eq!(terms.amount_for(140), Amount::from(49));
+    }
+}
@@ -0,0 +1,310 @@
+//! Tracing output for the SDK
+//!
+//! Deploys, payments, condition checks, oracle reads and X402 requests are
+//! instrumented with [`tracing`] spans carrying the contract id, network and
+//! transaction hash. Nothing is printed unless a subscriber is installed:
+//! applications already using `tracing` see the SDK's spans in their own
+//! subscriber, others can opt in to [`LogSubscriber`], which writes one line
+//! per event to stderr.
+//!
+//! ```no_run
+//! // Honour SMART402_LOG=debug, or stay silent when it is unset
+//! smart402::telemetry::init_from_env();
+//! ```
+
+use crate::{Error, Result};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::{self, Write as _};
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Metadata, Subscriber};
+use tracing_core::span::Current;
+
+/// Environment variable [`init_from_env`] reads the level from
+pub const LOG_ENV: &str = "SMART402_LOG";
+
+/// Install a [`LogSubscriber`] writing events at `level` and above to stderr
+///
+/// Fails with a `ConfigError` if the process already has a global
+/// subscriber.
+pub fn init(level: Level) -> Result<()> {
+    tracing::subscriber::set_global_default(LogSubscriber::new(level))
+        .map_err(|e| Error::ConfigError(format!("Tracing is already set up: {}", e)))
+}
+
+/// Install a [`LogSubscriber`] at the level named in `SMART402_LOG`
+///
+/// Returns whether a subscriber was installed: nothing happens when the
+/// variable is unset, names no level, or another subscriber is installed.
+pub fn init_from_env() -> bool {
+    std::env::var(LOG_ENV)
+        .ok()
+        .and_then(|level| level.trim().parse::<Level>().ok())
+        .is_some_and(|level| init(level).is_ok())
+}
+
+/// Subscriber writing one line per event, prefixed with its open spans
+///
+/// ```text
+/// 2024-01-01T00:00:00.000Z  INFO deploy{network=polygon contract_id=smart402:contract:abc123 tx_hash=0xabcdef}: contract deployed
+/// ```
+pub struct LogSubscriber {
+    max_level: Level,
+    writer: Mutex<Box<dyn Write + Send>>,
+    next_id: AtomicU64,
+    spans: Mutex<HashMap<u64, SpanData>>,
+}
+
+struct SpanData {
+    metadata: &'static Metadata<'static>,
+    fields: String,
+    parent: Option<u64>,
+    refs: usize,
+}
+
+thread_local! {
+    static CURRENT: RefCell<Vec<u64>> = const { RefCell::new(Vec::new()) };
+}
+
+impl LogSubscriber {
+    /// Write events at `level` and above to stderr
+    pub fn new(level: Level) -> Self {
+        Self {
+            max_level: level,
+            writer: Mutex::new(Box::new(std::io::stderr())),
+            next_id: AtomicU64::new(1),
+            spans: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Write to `writer` instead of stderr
+    pub fn with_writer(mut self, writer: impl Write + Send + 'static) -> Self {
+        self.writer = Mutex::new(Box::new(writer));
+        self
+    }
+
+    fn current(&self) -> Option<u64> {
+        CURRENT.with(|stack| stack.borrow().last().copied())
+    }
+
+    /// `outer{fields}:inner{fields}` for the span `id` and its parents
+    fn scope(&self, id: Option<u64>) -> String {
+        let spans = self.spans.lock().unwrap();
+        let mut scope = Vec::new();
+        let mut next = id;
+        while let Some(span) = next.and_then(|id| spans.get(&id)) {
+            let name = span.metadata.name();
+            scope.push(if span.fields.is_empty() {
+                name.to_string()
+            } else {
+                format!("{}{{{}}}", name, span.fields)
+            });
+            next = span.parent;
+        }
+        scope.reverse();
+        scope.join(":")
+    }
+}
+
+impl Subscriber for LogSubscriber {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= &self.max_level
+    }
+
+    fn new_span(&self, attributes: &Attributes<'_>) -> Id {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut fields = Fields::default();
+        attributes.record(&mut fields);
+        let parent = if attributes.is_root() {
+            None
+        } else if attributes.is_contextual() {
+            self.current()
+        } else {
+            attributes.parent().map(Id::into_u64)
+        };
+        self.spans.lock().unwrap().insert(
+            id,
+            SpanData {
+                metadata: attributes.metadata(),
+                fields: fields.line,
+                parent,
+                refs: 1,
+            },
+        );
+        Id::from_u64(id)
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        if let Some(span) = self.spans.lock().unwrap().get_mut(&span.into_u64()) {
+            let mut fields = Fields {
+                line: std::mem::take(&mut span.fields),
+                message: None,
+            };
+            values.record(&mut fields);
+            span.fields = fields.line;
+        }
+    }
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        let parent = if event.is_root() {
+            None
+        } else if event.is_contextual() {
+            self.current()
+        } else {
+            event.parent().map(Id::into_u64)
+        };
+
+        let mut line = format!(
+            "{} {:>5} ",
+            chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            event.metadata().level()
+        );
+        let scope = self.scope(parent);
+        if !scope.is_empty() {
+            let _ = write!(line, "{}: ", scope);
+        }
+        match (fields.message, fields.line.is_empty()) {
+            (Some(message), true) => line.push_str(&message),
+            (Some(message), false) => {
+                let _ = write!(line, "{} {}", message, fields.line);
+            }
+            (None, _) => line.push_str(&fields.line),
+        }
+        let _ = writeln!(self.writer.lock().unwrap(), "{}", line);
+    }
+
+    fn enter(&self, span: &Id) {
+        CURRENT.with(|stack| stack.borrow_mut().push(span.into_u64()));
+    }
+
+    fn exit(&self, span: &Id) {
+        CURRENT.with(|stack| {
+            let mut stack = stack.borrow_mut();
+            if let Some(position) = stack.iter().rposition(|id| *id == span.into_u64()) {
+                stack.remove(position);
+            }
+        });
+    }
+
+    fn current_span(&self) -> Current {
+        let spans = self.spans.lock().unwrap();
+        match self
+            .current()
+            .and_then(|id| spans.get(&id).map(|span| (id, span)))
+        {
+            Some((id, span)) => Current::new(Id::from_u64(id), span.metadata),
+            None => Current::none(),
+        }
+    }
+
+    fn clone_span(&self, span: &Id) -> Id {
+        if let Some(span) = self.spans.lock().unwrap().get_mut(&span.into_u64()) {
+            span.refs += 1;
+        }
+        span.clone()
+    }
+
+    fn try_close(&self, span: Id) -> bool {
+        let mut spans = self.spans.lock().unwrap();
+        let closed = match spans.get_mut(&span.into_u64()) {
+            Some(data) => {
+                data.refs -= 1;
+                data.refs == 0
+            }
+            None => false,
+        };
+        if closed {
+            spans.remove(&span.into_u64());
+        }
+        closed
+    }
+}
+
+/// `key=value` pairs of a span or event, with the message kept apart
+#[derive(Default)]
+struct Fields {
+    line: String,
+    message: Option<String>,
+}
+
+impl Fields {
+    fn push(&mut self, field: &Field, value: fmt::Arguments<'_>) {
+        if !self.line.is_empty() {
+            self.line.push(' ');
+        }
+        let _ = write!(self.line, "{}={}", field.name(), value);
+    }
+}
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = Some(value.to_string());
+        } else if value.is_empty() || value.contains(char::is_whitespace) {
+            self.push(field, format_args!("{:?}", value));
+        } else {
+            self.push(field, format_args!("{}", value));
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = Some(format!("{:?}", value));
+        } else {
+            self.push(field, format_args!("{:?}", value));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Contract, ContractConfig};
+    use std::sync::Arc;
+
+    /// Writer appending to a shared buffer, for reading output back in tests
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_deploy_is_traced_with_contract_and_transaction() {
+        let buffer = SharedBuffer::default();
+        let subscriber = LogSubscriber::new(Level::INFO).with_writer(buffer.clone());
+        let mut contract = Contract::from_config(ContractConfig::default()).unwrap();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        tracing::subscriber::with_default(subscriber, || {
+            runtime.block_on(contract.deploy("polygon")).unwrap();
+            tracing::debug!("below the level");
+        });
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let line = output.lines().next().unwrap();
+        assert!(line.contains(&format!(
+            " INFO deploy{{network=polygon contract_id={} tx_hash=0xabcdef1234567890}}",
+            contract.ucl.contract_id
+        )));
+        assert!(line.ends_with("contract deployed address=0x1234567890abcdef1234567890abcdef12345678"));
+        assert!(!output.contains("below the level"));
+    }
+}
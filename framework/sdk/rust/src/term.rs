@@ -0,0 +1,342 @@
+//! Contract terms
+//!
+//! A contract runs from its effective date for a [`ContractDuration`], e.g.
+//! `"12 months"`, and then renews as its [`Renewal`] says. Both parse from
+//! and serialize to the strings contract files have always used.
+//!
+//! A [`Termination`] clause says how the contract can end early: on notice,
+//! or at once when a condition it names for cause fails, and what happens
+//! to payments outstanding when it does.
+
+use crate::{Amount, UCLContract};
+use chrono::{DateTime, Months, NaiveTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// How long a contract term runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ContractDuration {
+    Days(u32),
+    Weeks(u32),
+    /// Calendar months, clamped to the last day of shorter months
+    Months(u32),
+    Years(u32),
+    /// No fixed end
+    Indefinite,
+}
+
+impl ContractDuration {
+    /// End of a term starting at `start`; `None` for indefinite terms
+    pub fn after(&self, start: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match *self {
+            ContractDuration::Days(days) => {
+                start.checked_add_signed(chrono::Duration::days(days.into()))
+            }
+            ContractDuration::Weeks(weeks) => {
+                start.checked_add_signed(chrono::Duration::weeks(weeks.into()))
+            }
+            ContractDuration::Months(months) => start.checked_add_months(Months::new(months)),
+            ContractDuration::Years(years) => {
+                start.checked_add_months(Months::new(years.checked_mul(12)?))
+            }
+            ContractDuration::Indefinite => None,
+        }
+    }
+}
+
+/// Twelve months
+impl Default for ContractDuration {
+    fn default() -> Self {
+        ContractDuration::Months(12)
+    }
+}
+
+impl fmt::Display for ContractDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (count, unit) = match *self {
+            ContractDuration::Days(count) => (count, "day"),
+            ContractDuration::Weeks(count) => (count, "week"),
+            ContractDuration::Months(count) => (count, "month"),
+            ContractDuration::Years(count) => (count, "year"),
+            ContractDuration::Indefinite => return f.pad("indefinite"),
+        };
+        let plural = if count == 1 { "" } else { "s" };
+        f.pad(&format!("{} {}{}", count, unit, plural))
+    }
+}
+
+impl FromStr for ContractDuration {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> crate::Result<Self> {
+        let duration = s.trim().to_lowercase();
+        if matches!(duration.as_str(), "indefinite" | "ongoing" | "perpetual") {
+            return Ok(ContractDuration::Indefinite);
+        }
+        let invalid = || {
+            crate::Error::ConfigError(format!(
+                "Invalid contract duration {:?}; expected e.g. \"30 days\", \"12 months\", \
+                 \"1 year\" or \"indefinite\"",
+                s
+            ))
+        };
+        let (count, unit) = duration.split_once(char::is_whitespace).ok_or_else(invalid)?;
+        let count: u32 = count.parse().map_err(|_| invalid())?;
+        match unit.trim().trim_end_matches('s') {
+            "day" => Ok(ContractDuration::Days(count)),
+            "week" => Ok(ContractDuration::Weeks(count)),
+            "month" => Ok(ContractDuration::Months(count)),
+            "year" => Ok(ContractDuration::Years(count)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl Serialize for ContractDuration {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ContractDuration {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// What happens when a contract term ends
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Renewal {
+    /// A new term starts automatically
+    #[default]
+    Auto,
+    /// The parties renew explicitly; until they do, the contract expires
+    Manual,
+    /// The contract expires
+    None,
+}
+
+impl fmt::Display for Renewal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Renewal::Auto => f.pad("auto"),
+            Renewal::Manual => f.pad("manual"),
+            Renewal::None => f.pad("none"),
+        }
+    }
+}
+
+impl FromStr for Renewal {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> crate::Result<Self> {
+        match s.trim().to_lowercase().replace('_', "-").as_str() {
+            "auto" | "automatic" | "auto-renew" => Ok(Renewal::Auto),
+            "manual" => Ok(Renewal::Manual),
+            "none" | "never" | "no" => Ok(Renewal::None),
+            _ => Err(crate::Error::ConfigError(format!(
+                "Unknown renewal {:?}; expected auto, manual or none",
+                s
+            ))),
+        }
+    }
+}
+
+impl Serialize for Renewal {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Renewal {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// How a contract can be ended before its term is up
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Termination {
+    /// Notice a party must give to terminate without cause, e.g.
+    /// `"30 days"`; without one, the contract cannot be terminated on
+    /// notice
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notice: Option<ContractDuration>,
+    /// Conditions whose failure terminates the contract at once
+    #[serde(default, skip_serializing_if = "Vec::is_empty", alias = "forCause")]
+    pub for_cause: Vec<String>,
+    /// What happens to payments outstanding at termination
+    #[serde(default)]
+    pub outstanding: OutstandingPayments,
+}
+
+/// Effect of termination on outstanding payments
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum OutstandingPayments {
+    /// Payments falling due before termination takes effect are made in
+    /// full; nothing is refunded
+    #[default]
+    Due,
+    /// Payments continue until termination, and the unused part of the
+    /// last payment's period is refunded
+    Prorated,
+    /// No payments are made once notice is given or a cause arises
+    Waived,
+}
+
+impl fmt::Display for OutstandingPayments {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutstandingPayments::Due => f.pad("due"),
+            OutstandingPayments::Prorated => f.pad("prorated"),
+            OutstandingPayments::Waived => f.pad("waived"),
+        }
+    }
+}
+
+/// Why a contract was terminated
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum TerminationReason {
+    /// The notice period of notice given at `given_at` ran out
+    Notice { given_at: DateTime<Utc> },
+    /// Condition `condition_id`, named for cause, failed
+    ForCause { condition_id: String },
+}
+
+impl fmt::Display for TerminationReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TerminationReason::Notice { given_at } => {
+                write!(f, "notice given at {}", given_at.to_rfc3339())
+            }
+            TerminationReason::ForCause { condition_id } => {
+                write!(f, "condition {} failed", condition_id)
+            }
+        }
+    }
+}
+
+impl Termination {
+    /// When notice given at `given_at` takes effect; `None` if the
+    /// contract cannot be terminated on notice
+    pub fn effective_after(&self, given_at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.notice?.after(given_at)
+    }
+
+    /// Refund owed on `paid` for the period from `from` to `until` when the
+    /// contract terminates at `at`: the unused part for prorated payments,
+    /// nothing otherwise
+    pub fn refund(
+        &self,
+        paid: Amount,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+        at: DateTime<Utc>,
+    ) -> Amount {
+        if self.outstanding != OutstandingPayments::Prorated || until <= from || at >= until {
+            return Amount::ZERO;
+        }
+        let unused = (until - at.max(from)).num_seconds();
+        let period = (until - from).num_seconds();
+        let share = rust_decimal::Decimal::from(unused) / rust_decimal::Decimal::from(period);
+        Amount::from(rust_decimal::Decimal::from(paid) * share)
+    }
+}
+
+impl UCLContract {
+    /// Start of the contract term: midnight UTC on the effective date
+    pub fn effective_at(&self) -> DateTime<Utc> {
+        self.metadata.dates.effective.and_time(NaiveTime::MIN).and_utc()
+    }
+
+    /// End of the contract term, unless it renews automatically or runs
+    /// indefinitely
+    pub fn expires_at(&self) -> Option<DateTime<Utc>> {
+        let dates = &self.metadata.dates;
+        if dates.renewal == Renewal::Auto {
+            return None;
+        }
+        dates.duration.after(self.effective_at())
+    }
+
+    /// Whether the contract term has ended without renewing
+    pub fn is_expired(&self) -> bool {
+        self.expires_at().is_some_and(|end| end <= Utc::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duration_and_renewal_parse_existing_strings() {
+        for name in ["30 days", "1 week", "12 months", "1 year", "indefinite"] {
+            let duration: ContractDuration = name.parse().unwrap();
+            assert_eq!(duration.to_string(), name);
+        }
+        assert_eq!("2 Years".parse::<ContractDuration>().unwrap(), ContractDuration::Years(2));
+        assert!("a while".parse::<ContractDuration>().is_err());
+        assert_eq!("Auto".parse::<Renewal>().unwrap(), Renewal::Auto);
+        assert_eq!(serde_json::to_string(&Renewal::Manual).unwrap(), "\"manual\"");
+        assert!(serde_json::from_str::<Renewal>("\"sometimes\"").is_err());
+    }
+
+    #[test]
+    fn test_termination_notice_and_refund() {
+        let termination: Termination = serde_json::from_value(serde_json::json!({
+            "notice": "30 days",
+            "forCause": ["uptime"],
+            "outstanding": "prorated",
+        }))
+        .unwrap();
+        assert_eq!(termination.for_cause, ["uptime"]);
+        let given_at: DateTime<Utc> = "2024-03-01T00:00:00Z".parse().unwrap();
+        assert_eq!(
+            termination.effective_after(given_at).unwrap().to_rfc3339(),
+            "2024-03-31T00:00:00+00:00"
+        );
+        assert_eq!(Termination::default().effective_after(given_at), None);
+
+        let until: DateTime<Utc> = "2024-04-01T00:00:00Z".parse().unwrap();
+        let at: DateTime<Utc> = "2024-03-21T00:00:00Z".parse().unwrap();
+        let refund = termination.refund(Amount::from(31), given_at, until, at);
+        assert_eq!(refund.round_dp(6), Amount::from(11));
+        assert_eq!(
+            Termination::default().refund(Amount::from(31), given_at, until, at),
+            Amount::ZERO
+        );
+    }
+
+    #[test]
+    fn test_expiry() {
+        let mut ucl = UCLContract::builder()
+            .effective(chrono::NaiveDate::from_ymd_opt(2024, 1, 31).unwrap())
+            .duration(ContractDuration::Months(1))
+            .build()
+            .unwrap();
+        assert_eq!(ucl.expires_at(), None);
+        assert!(!ucl.is_expired());
+
+        ucl.metadata.dates.renewal = Renewal::None;
+        assert_eq!(
+            ucl.expires_at().unwrap().to_rfc3339(),
+            "2024-02-29T00:00:00+00:00"
+        );
+        assert!(ucl.is_expired());
+
+        ucl.metadata.dates.duration = ContractDuration::Indefinite;
+        assert!(!ucl.is_expired());
+    }
+}
@@ -0,0 +1,306 @@
+//! Payment tokens
+//!
+//! A [`Token`] carries what its symbol alone does not: the token's on-chain
+//! decimals and its contract address on each network. Well-known tokens such
+//! as USDC are looked up by symbol; other tokens are built with
+//! [`Token::custom`].
+//!
+//! A token serializes as its bare symbol, e.g. `"USDC"`, when the symbol
+//! parses back to the same token, so existing contract files keep loading.
+//! Custom tokens serialize as an object with `symbol`, `decimals` and
+//! `addresses`.
+
+use crate::network::Network;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Decimals assumed for ERC-20 tokens that are not well known
+const DEFAULT_DECIMALS: u32 = 18;
+
+/// Token a contract is paid in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    symbol: String,
+    decimals: u32,
+    addresses: BTreeMap<Network, String>,
+}
+
+/// Symbol, decimals and contract addresses of a well-known token
+type WellKnown = (&'static str, u32, &'static [(Network, &'static str)]);
+
+const WELL_KNOWN: &[WellKnown] = &[
+    (
+        "USDC",
+        6,
+        &[
+            (Network::Ethereum, "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"),
+            (Network::Sepolia, "0x1c7D4B196Cb0C7B01d743Fbc6116a902379C7238"),
+            (Network::Polygon, "0x3c499c542cEF5E3811e1192ce70d8cC03d5c3359"),
+            (Network::PolygonAmoy, "0x41E94Eb019C0762f9Bfcf9Fb1E58725BfB0e7582"),
+            (Network::Base, "0x833589fCD6eDb6E08f4c7C32D4a71b54bdA02913"),
+            (Network::BaseSepolia, "0x036CbD53842c5426634e7929541eC2318f3dCF7e"),
+            (Network::Arbitrum, "0xaf88d065e77c8cC2239327C5EDb3A432268e5831"),
+            (Network::ArbitrumSepolia, "0x75faf114eafb1BDbe2F0316DF893fd58CE46AA4d"),
+            (Network::Optimism, "0x0b2C639c533813f4Aa9D7837CAf62653d097Ff85"),
+            (Network::OptimismSepolia, "0x5fd84259d66Cd46123540766Be93DFE6D43130D7"),
+            (Network::Avalanche, "0xB97EF9Ef8734C71904D8002F8b6Bc66Dd9c48a6E"),
+            (Network::AvalancheFuji, "0x5425890298aed601595a70AB815c96711a31Bc65"),
+        ],
+    ),
+    (
+        "USDT",
+        6,
+        &[
+            (Network::Ethereum, "0xdAC17F958D2ee523a2206206994597C13D831ec7"),
+            (Network::Polygon, "0xc2132D05D31c914a87C6611C10748AEb04B58e8F"),
+            (Network::Arbitrum, "0xFd086bC7CD5C481DCC9C85ebE478A1C0b69FCbb9"),
+            (Network::Optimism, "0x94b008aA00579c1307B0EF2c499aD98a8ce58e58"),
+            (Network::Avalanche, "0x9702230A8Ea53601f5cD2dc00fDBc13d4dF4A8c7"),
+        ],
+    ),
+    (
+        "EURC",
+        6,
+        &[
+            (Network::Ethereum, "0x1aBaEA1f7C830bD89Acc67eC4af516284b1bC33c"),
+            (Network::Base, "0x60a3E35Cc302bFA44Cb288Bc5a4F316Fdb1adb42"),
+            (Network::Avalanche, "0xC891EB4cbdEFf6e073e859e987815Ed1505c2ACD"),
+        ],
+    ),
+    (
+        "PYUSD",
+        6,
+        &[(Network::Ethereum, "0x6c3ea9036406852006290770BEdFcAbA0e23A0e8")],
+    ),
+    (
+        "DAI",
+        18,
+        &[
+            (Network::Ethereum, "0x6B175474E89094C44Da98b954EedeAC495271d0F"),
+            (Network::Polygon, "0x8f3Cf7ad23Cd3CaDbD9735AFf958023239c6A063"),
+            (Network::Base, "0x50c5725949A6F0c72E6C4a641F24049A917DB0Cb"),
+            (Network::Arbitrum, "0xDA10009cBd5D07dd0CeCc66161FC93D7c9000da1"),
+            (Network::Optimism, "0xDA10009cBd5D07dd0CeCc66161FC93D7c9000da1"),
+        ],
+    ),
+    (
+        "WBTC",
+        8,
+        &[
+            (Network::Ethereum, "0x2260FAC5E5542a773Aa44fBCfeDf7C193bc2C599"),
+            (Network::Polygon, "0x1BFD67037B42Cf73acF2047067bd4F2C47D9BfD6"),
+            (Network::Arbitrum, "0x2f2a2543B76A4166549F7aaB2e75Bef0aefC5B0f"),
+        ],
+    ),
+    (
+        "WETH",
+        18,
+        &[
+            (Network::Ethereum, "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"),
+            (Network::Polygon, "0x7ceB23fD6bC0adD59E62ac25578270cFf1b9f619"),
+            (Network::Base, "0x4200000000000000000000000000000000000006"),
+            (Network::Arbitrum, "0x82aF49447D8a07e3bd95BD0d56f35241523fBab1"),
+            (Network::Optimism, "0x4200000000000000000000000000000000000006"),
+        ],
+    ),
+    ("ETH", 18, &[]),
+    ("POL", 18, &[]),
+    ("MATIC", 18, &[]),
+    ("AVAX", 18, &[]),
+];
+
+impl Token {
+    /// Token with `symbol` and `decimals` and no known addresses
+    pub fn custom(symbol: impl Into<String>, decimals: u32) -> Self {
+        Self {
+            symbol: symbol.into(),
+            decimals,
+            addresses: BTreeMap::new(),
+        }
+    }
+
+    /// Set the token's contract address on `network`
+    pub fn with_address(mut self, network: Network, address: impl Into<String>) -> Self {
+        self.addresses.insert(network, address.into());
+        self
+    }
+
+    /// Well-known token with `symbol`, ignoring case
+    pub fn well_known(symbol: &str) -> Option<Self> {
+        let (symbol, decimals, addresses) = WELL_KNOWN
+            .iter()
+            .find(|(known, _, _)| known.eq_ignore_ascii_case(symbol.trim()))?;
+        Some(Self {
+            symbol: symbol.to_string(),
+            decimals: *decimals,
+            addresses: addresses
+                .iter()
+                .map(|(network, address)| (*network, address.to_string()))
+                .collect(),
+        })
+    }
+
+    /// Well-known token whose contract on `network` is at `address`,
+    /// ignoring case
+    pub fn well_known_at(network: Network, address: &str) -> Option<Self> {
+        let (symbol, _, _) = WELL_KNOWN.iter().find(|(_, _, addresses)| {
+            addresses
+                .iter()
+                .any(|(known, at)| *known == network && at.eq_ignore_ascii_case(address.trim()))
+        })?;
+        Self::well_known(symbol)
+    }
+
+    pub fn usdc() -> Self {
+        Self::well_known("USDC").expect("USDC is well known")
+    }
+
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    /// On-chain decimals, e.g. 6 for USDC
+    pub fn decimals(&self) -> u32 {
+        self.decimals
+    }
+
+    /// Contract address on `network`; `None` for native gas tokens and
+    /// networks the token is not known on
+    pub fn address(&self, network: Network) -> Option<&str> {
+        self.addresses.get(&network).map(String::as_str)
+    }
+
+    /// Networks the token has a known contract address on
+    pub fn networks(&self) -> impl Iterator<Item = Network> + '_ {
+        self.addresses.keys().copied()
+    }
+
+    /// Whether the token's symbol is `symbol`, ignoring case
+    pub fn is(&self, symbol: &str) -> bool {
+        self.symbol.eq_ignore_ascii_case(symbol)
+    }
+}
+
+/// USDC, the SDK's default payment token
+impl Default for Token {
+    fn default() -> Self {
+        Self::usdc()
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(&self.symbol)
+    }
+}
+
+impl AsRef<str> for Token {
+    fn as_ref(&self) -> &str {
+        &self.symbol
+    }
+}
+
+impl FromStr for Token {
+    type Err = crate::Error;
+
+    /// Well-known token with the symbol, otherwise a token with 18 decimals
+    /// and no known addresses
+    fn from_str(s: &str) -> crate::Result<Self> {
+        let symbol = s.trim();
+        if symbol.is_empty() {
+            return Err(crate::Error::ValidationError(
+                "Token symbol is empty".to_string(),
+            ));
+        }
+        Ok(Self::well_known(symbol).unwrap_or_else(|| Self::custom(symbol, DEFAULT_DECIMALS)))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum TokenRepr {
+    Symbol(String),
+    Custom {
+        symbol: String,
+        decimals: u32,
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        addresses: BTreeMap<Network, String>,
+    },
+}
+
+impl Serialize for Token {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let repr = match self.symbol.parse::<Token>() {
+            Ok(parsed) if parsed == *self => TokenRepr::Symbol(self.symbol.clone()),
+            _ => TokenRepr::Custom {
+                symbol: self.symbol.clone(),
+                decimals: self.decimals,
+                addresses: self.addresses.clone(),
+            },
+        };
+        repr.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Token {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match TokenRepr::deserialize(deserializer)? {
+            TokenRepr::Symbol(symbol) => symbol.parse().map_err(serde::de::Error::custom),
+            TokenRepr::Custom {
+                symbol,
+                decimals,
+                addresses,
+            } => Ok(Self {
+                symbol,
+                decimals,
+                addresses,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_well_known_tokens() {
+        let usdc: Token = "usdc".parse().unwrap();
+        assert_eq!(usdc, Token::usdc());
+        assert_eq!(usdc.symbol(), "USDC");
+        assert_eq!(usdc.decimals(), 6);
+        assert_eq!(
+            usdc.address(Network::Base),
+            Some("0x833589fCD6eDb6E08f4c7C32D4a71b54bdA02913")
+        );
+        assert_eq!(Token::well_known("WBTC").unwrap().decimals(), 8);
+        assert_eq!(Token::well_known("ETH").unwrap().address(Network::Ethereum), None);
+        let base_usdc = "0x833589fcd6edb6e08f4c7c32d4a71b54bda02913";
+        assert_eq!(Token::well_known_at(Network::Base, base_usdc), Some(Token::usdc()));
+        assert_eq!(Token::well_known_at(Network::Polygon, base_usdc), None);
+
+        let unknown: Token = "FOO".parse().unwrap();
+        assert_eq!(unknown.decimals(), 18);
+        assert!("  ".parse::<Token>().is_err());
+    }
+
+    #[test]
+    fn test_token_serde() {
+        let usdc: Token = serde_yaml::from_str("USDC").unwrap();
+        assert_eq!(serde_json::to_string(&usdc).unwrap(), "\"USDC\"");
+
+        let custom = Token::custom("ACME", 6).with_address(Network::Base, "0xacme");
+        let json = serde_json::to_value(&custom).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "symbol": "ACME",
+                "decimals": 6,
+                "addresses": {"base": "0xacme"}
+            })
+        );
+        assert_eq!(serde_json::from_value::<Token>(json).unwrap(), custom);
+    }
+}
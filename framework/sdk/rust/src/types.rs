@@ -1,9 +1,17 @@
 //! Type definitions for Smart402 SDK
 
+use crate::currency::Currency;
+use crate::money::Amount;
+use crate::network::Network;
+use crate::frequency::PaymentFrequency;
+use crate::pricing::PricingStructure;
+use crate::term::{ContractDuration, Renewal, Termination};
+use crate::token::Token;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[non_exhaustive]
 pub struct ContractConfig {
     #[serde(rename = "type")]
     pub contract_type: String,
@@ -12,43 +20,222 @@ pub struct ContractConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub conditions: Option<Vec<ConditionConfig>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub metadata: Option<HashMap<String, serde_json::Value>>,
+    pub metadata: Option<ContractMetadataInput>,
 }
 
-impl Default for ContractConfig {
-    fn default() -> Self {
+impl ContractConfig {
+    /// Config for a contract of `contract_type` paid as `payment`, without
+    /// parties, conditions or metadata
+    pub fn new(contract_type: impl Into<String>, payment: PaymentConfig) -> Self {
         Self {
-            contract_type: String::new(),
-            parties: Vec::new(),
-            payment: PaymentConfig::default(),
-            conditions: None,
-            metadata: None,
+            contract_type: contract_type.into(),
+            payment,
+            ..Default::default()
         }
     }
+
+    /// Add parties, e.g. their email addresses or wallet addresses: the
+    /// vendor first, then the customer
+    pub fn with_parties<I>(mut self, parties: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.parties.extend(parties.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn with_condition(mut self, condition: ConditionConfig) -> Self {
+        self.conditions.get_or_insert_with(Vec::new).push(condition);
+        self
+    }
+
+    pub fn with_metadata(mut self, metadata: ContractMetadataInput) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+}
+
+/// Descriptive metadata of a [`ContractConfig`]
+///
+/// The common fields feed the generated contract's summary and
+/// discoverability; any other JSON fields are kept in `custom`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ContractMetadataInput {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Listing category, e.g. `"saas"` or `"digital-product"`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(flatten)]
+    pub custom: HashMap<String, serde_json::Value>,
+}
+
+impl ContractMetadataInput {
+    /// Check that the common fields that are set are not blank and that
+    /// tags are distinct
+    pub fn validate(&self) -> crate::Result<()> {
+        let fields = [
+            ("title", &self.title),
+            ("description", &self.description),
+            ("category", &self.category),
+        ];
+        if let Some((field, _)) = fields
+            .iter()
+            .find(|(_, value)| value.as_deref().is_some_and(|v| v.trim().is_empty()))
+        {
+            return Err(crate::Error::ValidationError(format!(
+                "Contract metadata {} is blank",
+                field
+            )));
+        }
+        for (i, tag) in self.tags.iter().enumerate() {
+            if tag.trim().is_empty() {
+                return Err(crate::Error::ValidationError(
+                    "Contract metadata has a blank tag".to_string(),
+                ));
+            }
+            if self.tags[..i].iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                return Err(crate::Error::ValidationError(format!(
+                    "Contract metadata repeats tag {:?}",
+                    tag
+                )));
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[non_exhaustive]
 pub struct PaymentConfig {
-    pub amount: f64,
-    pub token: String,
-    pub frequency: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub blockchain: Option<String>,
+    pub amount: Amount,
+    pub token: Token,
+    pub frequency: PaymentFrequency,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub blockchain: Option<Network>,
+    #[serde(skip_serializing_if = "Option::is_none", alias = "dayOfMonth")]
     pub day_of_month: Option<u8>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl PaymentConfig {
+    /// Payment of `amount` in `token`, on the default network
+    pub fn new(amount: Amount, token: Token, frequency: PaymentFrequency) -> Self {
+        Self {
+            amount,
+            token,
+            frequency,
+            blockchain: None,
+            day_of_month: None,
+        }
+    }
+
+    pub fn with_blockchain(mut self, blockchain: Network) -> Self {
+        self.blockchain = Some(blockchain);
+        self
+    }
+
+    pub fn with_day_of_month(mut self, day: u8) -> Self {
+        self.day_of_month = Some(day);
+        self
+    }
+}
+
+/// Condition of a [`ContractConfig`], turned into a [`ConditionDefinition`]
+/// of the generated contract
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct ConditionConfig {
     pub id: String,
+    #[serde(default)]
     pub description: String,
+    /// Oracle or kind of check, e.g. `"payment"`; also read from `type`
+    #[serde(default, alias = "type")]
     pub source: String,
+    #[serde(default)]
     pub operator: String,
-    pub threshold: serde_json::Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub threshold: Option<serde_json::Value>,
+    /// Requirement over oracle values, as in [`ConditionDefinition::expression`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expression: Option<String>,
+    /// Whether the condition must hold, rather than being optional
+    #[serde(default = "default_required")]
+    pub required: bool,
+}
+
+fn default_required() -> bool {
+    true
+}
+
+impl ConditionConfig {
+    /// Required condition without a source, operator or threshold
+    pub fn new(id: impl Into<String>, description: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            description: description.into(),
+            source: String::new(),
+            operator: String::new(),
+            threshold: None,
+            expression: None,
+            required: true,
+        }
+    }
+
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.source = source.into();
+        self
+    }
+
+    /// Compare the source's value with `operator`, e.g. `">="`, against
+    /// `threshold`
+    pub fn with_threshold(
+        mut self,
+        operator: impl Into<String>,
+        threshold: impl Into<serde_json::Value>,
+    ) -> Self {
+        self.operator = operator.into();
+        self.threshold = Some(threshold.into());
+        self
+    }
+
+    pub fn with_expression(mut self, expression: impl Into<String>) -> Self {
+        self.expression = Some(expression.into());
+        self
+    }
+
+    /// Make the condition optional
+    pub fn optional(mut self) -> Self {
+        self.required = false;
+        self
+    }
+}
+
+impl From<ConditionConfig> for ConditionDefinition {
+    fn from(config: ConditionConfig) -> Self {
+        Self {
+            id: config.id,
+            description: config.description,
+            source: config.source,
+            operator: config.operator,
+            threshold: config.threshold,
+            expression: config.expression,
+            temporal: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UCLContract {
+    /// Schema version, see [`crate::utils::migrate`]; 0 for documents
+    /// written by SDK 0.x
+    #[serde(default, alias = "uclVersion")]
+    pub ucl_version: u32,
+    #[serde(alias = "contractId")]
     pub contract_id: String,
     pub version: String,
     pub standard: String,
@@ -58,14 +245,26 @@ pub struct UCLContract {
     pub conditions: Conditions,
     pub oracles: Vec<OracleDefinition>,
     pub rules: Vec<RuleDefinition>,
+    /// How alerts about failing conditions escalate; without one, each
+    /// failure is alerted once on every channel
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub escalation: Option<EscalationPolicy>,
+    /// How the contract can end before its term is up; without a clause,
+    /// it runs until it expires
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub termination: Option<Termination>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContractSummary {
     pub title: String,
+    #[serde(alias = "plainEnglish")]
     pub plain_english: String,
+    #[serde(alias = "whatItDoes")]
     pub what_it_does: String,
+    #[serde(alias = "whoItsFor")]
     pub who_its_for: String,
+    #[serde(alias = "whenItExecutes")]
     pub when_it_executes: String,
 }
 
@@ -76,31 +275,113 @@ pub struct ContractMetadata {
     pub category: String,
     pub parties: Vec<PartyInfo>,
     pub dates: DateInfo,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Fields of the config's metadata beyond the common ones
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub custom: HashMap<String, serde_json::Value>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl ContractMetadata {
+    /// First party with one of `roles`, in the order of `roles`
+    pub fn party(&self, roles: &[&str]) -> Option<&PartyInfo> {
+        roles.iter().find_map(|role| {
+            self.parties
+                .iter()
+                .find(|party| party.role.eq_ignore_ascii_case(role))
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct PartyInfo {
     pub role: String,
     pub identifier: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
+    /// EVM address the party pays from and is paid to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wallet: Option<String>,
+    /// Decentralized identifier, e.g. `"did:web:example.com"`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub did: Option<String>,
+    /// Where the party is reached about the contract
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contact: Option<ContactChannel>,
+    /// Whether the party's identity has been verified
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub verified: bool,
+}
+
+impl PartyInfo {
+    /// The party's wallet, or its identifier when that is an EVM address
+    pub fn wallet_address(&self) -> Option<&str> {
+        self.wallet.as_deref().or_else(|| {
+            crate::utils::is_evm_address(&self.identifier).then_some(self.identifier.as_str())
+        })
+    }
+}
+
+/// How a party is contacted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum ContactChannel {
+    Email { address: String },
+    Slack { channel: String },
+    Discord { channel: String },
+    Webhook { url: String },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DateInfo {
-    pub effective: String,
-    pub duration: String,
-    pub renewal: String,
+    /// First day of the contract term, `YYYY-MM-DD` in contract files
+    pub effective: chrono::NaiveDate,
+    pub duration: ContractDuration,
+    pub renewal: Renewal,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PaymentTerms {
-    pub structure: String,
-    pub amount: f64,
-    pub currency: String,
-    pub token: String,
-    pub blockchain: String,
-    pub frequency: String,
+    /// Usage charged on top of `amount`, `"fixed"` in contract files for
+    /// none
+    #[serde(default, with = "crate::pricing::structure")]
+    pub structure: PricingStructure,
+    pub amount: Amount,
+    /// Fiat currency the price is quoted in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<Currency>,
+    pub token: Token,
+    pub blockchain: Network,
+    pub frequency: PaymentFrequency,
+    /// Penalty on payments made late, applied by the rules of
+    /// [`crate::Contract::add_late_payment_rules`]
+    #[serde(default, skip_serializing_if = "Option::is_none", alias = "latePayment")]
+    pub late_payment: Option<LatePaymentTerms>,
+}
+
+impl PaymentTerms {
+    /// Currency the price is quoted in: the explicit one, or else the one
+    /// the token is pegged to
+    pub fn price_currency(&self) -> Option<Currency> {
+        self.currency.or_else(|| self.token.peg())
+    }
+}
+
+/// Fee or interest charged on a payment made after its due date and grace
+/// period
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatePaymentTerms {
+    /// Time after the due date before the payment is late, e.g. `"5 days"`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grace: Option<String>,
+    /// Flat fee added to the next payment, in the payment token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee: Option<Amount>,
+    /// Percentage of the payment amount added to the next payment, e.g.
+    /// `1.5`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interest: Option<Amount>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -108,6 +389,17 @@ pub struct Conditions {
     pub required: Vec<ConditionDefinition>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub optional: Option<Vec<ConditionDefinition>>,
+    /// Named combinations of conditions, which rules and later groups can
+    /// refer to by id
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<ConditionGroup>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConditionGroup {
+    pub id: String,
+    #[serde(flatten)]
+    pub conditions: RuleConditions,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -118,34 +410,185 @@ pub struct ConditionDefinition {
     pub operator: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub threshold: Option<serde_json::Value>,
+    /// Requirement over oracle values, e.g.
+    /// `"uptime >= 0.99 && incidents_last_30d < 3"`; replaces `source`,
+    /// `operator` and `threshold` when set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expression: Option<String>,
+    /// Deadline or grace period measured against the contract's dates
+    /// instead of an oracle
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temporal: Option<TemporalCondition>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Moment a time-based condition waits for; the condition holds once it
+/// has passed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum TemporalCondition {
+    /// `at` plus the grace period; `at` is a date (the end of that day), an
+    /// RFC 3339 time or a span after the effective date such as `"30 days"`
+    Deadline {
+        at: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        grace: Option<String>,
+    },
+    /// Grace period after the next payment fell due
+    #[serde(alias = "paymentOverdue")]
+    PaymentOverdue {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        grace: Option<String>,
+    },
+    /// Grace period after `condition` stopped being met
+    Breached {
+        condition: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        grace: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct OracleDefinition {
     pub id: String,
     #[serde(rename = "type")]
     pub oracle_type: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub endpoint: Option<String>,
+    /// Contract the oracle reads, e.g. a Chainlink aggregator
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    /// Network of `address`, when it differs from the payment network
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blockchain: Option<Network>,
+    /// Event signature an event oracle watches for, e.g.
+    /// `"DeliveryConfirmed(uint256,address)"`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event: Option<String>,
+    /// JSONPath selecting the reported value in the endpoint's response
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Request headers, e.g. for authentication; `${VAR}` is replaced by
+    /// the environment variable `VAR`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+    /// HTTP method of API requests, `GET` unless set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+    /// Query parameters of API requests; `${VAR}` is expanded
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub query: HashMap<String, String>,
+    /// JSON body of API requests
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<serde_json::Value>,
+    /// Credentials for the endpoint
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<OracleAuth>,
+    /// Mapping reducing the endpoint's response to the reported value, e.g.
+    /// `"$.flights[0].status | == landed"`; replaces `path`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mapping: Option<String>,
+    #[serde(alias = "refreshRate")]
     pub refresh_rate: String,
     pub required: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Credentials an API oracle sends; `${VAR}` in values is replaced by the
+/// environment variable `VAR`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum OracleAuth {
+    Bearer {
+        token: String,
+    },
+    Basic {
+        username: String,
+        #[serde(default)]
+        password: Option<String>,
+    },
+    /// Key sent in a header, or as a query parameter when `in` is `query`
+    #[serde(alias = "apiKey")]
+    ApiKey {
+        name: String,
+        value: String,
+        #[serde(rename = "in", default)]
+        location: ApiKeyLocation,
+    },
+}
+
+/// Where an API key is sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum ApiKeyLocation {
+    #[default]
+    Header,
+    Query,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct RuleDefinition {
+    #[serde(alias = "ruleId")]
     pub rule_id: String,
     pub name: String,
     pub trigger: String,
+    #[serde(default)]
     pub conditions: RuleConditions,
     pub actions: Vec<ActionDefinition>,
+    /// Consecutive failures an `oracle_failure` or `payment_failure`
+    /// trigger waits for; 1 when unset
+    #[serde(default, skip_serializing_if = "Option::is_none", alias = "afterFailures")]
+    pub after_failures: Option<u32>,
+    /// Oracle an `oracle_failure` trigger watches; every oracle when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct RuleConditions {
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "allOf")]
     pub all_of: Option<Vec<String>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "anyOf")]
     pub any_of: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none", alias = "nOfM")]
+    pub n_of_m: Option<Quorum>,
+}
+
+/// At least `n` of the conditions in `of` must hold
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Quorum {
+    pub n: usize,
+    pub of: Vec<String>,
+}
+
+/// Steps taken the longer a condition keeps failing
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EscalationPolicy {
+    pub steps: Vec<EscalationStep>,
+}
+
+/// One escalation step, taken once per failure of a condition
+///
+/// The step is due once the condition has failed `after_failures`
+/// consecutive checks and has been failing for `after`, whichever are set;
+/// a step with neither is due on the first failed check.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EscalationStep {
+    #[serde(default, skip_serializing_if = "Option::is_none", alias = "afterFailures")]
+    pub after_failures: Option<u32>,
+    /// Duration such as `"30m"` or `"24h"`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+    /// `notify` or `pause_contract`
+    pub action: String,
+    /// Notifier channel to alert (`webhook`, `email`, `slack`, `discord`);
+    /// every channel when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel: Option<String>,
+    /// Who to alert: party roles such as `vendor`, or addresses
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub to: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -159,26 +602,89 @@ pub struct ActionDefinition {
 pub struct DeployResult {
     pub success: bool,
     pub address: String,
+    #[serde(alias = "transactionHash")]
     pub transaction_hash: String,
-    pub network: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    pub network: Network,
+    /// Wallet of the payee party, which owns the contract
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", alias = "blockNumber")]
     pub block_number: Option<u64>,
+    #[serde(alias = "contractId")]
     pub contract_id: String,
 }
 
+/// Where and how a contract was deployed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deployment {
+    pub address: String,
+    #[serde(alias = "transactionHash")]
+    pub transaction_hash: String,
+    pub network: Network,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none", alias = "blockNumber")]
+    pub block_number: Option<u64>,
+}
+
+impl From<&DeployResult> for Deployment {
+    fn from(result: &DeployResult) -> Self {
+        Self {
+            address: result.address.clone(),
+            transaction_hash: result.transaction_hash.clone(),
+            network: result.network,
+            owner: result.owner.clone(),
+            block_number: result.block_number,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PaymentResult {
     pub success: bool,
+    #[serde(alias = "transactionHash")]
     pub transaction_hash: String,
-    pub amount: f64,
-    pub token: String,
-    pub network: String,
+    pub amount: Amount,
+    pub token: Token,
+    pub network: Network,
     pub from: String,
     pub to: String,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// How a payment was made
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum PaymentMethod {
+    /// On-chain token transfer
+    Crypto { network: Network },
+    /// Card or bank payment through a processor (e.g. "stripe", "paypal")
+    Fiat { provider: String },
+}
+
+/// Entry in a contract's payment history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PaymentRecord {
+    /// Transaction hash or processor reference
+    pub reference: String,
+    pub method: PaymentMethod,
+    pub amount: Amount,
+    /// Token symbol for crypto payments, ISO 4217 code for fiat
+    pub currency: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payer: Option<String>,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Reference of the payment this entry refunds; refunds carry a
+    /// negative amount
+    #[serde(default, skip_serializing_if = "Option::is_none", alias = "refundOf")]
+    pub refund_of: Option<String>,
+}
+
+/// Lifecycle status of a contract; serializes as its lowercase name
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum ContractStatus {
+    #[default]
     Draft,
     Deploying,
     Deployed,
@@ -202,9 +708,139 @@ impl std::fmt::Display for ContractStatus {
     }
 }
 
+impl std::str::FromStr for ContractStatus {
+    type Err = crate::Error;
+
+    /// Parse a status name, ignoring case
+    fn from_str(s: &str) -> crate::Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "draft" => Ok(ContractStatus::Draft),
+            "deploying" => Ok(ContractStatus::Deploying),
+            "deployed" => Ok(ContractStatus::Deployed),
+            "active" => Ok(ContractStatus::Active),
+            "paused" => Ok(ContractStatus::Paused),
+            "completed" => Ok(ContractStatus::Completed),
+            "failed" => Ok(ContractStatus::Failed),
+            _ => Err(crate::Error::ValidationError(format!(
+                "Unknown contract status {:?}; expected draft, deploying, deployed, active, \
+                 paused, completed or failed",
+                s
+            ))),
+        }
+    }
+}
+
+impl Serialize for ContractStatus {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ContractStatus {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConditionCheckResult {
+    #[serde(alias = "allMet")]
     pub all_met: bool,
     pub conditions: HashMap<String, bool>,
     pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Oracles that could not be read during the check
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unavailable: Vec<String>,
+    /// Signed values of the oracles read during the check, when the
+    /// oracles have an attestation key
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attestations: Vec<OracleAttestation>,
+}
+
+/// How every condition of a check was evaluated, from
+/// [`crate::Contract::check_conditions_explain`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckExplanation {
+    pub result: ConditionCheckResult,
+    /// Required conditions first, then optional ones, in contract order
+    pub conditions: Vec<ConditionExplanation>,
+}
+
+/// How one condition was evaluated
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConditionExplanation {
+    #[serde(alias = "conditionId")]
+    pub condition_id: String,
+    pub required: bool,
+    pub met: bool,
+    /// Oracle the condition compares, unless it has an expression or is
+    /// time-based
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Value read from `source`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operator: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub threshold: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expression: Option<String>,
+    /// Values read from the oracles the expression names
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub inputs: std::collections::BTreeMap<String, serde_json::Value>,
+    /// Why the condition holds or not, e.g. `99.5 >= 99.9 does not hold`
+    pub reason: String,
+}
+
+/// Outcome of one condition at one check, kept in the contract's condition
+/// history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConditionRecord {
+    #[serde(alias = "conditionId")]
+    pub condition_id: String,
+    #[serde(alias = "checkedAt")]
+    pub checked_at: chrono::DateTime<chrono::Utc>,
+    pub met: bool,
+    /// Oracle the condition compares, unless it has an expression or is
+    /// time-based
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Oracle that answered in place of `source`, after a switch to a
+    /// fallback
+    #[serde(default, skip_serializing_if = "Option::is_none", alias = "servedBy")]
+    pub served_by: Option<String>,
+    /// Value read from `source`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<serde_json::Value>,
+    /// Values read from the oracles the condition's expression names
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub inputs: std::collections::BTreeMap<String, serde_json::Value>,
+}
+
+/// An oracle value signed by the oracle adapter that read it
+///
+/// Kept with the condition check and with the payments it justified, so the
+/// data behind an automatic payment can be proven later with
+/// [`OracleAttestation::verify`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OracleAttestation {
+    #[serde(alias = "contractId")]
+    pub contract_id: String,
+    /// Oracle id the contract's conditions refer to
+    #[serde(alias = "oracleId")]
+    pub oracle_id: String,
+    /// Oracle that answered in place of `oracle_id`, after a switch to a
+    /// fallback
+    #[serde(default, skip_serializing_if = "Option::is_none", alias = "servedBy")]
+    pub served_by: Option<String>,
+    pub value: serde_json::Value,
+    #[serde(alias = "observedAt")]
+    pub observed_at: chrono::DateTime<chrono::Utc>,
+    pub scheme: crate::x402::SignatureScheme,
+    /// Identity of the attestation key
+    pub signer: String,
+    pub signature: String,
 }
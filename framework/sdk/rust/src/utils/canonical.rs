@@ -0,0 +1,128 @@
+//! Canonical form of contract content
+//!
+//! Signatures and digests over UCL content are computed over its canonical
+//! bytes, so every SDK derives the same digest from the same terms: compact
+//! JSON with object keys sorted by code point, strings in Unicode NFC and
+//! numbers in their shortest form, integral floats written as integers.
+
+use crate::{Result, UCLContract};
+use icu_normalizer::ComposingNormalizerBorrowed;
+use serde_json::{Number, Value};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+
+/// Largest integer an `f64` holds exactly
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+
+impl UCLContract {
+    /// Canonical JSON bytes of the contract, the input of signatures and
+    /// digests over its content
+    pub fn canonical_bytes(&self) -> Result<Vec<u8>> {
+        Ok(canonical_json(&serde_json::to_value(self)?).into_bytes())
+    }
+
+    /// SHA-256 of [`UCLContract::canonical_bytes`], hex-encoded
+    pub fn content_digest(&self) -> Result<String> {
+        Ok(hex::encode(Sha256::digest(self.canonical_bytes()?)))
+    }
+}
+
+/// Canonical JSON text of `value`
+pub fn canonical_json(value: &Value) -> String {
+    let mut out = String::new();
+    write_value(value, &mut out);
+    out
+}
+
+fn write_value(value: &Value, out: &mut String) {
+    match value {
+        Value::Null | Value::Bool(_) => out.push_str(&value.to_string()),
+        Value::Number(number) => write_number(number, out),
+        Value::String(s) => write_string(s, out),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(item, out);
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            // Keys are sorted after normalization, which may reorder them
+            let mut entries: Vec<_> = map.iter().map(|(key, value)| (nfc(key), value)).collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            out.push('{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_string(&key, out);
+                out.push(':');
+                write_value(value, out);
+            }
+            out.push('}');
+        }
+    }
+}
+
+fn write_number(number: &Number, out: &mut String) {
+    match number.as_f64() {
+        Some(float)
+            if !number.is_i64()
+                && !number.is_u64()
+                && float.fract() == 0.0
+                && float.abs() <= MAX_SAFE_INTEGER =>
+        {
+            // Also turns -0.0 into 0
+            out.push_str(&(float as i64).to_string())
+        }
+        _ => out.push_str(&number.to_string()),
+    }
+}
+
+fn write_string(s: &str, out: &mut String) {
+    let quoted = serde_json::to_string(&nfc(s)).expect("strings serialize");
+    out.push_str(&quoted);
+}
+
+fn nfc(s: &str) -> Cow<'_, str> {
+    ComposingNormalizerBorrowed::new_nfc().normalize(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_canonical_json() {
+        let value = json!({
+            "b": [1.0, -0.0, 2.5, 10],
+            "a": {"z": null, "caf\u{0065}\u{0301}": "cafe\u{0301}"},
+        });
+        assert_eq!(
+            canonical_json(&value),
+            "{\"a\":{\"caf\u{e9}\":\"caf\u{e9}\",\"z\":null},\"b\":[1,0,2.5,10]}"
+        );
+    }
+
+    #[test]
+    fn test_digest_ignores_representation() {
+        let ucl = UCLContract::builder()
+            .title("Caf\u{e9} supply")
+            .build()
+            .unwrap();
+        let mut decomposed = serde_json::to_value(&ucl).unwrap();
+        decomposed["summary"]["title"] = "Cafe\u{0301} supply".into();
+        let decomposed: UCLContract = serde_json::from_value(decomposed).unwrap();
+
+        assert_ne!(ucl.summary.title, decomposed.summary.title);
+        assert_eq!(
+            ucl.content_digest().unwrap(),
+            decomposed.content_digest().unwrap()
+        );
+        assert_eq!(ucl.content_digest().unwrap().len(), 64);
+    }
+}
@@ -0,0 +1,279 @@
+//! Structural diffs of contracts
+//!
+//! [`diff_files`] compares the terms of two contract files as loaded, so
+//! formatting, key order, file format and schema version make no
+//! difference, only the terms do. Lists of conditions, oracles, rules and
+//! parties are matched by their `id`, `rule_id` or `role` rather than by
+//! position, and paths name them by it, e.g. `oracles[uptime].endpoint`;
+//! other lists are compared item by item. Status and deployment records are
+//! not compared.
+
+use super::canonical::canonical_json;
+use crate::{Result, UCLContract};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeSet, HashSet};
+use std::fmt;
+use std::path::Path;
+
+/// Keys list items are matched by, in order of preference
+pub(crate) const ITEM_KEYS: &[&str] = &["id", "rule_id", "role"];
+
+/// Change to one field of a contract
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum ContractChange {
+    Added {
+        path: String,
+        value: Value,
+    },
+    Removed {
+        path: String,
+        value: Value,
+    },
+    Changed {
+        path: String,
+        from: Value,
+        to: Value,
+    },
+}
+
+impl ContractChange {
+    /// Path of the field, e.g. `"payment.amount"`
+    pub fn path(&self) -> &str {
+        match self {
+            ContractChange::Added { path, .. }
+            | ContractChange::Removed { path, .. }
+            | ContractChange::Changed { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for ContractChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContractChange::Added { path, value } => {
+                write!(f, "+ {}: {}", path, canonical_json(value))
+            }
+            ContractChange::Removed { path, value } => {
+                write!(f, "- {}: {}", path, canonical_json(value))
+            }
+            ContractChange::Changed { path, from, to } => write!(
+                f,
+                "~ {}: {} -> {}",
+                path,
+                canonical_json(from),
+                canonical_json(to)
+            ),
+        }
+    }
+}
+
+/// Changes between two versions of a contract, in document order
+///
+/// Displays as one line per change: `+` for added fields, `-` for removed
+/// ones and `~` for changed values.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ContractDiff {
+    pub changes: Vec<ContractChange>,
+}
+
+impl ContractDiff {
+    /// Whether the terms are the same
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Change to the field at `path`, if any
+    pub fn get(&self, path: &str) -> Option<&ContractChange> {
+        self.changes.iter().find(|change| change.path() == path)
+    }
+}
+
+impl fmt::Display for ContractDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No changes");
+        }
+        for change in &self.changes {
+            writeln!(f, "{}", change)?;
+        }
+        Ok(())
+    }
+}
+
+/// Diff the terms of two contracts
+pub fn diff_contracts(from: &UCLContract, to: &UCLContract) -> Result<ContractDiff> {
+    let mut diff = ContractDiff::default();
+    diff_values(
+        "",
+        &serde_json::to_value(from)?,
+        &serde_json::to_value(to)?,
+        &mut diff.changes,
+    );
+    Ok(diff)
+}
+
+/// Diff the terms of two contract files
+///
+/// Both files are loaded like [`super::load_contract`] does, so they may be
+/// in different formats, encrypted or written by older SDKs.
+pub fn diff_files(from: &Path, to: &Path) -> Result<ContractDiff> {
+    diff_contracts(&super::load_contract(from)?, &super::load_contract(to)?)
+}
+
+fn diff_values(path: &str, from: &Value, to: &Value, changes: &mut Vec<ContractChange>) {
+    match (from, to) {
+        (Value::Object(from), Value::Object(to)) => {
+            let keys: BTreeSet<_> = from.keys().chain(to.keys()).collect();
+            for key in keys {
+                diff_fields(&field_path(path, key), from.get(key), to.get(key), changes);
+            }
+        }
+        (Value::Array(from), Value::Array(to)) => match (keyed(from), keyed(to)) {
+            (Some(from), Some(to)) => {
+                let keys = from.iter().chain(&to).map(|(key, _)| key);
+                let mut seen = HashSet::new();
+                for key in keys.filter(|key| seen.insert(*key)) {
+                    let path = format!("{}[{}]", path, key);
+                    diff_fields(&path, item(&from, key), item(&to, key), changes);
+                }
+            }
+            _ => {
+                for i in 0..from.len().max(to.len()) {
+                    let path = format!("{}[{}]", path, i);
+                    diff_fields(&path, from.get(i), to.get(i), changes);
+                }
+            }
+        },
+        _ if canonical_json(from) != canonical_json(to) => changes.push(ContractChange::Changed {
+            path: path.to_string(),
+            from: from.clone(),
+            to: to.clone(),
+        }),
+        _ => {}
+    }
+}
+
+/// Diff a field that may be missing on either side; null counts as
+/// missing, and a missing list as an empty one
+fn diff_fields(
+    path: &str,
+    from: Option<&Value>,
+    to: Option<&Value>,
+    changes: &mut Vec<ContractChange>,
+) {
+    const EMPTY: &Value = &Value::Array(Vec::new());
+    let (from, to) = (from.filter(|v| !v.is_null()), to.filter(|v| !v.is_null()));
+    match (from, to) {
+        (Some(from), Some(to)) => diff_values(path, from, to, changes),
+        (Some(list @ Value::Array(_)), None) => diff_values(path, list, EMPTY, changes),
+        (None, Some(list @ Value::Array(_))) => diff_values(path, EMPTY, list, changes),
+        (Some(value), None) => changes.push(ContractChange::Removed {
+            path: path.to_string(),
+            value: value.clone(),
+        }),
+        (None, Some(value)) => changes.push(ContractChange::Added {
+            path: path.to_string(),
+            value: value.clone(),
+        }),
+        (None, None) => {}
+    }
+}
+
+/// Items with their key, if every item has a distinct string one
+fn keyed(items: &[Value]) -> Option<Vec<(String, &Value)>> {
+    let name = ITEM_KEYS.iter().find(|name| {
+        items
+            .iter()
+            .all(|item| item.get(**name).is_some_and(Value::is_string))
+    })?;
+    let keyed: Vec<_> = items
+        .iter()
+        .map(|item| (item[*name].as_str().unwrap_or_default().to_string(), item))
+        .collect();
+    let distinct: HashSet<_> = keyed.iter().map(|(key, _)| key).collect();
+    (distinct.len() == keyed.len()).then_some(keyed)
+}
+
+fn item<'a>(items: &[(String, &'a Value)], key: &str) -> Option<&'a Value> {
+    items.iter().find(|(k, _)| k == key).map(|(_, item)| *item)
+}
+
+fn field_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ConditionDefinition;
+    use crate::{Amount, Contract, ContractConfig};
+    use std::fs;
+
+    fn condition(id: &str) -> ConditionDefinition {
+        ConditionDefinition {
+            id: id.to_string(),
+            description: format!("{} holds", id),
+            source: "monitor".to_string(),
+            operator: ">=".to_string(),
+            threshold: Some(serde_json::json!(0.99)),
+            expression: None,
+            temporal: None,
+        }
+    }
+
+    #[test]
+    fn test_diff_files_ignores_formatting() {
+        let mut ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+        ucl.conditions.required = vec![condition("uptime"), condition("latency")];
+        let dir =
+            std::env::temp_dir().join(format!("smart402-diff-{:016x}", rand::random::<u64>()));
+        fs::create_dir_all(&dir).unwrap();
+        let (yaml, json) = (dir.join("a.yaml"), dir.join("b.json"));
+        crate::utils::save_contract(&ucl, &yaml, "yaml").unwrap();
+        crate::utils::save_contract(&ucl, &json, "json").unwrap();
+        let diff = diff_files(&yaml, &json).unwrap();
+        assert!(diff.is_empty(), "{}", diff);
+        assert_eq!(diff.to_string(), "No changes\n");
+
+        let mut changed = ucl.clone();
+        changed.payment.amount = Amount::from(149);
+        changed.conditions.required.reverse();
+        changed.conditions.required[0].threshold = Some(serde_json::json!(0.999));
+        changed.conditions.required.remove(1);
+        changed.metadata.tags.push("enterprise".to_string());
+        crate::utils::save_contract(&changed, &json, "json").unwrap();
+
+        let diff = diff_files(&yaml, &json).unwrap();
+        let paths: Vec<_> = diff.changes.iter().map(ContractChange::path).collect();
+        assert_eq!(
+            paths,
+            [
+                "conditions.required[uptime]",
+                "conditions.required[latency].threshold",
+                "metadata.tags[0]",
+                "payment.amount",
+            ]
+        );
+        assert!(matches!(
+            diff.get("conditions.required[uptime]"),
+            Some(ContractChange::Removed { .. })
+        ));
+        let rendered = diff.to_string();
+        assert!(rendered.contains("~ conditions.required[latency].threshold: 0.99 -> 0.999\n"));
+        assert!(
+            rendered.contains("+ metadata.tags[0]: \"enterprise\"\n"),
+            "{}",
+            rendered
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,169 @@
+//! Encrypted contract files
+//!
+//! Contract files name the parties and how to reach them and carry the
+//! commercial terms, so they can be stored encrypted with
+//! [age](https://age-encryption.org). A key is either a passphrase, an age
+//! recipient (`age1…`), which can only encrypt, or an age identity
+//! (`AGE-SECRET-KEY-1…`), which encrypts to its own recipient and decrypts.
+//! Encrypted files are ASCII-armored, so they stay text like the YAML, JSON
+//! and TOML they wrap, and decrypt with the `age` command line tool too.
+
+use age::secrecy::SecretString;
+use age::{scrypt, x25519};
+
+/// Environment variable [`super::load_contract`] reads the key of encrypted
+/// contract files from
+pub const CONTRACT_KEY_ENV: &str = "SMART402_CONTRACT_KEY";
+
+/// Why a contract file could not be encrypted or decrypted
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
+pub enum EncryptionError {
+    #[error(
+        "Contract file is encrypted; provide its key or set {}",
+        CONTRACT_KEY_ENV
+    )]
+    KeyRequired,
+
+    #[error("Invalid contract key: {reason}")]
+    InvalidKey { reason: String },
+
+    /// The file is encrypted to another passphrase or recipient
+    #[error("Contract file is not encrypted to this key")]
+    WrongKey,
+
+    #[error("Corrupt encrypted contract file: {reason}")]
+    Corrupt { reason: String },
+}
+
+impl EncryptionError {
+    /// Stable machine-readable code, e.g. `"encryption.wrong_key"`
+    pub fn code(&self) -> &'static str {
+        match self {
+            EncryptionError::KeyRequired => "encryption.key_required",
+            EncryptionError::InvalidKey { .. } => "encryption.invalid_key",
+            EncryptionError::WrongKey => "encryption.wrong_key",
+            EncryptionError::Corrupt { .. } => "encryption.corrupt",
+        }
+    }
+}
+
+enum Key {
+    Passphrase(SecretString),
+    Recipient(Box<x25519::Recipient>),
+    Identity(Box<x25519::Identity>),
+}
+
+impl Key {
+    fn parse(key: &str) -> Result<Self, EncryptionError> {
+        let invalid = |reason: &str| EncryptionError::InvalidKey {
+            reason: reason.to_string(),
+        };
+        let trimmed = key.trim();
+        if trimmed.starts_with("age1") {
+            let recipient = trimmed.parse().map_err(|e: &str| invalid(e))?;
+            Ok(Key::Recipient(Box::new(recipient)))
+        } else if trimmed.starts_with("AGE-SECRET-KEY-1") {
+            let identity = trimmed.parse().map_err(|e: &str| invalid(e))?;
+            Ok(Key::Identity(Box::new(identity)))
+        } else if trimmed.is_empty() {
+            Err(invalid("the passphrase is empty"))
+        } else {
+            Ok(Key::Passphrase(SecretString::from(key.to_owned())))
+        }
+    }
+}
+
+/// Whether `content` is an age-encrypted file, armored or binary
+pub fn is_encrypted(content: &[u8]) -> bool {
+    let content = content.trim_ascii_start();
+    content.starts_with(b"-----BEGIN AGE ENCRYPTED FILE-----")
+        || content.starts_with(b"age-encryption.org/")
+}
+
+/// Encrypt `plaintext` to a passphrase, recipient or identity, armored
+pub fn encrypt(plaintext: &[u8], key: &str) -> Result<String, EncryptionError> {
+    let encrypted = match Key::parse(key)? {
+        Key::Passphrase(passphrase) => {
+            age::encrypt_and_armor(&scrypt::Recipient::new(passphrase), plaintext)
+        }
+        Key::Recipient(recipient) => age::encrypt_and_armor(recipient.as_ref(), plaintext),
+        Key::Identity(identity) => age::encrypt_and_armor(&identity.to_public(), plaintext),
+    };
+    encrypted.map_err(|e| EncryptionError::InvalidKey {
+        reason: e.to_string(),
+    })
+}
+
+/// Decrypt an encrypted file with its passphrase or identity
+pub fn decrypt(content: &[u8], key: &str) -> Result<Vec<u8>, EncryptionError> {
+    let decrypted = match Key::parse(key)? {
+        Key::Passphrase(passphrase) => age::decrypt(&scrypt::Identity::new(passphrase), content),
+        Key::Identity(identity) => age::decrypt(identity.as_ref(), content),
+        Key::Recipient(_) => {
+            return Err(EncryptionError::InvalidKey {
+                reason: "an age1… recipient only encrypts; decrypt with its AGE-SECRET-KEY-1… \
+                         identity"
+                    .to_string(),
+            })
+        }
+    };
+    decrypted.map_err(|e| match e {
+        age::DecryptError::DecryptionFailed | age::DecryptError::NoMatchingKeys => {
+            EncryptionError::WrongKey
+        }
+        e => EncryptionError::Corrupt {
+            reason: e.to_string(),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{
+        format_of, load_contract_with_key, load_envelope_with_key, save_contract_encrypted,
+    };
+    use crate::{Contract, ContractConfig};
+    use age::secrecy::ExposeSecret;
+
+    #[test]
+    fn test_encrypted_contract_round_trip() {
+        let ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+        let dir = std::env::temp_dir().join(format!("smart402-age-{:016x}", rand::random::<u64>()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("contract.json.age");
+        assert_eq!(format_of(&path), "json");
+
+        let identity = x25519::Identity::generate();
+        let secret = identity.to_string();
+        let secret = secret.expose_secret();
+        let recipient = identity.to_public().to_string();
+        save_contract_encrypted(&ucl, &path, format_of(&path), &recipient).unwrap();
+        let content = std::fs::read(&path).unwrap();
+        assert!(is_encrypted(&content));
+        assert!(!String::from_utf8_lossy(&content).contains(&ucl.contract_id));
+        let loaded = load_contract_with_key(&path, secret).unwrap();
+        assert_eq!(loaded.contract_id, ucl.contract_id);
+
+        let error = load_contract_with_key(&path, &recipient).unwrap_err();
+        assert_eq!(error.code(), "encryption.invalid_key");
+        let other = x25519::Identity::generate().to_string();
+        let error = load_contract_with_key(&path, other.expose_secret()).unwrap_err();
+        assert_eq!(error.code(), "encryption.wrong_key");
+
+        save_contract_encrypted(&ucl, &path, "json", "correct horse battery staple").unwrap();
+        let envelope = load_envelope_with_key(&path, "correct horse battery staple").unwrap();
+        assert_eq!(envelope.contract.contract_id, ucl.contract_id);
+        let error = load_contract_with_key(&path, "wrong horse").unwrap_err();
+        assert_eq!(error.code(), "encryption.wrong_key");
+
+        // Plain files load whatever the key
+        let plain = dir.join("contract.yaml");
+        crate::utils::save_contract(&ucl, &plain, "yaml").unwrap();
+        assert!(load_contract_with_key(&plain, secret).is_ok());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
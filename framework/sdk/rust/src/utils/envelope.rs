@@ -0,0 +1,133 @@
+//! Contract files with runtime state
+//!
+//! A plain contract file holds only the UCL terms. A [`ContractEnvelope`]
+//! wraps them with what happened to the contract since: its status and,
+//! once deployed, where it lives on chain. Envelopes load wherever a plain
+//! contract does, and plain contracts load as draft envelopes.
+
+use super::schema::{SchemaError, SchemaViolation};
+use crate::{ContractStatus, Deployment, Result, UCLContract};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Contract terms together with the contract's runtime status
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContractEnvelope {
+    pub status: ContractStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deployment: Option<Deployment>,
+    pub contract: UCLContract,
+}
+
+impl ContractEnvelope {
+    /// Draft envelope around `contract`
+    pub fn draft(contract: UCLContract) -> Self {
+        Self {
+            status: ContractStatus::Draft,
+            deployment: None,
+            contract,
+        }
+    }
+
+    /// Parse an envelope or a plain contract document
+    ///
+    /// The contract is upgraded and validated like
+    /// [`UCLContract::parse_validated`] does; the paths of an envelope's
+    /// schema violations start at the envelope, e.g. `"contract.version"`.
+    pub fn parse(mut document: Value) -> Result<Self> {
+        if !is_envelope(&document) {
+            return Ok(Self::draft(UCLContract::parse_validated(document)?));
+        }
+        let contract =
+            UCLContract::parse_validated(document["contract"].take()).map_err(|e| match e {
+                crate::Error::Schema(SchemaError { violations }) => SchemaError {
+                    violations: violations
+                        .into_iter()
+                        .map(|violation| SchemaViolation {
+                            path: format!("contract.{}", violation.path),
+                            ..violation
+                        })
+                        .collect(),
+                }
+                .into(),
+                e => e,
+            })?;
+        let status = field(&mut document, "status")?;
+        let deployment = match document.get("deployment") {
+            None | Some(Value::Null) => None,
+            Some(_) => Some(field(&mut document, "deployment")?),
+        };
+        Ok(Self {
+            status,
+            deployment,
+            contract,
+        })
+    }
+}
+
+/// Deserialize the envelope field `name`, reporting the path of a bad value
+fn field<T: serde::de::DeserializeOwned>(document: &mut Value, name: &str) -> Result<T> {
+    serde_path_to_error::deserialize(document[name].take()).map_err(|e| {
+        let path = match e.path().to_string().as_str() {
+            "." => name.to_string(),
+            path => format!("{}.{}", name, path),
+        };
+        SchemaError {
+            violations: vec![SchemaViolation {
+                path,
+                message: e.inner().to_string(),
+            }],
+        }
+        .into()
+    })
+}
+
+fn is_envelope(document: &Value) -> bool {
+    document.get("contract").is_some_and(Value::is_object) && document.get("status").is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Contract, ContractConfig, Network};
+
+    #[tokio::test]
+    async fn test_envelope_round_trips_status_and_deployment() {
+        let mut contract = Contract::from_config(ContractConfig::default()).unwrap();
+        let plain = serde_json::to_value(&contract.ucl).unwrap();
+        assert_eq!(
+            ContractEnvelope::parse(plain).unwrap().status,
+            ContractStatus::Draft
+        );
+
+        contract.deploy(Network::Base).await.unwrap();
+        let exported = serde_yaml::to_string(&contract.envelope()).unwrap();
+        assert!(exported.contains("status: deployed"), "{}", exported);
+
+        let envelope = ContractEnvelope::parse(serde_yaml::from_str(&exported).unwrap()).unwrap();
+        let restored = Contract::from_envelope(envelope).unwrap();
+        assert_eq!(restored.status(), ContractStatus::Deployed);
+        assert_eq!(restored.address(), contract.address());
+        assert_eq!(restored.deployment().unwrap().network, Network::Base);
+        assert_eq!(restored.ucl.contract_id, contract.ucl.contract_id);
+
+        let mut document: Value = serde_yaml::from_str(&exported).unwrap();
+        document["contract"]["contract_id"] = "".into();
+        document["deployment"]["network"] = "atlantis".into();
+        let error = ContractEnvelope::parse(document.clone()).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("contract.contract_id: is required"),
+            "{}",
+            error
+        );
+        document["contract"]["contract_id"] = "smart402:test".into();
+        let error = ContractEnvelope::parse(document).unwrap_err().to_string();
+        assert!(
+            error.starts_with("Invalid contract: deployment.network: "),
+            "{}",
+            error
+        );
+    }
+}
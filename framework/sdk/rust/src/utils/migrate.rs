@@ -0,0 +1,206 @@
+//! UCL schema migrations
+//!
+//! Serialized contracts carry a `ucl_version`; documents without one were
+//! written by SDK 0.x and are version 0. Before a document is deserialized,
+//! the migrations of a [`MigrationRegistry`] upgrade it one version at a
+//! time to [`UCL_VERSION`], so older files keep loading as the schema
+//! evolves. Migrations accept the camelCase keys the JS and Python SDKs
+//! write, e.g. `uclVersion`, wherever they look for a field.
+
+use crate::{Result, UCLContract};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+/// Schema version of contracts written by this SDK
+pub const UCL_VERSION: u32 = 2;
+
+/// Upgrade of a serialized contract from one schema version to the next
+pub type Migration = fn(&mut Value) -> Result<()>;
+
+/// Migrations by the version they upgrade from
+#[derive(Debug, Clone)]
+pub struct MigrationRegistry {
+    migrations: BTreeMap<u32, Migration>,
+}
+
+impl MigrationRegistry {
+    /// Registry without any migrations, not even the built-in ones
+    pub fn empty() -> Self {
+        Self {
+            migrations: BTreeMap::new(),
+        }
+    }
+
+    /// Upgrade documents of version `from` to `from + 1` with `migration`,
+    /// replacing any migration registered for `from`
+    pub fn with_migration(mut self, from: u32, migration: Migration) -> Self {
+        self.migrations.insert(from, migration);
+        self
+    }
+
+    /// Upgrade `document` to [`UCL_VERSION`]
+    ///
+    /// Fails for documents written by a newer SDK and when a migration on
+    /// the way is missing or fails.
+    pub fn migrate(&self, mut document: Value) -> Result<Value> {
+        let version = version_of(&document)?;
+        if version > UCL_VERSION {
+            return Err(crate::Error::ValidationError(format!(
+                "Contract has UCL version {}, newer than the supported {}",
+                version, UCL_VERSION
+            )));
+        }
+        for from in version..UCL_VERSION {
+            let migration = self.migrations.get(&from).ok_or_else(|| {
+                crate::Error::ValidationError(format!(
+                    "No migration from UCL version {} to {}",
+                    from,
+                    from + 1
+                ))
+            })?;
+            migration(&mut document)?;
+            let contract = object(&mut document)?;
+            contract.remove("uclVersion");
+            contract.insert("ucl_version".to_string(), json!(from + 1));
+        }
+        Ok(document)
+    }
+
+    /// Upgrade `document` and deserialize it
+    pub fn migrate_contract(&self, document: Value) -> Result<UCLContract> {
+        Ok(serde_json::from_value(self.migrate(document)?)?)
+    }
+}
+
+/// The built-in migrations
+impl Default for MigrationRegistry {
+    fn default() -> Self {
+        Self::empty()
+            .with_migration(0, v0_to_v1)
+            .with_migration(1, v1_to_v2)
+    }
+}
+
+/// Upgrade a serialized contract of any supported version with the
+/// built-in migrations
+pub fn migrate(document: Value) -> Result<UCLContract> {
+    MigrationRegistry::default().migrate_contract(document)
+}
+
+fn version_of(document: &Value) -> Result<u32> {
+    match document
+        .get("ucl_version")
+        .or_else(|| document.get("uclVersion"))
+    {
+        None | Some(Value::Null) => Ok(0),
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| {
+                crate::Error::ValidationError(format!("Invalid UCL version: {}", version))
+            }),
+    }
+}
+
+/// Whether `object` has `field`, under its own or its camelCase name
+fn has_field(object: &Map<String, Value>, field: &str) -> bool {
+    object.contains_key(field) || object.contains_key(&camel_case(field))
+}
+
+fn camel_case(field: &str) -> String {
+    let mut parts = field.split('_');
+    let mut camel = parts.next().unwrap_or_default().to_string();
+    for part in parts {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            camel.extend(first.to_uppercase());
+            camel.push_str(chars.as_str());
+        }
+    }
+    camel
+}
+
+fn object(value: &mut Value) -> Result<&mut Map<String, Value>> {
+    value
+        .as_object_mut()
+        .ok_or_else(|| crate::Error::ValidationError("Contract is not an object".to_string()))
+}
+
+/// SDK 0.x treated the summary texts, parties, conditions, oracles and
+/// rules as optional; fill in empty ones
+fn v0_to_v1(document: &mut Value) -> Result<()> {
+    let contract = object(document)?;
+    let summary = contract.entry("summary").or_insert_with(|| json!({}));
+    for field in [
+        "title",
+        "plain_english",
+        "what_it_does",
+        "who_its_for",
+        "when_it_executes",
+    ] {
+        let summary = object(summary)?;
+        if !has_field(summary, field) {
+            summary.insert(field.to_string(), json!(""));
+        }
+    }
+    if let Some(metadata) = contract.get_mut("metadata") {
+        object(metadata)?.entry("parties").or_insert_with(|| json!([]));
+    }
+    let conditions = contract.entry("conditions").or_insert_with(|| json!({}));
+    object(conditions)?.entry("required").or_insert_with(|| json!([]));
+    contract.entry("oracles").or_insert_with(|| json!([]));
+    contract.entry("rules").or_insert_with(|| json!([]));
+    Ok(())
+}
+
+/// Version 1 allowed any currency string, e.g. the token symbol for
+/// unpegged tokens; drop currencies that are not ISO 4217 codes
+fn v1_to_v2(document: &mut Value) -> Result<()> {
+    if let Some(payment) = object(document)?.get_mut("payment") {
+        let payment = object(payment)?;
+        let iso = payment
+            .get("currency")
+            .and_then(Value::as_str)
+            .and_then(|currency| currency.parse::<crate::Currency>().ok());
+        match iso {
+            Some(currency) => payment.insert("currency".to_string(), json!(currency)),
+            None => payment.remove("currency"),
+        };
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Contract, ContractConfig};
+
+    #[test]
+    fn test_migrates_sdk_0x_documents() {
+        let mut document = serde_json::to_value(
+            Contract::from_config(ContractConfig::default())
+                .unwrap()
+                .ucl,
+        )
+        .unwrap();
+        let contract = document.as_object_mut().unwrap();
+        for field in ["ucl_version", "oracles", "rules", "conditions"] {
+            contract.remove(field);
+        }
+        contract["summary"].as_object_mut().unwrap().remove("who_its_for");
+
+        document["payment"]["currency"] = json!("usdc");
+
+        let ucl = migrate(document.clone()).unwrap();
+        assert_eq!(ucl.ucl_version, UCL_VERSION);
+        assert_eq!(ucl.payment.currency, None);
+        assert!(ucl.rules.is_empty());
+        assert_eq!(ucl.summary.who_its_for, "");
+
+        let missing = MigrationRegistry::empty().migrate(document.clone());
+        assert!(missing.unwrap_err().to_string().contains("No migration from UCL version 0"));
+
+        document["ucl_version"] = json!(UCL_VERSION + 1);
+        assert!(migrate(document).is_err());
+    }
+}
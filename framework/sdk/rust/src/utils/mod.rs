@@ -1,6 +1,23 @@
 //! Utility functions
 
+pub mod canonical;
+pub mod diff;
+pub mod encryption;
+pub mod envelope;
+pub mod migrate;
+pub mod parse;
+pub mod patch;
+#[cfg(feature = "protobuf")]
+pub mod protobuf;
+pub mod schema;
+pub mod signature;
+
+pub use diff::{diff_contracts, diff_files, ContractChange, ContractDiff};
+pub use patch::{apply_patch, load_patch};
+pub use signature::{sign_contract, verify_contract, ContractSignature};
+
 use crate::{Result, UCLContract};
+use envelope::ContractEnvelope;
 use std::fs;
 use std::path::Path;
 
@@ -14,40 +31,167 @@ pub fn export_json(ucl: &UCLContract) -> Result<String> {
     Ok(serde_json::to_string_pretty(ucl)?)
 }
 
+/// Export contract to TOML
+pub fn export_toml(ucl: &UCLContract) -> Result<String> {
+    Ok(toml::to_string_pretty(ucl)?)
+}
+
 /// Save contract to file
 pub fn save_contract(ucl: &UCLContract, path: &Path, format: &str) -> Result<()> {
-    let content = match format {
-        "yaml" | "yml" => export_yaml(ucl)?,
-        "json" => export_json(ucl)?,
-        _ => return Err(crate::Error::ValidationError(format!("Unsupported format: {}", format))),
-    };
+    fs::write(path, serialize(ucl, format)?)?;
+    Ok(())
+}
 
-    fs::write(path, content)?;
+/// Save contract to file, encrypted to a passphrase or age recipient
+///
+/// See [`encryption`] for the keys accepted.
+pub fn save_contract_encrypted(
+    ucl: &UCLContract,
+    path: &Path,
+    format: &str,
+    key: &str,
+) -> Result<()> {
+    fs::write(path, encryption::encrypt(serialize(ucl, format)?.as_bytes(), key)?)?;
     Ok(())
 }
 
-/// Load contract from file
-pub fn load_contract(path: &Path) -> Result<UCLContract> {
-    let content = fs::read_to_string(path)?;
+/// Save contract with its status and deployment to file
+pub fn save_envelope(envelope: &ContractEnvelope, path: &Path, format: &str) -> Result<()> {
+    fs::write(path, serialize(envelope, format)?)?;
+    Ok(())
+}
+
+/// Save contract with its status and deployment to file, encrypted to a
+/// passphrase or age recipient
+pub fn save_envelope_encrypted(
+    envelope: &ContractEnvelope,
+    path: &Path,
+    format: &str,
+    key: &str,
+) -> Result<()> {
+    fs::write(path, encryption::encrypt(serialize(envelope, format)?.as_bytes(), key)?)?;
+    Ok(())
+}
 
-    // Try YAML first, then JSON
-    if let Ok(ucl) = serde_yaml::from_str::<UCLContract>(&content) {
-        return Ok(ucl);
+fn serialize<T: serde::Serialize>(value: &T, format: &str) -> Result<String> {
+    match format {
+        "yaml" | "yml" => Ok(serde_yaml::to_string(value)?),
+        "json" => Ok(serde_json::to_string_pretty(value)?),
+        "toml" => Ok(toml::to_string_pretty(value)?),
+        _ => Err(crate::Error::ValidationError(format!("Unsupported format: {}", format))),
     }
+}
+
+/// Format of a contract file by its extension: `"json"`, `"toml"`, or
+/// else `"yaml"`; encrypted `.age` files by the extension before it
+pub fn format_of(path: &Path) -> &'static str {
+    parse::extension_format(path).unwrap_or("yaml")
+}
+
+/// Load contract from file, upgrading contracts written by older SDKs
+///
+/// Encrypted files are decrypted with the key in
+/// [`encryption::CONTRACT_KEY_ENV`]. Fails with a [`parse::ParseError`]
+/// giving the line and column of syntax errors, and with a
+/// [`schema::SchemaError`] naming the offending fields when the contract
+/// breaks the schema.
+pub fn load_contract(path: &Path) -> Result<UCLContract> {
+    Ok(load_envelope(path)?.contract)
+}
+
+/// Load contract from file, decrypting it with `key` if it is encrypted
+pub fn load_contract_with_key(path: &Path, key: &str) -> Result<UCLContract> {
+    Ok(load_envelope_with_key(path, key)?.contract)
+}
+
+/// Load contract with its status and deployment from file
+///
+/// Plain contract files load as drafts.
+pub fn load_envelope(path: &Path) -> Result<ContractEnvelope> {
+    let key = std::env::var(encryption::CONTRACT_KEY_ENV).ok();
+    read_envelope(path, key.as_deref())
+}
+
+/// Load contract with its status and deployment from file, decrypting it
+/// with `key` if it is encrypted
+pub fn load_envelope_with_key(path: &Path, key: &str) -> Result<ContractEnvelope> {
+    read_envelope(path, Some(key))
+}
 
-    if let Ok(ucl) = serde_json::from_str::<UCLContract>(&content) {
-        return Ok(ucl);
+fn read_envelope(path: &Path, key: Option<&str>) -> Result<ContractEnvelope> {
+    let mut content = fs::read(path)?;
+    if encryption::is_encrypted(&content) {
+        let key = key.ok_or(encryption::EncryptionError::KeyRequired)?;
+        content = encryption::decrypt(&content, key)?;
     }
+    let content = String::from_utf8(content)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    ContractEnvelope::parse(parse::parse_document(path, &content)?)
+}
 
-    Err(crate::Error::ValidationError("Could not parse contract file".to_string()))
+/// Whether `address` is an EVM address: `0x` and 40 hex digits
+pub fn is_evm_address(address: &str) -> bool {
+    address
+        .strip_prefix("0x")
+        .is_some_and(|hex| hex.len() == 40 && hex.chars().all(|c| c.is_ascii_hexdigit()))
 }
 
 /// Generate contract ID
+///
+/// The creation time is followed by random digits, so contracts created
+/// in the same second get different ids.
 pub fn generate_contract_id(contract_type: &str) -> String {
     use std::time::{SystemTime, UNIX_EPOCH};
     let timestamp = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_secs();
-    format!("smart402:{}:{:x}", contract_type, timestamp)
+    format!(
+        "smart402:{}:{:x}{:08x}",
+        contract_type,
+        timestamp,
+        rand::random::<u32>()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Contract, ContractConfig, ContractStatus, Network};
+
+    #[tokio::test]
+    async fn test_toml_round_trip() {
+        let mut contract = Contract::from_config(ContractConfig::default()).unwrap();
+        contract.ucl.payment.late_payment = Some(crate::LatePaymentTerms {
+            grace: Some("5 days".to_string()),
+            fee: Some(crate::Amount::new(25, 1)),
+            interest: None,
+        });
+        contract.deploy(Network::Base).await.unwrap();
+        let toml = export_toml(&contract.ucl).unwrap();
+        assert!(toml.contains("[payment]"), "{}", toml);
+
+        let dir = std::env::temp_dir().join(format!("smart402-toml-{:016x}", rand::random::<u64>()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("contract.toml");
+        save_envelope(&contract.envelope(), &path, format_of(&path)).unwrap();
+        let envelope = load_envelope(&path).unwrap();
+        assert_eq!(envelope.status, ContractStatus::Deployed);
+        assert_eq!(
+            serde_json::to_value(&envelope.contract).unwrap(),
+            serde_json::to_value(&contract.ucl).unwrap()
+        );
+
+        // Contract files without a .toml extension are sniffed
+        let renamed = dir.join("contract.txt");
+        fs::write(&renamed, toml).unwrap();
+        assert_eq!(load_contract(&renamed).unwrap().contract_id, contract.ucl.contract_id);
+        fs::remove_dir_all(dir).unwrap();
+    }
+    #[test]
+    fn test_contract_ids_differ_within_a_second() {
+        let id = generate_contract_id("custom");
+        assert!(id.starts_with("smart402:custom:"), "{}", id);
+        assert_ne!(id, generate_contract_id("custom"));
+    }
 }
@@ -0,0 +1,217 @@
+//! Parsing contract files
+//!
+//! The format of a contract file comes from its extension (`.json`,
+//! `.toml`, `.yaml` or `.yml`, looking past an `.age` extension) or, for
+//! other names, from its content: JSON starts with `{`, TOML with a
+//! `[table]` header or a `key = value` line, and anything else is YAML.
+//! Files are parsed in that one format, so a syntax error is reported as
+//! what it is, with its line and column, rather than as the failure of
+//! whichever format was tried last.
+
+use serde_json::Value;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Syntax error in a contract file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub path: PathBuf,
+    /// Format the file was parsed as: `"json"`, `"toml"` or `"yaml"`
+    pub format: &'static str,
+    /// 1-based line of the error, when the parser reports one
+    pub line: Option<usize>,
+    /// 1-based column of the error, when the parser reports one
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.display())?;
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+            if let Some(column) = self.column {
+                write!(f, ":{}", column)?;
+            }
+        }
+        write!(
+            f,
+            ": invalid {}: {}",
+            self.format.to_uppercase(),
+            self.message
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Format named by the extension of `path`, if it names one
+pub(crate) fn extension_format(path: &Path) -> Option<&'static str> {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => Some("json"),
+        Some("toml") => Some("toml"),
+        Some("yaml" | "yml") => Some("yaml"),
+        Some("age") => path
+            .file_stem()
+            .and_then(|stem| extension_format(Path::new(stem))),
+        _ => None,
+    }
+}
+
+/// Format of a contract file, by its extension or else its content
+pub fn detect_format(path: &Path, content: &str) -> &'static str {
+    if let Some(format) = extension_format(path) {
+        return format;
+    }
+    let first = content
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'));
+    match first {
+        Some(line) if line.starts_with('{') => "json",
+        Some(line) if is_toml_header(line) || is_toml_key_value(line) => "toml",
+        _ => "yaml",
+    }
+}
+
+fn is_toml_header(line: &str) -> bool {
+    line.starts_with('[') && line.ends_with(']') && !line.contains(',')
+}
+
+fn is_toml_key_value(line: &str) -> bool {
+    line.split_once('=').is_some_and(|(key, _)| {
+        let key = key.trim();
+        !key.is_empty()
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "_-.\"' ".contains(c))
+    })
+}
+
+/// Parse the document of a contract file in its detected format
+pub(crate) fn parse_document(path: &Path, content: &str) -> Result<Value, ParseError> {
+    let format = detect_format(path, content);
+    let error = |line, column, message: String| ParseError {
+        path: path.to_path_buf(),
+        format,
+        line,
+        column,
+        message,
+    };
+    let document: Value = match format {
+        "json" => serde_json::from_str(content).map_err(|e| {
+            let suffix = format!(" at line {} column {}", e.line(), e.column());
+            let message = e.to_string();
+            let message = message
+                .strip_suffix(&suffix)
+                .unwrap_or(&message)
+                .to_string();
+            error(Some(e.line()), Some(e.column()), message)
+        })?,
+        "toml" => toml::from_str(content).map_err(|e| {
+            let (line, column) = match e.span() {
+                Some(span) => {
+                    let (line, column) = line_column(content, span.start);
+                    (Some(line), Some(column))
+                }
+                None => (None, None),
+            };
+            error(line, column, e.message().trim().replace('\n', "; "))
+        })?,
+        _ => serde_yaml::from_str(content).map_err(|e| match e.location() {
+            Some(location) => {
+                // Drop the location the message starts with; keep the
+                // context it may add, e.g. "while parsing a flow sequence"
+                let position = format!(" at line {} column {}", location.line(), location.column());
+                let message = e.to_string().replacen(&position, "", 1);
+                error(Some(location.line()), Some(location.column()), message)
+            }
+            None => error(None, None, e.to_string()),
+        })?,
+    };
+    if document.is_object() {
+        Ok(document)
+    } else {
+        let found = match document {
+            Value::Null => "an empty document",
+            Value::Bool(_) => "a boolean",
+            Value::Number(_) => "a number",
+            Value::String(_) => "a string",
+            Value::Array(_) => "a list",
+            Value::Object(_) => unreachable!("checked above"),
+        };
+        Err(error(
+            None,
+            None,
+            format!("expected a mapping of contract fields, found {}", found),
+        ))
+    }
+}
+
+/// 1-based line and column of byte `offset` in `content`
+fn line_column(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset.min(content.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rsplit('\n')
+        .next()
+        .map_or(0, |line| line.chars().count())
+        + 1;
+    (line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::load_contract;
+    use std::fs;
+
+    #[test]
+    fn test_parse_errors_name_format_and_position() {
+        let dir =
+            std::env::temp_dir().join(format!("smart402-parse-{:016x}", rand::random::<u64>()));
+        fs::create_dir_all(&dir).unwrap();
+        let load = |name: &str, content: &str| {
+            let path = dir.join(name);
+            fs::write(&path, content).unwrap();
+            match load_contract(&path).unwrap_err() {
+                crate::Error::Parse(e) => e,
+                other => panic!("expected a parse error, got {}", other),
+            }
+        };
+
+        let error = load("contract.yaml", "contract_id: abc\n version: \"1.0\"\n");
+        assert_eq!(
+            (error.format, error.line, error.column),
+            ("yaml", Some(2), Some(9))
+        );
+        assert!(error.to_string().ends_with(
+            "contract.yaml:2:9: invalid YAML: mapping values are not allowed in this context"
+        ));
+
+        // Sniffed as JSON, so the JSON error is reported, not YAML's
+        let error = load("contract", "{\n  \"contract_id\": \"abc\",\n}\n");
+        assert_eq!(
+            (error.format, error.line, error.column),
+            ("json", Some(3), Some(1))
+        );
+        assert_eq!(error.message, "trailing comma");
+
+        let error = load(
+            "contract.txt",
+            "# terms\ncontract_id = \"abc\"\nversion = \n",
+        );
+        assert_eq!(
+            (error.format, error.line, error.column),
+            ("toml", Some(3), Some(11))
+        );
+
+        assert_eq!(detect_format(Path::new("contract.toml.age"), "{"), "toml");
+        let error = load("contract.json", "[1, 2]");
+        assert_eq!(
+            error.message,
+            "expected a mapping of contract fields, found a list"
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,197 @@
+//! Patching contracts
+//!
+//! [`apply_patch`] applies a small override to a base contract, e.g. a
+//! different amount or oracle endpoint per environment, without copying
+//! the whole document. Patches follow JSON Merge Patch (RFC 7386): fields
+//! in the patch replace those of the contract, nested mappings merge and
+//! `null` removes a field. Lists of conditions, oracles, rules and parties
+//! merge strategically instead of being replaced: items are matched by
+//! their `id`, `rule_id` or `role`, matching items are patched, new ones
+//! appended and items with `$patch: delete` removed. A list holding a
+//! `$patch: replace` item replaces the list outright, like other lists do.
+//!
+//! ```yaml
+//! payment:
+//!   amount: 149
+//! oracles:
+//!   - id: uptime_oracle
+//!     endpoint: https://status.example.com/api/uptime
+//! conditions:
+//!   required:
+//!     - id: latency
+//!       $patch: delete
+//! ```
+
+use super::diff::ITEM_KEYS;
+use crate::{Result, UCLContract};
+use serde_json::{Map, Value};
+use std::path::Path;
+
+/// Key of patch directives in list items
+pub const PATCH_DIRECTIVE: &str = "$patch";
+
+/// Apply a merge patch to a contract
+///
+/// The patched contract is validated like
+/// [`UCLContract::parse_validated`]; a patch that leaves it invalid fails
+/// with a [`super::schema::SchemaError`] naming the fields at fault.
+pub fn apply_patch(ucl: &UCLContract, patch: &Value) -> Result<UCLContract> {
+    if !patch.is_object() {
+        return Err(crate::Error::ValidationError(
+            "A contract patch must be a mapping of fields".to_string(),
+        ));
+    }
+    let mut document = serde_json::to_value(ucl)?;
+    merge(&mut document, patch);
+    UCLContract::parse_validated(document)
+}
+
+/// Load a patch from a YAML, JSON or TOML file
+pub fn load_patch(path: &Path) -> Result<Value> {
+    let content = std::fs::read_to_string(path)?;
+    Ok(super::parse::parse_document(path, &content)?)
+}
+
+/// Merge `patch` into `target`
+pub fn merge(target: &mut Value, patch: &Value) {
+    match patch {
+        Value::Object(fields) => {
+            if !target.is_object() {
+                *target = Value::Object(Map::new());
+            }
+            let map = target.as_object_mut().expect("made an object above");
+            for (key, value) in fields {
+                if key == PATCH_DIRECTIVE {
+                    continue;
+                }
+                if value.is_null() {
+                    map.remove(key);
+                } else {
+                    merge(map.entry(key.as_str()).or_insert(Value::Null), value);
+                }
+            }
+        }
+        Value::Array(items) => merge_list(target, items),
+        _ => *target = patch.clone(),
+    }
+}
+
+fn merge_list(target: &mut Value, items: &[Value]) {
+    let replace = items.iter().any(|item| directive(item) == Some("replace"));
+    let items: Vec<_> = items
+        .iter()
+        .filter(|item| directive(item) != Some("replace"))
+        .collect();
+
+    let key = ITEM_KEYS.iter().find(|name| {
+        !items.is_empty()
+            && items
+                .iter()
+                .all(|item| item.get(**name).is_some_and(Value::is_string))
+    });
+    if let (Some(name), false) = (key, replace) {
+        if target.is_null() {
+            *target = Value::Array(Vec::new());
+        }
+        if let Some(list) = target.as_array_mut().filter(|list| {
+            list.iter()
+                .all(|item| item.get(*name).is_some_and(Value::is_string))
+        }) {
+            for item in items {
+                let position = list
+                    .iter()
+                    .position(|existing| existing[*name] == item[*name]);
+                match (position, directive(item) == Some("delete")) {
+                    (Some(i), true) => {
+                        list.remove(i);
+                    }
+                    (None, true) => {}
+                    (Some(i), false) => merge(&mut list[i], item),
+                    (None, false) => list.push(patched(item)),
+                }
+            }
+            return;
+        }
+    }
+    *target = Value::Array(items.into_iter().map(patched).collect());
+}
+
+/// `value` without directives and null fields, as merged into nothing
+fn patched(value: &Value) -> Value {
+    let mut result = Value::Null;
+    merge(&mut result, value);
+    result
+}
+
+fn directive(item: &Value) -> Option<&str> {
+    item.get(PATCH_DIRECTIVE).and_then(Value::as_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ConditionDefinition;
+    use crate::{Amount, Contract, ContractConfig};
+    use serde_json::json;
+
+    fn condition(id: &str) -> ConditionDefinition {
+        ConditionDefinition {
+            id: id.to_string(),
+            description: format!("{} holds", id),
+            source: "monitor".to_string(),
+            operator: ">=".to_string(),
+            threshold: Some(json!(0.99)),
+            expression: None,
+            temporal: None,
+        }
+    }
+
+    #[test]
+    fn test_apply_patch_merges_keyed_lists() {
+        let mut ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+        ucl.conditions.required = vec![condition("uptime"), condition("latency")];
+        ucl.metadata.tags = vec!["api".to_string()];
+
+        let patched = apply_patch(
+            &ucl,
+            &json!({
+                "payment": {"amount": 149, "late_payment": null},
+                "metadata": {"tags": ["api", "enterprise"]},
+                "conditions": {"required": [
+                    {"id": "latency", "$patch": "delete"},
+                    {"id": "uptime", "threshold": 0.999},
+                    {"id": "errors", "description": "Few errors", "source": "monitor",
+                     "operator": "<", "threshold": 0.01, "expression": null},
+                ]},
+            }),
+        )
+        .unwrap();
+        assert_eq!(patched.payment.amount, Amount::from(149));
+        assert_eq!(patched.payment.late_payment, None);
+        assert_eq!(patched.metadata.tags, ["api", "enterprise"]);
+        let ids: Vec<_> = patched
+            .conditions
+            .required
+            .iter()
+            .map(|c| c.id.as_str())
+            .collect();
+        assert_eq!(ids, ["uptime", "errors"]);
+        assert_eq!(patched.conditions.required[0].threshold, Some(json!(0.999)));
+        assert_eq!(patched.conditions.required[0].description, "uptime holds");
+        assert_eq!(patched.contract_id, ucl.contract_id);
+
+        let replaced = apply_patch(
+            &ucl,
+            &json!({"conditions": {"required": [{"$patch": "replace"}, {"id": "latency",
+                "description": "", "source": "monitor", "operator": "<"}]}}),
+        )
+        .unwrap();
+        assert_eq!(replaced.conditions.required.len(), 1);
+        assert_eq!(replaced.conditions.required[0].threshold, None);
+
+        let error = apply_patch(&ucl, &json!({"payment": {"amount": "lots"}})).unwrap_err();
+        assert!(error.to_string().contains("payment.amount"), "{}", error);
+    }
+}
@@ -0,0 +1,780 @@
+//! Protobuf encoding of contracts
+//!
+//! [`v1`] holds the prost types of `proto/smart402/ucl/v1/ucl.proto`, which
+//! other services compile for their own language. [`UCLContract::to_protobuf`]
+//! and [`UCLContract::from_protobuf`] encode and decode contracts directly;
+//! `v1::UclContract::from(&ucl)` and `UCLContract::try_from(message)` convert
+//! between the types, e.g. to embed a contract in another message.
+//!
+//! Decoding validates like [`UCLContract::parse_validated`]: malformed
+//! values fail with a [`SchemaError`] naming the field. Empty lists and
+//! unset lists are the same in protobuf, so optional conditions and rule
+//! condition lists that are empty decode as unset.
+//!
+//! `v1` is generated by prost-build 0.12; regenerate it after changing the
+//! schema.
+
+use super::migrate::UCL_VERSION;
+use super::schema::{SchemaError, SchemaViolation};
+use crate::pricing::{PriceTier, PricingStructure};
+use crate::types::{
+    ActionDefinition, ApiKeyLocation, ConditionDefinition, ConditionGroup, Conditions,
+    ContactChannel, ContractMetadata, ContractSummary, DateInfo, EscalationPolicy, EscalationStep,
+    LatePaymentTerms, OracleAuth, OracleDefinition, PartyInfo, PaymentTerms, Quorum,
+    RuleConditions, RuleDefinition, TemporalCondition,
+};
+use crate::{Amount, OutstandingPayments, Result, Termination, Token, UCLContract};
+use prost::Message;
+use prost_types::value::Kind;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Types generated from `smart402/ucl/v1/ucl.proto`
+#[allow(missing_docs)]
+pub mod v1 {
+    include!("protobuf/smart402.ucl.v1.rs");
+}
+
+impl UCLContract {
+    /// Encode the contract as a `smart402.ucl.v1.UclContract` message
+    pub fn to_protobuf(&self) -> Vec<u8> {
+        v1::UclContract::from(self).encode_to_vec()
+    }
+
+    /// Decode and validate a `smart402.ucl.v1.UclContract` message
+    pub fn from_protobuf(bytes: &[u8]) -> Result<Self> {
+        let message = v1::UclContract::decode(bytes).map_err(|e| {
+            crate::Error::ValidationError(format!("Invalid protobuf contract: {}", e))
+        })?;
+        let ucl = Self::try_from(message)?;
+        let violations = ucl.schema_violations();
+        if violations.is_empty() {
+            Ok(ucl)
+        } else {
+            Err(SchemaError { violations }.into())
+        }
+    }
+}
+
+impl From<&UCLContract> for v1::UclContract {
+    fn from(ucl: &UCLContract) -> Self {
+        let metadata = &ucl.metadata;
+        let payment = &ucl.payment;
+        Self {
+            ucl_version: ucl.ucl_version,
+            contract_id: ucl.contract_id.clone(),
+            version: ucl.version.clone(),
+            standard: ucl.standard.clone(),
+            summary: Some(v1::Summary {
+                title: ucl.summary.title.clone(),
+                plain_english: ucl.summary.plain_english.clone(),
+                what_it_does: ucl.summary.what_it_does.clone(),
+                who_its_for: ucl.summary.who_its_for.clone(),
+                when_it_executes: ucl.summary.when_it_executes.clone(),
+            }),
+            metadata: Some(v1::Metadata {
+                r#type: metadata.contract_type.clone(),
+                category: metadata.category.clone(),
+                parties: metadata.parties.iter().map(party).collect(),
+                dates: Some(v1::Dates {
+                    effective: metadata.dates.effective.to_string(),
+                    duration: metadata.dates.duration.to_string(),
+                    renewal: metadata.dates.renewal.to_string(),
+                }),
+                tags: metadata.tags.clone(),
+                custom: metadata
+                    .custom
+                    .iter()
+                    .map(|(key, value)| (key.clone(), to_value(value)))
+                    .collect(),
+            }),
+            payment: Some(v1::PaymentTerms {
+                structure: pricing(&payment.structure),
+                amount: payment.amount.to_string(),
+                currency: payment.currency.map(|currency| currency.to_string()),
+                token: Some(v1::Token {
+                    symbol: payment.token.symbol().to_string(),
+                    decimals: payment.token.decimals(),
+                    addresses: payment
+                        .token
+                        .networks()
+                        .filter_map(|network| {
+                            let address = payment.token.address(network)?;
+                            Some((network.to_string(), address.to_string()))
+                        })
+                        .collect(),
+                }),
+                blockchain: payment.blockchain.to_string(),
+                frequency: payment.frequency.to_string(),
+                late_payment: payment
+                    .late_payment
+                    .as_ref()
+                    .map(|late| v1::LatePaymentTerms {
+                        grace: late.grace.clone(),
+                        fee: late.fee.map(|fee| fee.to_string()),
+                        interest: late.interest.map(Amount::to_f64),
+                    }),
+            }),
+            conditions: Some(v1::Conditions {
+                required: ucl.conditions.required.iter().map(condition).collect(),
+                optional: ucl
+                    .conditions
+                    .optional
+                    .iter()
+                    .flatten()
+                    .map(condition)
+                    .collect(),
+                groups: ucl
+                    .conditions
+                    .groups
+                    .iter()
+                    .map(|group| v1::ConditionGroup {
+                        id: group.id.clone(),
+                        conditions: Some(rule_conditions(&group.conditions)),
+                    })
+                    .collect(),
+            }),
+            oracles: ucl.oracles.iter().map(oracle).collect(),
+            rules: ucl
+                .rules
+                .iter()
+                .map(|rule| v1::Rule {
+                    rule_id: rule.rule_id.clone(),
+                    name: rule.name.clone(),
+                    trigger: rule.trigger.clone(),
+                    conditions: Some(rule_conditions(&rule.conditions)),
+                    actions: rule
+                        .actions
+                        .iter()
+                        .map(|action| v1::Action {
+                            action: action.action.clone(),
+                            params: action
+                                .params
+                                .iter()
+                                .map(|(name, value)| (name.clone(), to_value(value)))
+                                .collect(),
+                        })
+                        .collect(),
+                    after_failures: rule.after_failures,
+                    source: rule.source.clone(),
+                })
+                .collect(),
+            escalation: ucl.escalation.as_ref().map(|policy| v1::EscalationPolicy {
+                steps: policy
+                    .steps
+                    .iter()
+                    .map(|step| v1::EscalationStep {
+                        after_failures: step.after_failures,
+                        after: step.after.clone(),
+                        action: step.action.clone(),
+                        channel: step.channel.clone(),
+                        to: step.to.clone(),
+                    })
+                    .collect(),
+            }),
+            termination: ucl.termination.as_ref().map(|termination| v1::Termination {
+                notice: termination.notice.map(|notice| notice.to_string()),
+                for_cause: termination.for_cause.clone(),
+                outstanding: match termination.outstanding {
+                    OutstandingPayments::Due => v1::OutstandingPayments::Due,
+                    OutstandingPayments::Prorated => v1::OutstandingPayments::Prorated,
+                    OutstandingPayments::Waived => v1::OutstandingPayments::Waived,
+                } as i32,
+            }),
+        }
+    }
+}
+
+impl TryFrom<v1::UclContract> for UCLContract {
+    type Error = crate::Error;
+
+    /// Contract from a decoded message; fails with a [`SchemaError`] for
+    /// values that do not parse
+    fn try_from(message: v1::UclContract) -> Result<Self> {
+        if message.ucl_version > UCL_VERSION {
+            return Err(crate::Error::ValidationError(format!(
+                "Contract has UCL version {}, newer than the supported {}",
+                message.ucl_version, UCL_VERSION
+            )));
+        }
+        let summary = message.summary.unwrap_or_default();
+        let metadata = message.metadata.unwrap_or_default();
+        let dates = metadata.dates.unwrap_or_default();
+        let payment = message.payment.unwrap_or_default();
+        let token = payment.token.unwrap_or_default();
+        let conditions = message.conditions.unwrap_or_default();
+
+        let mut addresses = Vec::new();
+        for (network, address) in token.addresses {
+            addresses.push((parse(&network, "payment.token.addresses")?, address));
+        }
+        let token = addresses.into_iter().fold(
+            Token::custom(token.symbol, token.decimals),
+            |token, (network, address)| token.with_address(network, address),
+        );
+
+        let mut parties = Vec::new();
+        for party in metadata.parties {
+            parties.push(PartyInfo {
+                role: party.role,
+                identifier: party.identifier,
+                name: party.name,
+                wallet: party.wallet,
+                did: party.did,
+                contact: party
+                    .contact
+                    .and_then(|contact| contact.channel)
+                    .map(|channel| {
+                        use v1::contact_channel::Channel;
+                        match channel {
+                            Channel::Email(address) => ContactChannel::Email { address },
+                            Channel::Slack(channel) => ContactChannel::Slack { channel },
+                            Channel::Discord(channel) => ContactChannel::Discord { channel },
+                            Channel::Webhook(url) => ContactChannel::Webhook { url },
+                        }
+                    }),
+                verified: party.verified,
+            });
+        }
+
+        let late_payment = match payment.late_payment {
+            Some(late) => Some(LatePaymentTerms {
+                grace: late.grace,
+                fee: late
+                    .fee
+                    .map(|fee| parse(&fee, "payment.late_payment.fee"))
+                    .transpose()?,
+                interest: late
+                    .interest
+                    .map(|interest| {
+                        Amount::from_f64(interest).ok_or_else(|| {
+                            crate::Error::from(SchemaError {
+                                violations: vec![SchemaViolation {
+                                    path: "payment.late_payment.interest".to_string(),
+                                    message: format!("{} is not a number", interest),
+                                }],
+                            })
+                        })
+                    })
+                    .transpose()?,
+            }),
+            None => None,
+        };
+
+        let mut oracles = Vec::new();
+        for (i, oracle) in message.oracles.into_iter().enumerate() {
+            let blockchain = oracle
+                .blockchain
+                .map(|network| parse(&network, &format!("oracles[{}].blockchain", i)))
+                .transpose()?;
+            oracles.push(OracleDefinition {
+                id: oracle.id,
+                oracle_type: oracle.r#type,
+                endpoint: oracle.endpoint,
+                address: oracle.address,
+                blockchain,
+                event: oracle.event,
+                path: oracle.path,
+                headers: oracle.headers,
+                method: oracle.method,
+                query: oracle.query,
+                body: oracle.body.map(from_value),
+                auth: oracle.auth.and_then(|auth| auth.kind).map(|kind| {
+                    use v1::oracle_auth::Kind;
+                    match kind {
+                        Kind::Bearer(auth) => OracleAuth::Bearer { token: auth.token },
+                        Kind::Basic(auth) => OracleAuth::Basic {
+                            username: auth.username,
+                            password: auth.password,
+                        },
+                        Kind::ApiKey(auth) => OracleAuth::ApiKey {
+                            name: auth.name,
+                            value: auth.value,
+                            location: if auth.in_query {
+                                ApiKeyLocation::Query
+                            } else {
+                                ApiKeyLocation::Header
+                            },
+                        },
+                    }
+                }),
+                mapping: oracle.mapping,
+                refresh_rate: oracle.refresh_rate,
+                required: oracle.required,
+            });
+        }
+
+        let termination = match message.termination {
+            Some(termination) => Some(Termination {
+                notice: termination
+                    .notice
+                    .as_deref()
+                    .map(|notice| parse(notice, "termination.notice"))
+                    .transpose()?,
+                outstanding: match termination.outstanding() {
+                    v1::OutstandingPayments::Due => OutstandingPayments::Due,
+                    v1::OutstandingPayments::Prorated => OutstandingPayments::Prorated,
+                    v1::OutstandingPayments::Waived => OutstandingPayments::Waived,
+                },
+                for_cause: termination.for_cause,
+            }),
+            None => None,
+        };
+
+        Ok(UCLContract {
+            ucl_version: UCL_VERSION,
+            contract_id: message.contract_id,
+            version: message.version,
+            standard: message.standard,
+            summary: ContractSummary {
+                title: summary.title,
+                plain_english: summary.plain_english,
+                what_it_does: summary.what_it_does,
+                who_its_for: summary.who_its_for,
+                when_it_executes: summary.when_it_executes,
+            },
+            metadata: ContractMetadata {
+                contract_type: metadata.r#type,
+                category: metadata.category,
+                parties,
+                dates: DateInfo {
+                    effective: parse(&dates.effective, "metadata.dates.effective")?,
+                    duration: parse(&dates.duration, "metadata.dates.duration")?,
+                    renewal: parse(&dates.renewal, "metadata.dates.renewal")?,
+                },
+                tags: metadata.tags,
+                custom: metadata
+                    .custom
+                    .into_iter()
+                    .map(|(key, value)| (key, from_value(value)))
+                    .collect(),
+            },
+            payment: PaymentTerms {
+                structure: pricing_structure(payment.structure)?,
+                amount: parse(&payment.amount, "payment.amount")?,
+                currency: payment
+                    .currency
+                    .as_deref()
+                    .map(|currency| parse(currency, "payment.currency"))
+                    .transpose()?,
+                token,
+                blockchain: parse(&payment.blockchain, "payment.blockchain")?,
+                frequency: parse(&payment.frequency, "payment.frequency")?,
+                late_payment,
+            },
+            conditions: Conditions {
+                required: conditions
+                    .required
+                    .into_iter()
+                    .map(condition_definition)
+                    .collect(),
+                optional: (!conditions.optional.is_empty()).then(|| {
+                    conditions
+                        .optional
+                        .into_iter()
+                        .map(condition_definition)
+                        .collect()
+                }),
+                groups: conditions
+                    .groups
+                    .into_iter()
+                    .map(|group| ConditionGroup {
+                        id: group.id,
+                        conditions: group
+                            .conditions
+                            .map(rule_conditions_definition)
+                            .unwrap_or_default(),
+                    })
+                    .collect(),
+            },
+            oracles,
+            rules: message
+                .rules
+                .into_iter()
+                .map(|rule| RuleDefinition {
+                    rule_id: rule.rule_id,
+                    name: rule.name,
+                    trigger: rule.trigger,
+                    conditions: rule
+                        .conditions
+                        .map(rule_conditions_definition)
+                        .unwrap_or_default(),
+                    actions: rule
+                        .actions
+                        .into_iter()
+                        .map(|action| ActionDefinition {
+                            action: action.action,
+                            params: action
+                                .params
+                                .into_iter()
+                                .map(|(name, value)| (name, from_value(value)))
+                                .collect(),
+                        })
+                        .collect(),
+                    after_failures: rule.after_failures,
+                    source: rule.source,
+                })
+                .collect(),
+            escalation: message.escalation.map(|policy| EscalationPolicy {
+                steps: policy
+                    .steps
+                    .into_iter()
+                    .map(|step| EscalationStep {
+                        after_failures: step.after_failures,
+                        after: step.after,
+                        action: step.action,
+                        channel: step.channel,
+                        to: step.to,
+                    })
+                    .collect(),
+            }),
+            termination,
+        })
+    }
+}
+
+/// Parse the string form of the field at `path`
+fn parse<T>(value: &str, path: &str) -> Result<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    value.parse().map_err(|e: T::Err| {
+        SchemaError {
+            violations: vec![SchemaViolation {
+                path: path.to_string(),
+                message: format!("{:?} is invalid: {}", value, e),
+            }],
+        }
+        .into()
+    })
+}
+
+fn party(party: &PartyInfo) -> v1::Party {
+    use v1::contact_channel::Channel;
+    v1::Party {
+        role: party.role.clone(),
+        identifier: party.identifier.clone(),
+        name: party.name.clone(),
+        wallet: party.wallet.clone(),
+        did: party.did.clone(),
+        contact: party.contact.as_ref().map(|contact| v1::ContactChannel {
+            channel: Some(match contact {
+                ContactChannel::Email { address } => Channel::Email(address.clone()),
+                ContactChannel::Slack { channel } => Channel::Slack(channel.clone()),
+                ContactChannel::Discord { channel } => Channel::Discord(channel.clone()),
+                ContactChannel::Webhook { url } => Channel::Webhook(url.clone()),
+            }),
+        }),
+        verified: party.verified,
+    }
+}
+
+fn pricing(structure: &PricingStructure) -> Option<v1::PricingStructure> {
+    use v1::pricing_structure::Structure;
+    let structure = match structure {
+        PricingStructure::Fixed => return None,
+        PricingStructure::Usage {
+            unit,
+            unit_price,
+            source,
+        } => Structure::Usage(v1::UsagePricing {
+            unit: unit.clone(),
+            unit_price: unit_price.to_string(),
+            source: source.clone(),
+        }),
+        PricingStructure::Tiered {
+            unit,
+            tiers,
+            source,
+        } => Structure::Tiered(v1::TieredPricing {
+            unit: unit.clone(),
+            tiers: tiers
+                .iter()
+                .map(|tier| v1::PriceTier {
+                    up_to: tier.up_to,
+                    unit_price: tier.unit_price.to_string(),
+                })
+                .collect(),
+            source: source.clone(),
+        }),
+        PricingStructure::BasePlusOverage {
+            unit,
+            included,
+            overage_price,
+            source,
+        } => Structure::BasePlusOverage(v1::OveragePricing {
+            unit: unit.clone(),
+            included: *included,
+            overage_price: overage_price.to_string(),
+            source: source.clone(),
+        }),
+    };
+    Some(v1::PricingStructure {
+        structure: Some(structure),
+    })
+}
+
+fn pricing_structure(structure: Option<v1::PricingStructure>) -> Result<PricingStructure> {
+    use v1::pricing_structure::Structure;
+    let path = "payment.structure";
+    Ok(match structure.and_then(|structure| structure.structure) {
+        None => PricingStructure::Fixed,
+        Some(Structure::Usage(usage)) => PricingStructure::Usage {
+            unit: usage.unit,
+            unit_price: parse(&usage.unit_price, path)?,
+            source: usage.source,
+        },
+        Some(Structure::Tiered(tiered)) => {
+            let mut tiers = Vec::new();
+            for tier in tiered.tiers {
+                tiers.push(PriceTier {
+                    up_to: tier.up_to,
+                    unit_price: parse(&tier.unit_price, path)?,
+                });
+            }
+            PricingStructure::Tiered {
+                unit: tiered.unit,
+                tiers,
+                source: tiered.source,
+            }
+        }
+        Some(Structure::BasePlusOverage(overage)) => PricingStructure::BasePlusOverage {
+            unit: overage.unit,
+            included: overage.included,
+            overage_price: parse(&overage.overage_price, path)?,
+            source: overage.source,
+        },
+    })
+}
+
+fn condition(condition: &ConditionDefinition) -> v1::Condition {
+    use v1::temporal_condition::Kind;
+    v1::Condition {
+        id: condition.id.clone(),
+        description: condition.description.clone(),
+        source: condition.source.clone(),
+        operator: condition.operator.clone(),
+        threshold: condition.threshold.as_ref().map(to_value),
+        expression: condition.expression.clone(),
+        temporal: condition
+            .temporal
+            .as_ref()
+            .map(|temporal| v1::TemporalCondition {
+                kind: Some(match temporal {
+                    TemporalCondition::Deadline { at, grace } => Kind::Deadline(v1::Deadline {
+                        at: at.clone(),
+                        grace: grace.clone(),
+                    }),
+                    TemporalCondition::PaymentOverdue { grace } => {
+                        Kind::PaymentOverdue(v1::PaymentOverdue {
+                            grace: grace.clone(),
+                        })
+                    }
+                    TemporalCondition::Breached { condition, grace } => {
+                        Kind::Breached(v1::Breached {
+                            condition: condition.clone(),
+                            grace: grace.clone(),
+                        })
+                    }
+                }),
+            }),
+    }
+}
+
+fn condition_definition(condition: v1::Condition) -> ConditionDefinition {
+    use v1::temporal_condition::Kind;
+    ConditionDefinition {
+        id: condition.id,
+        description: condition.description,
+        source: condition.source,
+        operator: condition.operator,
+        threshold: condition.threshold.map(from_value),
+        expression: condition.expression,
+        temporal: condition
+            .temporal
+            .and_then(|temporal| temporal.kind)
+            .map(|kind| match kind {
+                Kind::Deadline(deadline) => TemporalCondition::Deadline {
+                    at: deadline.at,
+                    grace: deadline.grace,
+                },
+                Kind::PaymentOverdue(overdue) => TemporalCondition::PaymentOverdue {
+                    grace: overdue.grace,
+                },
+                Kind::Breached(breached) => TemporalCondition::Breached {
+                    condition: breached.condition,
+                    grace: breached.grace,
+                },
+            }),
+    }
+}
+
+fn rule_conditions(conditions: &RuleConditions) -> v1::RuleConditions {
+    v1::RuleConditions {
+        all_of: conditions.all_of.clone().unwrap_or_default(),
+        any_of: conditions.any_of.clone().unwrap_or_default(),
+        n_of_m: conditions.n_of_m.as_ref().map(|quorum| v1::Quorum {
+            n: quorum.n as u64,
+            of: quorum.of.clone(),
+        }),
+    }
+}
+
+fn rule_conditions_definition(conditions: v1::RuleConditions) -> RuleConditions {
+    RuleConditions {
+        all_of: (!conditions.all_of.is_empty()).then_some(conditions.all_of),
+        any_of: (!conditions.any_of.is_empty()).then_some(conditions.any_of),
+        n_of_m: conditions.n_of_m.map(|quorum| Quorum {
+            n: usize::try_from(quorum.n).unwrap_or(usize::MAX),
+            of: quorum.of,
+        }),
+    }
+}
+
+fn oracle(oracle: &OracleDefinition) -> v1::Oracle {
+    use v1::oracle_auth::Kind;
+    v1::Oracle {
+        id: oracle.id.clone(),
+        r#type: oracle.oracle_type.clone(),
+        endpoint: oracle.endpoint.clone(),
+        address: oracle.address.clone(),
+        blockchain: oracle.blockchain.map(|network| network.to_string()),
+        event: oracle.event.clone(),
+        path: oracle.path.clone(),
+        headers: oracle.headers.clone(),
+        method: oracle.method.clone(),
+        query: oracle.query.clone(),
+        body: oracle.body.as_ref().map(to_value),
+        auth: oracle.auth.as_ref().map(|auth| v1::OracleAuth {
+            kind: Some(match auth {
+                OracleAuth::Bearer { token } => Kind::Bearer(v1::BearerAuth {
+                    token: token.clone(),
+                }),
+                OracleAuth::Basic { username, password } => Kind::Basic(v1::BasicAuth {
+                    username: username.clone(),
+                    password: password.clone(),
+                }),
+                OracleAuth::ApiKey {
+                    name,
+                    value,
+                    location,
+                } => Kind::ApiKey(v1::ApiKeyAuth {
+                    name: name.clone(),
+                    value: value.clone(),
+                    in_query: *location == ApiKeyLocation::Query,
+                }),
+            }),
+        }),
+        mapping: oracle.mapping.clone(),
+        refresh_rate: oracle.refresh_rate.clone(),
+        required: oracle.required,
+    }
+}
+
+/// JSON value as a `google.protobuf.Value`
+fn to_value(value: &serde_json::Value) -> prost_types::Value {
+    use serde_json::Value;
+    let kind = match value {
+        Value::Null => Kind::NullValue(prost_types::NullValue::NullValue as i32),
+        Value::Bool(b) => Kind::BoolValue(*b),
+        Value::Number(n) => Kind::NumberValue(n.as_f64().unwrap_or_default()),
+        Value::String(s) => Kind::StringValue(s.clone()),
+        Value::Array(items) => Kind::ListValue(prost_types::ListValue {
+            values: items.iter().map(to_value).collect(),
+        }),
+        Value::Object(fields) => Kind::StructValue(prost_types::Struct {
+            fields: fields
+                .iter()
+                .map(|(key, value)| (key.clone(), to_value(value)))
+                .collect(),
+        }),
+    };
+    prost_types::Value { kind: Some(kind) }
+}
+
+/// `google.protobuf.Value` as JSON; integral numbers that fit a float
+/// exactly become integers again
+fn from_value(value: prost_types::Value) -> serde_json::Value {
+    use serde_json::Value;
+    /// Largest integer every smaller one of which a float holds exactly
+    const EXACT: f64 = 9_007_199_254_740_992.0;
+    match value.kind {
+        None | Some(Kind::NullValue(_)) => Value::Null,
+        Some(Kind::BoolValue(b)) => Value::Bool(b),
+        Some(Kind::NumberValue(n)) if n.fract() == 0.0 && n.abs() <= EXACT => Value::from(n as i64),
+        Some(Kind::NumberValue(n)) => {
+            serde_json::Number::from_f64(n).map_or(Value::Null, Value::Number)
+        }
+        Some(Kind::StringValue(s)) => Value::String(s),
+        Some(Kind::ListValue(list)) => {
+            Value::Array(list.values.into_iter().map(from_value).collect())
+        }
+        Some(Kind::StructValue(fields)) => Value::Object(
+            fields
+                .fields
+                .into_iter()
+                .map(|(key, value)| (key, from_value(value)))
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Amount, Contract, ContractConfig, ContractDuration};
+
+    #[test]
+    fn test_protobuf_round_trip() {
+        let mut ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+        ucl.payment.amount = Amount::new(995, 1);
+        ucl.payment.token = Token::custom("ACME", 2).with_address(
+            crate::Network::Base,
+            "0x0000000000000000000000000000000000000001",
+        );
+        ucl.payment.currency = None;
+        ucl.payment.structure = PricingStructure::BasePlusOverage {
+            unit: "GB".to_string(),
+            included: 100,
+            overage_price: Amount::new(25, 2),
+            source: None,
+        };
+        ucl.conditions.required.push(ConditionDefinition {
+            id: "uptime".to_string(),
+            description: "Uptime above 99.9%".to_string(),
+            source: "uptime_oracle".to_string(),
+            operator: ">=".to_string(),
+            threshold: Some(serde_json::json!({"value": 0.999, "window": 30, "tags": [null, "x"]})),
+            expression: None,
+            temporal: Some(TemporalCondition::Breached {
+                condition: "uptime".to_string(),
+                grace: Some("1h".to_string()),
+            }),
+        });
+        ucl.termination = Some(Termination {
+            notice: Some(ContractDuration::Days(30)),
+            for_cause: vec!["uptime".to_string()],
+            outstanding: OutstandingPayments::Prorated,
+        });
+
+        let bytes = ucl.to_protobuf();
+        let decoded = UCLContract::from_protobuf(&bytes).unwrap();
+        assert_eq!(
+            serde_json::to_value(&decoded).unwrap(),
+            serde_json::to_value(&ucl).unwrap()
+        );
+        assert_eq!(
+            decoded.content_digest().unwrap(),
+            ucl.content_digest().unwrap()
+        );
+
+        let mut message = v1::UclContract::from(&ucl);
+        message.payment.as_mut().unwrap().amount = "lots".to_string();
+        let error = UCLContract::from_protobuf(&message.encode_to_vec()).unwrap_err();
+        assert!(error.to_string().contains("payment.amount"), "{}", error);
+        assert!(UCLContract::from_protobuf(b"\xff\xff").is_err());
+    }
+}
@@ -0,0 +1,479 @@
+// This file is @generated by prost-build.
+/// A UCL contract
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UclContract {
+    /// Schema version, see the SDK's migrations
+    #[prost(uint32, tag = "1")]
+    pub ucl_version: u32,
+    #[prost(string, tag = "2")]
+    pub contract_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub version: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub standard: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "5")]
+    pub summary: ::core::option::Option<Summary>,
+    #[prost(message, optional, tag = "6")]
+    pub metadata: ::core::option::Option<Metadata>,
+    #[prost(message, optional, tag = "7")]
+    pub payment: ::core::option::Option<PaymentTerms>,
+    #[prost(message, optional, tag = "8")]
+    pub conditions: ::core::option::Option<Conditions>,
+    #[prost(message, repeated, tag = "9")]
+    pub oracles: ::prost::alloc::vec::Vec<Oracle>,
+    #[prost(message, repeated, tag = "10")]
+    pub rules: ::prost::alloc::vec::Vec<Rule>,
+    #[prost(message, optional, tag = "11")]
+    pub escalation: ::core::option::Option<EscalationPolicy>,
+    #[prost(message, optional, tag = "12")]
+    pub termination: ::core::option::Option<Termination>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Summary {
+    #[prost(string, tag = "1")]
+    pub title: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub plain_english: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub what_it_does: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub who_its_for: ::prost::alloc::string::String,
+    #[prost(string, tag = "5")]
+    pub when_it_executes: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Metadata {
+    #[prost(string, tag = "1")]
+    pub r#type: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub category: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "3")]
+    pub parties: ::prost::alloc::vec::Vec<Party>,
+    #[prost(message, optional, tag = "4")]
+    pub dates: ::core::option::Option<Dates>,
+    #[prost(string, repeated, tag = "5")]
+    pub tags: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(map = "string, message", tag = "6")]
+    pub custom: ::std::collections::HashMap<::prost::alloc::string::String, ::prost_types::Value>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Party {
+    #[prost(string, tag = "1")]
+    pub role: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub identifier: ::prost::alloc::string::String,
+    #[prost(string, optional, tag = "3")]
+    pub name: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "4")]
+    pub wallet: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "5")]
+    pub did: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(message, optional, tag = "6")]
+    pub contact: ::core::option::Option<ContactChannel>,
+    #[prost(bool, tag = "7")]
+    pub verified: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ContactChannel {
+    #[prost(oneof = "contact_channel::Channel", tags = "1, 2, 3, 4")]
+    pub channel: ::core::option::Option<contact_channel::Channel>,
+}
+/// Nested message and enum types in `ContactChannel`.
+pub mod contact_channel {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Channel {
+        /// Email address
+        #[prost(string, tag = "1")]
+        Email(::prost::alloc::string::String),
+        /// Slack channel
+        #[prost(string, tag = "2")]
+        Slack(::prost::alloc::string::String),
+        /// Discord channel
+        #[prost(string, tag = "3")]
+        Discord(::prost::alloc::string::String),
+        /// Webhook URL
+        #[prost(string, tag = "4")]
+        Webhook(::prost::alloc::string::String),
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Dates {
+    /// First day of the contract term, YYYY-MM-DD
+    #[prost(string, tag = "1")]
+    pub effective: ::prost::alloc::string::String,
+    /// e.g. "12 months" or "indefinite"
+    #[prost(string, tag = "2")]
+    pub duration: ::prost::alloc::string::String,
+    /// "auto", "manual" or "none"
+    #[prost(string, tag = "3")]
+    pub renewal: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PaymentTerms {
+    /// Unset for fixed pricing
+    #[prost(message, optional, tag = "1")]
+    pub structure: ::core::option::Option<PricingStructure>,
+    /// Decimal amount, e.g. "99.5"
+    #[prost(string, tag = "2")]
+    pub amount: ::prost::alloc::string::String,
+    /// ISO 4217 code
+    #[prost(string, optional, tag = "3")]
+    pub currency: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(message, optional, tag = "4")]
+    pub token: ::core::option::Option<Token>,
+    #[prost(string, tag = "5")]
+    pub blockchain: ::prost::alloc::string::String,
+    /// e.g. "monthly" or "per-request"
+    #[prost(string, tag = "6")]
+    pub frequency: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "7")]
+    pub late_payment: ::core::option::Option<LatePaymentTerms>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Token {
+    #[prost(string, tag = "1")]
+    pub symbol: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "2")]
+    pub decimals: u32,
+    /// Contract address by network name
+    #[prost(map = "string, string", tag = "3")]
+    pub addresses: ::std::collections::HashMap<::prost::alloc::string::String, ::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PricingStructure {
+    #[prost(oneof = "pricing_structure::Structure", tags = "1, 2, 3")]
+    pub structure: ::core::option::Option<pricing_structure::Structure>,
+}
+/// Nested message and enum types in `PricingStructure`.
+pub mod pricing_structure {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Structure {
+        #[prost(message, tag = "1")]
+        Usage(super::UsagePricing),
+        #[prost(message, tag = "2")]
+        Tiered(super::TieredPricing),
+        #[prost(message, tag = "3")]
+        BasePlusOverage(super::OveragePricing),
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UsagePricing {
+    #[prost(string, tag = "1")]
+    pub unit: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub unit_price: ::prost::alloc::string::String,
+    #[prost(string, optional, tag = "3")]
+    pub source: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TieredPricing {
+    #[prost(string, tag = "1")]
+    pub unit: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "2")]
+    pub tiers: ::prost::alloc::vec::Vec<PriceTier>,
+    #[prost(string, optional, tag = "3")]
+    pub source: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PriceTier {
+    /// Unset for the final, unbounded tier
+    #[prost(uint64, optional, tag = "1")]
+    pub up_to: ::core::option::Option<u64>,
+    #[prost(string, tag = "2")]
+    pub unit_price: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct OveragePricing {
+    #[prost(string, tag = "1")]
+    pub unit: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "2")]
+    pub included: u64,
+    #[prost(string, tag = "3")]
+    pub overage_price: ::prost::alloc::string::String,
+    #[prost(string, optional, tag = "4")]
+    pub source: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LatePaymentTerms {
+    #[prost(string, optional, tag = "1")]
+    pub grace: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "2")]
+    pub fee: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(double, optional, tag = "3")]
+    pub interest: ::core::option::Option<f64>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Conditions {
+    #[prost(message, repeated, tag = "1")]
+    pub required: ::prost::alloc::vec::Vec<Condition>,
+    #[prost(message, repeated, tag = "2")]
+    pub optional: ::prost::alloc::vec::Vec<Condition>,
+    #[prost(message, repeated, tag = "3")]
+    pub groups: ::prost::alloc::vec::Vec<ConditionGroup>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Condition {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub description: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub source: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub operator: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "5")]
+    pub threshold: ::core::option::Option<::prost_types::Value>,
+    #[prost(string, optional, tag = "6")]
+    pub expression: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(message, optional, tag = "7")]
+    pub temporal: ::core::option::Option<TemporalCondition>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TemporalCondition {
+    #[prost(oneof = "temporal_condition::Kind", tags = "1, 2, 3")]
+    pub kind: ::core::option::Option<temporal_condition::Kind>,
+}
+/// Nested message and enum types in `TemporalCondition`.
+pub mod temporal_condition {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Kind {
+        #[prost(message, tag = "1")]
+        Deadline(super::Deadline),
+        #[prost(message, tag = "2")]
+        PaymentOverdue(super::PaymentOverdue),
+        #[prost(message, tag = "3")]
+        Breached(super::Breached),
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Deadline {
+    #[prost(string, tag = "1")]
+    pub at: ::prost::alloc::string::String,
+    #[prost(string, optional, tag = "2")]
+    pub grace: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PaymentOverdue {
+    #[prost(string, optional, tag = "1")]
+    pub grace: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Breached {
+    #[prost(string, tag = "1")]
+    pub condition: ::prost::alloc::string::String,
+    #[prost(string, optional, tag = "2")]
+    pub grace: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ConditionGroup {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub conditions: ::core::option::Option<RuleConditions>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RuleConditions {
+    #[prost(string, repeated, tag = "1")]
+    pub all_of: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, repeated, tag = "2")]
+    pub any_of: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(message, optional, tag = "3")]
+    pub n_of_m: ::core::option::Option<Quorum>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Quorum {
+    #[prost(uint64, tag = "1")]
+    pub n: u64,
+    #[prost(string, repeated, tag = "2")]
+    pub of: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Oracle {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub r#type: ::prost::alloc::string::String,
+    #[prost(string, optional, tag = "3")]
+    pub endpoint: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "4")]
+    pub address: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "5")]
+    pub blockchain: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "6")]
+    pub event: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "7")]
+    pub path: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(map = "string, string", tag = "8")]
+    pub headers: ::std::collections::HashMap<::prost::alloc::string::String, ::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "9")]
+    pub method: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(map = "string, string", tag = "10")]
+    pub query: ::std::collections::HashMap<::prost::alloc::string::String, ::prost::alloc::string::String>,
+    #[prost(message, optional, tag = "11")]
+    pub body: ::core::option::Option<::prost_types::Value>,
+    #[prost(message, optional, tag = "12")]
+    pub auth: ::core::option::Option<OracleAuth>,
+    #[prost(string, optional, tag = "13")]
+    pub mapping: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, tag = "14")]
+    pub refresh_rate: ::prost::alloc::string::String,
+    #[prost(bool, tag = "15")]
+    pub required: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct OracleAuth {
+    #[prost(oneof = "oracle_auth::Kind", tags = "1, 2, 3")]
+    pub kind: ::core::option::Option<oracle_auth::Kind>,
+}
+/// Nested message and enum types in `OracleAuth`.
+pub mod oracle_auth {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Kind {
+        #[prost(message, tag = "1")]
+        Bearer(super::BearerAuth),
+        #[prost(message, tag = "2")]
+        Basic(super::BasicAuth),
+        #[prost(message, tag = "3")]
+        ApiKey(super::ApiKeyAuth),
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BearerAuth {
+    #[prost(string, tag = "1")]
+    pub token: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BasicAuth {
+    #[prost(string, tag = "1")]
+    pub username: ::prost::alloc::string::String,
+    #[prost(string, optional, tag = "2")]
+    pub password: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ApiKeyAuth {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub value: ::prost::alloc::string::String,
+    /// Sent as a query parameter instead of a header
+    #[prost(bool, tag = "3")]
+    pub in_query: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Rule {
+    #[prost(string, tag = "1")]
+    pub rule_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub trigger: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "4")]
+    pub conditions: ::core::option::Option<RuleConditions>,
+    #[prost(message, repeated, tag = "5")]
+    pub actions: ::prost::alloc::vec::Vec<Action>,
+    #[prost(uint32, optional, tag = "6")]
+    pub after_failures: ::core::option::Option<u32>,
+    #[prost(string, optional, tag = "7")]
+    pub source: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Action {
+    #[prost(string, tag = "1")]
+    pub action: ::prost::alloc::string::String,
+    #[prost(map = "string, message", tag = "2")]
+    pub params: ::std::collections::HashMap<::prost::alloc::string::String, ::prost_types::Value>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct EscalationPolicy {
+    #[prost(message, repeated, tag = "1")]
+    pub steps: ::prost::alloc::vec::Vec<EscalationStep>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct EscalationStep {
+    #[prost(uint32, optional, tag = "1")]
+    pub after_failures: ::core::option::Option<u32>,
+    #[prost(string, optional, tag = "2")]
+    pub after: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, tag = "3")]
+    pub action: ::prost::alloc::string::String,
+    #[prost(string, optional, tag = "4")]
+    pub channel: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, repeated, tag = "5")]
+    pub to: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Termination {
+    /// e.g. "30 days"; unset if the contract cannot be terminated on notice
+    #[prost(string, optional, tag = "1")]
+    pub notice: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, repeated, tag = "2")]
+    pub for_cause: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(enumeration = "OutstandingPayments", tag = "3")]
+    pub outstanding: i32,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum OutstandingPayments {
+    Due = 0,
+    Prorated = 1,
+    Waived = 2,
+}
+impl OutstandingPayments {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            OutstandingPayments::Due => "OUTSTANDING_PAYMENTS_DUE",
+            OutstandingPayments::Prorated => "OUTSTANDING_PAYMENTS_PRORATED",
+            OutstandingPayments::Waived => "OUTSTANDING_PAYMENTS_WAIVED",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "OUTSTANDING_PAYMENTS_DUE" => Some(Self::Due),
+            "OUTSTANDING_PAYMENTS_PRORATED" => Some(Self::Prorated),
+            "OUTSTANDING_PAYMENTS_WAIVED" => Some(Self::Waived),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,200 @@
+//! Validated deserialization of contract documents
+//!
+//! [`UCLContract::parse_validated`] rejects documents that deserialize but
+//! break the schema, e.g. a negative amount or an empty contract id, and
+//! reports every violation with the path of the offending field.
+
+use super::migrate::MigrationRegistry;
+use crate::UCLContract;
+use std::collections::HashSet;
+use std::fmt;
+
+/// Field of a contract document that breaks the schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaViolation {
+    /// Path of the field, e.g. `"conditions.required[0].id"`
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Every schema violation of a contract document
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Invalid contract: {}", .violations.iter().map(ToString::to_string).collect::<Vec<_>>().join("; "))]
+pub struct SchemaError {
+    pub violations: Vec<SchemaViolation>,
+}
+
+impl UCLContract {
+    /// Upgrade, deserialize and validate a contract document
+    ///
+    /// Fails with a [`SchemaError`] naming the field at fault when the
+    /// document does not deserialize or when [`UCLContract::schema_violations`]
+    /// finds any.
+    pub fn parse_validated(document: serde_json::Value) -> crate::Result<Self> {
+        let document = MigrationRegistry::default().migrate(document)?;
+        let ucl: UCLContract = serde_path_to_error::deserialize(document).map_err(|e| {
+            SchemaError {
+                violations: vec![SchemaViolation {
+                    path: e.path().to_string(),
+                    message: e.inner().to_string(),
+                }],
+            }
+        })?;
+        let violations = ucl.schema_violations();
+        if violations.is_empty() {
+            Ok(ucl)
+        } else {
+            Err(SchemaError { violations }.into())
+        }
+    }
+
+    /// Fields that are well-typed but hold values the schema forbids
+    pub fn schema_violations(&self) -> Vec<SchemaViolation> {
+        let mut violations = Vec::new();
+        let mut violation = |path: String, message: String| {
+            violations.push(SchemaViolation { path, message });
+        };
+
+        if self.contract_id.trim().is_empty() {
+            violation("contract_id".to_string(), "is required".to_string());
+        }
+        if self.version.trim().is_empty() {
+            violation("version".to_string(), "is required".to_string());
+        }
+        for (i, party) in self.metadata.parties.iter().enumerate() {
+            if let Some(wallet) = party
+                .wallet
+                .as_deref()
+                .filter(|wallet| !super::is_evm_address(wallet))
+            {
+                violation(
+                    format!("metadata.parties[{}].wallet", i),
+                    format!("{:?} is not an EVM address", wallet),
+                );
+            }
+        }
+
+        if self.payment.amount.is_sign_negative() {
+            violation("payment.amount".to_string(), "must not be negative".to_string());
+        }
+        if let Some(late) = &self.payment.late_payment {
+            if late.fee.is_some_and(|fee| fee.is_sign_negative()) {
+                violation(
+                    "payment.late_payment.fee".to_string(),
+                    "must not be negative".to_string(),
+                );
+            }
+            if late.interest.is_some_and(|interest| interest.is_sign_negative()) {
+                violation(
+                    "payment.late_payment.interest".to_string(),
+                    "must not be negative".to_string(),
+                );
+            }
+        }
+
+        let mut ids = HashSet::new();
+        let conditions = self
+            .conditions
+            .required
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (format!("conditions.required[{}].id", i), c))
+            .chain(
+                self.conditions
+                    .optional
+                    .iter()
+                    .flatten()
+                    .enumerate()
+                    .map(|(i, c)| (format!("conditions.optional[{}].id", i), c)),
+            );
+        for (path, condition) in conditions {
+            if condition.id.trim().is_empty() {
+                violation(path, "is required".to_string());
+            } else if !ids.insert(condition.id.as_str()) {
+                violation(path, format!("duplicates condition {:?}", condition.id));
+            }
+        }
+        violations
+    }
+}
+
+impl TryFrom<serde_json::Value> for UCLContract {
+    type Error = crate::Error;
+
+    fn try_from(document: serde_json::Value) -> crate::Result<Self> {
+        Self::parse_validated(document)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Contract, ContractConfig};
+
+    fn document() -> serde_json::Value {
+        serde_json::to_value(
+            Contract::from_config(ContractConfig::default())
+                .unwrap()
+                .ucl,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_validated_reports_field_paths() {
+        assert!(UCLContract::try_from(document()).is_ok());
+
+        let mut malformed = document();
+        malformed["contract_id"] = "".into();
+        malformed["payment"]["amount"] = (-5).into();
+        malformed["conditions"]["required"] = serde_json::json!([
+            {"id": "uptime", "description": "", "source": "", "operator": ""},
+            {"id": "uptime", "description": "", "source": "", "operator": ""}
+        ]);
+        let error = UCLContract::parse_validated(malformed).unwrap_err();
+        let crate::Error::Schema(SchemaError { violations }) = error else {
+            panic!("expected a schema error, got {}", error);
+        };
+        let paths: Vec<_> = violations.iter().map(|v| v.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            ["contract_id", "payment.amount", "conditions.required[1].id"]
+        );
+
+        let mut mistyped = document();
+        mistyped["payment"]["frequency"] = "fortnightly".into();
+        let error = UCLContract::parse_validated(mistyped).unwrap_err().to_string();
+        assert!(error.starts_with("Invalid contract: payment.frequency: "), "{}", error);
+        assert!(error.contains("\"fortnightly\""), "{}", error);
+    }
+    #[test]
+    fn test_parse_validated_accepts_camel_case_keys() {
+        let mut document = serde_json::json!({
+            "contractId": "smart402:saas:js0001",
+            "version": "1.0",
+            "standard": "UCL-1.0",
+            "summary": {"title": "Pro plan", "plainEnglish": "Pays 99 USDC monthly"},
+            "metadata": document()["metadata"].take(),
+            "payment": document()["payment"].take(),
+            "rules": [{"ruleId": "pay", "name": "Pay", "trigger": "time_based", "actions": []}],
+        });
+        document["payment"]["latePayment"] = serde_json::json!({"fee": 5});
+
+        let mut newer = document.clone();
+        newer["uclVersion"] = 99.into();
+        assert!(UCLContract::parse_validated(newer).is_err());
+
+        let ucl = UCLContract::parse_validated(document).unwrap();
+        assert_eq!(ucl.contract_id, "smart402:saas:js0001");
+        assert_eq!(ucl.summary.plain_english, "Pays 99 USDC monthly");
+        assert_eq!(ucl.summary.what_it_does, "");
+        assert_eq!(ucl.rules[0].rule_id, "pay");
+        assert!(ucl.payment.late_payment.is_some());
+    }
+}
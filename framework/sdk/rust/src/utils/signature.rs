@@ -0,0 +1,130 @@
+//! Detached signatures of contract files
+//!
+//! A party signs the [canonical bytes](UCLContract::canonical_bytes) of a
+//! contract, so the signature holds whatever the file's format, key order
+//! or Unicode form, and breaks on any change to the terms. The signature is
+//! kept next to the contract file, by convention as `<file>.sig`, and a
+//! contract file plus the signatures of its parties make a tamper-evident
+//! record of the agreement.
+
+use crate::x402::signing::{verify_signature, SignatureError, SignatureScheme, X402Signer};
+use crate::{Result, UCLContract};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Detached signature of a contract
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContractSignature {
+    pub contract_id: String,
+    /// [Content digest](UCLContract::content_digest) of the signed contract
+    pub digest: String,
+    pub scheme: SignatureScheme,
+    /// Identity of the signing key: an address for secp256k1, a base58
+    /// public key for Ed25519
+    pub signer: String,
+    pub signature: String,
+}
+
+/// Sign the canonical bytes of `ucl` with `key`
+pub fn sign_contract(ucl: &UCLContract, key: &dyn X402Signer) -> Result<ContractSignature> {
+    Ok(ContractSignature {
+        contract_id: ucl.contract_id.clone(),
+        digest: ucl.content_digest()?,
+        scheme: key.scheme(),
+        signer: key.identity(),
+        signature: key.sign(&canonical_text(ucl)?)?,
+    })
+}
+
+/// Verify that `signer` signed `ucl` as it is now
+///
+/// Fails with a [`SignatureError`] when the contract changed since it was
+/// signed or the signature was not made by `signer`.
+pub fn verify_contract(
+    ucl: &UCLContract,
+    signature: &ContractSignature,
+    signer: &str,
+) -> Result<()> {
+    if signature.digest != ucl.content_digest()? {
+        return Err(SignatureError::Invalid {
+            reason: format!("{} was changed after it was signed", signature.contract_id),
+        }
+        .into());
+    }
+    verify_signature(
+        signature.scheme,
+        &canonical_text(ucl)?,
+        &signature.signature,
+        signer,
+    )?;
+    Ok(())
+}
+
+/// Where the signature of a contract file is kept by default: `<file>.sig`
+pub fn signature_path(contract_path: &Path) -> PathBuf {
+    let mut path = contract_path.as_os_str().to_owned();
+    path.push(".sig");
+    PathBuf::from(path)
+}
+
+/// Save a signature to file as JSON
+pub fn save_signature(signature: &ContractSignature, path: &Path) -> Result<()> {
+    fs::write(path, serde_json::to_string_pretty(signature)?)?;
+    Ok(())
+}
+
+/// Load a signature from file
+pub fn load_signature(path: &Path) -> Result<ContractSignature> {
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+fn canonical_text(ucl: &UCLContract) -> Result<String> {
+    Ok(String::from_utf8(ucl.canonical_bytes()?).expect("canonical JSON is UTF-8"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::x402::{Ed25519Signer, Secp256k1Signer};
+    use crate::{Amount, Contract, ContractConfig};
+
+    const TEST_KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    #[test]
+    fn test_detached_signature() {
+        let ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+        let payer = Secp256k1Signer::from_private_key(TEST_KEY).unwrap();
+        let payee = Ed25519Signer::from_secret(&"01".repeat(32)).unwrap();
+
+        // Signatures survive a round trip through another file format
+        let dir = std::env::temp_dir().join(format!("smart402-sig-{:016x}", rand::random::<u64>()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("contract.yaml");
+        crate::utils::save_contract(&ucl, &path, "yaml").unwrap();
+        let signature =
+            sign_contract(&crate::utils::load_contract(&path).unwrap(), &payer).unwrap();
+        save_signature(&signature, &signature_path(&path)).unwrap();
+        let signature = load_signature(&dir.join("contract.yaml.sig")).unwrap();
+        verify_contract(&ucl, &signature, &payer.address()).unwrap();
+        fs::remove_dir_all(dir).unwrap();
+
+        let error = verify_contract(&ucl, &signature, &payee.public_key()).unwrap_err();
+        assert_eq!(error.code(), "signature.signer_mismatch");
+
+        let signature = sign_contract(&ucl, &payee).unwrap();
+        verify_contract(&ucl, &signature, &payee.public_key()).unwrap();
+        let mut tampered = ucl.clone();
+        tampered.payment.amount = Amount::from(1);
+        let error = verify_contract(&tampered, &signature, &payee.public_key()).unwrap_err();
+        assert_eq!(error.code(), "signature.invalid");
+        // A forged digest still fails on the signature itself
+        let forged = ContractSignature {
+            digest: tampered.content_digest().unwrap(),
+            ..signature
+        };
+        assert!(verify_contract(&tampered, &forged, &payee.public_key()).is_err());
+    }
+}
@@ -0,0 +1,78 @@
+//! Batched payment submission
+
+use super::client::{PaymentResponse, X402Client, X402Headers};
+use crate::Result;
+use futures::stream::{self, StreamExt};
+use std::collections::HashMap;
+
+/// Default number of payments in flight at once
+pub const DEFAULT_BATCH_CONCURRENCY: usize = 16;
+
+/// A signed payment waiting to be submitted
+#[derive(Debug, Clone)]
+pub struct PaymentRequest {
+    pub headers: X402Headers,
+    pub payload: HashMap<String, String>,
+}
+
+impl PaymentRequest {
+    pub fn new(headers: X402Headers, payload: HashMap<String, String>) -> Self {
+        Self { headers, payload }
+    }
+}
+
+impl X402Client {
+    /// Submit many payments, pipelined over the client's connection pool
+    ///
+    /// Returns one outcome per request, in request order. A failed payment
+    /// does not stop the others; each is retried according to the client's
+    /// [`RetryPolicy`](super::RetryPolicy).
+    pub async fn send_batch(&self, requests: Vec<PaymentRequest>) -> Vec<Result<PaymentResponse>> {
+        self.send_batch_with_concurrency(requests, DEFAULT_BATCH_CONCURRENCY)
+            .await
+    }
+
+    /// Submit many payments with at most `concurrency` in flight
+    pub async fn send_batch_with_concurrency(
+        &self,
+        requests: Vec<PaymentRequest>,
+        concurrency: usize,
+    ) -> Vec<Result<PaymentResponse>> {
+        stream::iter(requests)
+            .map(|request| self.send_payment_request(request.headers, request.payload))
+            .buffered(concurrency.max(1))
+            .collect()
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::x402::testing::MockFacilitator;
+    use crate::{Contract, ContractConfig};
+
+    const TEST_KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    #[tokio::test]
+    async fn test_send_batch_reports_each_outcome() {
+        let facilitator = MockFacilitator::start().await.unwrap();
+        let client = X402Client::with_private_key(facilitator.url().to_string(), TEST_KEY).unwrap();
+        let ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+
+        let mut requests: Vec<_> = (0..5)
+            .map(|_| {
+                PaymentRequest::new(client.generate_headers(&ucl, true).unwrap(), HashMap::new())
+            })
+            .collect();
+        requests[2].headers.payment_amount = "1000".to_string();
+
+        let outcomes = client.send_batch_with_concurrency(requests, 2).await;
+        assert_eq!(outcomes.len(), 5);
+        assert!(outcomes[2].is_err());
+        assert_eq!(outcomes.iter().filter(|o| o.is_ok()).count(), 4);
+        assert_eq!(facilitator.payments().len(), 4);
+    }
+}
@@ -0,0 +1,248 @@
+//! Builder for [`X402Client`]
+
+use super::client::{X402Client, DEFAULT_POLL_INTERVAL};
+use super::policy::SpendingPolicy;
+use super::retry::RetryPolicy;
+use super::signing::{Ed25519Signer, Secp256k1Signer, X402Signer};
+use super::verify::PaymentVerifier;
+use crate::Result;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Default facilitator endpoint
+pub const DEFAULT_ENDPOINT: &str = "https://x402.smart402.io";
+
+enum SignerConfig {
+    Secp256k1(String),
+    Ed25519(String),
+    Custom(Arc<dyn X402Signer>),
+}
+
+/// Configures HTTP behavior, signing and retries of an [`X402Client`]
+///
+/// ```no_run
+/// use smart402::X402Client;
+/// use std::time::Duration;
+///
+/// # fn main() -> smart402::Result<()> {
+/// let client = X402Client::builder()
+///     .endpoint("https://x402.smart402.io")
+///     .private_key("0x...")
+///     .timeout(Duration::from_secs(10))
+///     .pool_max_idle_per_host(32)
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Default)]
+pub struct X402ClientBuilder {
+    endpoint: Option<String>,
+    signer: Option<SignerConfig>,
+    http: Option<reqwest::Client>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    pool_max_idle_per_host: Option<usize>,
+    pool_idle_timeout: Option<Duration>,
+    proxy: Option<String>,
+    user_agent: Option<String>,
+    retry: Option<RetryPolicy>,
+    poll_interval: Option<Duration>,
+    verifier: Option<PaymentVerifier>,
+    policy: Option<SpendingPolicy>,
+}
+
+impl X402ClientBuilder {
+    /// Facilitator endpoint (defaults to [`DEFAULT_ENDPOINT`])
+    pub fn endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = Some(endpoint.into());
+        self
+    }
+
+    /// Sign with a hex-encoded secp256k1 private key
+    pub fn private_key(mut self, private_key: impl Into<String>) -> Self {
+        self.signer = Some(SignerConfig::Secp256k1(private_key.into()));
+        self
+    }
+
+    /// Sign with an Ed25519 (Solana) secret key
+    pub fn ed25519_key(mut self, secret: impl Into<String>) -> Self {
+        self.signer = Some(SignerConfig::Ed25519(secret.into()));
+        self
+    }
+
+    /// Sign with a custom signer
+    pub fn signer(mut self, signer: Arc<dyn X402Signer>) -> Self {
+        self.signer = Some(SignerConfig::Custom(signer));
+        self
+    }
+
+    /// Reuse an existing HTTP client and its connection pool
+    ///
+    /// Cannot be combined with the timeout, pool, proxy or user agent
+    /// options, which configure a client the builder creates itself.
+    pub fn http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = Some(http);
+        self
+    }
+
+    /// Total timeout of each HTTP request
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Timeout for establishing connections
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Maximum idle connections kept per host
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// How long idle connections are kept open
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Route all requests through a proxy URL
+    pub fn proxy(mut self, url: impl Into<String>) -> Self {
+        self.proxy = Some(url.into());
+        self
+    }
+
+    /// User agent sent with every request
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Retry policy for facilitator requests
+    pub fn retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// Spending limits enforced before signing any payment
+    pub fn spending_policy(mut self, policy: SpendingPolicy) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Interval between settlement status polls
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = Some(interval);
+        self
+    }
+
+    /// Verifier used for incoming payments
+    pub fn verifier(mut self, verifier: PaymentVerifier) -> Self {
+        self.verifier = Some(verifier);
+        self
+    }
+
+    /// Build the client
+    pub fn build(self) -> Result<X402Client> {
+        let http = match self.http {
+            Some(http) => {
+                if self.timeout.is_some()
+                    || self.connect_timeout.is_some()
+                    || self.pool_max_idle_per_host.is_some()
+                    || self.pool_idle_timeout.is_some()
+                    || self.proxy.is_some()
+                    || self.user_agent.is_some()
+                {
+                    return Err(crate::Error::ConfigError(
+                        "HTTP options cannot be combined with an injected http_client".to_string(),
+                    ));
+                }
+                http
+            }
+            None => {
+                let mut http = reqwest::Client::builder().user_agent(
+                    self.user_agent
+                        .unwrap_or_else(|| format!("smart402-rust/{}", crate::VERSION)),
+                );
+                if let Some(timeout) = self.timeout {
+                    http = http.timeout(timeout);
+                }
+                if let Some(timeout) = self.connect_timeout {
+                    http = http.connect_timeout(timeout);
+                }
+                if let Some(max) = self.pool_max_idle_per_host {
+                    http = http.pool_max_idle_per_host(max);
+                }
+                if let Some(timeout) = self.pool_idle_timeout {
+                    http = http.pool_idle_timeout(timeout);
+                }
+                if let Some(proxy) = &self.proxy {
+                    http = http.proxy(reqwest::Proxy::all(proxy)?);
+                }
+                http.build()?
+            }
+        };
+
+        let signer: Option<Arc<dyn X402Signer>> = match self.signer {
+            None => None,
+            Some(SignerConfig::Secp256k1(key)) => {
+                Some(Arc::new(Secp256k1Signer::from_private_key(&key)?))
+            }
+            Some(SignerConfig::Ed25519(secret)) => {
+                Some(Arc::new(Ed25519Signer::from_secret(&secret)?))
+            }
+            Some(SignerConfig::Custom(signer)) => Some(signer),
+        };
+
+        Ok(X402Client {
+            endpoint: self
+                .endpoint
+                .unwrap_or_else(|| DEFAULT_ENDPOINT.to_string()),
+            signer,
+            verifier: self.verifier.unwrap_or_default(),
+            http,
+            poll_interval: self.poll_interval.unwrap_or(DEFAULT_POLL_INTERVAL),
+            retry: self.retry.unwrap_or_default(),
+            policy: self.policy.map(Arc::new),
+        })
+    }
+}
+
+impl X402Client {
+    /// Start configuring a client
+    pub fn builder() -> X402ClientBuilder {
+        X402ClientBuilder::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_configures_client() {
+        let client = X402Client::builder()
+            .private_key("0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318")
+            .timeout(Duration::from_secs(5))
+            .pool_max_idle_per_host(8)
+            .build()
+            .unwrap();
+        assert_eq!(
+            client.url("payments"),
+            format!("{}/payments", DEFAULT_ENDPOINT)
+        );
+        assert!(client.signer_address().is_some());
+    }
+
+    #[test]
+    fn test_injected_client_rejects_http_options() {
+        let result = X402Client::builder()
+            .http_client(reqwest::Client::new())
+            .timeout(Duration::from_secs(5))
+            .build();
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,237 @@
+//! HTTP 402 challenges
+//!
+//! [`PaymentRequired`] describes the payment a request must carry. The
+//! paywall middleware emits it automatically; servers without middleware
+//! can build one with [`PaymentRequired::for_contract`] and write its
+//! status, headers and body with whatever framework they use.
+
+use super::fiat::{FiatOption, FIAT_HEADER};
+use super::price::{PriceOption, PRICES_HEADER};
+use super::standard::{PaymentRequirements, X402_VERSION};
+use super::verify::VerificationError;
+use crate::UCLContract;
+use http::{HeaderValue, Response, StatusCode};
+use std::sync::Arc;
+
+/// Party roles that receive payments, in order of preference
+pub(crate) const PAYEE_ROLES: &[&str] = &["payee", "vendor", "seller", "provider", "recipient"];
+
+/// Party roles that make payments, in order of preference
+pub(crate) const PAYER_ROLES: &[&str] = &["payer", "customer", "client", "buyer", "tenant"];
+
+/// HTTP 402 response listing the payment a request must carry
+#[derive(Debug, Clone)]
+pub struct PaymentRequired {
+    ucl: Arc<UCLContract>,
+    reason: Option<Box<VerificationError>>,
+    pay_to: Option<String>,
+    contract_url: Option<String>,
+    alternatives: Box<Alternatives>,
+}
+
+/// Other ways to pay advertised next to the contract terms
+#[derive(Debug, Clone, Default)]
+struct Alternatives {
+    /// Alternative prices advertised besides the contract terms
+    prices: Vec<PriceOption>,
+    /// Card or PayPal checkouts for payers without crypto
+    fiat: Vec<FiatOption>,
+    /// Standard x402 requirements advertised alongside the X402 headers
+    accepts: Vec<PaymentRequirements>,
+}
+
+impl PaymentRequired {
+    /// Challenge for a contract's payment terms
+    ///
+    /// The pay-to address defaults to the wallet, or else the identifier, of
+    /// the contract's payee party (role "payee", "vendor", "seller", ...).
+    pub fn for_contract(ucl: &UCLContract) -> Self {
+        Self::from_shared(&Arc::new(ucl.clone()))
+    }
+
+    pub(crate) fn from_shared(ucl: &Arc<UCLContract>) -> Self {
+        let pay_to = ucl
+            .metadata
+            .party(PAYEE_ROLES)
+            .map(|p| p.wallet_address().unwrap_or(&p.identifier).to_string());
+        Self {
+            ucl: Arc::clone(ucl),
+            reason: None,
+            pay_to,
+            contract_url: None,
+            alternatives: Box::default(),
+        }
+    }
+
+    /// Address the payment must be sent to
+    pub fn with_pay_to(mut self, address: impl Into<String>) -> Self {
+        self.pay_to = Some(address.into());
+        self
+    }
+
+    /// Link where the payer can read the contract
+    pub fn with_contract_url(mut self, url: impl Into<String>) -> Self {
+        self.contract_url = Some(url.into());
+        self
+    }
+
+    /// Also accept payment in another token, network or amount
+    pub fn with_price_option(mut self, price: PriceOption) -> Self {
+        self.alternatives.prices.push(price);
+        self
+    }
+
+    /// Also offer a fiat checkout
+    pub fn with_fiat_option(mut self, option: FiatOption) -> Self {
+        self.alternatives.fiat.push(option);
+        self
+    }
+
+    /// Why an attached payment was rejected
+    pub fn with_reason(mut self, reason: VerificationError) -> Self {
+        self.reason = Some(Box::new(reason));
+        self
+    }
+
+    /// Also advertise a standard x402 payment option
+    pub fn with_requirements(mut self, requirements: PaymentRequirements) -> Self {
+        self.alternatives.accepts.push(requirements);
+        self
+    }
+
+    /// Why the attached payment was rejected, if one was attached
+    pub fn reason(&self) -> Option<&VerificationError> {
+        self.reason.as_deref()
+    }
+
+    /// HTTP status of the challenge (always 402)
+    pub fn status(&self) -> StatusCode {
+        StatusCode::PAYMENT_REQUIRED
+    }
+
+    /// Contract address of the payment token on the settlement network
+    fn asset(&self) -> Option<&str> {
+        self.ucl.payment.token.address(self.ucl.payment.blockchain)
+    }
+
+    /// Headers describing the required payment
+    pub fn headers(&self) -> Vec<(&'static str, String)> {
+        let mut headers = vec![
+            ("X402-Contract-ID", self.ucl.contract_id.clone()),
+            ("X402-Payment-Amount", self.ucl.payment.amount.to_string()),
+            ("X402-Payment-Token", self.ucl.payment.token.to_string()),
+            (
+                "X402-Settlement-Network",
+                self.ucl.payment.blockchain.to_string(),
+            ),
+        ];
+        if let Some(asset) = self.asset() {
+            headers.push(("X402-Payment-Asset", asset.to_string()));
+        }
+        if let Some(pay_to) = &self.pay_to {
+            headers.push(("X402-Pay-To", pay_to.clone()));
+        }
+        if !self.alternatives.prices.is_empty() {
+            headers.push((
+                PRICES_HEADER,
+                PriceOption::format_header(&self.alternatives.prices),
+            ));
+        }
+        if let Some(fiat) = self.alternatives.fiat.first() {
+            headers.push((FIAT_HEADER, fiat.url.clone()));
+        }
+        if let Some(url) = &self.contract_url {
+            headers.push(("X402-Contract-URL", url.clone()));
+        }
+        headers
+    }
+
+    /// Machine-readable JSON body
+    pub fn body(&self) -> serde_json::Value {
+        let mut body = serde_json::json!({
+            "error": "payment_required",
+            "reason": self.reason.as_ref().map(ToString::to_string),
+            "contract_id": self.ucl.contract_id,
+            "amount": self.ucl.payment.amount.to_string(),
+            "token": self.ucl.payment.token.symbol(),
+            "decimals": self.ucl.payment.token.decimals(),
+            "asset": self.asset(),
+            "network": self.ucl.payment.blockchain,
+            "frequency": self.ucl.payment.frequency,
+            "pay_to": self.pay_to,
+            "contract_url": self.contract_url,
+        });
+        if !self.alternatives.prices.is_empty() {
+            let options: Vec<_> = std::iter::once(PriceOption::for_contract(&self.ucl))
+                .chain(self.alternatives.prices.iter().cloned())
+                .collect();
+            body["options"] = serde_json::json!(options);
+        }
+        if !self.alternatives.fiat.is_empty() {
+            body["fiat"] = serde_json::json!(self.alternatives.fiat);
+        }
+        if !self.alternatives.accepts.is_empty() {
+            body["x402Version"] = X402_VERSION.into();
+            body["accepts"] = serde_json::json!(self.alternatives.accepts);
+        }
+        body
+    }
+
+    /// Build the 402 response with any body type constructible from a string
+    pub fn into_http_response<B: From<String>>(self) -> Response<B> {
+        let mut response = Response::new(B::from(self.body().to_string()));
+        *response.status_mut() = self.status();
+        let headers = response.headers_mut();
+        headers.insert(
+            http::header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        );
+        for (name, value) in self.headers() {
+            if let Ok(value) = HeaderValue::from_str(&value) {
+                headers.insert(name, value);
+            }
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PartyInfo;
+    use crate::{Amount, Contract, ContractConfig};
+
+    #[test]
+    fn test_challenge_for_contract() {
+        let mut ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+        ucl.payment.amount = Amount::new(5, 2);
+        ucl.metadata.parties.push(PartyInfo {
+            role: "vendor".to_string(),
+            identifier: "0x209693Bc6afc0C5328bA36FaF03C514EF312287C".to_string(),
+            name: None,
+            ..Default::default()
+        });
+
+        let challenge =
+            PaymentRequired::for_contract(&ucl).with_contract_url("https://api.test/contracts/1");
+        let body = challenge.body();
+        assert_eq!(body["amount"], "0.05");
+        assert_eq!(body["pay_to"], "0x209693Bc6afc0C5328bA36FaF03C514EF312287C");
+        assert_eq!(body["decimals"], 6);
+        assert_eq!(body["asset"], "0x3c499c542cEF5E3811e1192ce70d8cC03d5c3359");
+        assert!(body.get("accepts").is_none());
+
+        let response: Response<String> = challenge.into_http_response();
+        assert_eq!(response.status(), StatusCode::PAYMENT_REQUIRED);
+        assert_eq!(
+            response.headers()["X402-Contract-URL"],
+            "https://api.test/contracts/1"
+        );
+        assert_eq!(
+            response.headers()["X402-Payment-Asset"],
+            "0x3c499c542cEF5E3811e1192ce70d8cC03d5c3359"
+        );
+    }
+}
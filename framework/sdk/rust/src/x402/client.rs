@@ -1,7 +1,34 @@
 //! X402 HTTP client
 
-use crate::{Result, UCLContract};
+use super::nonce::{generate_nonce, nonce_timestamp};
+use super::policy::SpendingPolicy;
+use super::price::PriceOption;
+use super::retry::{is_transient, is_unavailable, retry_after, RetryPolicy};
+use super::signing::{
+    verify_signature, Ed25519Signer, Secp256k1Signer, SignatureScheme, X402Signer,
+};
+use super::verify::{PaymentVerifier, VerificationError};
+use crate::{Amount, Result, UCLContract};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Default interval between settlement status polls
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Names of the headers making up an X402 payment
+pub const HEADER_NAMES: [&str; 9] = [
+    "X402-Contract-ID",
+    "X402-Payment-Amount",
+    "X402-Payment-Token",
+    "X402-Settlement-Network",
+    "X402-Conditions-Met",
+    "X402-Signature",
+    "X402-Signature-Scheme",
+    "X402-Signer",
+    "X402-Nonce",
+];
 
 /// X402 HTTP headers
 #[derive(Debug, Clone)]
@@ -12,10 +39,146 @@ pub struct X402Headers {
     pub settlement_network: String,
     pub conditions_met: String,
     pub signature: String,
+    pub signature_scheme: SignatureScheme,
+    /// Signer identity: EVM address or base58 Ed25519 public key
+    pub signer: String,
     pub nonce: String,
+    /// [Content digest](UCLContract::content_digest) of the contract,
+    /// binding the payment to its exact terms; optional on the wire
+    pub contract_digest: Option<String>,
 }
 
 impl X402Headers {
+    /// Canonical payload covered by the signature
+    ///
+    /// One `Name:value` line per signed header in a fixed order, so both
+    /// sides derive identical bytes regardless of transport header order.
+    /// The contract digest line is only present when the digest is.
+    pub fn signing_payload(&self) -> String {
+        let payload = format!(
+            "X402-Contract-ID:{}\nX402-Payment-Amount:{}\nX402-Payment-Token:{}\n\
+             X402-Settlement-Network:{}\nX402-Conditions-Met:{}\nX402-Nonce:{}\n\
+             X402-Signature-Scheme:{}\nX402-Signer:{}",
+            self.contract_id,
+            self.payment_amount,
+            self.payment_token,
+            self.settlement_network,
+            self.conditions_met,
+            self.nonce,
+            self.signature_scheme,
+            self.signer
+        );
+        match &self.contract_digest {
+            Some(digest) => format!("{}\nX402-Contract-Digest:{}", payload, digest),
+            None => payload,
+        }
+    }
+
+    /// Verify the signature and return the signer identity
+    pub fn verify_signature(&self) -> Result<String> {
+        verify_signature(
+            self.signature_scheme,
+            &self.signing_payload(),
+            &self.signature,
+            &self.signer,
+        )
+    }
+
+    /// Parse headers from an HTTP header map
+    ///
+    /// Header names are matched case-insensitively. All payment headers are
+    /// required; the amount must be a non-negative decimal, the nonce must
+    /// start with a unix timestamp and the signature scheme must be known.
+    pub fn from_headers(headers: &http::HeaderMap) -> std::result::Result<Self, VerificationError> {
+        Self::parse(|name| {
+            headers
+                .get(name)
+                .map(|v| {
+                    v.to_str()
+                        .map(str::to_string)
+                        .map_err(|_| malformed(name, "not valid UTF-8"))
+                })
+                .transpose()
+        })
+    }
+
+    /// Parse headers from a name/value map, the inverse of [`X402Headers::to_map`]
+    pub fn from_map(map: &HashMap<String, String>) -> std::result::Result<Self, VerificationError> {
+        Self::parse(|name| {
+            Ok(map
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.clone()))
+        })
+    }
+
+    fn parse(
+        lookup: impl Fn(&str) -> std::result::Result<Option<String>, VerificationError>,
+    ) -> std::result::Result<Self, VerificationError> {
+        let get = |name: &str| -> std::result::Result<String, VerificationError> {
+            let value = lookup(name)?
+                .map(|v| v.trim().to_string())
+                .ok_or_else(|| VerificationError::MissingHeader(name.to_string()))?;
+            if value.is_empty() {
+                return Err(malformed(name, "empty value"));
+            }
+            Ok(value)
+        };
+
+        let payment_amount = get("X402-Payment-Amount")?;
+        match payment_amount.parse::<Amount>() {
+            Ok(amount) if !amount.is_sign_negative() => {}
+            _ => {
+                return Err(malformed(
+                    "X402-Payment-Amount",
+                    &format!("not a non-negative decimal: {}", payment_amount),
+                ))
+            }
+        }
+
+        let conditions_met = get("X402-Conditions-Met")?;
+        if conditions_met.parse::<bool>().is_err() {
+            return Err(malformed(
+                "X402-Conditions-Met",
+                &format!("expected true or false, got {}", conditions_met),
+            ));
+        }
+
+        let nonce = get("X402-Nonce")?;
+        if nonce_timestamp(&nonce).is_none() {
+            return Err(malformed(
+                "X402-Nonce",
+                &format!("does not start with a unix timestamp: {}", nonce),
+            ));
+        }
+
+        let signature_scheme = get("X402-Signature-Scheme")?
+            .parse()
+            .map_err(|e: crate::Error| malformed("X402-Signature-Scheme", &e.to_string()))?;
+
+        Ok(Self {
+            contract_id: get("X402-Contract-ID")?,
+            payment_amount,
+            payment_token: get("X402-Payment-Token")?,
+            settlement_network: get("X402-Settlement-Network")?,
+            conditions_met,
+            signature: get("X402-Signature")?,
+            signature_scheme,
+            signer: get("X402-Signer")?,
+            nonce,
+            contract_digest: lookup("X402-Contract-Digest")?
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty()),
+        })
+    }
+
+    /// Idempotency key of the payment, identical across retries of the same headers
+    pub fn idempotency_key(&self) -> String {
+        use sha2::{Digest, Sha256};
+        let digest = Sha256::digest(format!("{}\n{}", self.signing_payload(), self.signature));
+        hex::encode(&digest[..16])
+    }
+
     /// Convert to HashMap
     pub fn to_map(&self) -> HashMap<String, String> {
         let mut map = HashMap::new();
@@ -37,86 +200,386 @@ impl X402Headers {
             self.conditions_met.clone(),
         );
         map.insert("X402-Signature".to_string(), self.signature.clone());
+        map.insert(
+            "X402-Signature-Scheme".to_string(),
+            self.signature_scheme.to_string(),
+        );
+        map.insert("X402-Signer".to_string(), self.signer.clone());
         map.insert("X402-Nonce".to_string(), self.nonce.clone());
+        if let Some(digest) = &self.contract_digest {
+            map.insert("X402-Contract-Digest".to_string(), digest.clone());
+        }
         map
     }
 }
 
+fn malformed(header: &str, reason: &str) -> VerificationError {
+    VerificationError::MalformedHeader {
+        header: header.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
 /// X402 Client
 pub struct X402Client {
-    endpoint: String,
+    pub(crate) endpoint: String,
+    pub(crate) signer: Option<Arc<dyn X402Signer>>,
+    pub(crate) verifier: PaymentVerifier,
+    pub(crate) http: reqwest::Client,
+    pub(crate) poll_interval: Duration,
+    pub(crate) retry: RetryPolicy,
+    pub(crate) policy: Option<Arc<SpendingPolicy>>,
 }
 
 impl X402Client {
     /// Create new X402 client
     pub fn new(endpoint: String) -> Self {
-        Self { endpoint }
+        Self {
+            endpoint,
+            signer: None,
+            verifier: PaymentVerifier::new(),
+            http: reqwest::Client::new(),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            retry: RetryPolicy::default(),
+            policy: None,
+        }
+    }
+
+    /// Create X402 client that signs headers with the given secp256k1 private key
+    pub fn with_private_key(endpoint: String, private_key: &str) -> Result<Self> {
+        Ok(Self::with_signer(
+            endpoint,
+            Arc::new(Secp256k1Signer::from_private_key(private_key)?),
+        ))
+    }
+
+    /// Create X402 client that signs headers with an Ed25519 (Solana) key
+    pub fn with_ed25519_key(endpoint: String, secret: &str) -> Result<Self> {
+        Ok(Self::with_signer(
+            endpoint,
+            Arc::new(Ed25519Signer::from_secret(secret)?),
+        ))
+    }
+
+    /// Create X402 client with a custom signer
+    pub fn with_signer(endpoint: String, signer: Arc<dyn X402Signer>) -> Self {
+        Self {
+            endpoint,
+            signer: Some(signer),
+            verifier: PaymentVerifier::new(),
+            http: reqwest::Client::new(),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            retry: RetryPolicy::default(),
+            policy: None,
+        }
+    }
+
+    /// Identity of the signing key, if configured
+    pub fn signer_address(&self) -> Option<String> {
+        self.signer.as_ref().map(|s| s.identity())
+    }
+
+    /// Signature scheme of the signing key, if configured
+    pub fn signature_scheme(&self) -> Option<SignatureScheme> {
+        self.signer.as_ref().map(|s| s.scheme())
     }
 
     /// Generate X402 headers for contract
+    ///
+    /// Requires a signer; headers are signed over
+    /// `X402Headers::signing_payload` with the signer's scheme.
+    ///
+    /// Fails with a `PolicyViolation` if the client's spending policy
+    /// forbids the payment.
     pub fn generate_headers(&self, ucl: &UCLContract, conditions_met: bool) -> Result<X402Headers> {
-        let nonce = Self::generate_nonce();
-        let signature = self.generate_signature(ucl, &nonce)?;
+        self.generate_headers_with_price(ucl, conditions_met, &PriceOption::for_contract(ucl))
+    }
+
+    /// Generate X402 headers paying with a negotiated price option
+    ///
+    /// Like [`X402Client::generate_headers`], but signs the option's amount,
+    /// token and network instead of the contract's own terms.
+    pub fn generate_headers_with_price(
+        &self,
+        ucl: &UCLContract,
+        conditions_met: bool,
+        price: &PriceOption,
+    ) -> Result<X402Headers> {
+        let signer = self.signer.as_ref().ok_or_else(|| {
+            crate::Error::ConfigError(
+                "X402Client has no private key; use X402Client::with_private_key".to_string(),
+            )
+        })?;
 
-        Ok(X402Headers {
+        let amount = price.amount.parse().map_err(|_| {
+            crate::Error::PaymentError(format!("Invalid payment amount: {}", price.amount))
+        })?;
+        self.authorize_spend(&ucl.contract_id, amount)?;
+
+        let mut headers = X402Headers {
             contract_id: ucl.contract_id.clone(),
-            payment_amount: ucl.payment.amount.to_string(),
-            payment_token: ucl.payment.token.clone(),
-            settlement_network: ucl.payment.blockchain.clone(),
+            payment_amount: price.amount.clone(),
+            payment_token: price.token.clone(),
+            settlement_network: price.network.clone(),
             conditions_met: conditions_met.to_string(),
-            signature,
-            nonce,
-        })
+            signature: String::new(),
+            signature_scheme: signer.scheme(),
+            signer: signer.identity(),
+            nonce: generate_nonce(),
+            contract_digest: Some(ucl.content_digest()?),
+        };
+        headers.signature = signer.sign(&headers.signing_payload())?;
+
+        Ok(headers)
+    }
+
+    /// Set interval between settlement status polls
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
     }
 
-    /// Send payment request
+    /// Submit a signed payment to the facilitator
+    ///
+    /// POSTs `payload` as JSON to `{endpoint}/payments` with the X402 headers
+    /// attached. The returned reference can be passed to
+    /// [`X402Client::await_settlement`].
+    #[tracing::instrument(
+        name = "x402_payment",
+        skip_all,
+        fields(
+            contract_id = %headers.contract_id,
+            network = %headers.settlement_network,
+            endpoint = %self.endpoint,
+            tx_hash,
+        )
+    )]
     pub async fn send_payment_request(
         &self,
         headers: X402Headers,
-        _payload: HashMap<String, String>,
+        payload: HashMap<String, String>,
     ) -> Result<PaymentResponse> {
-        // Placeholder - would make actual HTTP request
-        Ok(PaymentResponse {
-            status: "accepted".to_string(),
-            transaction_hash: Some("0xabc123".to_string()),
-            confirmation_url: Some(format!("{}/confirm", self.endpoint)),
-        })
-    }
+        let idempotency_key = headers.idempotency_key();
+        let mut attempt = 1;
+        loop {
+            let mut request = self
+                .http
+                .post(self.url("payments"))
+                .header("Idempotency-Key", &idempotency_key)
+                .json(&payload);
+            for (name, value) in headers.to_map() {
+                request = request.header(name, value);
+            }
 
-    /// Verify X402 response
-    pub fn verify_response(&self, _headers: &HashMap<String, String>) -> Result<bool> {
-        // Placeholder - would verify signature
-        Ok(true)
-    }
+            let retries_left = attempt < self.retry.max_attempts;
+            let mut wait = None;
+            match request.send().await {
+                Ok(response) if response.status().is_success() => {
+                    let payment: PaymentResponse = response.json().await?;
+                    if let Some(tx_hash) = &payment.transaction_hash {
+                        tracing::Span::current().record("tx_hash", tx_hash.as_str());
+                    }
+                    tracing::info!(attempt, status = %payment.status, "payment submitted");
+                    return Ok(payment);
+                }
+                Ok(response) if retries_left && is_unavailable(response.status()) => {
+                    tracing::warn!(attempt, status = %response.status(), "facilitator unavailable, retrying");
+                    wait = retry_after(&response);
+                }
+                Ok(response) if is_unavailable(response.status()) => {
+                    let status = response.status();
+                    tracing::warn!(attempt, status = %status, "facilitator unavailable");
+                    return Err(crate::Error::Unavailable {
+                        retry_after: retry_after(&response),
+                        reason: format!(
+                            "Facilitator answered {} after {} attempt(s)",
+                            status, attempt
+                        ),
+                    });
+                }
+                Ok(response) => {
+                    let status = response.status();
+                    let body = response.text().await.unwrap_or_default();
+                    tracing::warn!(attempt, status = %status, "facilitator rejected payment");
+                    return Err(crate::Error::PaymentError(format!(
+                        "Facilitator rejected payment ({}): {}",
+                        status, body
+                    )));
+                }
+                Err(e) if retries_left && is_transient(&e) => {
+                    tracing::warn!(attempt, error = %e, "facilitator unreachable, retrying");
+                }
+                Err(e) => {
+                    tracing::warn!(attempt, error = %e, "payment request failed");
+                    return Err(e.into());
+                }
+            }
 
-    fn generate_nonce() -> String {
-        use std::time::{SystemTime, UNIX_EPOCH};
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        format!("{}", timestamp)
+            // Honour Retry-After, capped like any other backoff
+            let backoff = self.retry.backoff(attempt);
+            let delay = wait.map_or(backoff, |wait| wait.clamp(backoff, self.retry.max_backoff));
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
     }
 
-    fn generate_signature(&self, ucl: &UCLContract, nonce: &str) -> Result<String> {
-        // Placeholder - would generate actual cryptographic signature
-        let data = format!(
-            "{}:{}:{}:{}",
-            ucl.contract_id, ucl.payment.amount, ucl.payment.token, nonce
-        );
-        Ok(format!("sig_{}", Self::simple_hash(&data)))
+    /// Set retry policy for facilitator requests
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
     }
 
-    fn simple_hash(data: &str) -> String {
-        // Placeholder - would use actual hash function
-        format!("{:x}", data.len())
+    pub(crate) fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.endpoint.trim_end_matches('/'), path)
     }
 }
 
-/// Payment response
-#[derive(Debug, Clone)]
+/// Facilitator response to a submitted payment
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PaymentResponse {
     pub status: String,
+    /// Facilitator reference used to poll settlement
+    #[serde(default)]
+    pub reference: Option<String>,
+    #[serde(default)]
     pub transaction_hash: Option<String>,
+    #[serde(default)]
     pub confirmation_url: Option<String>,
 }
+
+impl PaymentResponse {
+    /// Reference to poll settlement with: the facilitator reference or the tx hash
+    pub fn settlement_reference(&self) -> Option<&str> {
+        self.reference
+            .as_deref()
+            .or(self.transaction_hash.as_deref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Contract, ContractConfig};
+
+    const TEST_KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    #[test]
+    fn test_signed_headers_recover_signer() {
+        let client =
+            X402Client::with_private_key("https://x402.test".to_string(), TEST_KEY).unwrap();
+        let ucl = Contract::from_config(ContractConfig::default()).unwrap().ucl;
+        let signer = client.signer_address().unwrap();
+
+        let mut headers = client.generate_headers(&ucl, true).unwrap();
+        assert_eq!(headers.signature_scheme, SignatureScheme::Secp256k1);
+        assert_eq!(headers.verify_signature().unwrap(), signer);
+
+        headers.payment_amount = "0.01".to_string();
+        assert!(headers.verify_signature().is_err());
+    }
+
+    #[test]
+    fn test_ed25519_signed_headers() {
+        let client =
+            X402Client::with_ed25519_key("https://x402.test".to_string(), &"07".repeat(32))
+                .unwrap();
+        let ucl = Contract::from_config(ContractConfig::default()).unwrap().ucl;
+
+        let headers = client.generate_headers(&ucl, true).unwrap();
+        assert_eq!(headers.to_map()["X402-Signature-Scheme"], "ed25519");
+        assert_eq!(
+            headers.verify_signature().unwrap(),
+            client.signer_address().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_headers_round_trip_through_header_map() {
+        let client =
+            X402Client::with_private_key("https://x402.test".to_string(), TEST_KEY).unwrap();
+        let ucl = Contract::from_config(ContractConfig::default()).unwrap().ucl;
+        let headers = client.generate_headers(&ucl, true).unwrap();
+
+        let mut header_map = http::HeaderMap::new();
+        for (name, value) in headers.to_map() {
+            header_map.insert(
+                http::HeaderName::from_bytes(name.as_bytes()).unwrap(),
+                value.parse().unwrap(),
+            );
+        }
+        let parsed = X402Headers::from_headers(&header_map).unwrap();
+        assert_eq!(parsed.signing_payload(), headers.signing_payload());
+        assert!(parsed.verify_signature().is_ok());
+
+        let mut map = headers.to_map();
+        map.insert("X402-Payment-Amount".to_string(), "-1".to_string());
+        assert!(matches!(
+            X402Headers::from_map(&map),
+            Err(VerificationError::MalformedHeader { .. })
+        ));
+        map.insert("X402-Payment-Amount".to_string(), "0".to_string());
+        map.remove("X402-Signer");
+        assert_eq!(
+            X402Headers::from_map(&map).unwrap_err(),
+            VerificationError::MissingHeader("X402-Signer".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_payment_retries_with_stable_idempotency_key() {
+        use crate::x402::test_server::{header, serve};
+
+        let (endpoint, requests) = serve(vec![
+            (503, "{}"),
+            (200, r#"{"status":"accepted","reference":"pay_1"}"#),
+        ])
+        .await;
+        let client = X402Client::with_private_key(endpoint, TEST_KEY)
+            .unwrap()
+            .with_retry_policy(RetryPolicy {
+                initial_backoff: Duration::from_millis(1),
+                ..RetryPolicy::default()
+            });
+        let ucl = Contract::from_config(ContractConfig::default()).unwrap().ucl;
+        let headers = client.generate_headers(&ucl, true).unwrap();
+
+        let response = client
+            .send_payment_request(headers.clone(), HashMap::new())
+            .await
+            .unwrap();
+        assert_eq!(response.settlement_reference(), Some("pay_1"));
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        for request in requests.iter() {
+            assert_eq!(
+                header(request, "Idempotency-Key"),
+                Some(headers.idempotency_key().as_str())
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_payment_rejection_is_not_retried() {
+        let (endpoint, requests) =
+            crate::x402::test_server::serve(vec![(402, r#"{"error":"insufficient funds"}"#)])
+                .await;
+        let client = X402Client::with_private_key(endpoint, TEST_KEY).unwrap();
+        let ucl = Contract::from_config(ContractConfig::default()).unwrap().ucl;
+        let headers = client.generate_headers(&ucl, true).unwrap();
+
+        let err = client
+            .send_payment_request(headers, HashMap::new())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("insufficient funds"));
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_unsigned_client_rejects_header_generation() {
+        let client = X402Client::new("https://x402.test".to_string());
+        let ucl = Contract::from_config(ContractConfig::default()).unwrap().ucl;
+        assert!(client.generate_headers(&ucl, true).is_err());
+    }
+}
@@ -0,0 +1,227 @@
+//! Prepaid request credits
+//!
+//! A verified contract payment buys a block of request credits. Later
+//! requests only prove who is calling, by signing a fresh nonce, and each
+//! one consumes a credit until the balance runs out and the paywall answers
+//! 402 again.
+
+use super::client::X402Client;
+use super::nonce::generate_nonce;
+use super::signing::{verify_signature, SignatureScheme};
+use super::verify::{party_role, VerificationError};
+use crate::{Result, UCLContract};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Header naming the contract whose credits a request spends
+pub const CREDIT_HEADER: &str = "X402-Credit-Contract";
+
+/// Signed claim to spend one credit
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreditClaim {
+    pub contract_id: String,
+    pub nonce: String,
+    pub signature_scheme: SignatureScheme,
+    pub signer: String,
+    pub signature: String,
+}
+
+impl CreditClaim {
+    /// Canonical payload covered by the signature
+    pub fn signing_payload(&self) -> String {
+        format!(
+            "{}:{}\nX402-Nonce:{}\nX402-Signature-Scheme:{}\nX402-Signer:{}",
+            CREDIT_HEADER, self.contract_id, self.nonce, self.signature_scheme, self.signer
+        )
+    }
+
+    /// Convert to header map
+    pub fn to_map(&self) -> HashMap<String, String> {
+        HashMap::from([
+            (CREDIT_HEADER.to_string(), self.contract_id.clone()),
+            ("X402-Nonce".to_string(), self.nonce.clone()),
+            (
+                "X402-Signature-Scheme".to_string(),
+                self.signature_scheme.to_string(),
+            ),
+            ("X402-Signer".to_string(), self.signer.clone()),
+            ("X402-Signature".to_string(), self.signature.clone()),
+        ])
+    }
+
+    /// Parse from headers, the inverse of [`CreditClaim::to_map`]
+    pub fn from_map(map: &HashMap<String, String>) -> std::result::Result<Self, VerificationError> {
+        let get = |name: &str| {
+            map.iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.trim().to_string())
+                .ok_or_else(|| VerificationError::MissingHeader(name.to_string()))
+        };
+        Ok(Self {
+            contract_id: get(CREDIT_HEADER)?,
+            nonce: get("X402-Nonce")?,
+            signature_scheme: get("X402-Signature-Scheme")?.parse().map_err(
+                |e: crate::Error| VerificationError::MalformedHeader {
+                    header: "X402-Signature-Scheme".to_string(),
+                    reason: e.to_string(),
+                },
+            )?,
+            signer: get("X402-Signer")?,
+            signature: get("X402-Signature")?,
+        })
+    }
+
+    /// Verify the claim's signature and contract, returning the signer and role
+    pub fn verify(
+        &self,
+        ucl: &UCLContract,
+    ) -> std::result::Result<(String, String), VerificationError> {
+        if self.contract_id != ucl.contract_id {
+            return Err(VerificationError::ContractMismatch {
+                expected: ucl.contract_id.clone(),
+                actual: self.contract_id.clone(),
+            });
+        }
+        let signer = verify_signature(
+            self.signature_scheme,
+            &self.signing_payload(),
+            &self.signature,
+            &self.signer,
+        )
+        .map_err(|e| VerificationError::InvalidSignature(e.to_string()))?;
+        let role = party_role(ucl, self.signature_scheme, &signer)?;
+        Ok((signer, role))
+    }
+}
+
+/// Request credit balances per signer
+#[derive(Debug)]
+pub struct CreditLedger {
+    credits_per_payment: u64,
+    balances: Mutex<HashMap<String, u64>>,
+}
+
+impl CreditLedger {
+    /// Create ledger granting `credits_per_payment` credits per verified payment
+    pub fn new(credits_per_payment: u64) -> Self {
+        Self {
+            credits_per_payment,
+            balances: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Credits bought by one payment
+    pub fn credits_per_payment(&self) -> u64 {
+        self.credits_per_payment
+    }
+
+    /// Add the credits bought by one payment, returning the new balance
+    pub fn record_payment(&self, signer: &str) -> u64 {
+        self.grant(signer, self.credits_per_payment)
+    }
+
+    /// Add `credits` to a signer's balance, returning the new balance
+    pub fn grant(&self, signer: &str, credits: u64) -> u64 {
+        let mut balances = self.balances.lock().unwrap();
+        let balance = balances.entry(signer.to_lowercase()).or_default();
+        *balance += credits;
+        *balance
+    }
+
+    /// Spend one credit, returning the remaining balance
+    pub fn consume(&self, signer: &str) -> std::result::Result<u64, VerificationError> {
+        let mut balances = self.balances.lock().unwrap();
+        match balances.get_mut(&signer.to_lowercase()) {
+            Some(balance) if *balance > 0 => {
+                *balance -= 1;
+                Ok(*balance)
+            }
+            _ => Err(VerificationError::CreditsExhausted(signer.to_string())),
+        }
+    }
+
+    /// Remaining credits of a signer
+    pub fn remaining(&self, signer: &str) -> u64 {
+        self.balances
+            .lock()
+            .unwrap()
+            .get(&signer.to_lowercase())
+            .copied()
+            .unwrap_or(0)
+    }
+}
+
+impl X402Client {
+    /// Sign a claim to spend one prepaid credit of a contract
+    pub fn credit_claim(&self, ucl: &UCLContract) -> Result<CreditClaim> {
+        let signer = self
+            .signer
+            .as_ref()
+            .ok_or_else(|| crate::Error::ConfigError("X402Client has no signer".to_string()))?;
+        let mut claim = CreditClaim {
+            contract_id: ucl.contract_id.clone(),
+            nonce: generate_nonce(),
+            signature_scheme: signer.scheme(),
+            signer: signer.identity(),
+            signature: String::new(),
+        };
+        claim.signature = signer.sign(&claim.signing_payload())?;
+        Ok(claim)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PartyInfo;
+    use crate::x402::X402Paywall;
+    use crate::{Contract, ContractConfig};
+    use std::sync::Arc;
+
+    const TEST_KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    fn header_map(map: HashMap<String, String>) -> http::HeaderMap {
+        map.into_iter()
+            .map(|(k, v)| {
+                (
+                    http::HeaderName::from_bytes(k.as_bytes()).unwrap(),
+                    v.parse().unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_payment_buys_credits_until_exhausted() {
+        let client =
+            X402Client::with_private_key("https://x402.test".to_string(), TEST_KEY).unwrap();
+        let signer = client.signer_address().unwrap();
+        let mut ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+        ucl.metadata.parties.push(PartyInfo {
+            role: "payer".to_string(),
+            identifier: signer.clone(),
+            name: None,
+            ..Default::default()
+        });
+
+        let ledger = Arc::new(CreditLedger::new(2));
+        let wall = X402Paywall::new(ucl.clone()).with_credit_ledger(ledger.clone());
+
+        let claim = header_map(client.credit_claim(&ucl).unwrap().to_map());
+        assert!(matches!(
+            wall.verify(&claim).unwrap_err().reason(),
+            Some(VerificationError::CreditsExhausted(_))
+        ));
+
+        let payment = header_map(client.generate_headers(&ucl, true).unwrap().to_map());
+        assert_eq!(wall.verify(&payment).unwrap().credits_remaining, Some(1));
+        assert_eq!(ledger.remaining(&signer), 1);
+
+        let claim = header_map(client.credit_claim(&ucl).unwrap().to_map());
+        assert_eq!(wall.verify(&claim).unwrap().credits_remaining, Some(0));
+        let claim = header_map(client.credit_claim(&ucl).unwrap().to_map());
+        assert!(wall.verify(&claim).is_err());
+    }
+}
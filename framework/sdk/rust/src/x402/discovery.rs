@@ -0,0 +1,174 @@
+//! Service discovery through `/.well-known/x402`
+//!
+//! Services describe their paid endpoints, prices and accepted tokens in a
+//! JSON descriptor so agents can plan spending before calling:
+//!
+//! ```json
+//! {
+//!   "name": "Weather API",
+//!   "pay_to": "0x2096...",
+//!   "endpoints": [
+//!     { "path": "/forecast", "method": "GET", "contract_id": "smart402:weather",
+//!       "prices": [{ "amount": "0.01", "token": "USDC", "network": "base" }] }
+//!   ]
+//! }
+//! ```
+
+use super::client::X402Client;
+use super::price::{choose_price, PriceOption};
+use crate::Result;
+use serde::{Deserialize, Serialize};
+
+/// Path of the descriptor relative to the service root
+pub const WELL_KNOWN_PATH: &str = "/.well-known/x402";
+
+/// Paid endpoints offered by a service
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServiceDescriptor {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Default payee of the service's endpoints
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pay_to: Option<String>,
+    /// Facilitator the service settles through
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub facilitator: Option<String>,
+    pub endpoints: Vec<PaidEndpoint>,
+}
+
+/// One paid endpoint and its prices
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PaidEndpoint {
+    pub path: String,
+    #[serde(default = "default_method")]
+    pub method: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Contract payments to the endpoint are made under
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contract_id: Option<String>,
+    /// Accepted prices, one per token and network
+    pub prices: Vec<PriceOption>,
+}
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+impl PaidEndpoint {
+    /// Price per call in `token` on `network`, if accepted
+    pub fn price_in(&self, token: &str, network: &str) -> Option<f64> {
+        self.prices
+            .iter()
+            .find(|p| p.matches(token, network))
+            .and_then(|p| p.amount.parse().ok())
+    }
+}
+
+impl ServiceDescriptor {
+    /// Endpoint matching a method and path (method is case-insensitive)
+    pub fn endpoint(&self, method: &str, path: &str) -> Option<&PaidEndpoint> {
+        self.endpoints
+            .iter()
+            .find(|e| e.path == path && e.method.eq_ignore_ascii_case(method))
+    }
+
+    /// Every `(token, network)` pair accepted by at least one endpoint
+    pub fn accepted_tokens(&self) -> Vec<(String, String)> {
+        let mut tokens: Vec<(String, String)> = Vec::new();
+        for price in self.endpoints.iter().flat_map(|e| &e.prices) {
+            if !tokens.iter().any(|(t, n)| price.matches(t, n)) {
+                tokens.push((price.token.clone(), price.network.clone()));
+            }
+        }
+        tokens
+    }
+
+    /// Estimated cost of a call plan, paying each call with the first of
+    /// `preferences` the endpoint accepts
+    ///
+    /// `calls` lists `(method, path, count)`. Fails naming the first call
+    /// that is not a listed endpoint or accepts none of the preferences.
+    pub fn estimate_cost(
+        &self,
+        calls: &[(&str, &str, u32)],
+        preferences: &[(&str, &str)],
+    ) -> Result<Vec<(PriceOption, u32)>> {
+        calls
+            .iter()
+            .map(|(method, path, count)| {
+                let endpoint = self.endpoint(method, path).ok_or_else(|| {
+                    crate::Error::NotFoundError(format!("No paid endpoint {} {}", method, path))
+                })?;
+                let price = choose_price(&endpoint.prices, preferences).ok_or_else(|| {
+                    crate::Error::PaymentError(format!(
+                        "{} {} accepts none of the preferred tokens",
+                        method, path
+                    ))
+                })?;
+                Ok((price.clone(), *count))
+            })
+            .collect()
+    }
+}
+
+impl X402Client {
+    /// Fetch a service's `/.well-known/x402` descriptor
+    ///
+    /// `service_url` is the service root, e.g. `https://api.example.com`.
+    pub async fn discover(&self, service_url: &str) -> Result<ServiceDescriptor> {
+        let url = format!("{}{}", service_url.trim_end_matches('/'), WELL_KNOWN_PATH);
+        let response = self.http.get(&url).send().await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(crate::Error::NotFoundError(format!(
+                "{} does not publish an X402 descriptor",
+                service_url
+            )));
+        }
+        Ok(response.error_for_status()?.json().await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::x402::test_server::serve;
+
+    const DESCRIPTOR: &str = r#"{
+        "name": "Weather API",
+        "endpoints": [
+            {"path": "/forecast", "prices": [
+                {"amount": "0.01", "token": "USDC", "network": "base"},
+                {"amount": "0.012", "token": "DAI", "network": "polygon"}
+            ]},
+            {"path": "/alerts", "method": "POST", "prices": [
+                {"amount": "0.05", "token": "USDC", "network": "base"}
+            ]}
+        ]
+    }"#;
+
+    #[tokio::test]
+    async fn test_discover_and_plan() {
+        let (url, requests) = serve(vec![(200, DESCRIPTOR)]).await;
+        let client = X402Client::new("https://x402.test".to_string());
+
+        let descriptor = client.discover(&url).await.unwrap();
+        assert!(requests.lock().unwrap()[0].starts_with("GET /.well-known/x402 "));
+        assert_eq!(descriptor.endpoints[0].method, "GET");
+        assert_eq!(descriptor.accepted_tokens().len(), 2);
+
+        let plan = descriptor
+            .estimate_cost(
+                &[("GET", "/forecast", 100), ("post", "/alerts", 2)],
+                &[("DAI", "polygon"), ("USDC", "base")],
+            )
+            .unwrap();
+        assert_eq!(plan[0].0.token, "DAI");
+        assert_eq!(plan[1].0.token, "USDC");
+        assert!(descriptor
+            .estimate_cost(&[("GET", "/missing", 1)], &[("USDC", "base")])
+            .is_err());
+    }
+}
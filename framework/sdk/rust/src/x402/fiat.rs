@@ -0,0 +1,316 @@
+//! Fiat fallback for counterparties that cannot pay in crypto
+//!
+//! A 402 challenge can offer a card or PayPal checkout next to the X402
+//! terms. Checkout links are generated from the contract's fiat price
+//! (`payment.amount` in the contract's price currency); once the processor reports
+//! the payment, it is recorded in the contract's payment history as a
+//! [`PaymentMethod::Fiat`] entry.
+
+use super::nonce::unix_now;
+use super::webhook::check_signature;
+use crate::{Amount, Contract, Currency, PaymentMethod, PaymentRecord, Result, UCLContract};
+use serde::{Deserialize, Serialize};
+
+/// Header carrying the fiat checkout URL of a 402 challenge
+pub const FIAT_HEADER: &str = "X402-Fiat-Checkout";
+
+/// Default Stripe API base URL
+pub const STRIPE_API_BASE: &str = "https://api.stripe.com";
+
+/// Fiat checkout offered alongside the X402 terms
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FiatOption {
+    /// Payment processor, e.g. "stripe" or "paypal"
+    pub provider: String,
+    /// Checkout page the payer is sent to
+    pub url: String,
+    /// Decimal amount in `currency`
+    pub amount: String,
+    /// ISO 4217 currency code
+    pub currency: String,
+}
+
+impl FiatOption {
+    /// Static checkout link (e.g. a Stripe Payment Link) priced from the contract
+    pub fn payment_link(provider: &str, url: &str, ucl: &UCLContract) -> Self {
+        Self {
+            provider: provider.to_string(),
+            url: url.to_string(),
+            amount: ucl.payment.amount.to_string(),
+            currency: price_currency(ucl),
+        }
+    }
+
+    /// PayPal.Me link for the contract amount
+    pub fn paypal_me(username: &str, ucl: &UCLContract) -> Self {
+        let currency = price_currency(ucl);
+        Self {
+            provider: "paypal".to_string(),
+            url: format!(
+                "https://paypal.me/{}/{}{}",
+                username, ucl.payment.amount, currency
+            ),
+            amount: ucl.payment.amount.to_string(),
+            currency,
+        }
+    }
+}
+
+/// Creates Stripe Checkout sessions for contract payments
+pub struct StripeCheckout {
+    secret_key: String,
+    success_url: String,
+    cancel_url: String,
+    api_base: String,
+    http: reqwest::Client,
+}
+
+impl StripeCheckout {
+    /// Create client with a Stripe secret key and the redirect URLs
+    pub fn new(secret_key: &str, success_url: &str, cancel_url: &str) -> Self {
+        Self {
+            secret_key: secret_key.to_string(),
+            success_url: success_url.to_string(),
+            cancel_url: cancel_url.to_string(),
+            api_base: STRIPE_API_BASE.to_string(),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Use another API base URL (e.g. a local Stripe mock)
+    pub fn with_api_base(mut self, api_base: &str) -> Self {
+        self.api_base = api_base.to_string();
+        self
+    }
+
+    /// Create a one-off Checkout session paying the contract amount
+    ///
+    /// The contract ID is stored in the session's `client_reference_id`
+    /// so the completion webhook can be matched back to the contract.
+    pub async fn create_session(&self, ucl: &UCLContract) -> Result<FiatOption> {
+        let currency = ucl.payment.price_currency().ok_or_else(|| {
+            crate::Error::PaymentError(format!(
+                "Contract {} has no fiat currency to charge in",
+                ucl.contract_id
+            ))
+        })?;
+        let unit_amount = ucl
+            .payment
+            .amount
+            .to_base_units(currency.minor_units())
+            .unwrap_or(0)
+            .to_string();
+        let currency_code = currency.code().to_lowercase();
+        let form = [
+            ("mode", "payment"),
+            ("success_url", self.success_url.as_str()),
+            ("cancel_url", self.cancel_url.as_str()),
+            ("client_reference_id", ucl.contract_id.as_str()),
+            ("line_items[0][quantity]", "1"),
+            ("line_items[0][price_data][currency]", currency_code.as_str()),
+            (
+                "line_items[0][price_data][unit_amount]",
+                unit_amount.as_str(),
+            ),
+            (
+                "line_items[0][price_data][product_data][name]",
+                ucl.summary.title.as_str(),
+            ),
+        ];
+
+        let response = self
+            .http
+            .post(format!(
+                "{}/v1/checkout/sessions",
+                self.api_base.trim_end_matches('/')
+            ))
+            .bearer_auth(&self.secret_key)
+            .form(&form)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(crate::Error::PaymentError(format!(
+                "Stripe rejected checkout session ({}): {}",
+                status, body
+            )));
+        }
+        let session: serde_json::Value = response.json().await?;
+        let url = session["url"].as_str().ok_or_else(|| {
+            crate::Error::PaymentError("Stripe checkout session has no URL".to_string())
+        })?;
+        Ok(FiatOption {
+            provider: "stripe".to_string(),
+            url: url.to_string(),
+            amount: ucl.payment.amount.to_string(),
+            currency: currency.to_string(),
+        })
+    }
+}
+
+/// Fiat payment reported by a processor
+#[derive(Debug, Clone, PartialEq)]
+pub struct FiatSettlement {
+    pub provider: String,
+    /// Processor reference (e.g. Stripe payment intent ID)
+    pub reference: String,
+    pub contract_id: String,
+    /// Decimal amount in `currency`
+    pub amount: Amount,
+    pub currency: Currency,
+    pub payer: Option<String>,
+}
+
+impl FiatSettlement {
+    /// Parse a signed Stripe webhook
+    ///
+    /// `signature` is the `Stripe-Signature` header. Returns `None` for
+    /// events other than a paid `checkout.session.completed`.
+    pub fn from_stripe_webhook(
+        payload: &[u8],
+        signature: &str,
+        secret: &str,
+    ) -> Result<Option<Self>> {
+        check_signature(payload, signature, secret, unix_now())?;
+        let event: serde_json::Value = serde_json::from_slice(payload)?;
+        let session = &event["data"]["object"];
+        if event["type"] != "checkout.session.completed" || session["payment_status"] != "paid" {
+            return Ok(None);
+        }
+
+        let field = |name: &str| {
+            session[name].as_str().map(str::to_string).ok_or_else(|| {
+                crate::Error::PaymentError(format!("Stripe checkout session lacks {}", name))
+            })
+        };
+        let currency: Currency = field("currency")?.parse()?;
+        let amount = session["amount_total"]
+            .as_u64()
+            .and_then(|minor_units| {
+                Amount::from_base_units(minor_units.into(), currency.minor_units())
+            })
+            .ok_or_else(|| {
+                crate::Error::PaymentError("Stripe checkout session lacks amount_total".to_string())
+            })?;
+        Ok(Some(Self {
+            provider: "stripe".to_string(),
+            reference: field("payment_intent").or_else(|_| field("id"))?,
+            contract_id: field("client_reference_id")?,
+            amount,
+            currency,
+            payer: session["customer_details"]["email"]
+                .as_str()
+                .map(str::to_string),
+        }))
+    }
+}
+
+impl Contract {
+    /// Record a fiat payment in the payment history
+    ///
+    /// The settlement must reference this contract and pay at least the
+    /// contract amount in the contract currency.
+    pub fn record_fiat_settlement(&mut self, settlement: FiatSettlement) -> Result<()> {
+        let terms = &self.ucl.payment;
+        if settlement.contract_id != self.ucl.contract_id {
+            return Err(crate::Error::PaymentError(format!(
+                "Fiat payment {} is for contract {}, not {}",
+                settlement.reference, settlement.contract_id, self.ucl.contract_id
+            )));
+        }
+        if terms.price_currency() != Some(settlement.currency) || settlement.amount < terms.amount {
+            return Err(crate::Error::PaymentError(format!(
+                "Fiat payment {} of {} {} does not cover {} {}",
+                settlement.reference,
+                settlement.amount,
+                settlement.currency,
+                terms.amount,
+                terms
+                    .price_currency()
+                    .map_or_else(|| terms.token.to_string(), |currency| currency.to_string())
+            )));
+        }
+
+        self.record_payment(PaymentRecord {
+            reference: settlement.reference,
+            method: PaymentMethod::Fiat {
+                provider: settlement.provider,
+            },
+            amount: settlement.amount,
+            currency: settlement.currency.to_string(),
+            payer: settlement.payer,
+            timestamp: chrono::Utc::now(),
+            refund_of: None,
+        });
+        Ok(())
+    }
+}
+
+/// ISO 4217 code of the contract's price currency; empty when it has none
+fn price_currency(ucl: &UCLContract) -> String {
+    ucl.payment
+        .price_currency()
+        .map(|currency| currency.to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::x402::sign_webhook;
+    use crate::ContractConfig;
+
+    #[test]
+    fn test_stripe_settlement_is_recorded() {
+        let mut contract = Contract::from_config(ContractConfig::default()).unwrap();
+        contract.ucl.payment.amount = Amount::new(4999, 2);
+
+        let payload = serde_json::json!({
+            "type": "checkout.session.completed",
+            "data": { "object": {
+                "id": "cs_test_1",
+                "payment_intent": "pi_1",
+                "payment_status": "paid",
+                "client_reference_id": contract.ucl.contract_id,
+                "amount_total": 4999,
+                "currency": "usd",
+                "customer_details": { "email": "buyer@example.com" }
+            }}
+        })
+        .to_string();
+        let signature = sign_webhook(payload.as_bytes(), "whsec_stripe");
+
+        let settlement =
+            FiatSettlement::from_stripe_webhook(payload.as_bytes(), &signature, "whsec_stripe")
+                .unwrap()
+                .unwrap();
+        assert_eq!(settlement.amount, Amount::new(4999, 2));
+        contract.record_fiat_settlement(settlement.clone()).unwrap();
+
+        let record = &contract.payment_history()[0];
+        assert_eq!(record.reference, "pi_1");
+        assert_eq!(
+            record.method,
+            PaymentMethod::Fiat {
+                provider: "stripe".to_string()
+            }
+        );
+
+        let underpaid = FiatSettlement {
+            amount: Amount::from(10),
+            ..settlement
+        };
+        assert!(contract.record_fiat_settlement(underpaid).is_err());
+    }
+
+    #[test]
+    fn test_paypal_me_link() {
+        let mut ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+        ucl.payment.amount = Amount::from(25);
+        let option = FiatOption::paypal_me("acme", &ucl);
+        assert_eq!(option.url, "https://paypal.me/acme/25USD");
+    }
+}
@@ -0,0 +1,146 @@
+//! X402 payments over gRPC metadata
+//!
+//! Payment headers travel as lowercase metadata entries (`x402-signature`,
+//! `x402-nonce`, ...). [`X402Interceptor`] enforces a paywall on a tonic
+//! service: calls without a valid payment fail with `FAILED_PRECONDITION`,
+//! the challenge headers attached as trailing metadata and the JSON 402
+//! body as the status message.
+//!
+//! ```no_run
+//! # fn build(ucl: smart402::UCLContract) {
+//! use smart402::x402::grpc::X402Interceptor;
+//! use smart402::x402::X402Paywall;
+//!
+//! let interceptor = X402Interceptor::new(X402Paywall::new(ucl));
+//! // MyServiceServer::with_interceptor(service, interceptor)
+//! # }
+//! ```
+//!
+//! Receipt tokens are not returned over gRPC: interceptors cannot add
+//! response metadata. Nor are standard x402 payments accepted: interceptors
+//! cannot wait for the facilitator to settle them.
+
+use super::challenge::PaymentRequired;
+use super::client::X402Headers;
+use super::paywall::{PaymentContext, X402Paywall};
+use super::verify::VerificationError;
+use tonic::metadata::{MetadataKey, MetadataMap, MetadataValue};
+use tonic::service::Interceptor;
+use tonic::{Code, Request, Status};
+
+/// Copy signed payment headers into gRPC metadata
+pub fn insert_payment_metadata(metadata: &mut MetadataMap, headers: &X402Headers) {
+    insert_headers(metadata, headers.to_map());
+}
+
+/// Attach signed payment headers to an outgoing request
+pub fn attach_payment<T>(request: &mut Request<T>, headers: &X402Headers) {
+    insert_payment_metadata(request.metadata_mut(), headers);
+}
+
+/// Parse payment headers from gRPC metadata
+pub fn payment_from_metadata(
+    metadata: &MetadataMap,
+) -> std::result::Result<X402Headers, VerificationError> {
+    X402Headers::from_headers(&metadata.clone().into_headers())
+}
+
+/// Whether a status is an X402 payment challenge
+///
+/// The challenge headers can be read with
+/// [`PriceOption::from_challenge`](super::PriceOption::from_challenge) on
+/// `status.metadata().clone().into_headers()`.
+pub fn is_payment_required(status: &Status) -> bool {
+    status.code() == Code::FailedPrecondition && status.metadata().contains_key("x402-contract-id")
+}
+
+impl From<PaymentRequired> for Status {
+    fn from(required: PaymentRequired) -> Self {
+        let mut metadata = MetadataMap::new();
+        insert_headers(&mut metadata, required.headers());
+        Status::with_metadata(
+            Code::FailedPrecondition,
+            required.body().to_string(),
+            metadata,
+        )
+    }
+}
+
+/// Tonic interceptor enforcing an X402 paywall
+///
+/// Verified payments are inserted into request extensions as
+/// [`PaymentContext`].
+#[derive(Clone)]
+pub struct X402Interceptor {
+    wall: X402Paywall,
+}
+
+impl X402Interceptor {
+    /// Create interceptor enforcing `wall`
+    pub fn new(wall: X402Paywall) -> Self {
+        Self { wall }
+    }
+}
+
+impl Interceptor for X402Interceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        let headers = request.metadata().clone().into_headers();
+        let payment: PaymentContext = self.wall.verify(&headers)?;
+        request.extensions_mut().insert(payment);
+        Ok(request)
+    }
+}
+
+/// Insert headers as lowercase ASCII metadata, skipping invalid values
+fn insert_headers<K: AsRef<str>>(
+    metadata: &mut MetadataMap,
+    headers: impl IntoIterator<Item = (K, String)>,
+) {
+    for (name, value) in headers {
+        if let (Ok(key), Ok(value)) = (
+            MetadataKey::from_bytes(name.as_ref().to_ascii_lowercase().as_bytes()),
+            MetadataValue::try_from(value.as_str()),
+        ) {
+            metadata.insert(key, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PartyInfo;
+    use crate::x402::X402Client;
+    use crate::{Contract, ContractConfig};
+
+    const TEST_KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    #[test]
+    fn test_interceptor_challenges_and_verifies() {
+        let client =
+            X402Client::with_private_key("https://x402.test".to_string(), TEST_KEY).unwrap();
+        let mut ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+        ucl.metadata.parties.push(PartyInfo {
+            role: "payer".to_string(),
+            identifier: client.signer_address().unwrap(),
+            name: None,
+            ..Default::default()
+        });
+        let mut interceptor = X402Interceptor::new(X402Paywall::new(ucl.clone()));
+
+        let status = interceptor.call(Request::new(())).unwrap_err();
+        assert!(is_payment_required(&status));
+        assert_eq!(status.metadata().get("x402-payment-token").unwrap(), "USDC");
+
+        let mut request = Request::new(());
+        attach_payment(&mut request, &client.generate_headers(&ucl, true).unwrap());
+        assert!(payment_from_metadata(request.metadata()).is_ok());
+        let request = interceptor.call(request).unwrap();
+        assert_eq!(
+            request.extensions().get::<PaymentContext>().unwrap().role,
+            "payer"
+        );
+    }
+}
@@ -0,0 +1,140 @@
+//! Generic `tower::Layer` enforcing an X402 paywall
+//!
+//! Works with any stack built on `http` 0.2 requests (hyper, tonic, warp).
+//! Requests without a valid payment are answered with HTTP 402; verified
+//! payments are inserted into request extensions as [`PaymentContext`],
+//! receipts minted for them are returned in the `X402-Receipt` header and
+//! settlements of standard x402 payments in `X-PAYMENT-RESPONSE`.
+
+use super::paywall::{PaymentContext, X402Paywall};
+use super::receipt::RECEIPT_HEADER;
+use super::standard::PAYMENT_RESPONSE_HEADER;
+use http::{HeaderValue, Request, Response};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Layer wrapping services in an [`X402Service`]
+#[derive(Clone)]
+pub struct X402Layer {
+    wall: X402Paywall,
+}
+
+impl X402Layer {
+    /// Create layer enforcing `wall`
+    pub fn new(wall: X402Paywall) -> Self {
+        Self { wall }
+    }
+}
+
+impl<S> Layer<S> for X402Layer {
+    type Service = X402Service<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        X402Service {
+            inner,
+            wall: self.wall.clone(),
+        }
+    }
+}
+
+/// Service that only forwards requests carrying a valid X402 payment
+#[derive(Clone)]
+pub struct X402Service<S> {
+    inner: S,
+    wall: X402Paywall,
+}
+
+impl<S, B, ResBody> Service<Request<B>> for X402Service<S>
+where
+    S: Service<Request<B>, Response = Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Send + 'static,
+    ResBody: From<String> + Send + 'static,
+    B: Send + 'static,
+{
+    type Response = Response<ResBody>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<B>) -> Self::Future {
+        let wall = self.wall.clone();
+        // The ready service handles this request; a clone takes its place
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        Box::pin(async move {
+            match wall.verify_and_settle(request.headers()).await {
+                Ok(payment) => {
+                    let receipt = payment.receipt.clone();
+                    let settlement = payment.settlement.clone();
+                    request.extensions_mut().insert::<PaymentContext>(payment);
+                    let mut response = inner.call(request).await?;
+                    if let Some(receipt) = receipt.and_then(|r| HeaderValue::from_str(&r).ok()) {
+                        response.headers_mut().insert(RECEIPT_HEADER, receipt);
+                    }
+                    if let Some(settlement) =
+                        settlement.and_then(|s| HeaderValue::from_str(&s).ok())
+                    {
+                        response
+                            .headers_mut()
+                            .insert(PAYMENT_RESPONSE_HEADER, settlement);
+                    }
+                    Ok(response)
+                }
+                Err(required) => Ok(required.into_http_response()),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PartyInfo;
+    use crate::x402::X402Client;
+    use crate::{Contract, ContractConfig};
+    use http::StatusCode;
+    use std::convert::Infallible;
+    use tower::{service_fn, ServiceExt};
+
+    const TEST_KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    #[tokio::test]
+    async fn test_layer_gates_inner_service() {
+        let client =
+            X402Client::with_private_key("https://x402.test".to_string(), TEST_KEY).unwrap();
+        let mut ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+        ucl.metadata.parties.push(PartyInfo {
+            role: "payer".to_string(),
+            identifier: client.signer_address().unwrap(),
+            name: None,
+            ..Default::default()
+        });
+
+        let service = X402Layer::new(X402Paywall::new(ucl.clone())).layer(service_fn(
+            |request: Request<()>| async move {
+                let payment = request.extensions().get::<PaymentContext>().unwrap();
+                Ok::<_, Infallible>(Response::new(payment.role.clone()))
+            },
+        ));
+
+        let unpaid = service.clone().oneshot(Request::new(())).await.unwrap();
+        assert_eq!(unpaid.status(), StatusCode::PAYMENT_REQUIRED);
+
+        let mut request = Request::builder();
+        for (name, value) in client.generate_headers(&ucl, true).unwrap().to_map() {
+            request = request.header(name, value);
+        }
+        let paid = service.oneshot(request.body(()).unwrap()).await.unwrap();
+        assert_eq!(paid.status(), StatusCode::OK);
+        assert_eq!(paid.into_body(), "payer");
+    }
+}
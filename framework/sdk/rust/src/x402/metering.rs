@@ -0,0 +1,302 @@
+//! Usage metering for per-request contracts
+//!
+//! A [`UsageMeter`] installed on an [`X402Paywall`] records every paid
+//! request it verifies. Contracts billed `"per-request"` own a meter and
+//! aggregate it into a [`UsageReport`] per hour, day, week or month with
+//! [`Contract::usage_report`]; a [`UsageReporter`] pushes reports to a
+//! billing endpoint.
+
+use super::paywall::{PaymentContext, X402Paywall};
+use crate::{Contract, PaymentFrequency, Result};
+use chrono::{DateTime, Datelike, Duration, DurationRound, Months, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+/// One verified paid request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageEvent {
+    pub timestamp: DateTime<Utc>,
+    pub payer: String,
+    /// Amount paid for the request; zero when it spent a prepaid credit
+    pub amount: f64,
+    pub nonce: String,
+}
+
+/// Thread-safe record of paid requests
+#[derive(Debug, Default)]
+pub struct UsageMeter {
+    events: Mutex<Vec<UsageEvent>>,
+}
+
+impl UsageMeter {
+    /// Create empty meter
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a verified payment
+    pub fn record(&self, payment: &PaymentContext) {
+        self.record_event(UsageEvent {
+            timestamp: Utc::now(),
+            payer: payment.signer.clone(),
+            amount: payment.amount.parse().unwrap_or(0.0),
+            nonce: payment.nonce.clone(),
+        });
+    }
+
+    /// Record an event, e.g. one restored from storage
+    pub fn record_event(&self, event: UsageEvent) {
+        self.events.lock().unwrap().push(event);
+    }
+
+    /// All recorded events, oldest first
+    pub fn events(&self) -> Vec<UsageEvent> {
+        self.events.lock().unwrap().clone()
+    }
+
+    /// Aggregate the recorded events per `period`
+    pub fn report(&self, contract_id: &str, period: UsagePeriod) -> UsageReport {
+        let mut buckets: BTreeMap<DateTime<Utc>, UsageBucket> = BTreeMap::new();
+        for event in self.events.lock().unwrap().iter() {
+            let start = period.start_of(event.timestamp);
+            let bucket = buckets.entry(start).or_insert_with(|| UsageBucket {
+                start,
+                end: period.end_of(start),
+                requests: 0,
+                amount: 0.0,
+                payers: BTreeMap::new(),
+            });
+            bucket.requests += 1;
+            bucket.amount += event.amount;
+            *bucket.payers.entry(event.payer.clone()).or_default() += 1;
+        }
+
+        let buckets: Vec<UsageBucket> = buckets.into_values().collect();
+        UsageReport {
+            contract_id: contract_id.to_string(),
+            period,
+            total_requests: buckets.iter().map(|b| b.requests).sum(),
+            total_amount: buckets.iter().map(|b| b.amount).sum(),
+            buckets,
+            generated_at: Utc::now(),
+        }
+    }
+}
+
+/// Aggregation period of a usage report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum UsagePeriod {
+    Hour,
+    Day,
+    /// ISO weeks, starting on Monday
+    Week,
+    Month,
+}
+
+impl UsagePeriod {
+    /// Start of the period containing `time`
+    pub fn start_of(self, time: DateTime<Utc>) -> DateTime<Utc> {
+        let midnight = |date: chrono::NaiveDate| date.and_time(NaiveTime::MIN).and_utc();
+        match self {
+            UsagePeriod::Hour => time.duration_trunc(Duration::hours(1)).unwrap_or(time),
+            UsagePeriod::Day => midnight(time.date_naive()),
+            UsagePeriod::Week => midnight(
+                time.date_naive() - Duration::days(time.weekday().num_days_from_monday().into()),
+            ),
+            UsagePeriod::Month => midnight(time.date_naive().with_day(1).unwrap_or_default()),
+        }
+    }
+
+    /// End (exclusive) of the period starting at `start`
+    pub fn end_of(self, start: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            UsagePeriod::Hour => start + Duration::hours(1),
+            UsagePeriod::Day => start + Duration::days(1),
+            UsagePeriod::Week => start + Duration::weeks(1),
+            UsagePeriod::Month => start.checked_add_months(Months::new(1)).unwrap_or(start),
+        }
+    }
+}
+
+/// Usage within one period
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageBucket {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub requests: u64,
+    pub amount: f64,
+    /// Requests per payer
+    pub payers: BTreeMap<String, u64>,
+}
+
+/// Usage of a contract aggregated per period
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageReport {
+    pub contract_id: String,
+    pub period: UsagePeriod,
+    /// Periods with usage, oldest first
+    pub buckets: Vec<UsageBucket>,
+    pub total_requests: u64,
+    pub total_amount: f64,
+    pub generated_at: DateTime<Utc>,
+}
+
+/// Pushes usage reports to a billing endpoint
+#[derive(Debug, Clone)]
+pub struct UsageReporter {
+    endpoint: String,
+    http: reqwest::Client,
+    api_key: Option<String>,
+}
+
+impl UsageReporter {
+    /// Report to `endpoint`, which receives each report as a JSON POST
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            http: reqwest::Client::new(),
+            api_key: None,
+        }
+    }
+
+    /// Send `Authorization: Bearer <key>` with every report
+    pub fn with_api_key(mut self, key: impl Into<String>) -> Self {
+        self.api_key = Some(key.into());
+        self
+    }
+
+    /// Push a report
+    pub async fn push(&self, report: &UsageReport) -> Result<()> {
+        let mut request = self.http.post(&self.endpoint).json(report);
+        if let Some(key) = &self.api_key {
+            request = request.bearer_auth(key);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(crate::Error::NetworkError(format!(
+                "Usage endpoint rejected report ({}): {}",
+                status, body
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Contract {
+    /// Usage of a per-request contract aggregated per `period`
+    ///
+    /// Counts the requests recorded by [`Contract::usage_meter`]; fails for
+    /// contracts with any other payment frequency.
+    pub fn usage_report(&self, period: UsagePeriod) -> Result<UsageReport> {
+        if self.ucl.payment.frequency != PaymentFrequency::PerRequest {
+            return Err(crate::Error::ConfigError(format!(
+                "Contract {} is billed {}, not {}",
+                self.ucl.contract_id,
+                self.ucl.payment.frequency,
+                PaymentFrequency::PerRequest
+            )));
+        }
+        Ok(self.usage_meter().report(&self.ucl.contract_id, period))
+    }
+}
+
+impl X402Paywall {
+    /// Meter every paid request the paywall verifies
+    ///
+    /// Requests authorized by a receipt are not metered again.
+    pub fn with_usage_meter(mut self, meter: Arc<UsageMeter>) -> Self {
+        self.meter = Some(meter);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PartyInfo;
+    use crate::x402::test_server::serve;
+    use crate::x402::X402Client;
+    use crate::{Amount, ContractConfig};
+    use http::HeaderMap;
+
+    const TEST_KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    fn at(time: &str) -> DateTime<Utc> {
+        time.parse().unwrap()
+    }
+
+    #[test]
+    fn test_period_boundaries() {
+        let time = at("2024-05-15T13:45:00Z");
+        assert_eq!(UsagePeriod::Hour.start_of(time), at("2024-05-15T13:00:00Z"));
+        assert_eq!(UsagePeriod::Day.start_of(time), at("2024-05-15T00:00:00Z"));
+        assert_eq!(UsagePeriod::Week.start_of(time), at("2024-05-13T00:00:00Z"));
+        assert_eq!(
+            UsagePeriod::Month.start_of(time),
+            at("2024-05-01T00:00:00Z")
+        );
+        assert_eq!(
+            UsagePeriod::Month.end_of(at("2024-05-01T00:00:00Z")),
+            at("2024-06-01T00:00:00Z")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_meters_paid_requests() {
+        let client =
+            X402Client::with_private_key("https://x402.test".to_string(), TEST_KEY).unwrap();
+        let mut contract = Contract::from_config(ContractConfig::default()).unwrap();
+        contract.ucl.payment.amount = Amount::new(1, 2);
+        contract.ucl.payment.frequency = PaymentFrequency::PerRequest;
+        contract.ucl.metadata.parties.push(PartyInfo {
+            role: "payer".to_string(),
+            identifier: client.signer_address().unwrap(),
+            name: None,
+            ..Default::default()
+        });
+        let wall = X402Paywall::new(contract.ucl.clone()).with_usage_meter(contract.usage_meter());
+
+        for _ in 0..3 {
+            let mut headers = HeaderMap::new();
+            for (name, value) in client
+                .generate_headers(&contract.ucl, true)
+                .unwrap()
+                .to_map()
+            {
+                headers.insert(
+                    http::HeaderName::from_bytes(name.as_bytes()).unwrap(),
+                    value.parse().unwrap(),
+                );
+            }
+            wall.verify(&headers).unwrap();
+        }
+        assert!(wall.verify(&HeaderMap::new()).is_err());
+
+        let report = contract.usage_report(UsagePeriod::Day).unwrap();
+        assert_eq!(report.total_requests, 3);
+        assert!((report.total_amount - 0.03).abs() < 1e-9);
+        assert_eq!(report.buckets.len(), 1);
+        assert_eq!(
+            report.buckets[0].payers[&client.signer_address().unwrap()],
+            3
+        );
+
+        let (endpoint, requests) = serve(vec![(202, "{}")]).await;
+        UsageReporter::new(format!("{}/usage", endpoint))
+            .push(&report)
+            .await
+            .unwrap();
+        assert!(requests.lock().unwrap()[0].contains(r#""total_requests":3"#));
+    }
+
+    #[test]
+    fn test_usage_report_requires_per_request_billing() {
+        let contract = Contract::from_config(ContractConfig::default()).unwrap();
+        assert!(contract.usage_report(UsagePeriod::Month).is_err());
+    }
+}
@@ -1,5 +1,62 @@
 //! X402 Protocol module
 
+pub mod batch;
+pub mod builder;
+pub mod challenge;
 pub mod client;
+pub mod credits;
+pub mod discovery;
+pub mod fiat;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod metering;
+pub mod nonce;
+pub mod paywall;
+pub mod policy;
+pub mod price;
+#[cfg(feature = "server")]
+pub mod proxy;
+pub mod receipt;
+pub mod refund;
+#[cfg(feature = "tower")]
+pub mod layer;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod retry;
+pub mod settlement;
+pub mod signing;
+pub mod standard;
+pub mod tab;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod verify;
+pub mod webhook;
 
+#[cfg(test)]
+pub(crate) mod test_server;
+
+pub use batch::PaymentRequest;
+pub use builder::X402ClientBuilder;
 pub use client::X402Client;
+pub use standard::{FacilitatorClient, PaymentPayload, PaymentRequirements};
+pub use signing::{Ed25519Signer, Secp256k1Signer, SignatureError, SignatureScheme, X402Signer};
+pub use challenge::PaymentRequired;
+pub use paywall::{PaymentContext, X402Paywall};
+pub use policy::{PolicyViolation, SpendingPolicy};
+pub use price::{choose_price, PriceOption};
+pub use receipt::{verify_receipt, ReceiptClaims, ReceiptIssuer};
+pub use refund::{RefundRequest, VerifiedRefund};
+pub use settlement::{SettlementReceipt, SettlementStatus};
+pub use tab::{PaymentTab, TabCommitment, TabLedger};
+pub use credits::{CreditClaim, CreditLedger};
+pub use discovery::{PaidEndpoint, ServiceDescriptor};
+pub use fiat::{FiatOption, FiatSettlement, StripeCheckout};
+pub use metering::{UsageMeter, UsagePeriod, UsageReport, UsageReporter};
+pub use retry::RetryPolicy;
+pub use nonce::{FileNonceStore, InMemoryNonceStore, NonceStore};
+pub use webhook::{sign_webhook, verify_webhook, RefundNotice, WebhookEvent};
+pub use verify::{PaymentVerifier, VerificationError, VerifiedPayment};
+#[cfg(feature = "tower")]
+pub use layer::{X402Layer, X402Service};
+#[cfg(feature = "server")]
+pub use proxy::PaywallProxy;
@@ -0,0 +1,209 @@
+//! Replay protection for X402 payment nonces
+//!
+//! Nonces have the form `<unix-seconds>-<random hex>`. Servers record every
+//! accepted nonce per signer and reject duplicates until the nonce has aged
+//! past the verifier's freshness window, after which it is rejected as stale
+//! anyway and can be forgotten.
+
+use super::verify::VerificationError;
+use crate::Result;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Record of nonces already accepted by an X402 server
+pub trait NonceStore: Send + Sync {
+    /// Record `nonce` for `signer`, failing if it was already used
+    ///
+    /// `expires_at` is the unix time after which the nonce is stale and
+    /// may be pruned.
+    fn check_and_record(
+        &self,
+        signer: &str,
+        nonce: &str,
+        expires_at: u64,
+    ) -> std::result::Result<(), VerificationError>;
+}
+
+/// Generate a fresh nonce: the current unix time plus 64 random bits
+pub fn generate_nonce() -> String {
+    format!("{}-{:016x}", unix_now(), rand::random::<u64>())
+}
+
+/// Unix timestamp a nonce was issued at
+pub fn nonce_timestamp(nonce: &str) -> Option<u64> {
+    nonce.split('-').next()?.parse().ok()
+}
+
+/// Nonce store kept in process memory
+#[derive(Debug, Default)]
+pub struct InMemoryNonceStore {
+    seen: Mutex<HashMap<String, u64>>,
+}
+
+impl InMemoryNonceStore {
+    /// Create empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of nonces currently tracked
+    pub fn len(&self) -> usize {
+        self.seen.lock().unwrap().len()
+    }
+
+    /// Whether no nonces are tracked
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl NonceStore for InMemoryNonceStore {
+    fn check_and_record(
+        &self,
+        signer: &str,
+        nonce: &str,
+        expires_at: u64,
+    ) -> std::result::Result<(), VerificationError> {
+        let mut seen = self.seen.lock().unwrap();
+        record(&mut seen, signer, nonce, expires_at)
+    }
+}
+
+/// Nonce store persisted to a JSON file, surviving server restarts
+///
+/// Suitable for a single server process; share a database-backed
+/// implementation of [`NonceStore`] across replicas.
+#[derive(Debug)]
+pub struct FileNonceStore {
+    path: PathBuf,
+    seen: Mutex<HashMap<String, u64>>,
+}
+
+impl FileNonceStore {
+    /// Open the store at `path`, loading previously recorded nonces
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let seen = if path.exists() {
+            serde_json::from_str(&std::fs::read_to_string(&path)?)?
+        } else {
+            HashMap::new()
+        };
+        Ok(Self {
+            path,
+            seen: Mutex::new(seen),
+        })
+    }
+
+    fn persist(&self, seen: &HashMap<String, u64>) -> Result<()> {
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_string(seen)?)?;
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+impl NonceStore for FileNonceStore {
+    fn check_and_record(
+        &self,
+        signer: &str,
+        nonce: &str,
+        expires_at: u64,
+    ) -> std::result::Result<(), VerificationError> {
+        let mut seen = self.seen.lock().unwrap();
+        record(&mut seen, signer, nonce, expires_at)?;
+        // Failing to persist must not let the nonce be replayed after a restart
+        self.persist(&seen).map_err(|e| {
+            seen.remove(&key(signer, nonce));
+            VerificationError::NonceStoreUnavailable(e.to_string())
+        })
+    }
+}
+
+fn record(
+    seen: &mut HashMap<String, u64>,
+    signer: &str,
+    nonce: &str,
+    expires_at: u64,
+) -> std::result::Result<(), VerificationError> {
+    let now = unix_now();
+    seen.retain(|_, expires| *expires >= now);
+
+    let key = key(signer, nonce);
+    if seen.contains_key(&key) {
+        return Err(VerificationError::ReplayedNonce(nonce.to_string()));
+    }
+    seen.insert(key, expires_at);
+    Ok(())
+}
+
+/// Secp256k1 `0x` addresses are case-insensitive; Ed25519 base58 keys are not
+fn key(signer: &str, nonce: &str) -> String {
+    if signer.starts_with("0x") {
+        format!("{}:{}", signer.to_lowercase(), nonce)
+    } else {
+        format!("{}:{}", signer, nonce)
+    }
+}
+
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_nonces_are_unique_and_timestamped() {
+        let a = generate_nonce();
+        let b = generate_nonce();
+        assert_ne!(a, b);
+        assert!(nonce_timestamp(&a).unwrap() >= unix_now() - 1);
+    }
+
+    #[test]
+    fn test_rejects_replayed_nonce() {
+        let store = InMemoryNonceStore::new();
+        let expires = unix_now() + 60;
+        store.check_and_record("0xabc", "1-1", expires).unwrap();
+        store.check_and_record("0xdef", "1-1", expires).unwrap();
+        assert_eq!(
+            store.check_and_record("0xABC", "1-1", expires),
+            Err(VerificationError::ReplayedNonce("1-1".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_ed25519_signers_are_case_sensitive() {
+        let store = InMemoryNonceStore::new();
+        let expires = unix_now() + 60;
+        store
+            .check_and_record("7Kq3sV9Jd2mPx1", "1-1", expires)
+            .unwrap();
+        store
+            .check_and_record("7kQ3Sv9jD2MpX1", "1-1", expires)
+            .unwrap();
+        assert!(store
+            .check_and_record("7Kq3sV9Jd2mPx1", "1-1", expires)
+            .is_err());
+    }
+
+    #[test]
+    fn test_file_store_survives_reopen() {
+        let path = std::env::temp_dir().join(format!("smart402-nonces-{}.json", generate_nonce()));
+        let expires = unix_now() + 60;
+        FileNonceStore::open(&path)
+            .unwrap()
+            .check_and_record("0xabc", "1-1", expires)
+            .unwrap();
+
+        let reopened = FileNonceStore::open(&path).unwrap();
+        assert!(reopened.check_and_record("0xabc", "1-1", expires).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
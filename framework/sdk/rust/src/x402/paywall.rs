@@ -0,0 +1,470 @@
+//! Framework-neutral X402 paywall core
+//!
+//! Shared by the axum integration ([`super::server`]) and the tower layer
+//! ([`super::layer`]).
+
+use super::challenge::PaymentRequired;
+use super::client::{X402Headers, HEADER_NAMES};
+use super::credits::{CreditClaim, CreditLedger, CREDIT_HEADER};
+use super::fiat::FiatOption;
+use super::metering::UsageMeter;
+use super::price::PriceOption;
+use super::receipt::{ReceiptIssuer, RECEIPT_HEADER};
+use super::standard::{FacilitatorClient, PaymentPayload, PaymentRequirements, PAYMENT_HEADER};
+use super::tab::{TabCommitment, TabLedger};
+use super::signing::SignatureScheme;
+use super::verify::{is_party, PaymentVerifier, VerificationError, VerifiedPayment};
+use crate::UCLContract;
+use http::{HeaderMap, HeaderValue};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Paywall state: the contract payments are checked against
+#[derive(Clone)]
+pub struct X402Paywall {
+    ucl: Arc<UCLContract>,
+    verifier: PaymentVerifier,
+    standard: Option<Arc<StandardPayments>>,
+    tabs: Option<Arc<TabLedger>>,
+    credits: Option<Arc<CreditLedger>>,
+    receipts: Option<Arc<ReceiptIssuer>>,
+    fiat: Vec<FiatOption>,
+    pub(super) meter: Option<Arc<UsageMeter>>,
+}
+
+/// Standard x402 payments accepted by a paywall and who settles them
+struct StandardPayments {
+    requirements: PaymentRequirements,
+    facilitator: FacilitatorClient,
+}
+
+impl X402Paywall {
+    /// Create paywall for a contract with an in-memory nonce store
+    pub fn new(ucl: UCLContract) -> Self {
+        Self {
+            ucl: Arc::new(ucl),
+            verifier: PaymentVerifier::new(),
+            standard: None,
+            tabs: None,
+            credits: None,
+            receipts: None,
+            fiat: Vec::new(),
+            meter: None,
+        }
+    }
+
+    /// Also accept standard x402 `X-PAYMENT` payments meeting `requirements`
+    ///
+    /// The requirements are advertised in the `accepts` list of 402 bodies.
+    /// `facilitator` verifies and settles each payment before the request
+    /// is admitted, see [`X402Paywall::verify_and_settle`].
+    pub fn with_standard_requirements(
+        mut self,
+        requirements: PaymentRequirements,
+        facilitator: FacilitatorClient,
+    ) -> Self {
+        self.standard = Some(Arc::new(StandardPayments {
+            requirements,
+            facilitator,
+        }));
+        self
+    }
+
+    /// Also accept tab commitments, recording balances in `ledger`
+    pub fn with_tab_ledger(mut self, ledger: Arc<TabLedger>) -> Self {
+        self.tabs = Some(ledger);
+        self
+    }
+
+    /// Sell prepaid credits: each verified payment adds a block of credits
+    /// and every request, paid or claimed, spends one
+    pub fn with_credit_ledger(mut self, ledger: Arc<CreditLedger>) -> Self {
+        self.credits = Some(ledger);
+        self
+    }
+
+    /// Mint a receipt token for every verified payment and accept valid
+    /// receipts in place of a new payment until they expire
+    pub fn with_receipts(mut self, issuer: ReceiptIssuer) -> Self {
+        self.receipts = Some(Arc::new(issuer));
+        self
+    }
+
+    /// Also accept payments in alternative tokens and networks
+    ///
+    /// The options are advertised in 402 challenges. Call after
+    /// [`X402Paywall::with_verifier`], which replaces them.
+    pub fn with_price_options(mut self, prices: Vec<PriceOption>) -> Self {
+        self.verifier = self.verifier.with_accepted_prices(prices);
+        self
+    }
+
+    /// Offer a fiat checkout in 402 challenges
+    pub fn with_fiat_option(mut self, option: FiatOption) -> Self {
+        self.fiat.push(option);
+        self
+    }
+
+    /// Use a custom verifier (nonce age, persistent nonce store)
+    pub fn with_verifier(mut self, verifier: PaymentVerifier) -> Self {
+        self.verifier = verifier;
+        self
+    }
+
+    /// Contract the paywall enforces
+    pub fn contract(&self) -> &UCLContract {
+        &self.ucl
+    }
+
+    pub(crate) fn verifier(&self) -> &PaymentVerifier {
+        &self.verifier
+    }
+
+    pub(crate) fn receipt_issuer(&self) -> Option<&ReceiptIssuer> {
+        self.receipts.as_deref()
+    }
+
+    /// Verify the X402 headers of a request
+    ///
+    /// Standard x402 payments are refused: only
+    /// [`X402Paywall::verify_and_settle`] can settle them.
+    #[tracing::instrument(name = "x402_verify", skip_all, fields(contract_id = %self.ucl.contract_id))]
+    pub fn verify(&self, headers: &HeaderMap) -> Result<PaymentContext, PaymentRequired> {
+        let verified = match self.standard_payment(headers) {
+            Some(_) => Err(self.payment_required(Some(VerificationError::Unsettled(
+                "standard x402 payments are not accepted here".to_string(),
+            )))),
+            None => self.verify_request(headers),
+        };
+        log_verification(&verified);
+        verified
+    }
+
+    /// Verify the X402 headers of a request, settling standard x402 payments
+    ///
+    /// An `X-PAYMENT` payment is checked locally, then verified and settled
+    /// by the facilitator; the settlement is returned in
+    /// [`PaymentContext::settlement`]. Other payments are verified as by
+    /// [`X402Paywall::verify`].
+    #[tracing::instrument(name = "x402_verify", skip_all, fields(contract_id = %self.ucl.contract_id))]
+    pub async fn verify_and_settle(
+        &self,
+        headers: &HeaderMap,
+    ) -> Result<PaymentContext, PaymentRequired> {
+        let verified = match self.standard_payment(headers) {
+            Some((standard, payment)) => self
+                .verify_standard(standard, payment)
+                .await
+                .map(|payment| self.meter(payment))
+                .map(|payment| self.attach_receipt(payment))
+                .map_err(|e| self.payment_required(Some(e))),
+            None => self.verify_request(headers),
+        };
+        log_verification(&verified);
+        verified
+    }
+
+    /// The `X-PAYMENT` header of a request, when standard payments are
+    /// accepted and no receipt takes precedence
+    fn standard_payment<'a>(
+        &'a self,
+        headers: &'a HeaderMap,
+    ) -> Option<(&'a StandardPayments, &'a HeaderValue)> {
+        if self.receipts.is_some() && headers.contains_key(RECEIPT_HEADER) {
+            return None;
+        }
+        Some((self.standard.as_deref()?, headers.get(PAYMENT_HEADER)?))
+    }
+
+    fn verify_request(&self, headers: &HeaderMap) -> Result<PaymentContext, PaymentRequired> {
+        if let (Some(issuer), Some(receipt)) = (&self.receipts, headers.get(RECEIPT_HEADER)) {
+            return self
+                .verify_receipt(issuer, receipt)
+                .map_err(|e| self.payment_required(Some(e)));
+        }
+        if let (Some(ledger), true) = (&self.tabs, headers.contains_key("X402-Tab-ID")) {
+            return self
+                .verify_tab(ledger, headers)
+                .map(|payment| self.meter(payment))
+                .map_err(|e| self.payment_required(Some(e)));
+        }
+        if let (Some(ledger), true) = (&self.credits, headers.contains_key(CREDIT_HEADER)) {
+            return self
+                .verify_credit_claim(ledger, headers)
+                .map(|payment| self.meter(payment))
+                .map_err(|e| self.payment_required(Some(e)));
+        }
+        if !HEADER_NAMES.iter().any(|name| headers.contains_key(*name)) {
+            return Err(self.payment_required(None));
+        }
+        X402Headers::from_headers(headers)
+            .and_then(|headers| self.verifier.verify_headers(&self.ucl, &headers))
+            .map(PaymentContext::from)
+            .and_then(|mut payment| {
+                if let Some(ledger) = &self.credits {
+                    ledger.record_payment(&payment.signer);
+                    payment.credits_remaining = Some(ledger.consume(&payment.signer)?);
+                }
+                Ok(payment)
+            })
+            .map(|payment| self.meter(payment))
+            .map(|payment| self.attach_receipt(payment))
+            .map_err(|e| self.payment_required(Some(e)))
+    }
+
+    /// Record a verified payment with the usage meter, if any
+    fn meter(&self, payment: PaymentContext) -> PaymentContext {
+        if let Some(meter) = &self.meter {
+            meter.record(&payment);
+        }
+        payment
+    }
+
+    /// Mint a receipt for a one-off payment when receipts are enabled
+    ///
+    /// Payments that buy credits get none: the credits already cover
+    /// follow-up requests.
+    fn attach_receipt(&self, mut payment: PaymentContext) -> PaymentContext {
+        if let (Some(issuer), None) = (&self.receipts, payment.credits_remaining) {
+            payment.receipt = issuer.issue(&payment).ok();
+        }
+        payment
+    }
+
+    /// Remaining prepaid credits of a signer, if credits are sold
+    pub fn credits_remaining(&self, signer: &str) -> Option<u64> {
+        self.credits.as_ref().map(|ledger| ledger.remaining(signer))
+    }
+
+    fn verify_receipt(
+        &self,
+        issuer: &ReceiptIssuer,
+        header: &HeaderValue,
+    ) -> Result<PaymentContext, VerificationError> {
+        let token = header
+            .to_str()
+            .map_err(|_| VerificationError::InvalidReceipt("not valid UTF-8".to_string()))?;
+        let claims = issuer.verify(token)?;
+        if claims.cid != self.ucl.contract_id {
+            return Err(VerificationError::ContractMismatch {
+                expected: self.ucl.contract_id.clone(),
+                actual: claims.cid,
+            });
+        }
+        Ok(PaymentContext {
+            contract_id: claims.cid,
+            signer: claims.sub,
+            role: claims.role,
+            amount: claims.amount,
+            token: claims.token,
+            network: claims.network,
+            nonce: claims.jti,
+            credits_remaining: None,
+            receipt: None,
+            settlement: None,
+        })
+    }
+
+    fn verify_credit_claim(
+        &self,
+        ledger: &CreditLedger,
+        headers: &HeaderMap,
+    ) -> Result<PaymentContext, VerificationError> {
+        let claim = CreditClaim::from_map(&header_map_to_hash_map(headers))?;
+        let (signer, role) = claim.verify(&self.ucl)?;
+        if ledger.remaining(&signer) == 0 {
+            return Err(VerificationError::CreditsExhausted(signer));
+        }
+        self.verifier.accept_nonce(&signer, &claim.nonce)?;
+        let remaining = ledger.consume(&signer)?;
+        Ok(PaymentContext {
+            contract_id: claim.contract_id,
+            signer,
+            role,
+            amount: "0".to_string(),
+            token: self.ucl.payment.token.to_string(),
+            network: self.ucl.payment.blockchain.to_string(),
+            nonce: claim.nonce,
+            credits_remaining: Some(remaining),
+            receipt: None,
+            settlement: None,
+        })
+    }
+
+    fn verify_tab(
+        &self,
+        ledger: &TabLedger,
+        headers: &HeaderMap,
+    ) -> Result<PaymentContext, VerificationError> {
+        let commitment = TabCommitment::from_map(&header_map_to_hash_map(headers))?;
+        let balance = ledger.accept(&self.ucl, &commitment)?;
+        Ok(PaymentContext {
+            contract_id: commitment.contract_id,
+            signer: balance.signer,
+            role: balance.role,
+            amount: self.ucl.payment.amount.to_string(),
+            token: self.ucl.payment.token.to_string(),
+            network: self.ucl.payment.blockchain.to_string(),
+            nonce: format!("{}:{}", commitment.tab_id, commitment.sequence),
+            credits_remaining: None,
+            receipt: None,
+            settlement: None,
+        })
+    }
+
+    async fn verify_standard(
+        &self,
+        standard: &StandardPayments,
+        header: &HeaderValue,
+    ) -> Result<PaymentContext, VerificationError> {
+        let requirements = &standard.requirements;
+        let header = header
+            .to_str()
+            .map_err(|_| VerificationError::MalformedHeader {
+                header: PAYMENT_HEADER.to_string(),
+                reason: "not valid UTF-8".to_string(),
+            })?;
+        let payment = PaymentPayload::decode(header)?;
+        let payer = payment.verify(requirements)?;
+        let authorization = &payment.payload.authorization;
+        let amount = requirements
+            .asset_amount(&authorization.value)
+            .map_err(|e| VerificationError::MalformedHeader {
+                header: PAYMENT_HEADER.to_string(),
+                reason: e.to_string(),
+            })?;
+
+        // Only the facilitator knows whether the payer holds the funds
+        let unavailable =
+            |e: crate::Error| VerificationError::FacilitatorUnavailable(e.to_string());
+        let verdict = standard
+            .facilitator
+            .verify(&payment, requirements)
+            .await
+            .map_err(unavailable)?;
+        if !verdict.is_valid {
+            return Err(VerificationError::FacilitatorRejected(
+                verdict
+                    .invalid_reason
+                    .unwrap_or_else(|| "invalid payment".to_string()),
+            ));
+        }
+        self.verifier.record_nonce(
+            &payer,
+            &authorization.nonce,
+            authorization.valid_before.parse().unwrap_or_default(),
+        )?;
+        let settlement = standard
+            .facilitator
+            .settle(&payment, requirements)
+            .await
+            .map_err(unavailable)?;
+        if !settlement.success {
+            return Err(VerificationError::Unsettled(
+                settlement
+                    .error_reason
+                    .unwrap_or_else(|| "settlement failed".to_string()),
+            ));
+        }
+
+        // Anyone may pay a standard challenge; parties keep their contract role
+        let role = self
+            .ucl
+            .metadata
+            .parties
+            .iter()
+            .find(|p| is_party(p, SignatureScheme::Secp256k1, &payer))
+            .map(|p| p.role.clone())
+            .unwrap_or_else(|| "payer".to_string());
+
+        Ok(PaymentContext {
+            contract_id: self.ucl.contract_id.clone(),
+            signer: payer,
+            role,
+            amount: amount.to_string(),
+            token: self.ucl.payment.token.to_string(),
+            network: payment.network.clone(),
+            nonce: authorization.nonce.clone(),
+            credits_remaining: None,
+            receipt: None,
+            settlement: settlement.encode().ok(),
+        })
+    }
+
+    fn payment_required(&self, reason: Option<VerificationError>) -> PaymentRequired {
+        let mut required = PaymentRequired::from_shared(&self.ucl);
+        if let Some(reason) = reason {
+            required = required.with_reason(reason);
+        }
+        for price in self.verifier.accepted_prices() {
+            required = required.with_price_option(price.clone());
+        }
+        for option in &self.fiat {
+            required = required.with_fiat_option(option.clone());
+        }
+        if let Some(standard) = &self.standard {
+            required = required.with_requirements(standard.requirements.clone());
+        }
+        required
+    }
+}
+
+/// Verified payment attached to a request, available to handlers
+#[derive(Debug, Clone)]
+pub struct PaymentContext {
+    pub contract_id: String,
+    /// Verified signer identity
+    pub signer: String,
+    /// Role of the signer in the contract
+    pub role: String,
+    pub amount: String,
+    pub token: String,
+    pub network: String,
+    pub nonce: String,
+    /// Prepaid credits left after this request, when the paywall sells credits
+    pub credits_remaining: Option<u64>,
+    /// Receipt token minted for this payment, returned in the `X402-Receipt`
+    /// response header
+    pub receipt: Option<String>,
+    /// Facilitator settlement of a standard x402 payment, returned in the
+    /// `X-PAYMENT-RESPONSE` response header
+    pub settlement: Option<String>,
+}
+
+impl From<VerifiedPayment> for PaymentContext {
+    fn from(payment: VerifiedPayment) -> Self {
+        Self {
+            contract_id: payment.headers.contract_id,
+            signer: payment.signer,
+            role: payment.role,
+            amount: payment.headers.payment_amount,
+            token: payment.headers.payment_token,
+            network: payment.headers.settlement_network,
+            nonce: payment.headers.nonce,
+            credits_remaining: None,
+            receipt: None,
+            settlement: None,
+        }
+    }
+}
+
+fn log_verification(verified: &Result<PaymentContext, PaymentRequired>) {
+    match verified {
+        Ok(payment) => tracing::info!(
+            signer = %payment.signer,
+            amount = %payment.amount,
+            token = %payment.token,
+            network = %payment.network,
+            "payment verified"
+        ),
+        Err(required) => tracing::debug!(reason = ?required.reason(), "payment required"),
+    }
+}
+
+/// Header names mapped to their (UTF-8) values
+fn header_map_to_hash_map(headers: &HeaderMap) -> HashMap<String, String> {
+    headers
+        .iter()
+        .filter_map(|(k, v)| Some((k.as_str().to_string(), v.to_str().ok()?.to_string())))
+        .collect()
+}
@@ -0,0 +1,253 @@
+//! Client-side spending limits
+//!
+//! A [`SpendingPolicy`] installed on an [`X402Client`] is checked before any
+//! payment is signed: X402 headers, tab commitments and standard x402
+//! authorizations. Amounts are compared in whole token units regardless of
+//! token, so limits assume payments in one denomination (typically a USD
+//! stablecoin); standard payments are converted from base units with the
+//! asset's decimals.
+
+use super::client::X402Client;
+use crate::Amount;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Window of the hourly limits
+const HOUR: Duration = Duration::from_secs(3600);
+
+/// Why a spending policy refused to sign a payment
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[non_exhaustive]
+pub enum PolicyViolation {
+    #[error("Payment of {amount} exceeds the per-request limit of {limit}")]
+    PerRequest { amount: Amount, limit: Amount },
+
+    #[error("Payment would bring spending in the last hour to {total}, limit is {limit}")]
+    PerHour { total: Amount, limit: Amount },
+
+    #[error("Payment would bring spending on contract {contract_id} to {total}, limit is {limit}")]
+    PerContract {
+        contract_id: String,
+        total: Amount,
+        limit: Amount,
+    },
+
+    #[error("{limit} payments were already made in the last hour")]
+    RateLimited { limit: u32 },
+}
+
+#[derive(Debug, Default)]
+struct Spending {
+    /// Time and amount of every payment in the last hour, oldest first
+    recent: VecDeque<(Instant, Amount)>,
+    per_contract: HashMap<String, Amount>,
+}
+
+/// Hard limits on what a client may sign
+#[derive(Debug, Default)]
+pub struct SpendingPolicy {
+    max_per_request: Option<Amount>,
+    max_per_hour: Option<Amount>,
+    max_per_contract: Option<Amount>,
+    max_payments_per_hour: Option<u32>,
+    spending: Mutex<Spending>,
+}
+
+impl SpendingPolicy {
+    /// Policy without limits; add them with the `with_*` methods
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Largest amount a single payment may carry
+    pub fn with_max_per_request(mut self, amount: Amount) -> Self {
+        self.max_per_request = Some(amount);
+        self
+    }
+
+    /// Largest total over any rolling hour
+    pub fn with_max_per_hour(mut self, amount: Amount) -> Self {
+        self.max_per_hour = Some(amount);
+        self
+    }
+
+    /// Largest total paid under one contract over the policy's lifetime
+    pub fn with_max_per_contract(mut self, amount: Amount) -> Self {
+        self.max_per_contract = Some(amount);
+        self
+    }
+
+    /// Largest number of payments over any rolling hour
+    pub fn with_max_payments_per_hour(mut self, count: u32) -> Self {
+        self.max_payments_per_hour = Some(count);
+        self
+    }
+
+    /// Check a payment against every limit and record it if allowed
+    pub fn authorize(&self, contract_id: &str, amount: Amount) -> Result<(), PolicyViolation> {
+        if let Some(limit) = self.max_per_request {
+            if amount > limit {
+                return Err(PolicyViolation::PerRequest { amount, limit });
+            }
+        }
+
+        let mut spending = self.spending.lock().unwrap();
+        let now = Instant::now();
+        while let Some((at, _)) = spending.recent.front() {
+            if now.duration_since(*at) < HOUR {
+                break;
+            }
+            spending.recent.pop_front();
+        }
+
+        if let Some(limit) = self.max_payments_per_hour {
+            if spending.recent.len() >= limit as usize {
+                return Err(PolicyViolation::RateLimited { limit });
+            }
+        }
+        if let Some(limit) = self.max_per_hour {
+            let total = spending.recent.iter().map(|(_, a)| *a).sum::<Amount>() + amount;
+            if total > limit {
+                return Err(PolicyViolation::PerHour { total, limit });
+            }
+        }
+        let contract_total = spending
+            .per_contract
+            .get(contract_id)
+            .copied()
+            .unwrap_or_default()
+            + amount;
+        if let Some(limit) = self.max_per_contract {
+            if contract_total > limit {
+                return Err(PolicyViolation::PerContract {
+                    contract_id: contract_id.to_string(),
+                    total: contract_total,
+                    limit,
+                });
+            }
+        }
+
+        spending.recent.push_back((now, amount));
+        spending
+            .per_contract
+            .insert(contract_id.to_string(), contract_total);
+        Ok(())
+    }
+
+    /// Total authorized over the last hour
+    pub fn spent_last_hour(&self) -> Amount {
+        let now = Instant::now();
+        self.spending
+            .lock()
+            .unwrap()
+            .recent
+            .iter()
+            .filter(|(at, _)| now.duration_since(*at) < HOUR)
+            .map(|(_, amount)| *amount)
+            .sum()
+    }
+
+    /// Total authorized under a contract
+    pub fn spent_on(&self, contract_id: &str) -> Amount {
+        self.spending
+            .lock()
+            .unwrap()
+            .per_contract
+            .get(contract_id)
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+impl X402Client {
+    /// Enforce `policy` before signing any payment
+    pub fn with_spending_policy(mut self, policy: SpendingPolicy) -> Self {
+        self.policy = Some(Arc::new(policy));
+        self
+    }
+
+    /// Spending policy in force, if any
+    pub fn spending_policy(&self) -> Option<&SpendingPolicy> {
+        self.policy.as_deref()
+    }
+
+    /// Authorize a payment against the spending policy, if one is set
+    pub(crate) fn authorize_spend(&self, contract_id: &str, amount: Amount) -> crate::Result<()> {
+        match &self.policy {
+            Some(policy) => Ok(policy.authorize(contract_id, amount)?),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Amount, Contract, ContractConfig, Error};
+
+    const TEST_KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    #[test]
+    fn test_policy_limits() {
+        let policy = SpendingPolicy::new()
+            .with_max_per_request(Amount::from(1))
+            .with_max_per_hour(Amount::new(25, 1))
+            .with_max_per_contract(Amount::new(15, 1));
+
+        assert_eq!(
+            policy.authorize("a", Amount::from(2)),
+            Err(PolicyViolation::PerRequest {
+                amount: Amount::from(2),
+                limit: Amount::from(1)
+            })
+        );
+        policy.authorize("a", Amount::from(1)).unwrap();
+        assert!(matches!(
+            policy.authorize("a", Amount::from(1)),
+            Err(PolicyViolation::PerContract { .. })
+        ));
+        policy.authorize("b", Amount::from(1)).unwrap();
+        assert!(matches!(
+            policy.authorize("c", Amount::from(1)),
+            Err(PolicyViolation::PerHour { .. })
+        ));
+        assert_eq!(policy.spent_last_hour(), Amount::from(2));
+
+        let limited = SpendingPolicy::new().with_max_payments_per_hour(1);
+        limited.authorize("a", Amount::new(1, 1)).unwrap();
+        assert_eq!(
+            limited.authorize("a", Amount::new(1, 1)),
+            Err(PolicyViolation::RateLimited { limit: 1 })
+        );
+    }
+
+    #[test]
+    fn test_limits_are_exact() {
+        // 0.1 + 0.2 exceeds 0.3 in floats
+        let policy = SpendingPolicy::new().with_max_per_contract(Amount::new(3, 1));
+        policy.authorize("a", Amount::new(1, 1)).unwrap();
+        policy.authorize("a", Amount::new(2, 1)).unwrap();
+        assert_eq!(policy.spent_on("a"), Amount::new(3, 1));
+    }
+
+    #[test]
+    fn test_client_refuses_to_sign_over_limit() {
+        let client = X402Client::with_private_key("https://x402.test".to_string(), TEST_KEY)
+            .unwrap()
+            .with_spending_policy(SpendingPolicy::new().with_max_per_request(Amount::new(1, 2)));
+        let mut ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+        ucl.payment.amount = Amount::from(5);
+
+        assert!(matches!(
+            client.generate_headers(&ucl, true),
+            Err(Error::PolicyViolation(PolicyViolation::PerRequest { .. }))
+        ));
+        assert!(matches!(
+            client.open_tab(&ucl).unwrap().next_commitment(),
+            Err(Error::PolicyViolation(_))
+        ));
+    }
+}
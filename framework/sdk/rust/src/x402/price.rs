@@ -0,0 +1,194 @@
+//! Multi-token price negotiation
+//!
+//! A 402 challenge may advertise alternative `(amount, token, network)`
+//! tuples next to the contract's own terms in the `X402-Accept-Prices`
+//! header, e.g. `0.05 USDC base, 0.05 DAI polygon`. The client picks the
+//! option it can pay and signs that amount, token and network into its
+//! regular X402 headers; the verifier accepts any advertised option.
+
+use super::verify::VerificationError;
+use crate::network::same_network;
+use crate::{Amount, UCLContract};
+use serde::{Deserialize, Serialize};
+
+/// Header listing alternative prices a server accepts
+pub const PRICES_HEADER: &str = "X402-Accept-Prices";
+
+/// One acceptable way to pay: an amount of a token on a network
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PriceOption {
+    /// Decimal amount in whole token units
+    pub amount: String,
+    pub token: String,
+    pub network: String,
+}
+
+impl PriceOption {
+    pub fn new(
+        amount: impl Into<String>,
+        token: impl Into<String>,
+        network: impl Into<String>,
+    ) -> Self {
+        Self {
+            amount: amount.into(),
+            token: token.into(),
+            network: network.into(),
+        }
+    }
+
+    /// The contract's own payment terms as an option
+    pub fn for_contract(ucl: &UCLContract) -> Self {
+        Self::new(
+            ucl.payment.amount.to_string(),
+            ucl.payment.token.symbol(),
+            ucl.payment.blockchain.to_string(),
+        )
+    }
+
+    /// Whether this option is paid in `token` on `network` (case-insensitive,
+    /// network aliases included)
+    pub fn matches(&self, token: &str, network: &str) -> bool {
+        self.token.eq_ignore_ascii_case(token) && same_network(&self.network, network)
+    }
+
+    /// Format options as an `X402-Accept-Prices` header value
+    pub fn format_header(options: &[PriceOption]) -> String {
+        options
+            .iter()
+            .map(|o| format!("{} {} {}", o.amount, o.token, o.network))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Parse an `X402-Accept-Prices` header value
+    pub fn parse_header(value: &str) -> std::result::Result<Vec<Self>, VerificationError> {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let malformed = |reason: String| VerificationError::MalformedHeader {
+                    header: PRICES_HEADER.to_string(),
+                    reason,
+                };
+                let parts: Vec<&str> = entry.split_whitespace().collect();
+                let [amount, token, network] = parts[..] else {
+                    return Err(malformed(format!(
+                        "expected \"amount token network\", got {}",
+                        entry
+                    )));
+                };
+                match amount.parse::<Amount>() {
+                    Ok(a) if !a.is_sign_negative() => Ok(Self::new(amount, token, network)),
+                    _ => Err(malformed(format!("not a non-negative decimal: {}", amount))),
+                }
+            })
+            .collect()
+    }
+
+    /// Options offered by a 402 response: the contract terms first, then
+    /// any alternatives from `X402-Accept-Prices`
+    pub fn from_challenge(
+        headers: &http::HeaderMap,
+    ) -> std::result::Result<Vec<Self>, VerificationError> {
+        let get = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+        let mut options = Vec::new();
+        if let (Some(amount), Some(token), Some(network)) = (
+            get("X402-Payment-Amount"),
+            get("X402-Payment-Token"),
+            get("X402-Settlement-Network"),
+        ) {
+            options.push(Self::new(amount, token, network));
+        }
+        if let Some(value) = get(PRICES_HEADER) {
+            for option in Self::parse_header(value)? {
+                if !options.contains(&option) {
+                    options.push(option);
+                }
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// Pick the first option paid with a preferred `(token, network)` pair
+///
+/// `preferences` is ordered from most to least preferred, typically the
+/// balances the caller holds.
+pub fn choose_price<'a>(
+    options: &'a [PriceOption],
+    preferences: &[(&str, &str)],
+) -> Option<&'a PriceOption> {
+    preferences
+        .iter()
+        .find_map(|(token, network)| options.iter().find(|option| option.matches(token, network)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PartyInfo;
+    use crate::x402::{PaymentRequired, PaymentVerifier, VerificationError, X402Client};
+    use crate::{Amount, Contract, ContractConfig};
+
+    const TEST_KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    #[test]
+    fn test_negotiated_price_is_verified() {
+        let client =
+            X402Client::with_private_key("https://x402.test".to_string(), TEST_KEY).unwrap();
+        let mut ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+        ucl.payment.amount = Amount::new(5, 2);
+        ucl.payment.token = "USDC".parse().unwrap();
+        ucl.payment.blockchain = crate::Network::Base;
+        ucl.metadata.parties.push(PartyInfo {
+            role: "payer".to_string(),
+            identifier: client.signer_address().unwrap(),
+            name: None,
+            ..Default::default()
+        });
+
+        let challenge: http::Response<String> = PaymentRequired::for_contract(&ucl)
+            .with_price_option(PriceOption::new("0.06", "DAI", "polygon"))
+            .into_http_response();
+        let options = PriceOption::from_challenge(challenge.headers()).unwrap();
+        assert_eq!(options.len(), 2);
+
+        let price = choose_price(&options, &[("dai", "polygon"), ("USDC", "base")]).unwrap();
+        assert_eq!(price.token, "DAI");
+        let headers = client
+            .generate_headers_with_price(&ucl, true, price)
+            .unwrap()
+            .to_map();
+
+        let verifier = PaymentVerifier::new()
+            .with_accepted_prices(vec![PriceOption::new("0.06", "DAI", "polygon")]);
+        verifier.verify(&ucl, &headers).unwrap();
+        assert!(matches!(
+            PaymentVerifier::new().verify(&ucl, &headers),
+            Err(VerificationError::TermsMismatch { .. })
+        ));
+
+        let underpaid = client
+            .generate_headers_with_price(&ucl, true, &PriceOption::new("0.05", "DAI", "polygon"))
+            .unwrap()
+            .to_map();
+        assert!(matches!(
+            verifier.verify(&ucl, &underpaid),
+            Err(VerificationError::TermsMismatch { field, .. }) if field == "amount"
+        ));
+    }
+
+    #[test]
+    fn test_parse_prices_header() {
+        let options = PriceOption::parse_header("0.05 USDC base, 1 DAI polygon").unwrap();
+        assert_eq!(
+            PriceOption::format_header(&options),
+            "0.05 USDC base, 1 DAI polygon"
+        );
+        assert!(PriceOption::parse_header("0.05 USDC").is_err());
+        assert!(PriceOption::parse_header("-1 USDC base").is_err());
+    }
+}
@@ -0,0 +1,170 @@
+//! Reverse proxy enforcing an X402 paywall in front of any HTTP backend
+//!
+//! Every request must carry a valid payment for the contract; paid
+//! requests are forwarded to the upstream with the verified payer in the
+//! `X402-Verified-Signer` and `X402-Verified-Role` headers. Used by
+//! `smart402 serve --paywall`.
+
+use super::paywall::{PaymentContext, X402Paywall};
+use super::server::paywall;
+use crate::Result;
+use axum::body::{Bytes, Full};
+use axum::extract::State;
+use axum::http::{HeaderMap, HeaderName, Method, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use axum::{middleware, Extension, Router};
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+/// Headers describing a single connection, never forwarded
+const HOP_BY_HOP: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+    "host",
+    "content-length",
+];
+
+/// Reverse proxy charging for every request
+#[derive(Clone)]
+pub struct PaywallProxy {
+    wall: X402Paywall,
+    upstream: Arc<str>,
+    http: reqwest::Client,
+}
+
+impl PaywallProxy {
+    /// Proxy to `upstream` (e.g. `http://localhost:3000`) behind `wall`
+    pub fn new(wall: X402Paywall, upstream: &str) -> Self {
+        Self {
+            wall,
+            upstream: upstream.trim_end_matches('/').into(),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Router serving every path through the paywall
+    pub fn router(self) -> Router {
+        let wall = self.wall.clone();
+        Router::new()
+            .fallback(forward)
+            .layer(middleware::from_fn_with_state(wall, paywall))
+            .with_state(self)
+    }
+
+    /// Listen on `addr` until the process exits
+    pub async fn serve(self, addr: SocketAddr) -> Result<()> {
+        axum::Server::try_bind(&addr)
+            .map_err(|e| crate::Error::ConfigError(format!("Cannot listen on {}: {}", addr, e)))?
+            .serve(self.router().into_make_service())
+            .await
+            .map_err(|e| crate::Error::NetworkError(e.to_string()))
+    }
+}
+
+async fn forward(
+    State(proxy): State<PaywallProxy>,
+    Extension(payment): Extension<PaymentContext>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let path = uri.path_and_query().map_or("/", |p| p.as_str());
+    let mut request = proxy
+        .http
+        .request(method, format!("{}{}", proxy.upstream, path))
+        .body(body);
+    for (name, value) in forwardable(&headers) {
+        request = request.header(name, value);
+    }
+    request = request
+        .header("X402-Verified-Signer", &payment.signer)
+        .header("X402-Verified-Role", &payment.role);
+
+    let upstream = match request.send().await {
+        Ok(upstream) => upstream,
+        Err(e) => return (StatusCode::BAD_GATEWAY, e.to_string()).into_response(),
+    };
+    let status = upstream.status();
+    let headers = upstream.headers().clone();
+    let body = match upstream.bytes().await {
+        Ok(body) => body,
+        Err(e) => return (StatusCode::BAD_GATEWAY, e.to_string()).into_response(),
+    };
+
+    let mut response = Response::new(axum::body::boxed(Full::new(body)));
+    *response.status_mut() = status;
+    for (name, value) in forwardable(&headers) {
+        response.headers_mut().append(name.clone(), value.clone());
+    }
+    response
+}
+
+fn forwardable(
+    headers: &HeaderMap,
+) -> impl Iterator<Item = (&HeaderName, &axum::http::HeaderValue)> {
+    headers
+        .iter()
+        .filter(|(name, _)| !HOP_BY_HOP.contains(&name.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PartyInfo;
+    use crate::x402::test_server::{header, serve};
+    use crate::x402::X402Client;
+    use crate::{Contract, ContractConfig};
+    use axum::body::Body;
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    const TEST_KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    #[tokio::test]
+    async fn test_proxy_forwards_paid_requests() {
+        let (upstream, requests) = serve(vec![(200, r#"{"forecast":"sunny"}"#)]).await;
+        let client =
+            X402Client::with_private_key("https://x402.test".to_string(), TEST_KEY).unwrap();
+        let mut ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+        ucl.metadata.parties.push(PartyInfo {
+            role: "payer".to_string(),
+            identifier: client.signer_address().unwrap(),
+            name: None,
+            ..Default::default()
+        });
+        let app = PaywallProxy::new(X402Paywall::new(ucl.clone()), &upstream).router();
+
+        let unpaid = app
+            .clone()
+            .oneshot(Request::get("/forecast").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(unpaid.status(), StatusCode::PAYMENT_REQUIRED);
+
+        let mut request = Request::get("/forecast?city=paris");
+        for (name, value) in client.generate_headers(&ucl, true).unwrap().to_map() {
+            request = request.header(name, value);
+        }
+        let paid = app
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(paid.status(), StatusCode::OK);
+
+        let forwarded = requests.lock().unwrap()[0].clone();
+        assert!(forwarded.starts_with("GET /forecast?city=paris "));
+        assert_eq!(
+            header(&forwarded, "X402-Verified-Signer"),
+            client.signer_address().as_deref()
+        );
+    }
+}
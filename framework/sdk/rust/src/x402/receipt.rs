@@ -0,0 +1,284 @@
+//! Signed payment receipt tokens
+//!
+//! After verifying a payment the paywall can mint a short-lived receipt, a
+//! JWT signed by the server's [`X402Signer`], and return it in the
+//! `X402-Receipt` response header. The client presents the token on later
+//! requests instead of paying again until it expires.
+//!
+//! Ed25519 receipts use the standard `EdDSA` JWT algorithm. Secp256k1
+//! receipts use `EIP191`: an EIP-191 personal-message signature over the
+//! JWT signing input, verifiable by recovering the server address.
+
+use super::nonce::unix_now;
+use super::paywall::PaymentContext;
+use super::signing::{verify_signature, SignatureError, SignatureScheme, X402Signer};
+use super::verify::VerificationError;
+use crate::Result;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Header carrying a receipt token, in responses and later requests
+pub const RECEIPT_HEADER: &str = "X402-Receipt";
+
+/// Default lifetime of a receipt
+pub const DEFAULT_RECEIPT_TTL: Duration = Duration::from_secs(300);
+
+/// Claims of a receipt token
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReceiptClaims {
+    /// Identity of the server that issued the receipt
+    pub iss: String,
+    /// Verified payer identity
+    pub sub: String,
+    /// Contract the payment was made under
+    pub cid: String,
+    pub role: String,
+    pub amount: String,
+    pub token: String,
+    pub network: String,
+    /// Nonce of the payment the receipt was minted for
+    pub jti: String,
+    pub iat: u64,
+    pub exp: u64,
+}
+
+impl ReceiptClaims {
+    /// Read claims without checking the signature
+    ///
+    /// Lets clients inspect expiry of a token they received; servers must
+    /// use [`ReceiptIssuer::verify`] or [`verify_receipt`].
+    pub fn decode_unverified(token: &str) -> std::result::Result<Self, VerificationError> {
+        let (_, claims, _) = split(token)?;
+        decode_segment(claims)
+    }
+
+    /// Whether the receipt has expired
+    pub fn is_expired(&self) -> bool {
+        unix_now() >= self.exp
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct JwtHeader {
+    alg: String,
+    typ: String,
+}
+
+/// Mints and verifies receipt tokens with a server key
+#[derive(Clone)]
+pub struct ReceiptIssuer {
+    signer: Arc<dyn X402Signer>,
+    ttl: Duration,
+}
+
+impl ReceiptIssuer {
+    /// Issue receipts signed by `signer`, valid for [`DEFAULT_RECEIPT_TTL`]
+    pub fn new(signer: Arc<dyn X402Signer>) -> Self {
+        Self {
+            signer,
+            ttl: DEFAULT_RECEIPT_TTL,
+        }
+    }
+
+    /// Set receipt lifetime
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Identity receipts are verified against
+    pub fn identity(&self) -> String {
+        self.signer.identity()
+    }
+
+    /// Mint a receipt for a verified payment
+    pub fn issue(&self, payment: &PaymentContext) -> Result<String> {
+        let now = unix_now();
+        let claims = ReceiptClaims {
+            iss: self.signer.identity(),
+            sub: payment.signer.clone(),
+            cid: payment.contract_id.clone(),
+            role: payment.role.clone(),
+            amount: payment.amount.clone(),
+            token: payment.token.clone(),
+            network: payment.network.clone(),
+            jti: payment.nonce.clone(),
+            iat: now,
+            exp: now + self.ttl.as_secs(),
+        };
+        let header = JwtHeader {
+            alg: algorithm(self.signer.scheme()).to_string(),
+            typ: "JWT".to_string(),
+        };
+        let signing_input = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(serde_json::to_vec(&header)?),
+            URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims)?)
+        );
+        let signature = self.signer.sign(&signing_input)?;
+        let signature = match self.signer.scheme() {
+            SignatureScheme::Secp256k1 => hex::decode(signature.trim_start_matches("0x"))
+                .map_err(|e| SignatureError::Malformed {
+                    reason: e.to_string(),
+                })?,
+            SignatureScheme::Ed25519 => bs58::decode(signature)
+                .into_vec()
+                .map_err(|e| SignatureError::Malformed {
+                    reason: e.to_string(),
+                })?,
+        };
+        Ok(format!(
+            "{}.{}",
+            signing_input,
+            URL_SAFE_NO_PAD.encode(signature)
+        ))
+    }
+
+    /// Verify a receipt issued by this issuer
+    pub fn verify(&self, token: &str) -> std::result::Result<ReceiptClaims, VerificationError> {
+        verify_receipt(token, &self.signer.identity())
+    }
+
+    /// Verify the signature of a receipt that may have expired
+    pub(crate) fn verify_allowing_expired(
+        &self,
+        token: &str,
+    ) -> std::result::Result<ReceiptClaims, VerificationError> {
+        verify_signed_claims(token, &self.signer.identity())
+    }
+}
+
+/// Verify a receipt's signature and expiry against the issuer identity
+///
+/// Fails for an empty `issuer`, which no receipt is signed by.
+pub fn verify_receipt(
+    token: &str,
+    issuer: &str,
+) -> std::result::Result<ReceiptClaims, VerificationError> {
+    let claims = verify_signed_claims(token, issuer)?;
+    if claims.is_expired() {
+        return Err(VerificationError::ExpiredReceipt);
+    }
+    Ok(claims)
+}
+
+fn verify_signed_claims(
+    token: &str,
+    issuer: &str,
+) -> std::result::Result<ReceiptClaims, VerificationError> {
+    // An empty identity would skip the signer check
+    if issuer.is_empty() {
+        return Err(invalid("no issuer to verify the signature against"));
+    }
+    let (header, claims, signature) = split(token)?;
+    let jwt_header: JwtHeader = decode_segment(header)?;
+    let scheme = match jwt_header.alg.as_str() {
+        "EIP191" => SignatureScheme::Secp256k1,
+        "EdDSA" => SignatureScheme::Ed25519,
+        alg => return Err(invalid(&format!("unsupported algorithm {}", alg))),
+    };
+    let signature = URL_SAFE_NO_PAD
+        .decode(signature)
+        .map_err(|_| invalid("signature is not base64url"))?;
+    let signature = match scheme {
+        SignatureScheme::Secp256k1 => format!("0x{}", hex::encode(signature)),
+        SignatureScheme::Ed25519 => bs58::encode(signature).into_string(),
+    };
+    verify_signature(
+        scheme,
+        &format!("{}.{}", header, claims),
+        &signature,
+        issuer,
+    )
+    .map_err(|e| invalid(&e.to_string()))?;
+
+    decode_segment(claims)
+}
+
+fn algorithm(scheme: SignatureScheme) -> &'static str {
+    match scheme {
+        SignatureScheme::Secp256k1 => "EIP191",
+        SignatureScheme::Ed25519 => "EdDSA",
+    }
+}
+
+fn split(token: &str) -> std::result::Result<(&str, &str, &str), VerificationError> {
+    let mut parts = token.trim().split('.');
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(header), Some(claims), Some(signature), None) => Ok((header, claims, signature)),
+        _ => Err(invalid("expected three dot-separated segments")),
+    }
+}
+
+fn decode_segment<T: serde::de::DeserializeOwned>(
+    segment: &str,
+) -> std::result::Result<T, VerificationError> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(segment)
+        .map_err(|_| invalid("segment is not base64url"))?;
+    serde_json::from_slice(&bytes).map_err(|e| invalid(&e.to_string()))
+}
+
+fn invalid(reason: &str) -> VerificationError {
+    VerificationError::InvalidReceipt(reason.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::x402::{Ed25519Signer, Secp256k1Signer};
+
+    const TEST_KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    fn payment() -> PaymentContext {
+        PaymentContext {
+            contract_id: "smart402:test".to_string(),
+            signer: "0xpayer".to_string(),
+            role: "payer".to_string(),
+            amount: "0.05".to_string(),
+            token: "USDC".to_string(),
+            network: "base".to_string(),
+            nonce: "1700000000-00".to_string(),
+            credits_remaining: None,
+            receipt: None,
+            settlement: None,
+        }
+    }
+
+    #[test]
+    fn test_receipt_round_trip() {
+        for signer in [
+            Arc::new(Secp256k1Signer::from_private_key(TEST_KEY).unwrap()) as Arc<dyn X402Signer>,
+            Arc::new(Ed25519Signer::from_secret(&"11".repeat(32)).unwrap()),
+        ] {
+            let issuer = ReceiptIssuer::new(signer);
+            let token = issuer.issue(&payment()).unwrap();
+
+            let claims = verify_receipt(&token, &issuer.identity()).unwrap();
+            assert_eq!(claims.sub, "0xpayer");
+            assert!(matches!(
+                verify_receipt(&token, ""),
+                Err(VerificationError::InvalidReceipt(_))
+            ));
+            assert_eq!(ReceiptClaims::decode_unverified(&token).unwrap(), claims);
+
+            let mut tampered = token.clone();
+            tampered.insert(token.find('.').unwrap() + 2, 'x');
+            assert!(issuer.verify(&tampered).is_err());
+        }
+    }
+
+    #[test]
+    fn test_expired_receipt_is_rejected() {
+        let signer = Arc::new(Secp256k1Signer::from_private_key(TEST_KEY).unwrap());
+        let issuer = ReceiptIssuer::new(signer).with_ttl(Duration::ZERO);
+        let token = issuer.issue(&payment()).unwrap();
+        assert_eq!(
+            issuer.verify(&token),
+            Err(VerificationError::ExpiredReceipt)
+        );
+    }
+}
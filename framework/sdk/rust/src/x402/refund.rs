@@ -0,0 +1,301 @@
+//! Refunds over X402
+//!
+//! A payer asks for a refund with a signed [`RefundRequest`] naming the
+//! nonce of the original payment, optionally backed by the receipt token
+//! the paywall minted for it. The server checks the request with
+//! [`X402Paywall::verify_refund`]: a refund is only allowed while some
+//! required contract condition is unmet, i.e. the service the payment
+//! bought was not delivered. The resulting [`RefundNotice`] is recorded by
+//! both parties with [`Contract::record_refund`], linking the refund to
+//! the original payment in their histories.
+
+use super::client::{X402Client, X402Headers};
+use super::nonce::generate_nonce;
+use super::paywall::X402Paywall;
+use super::signing::{verify_signature, SignatureScheme};
+use super::verify::{party_role, VerificationError};
+use super::webhook::RefundNotice;
+use crate::{
+    Amount, ConditionCheckResult, Contract, PaymentMethod, PaymentRecord, Result, UCLContract,
+};
+use serde::{Deserialize, Serialize};
+
+/// Signed request to refund an earlier payment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RefundRequest {
+    pub contract_id: String,
+    /// Nonce of the payment to refund
+    pub payment_nonce: String,
+    /// Decimal amount to refund
+    pub amount: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Receipt token minted for the payment, if the server issues receipts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub receipt: Option<String>,
+    /// Fresh nonce of the request itself
+    pub nonce: String,
+    pub signature_scheme: SignatureScheme,
+    pub signer: String,
+    pub signature: String,
+}
+
+impl RefundRequest {
+    /// Canonical payload covered by the signature
+    pub fn signing_payload(&self) -> String {
+        format!(
+            "X402-Refund:{}\nX402-Payment-Nonce:{}\nX402-Refund-Amount:{}\nX402-Refund-Reason:{}\n\
+             X402-Receipt:{}\nX402-Nonce:{}\nX402-Signature-Scheme:{}\nX402-Signer:{}",
+            self.contract_id,
+            self.payment_nonce,
+            self.amount,
+            self.reason.as_deref().unwrap_or(""),
+            self.receipt.as_deref().unwrap_or(""),
+            self.nonce,
+            self.signature_scheme,
+            self.signer
+        )
+    }
+}
+
+/// Refund request that passed every check
+#[derive(Debug, Clone)]
+pub struct VerifiedRefund {
+    pub request: RefundRequest,
+    /// Verified signer identity
+    pub signer: String,
+    /// Role of the signer in the contract
+    pub role: String,
+    pub amount: Amount,
+}
+
+impl X402Client {
+    /// Sign a request to refund the payment made with `payment`
+    ///
+    /// Pass the receipt returned with the payment, if any, so the server
+    /// can check the amount paid without its own records.
+    pub fn refund_request(
+        &self,
+        ucl: &UCLContract,
+        payment: &X402Headers,
+        amount: Amount,
+        reason: Option<&str>,
+        receipt: Option<&str>,
+    ) -> Result<RefundRequest> {
+        let signer = self
+            .signer
+            .as_ref()
+            .ok_or_else(|| crate::Error::ConfigError("X402Client has no signer".to_string()))?;
+        let mut request = RefundRequest {
+            contract_id: ucl.contract_id.clone(),
+            payment_nonce: payment.nonce.clone(),
+            amount: amount.to_string(),
+            reason: reason.map(str::to_string),
+            receipt: receipt.map(str::to_string),
+            nonce: generate_nonce(),
+            signature_scheme: signer.scheme(),
+            signer: signer.identity(),
+            signature: String::new(),
+        };
+        request.signature = signer.sign(&request.signing_payload())?;
+        Ok(request)
+    }
+
+    /// Submit a refund request to the facilitator
+    pub async fn submit_refund(&self, request: &RefundRequest) -> Result<RefundNotice> {
+        let response = self
+            .http
+            .post(self.url("refunds"))
+            .json(request)
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(crate::Error::PaymentError(format!(
+                "Facilitator rejected refund ({}): {}",
+                status, body
+            )));
+        }
+        Ok(response.json().await?)
+    }
+}
+
+impl X402Paywall {
+    /// Check a refund request against the contract and its conditions
+    ///
+    /// Verifies the signature, that the signer is a contract party, that
+    /// the request nonce is fresh and, when a receipt is attached, that it
+    /// was minted by this paywall for the same payer and payment and covers
+    /// the amount. The refund is eligible only if `conditions` shows a
+    /// required condition unmet.
+    pub fn verify_refund(
+        &self,
+        request: &RefundRequest,
+        conditions: &ConditionCheckResult,
+    ) -> std::result::Result<VerifiedRefund, VerificationError> {
+        let ucl = self.contract();
+        if request.contract_id != ucl.contract_id {
+            return Err(VerificationError::ContractMismatch {
+                expected: ucl.contract_id.clone(),
+                actual: request.contract_id.clone(),
+            });
+        }
+        let amount = match request.amount.parse::<Amount>() {
+            Ok(amount) if amount > Amount::ZERO => amount,
+            _ => {
+                return Err(VerificationError::MalformedHeader {
+                    header: "X402-Refund-Amount".to_string(),
+                    reason: format!("not a positive decimal: {}", request.amount),
+                })
+            }
+        };
+        let signer = verify_signature(
+            request.signature_scheme,
+            &request.signing_payload(),
+            &request.signature,
+            &request.signer,
+        )
+        .map_err(|e| VerificationError::InvalidSignature(e.to_string()))?;
+        let role = party_role(ucl, request.signature_scheme, &signer)?;
+
+        let paid = match (&request.receipt, self.receipt_issuer()) {
+            (Some(token), Some(issuer)) => {
+                let claims = issuer.verify_allowing_expired(token)?;
+                if claims.jti != request.payment_nonce || !claims.sub.eq_ignore_ascii_case(&signer)
+                {
+                    return Err(VerificationError::InvalidReceipt(
+                        "receipt is for another payment".to_string(),
+                    ));
+                }
+                claims.amount.parse().unwrap_or(Amount::ZERO)
+            }
+            _ => ucl.payment.amount,
+        };
+        if amount > paid {
+            return Err(VerificationError::RefundIneligible(format!(
+                "refund of {} exceeds the {} paid",
+                amount, paid
+            )));
+        }
+
+        let unmet = ucl
+            .conditions
+            .required
+            .iter()
+            .any(|c| !conditions.conditions.get(&c.id).copied().unwrap_or(false));
+        if conditions.all_met || !unmet {
+            return Err(VerificationError::RefundIneligible(
+                "all required contract conditions are met".to_string(),
+            ));
+        }
+
+        self.verifier().accept_nonce(&signer, &request.nonce)?;
+        Ok(VerifiedRefund {
+            request: request.clone(),
+            signer,
+            role,
+            amount,
+        })
+    }
+}
+
+impl Contract {
+    /// Record an issued refund in the payment history
+    ///
+    /// The entry carries the negated amount and links to the refunded
+    /// payment through `refund_of`.
+    pub fn record_refund(&mut self, notice: &RefundNotice) -> Result<()> {
+        let amount: Amount = notice.amount.parse().map_err(|_| {
+            crate::Error::PaymentError(format!("Invalid refund amount: {}", notice.amount))
+        })?;
+        self.record_payment(PaymentRecord {
+            reference: notice
+                .transaction_hash
+                .clone()
+                .unwrap_or_else(|| notice.refund_id.clone()),
+            method: PaymentMethod::Crypto {
+                network: self.ucl.payment.blockchain,
+            },
+            amount: -amount.abs(),
+            currency: self.ucl.payment.token.to_string(),
+            payer: None,
+            timestamp: chrono::Utc::now(),
+            refund_of: Some(notice.payment_reference.clone()),
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ConditionDefinition, PartyInfo};
+    use crate::ContractConfig;
+    use std::collections::HashMap;
+
+    const TEST_KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    fn conditions(met: bool) -> ConditionCheckResult {
+        ConditionCheckResult {
+            all_met: met,
+            conditions: HashMap::from([("uptime".to_string(), met)]),
+            timestamp: chrono::Utc::now(),
+            unavailable: Vec::new(),
+            attestations: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_refund_requires_unmet_condition() {
+        let client =
+            X402Client::with_private_key("https://x402.test".to_string(), TEST_KEY).unwrap();
+        let mut contract = Contract::from_config(ContractConfig::default()).unwrap();
+        contract.ucl.payment.amount = Amount::from(10);
+        contract.ucl.metadata.parties.push(PartyInfo {
+            role: "payer".to_string(),
+            identifier: client.signer_address().unwrap(),
+            name: None,
+            ..Default::default()
+        });
+        contract.ucl.conditions.required.push(ConditionDefinition {
+            id: "uptime".to_string(),
+            description: "99.9% uptime".to_string(),
+            source: "monitor".to_string(),
+            operator: ">=".to_string(),
+            threshold: Some(99.9.into()),
+            expression: None,
+            temporal: None,
+        });
+        let wall = X402Paywall::new(contract.ucl.clone());
+        let payment = client.generate_headers(&contract.ucl, true).unwrap();
+
+        let request = client
+            .refund_request(&contract.ucl, &payment, Amount::from(10), Some("outage"), None)
+            .unwrap();
+        assert!(matches!(
+            wall.verify_refund(&request, &conditions(true)),
+            Err(VerificationError::RefundIneligible(_))
+        ));
+        let refund = wall.verify_refund(&request, &conditions(false)).unwrap();
+        assert_eq!(refund.role, "payer");
+
+        let too_much = client
+            .refund_request(&contract.ucl, &payment, Amount::from(11), None, None)
+            .unwrap();
+        assert!(wall.verify_refund(&too_much, &conditions(false)).is_err());
+
+        contract
+            .record_refund(&RefundNotice {
+                refund_id: "rf_1".to_string(),
+                payment_reference: "0xpaid".to_string(),
+                amount: "10".to_string(),
+                transaction_hash: Some("0xrefund".to_string()),
+                reason: Some("outage".to_string()),
+            })
+            .unwrap();
+        let record = &contract.payment_history()[0];
+        assert_eq!(record.amount, Amount::from(-10));
+        assert_eq!(record.refund_of.as_deref(), Some("0xpaid"));
+    }
+}
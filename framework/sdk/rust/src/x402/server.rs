@@ -0,0 +1,227 @@
+//! Axum integration for X402 paywalls
+//!
+//! ```no_run
+//! use axum::{middleware, routing::get, Router};
+//! use smart402::x402::server::{paywall, PaymentContext, X402Paywall};
+//! # fn build(ucl: smart402::UCLContract) -> Router {
+//! async fn report(payment: PaymentContext) -> String {
+//!     format!("paid by {}", payment.signer)
+//! }
+//!
+//! let wall = X402Paywall::new(ucl);
+//! Router::new()
+//!     .route("/report", get(report))
+//!     .layer(middleware::from_fn_with_state(wall.clone(), paywall))
+//!     .with_state(wall)
+//! # }
+//! ```
+
+pub use super::challenge::PaymentRequired;
+pub use super::paywall::{PaymentContext, X402Paywall};
+use super::receipt::RECEIPT_HEADER;
+use super::standard::PAYMENT_RESPONSE_HEADER;
+use axum::async_trait;
+use axum::extract::{FromRef, FromRequestParts, State};
+use axum::http::request::Parts;
+use axum::http::{HeaderValue, Request};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+impl IntoResponse for PaymentRequired {
+    fn into_response(self) -> Response {
+        self.into_http_response::<String>().into_response()
+    }
+}
+
+/// Middleware that answers 402 unless the request carries a valid payment
+///
+/// Install with `axum::middleware::from_fn_with_state`. Verified payments
+/// are inserted into request extensions as [`PaymentContext`]; receipts
+/// minted for them are returned in the `X402-Receipt` response header and
+/// settlements of standard x402 payments in `X-PAYMENT-RESPONSE`.
+pub async fn paywall<B>(
+    State(wall): State<X402Paywall>,
+    mut request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    match wall.verify_and_settle(request.headers()).await {
+        Ok(payment) => {
+            let receipt = payment.receipt.clone();
+            let settlement = payment.settlement.clone();
+            request.extensions_mut().insert(payment);
+            let mut response = next.run(request).await;
+            if let Some(receipt) = receipt.and_then(|r| HeaderValue::from_str(&r).ok()) {
+                response.headers_mut().insert(RECEIPT_HEADER, receipt);
+            }
+            if let Some(settlement) = settlement.and_then(|s| HeaderValue::from_str(&s).ok()) {
+                response
+                    .headers_mut()
+                    .insert(PAYMENT_RESPONSE_HEADER, settlement);
+            }
+            response
+        }
+        Err(required) => required.into_response(),
+    }
+}
+
+/// Extracts the verified payment, verifying it if no middleware did
+///
+/// Only the [`paywall`] middleware returns settlement headers.
+#[async_trait]
+impl<S> FromRequestParts<S> for PaymentContext
+where
+    X402Paywall: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = PaymentRequired;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(payment) = parts.extensions.get::<PaymentContext>() {
+            return Ok(payment.clone());
+        }
+        let payment = X402Paywall::from_ref(state)
+            .verify_and_settle(&parts.headers)
+            .await?;
+        parts.extensions.insert(payment.clone());
+        Ok(payment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PartyInfo;
+    use crate::x402::{Ed25519Signer, ReceiptIssuer, X402Client};
+    use crate::{Contract, ContractConfig, UCLContract};
+    use axum::body::Body;
+    use axum::http::StatusCode;
+    use axum::routing::get;
+    use axum::{middleware, Router};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    const TEST_KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    async fn handler(payment: PaymentContext) -> String {
+        payment.role
+    }
+
+    fn setup() -> (X402Client, UCLContract, Router) {
+        let client =
+            X402Client::with_private_key("https://x402.test".to_string(), TEST_KEY).unwrap();
+        let mut ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+        ucl.metadata.parties.push(PartyInfo {
+            role: "payer".to_string(),
+            identifier: client.signer_address().unwrap(),
+            name: None,
+            ..Default::default()
+        });
+        let wall = X402Paywall::new(ucl.clone());
+        let app = Router::new()
+            .route("/paid", get(handler))
+            .layer(middleware::from_fn_with_state(wall.clone(), paywall))
+            .with_state(wall);
+        (client, ucl, app)
+    }
+
+    #[tokio::test]
+    async fn test_unpaid_request_gets_402() {
+        let (_, _, app) = setup();
+        let response = app
+            .oneshot(Request::get("/paid").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYMENT_REQUIRED);
+        assert_eq!(response.headers()["X402-Payment-Token"], "USDC");
+    }
+
+    #[tokio::test]
+    async fn test_paid_request_reaches_handler() {
+        let (client, ucl, app) = setup();
+        let mut request = Request::get("/paid");
+        for (name, value) in client.generate_headers(&ucl, true).unwrap().to_map() {
+            request = request.header(name, value);
+        }
+
+        let response = app
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_receipt_replaces_payment() {
+        let (client, ucl, _) = setup();
+        let issuer = ReceiptIssuer::new(Arc::new(
+            Ed25519Signer::from_secret(&"22".repeat(32)).unwrap(),
+        ));
+        let wall = X402Paywall::new(ucl.clone()).with_receipts(issuer);
+        let app = Router::new()
+            .route("/paid", get(handler))
+            .layer(middleware::from_fn_with_state(wall.clone(), paywall))
+            .with_state(wall);
+
+        let mut request = Request::get("/paid");
+        for (name, value) in client.generate_headers(&ucl, true).unwrap().to_map() {
+            request = request.header(name, value);
+        }
+        let response = app
+            .clone()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let receipt = response.headers()[RECEIPT_HEADER].clone();
+
+        let response = app
+            .oneshot(
+                Request::get("/paid")
+                    .header(RECEIPT_HEADER, receipt)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_standard_payment_returns_settlement() {
+        use crate::x402::standard::{FacilitatorClient, PaymentRequirements, PAYMENT_HEADER};
+
+        let (client, mut ucl, _) = setup();
+        ucl.payment.blockchain = crate::Network::BaseSepolia;
+        let requirements = PaymentRequirements::for_contract(
+            &ucl,
+            "/paid",
+            "0x209693Bc6afc0C5328bA36FaF03C514EF312287C",
+        )
+        .unwrap();
+        let payment = client.create_standard_payment(&requirements).unwrap();
+        let (url, _) = crate::x402::test_server::serve(vec![
+            (200, r#"{"isValid":true}"#),
+            (200, r#"{"success":true,"transaction":"0xabc"}"#),
+        ])
+        .await;
+        let wall = X402Paywall::new(ucl)
+            .with_standard_requirements(requirements, FacilitatorClient::new(url));
+        let app = Router::new()
+            .route("/paid", get(handler))
+            .layer(middleware::from_fn_with_state(wall.clone(), paywall))
+            .with_state(wall);
+
+        let response = app
+            .oneshot(
+                Request::get("/paid")
+                    .header(PAYMENT_HEADER, payment.encode().unwrap())
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().contains_key(PAYMENT_RESPONSE_HEADER));
+    }
+}
@@ -0,0 +1,150 @@
+//! Settlement polling and payment receipts
+
+use super::client::X402Client;
+use crate::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Settlement state of a submitted payment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum SettlementStatus {
+    /// Accepted by the facilitator, not yet broadcast
+    Pending,
+    /// Broadcast, waiting for confirmations
+    Submitted,
+    /// Confirmed on-chain
+    Settled,
+    /// Rejected or reverted
+    Failed,
+}
+
+impl SettlementStatus {
+    /// Whether polling can stop
+    pub fn is_final(self) -> bool {
+        matches!(self, SettlementStatus::Settled | SettlementStatus::Failed)
+    }
+}
+
+/// Facilitator view of a payment's settlement
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettlementReceipt {
+    /// Facilitator reference or transaction hash the receipt was fetched by
+    pub reference: String,
+    pub status: SettlementStatus,
+    #[serde(default)]
+    pub transaction_hash: Option<String>,
+    #[serde(default)]
+    pub network: Option<String>,
+    #[serde(default)]
+    pub block_number: Option<u64>,
+    #[serde(default)]
+    pub confirmations: u64,
+    #[serde(default)]
+    pub settled_at: Option<DateTime<Utc>>,
+    /// Failure reason reported by the facilitator
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl X402Client {
+    /// Fetch the current settlement state of a payment
+    pub async fn settlement_status(&self, reference: &str) -> Result<SettlementReceipt> {
+        let response = self
+            .http
+            .get(self.url(&format!("payments/{}", reference)))
+            .send()
+            .await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(crate::Error::NotFoundError(format!(
+                "Payment {} is unknown to the facilitator",
+                reference
+            )));
+        }
+        Ok(response.error_for_status()?.json().await?)
+    }
+
+    /// Poll until a payment is settled on-chain
+    ///
+    /// `reference` is the facilitator reference or transaction hash from
+    /// [`super::client::PaymentResponse::settlement_reference`]. Fails with a
+    /// `PaymentError` if the payment fails or `timeout` elapses first.
+    #[tracing::instrument(skip(self), fields(status))]
+    pub async fn await_settlement(
+        &self,
+        reference: &str,
+        timeout: Duration,
+    ) -> Result<SettlementReceipt> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let receipt = self.settlement_status(reference).await?;
+            tracing::Span::current().record("status", tracing::field::debug(&receipt.status));
+            match receipt.status {
+                SettlementStatus::Settled => {
+                    tracing::info!(block_number = receipt.block_number, "payment settled");
+                    return Ok(receipt);
+                }
+                SettlementStatus::Failed => {
+                    return Err(crate::Error::PaymentError(format!(
+                        "Payment {} failed to settle: {}",
+                        reference,
+                        receipt.error.as_deref().unwrap_or("no reason given")
+                    )))
+                }
+                SettlementStatus::Pending | SettlementStatus::Submitted => {}
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(crate::Error::PaymentError(format!(
+                    "Timed out after {:?} waiting for payment {} to settle (last status: {:?})",
+                    timeout, reference, receipt.status
+                )));
+            }
+            tokio::time::sleep(self.poll_interval.min(deadline - now)).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::x402::test_server::serve;
+
+    #[tokio::test]
+    async fn test_await_settlement_polls_until_settled() {
+        let (endpoint, _) = serve(vec![
+            (200, r#"{"reference":"pay_1","status":"pending"}"#),
+            (200, r#"{"reference":"pay_1","status":"submitted","transaction_hash":"0xabc"}"#),
+            (200, r#"{"reference":"pay_1","status":"settled","transaction_hash":"0xabc","block_number":42,"confirmations":3}"#),
+        ])
+        .await;
+        let client = X402Client::new(endpoint).with_poll_interval(Duration::from_millis(10));
+
+        let receipt = client
+            .await_settlement("pay_1", Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(receipt.status, SettlementStatus::Settled);
+        assert_eq!(receipt.block_number, Some(42));
+    }
+
+    #[tokio::test]
+    async fn test_await_settlement_reports_failure() {
+        let (endpoint, _) = serve(vec![(
+            200,
+            r#"{"reference":"pay_2","status":"failed","error":"reverted"}"#,
+        )])
+        .await;
+        let client = X402Client::new(endpoint);
+
+        let err = client
+            .await_settlement("pay_2", Duration::from_secs(5))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("reverted"));
+    }
+}
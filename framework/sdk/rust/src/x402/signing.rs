@@ -0,0 +1,348 @@
+//! Cryptographic signing of X402 headers
+
+use crate::Result;
+use ed25519_dalek::{Signer as _, Verifier as _};
+use ethers::signers::{LocalWallet, Signer as _};
+use ethers::types::Signature;
+use ethers::utils::hash_message;
+use std::str::FromStr;
+
+/// Signature scheme used for X402 headers, advertised in `X402-Signature-Scheme`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum SignatureScheme {
+    /// secp256k1 EIP-191 personal-message signatures (EVM wallets)
+    Secp256k1,
+    /// Ed25519 signatures (Solana wallets)
+    Ed25519,
+}
+
+impl std::fmt::Display for SignatureScheme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignatureScheme::Secp256k1 => write!(f, "secp256k1"),
+            SignatureScheme::Ed25519 => write!(f, "ed25519"),
+        }
+    }
+}
+
+impl FromStr for SignatureScheme {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "secp256k1" | "eip191" => Ok(SignatureScheme::Secp256k1),
+            "ed25519" => Ok(SignatureScheme::Ed25519),
+            _ => Err(SignatureError::UnsupportedScheme {
+                scheme: s.to_string(),
+            }
+            .into()),
+        }
+    }
+}
+
+/// Why a signature could not be made or verified
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
+pub enum SignatureError {
+    #[error("Unsupported signature scheme: {scheme}")]
+    UnsupportedScheme { scheme: String },
+
+    #[error("{scheme} signers do not support {operation}")]
+    Unsupported {
+        scheme: SignatureScheme,
+        operation: &'static str,
+    },
+
+    #[error("Invalid signing key: {reason}")]
+    InvalidKey { reason: String },
+
+    #[error("Signing failed: {reason}")]
+    SigningFailed { reason: String },
+
+    #[error("Malformed signature: {reason}")]
+    Malformed { reason: String },
+
+    #[error("Invalid signature: {reason}")]
+    Invalid { reason: String },
+
+    #[error("Signature was made by {actual}, not {expected}")]
+    SignerMismatch { expected: String, actual: String },
+
+    #[error("Webhook signature rejected: {reason}")]
+    Webhook { reason: String },
+}
+
+impl SignatureError {
+    /// Stable machine-readable code, e.g. `"signature.signer_mismatch"`
+    pub fn code(&self) -> &'static str {
+        match self {
+            SignatureError::UnsupportedScheme { .. } => "signature.unsupported_scheme",
+            SignatureError::Unsupported { .. } => "signature.unsupported",
+            SignatureError::InvalidKey { .. } => "signature.invalid_key",
+            SignatureError::SigningFailed { .. } => "signature.signing_failed",
+            SignatureError::Malformed { .. } => "signature.malformed",
+            SignatureError::Invalid { .. } => "signature.invalid",
+            SignatureError::SignerMismatch { .. } => "signature.signer_mismatch",
+            SignatureError::Webhook { .. } => "signature.webhook",
+        }
+    }
+}
+
+/// A key that can sign X402 header payloads
+pub trait X402Signer: Send + Sync {
+    /// Scheme of the produced signatures
+    fn scheme(&self) -> SignatureScheme;
+
+    /// Public identity of the key, sent as `X402-Signer`
+    ///
+    /// A checksummed address for secp256k1, a base58 public key for Ed25519.
+    fn identity(&self) -> String;
+
+    /// Sign a header payload, returning the encoded signature
+    fn sign(&self, payload: &str) -> Result<String>;
+
+    /// Sign a raw 32-byte digest (e.g. an EIP-712 hash)
+    ///
+    /// Only EVM signers support this; it backs the standard x402 `exact` scheme.
+    fn sign_digest(&self, _digest: [u8; 32]) -> Result<String> {
+        Err(SignatureError::Unsupported {
+            scheme: self.scheme(),
+            operation: "signing EVM typed data",
+        }
+        .into())
+    }
+}
+
+/// secp256k1 signer producing EIP-191 personal-message signatures
+#[derive(Debug, Clone)]
+pub struct Secp256k1Signer {
+    wallet: LocalWallet,
+}
+
+impl Secp256k1Signer {
+    /// Create signer from a hex-encoded private key (with or without `0x`)
+    pub fn from_private_key(private_key: &str) -> Result<Self> {
+        let wallet = LocalWallet::from_str(private_key.trim_start_matches("0x"))
+            .map_err(|e| SignatureError::InvalidKey {
+                reason: e.to_string(),
+            })?;
+        Ok(Self { wallet })
+    }
+
+    /// Checksummed address of the signing key
+    pub fn address(&self) -> String {
+        ethers::utils::to_checksum(&self.wallet.address(), None)
+    }
+}
+
+impl X402Signer for Secp256k1Signer {
+    fn scheme(&self) -> SignatureScheme {
+        SignatureScheme::Secp256k1
+    }
+
+    fn identity(&self) -> String {
+        self.address()
+    }
+
+    /// Sign `payload` as an EIP-191 personal message, returning `0x`-prefixed hex
+    fn sign(&self, payload: &str) -> Result<String> {
+        let signature = self
+            .wallet
+            .sign_hash(hash_message(payload))
+            .map_err(|e| SignatureError::SigningFailed {
+                reason: e.to_string(),
+            })?;
+        Ok(format!("0x{}", signature))
+    }
+
+    fn sign_digest(&self, digest: [u8; 32]) -> Result<String> {
+        let signature = self
+            .wallet
+            .sign_hash(digest.into())
+            .map_err(|e| SignatureError::SigningFailed {
+                reason: e.to_string(),
+            })?;
+        Ok(format!("0x{}", signature))
+    }
+}
+
+/// Ed25519 signer compatible with Solana keypairs
+#[derive(Debug, Clone)]
+pub struct Ed25519Signer {
+    key: ed25519_dalek::SigningKey,
+}
+
+impl Ed25519Signer {
+    /// Create signer from a secret key
+    ///
+    /// Accepts a 32-byte seed or a 64-byte Solana keypair, encoded as hex
+    /// (with or without `0x`) or base58.
+    pub fn from_secret(secret: &str) -> Result<Self> {
+        let bytes = decode_key_bytes(secret)?;
+        let seed: [u8; 32] = match bytes.len() {
+            32 | 64 => bytes[..32].try_into().expect("length checked"),
+            n => {
+                return Err(SignatureError::InvalidKey {
+                    reason: format!("Ed25519 secret must be 32 or 64 bytes, got {}", n),
+                }
+                .into())
+            }
+        };
+
+        let key = ed25519_dalek::SigningKey::from_bytes(&seed);
+        if bytes.len() == 64 && key.verifying_key().as_bytes()[..] != bytes[32..] {
+            return Err(SignatureError::InvalidKey {
+                reason: "Ed25519 keypair public half does not match secret".to_string(),
+            }
+            .into());
+        }
+        Ok(Self { key })
+    }
+
+    /// Base58-encoded public key
+    pub fn public_key(&self) -> String {
+        bs58::encode(self.key.verifying_key().as_bytes()).into_string()
+    }
+}
+
+impl X402Signer for Ed25519Signer {
+    fn scheme(&self) -> SignatureScheme {
+        SignatureScheme::Ed25519
+    }
+
+    fn identity(&self) -> String {
+        self.public_key()
+    }
+
+    /// Sign `payload` bytes, returning a base58-encoded signature
+    fn sign(&self, payload: &str) -> Result<String> {
+        let signature = self.key.sign(payload.as_bytes());
+        Ok(bs58::encode(signature.to_bytes()).into_string())
+    }
+}
+
+/// Recover the checksummed signer address of an EIP-191 signature over `payload`
+pub fn recover_signer(payload: &str, signature: &str) -> Result<String> {
+    let signature = Signature::from_str(signature.trim_start_matches("0x"))
+        .map_err(|e| SignatureError::Malformed {
+            reason: e.to_string(),
+        })?;
+    let address = signature
+        .recover(payload)
+        .map_err(|e| SignatureError::Invalid {
+            reason: e.to_string(),
+        })?;
+    Ok(ethers::utils::to_checksum(&address, None))
+}
+
+/// Verify a signature over `payload` and return the verified signer identity
+///
+/// For secp256k1 the signer is recovered from the signature and, when
+/// `claimed_signer` is non-empty, must match it. For Ed25519 the claimed
+/// signer is the public key the signature is checked against.
+pub fn verify_signature(
+    scheme: SignatureScheme,
+    payload: &str,
+    signature: &str,
+    claimed_signer: &str,
+) -> Result<String> {
+    match scheme {
+        SignatureScheme::Secp256k1 => {
+            let recovered = recover_signer(payload, signature)?;
+            if !claimed_signer.is_empty() && !recovered.eq_ignore_ascii_case(claimed_signer) {
+                return Err(SignatureError::SignerMismatch {
+                    expected: claimed_signer.to_string(),
+                    actual: recovered,
+                }
+                .into());
+            }
+            Ok(recovered)
+        }
+        SignatureScheme::Ed25519 => {
+            let public_key: [u8; 32] = bs58::decode(claimed_signer)
+                .into_vec()
+                .ok()
+                .and_then(|b| b.try_into().ok())
+                .ok_or_else(|| SignatureError::InvalidKey {
+                    reason: format!("Malformed Ed25519 public key: {}", claimed_signer),
+                })?;
+            let key = ed25519_dalek::VerifyingKey::from_bytes(&public_key).map_err(|e| {
+                SignatureError::InvalidKey {
+                    reason: e.to_string(),
+                }
+            })?;
+            let signature: [u8; 64] = bs58::decode(signature)
+                .into_vec()
+                .ok()
+                .and_then(|b| b.try_into().ok())
+                .ok_or_else(|| SignatureError::Malformed {
+                    reason: "not a base58 Ed25519 signature".to_string(),
+                })?;
+            key.verify(
+                payload.as_bytes(),
+                &ed25519_dalek::Signature::from_bytes(&signature),
+            )
+            .map_err(|e| SignatureError::Invalid {
+                reason: e.to_string(),
+            })?;
+            Ok(claimed_signer.to_string())
+        }
+    }
+}
+
+fn decode_key_bytes(secret: &str) -> Result<Vec<u8>> {
+    let trimmed = secret.trim();
+    let hex_part = trimmed.strip_prefix("0x").unwrap_or(trimmed);
+    if hex_part.len().is_multiple_of(2) && hex_part.chars().all(|c| c.is_ascii_hexdigit()) {
+        if let Ok(bytes) = hex::decode(hex_part) {
+            return Ok(bytes);
+        }
+    }
+    bs58::decode(trimmed)
+        .into_vec()
+        .map_err(|e| {
+            SignatureError::InvalidKey {
+                reason: format!("Invalid key encoding: {}", e),
+            }
+            .into()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ed25519_round_trip() {
+        let signer = Ed25519Signer::from_secret(&"01".repeat(32)).unwrap();
+        let signature = signer.sign("payload").unwrap();
+
+        let verified = verify_signature(
+            SignatureScheme::Ed25519,
+            "payload",
+            &signature,
+            &signer.identity(),
+        )
+        .unwrap();
+        assert_eq!(verified, signer.public_key());
+        assert!(verify_signature(
+            SignatureScheme::Ed25519,
+            "tampered",
+            &signature,
+            &signer.identity()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_scheme() {
+        assert_eq!(
+            "ed25519".parse::<SignatureScheme>().unwrap(),
+            SignatureScheme::Ed25519
+        );
+        let error = "rsa".parse::<SignatureScheme>().unwrap_err();
+        assert_eq!(error.code(), "signature.unsupported_scheme");
+    }
+}
@@ -0,0 +1,613 @@
+//! Interop with the standard x402 wire format
+//!
+//! Implements the `exact` EVM scheme used by the Coinbase facilitator: the
+//! 402 body lists accepted [`PaymentRequirements`], the client answers with a
+//! base64 JSON [`PaymentPayload`] in the `X-PAYMENT` header carrying an
+//! EIP-3009 `transferWithAuthorization` signature, and the facilitator's
+//! `/verify` and `/settle` endpoints check and execute the transfer.
+
+use super::client::X402Client;
+use super::nonce::unix_now;
+use super::signing::SignatureError;
+use super::verify::VerificationError;
+use crate::{Amount, Network, Result, Token, UCLContract};
+use base64::Engine as _;
+use ethers::types::transaction::eip712::{Eip712, TypedData};
+use ethers::types::{Signature, H256};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Protocol version spoken by this module
+pub const X402_VERSION: u32 = 1;
+
+/// Request header carrying the encoded payment payload
+pub const PAYMENT_HEADER: &str = "X-PAYMENT";
+
+/// Response header carrying the encoded settlement result
+pub const PAYMENT_RESPONSE_HEADER: &str = "X-PAYMENT-RESPONSE";
+
+/// Default validity window of a signed authorization
+const DEFAULT_TIMEOUT_SECS: u64 = 60;
+
+/// One acceptable way to pay for a resource
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentRequirements {
+    pub scheme: String,
+    pub network: String,
+    /// Amount in the asset's smallest unit
+    pub max_amount_required: String,
+    pub resource: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub mime_type: String,
+    pub pay_to: String,
+    pub max_timeout_seconds: u64,
+    /// Token contract address
+    pub asset: String,
+    /// EIP-712 domain `name` and `version` of the token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extra: Option<serde_json::Value>,
+}
+
+impl PaymentRequirements {
+    /// Requirements for paying a contract's amount to `pay_to`
+    ///
+    /// The asset is the token's address on the contract's network. Its
+    /// EIP-712 domain is known for USDC; use
+    /// [`PaymentRequirements::with_asset`] for other tokens.
+    pub fn for_contract(ucl: &UCLContract, resource: &str, pay_to: &str) -> Result<Self> {
+        let network = ucl.payment.blockchain;
+        let token = &ucl.payment.token;
+        let asset = token.address(network).unwrap_or_default().to_string();
+        let extra = (token.is("USDC") && !asset.is_empty()).then(|| {
+            serde_json::json!({ "name": usdc_domain_name(network), "version": "2" })
+        });
+
+        Ok(Self {
+            scheme: "exact".to_string(),
+            network: network.to_string(),
+            max_amount_required: crate::llmo::engine::base_units(ucl.payment.amount, token.decimals())?,
+            resource: resource.to_string(),
+            description: ucl.summary.title.clone(),
+            mime_type: "application/json".to_string(),
+            pay_to: pay_to.to_string(),
+            max_timeout_seconds: DEFAULT_TIMEOUT_SECS,
+            asset,
+            extra,
+        })
+    }
+
+    /// Set the token contract and its EIP-712 domain name and version
+    pub fn with_asset(mut self, asset: &str, name: &str, version: &str) -> Self {
+        self.asset = asset.to_string();
+        self.extra = Some(serde_json::json!({ "name": name, "version": version }));
+        self
+    }
+
+    /// Amount in token units of `base_units` of the asset
+    ///
+    /// Fails unless the asset is a well-known token on the network, as its
+    /// decimals are not known otherwise.
+    pub fn asset_amount(&self, base_units: &str) -> Result<Amount> {
+        let network: Network = self.network.parse()?;
+        let token = Token::well_known_at(network, &self.asset).ok_or_else(|| {
+            crate::Error::ConfigError(format!(
+                "Unknown x402 asset {} on {}",
+                self.asset, self.network
+            ))
+        })?;
+        base_units
+            .parse()
+            .ok()
+            .and_then(|units| Amount::from_base_units(units, token.decimals()))
+            .ok_or_else(|| {
+                crate::Error::PaymentError(format!("Invalid amount of {}: {}", token, base_units))
+            })
+    }
+
+    fn domain_field(&self, field: &str) -> Result<String> {
+        self.extra
+            .as_ref()
+            .and_then(|e| e.get(field))
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+            .ok_or_else(|| {
+                crate::Error::ConfigError(format!(
+                    "Payment requirements for {} lack the token's EIP-712 {}",
+                    self.network, field
+                ))
+            })
+    }
+}
+
+/// JSON body of a standard 402 response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentRequiredBody {
+    pub x402_version: u32,
+    pub accepts: Vec<PaymentRequirements>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// EIP-3009 transfer authorization
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Authorization {
+    pub from: String,
+    pub to: String,
+    pub value: String,
+    pub valid_after: String,
+    pub valid_before: String,
+    /// 32-byte hex nonce
+    pub nonce: String,
+}
+
+/// Signed authorization of the `exact` EVM scheme
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExactEvmPayload {
+    pub signature: String,
+    pub authorization: Authorization,
+}
+
+/// Payment sent in the `X-PAYMENT` header
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentPayload {
+    pub x402_version: u32,
+    pub scheme: String,
+    pub network: String,
+    pub payload: ExactEvmPayload,
+}
+
+impl PaymentPayload {
+    /// Encode as an `X-PAYMENT` header value (base64 JSON)
+    pub fn encode(&self) -> Result<String> {
+        Ok(base64::engine::general_purpose::STANDARD.encode(serde_json::to_vec(self)?))
+    }
+
+    /// Decode an `X-PAYMENT` header value
+    pub fn decode(header: &str) -> std::result::Result<Self, VerificationError> {
+        let malformed = |reason: String| VerificationError::MalformedHeader {
+            header: PAYMENT_HEADER.to_string(),
+            reason,
+        };
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(header.trim())
+            .map_err(|e| malformed(e.to_string()))?;
+        serde_json::from_slice(&bytes).map_err(|e| malformed(e.to_string()))
+    }
+
+    /// Check the payment against `requirements` without contacting a facilitator
+    ///
+    /// Verifies scheme, network, recipient, amount, validity window and the
+    /// EIP-712 signature, returning the payer address. Whether the payer holds
+    /// the funds is only known to the facilitator's `/verify`.
+    pub fn verify(
+        &self,
+        requirements: &PaymentRequirements,
+    ) -> std::result::Result<String, VerificationError> {
+        let auth = &self.payload.authorization;
+        let mismatch =
+            |field: &str, expected: &str, actual: &str| VerificationError::TermsMismatch {
+                field: field.to_string(),
+                expected: expected.to_string(),
+                actual: actual.to_string(),
+            };
+
+        if self.scheme != requirements.scheme {
+            return Err(mismatch("scheme", &requirements.scheme, &self.scheme));
+        }
+        if !self.network.eq_ignore_ascii_case(&requirements.network) {
+            return Err(mismatch("network", &requirements.network, &self.network));
+        }
+        if !auth.to.eq_ignore_ascii_case(&requirements.pay_to) {
+            return Err(mismatch("pay_to", &requirements.pay_to, &auth.to));
+        }
+        let value: u128 = auth.value.parse().unwrap_or(0);
+        let required: u128 = requirements
+            .max_amount_required
+            .parse()
+            .unwrap_or(u128::MAX);
+        if value < required {
+            return Err(mismatch(
+                "amount",
+                &requirements.max_amount_required,
+                &auth.value,
+            ));
+        }
+
+        let now = unix_now();
+        let valid_after: u64 = auth.valid_after.parse().unwrap_or(u64::MAX);
+        let valid_before: u64 = auth.valid_before.parse().unwrap_or(0);
+        if now < valid_after {
+            return Err(VerificationError::FutureNonce {
+                ahead_secs: valid_after - now,
+            });
+        }
+        if now >= valid_before {
+            return Err(VerificationError::StaleNonce {
+                age_secs: now - valid_before,
+                max_age_secs: requirements.max_timeout_seconds,
+            });
+        }
+
+        let digest = authorization_digest(requirements, auth)
+            .map_err(|e| VerificationError::InvalidSignature(e.to_string()))?;
+        let signature = Signature::from_str(self.payload.signature.trim_start_matches("0x"))
+            .map_err(|e| VerificationError::InvalidSignature(e.to_string()))?;
+        let payer = signature
+            .recover(H256::from(digest))
+            .map_err(|e| VerificationError::InvalidSignature(e.to_string()))?;
+        let payer = ethers::utils::to_checksum(&payer, None);
+        if !payer.eq_ignore_ascii_case(&auth.from) {
+            return Err(VerificationError::InvalidSignature(format!(
+                "authorization from {} was signed by {}",
+                auth.from, payer
+            )));
+        }
+        Ok(payer)
+    }
+}
+
+/// Facilitator answer to `/verify`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyResponse {
+    pub is_valid: bool,
+    #[serde(default)]
+    pub invalid_reason: Option<String>,
+    #[serde(default)]
+    pub payer: Option<String>,
+}
+
+/// Facilitator answer to `/settle`, echoed in `X-PAYMENT-RESPONSE`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettleResponse {
+    pub success: bool,
+    #[serde(default)]
+    pub error_reason: Option<String>,
+    #[serde(default)]
+    pub transaction: Option<String>,
+    #[serde(default)]
+    pub network: Option<String>,
+    #[serde(default)]
+    pub payer: Option<String>,
+}
+
+impl SettleResponse {
+    /// Encode as an `X-PAYMENT-RESPONSE` header value
+    pub fn encode(&self) -> Result<String> {
+        Ok(base64::engine::general_purpose::STANDARD.encode(serde_json::to_vec(self)?))
+    }
+}
+
+/// Client for a standard x402 facilitator
+#[derive(Debug, Clone)]
+pub struct FacilitatorClient {
+    url: String,
+    http: reqwest::Client,
+}
+
+impl FacilitatorClient {
+    /// Create client for the facilitator at `url` (e.g. "https://x402.org/facilitator")
+    pub fn new(url: String) -> Self {
+        Self {
+            url,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Ask the facilitator whether a payment satisfies the requirements
+    pub async fn verify(
+        &self,
+        payment: &PaymentPayload,
+        requirements: &PaymentRequirements,
+    ) -> Result<VerifyResponse> {
+        self.post("verify", payment, requirements).await
+    }
+
+    /// Ask the facilitator to execute the transfer on-chain
+    pub async fn settle(
+        &self,
+        payment: &PaymentPayload,
+        requirements: &PaymentRequirements,
+    ) -> Result<SettleResponse> {
+        self.post("settle", payment, requirements).await
+    }
+
+    async fn post<T: serde::de::DeserializeOwned>(
+        &self,
+        path: &str,
+        payment: &PaymentPayload,
+        requirements: &PaymentRequirements,
+    ) -> Result<T> {
+        let body = serde_json::json!({
+            "x402Version": X402_VERSION,
+            "paymentPayload": payment,
+            "paymentHeader": payment.encode()?,
+            "paymentRequirements": requirements,
+        });
+        let response = self
+            .http
+            .post(format!("{}/{}", self.url.trim_end_matches('/'), path))
+            .json(&body)
+            .send()
+            .await?;
+        Ok(response.error_for_status()?.json().await?)
+    }
+}
+
+impl X402Client {
+    /// Sign a standard `exact` payment for the given requirements
+    ///
+    /// Requires a secp256k1 signer; the authorization is valid from now
+    /// until `max_timeout_seconds` have elapsed. Fails with a
+    /// `PolicyViolation` if the client's spending policy forbids it.
+    pub fn create_standard_payment(
+        &self,
+        requirements: &PaymentRequirements,
+    ) -> Result<PaymentPayload> {
+        let signer = self
+            .signer
+            .as_ref()
+            .ok_or_else(|| crate::Error::ConfigError("X402Client has no signer".to_string()))?;
+
+        let amount = requirements.asset_amount(&requirements.max_amount_required)?;
+        self.authorize_spend(&requirements.resource, amount)?;
+
+        let now = unix_now();
+        let authorization = Authorization {
+            from: signer.identity(),
+            to: requirements.pay_to.clone(),
+            value: requirements.max_amount_required.clone(),
+            valid_after: now.saturating_sub(600).to_string(),
+            valid_before: (now + requirements.max_timeout_seconds).to_string(),
+            nonce: format!("0x{}", hex::encode(rand::random::<[u8; 32]>())),
+        };
+        let digest = authorization_digest(requirements, &authorization)?;
+
+        Ok(PaymentPayload {
+            x402_version: X402_VERSION,
+            scheme: requirements.scheme.clone(),
+            network: requirements.network.clone(),
+            payload: ExactEvmPayload {
+                signature: signer.sign_digest(digest)?,
+                authorization,
+            },
+        })
+    }
+
+    /// Pick the first supported requirement from a 402 body and sign a payment
+    ///
+    /// Returns the `X-PAYMENT` header value.
+    pub fn standard_payment_header(&self, body: &PaymentRequiredBody) -> Result<String> {
+        let requirements = body
+            .accepts
+            .iter()
+            .find(|r| r.scheme == "exact" && r.network.parse::<Network>().is_ok())
+            .ok_or_else(|| {
+                crate::Error::PaymentError("No supported x402 payment requirement".to_string())
+            })?;
+        self.create_standard_payment(requirements)?.encode()
+    }
+}
+
+/// EIP-712 digest of a `TransferWithAuthorization` message
+fn authorization_digest(
+    requirements: &PaymentRequirements,
+    auth: &Authorization,
+) -> Result<[u8; 32]> {
+    let chain_id = requirements
+        .network
+        .parse::<Network>()
+        .map_err(|_| {
+            crate::Error::ConfigError(format!(
+                "Unsupported x402 network: {}",
+                requirements.network
+            ))
+        })?
+        .chain_id();
+
+    let typed: TypedData = serde_json::from_value(serde_json::json!({
+        "types": {
+            "EIP712Domain": [
+                { "name": "name", "type": "string" },
+                { "name": "version", "type": "string" },
+                { "name": "chainId", "type": "uint256" },
+                { "name": "verifyingContract", "type": "address" },
+            ],
+            "TransferWithAuthorization": [
+                { "name": "from", "type": "address" },
+                { "name": "to", "type": "address" },
+                { "name": "value", "type": "uint256" },
+                { "name": "validAfter", "type": "uint256" },
+                { "name": "validBefore", "type": "uint256" },
+                { "name": "nonce", "type": "bytes32" },
+            ],
+        },
+        "primaryType": "TransferWithAuthorization",
+        "domain": {
+            "name": requirements.domain_field("name")?,
+            "version": requirements.domain_field("version")?,
+            "chainId": chain_id,
+            "verifyingContract": requirements.asset,
+        },
+        "message": auth,
+    }))?;
+
+    typed.encode_eip712().map_err(|e| {
+        SignatureError::SigningFailed {
+            reason: e.to_string(),
+        }
+        .into()
+    })
+}
+
+/// Testnet USDC deployments use the domain name "USDC"
+fn usdc_domain_name(network: Network) -> &'static str {
+    if network.is_testnet() {
+        "USDC"
+    } else {
+        "USD Coin"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::x402::test_server::serve;
+    use crate::{Amount, Contract, ContractConfig};
+
+    const TEST_KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+    const PAY_TO: &str = "0x209693Bc6afc0C5328bA36FaF03C514EF312287C";
+
+    fn requirements() -> PaymentRequirements {
+        let mut ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+        ucl.payment.amount = Amount::new(1, 2);
+        ucl.payment.blockchain = Network::BaseSepolia;
+        PaymentRequirements::for_contract(&ucl, "https://api.test/report", PAY_TO).unwrap()
+    }
+
+    #[test]
+    fn test_requirements_use_atomic_units() {
+        let requirements = requirements();
+        assert_eq!(requirements.max_amount_required, "10000");
+        assert_eq!(
+            requirements.asset,
+            "0x036CbD53842c5426634e7929541eC2318f3dCF7e"
+        );
+    }
+
+    #[test]
+    fn test_signed_payment_round_trips_and_verifies() {
+        let client =
+            X402Client::with_private_key("https://x402.test".to_string(), TEST_KEY).unwrap();
+        let requirements = requirements();
+
+        let header = client
+            .standard_payment_header(&PaymentRequiredBody {
+                x402_version: X402_VERSION,
+                accepts: vec![requirements.clone()],
+                error: None,
+            })
+            .unwrap();
+        let payment = PaymentPayload::decode(&header).unwrap();
+        assert_eq!(
+            payment.verify(&requirements).unwrap(),
+            client.signer_address().unwrap()
+        );
+
+        let mut underpaid = requirements.clone();
+        underpaid.max_amount_required = "20000".to_string();
+        assert!(matches!(
+            payment.verify(&underpaid),
+            Err(VerificationError::TermsMismatch { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_paywall_settles_through_facilitator() {
+        let client =
+            X402Client::with_private_key("https://x402.test".to_string(), TEST_KEY).unwrap();
+        let requirements = requirements();
+        let mut headers = http::HeaderMap::new();
+        let payment = client.create_standard_payment(&requirements).unwrap();
+        headers.insert(PAYMENT_HEADER, payment.encode().unwrap().parse().unwrap());
+
+        let (url, requests) = serve(vec![
+            (200, r#"{"isValid":true}"#),
+            (
+                200,
+                r#"{"success":true,"transaction":"0xabc","network":"base-sepolia"}"#,
+            ),
+            (200, r#"{"isValid":true}"#),
+        ])
+        .await;
+        let ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+        let wall = crate::x402::X402Paywall::new(ucl)
+            .with_standard_requirements(requirements, FacilitatorClient::new(url));
+        assert!(wall
+            .verify_and_settle(&http::HeaderMap::new())
+            .await
+            .is_err());
+        assert!(matches!(
+            wall.verify(&headers).unwrap_err().reason(),
+            Some(VerificationError::Unsettled(_))
+        ));
+
+        let paid = wall.verify_and_settle(&headers).await.unwrap();
+        assert_eq!(paid.role, "payer");
+        assert_eq!(paid.amount, "0.01");
+        let settlement: SettleResponse = serde_json::from_slice(
+            &base64::engine::general_purpose::STANDARD
+                .decode(paid.settlement.unwrap())
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(settlement.transaction.as_deref(), Some("0xabc"));
+        assert!(matches!(
+            wall.verify_and_settle(&headers).await.unwrap_err().reason(),
+            Some(VerificationError::ReplayedNonce(_))
+        ));
+        let requests = requests.lock().unwrap();
+        assert!(requests[0].starts_with("POST /verify"));
+        assert!(requests[1].starts_with("POST /settle"));
+    }
+
+    #[tokio::test]
+    async fn test_paywall_refuses_payments_the_facilitator_rejects() {
+        let client =
+            X402Client::with_private_key("https://x402.test".to_string(), TEST_KEY).unwrap();
+        let requirements = requirements();
+        let mut headers = http::HeaderMap::new();
+        let payment = client.create_standard_payment(&requirements).unwrap();
+        headers.insert(PAYMENT_HEADER, payment.encode().unwrap().parse().unwrap());
+
+        let (url, requests) = serve(vec![(
+            200,
+            r#"{"isValid":false,"invalidReason":"insufficient_funds"}"#,
+        )])
+        .await;
+        let ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+        let wall = crate::x402::X402Paywall::new(ucl)
+            .with_standard_requirements(requirements, FacilitatorClient::new(url));
+
+        assert_eq!(
+            wall.verify_and_settle(&headers).await.unwrap_err().reason(),
+            Some(&VerificationError::FacilitatorRejected(
+                "insufficient_funds".to_string()
+            ))
+        );
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_asset_amount_uses_token_decimals() {
+        let mut requirements = requirements();
+        assert_eq!(
+            requirements.asset_amount("1500000").unwrap(),
+            Amount::new(15, 1)
+        );
+        assert!(requirements.asset_amount("-1").is_err());
+
+        requirements.network = "base".to_string();
+        requirements.asset = "0x50c5725949A6F0c72E6C4a641F24049A917DB0Cb".to_string();
+        assert_eq!(
+            requirements.asset_amount("2000000000000000000").unwrap(),
+            Amount::from(2)
+        );
+
+        requirements.asset = "0x0000000000000000000000000000000000000001".to_string();
+        assert!(requirements.asset_amount("1").is_err());
+    }
+}
@@ -0,0 +1,360 @@
+//! Streaming micropayments over payment tabs
+//!
+//! Instead of settling every request on-chain, the client opens a tab and
+//! signs a commitment per request. Each commitment carries the cumulative
+//! amount owed so far, so only the latest one needs to be settled: the
+//! server keeps the running balance and submits it in one transfer when the
+//! tab is closed.
+
+use super::client::{PaymentResponse, X402Client};
+use super::policy::SpendingPolicy;
+use super::signing::{verify_signature, SignatureScheme, X402Signer};
+use super::verify::{party_role, VerificationError};
+use crate::{Amount, Result, UCLContract};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Names of the headers carrying a tab commitment
+pub const TAB_HEADER_NAMES: [&str; 7] = [
+    "X402-Tab-ID",
+    "X402-Contract-ID",
+    "X402-Tab-Sequence",
+    "X402-Tab-Amount",
+    "X402-Signature",
+    "X402-Signature-Scheme",
+    "X402-Signer",
+];
+
+/// Signed promise to pay the cumulative amount of a tab
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TabCommitment {
+    pub tab_id: String,
+    pub contract_id: String,
+    /// 1-based request counter within the tab
+    pub sequence: u64,
+    /// Total owed so far, in the token's base units
+    pub cumulative_amount: u128,
+    pub signature_scheme: SignatureScheme,
+    pub signer: String,
+    pub signature: String,
+}
+
+impl TabCommitment {
+    /// Canonical payload covered by the signature
+    pub fn signing_payload(&self) -> String {
+        format!(
+            "X402-Tab-ID:{}\nX402-Contract-ID:{}\nX402-Tab-Sequence:{}\nX402-Tab-Amount:{}\n\
+             X402-Signature-Scheme:{}\nX402-Signer:{}",
+            self.tab_id,
+            self.contract_id,
+            self.sequence,
+            self.cumulative_amount,
+            self.signature_scheme,
+            self.signer
+        )
+    }
+
+    /// Convert to header map
+    pub fn to_map(&self) -> HashMap<String, String> {
+        TAB_HEADER_NAMES
+            .iter()
+            .zip([
+                self.tab_id.clone(),
+                self.contract_id.clone(),
+                self.sequence.to_string(),
+                self.cumulative_amount.to_string(),
+                self.signature.clone(),
+                self.signature_scheme.to_string(),
+                self.signer.clone(),
+            ])
+            .map(|(name, value)| (name.to_string(), value))
+            .collect()
+    }
+
+    /// Parse from headers, the inverse of [`TabCommitment::to_map`]
+    pub fn from_map(map: &HashMap<String, String>) -> std::result::Result<Self, VerificationError> {
+        let get = |name: &str| {
+            map.iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.trim().to_string())
+                .ok_or_else(|| VerificationError::MissingHeader(name.to_string()))
+        };
+        let number = |name: &str| {
+            get(name)?
+                .parse::<u128>()
+                .map_err(|e| VerificationError::MalformedHeader {
+                    header: name.to_string(),
+                    reason: e.to_string(),
+                })
+        };
+
+        Ok(Self {
+            tab_id: get("X402-Tab-ID")?,
+            contract_id: get("X402-Contract-ID")?,
+            sequence: number("X402-Tab-Sequence")?.try_into().map_err(|_| {
+                VerificationError::MalformedHeader {
+                    header: "X402-Tab-Sequence".to_string(),
+                    reason: "out of range".to_string(),
+                }
+            })?,
+            cumulative_amount: number("X402-Tab-Amount")?,
+            signature_scheme: get("X402-Signature-Scheme")?.parse().map_err(
+                |e: crate::Error| VerificationError::MalformedHeader {
+                    header: "X402-Signature-Scheme".to_string(),
+                    reason: e.to_string(),
+                },
+            )?,
+            signer: get("X402-Signer")?,
+            signature: get("X402-Signature")?,
+        })
+    }
+}
+
+/// Client side of an open tab
+pub struct PaymentTab {
+    tab_id: String,
+    contract_id: String,
+    unit_amount: u128,
+    signer: Arc<dyn X402Signer>,
+    policy: Option<Arc<SpendingPolicy>>,
+    /// Contract amount per request in whole token units
+    unit_price: Amount,
+    latest: Option<TabCommitment>,
+}
+
+impl PaymentTab {
+    /// Tab identifier
+    pub fn id(&self) -> &str {
+        &self.tab_id
+    }
+
+    /// Sign the commitment for one more request
+    ///
+    /// Fails with a `PolicyViolation` if the client's spending policy
+    /// forbids another request.
+    pub fn next_commitment(&mut self) -> Result<TabCommitment> {
+        if let Some(policy) = &self.policy {
+            policy.authorize(&self.contract_id, self.unit_price)?;
+        }
+        let (sequence, owed) = self
+            .latest
+            .as_ref()
+            .map_or((0, 0), |c| (c.sequence, c.cumulative_amount));
+
+        let mut commitment = TabCommitment {
+            tab_id: self.tab_id.clone(),
+            contract_id: self.contract_id.clone(),
+            sequence: sequence + 1,
+            cumulative_amount: owed + self.unit_amount,
+            signature_scheme: self.signer.scheme(),
+            signer: self.signer.identity(),
+            signature: String::new(),
+        };
+        commitment.signature = self.signer.sign(&commitment.signing_payload())?;
+        self.latest = Some(commitment.clone());
+        Ok(commitment)
+    }
+
+    /// Most recent commitment, which supersedes all earlier ones
+    pub fn latest(&self) -> Option<&TabCommitment> {
+        self.latest.as_ref()
+    }
+}
+
+impl X402Client {
+    /// Open a tab billing the contract amount per request
+    pub fn open_tab(&self, ucl: &UCLContract) -> Result<PaymentTab> {
+        let signer = self
+            .signer
+            .clone()
+            .ok_or_else(|| crate::Error::ConfigError("X402Client has no signer".to_string()))?;
+        Ok(PaymentTab {
+            tab_id: format!("tab_{:016x}", rand::random::<u64>()),
+            contract_id: ucl.contract_id.clone(),
+            unit_amount: unit_amount(ucl),
+            signer,
+            policy: self.policy.clone(),
+            unit_price: ucl.payment.amount,
+            latest: None,
+        })
+    }
+
+    /// Settle a tab by submitting its latest commitment to the facilitator
+    pub async fn close_tab(&self, tab: PaymentTab) -> Result<PaymentResponse> {
+        let commitment = tab.latest.ok_or_else(|| {
+            crate::Error::PaymentError(format!("Tab {} has no commitments", tab.tab_id))
+        })?;
+
+        let response = self
+            .http
+            .post(self.url(&format!("tabs/{}/close", commitment.tab_id)))
+            .json(&commitment)
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(crate::Error::PaymentError(format!(
+                "Facilitator rejected tab settlement ({}): {}",
+                status, body
+            )));
+        }
+        Ok(response.json().await?)
+    }
+}
+
+/// Running balance of a tab on the server
+#[derive(Debug, Clone)]
+pub struct TabBalance {
+    pub signer: String,
+    /// Role of the signer in the contract
+    pub role: String,
+    pub requests: u64,
+    /// Owed so far, in the token's base units
+    pub owed: u128,
+    /// Commitment to settle when the tab is closed
+    pub latest: TabCommitment,
+}
+
+/// Server-side accounting of open tabs
+#[derive(Debug, Default)]
+pub struct TabLedger {
+    tabs: Mutex<HashMap<String, TabBalance>>,
+}
+
+impl TabLedger {
+    /// Create empty ledger
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Verify and record the commitment for one request
+    ///
+    /// Commitments must arrive in sequence and raise the owed amount by at
+    /// least the contract's per-request price.
+    pub fn accept(
+        &self,
+        ucl: &UCLContract,
+        commitment: &TabCommitment,
+    ) -> std::result::Result<TabBalance, VerificationError> {
+        if commitment.contract_id != ucl.contract_id {
+            return Err(VerificationError::ContractMismatch {
+                expected: ucl.contract_id.clone(),
+                actual: commitment.contract_id.clone(),
+            });
+        }
+        let signer = verify_signature(
+            commitment.signature_scheme,
+            &commitment.signing_payload(),
+            &commitment.signature,
+            &commitment.signer,
+        )
+        .map_err(|e| VerificationError::InvalidSignature(e.to_string()))?;
+        let role = party_role(ucl, commitment.signature_scheme, &signer)?;
+
+        let mut tabs = self.tabs.lock().unwrap();
+        let (sequence, owed) = match tabs.get(&commitment.tab_id) {
+            Some(balance) if balance.signer != signer => {
+                return Err(VerificationError::UnauthorizedSigner(signer))
+            }
+            Some(balance) => (balance.requests, balance.owed),
+            None => (0, 0),
+        };
+
+        if commitment.sequence != sequence + 1 {
+            return Err(VerificationError::TabSequence {
+                tab_id: commitment.tab_id.clone(),
+                expected: sequence + 1,
+                actual: commitment.sequence,
+            });
+        }
+        let required = owed + unit_amount(ucl);
+        if commitment.cumulative_amount < required {
+            return Err(VerificationError::TermsMismatch {
+                field: "tab amount".to_string(),
+                expected: required.to_string(),
+                actual: commitment.cumulative_amount.to_string(),
+            });
+        }
+
+        let balance = TabBalance {
+            signer,
+            role,
+            requests: commitment.sequence,
+            owed: commitment.cumulative_amount,
+            latest: commitment.clone(),
+        };
+        tabs.insert(commitment.tab_id.clone(), balance.clone());
+        Ok(balance)
+    }
+
+    /// Current balance of a tab
+    pub fn balance(&self, tab_id: &str) -> Option<TabBalance> {
+        self.tabs.lock().unwrap().get(tab_id).cloned()
+    }
+
+    /// Close a tab, returning its final balance for settlement
+    pub fn close_tab(&self, tab_id: &str) -> Option<TabBalance> {
+        self.tabs.lock().unwrap().remove(tab_id)
+    }
+
+    /// Ids of all open tabs
+    pub fn open_tabs(&self) -> Vec<String> {
+        self.tabs.lock().unwrap().keys().cloned().collect()
+    }
+}
+
+/// Per-request price in base units
+fn unit_amount(ucl: &UCLContract) -> u128 {
+    let decimals = ucl.payment.token.decimals();
+    ucl.payment.amount.to_base_units(decimals).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PartyInfo;
+    use crate::{Amount, Contract, ContractConfig};
+
+    const TEST_KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    #[test]
+    fn test_tab_accumulates_commitments() {
+        let client =
+            X402Client::with_private_key("https://x402.test".to_string(), TEST_KEY).unwrap();
+        let mut ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+        ucl.payment.amount = Amount::new(1, 3);
+        ucl.metadata.parties.push(PartyInfo {
+            role: "payer".to_string(),
+            identifier: client.signer_address().unwrap(),
+            name: None,
+            ..Default::default()
+        });
+
+        let ledger = TabLedger::new();
+        let mut tab = client.open_tab(&ucl).unwrap();
+        for _ in 0..3 {
+            let headers = tab.next_commitment().unwrap().to_map();
+            ledger
+                .accept(&ucl, &TabCommitment::from_map(&headers).unwrap())
+                .unwrap();
+        }
+
+        let balance = ledger.balance(tab.id()).unwrap();
+        assert_eq!(balance.requests, 3);
+        assert_eq!(balance.owed, 3000);
+
+        let replayed = tab.latest().unwrap().clone();
+        assert!(matches!(
+            ledger.accept(&ucl, &replayed),
+            Err(VerificationError::TabSequence { expected: 4, .. })
+        ));
+
+        let closed = ledger.close_tab(tab.id()).unwrap();
+        assert_eq!(closed.latest, replayed);
+        assert!(ledger.open_tabs().is_empty());
+    }
+}
@@ -0,0 +1,362 @@
+//! Local mock facilitator for integration tests
+//!
+//! [`MockFacilitator`] serves the facilitator endpoints used by
+//! [`X402Client`](super::X402Client) on a random localhost port, so tests
+//! can submit payments and poll settlement without reaching a real
+//! facilitator:
+//!
+//! ```no_run
+//! # async fn run() -> smart402::Result<()> {
+//! use smart402::x402::testing::{MockBehavior, MockFacilitator};
+//! use smart402::x402::X402Client;
+//!
+//! let facilitator = MockFacilitator::start().await?;
+//! facilitator.set_behavior(MockBehavior::RequireRetry(2));
+//! let client = X402Client::builder().endpoint(facilitator.url()).build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use super::client::{PaymentResponse, X402Headers};
+use super::settlement::{SettlementReceipt, SettlementStatus};
+use crate::Result;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+/// How the mock answers payment submissions
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum MockBehavior {
+    /// Verify the signature and accept valid payments
+    Accept,
+    /// Reject every payment with 402 and this reason
+    Reject(String),
+    /// Wait before answering, then behave like `Accept`
+    Delay(Duration),
+    /// Answer 503 to the next `n` submissions, then behave like `Accept`
+    RequireRetry(u32),
+}
+
+/// Payment accepted by a [`MockFacilitator`]
+#[derive(Debug, Clone)]
+pub struct RecordedPayment {
+    pub reference: String,
+    pub headers: X402Headers,
+    /// Verified signer identity
+    pub signer: String,
+    pub idempotency_key: Option<String>,
+    pub payload: HashMap<String, String>,
+}
+
+#[derive(Default)]
+struct State {
+    behavior: Option<MockBehavior>,
+    /// Submissions received, including rejected and retried ones
+    submissions: usize,
+    payments: Vec<RecordedPayment>,
+}
+
+/// Local HTTP server imitating an X402 facilitator
+///
+/// The server stops when the facilitator is dropped.
+pub struct MockFacilitator {
+    url: String,
+    state: Arc<Mutex<State>>,
+    task: JoinHandle<()>,
+}
+
+impl MockFacilitator {
+    /// Start a facilitator that accepts every validly signed payment
+    pub async fn start() -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}", listener.local_addr()?);
+        let state = Arc::new(Mutex::new(State::default()));
+
+        let shared = state.clone();
+        let task = tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                tokio::spawn(handle_connection(socket, shared.clone()));
+            }
+        });
+        Ok(Self { url, state, task })
+    }
+
+    /// Base URL to pass as the client endpoint
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Change how later submissions are answered
+    pub fn set_behavior(&self, behavior: MockBehavior) {
+        self.state.lock().unwrap().behavior = Some(behavior);
+    }
+
+    /// Payments accepted so far, in order
+    pub fn payments(&self) -> Vec<RecordedPayment> {
+        self.state.lock().unwrap().payments.clone()
+    }
+
+    /// Number of payment submissions received, including retries
+    pub fn submissions(&self) -> usize {
+        self.state.lock().unwrap().submissions
+    }
+}
+
+impl Drop for MockFacilitator {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Parsed HTTP request
+struct Request {
+    method: String,
+    path: String,
+    headers: HashMap<String, String>,
+    body: Vec<u8>,
+}
+
+async fn handle_connection(mut socket: TcpStream, state: Arc<Mutex<State>>) {
+    let Some(request) = read_request(&mut socket).await else {
+        return;
+    };
+    let (status, body) = respond(request, &state).await;
+    let response = format!(
+        "HTTP/1.1 {} Mock\r\ncontent-type: application/json\r\n\
+         content-length: {}\r\nconnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    let _ = socket.write_all(response.as_bytes()).await;
+}
+
+async fn read_request(socket: &mut TcpStream) -> Option<Request> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let head_end = loop {
+        let n = socket.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(i) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break i + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buf[..head_end]).into_owned();
+    let mut lines = head.lines();
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let path = request_line.next()?.to_string();
+    let headers: HashMap<String, String> = lines
+        .filter_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            Some((name.trim().to_string(), value.trim().to_string()))
+        })
+        .collect();
+
+    let length = headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, v)| v.parse().ok())
+        .unwrap_or(0);
+    let mut body = buf[head_end..].to_vec();
+    while body.len() < length {
+        let n = socket.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            break;
+        }
+        body.extend_from_slice(&chunk[..n]);
+    }
+
+    Some(Request {
+        method,
+        path,
+        headers,
+        body,
+    })
+}
+
+async fn respond(request: Request, state: &Mutex<State>) -> (u16, String) {
+    match (request.method.as_str(), request.path.as_str()) {
+        ("POST", "/payments") => submit_payment(request, state).await,
+        ("GET", path) if path.starts_with("/payments/") => {
+            let reference = &path["/payments/".len()..];
+            settlement(reference, state)
+        }
+        _ => (404, error_body("not found")),
+    }
+}
+
+async fn submit_payment(request: Request, state: &Mutex<State>) -> (u16, String) {
+    let behavior = {
+        let mut state = state.lock().unwrap();
+        state.submissions += 1;
+        let behavior = state.behavior.clone().unwrap_or(MockBehavior::Accept);
+        if let MockBehavior::RequireRetry(n) = behavior {
+            state.behavior = Some(match n {
+                0 | 1 => MockBehavior::Accept,
+                n => MockBehavior::RequireRetry(n - 1),
+            });
+            if n > 0 {
+                return (503, error_body("temporarily unavailable, retry"));
+            }
+        }
+        behavior
+    };
+    match behavior {
+        MockBehavior::Reject(reason) => return (402, error_body(&reason)),
+        MockBehavior::Delay(delay) => tokio::time::sleep(delay).await,
+        MockBehavior::Accept | MockBehavior::RequireRetry(_) => {}
+    }
+
+    let headers = match X402Headers::from_map(&request.headers) {
+        Ok(headers) => headers,
+        Err(e) => return (400, error_body(&e.to_string())),
+    };
+    let signer = match headers.verify_signature() {
+        Ok(signer) => signer,
+        Err(e) => return (401, error_body(&e.to_string())),
+    };
+    let payload = serde_json::from_slice(&request.body).unwrap_or_default();
+    let idempotency_key = request
+        .headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case("Idempotency-Key"))
+        .map(|(_, v)| v.clone());
+
+    let mut state = state.lock().unwrap();
+    let existing = state
+        .payments
+        .iter()
+        .find(|p| idempotency_key.is_some() && p.idempotency_key == idempotency_key);
+    let reference = match existing {
+        Some(payment) => payment.reference.clone(),
+        None => {
+            let reference = format!("mock_{}", state.payments.len() + 1);
+            state.payments.push(RecordedPayment {
+                reference: reference.clone(),
+                headers,
+                signer,
+                idempotency_key,
+                payload,
+            });
+            reference
+        }
+    };
+    let response = PaymentResponse {
+        status: "accepted".to_string(),
+        transaction_hash: Some(transaction_hash(&reference)),
+        reference: Some(reference),
+        confirmation_url: None,
+    };
+    (200, serde_json::to_string(&response).unwrap_or_default())
+}
+
+fn settlement(reference: &str, state: &Mutex<State>) -> (u16, String) {
+    let state = state.lock().unwrap();
+    let Some(payment) = state.payments.iter().find(|p| p.reference == reference) else {
+        return (404, error_body("unknown payment"));
+    };
+    let receipt = SettlementReceipt {
+        reference: payment.reference.clone(),
+        status: SettlementStatus::Settled,
+        transaction_hash: Some(transaction_hash(&payment.reference)),
+        network: Some(payment.headers.settlement_network.clone()),
+        block_number: Some(1),
+        confirmations: 1,
+        settled_at: Some(chrono::Utc::now()),
+        error: None,
+    };
+    (200, serde_json::to_string(&receipt).unwrap_or_default())
+}
+
+/// Deterministic fake transaction hash for a reference
+fn transaction_hash(reference: &str) -> String {
+    use sha2::{Digest, Sha256};
+    format!("0x{}", hex::encode(Sha256::digest(reference.as_bytes())))
+}
+
+fn error_body(reason: &str) -> String {
+    serde_json::json!({ "error": reason }).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::x402::{RetryPolicy, X402Client};
+    use crate::{Contract, ContractConfig};
+
+    const TEST_KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    #[tokio::test]
+    async fn test_mock_facilitator_accepts_after_retries() {
+        let facilitator = MockFacilitator::start().await.unwrap();
+        facilitator.set_behavior(MockBehavior::RequireRetry(2));
+        let client = X402Client::with_private_key(facilitator.url().to_string(), TEST_KEY)
+            .unwrap()
+            .with_retry_policy(RetryPolicy {
+                initial_backoff: Duration::from_millis(1),
+                ..RetryPolicy::default()
+            });
+        let ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+
+        let headers = client.generate_headers(&ucl, true).unwrap();
+        let response = client
+            .send_payment_request(headers, HashMap::new())
+            .await
+            .unwrap();
+        assert_eq!(facilitator.submissions(), 3);
+        assert_eq!(facilitator.payments().len(), 1);
+        assert_eq!(
+            facilitator.payments()[0].signer,
+            client.signer_address().unwrap()
+        );
+
+        let receipt = client
+            .await_settlement(
+                response.settlement_reference().unwrap(),
+                Duration::from_secs(1),
+            )
+            .await
+            .unwrap();
+        assert_eq!(receipt.status, SettlementStatus::Settled);
+    }
+
+    #[tokio::test]
+    async fn test_mock_facilitator_rejects() {
+        let facilitator = MockFacilitator::start().await.unwrap();
+        facilitator.set_behavior(MockBehavior::Reject("insufficient funds".to_string()));
+        let client = X402Client::with_private_key(facilitator.url().to_string(), TEST_KEY).unwrap();
+        let ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+
+        let headers = client.generate_headers(&ucl, true).unwrap();
+        let err = client
+            .send_payment_request(headers, HashMap::new())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("insufficient funds"));
+        assert!(!err.is_retryable());
+        assert!(facilitator.payments().is_empty());
+
+        facilitator.set_behavior(MockBehavior::RequireRetry(1));
+        let headers = client.generate_headers(&ucl, true).unwrap();
+        let err = client
+            .with_retry_policy(RetryPolicy::none())
+            .send_payment_request(headers, HashMap::new())
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "unavailable");
+        assert!(err.is_retryable());
+    }
+}
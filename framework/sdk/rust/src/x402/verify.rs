@@ -0,0 +1,452 @@
+//! Server-side verification of signed X402 payments
+
+use super::client::{X402Client, X402Headers};
+use super::nonce::{nonce_timestamp, unix_now, InMemoryNonceStore, NonceStore};
+use super::price::PriceOption;
+use super::signing::SignatureScheme;
+use crate::types::PartyInfo;
+use crate::{Amount, Network, UCLContract};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Default maximum age of a payment nonce
+pub const DEFAULT_MAX_NONCE_AGE: Duration = Duration::from_secs(300);
+
+/// Tolerated clock skew for nonces timestamped in the future
+const MAX_CLOCK_SKEW: Duration = Duration::from_secs(30);
+
+/// Structured reason an X402 payment was rejected
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[non_exhaustive]
+pub enum VerificationError {
+    #[error("Missing X402 header: {0}")]
+    MissingHeader(String),
+
+    #[error("Malformed X402 header {header}: {reason}")]
+    MalformedHeader { header: String, reason: String },
+
+    #[error("Payment is for contract {actual}, expected {expected}")]
+    ContractMismatch { expected: String, actual: String },
+
+    #[error("Payment {field} is {actual}, contract requires {expected}")]
+    TermsMismatch {
+        field: String,
+        expected: String,
+        actual: String,
+    },
+
+    #[error("Invalid payment signature: {0}")]
+    InvalidSignature(String),
+
+    #[error("Signer {0} is not a party to the contract")]
+    UnauthorizedSigner(String),
+
+    #[error("Nonce is {age_secs}s old, maximum is {max_age_secs}s")]
+    StaleNonce { age_secs: u64, max_age_secs: u64 },
+
+    #[error("Nonce is {ahead_secs}s in the future")]
+    FutureNonce { ahead_secs: u64 },
+
+    #[error("Nonce {0} was already used")]
+    ReplayedNonce(String),
+
+    #[error("Nonce store unavailable: {0}")]
+    NonceStoreUnavailable(String),
+
+    #[error("No request credits left for {0}")]
+    CreditsExhausted(String),
+
+    #[error("Tab {tab_id} expected commitment {expected}, got {actual}")]
+    TabSequence {
+        tab_id: String,
+        expected: u64,
+        actual: u64,
+    },
+
+    #[error("Invalid payment receipt: {0}")]
+    InvalidReceipt(String),
+
+    #[error("Payment receipt has expired")]
+    ExpiredReceipt,
+
+    #[error("Refund not allowed: {0}")]
+    RefundIneligible(String),
+
+    #[error("Facilitator rejected the payment: {0}")]
+    FacilitatorRejected(String),
+
+    #[error("Facilitator unavailable: {0}")]
+    FacilitatorUnavailable(String),
+
+    #[error("Payment was not settled: {0}")]
+    Unsettled(String),
+}
+
+/// A payment whose signature, signer and terms were verified
+#[derive(Debug, Clone)]
+pub struct VerifiedPayment {
+    pub headers: X402Headers,
+    /// Verified signer identity
+    pub signer: String,
+    /// Role of the signer in the contract (e.g. "payer")
+    pub role: String,
+}
+
+/// Verifies signed payments and rejects replayed nonces
+#[derive(Clone)]
+pub struct PaymentVerifier {
+    max_nonce_age: Duration,
+    nonces: Arc<dyn NonceStore>,
+    prices: Arc<[PriceOption]>,
+}
+
+impl Default for PaymentVerifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PaymentVerifier {
+    /// Create verifier with an in-memory nonce store and default nonce age
+    pub fn new() -> Self {
+        Self {
+            max_nonce_age: DEFAULT_MAX_NONCE_AGE,
+            nonces: Arc::new(InMemoryNonceStore::new()),
+            prices: Arc::new([]),
+        }
+    }
+
+    /// Set maximum accepted nonce age
+    pub fn with_max_nonce_age(mut self, max_nonce_age: Duration) -> Self {
+        self.max_nonce_age = max_nonce_age;
+        self
+    }
+
+    /// Use a custom (e.g. persistent) nonce store
+    pub fn with_nonce_store(mut self, nonces: Arc<dyn NonceStore>) -> Self {
+        self.nonces = nonces;
+        self
+    }
+
+    /// Also accept payments in these alternative tokens and networks
+    ///
+    /// The contract's own terms are always accepted.
+    pub fn with_accepted_prices(mut self, prices: Vec<PriceOption>) -> Self {
+        self.prices = prices.into();
+        self
+    }
+
+    /// Alternative prices accepted besides the contract terms
+    pub fn accepted_prices(&self) -> &[PriceOption] {
+        &self.prices
+    }
+
+    /// Verify a payment and record its nonce
+    ///
+    /// The nonce is only recorded once every other check has passed, so a
+    /// rejected payment does not burn it.
+    pub fn verify(
+        &self,
+        ucl: &UCLContract,
+        headers: &HashMap<String, String>,
+    ) -> std::result::Result<VerifiedPayment, VerificationError> {
+        self.verify_headers(ucl, &X402Headers::from_map(headers)?)
+    }
+
+    /// Verify already-parsed headers and record their nonce
+    pub fn verify_headers(
+        &self,
+        ucl: &UCLContract,
+        headers: &X402Headers,
+    ) -> std::result::Result<VerifiedPayment, VerificationError> {
+        let payment = verify_priced_payment(ucl, headers, self.max_nonce_age, &self.prices)?;
+        self.accept_nonce(&payment.signer, &payment.headers.nonce)?;
+        Ok(payment)
+    }
+
+    /// Check a timestamped nonce is fresh and record it for `signer`
+    pub(crate) fn accept_nonce(
+        &self,
+        signer: &str,
+        nonce: &str,
+    ) -> std::result::Result<(), VerificationError> {
+        check_nonce(nonce, self.max_nonce_age)?;
+        let issued = nonce_timestamp(nonce).unwrap_or_default();
+        self.record_nonce(
+            signer,
+            nonce,
+            issued + self.max_nonce_age.as_secs() + MAX_CLOCK_SKEW.as_secs(),
+        )
+    }
+
+    /// Record a nonce verified by other means, rejecting replays
+    pub(crate) fn record_nonce(
+        &self,
+        signer: &str,
+        nonce: &str,
+        expires_at: u64,
+    ) -> std::result::Result<(), VerificationError> {
+        self.nonces.check_and_record(signer, nonce, expires_at)
+    }
+}
+
+impl X402Client {
+    /// Verify a signed X402 payment against the contract it references
+    ///
+    /// Checks the signature, that the signer is a party of `ucl`, that the
+    /// payment terms match the contract and that the nonce is fresh and
+    /// has not been seen by this client before.
+    pub fn verify_response(
+        &self,
+        ucl: &UCLContract,
+        headers: &HashMap<String, String>,
+    ) -> std::result::Result<VerifiedPayment, VerificationError> {
+        self.verifier.verify(ucl, headers)
+    }
+
+    /// Replace the verifier used by [`X402Client::verify_response`]
+    pub fn with_verifier(mut self, verifier: PaymentVerifier) -> Self {
+        self.verifier = verifier;
+        self
+    }
+}
+
+/// Verify a signed X402 payment with an explicit nonce age limit
+///
+/// Stateless: does not track used nonces. Use [`PaymentVerifier`] to also
+/// reject replays.
+pub fn verify_payment(
+    ucl: &UCLContract,
+    headers: &X402Headers,
+    max_nonce_age: Duration,
+) -> std::result::Result<VerifiedPayment, VerificationError> {
+    verify_priced_payment(ucl, headers, max_nonce_age, &[])
+}
+
+fn verify_priced_payment(
+    ucl: &UCLContract,
+    headers: &X402Headers,
+    max_nonce_age: Duration,
+    prices: &[PriceOption],
+) -> std::result::Result<VerifiedPayment, VerificationError> {
+    if headers.contract_id != ucl.contract_id {
+        return Err(VerificationError::ContractMismatch {
+            expected: ucl.contract_id.clone(),
+            actual: headers.contract_id.clone(),
+        });
+    }
+    check_terms(ucl, prices, headers)?;
+
+    let signer = headers
+        .verify_signature()
+        .map_err(|e| VerificationError::InvalidSignature(e.to_string()))?;
+
+    let role = party_role(ucl, headers.signature_scheme, &signer)?;
+    // Signed over different terms than the contract has now
+    if let Some(actual) = &headers.contract_digest {
+        let expected = ucl.content_digest().unwrap_or_default();
+        if *actual != expected {
+            return Err(VerificationError::TermsMismatch {
+                field: "contract digest".to_string(),
+                expected,
+                actual: actual.clone(),
+            });
+        }
+    }
+
+    check_nonce(&headers.nonce, max_nonce_age)?;
+
+    Ok(VerifiedPayment {
+        headers: headers.clone(),
+        signer,
+        role,
+    })
+}
+
+fn check_terms(
+    ucl: &UCLContract,
+    prices: &[PriceOption],
+    headers: &X402Headers,
+) -> std::result::Result<(), VerificationError> {
+    // An advertised alternative replaces the contract's token and network
+    let alternative = prices
+        .iter()
+        .find(|p| p.matches(&headers.payment_token, &headers.settlement_network));
+    let (required, expected) = match alternative {
+        Some(price) => (price.amount.parse().ok(), price.amount.clone()),
+        None => (Some(ucl.payment.amount), ucl.payment.amount.to_string()),
+    };
+    let amount: Amount =
+        headers
+            .payment_amount
+            .parse()
+            .map_err(|_| VerificationError::MalformedHeader {
+                header: "X402-Payment-Amount".to_string(),
+                reason: format!("not a number: {}", headers.payment_amount),
+            })?;
+    if required.is_none_or(|required| amount < required) {
+        return Err(VerificationError::TermsMismatch {
+            field: "amount".to_string(),
+            expected,
+            actual: headers.payment_amount.clone(),
+        });
+    }
+    if alternative.is_some() {
+        return Ok(());
+    }
+    if !ucl.payment.token.is(&headers.payment_token) {
+        return Err(VerificationError::TermsMismatch {
+            field: "token".to_string(),
+            expected: ucl.payment.token.to_string(),
+            actual: headers.payment_token.clone(),
+        });
+    }
+    if headers.settlement_network.parse::<Network>().ok() != Some(ucl.payment.blockchain) {
+        return Err(VerificationError::TermsMismatch {
+            field: "network".to_string(),
+            expected: ucl.payment.blockchain.to_string(),
+            actual: headers.settlement_network.clone(),
+        });
+    }
+    Ok(())
+}
+
+fn check_nonce(nonce: &str, max_age: Duration) -> std::result::Result<(), VerificationError> {
+    let issued = nonce_timestamp(nonce).ok_or_else(|| VerificationError::MalformedHeader {
+        header: "X402-Nonce".to_string(),
+        reason: format!("does not start with a unix timestamp: {}", nonce),
+    })?;
+    let now = unix_now();
+
+    if issued > now + MAX_CLOCK_SKEW.as_secs() {
+        return Err(VerificationError::FutureNonce {
+            ahead_secs: issued - now,
+        });
+    }
+    let age = now.saturating_sub(issued);
+    if age > max_age.as_secs() {
+        return Err(VerificationError::StaleNonce {
+            age_secs: age,
+            max_age_secs: max_age.as_secs(),
+        });
+    }
+    Ok(())
+}
+
+/// Role of the contract party with identity `signer`
+pub(crate) fn party_role(
+    ucl: &UCLContract,
+    scheme: SignatureScheme,
+    signer: &str,
+) -> std::result::Result<String, VerificationError> {
+    ucl.metadata
+        .parties
+        .iter()
+        .find(|p| is_party(p, scheme, signer))
+        .map(|p| p.role.clone())
+        .ok_or_else(|| VerificationError::UnauthorizedSigner(signer.to_string()))
+}
+
+/// Whether `signer` is `party`, by its identifier or its wallet address
+pub(crate) fn is_party(party: &PartyInfo, scheme: SignatureScheme, signer: &str) -> bool {
+    same_identity(scheme, &party.identifier, signer)
+        || party
+            .wallet_address()
+            .is_some_and(|wallet| same_identity(scheme, wallet, signer))
+}
+
+/// EVM addresses compare case-insensitively, Ed25519 keys exactly
+fn same_identity(scheme: SignatureScheme, party: &str, signer: &str) -> bool {
+    match scheme {
+        SignatureScheme::Secp256k1 => party.eq_ignore_ascii_case(signer),
+        SignatureScheme::Ed25519 => party == signer,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Contract, ContractConfig};
+
+    const TEST_KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    fn setup() -> (X402Client, UCLContract) {
+        let client =
+            X402Client::with_private_key("https://x402.test".to_string(), TEST_KEY).unwrap();
+        let mut ucl = Contract::from_config(ContractConfig::default())
+            .unwrap()
+            .ucl;
+        ucl.metadata.parties.push(PartyInfo {
+            role: "payer".to_string(),
+            identifier: client.signer_address().unwrap().to_lowercase(),
+            name: None,
+            ..Default::default()
+        });
+        (client, ucl)
+    }
+
+    #[test]
+    fn test_verify_signed_payment() {
+        let (client, ucl) = setup();
+        let headers = client.generate_headers(&ucl, true).unwrap().to_map();
+
+        let verified = client.verify_response(&ucl, &headers).unwrap();
+        assert_eq!(verified.role, "payer");
+        assert_eq!(verified.signer, client.signer_address().unwrap());
+
+        assert!(matches!(
+            client.verify_response(&ucl, &headers),
+            Err(VerificationError::ReplayedNonce(_))
+        ));
+
+        // A party known by email signs with the wallet it lists
+        let mut ucl = ucl;
+        ucl.metadata.parties[0].identifier = "billing@customer.example".to_string();
+        ucl.metadata.parties[0].wallet = client.signer_address();
+        let headers = client.generate_headers(&ucl, true).unwrap().to_map();
+        assert_eq!(client.verify_response(&ucl, &headers).unwrap().role, "payer");
+    }
+
+    #[test]
+    fn test_rejects_tampered_and_unauthorized_payments() {
+        let (client, mut ucl) = setup();
+        let headers = client.generate_headers(&ucl, true).unwrap();
+
+        let mut tampered = headers.clone();
+        tampered.nonce = "1000".to_string();
+        assert!(matches!(
+            client.verify_response(&ucl, &tampered.to_map()),
+            Err(VerificationError::InvalidSignature(_))
+        ));
+
+        let mut amended = ucl.clone();
+        amended.summary.title = "Amended".to_string();
+        assert!(matches!(
+            client.verify_response(&amended, &headers.to_map()),
+            Err(VerificationError::TermsMismatch { field, .. }) if field == "contract digest"
+        ));
+
+        ucl.metadata.parties.clear();
+        assert!(matches!(
+            client.verify_response(&ucl, &headers.to_map()),
+            Err(VerificationError::UnauthorizedSigner(_))
+        ));
+
+        let mut missing = headers.to_map();
+        missing.remove("X402-Nonce");
+        assert_eq!(
+            client.verify_response(&ucl, &missing).unwrap_err(),
+            VerificationError::MissingHeader("X402-Nonce".to_string())
+        );
+    }
+
+    #[test]
+    fn test_nonce_freshness() {
+        assert!(check_nonce("1000", DEFAULT_MAX_NONCE_AGE).is_err());
+        assert!(matches!(
+            check_nonce("99999999999", DEFAULT_MAX_NONCE_AGE),
+            Err(VerificationError::FutureNonce { .. })
+        ));
+    }
+}
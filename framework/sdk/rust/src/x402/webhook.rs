@@ -0,0 +1,152 @@
+//! Verification of facilitator webhook notifications
+//!
+//! Webhooks carry an `X402-Webhook-Signature` header of the form
+//! `t=<unix seconds>,v1=<hex HMAC-SHA256>`, where the MAC covers
+//! `"<t>.<raw body>"` under the shared webhook secret.
+
+use super::nonce::unix_now;
+use super::settlement::SettlementReceipt;
+use super::signing::SignatureError;
+use crate::Result;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+/// Header carrying the webhook signature
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "X402-Webhook-Signature";
+
+/// Maximum age of a webhook signature
+pub const WEBHOOK_TOLERANCE_SECS: u64 = 300;
+
+/// Refund notification details
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RefundNotice {
+    pub refund_id: String,
+    /// Reference of the refunded payment
+    pub payment_reference: String,
+    pub amount: String,
+    #[serde(default)]
+    pub transaction_hash: Option<String>,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// Event delivered by the facilitator
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data")]
+#[non_exhaustive]
+pub enum WebhookEvent {
+    #[serde(rename = "payment.settled")]
+    PaymentSettled(SettlementReceipt),
+    #[serde(rename = "payment.failed")]
+    PaymentFailed(SettlementReceipt),
+    #[serde(rename = "refund.issued")]
+    RefundIssued(RefundNotice),
+    #[serde(rename = "refund.failed")]
+    RefundFailed(RefundNotice),
+}
+
+/// Verify a webhook signature and parse its event
+pub fn verify_webhook(payload: &[u8], signature: &str, secret: &str) -> Result<WebhookEvent> {
+    verify_webhook_at(payload, signature, secret, unix_now())
+}
+
+/// Sign a webhook body, returning the signature header value
+pub fn sign_webhook(payload: &[u8], secret: &str) -> String {
+    let timestamp = unix_now();
+    format!(
+        "t={},v1={}",
+        timestamp,
+        hex::encode(mac(timestamp, payload, secret).finalize().into_bytes())
+    )
+}
+
+fn verify_webhook_at(
+    payload: &[u8],
+    signature: &str,
+    secret: &str,
+    now: u64,
+) -> Result<WebhookEvent> {
+    check_signature(payload, signature, secret, now)?;
+    Ok(serde_json::from_slice(payload)?)
+}
+
+/// Check a `t=..,v1=..` signature over `"<t>.<body>"`
+///
+/// Also the scheme of Stripe's `Stripe-Signature` header.
+pub(crate) fn check_signature(
+    payload: &[u8],
+    signature: &str,
+    secret: &str,
+    now: u64,
+) -> Result<()> {
+    let invalid = |reason: &str| {
+        crate::Error::from(SignatureError::Webhook {
+            reason: reason.to_string(),
+        })
+    };
+
+    let mut timestamp = None;
+    let mut candidates = Vec::new();
+    for part in signature.split(',') {
+        match part.trim().split_once('=') {
+            Some(("t", value)) => timestamp = value.parse::<u64>().ok(),
+            Some(("v1", value)) => candidates.push(value),
+            _ => {}
+        }
+    }
+    let timestamp = timestamp.ok_or_else(|| invalid("signature has no timestamp"))?;
+    if now.abs_diff(timestamp) > WEBHOOK_TOLERANCE_SECS {
+        return Err(invalid("signature is outside the tolerance window"));
+    }
+
+    // Several v1 entries may be present while a secret is being rotated
+    let verified = candidates.iter().any(|candidate| {
+        hex::decode(candidate)
+            .map(|expected| {
+                mac(timestamp, payload, secret)
+                    .verify_slice(&expected)
+                    .is_ok()
+            })
+            .unwrap_or(false)
+    });
+    if !verified {
+        return Err(invalid("signature does not match"));
+    }
+    Ok(())
+}
+
+fn mac(timestamp: u64, payload: &[u8], secret: &str) -> Hmac<Sha256> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(payload);
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BODY: &str = r#"{"type":"payment.settled","data":{"reference":"pay_1","status":"settled","transaction_hash":"0xabc"}}"#;
+
+    #[test]
+    fn test_verify_signed_webhook() {
+        let signature = sign_webhook(BODY.as_bytes(), "whsec_test");
+        match verify_webhook(BODY.as_bytes(), &signature, "whsec_test").unwrap() {
+            WebhookEvent::PaymentSettled(receipt) => assert_eq!(receipt.reference, "pay_1"),
+            other => panic!("unexpected event {:?}", other),
+        }
+
+        assert!(verify_webhook(BODY.as_bytes(), &signature, "whsec_other").is_err());
+        assert!(verify_webhook(b"{}", &signature, "whsec_test").is_err());
+    }
+
+    #[test]
+    fn test_rejects_stale_signature() {
+        let signature = sign_webhook(BODY.as_bytes(), "whsec_test");
+        let later = unix_now() + WEBHOOK_TOLERANCE_SECS + 1;
+        assert!(verify_webhook_at(BODY.as_bytes(), &signature, "whsec_test", later).is_err());
+    }
+}
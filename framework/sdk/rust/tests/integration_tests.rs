@@ -3,31 +3,24 @@
 //! Comprehensive test suite for Smart402 Rust SDK functionality
 
 use smart402::{
-    Smart402, Contract, ContractConfig, PaymentConfig,
-    AEOEngine, LLMOEngine, X402Client, Error, Result,
+    Amount, Network, Token, Smart402, ConditionConfig, ContractConfig, ContractMetadataInput, PaymentConfig, PaymentFrequency,
+    AEOEngine, LLMOEngine, X402Client, Result,
 };
 
+const TEST_KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
 #[tokio::test]
 async fn test_create_basic_contract() -> Result<()> {
-    let contract = Smart402::create(ContractConfig {
-        contract_type: "saas-subscription".to_string(),
-        parties: vec![
-            "vendor@example.com".to_string(),
-            "customer@example.com".to_string(),
-        ],
-        payment: PaymentConfig {
-            amount: 99.0,
-            token: "USDC".to_string(),
-            blockchain: "polygon".to_string(),
-            frequency: "monthly".to_string(),
-        },
-        conditions: None,
-        metadata: None,
-    }).await?;
+    let contract = Smart402::create(ContractConfig::new(
+        "saas-subscription",
+        PaymentConfig::new(Amount::from(99), Token::usdc(), PaymentFrequency::Monthly { day: None })
+            .with_blockchain(Network::Polygon),
+    )
+    .with_parties(["vendor@example.com", "customer@example.com"])).await?;
 
     assert!(contract.ucl.contract_id.contains("smart402:"));
-    assert_eq!(contract.ucl.payment.amount, 99.0);
-    assert_eq!(contract.ucl.payment.token, "USDC");
+    assert_eq!(contract.ucl.payment.amount, Amount::from(99));
+    assert_eq!(contract.ucl.payment.token, Token::usdc());
     assert_eq!(contract.ucl.metadata.parties.len(), 2);
 
     Ok(())
@@ -42,25 +35,19 @@ async fn test_create_from_template() -> Result<()> {
 
     let contract = Smart402::from_template("saas-subscription".to_string(), variables).await?;
 
-    assert_eq!(contract.ucl.payment.amount, 49.0);
+    assert_eq!(contract.ucl.payment.amount, Amount::from(49));
 
     Ok(())
 }
 
 #[tokio::test]
 async fn test_unique_contract_ids() -> Result<()> {
-    let config = ContractConfig {
-        contract_type: "test".to_string(),
-        parties: vec!["a@test.com".to_string(), "b@test.com".to_string()],
-        payment: PaymentConfig {
-            amount: 10.0,
-            token: "USDC".to_string(),
-            blockchain: "polygon".to_string(),
-            frequency: "monthly".to_string(),
-        },
-        conditions: None,
-        metadata: None,
-    };
+    let config = ContractConfig::new(
+        "test",
+        PaymentConfig::new(Amount::from(10), Token::usdc(), PaymentFrequency::Monthly { day: None })
+            .with_blockchain(Network::Polygon),
+    )
+    .with_parties(["a@test.com", "b@test.com"]);
 
     let contract1 = Smart402::create(config.clone()).await?;
     let contract2 = Smart402::create(config).await?;
@@ -72,25 +59,18 @@ async fn test_unique_contract_ids() -> Result<()> {
 
 #[tokio::test]
 async fn test_calculate_aeo_score() -> Result<()> {
-    let contract = Smart402::create(ContractConfig {
-        contract_type: "saas-subscription".to_string(),
-        parties: vec![
-            "vendor@example.com".to_string(),
-            "customer@example.com".to_string(),
-        ],
-        payment: PaymentConfig {
-            amount: 99.0,
-            token: "USDC".to_string(),
-            blockchain: "polygon".to_string(),
-            frequency: "monthly".to_string(),
-        },
-        conditions: None,
-        metadata: Some(serde_json::json!({
-            "title": "Monthly SaaS Subscription",
-            "description": "Automated monthly payment for software service",
-            "category": "saas"
-        })),
-    }).await?;
+    let contract = Smart402::create(ContractConfig::new(
+        "saas-subscription",
+        PaymentConfig::new(Amount::from(99), Token::usdc(), PaymentFrequency::Monthly { day: None })
+            .with_blockchain(Network::Polygon),
+    )
+    .with_parties(["vendor@example.com", "customer@example.com"])
+    .with_metadata(ContractMetadataInput {
+        title: Some("Monthly SaaS Subscription".to_string()),
+        description: Some("Automated monthly payment for software service".to_string()),
+        category: Some("saas".to_string()),
+        ..Default::default()
+    })).await?;
 
     let aeo = AEOEngine::new();
     let score = aeo.calculate_score(&contract.ucl)?;
@@ -105,18 +85,12 @@ async fn test_calculate_aeo_score() -> Result<()> {
 
 #[tokio::test]
 async fn test_generate_jsonld() -> Result<()> {
-    let contract = Smart402::create(ContractConfig {
-        contract_type: "saas-subscription".to_string(),
-        parties: vec!["vendor@example.com".to_string(), "customer@example.com".to_string()],
-        payment: PaymentConfig {
-            amount: 99.0,
-            token: "USDC".to_string(),
-            blockchain: "polygon".to_string(),
-            frequency: "monthly".to_string(),
-        },
-        conditions: None,
-        metadata: None,
-    }).await?;
+    let contract = Smart402::create(ContractConfig::new(
+        "saas-subscription",
+        PaymentConfig::new(Amount::from(99), Token::usdc(), PaymentFrequency::Monthly { day: None })
+            .with_blockchain(Network::Polygon),
+    )
+    .with_parties(["vendor@example.com", "customer@example.com"])).await?;
 
     let aeo = AEOEngine::new();
     let jsonld = aeo.generate_jsonld(&contract.ucl)?;
@@ -131,18 +105,12 @@ async fn test_generate_jsonld() -> Result<()> {
 
 #[tokio::test]
 async fn test_validate_contract() -> Result<()> {
-    let contract = Smart402::create(ContractConfig {
-        contract_type: "saas-subscription".to_string(),
-        parties: vec!["vendor@example.com".to_string(), "customer@example.com".to_string()],
-        payment: PaymentConfig {
-            amount: 99.0,
-            token: "USDC".to_string(),
-            blockchain: "polygon".to_string(),
-            frequency: "monthly".to_string(),
-        },
-        conditions: None,
-        metadata: None,
-    }).await?;
+    let contract = Smart402::create(ContractConfig::new(
+        "saas-subscription",
+        PaymentConfig::new(Amount::from(99), Token::usdc(), PaymentFrequency::Monthly { day: None })
+            .with_blockchain(Network::Polygon),
+    )
+    .with_parties(["vendor@example.com", "customer@example.com"])).await?;
 
     let llmo = LLMOEngine::new();
     let validation = llmo.validate(&contract.ucl)?;
@@ -155,18 +123,12 @@ async fn test_validate_contract() -> Result<()> {
 
 #[tokio::test]
 async fn test_generate_explanation() -> Result<()> {
-    let contract = Smart402::create(ContractConfig {
-        contract_type: "saas-subscription".to_string(),
-        parties: vec!["vendor@example.com".to_string(), "customer@example.com".to_string()],
-        payment: PaymentConfig {
-            amount: 99.0,
-            token: "USDC".to_string(),
-            blockchain: "polygon".to_string(),
-            frequency: "monthly".to_string(),
-        },
-        conditions: None,
-        metadata: None,
-    }).await?;
+    let contract = Smart402::create(ContractConfig::new(
+        "saas-subscription",
+        PaymentConfig::new(Amount::from(99), Token::usdc(), PaymentFrequency::Monthly { day: None })
+            .with_blockchain(Network::Polygon),
+    )
+    .with_parties(["vendor@example.com", "customer@example.com"])).await?;
 
     let llmo = LLMOEngine::new();
     let explanation = llmo.explain(&contract.ucl)?;
@@ -180,18 +142,12 @@ async fn test_generate_explanation() -> Result<()> {
 
 #[tokio::test]
 async fn test_compile_to_solidity() -> Result<()> {
-    let contract = Smart402::create(ContractConfig {
-        contract_type: "saas-subscription".to_string(),
-        parties: vec!["vendor@example.com".to_string(), "customer@example.com".to_string()],
-        payment: PaymentConfig {
-            amount: 99.0,
-            token: "USDC".to_string(),
-            blockchain: "polygon".to_string(),
-            frequency: "monthly".to_string(),
-        },
-        conditions: None,
-        metadata: None,
-    }).await?;
+    let contract = Smart402::create(ContractConfig::new(
+        "saas-subscription",
+        PaymentConfig::new(Amount::from(99), Token::usdc(), PaymentFrequency::Monthly { day: None })
+            .with_blockchain(Network::Polygon),
+    )
+    .with_parties(["vendor@example.com", "customer@example.com"])).await?;
 
     let llmo = LLMOEngine::new();
     let solidity = llmo.compile(&contract.ucl, "solidity")?;
@@ -205,18 +161,12 @@ async fn test_compile_to_solidity() -> Result<()> {
 
 #[tokio::test]
 async fn test_compile_to_javascript() -> Result<()> {
-    let contract = Smart402::create(ContractConfig {
-        contract_type: "saas-subscription".to_string(),
-        parties: vec!["vendor@example.com".to_string(), "customer@example.com".to_string()],
-        payment: PaymentConfig {
-            amount: 99.0,
-            token: "USDC".to_string(),
-            blockchain: "polygon".to_string(),
-            frequency: "monthly".to_string(),
-        },
-        conditions: None,
-        metadata: None,
-    }).await?;
+    let contract = Smart402::create(ContractConfig::new(
+        "saas-subscription",
+        PaymentConfig::new(Amount::from(99), Token::usdc(), PaymentFrequency::Monthly { day: None })
+            .with_blockchain(Network::Polygon),
+    )
+    .with_parties(["vendor@example.com", "customer@example.com"])).await?;
 
     let llmo = LLMOEngine::new();
     let javascript = llmo.compile(&contract.ucl, "javascript")?;
@@ -230,18 +180,12 @@ async fn test_compile_to_javascript() -> Result<()> {
 
 #[tokio::test]
 async fn test_compile_to_rust() -> Result<()> {
-    let contract = Smart402::create(ContractConfig {
-        contract_type: "saas-subscription".to_string(),
-        parties: vec!["vendor@example.com".to_string(), "customer@example.com".to_string()],
-        payment: PaymentConfig {
-            amount: 99.0,
-            token: "USDC".to_string(),
-            blockchain: "polygon".to_string(),
-            frequency: "monthly".to_string(),
-        },
-        conditions: None,
-        metadata: None,
-    }).await?;
+    let contract = Smart402::create(ContractConfig::new(
+        "saas-subscription",
+        PaymentConfig::new(Amount::from(99), Token::usdc(), PaymentFrequency::Monthly { day: None })
+            .with_blockchain(Network::Polygon),
+    )
+    .with_parties(["vendor@example.com", "customer@example.com"])).await?;
 
     let llmo = LLMOEngine::new();
     let rust = llmo.compile(&contract.ucl, "rust")?;
@@ -255,20 +199,14 @@ async fn test_compile_to_rust() -> Result<()> {
 
 #[tokio::test]
 async fn test_generate_x402_headers() -> Result<()> {
-    let contract = Smart402::create(ContractConfig {
-        contract_type: "api-payment".to_string(),
-        parties: vec!["provider@api.com".to_string(), "consumer@client.com".to_string()],
-        payment: PaymentConfig {
-            amount: 0.10,
-            token: "USDC".to_string(),
-            blockchain: "polygon".to_string(),
-            frequency: "per-request".to_string(),
-        },
-        conditions: None,
-        metadata: None,
-    }).await?;
-
-    let x402 = X402Client::new("https://x402.smart402.io".to_string());
+    let contract = Smart402::create(ContractConfig::new(
+        "api-payment",
+        PaymentConfig::new(Amount::new(1, 1), Token::usdc(), PaymentFrequency::PerRequest)
+            .with_blockchain(Network::Polygon),
+    )
+    .with_parties(["provider@api.com", "consumer@client.com"])).await?;
+
+    let x402 = X402Client::with_private_key("https://x402.smart402.io".to_string(), TEST_KEY)?;
     let headers = x402.generate_headers(&contract.ucl, true)?;
 
     assert_eq!(headers.contract_id, contract.ucl.contract_id);
@@ -283,22 +221,16 @@ async fn test_generate_x402_headers() -> Result<()> {
 
 #[tokio::test]
 async fn test_deploy_to_testnet() -> Result<()> {
-    let mut contract = Smart402::create(ContractConfig {
-        contract_type: "test".to_string(),
-        parties: vec!["a@test.com".to_string(), "b@test.com".to_string()],
-        payment: PaymentConfig {
-            amount: 10.0,
-            token: "USDC".to_string(),
-            blockchain: "polygon-mumbai".to_string(),
-            frequency: "one-time".to_string(),
-        },
-        conditions: None,
-        metadata: None,
-    }).await?;
+    let mut contract = Smart402::create(ContractConfig::new(
+        "test",
+        PaymentConfig::new(Amount::from(10), Token::usdc(), PaymentFrequency::OneTime)
+            .with_blockchain(Network::PolygonMumbai),
+    )
+    .with_parties(["a@test.com", "b@test.com"])).await?;
 
     let result = contract.deploy("polygon-mumbai").await?;
 
-    assert_eq!(result.network, "polygon-mumbai");
+    assert_eq!(result.network, Network::PolygonMumbai);
     assert!(result.address.starts_with("0x"));
     assert_eq!(result.address.len(), 42);
     assert!(result.transaction_hash.starts_with("0x"));
@@ -308,70 +240,55 @@ async fn test_deploy_to_testnet() -> Result<()> {
 
 #[tokio::test]
 async fn test_check_conditions() -> Result<()> {
-    let contract = Smart402::create(ContractConfig {
-        contract_type: "saas-subscription".to_string(),
-        parties: vec!["vendor@example.com".to_string(), "customer@example.com".to_string()],
-        payment: PaymentConfig {
-            amount: 99.0,
-            token: "USDC".to_string(),
-            blockchain: "polygon".to_string(),
-            frequency: "monthly".to_string(),
-        },
-        conditions: Some(vec![serde_json::json!({
-            "id": "uptime_check",
-            "type": "api",
-            "description": "Service uptime > 99%",
-            "threshold": 0.99
-        })]),
-        metadata: None,
-    }).await?;
+    let contract = Smart402::create(ContractConfig::new(
+        "saas-subscription",
+        PaymentConfig::new(Amount::from(99), Token::usdc(), PaymentFrequency::Monthly { day: None })
+            .with_blockchain(Network::Polygon),
+    )
+    .with_parties(["vendor@example.com", "customer@example.com"])
+    .with_condition(
+        ConditionConfig::new("uptime_check", "Service uptime > 99%")
+            .with_source("api")
+            .with_threshold(">", 0.99),
+    )).await?;
 
     let result = contract.check_conditions().await?;
 
-    assert!(result.timestamp > 0);
+    assert!(result.timestamp.timestamp() > 0);
 
     Ok(())
 }
 
 #[tokio::test]
 async fn test_execute_payment() -> Result<()> {
-    let contract = Smart402::create(ContractConfig {
-        contract_type: "test".to_string(),
-        parties: vec!["a@test.com".to_string(), "b@test.com".to_string()],
-        payment: PaymentConfig {
-            amount: 99.0,
-            token: "USDC".to_string(),
-            blockchain: "polygon".to_string(),
-            frequency: "monthly".to_string(),
-        },
-        conditions: None,
-        metadata: None,
-    }).await?;
+    let contract = Smart402::create(ContractConfig::new(
+        "test",
+        PaymentConfig::new(Amount::from(99), Token::usdc(), PaymentFrequency::Monthly { day: None })
+            .with_blockchain(Network::Polygon),
+    )
+    .with_parties([
+        "0x209693Bc6afc0C5328bA36FaF03C514EF312287C",
+        "0x00000000000000000000000000000000000000c0",
+    ])).await?;
 
     let result = contract.execute_payment().await?;
 
     assert!(result.success);
     assert!(result.transaction_hash.starts_with("0x"));
-    assert_eq!(result.amount, 99.0);
-    assert_eq!(result.token, "USDC");
+    assert_eq!(result.amount, Amount::from(99));
+    assert_eq!(result.token, Token::usdc());
 
     Ok(())
 }
 
 #[tokio::test]
 async fn test_export_yaml() -> Result<()> {
-    let contract = Smart402::create(ContractConfig {
-        contract_type: "test".to_string(),
-        parties: vec!["a@test.com".to_string(), "b@test.com".to_string()],
-        payment: PaymentConfig {
-            amount: 10.0,
-            token: "USDC".to_string(),
-            blockchain: "polygon".to_string(),
-            frequency: "monthly".to_string(),
-        },
-        conditions: None,
-        metadata: None,
-    }).await?;
+    let contract = Smart402::create(ContractConfig::new(
+        "test",
+        PaymentConfig::new(Amount::from(10), Token::usdc(), PaymentFrequency::Monthly { day: None })
+            .with_blockchain(Network::Polygon),
+    )
+    .with_parties(["a@test.com", "b@test.com"])).await?;
 
     let yaml = smart402::utils::export_yaml(&contract.ucl)?;
 
@@ -384,18 +301,12 @@ async fn test_export_yaml() -> Result<()> {
 
 #[tokio::test]
 async fn test_export_json() -> Result<()> {
-    let contract = Smart402::create(ContractConfig {
-        contract_type: "test".to_string(),
-        parties: vec!["a@test.com".to_string(), "b@test.com".to_string()],
-        payment: PaymentConfig {
-            amount: 10.0,
-            token: "USDC".to_string(),
-            blockchain: "polygon".to_string(),
-            frequency: "monthly".to_string(),
-        },
-        conditions: None,
-        metadata: None,
-    }).await?;
+    let contract = Smart402::create(ContractConfig::new(
+        "test",
+        PaymentConfig::new(Amount::from(10), Token::usdc(), PaymentFrequency::Monthly { day: None })
+            .with_blockchain(Network::Polygon),
+    )
+    .with_parties(["a@test.com", "b@test.com"])).await?;
 
     let json = smart402::utils::export_json(&contract.ucl)?;
     let parsed: serde_json::Value = serde_json::from_str(&json)?;
@@ -412,41 +323,28 @@ async fn test_list_templates() {
     let templates = Smart402::get_templates();
 
     assert!(!templates.is_empty());
-    assert!(templates.len() > 0);
 }
 
 #[tokio::test]
 async fn test_invalid_payment_amount() {
-    let result = Smart402::create(ContractConfig {
-        contract_type: "test".to_string(),
-        parties: vec!["a@test.com".to_string(), "b@test.com".to_string()],
-        payment: PaymentConfig {
-            amount: -100.0,  // Invalid: negative amount
-            token: "USDC".to_string(),
-            blockchain: "polygon".to_string(),
-            frequency: "monthly".to_string(),
-        },
-        conditions: None,
-        metadata: None,
-    }).await;
+    let result = Smart402::create(ContractConfig::new(
+        "test",
+        PaymentConfig::new(Amount::from(-100), Token::usdc(), PaymentFrequency::Monthly { day: None })
+            .with_blockchain(Network::Polygon),  // Invalid: negative amount
+    )
+    .with_parties(["a@test.com", "b@test.com"])).await;
 
     assert!(result.is_err());
 }
 
 #[tokio::test]
 async fn test_contract_summary() -> Result<()> {
-    let contract = Smart402::create(ContractConfig {
-        contract_type: "saas-subscription".to_string(),
-        parties: vec!["vendor@example.com".to_string(), "customer@example.com".to_string()],
-        payment: PaymentConfig {
-            amount: 99.0,
-            token: "USDC".to_string(),
-            blockchain: "polygon".to_string(),
-            frequency: "monthly".to_string(),
-        },
-        conditions: None,
-        metadata: None,
-    }).await?;
+    let contract = Smart402::create(ContractConfig::new(
+        "saas-subscription",
+        PaymentConfig::new(Amount::from(99), Token::usdc(), PaymentFrequency::Monthly { day: None })
+            .with_blockchain(Network::Polygon),
+    )
+    .with_parties(["vendor@example.com", "customer@example.com"])).await?;
 
     let summary = contract.get_summary();
 
@@ -459,36 +357,26 @@ async fn test_contract_summary() -> Result<()> {
 
 #[tokio::test]
 async fn test_aeo_score_improvement_with_metadata() -> Result<()> {
-    let basic_contract = Smart402::create(ContractConfig {
-        contract_type: "test".to_string(),
-        parties: vec!["a@test.com".to_string(), "b@test.com".to_string()],
-        payment: PaymentConfig {
-            amount: 10.0,
-            token: "USDC".to_string(),
-            blockchain: "polygon".to_string(),
-            frequency: "monthly".to_string(),
-        },
-        conditions: None,
-        metadata: None,
-    }).await?;
-
-    let rich_contract = Smart402::create(ContractConfig {
-        contract_type: "test".to_string(),
-        parties: vec!["a@test.com".to_string(), "b@test.com".to_string()],
-        payment: PaymentConfig {
-            amount: 10.0,
-            token: "USDC".to_string(),
-            blockchain: "polygon".to_string(),
-            frequency: "monthly".to_string(),
-        },
-        conditions: None,
-        metadata: Some(serde_json::json!({
-            "title": "Comprehensive Test Contract",
-            "description": "Detailed description with rich metadata",
-            "category": "testing",
-            "tags": ["test", "example", "smart402"]
-        })),
-    }).await?;
+    let basic_contract = Smart402::create(ContractConfig::new(
+        "test",
+        PaymentConfig::new(Amount::from(10), Token::usdc(), PaymentFrequency::Monthly { day: None })
+            .with_blockchain(Network::Polygon),
+    )
+    .with_parties(["a@test.com", "b@test.com"])).await?;
+
+    let rich_contract = Smart402::create(ContractConfig::new(
+        "test",
+        PaymentConfig::new(Amount::from(10), Token::usdc(), PaymentFrequency::Monthly { day: None })
+            .with_blockchain(Network::Polygon),
+    )
+    .with_parties(["a@test.com", "b@test.com"])
+    .with_metadata(ContractMetadataInput {
+        title: Some("Comprehensive Test Contract".to_string()),
+        description: Some("Detailed description with rich metadata".to_string()),
+        category: Some("testing".to_string()),
+        tags: vec!["test".to_string(), "example".to_string(), "smart402".to_string()],
+        ..Default::default()
+    })).await?;
 
     let aeo = AEOEngine::new();
     let basic_score = aeo.calculate_score(&basic_contract.ucl)?;
@@ -501,18 +389,13 @@ async fn test_aeo_score_improvement_with_metadata() -> Result<()> {
 
 #[tokio::test]
 async fn test_validation_errors() -> Result<()> {
-    let invalid_contract = Smart402::create(ContractConfig {
-        contract_type: "test".to_string(),
-        parties: vec![],  // Invalid: no parties
-        payment: PaymentConfig {
-            amount: -10.0,  // Invalid: negative amount
-            token: "USDC".to_string(),
-            blockchain: "polygon".to_string(),
-            frequency: "monthly".to_string(),
-        },
-        conditions: None,
-        metadata: None,
-    }).await?;
+    let mut invalid_contract = Smart402::create(ContractConfig::new(
+        "test",
+        PaymentConfig::new(Amount::from(10), Token::usdc(), PaymentFrequency::Monthly { day: None })
+            .with_blockchain(Network::Polygon),
+    )).await?;
+    // Invalid: negative amount, which create itself rejects
+    invalid_contract.ucl.payment.amount = Amount::from(-10);
 
     let llmo = LLMOEngine::new();
     let validation = llmo.validate(&invalid_contract.ucl)?;
@@ -525,20 +408,14 @@ async fn test_validation_errors() -> Result<()> {
 
 #[tokio::test]
 async fn test_x402_unique_nonce() -> Result<()> {
-    let contract = Smart402::create(ContractConfig {
-        contract_type: "api-payment".to_string(),
-        parties: vec!["provider@api.com".to_string(), "consumer@client.com".to_string()],
-        payment: PaymentConfig {
-            amount: 0.10,
-            token: "USDC".to_string(),
-            blockchain: "polygon".to_string(),
-            frequency: "per-request".to_string(),
-        },
-        conditions: None,
-        metadata: None,
-    }).await?;
-
-    let x402 = X402Client::new("https://x402.smart402.io".to_string());
+    let contract = Smart402::create(ContractConfig::new(
+        "api-payment",
+        PaymentConfig::new(Amount::new(1, 1), Token::usdc(), PaymentFrequency::PerRequest)
+            .with_blockchain(Network::Polygon),
+    )
+    .with_parties(["provider@api.com", "consumer@client.com"])).await?;
+
+    let x402 = X402Client::with_private_key("https://x402.smart402.io".to_string(), TEST_KEY)?;
     let headers1 = x402.generate_headers(&contract.ucl, true)?;
 
     // Sleep to ensure different timestamp